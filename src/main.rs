@@ -7,16 +7,49 @@
 
 use raylib::prelude::*;
 use std::time;
+use std::thread;
+use std::path::Path;
+use std::collections::HashSet;
 
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, OnceLock};
 
 pub mod simulation;
-use simulation::simulator::simulator::{SimArea, CrowdSim};
-use simulation::pedestrian::pedestrian::Etiquette;
+use simulation::simulator::simulator::{SimArea, CrowdSim, OdDemand, CrossingDirection, DrawLayers, RunStatus, CancelToken, Recirculation};
+use simulation::behaviour::behaviour::{BehaviourModel, Etiquette};
+use simulation::social_force::social_force::SocialForce;
+use simulation::gtfs::gtfs;
+use simulation::export::export;
+use simulation::scenario::scenario::Scenario;
+use simulation::pool::pool::SimulationPool;
+use simulation::junction::junction::PriorityRule;
+use simulation::cache::cache::{ResultCache, RunResults};
+use simulation::console::console::{Console, Command};
+use simulation::arrivals::arrivals::Poisson;
+use simulation::annotation::annotation::{self, Annotation, AnnotationLog};
+use simulation::geom::geom::{Vec2, Zone};
+use simulation::statistics::statistics::{self, ResultBreakdown, TravelTimeStats, WelchTest};
+use simulation::replay::replay::Replay;
+use simulation::checkpoint::checkpoint::Checkpoint;
+use simulation::observer::observer::Observer;
+use simulation::profiling::profiling::{Phase, Profiler};
+use simulation::experiment::experiment::{Experiment, ExperimentRun};
+use simulation::calibration::calibration;
+use simulation::noncompliant::noncompliant::{NonCompliant, NonCompliance};
+use simulation::pedestrian::pedestrian::AfterDwell;
+use simulation::params::params::{Integrator, StallRecovery};
+use simulation::herding::herding::Herding;
+use simulation::distancing::distancing::{Distancing, DISTANCING_COHORT};
+use simulation::pressure::pressure::PressureThresholds;
+use simulation::walkway::walkway::{WalkwayEtiquette, WalkwaySide};
+use simulation::vehicle::vehicle::VehicleParams;
 
 
-/// Speed multiplier if rendering the simulation
+/// Speed multiplier if rendering the simulation (it can be changed with + and - while running)
 const SIM_SPEED: f64 = 1.0;
+/// The slowest & fastest speed multipliers that + and - can reach
+const SIM_SPEED_BOUNDS: (f64, f64) = (0.0625, 16.0);
+/// The most timesteps simulated per frame when rendering. If a frame takes longer than this many timesteps, the simulation falls behind real time rather than freezing the window trying to catch up.
+const MAX_TIMESTEPS_PER_FRAME: u32 = 50;
 
 /// Run & display the simulation in real time (true), or run the entire simulation immediately & return the results (false)
 const RENDER: bool = true;
@@ -33,61 +66,353 @@ const RENDER: bool = true;
 /// 6 = simulate many different pedestrian flow rates
 /// 7 = compare the left-bias and no-bias simulations many times
 /// 
+/// 8 = train platform with alighting passengers
+/// 9 = corridor with a corner, using waypoints
+/// 10 = two rooms with a pillar, using path planning or a floor field (see USE_FLOOR_FIELD)
+/// 11 = compare junction priority rules at the crossroads many times
+/// 12 = compare the left-bias and no-bias simulations with and without intent signalling many times
+/// 13 = station concourse with several entrances & exits, using an origin-destination matrix
+/// 14 = the left-bias and no-bias simulations side by side, with the same seed & stepped together
+/// 15 = fit the model parameters in CALIBRATION_PARAMS to the calibration goal
+/// 16 = measure how much non-compliant pedestrians (see NON_COMPLIANCE) slow everyone else down, many times
+/// 17 = compare how much each integrator biases travel times with long timesteps (see COARSE_TIME_SCALE), many times
+/// 18 = station entrance where everyone passes through a line of ticket gates
+/// 19 = ticket hall where some pedestrians queue at ticket machines on the way through
+/// 20 = corridor with a moving walkway in one direction, beside a stream walking the other way
+/// 21 = corridor crossed by a cycle path, where pedestrians give way to cyclists
+/// 22 = hall split by a partition, where pedestrians that don't know the way through find it by following signs
+/// 23 = measure the throughput lost to physical distancing many times
+/// 
 /// _ = original debug sim
 const SIM_TYPE: usize = 0;
 
+/// Which behaviour model the pedestrians use
+/// false = rule-based etiquettes
+/// true = social force model (with the etiquette as a sideways bias)
+const USE_SOCIAL_FORCE: bool = false;
+
+/// Simulation time at which every left-biased pedestrian switches to no bias, in seconds (None = never)
+const LEFT_BIAS_SWITCH_TIME: Option<f64> = None;
+
+/// How far ahead pedestrians signal the direction they intend to walk in to their neighbours, in seconds (None = no signalling)
+const INTENT_SIGNALLING_HORIZON: Option<f64> = None;
+
+/// The priority rule applied where the streams cross in the crossroads simulation (None = no junction rule)
+const JUNCTION_RULE: Option<PriorityRule> = None;
+
+/// Simulation time at which every pedestrian abandons its destination and heads for the nearest exit, in seconds (None = no evacuation).
+/// Only scenarios with designated exits can be evacuated.
+const EVACUATION_TIME: Option<f64> = None;
+
 /// Total number of pedestrians to simulate
 const TOTAL_PEDESTRIANS: u32 = 1040;
 
 /// Walkers per second during peak times
 const WALKER_RATE: f64 = 0.8;
 
-/// Simulation time scale when not rendering
+/// Add pedestrians with exponentially distributed gaps between arrivals (a Poisson process per group), seeded with this number (None = add pedestrians at regular intervals).
+/// Repeated runs add the run number to the seed, so that each run has different arrivals but compared simulations share them.
+const POISSON_ARRIVAL_SEED: Option<u64> = None;
+
+/// Hold pedestrians back until there is room for them at their start position, instead of letting them start on top of other pedestrians.
+/// The length of the resulting queue is reported when not rendering.
+const SPAWN_GATE: bool = false;
+
+/// Show the percentage of pedestrians finished while a single simulation runs without rendering.
+/// Either way, Ctrl-C stops the run and prints the results so far.
+const SHOW_PROGRESS: bool = true;
+
+/// Stop a run that isn't being rendered once it has simulated this long, in seconds, even if some pedestrians haven't finished (None = no limit)
+const MAX_SIM_TIME: Option<f64> = None;
+/// Stop a run that isn't being rendered once it has taken this long in real time, in seconds, even if some pedestrians haven't finished (None = no limit)
+const MAX_WALL_TIME: Option<f64> = None;
+
+/// Make pedestrians walk cyclic routes, starting again when they reach their destination instead of finishing, so that a constant number of them keep walking
+/// (e.g. for fundamental diagrams with EXPORT_FUNDAMENTAL_DIAGRAM). Runs that aren't rendered then stop at MAX_SIM_TIME, or after an hour without it. (None = pedestrians finish)
+const RECIRCULATION: Option<Recirculation> = None;
+
+/// Record how crowded each part of the area is on a grid with cells this size, in metres, and save a heatmap PNG & CSV when not rendering (None = don't record)
+const DENSITY_CELL_SIZE: Option<f64> = None;
+/// The cell size used if the heatmap is shown while rendering (with H) without DENSITY_CELL_SIZE set, in metres. Recording starts when it is first shown.
+const LIVE_HEATMAP_CELL_SIZE: f64 = 0.5;
+
+/// Save the density & speed measured in each measurement area every timestep, for comparison with empirical fundamental diagrams (when not rendering)
+const EXPORT_FUNDAMENTAL_DIAGRAM: bool = false;
+/// Also measure density & speed in each measurement area with the Voronoi method, cutting off each pedestrian's Voronoi cell at this radius, in metres (None = only count pedestrians)
+const VORONOI_CUTOFF_RADIUS: Option<f64> = None;
+
+/// Record every pedestrian's position, speed & heading at this interval, in seconds, and save the trajectories as CSV (and Parquet, with the `parquet` feature) when not rendering (None = don't record)
+const TRAJECTORY_INTERVAL: Option<f64> = None;
+
+/// Measure the crowd pressure on every pedestrian at this interval, in seconds, and save it as CSV when not rendering, along with the times & places it was above PRESSURE_THRESHOLDS (None = don't measure)
+const PRESSURE_INTERVAL: Option<f64> = None;
+/// The width and height of the grid cells that dangerous crowd pressure is flagged in, in metres
+const PRESSURE_CELL_SIZE: f64 = 2.0;
+/// The crowd pressure (s^-2, only at densities of at least `density` pedestrians/m²) and contact force per unit mass (m⋅s^-2) above which a part of the crowd is flagged as at risk of a crush
+const PRESSURE_THRESHOLDS: PressureThresholds = PressureThresholds {density: 5.0, pressure: 0.02, contact_force: 55.0};
+
+/// Record how long every pair of pedestrians spends within this distance of each other, in metres, and save the contact graph as CSV when not rendering (None = don't record)
+const CONTACT_DISTANCE: Option<f64> = None;
+/// The shortest total time two pedestrians need to spend within CONTACT_DISTANCE of each other to count as close contacts in the summary, in seconds
+const CLOSE_CONTACT_TIME: f64 = 15.0;
+
+/// true = save every pedestrian's travel time as CSV (and Parquet, with the `parquet` feature) when not rendering
+const SAVE_TRAVEL_TIMES: bool = false;
+
+/// A trajectory CSV file saved by an earlier run to play back over the selected simulation's environment, instead of simulating (None = simulate).
+/// When not rendering, the number of pedestrians walking & their mean speed are printed every second of the recording instead.
+const REPLAY_FILE: Option<&str> = None;
+
+/// Save every crossing of each counting line, and the flux through it every second, when not rendering
+const EXPORT_LINE_FLUX: bool = false;
+
+/// Seed the randomness of each simulation (start & end points, speeds, parameters & noise) with this number, so that a run can be repeated exactly (None = different every run).
+/// Repeated comparison runs all share the seed. The arrival process is seeded separately (see POISSON_ARRIVAL_SEED).
+const SIM_SEED: Option<u64> = None;
+
+/// Save a checkpoint of the full simulation state at this simulation time, in seconds, then carry on (when not rendering, None = don't save)
+const CHECKPOINT_TIME: Option<f64> = None;
+/// A checkpoint saved by an earlier run to resume the selected simulation from, instead of starting it from the beginning.
+/// The simulation's own settings (parameters, arrival process, etc.) are kept, so a moment can be re-run with different ones.
+const RESUME_CHECKPOINT: Option<&str> = None;
+
+/// Report the most pedestrians walking at once, and when, when not rendering
+const REPORT_PEAK_OCCUPANCY: bool = false;
+
+/// The most stalls (see the `stall_time` parameter) listed individually when not rendering. Every stall is counted.
+const MAX_REPORTED_STALLS: usize = 10;
+
+/// GTFS stop_times.txt file used to schedule train arrivals in the platform simulation (None = trains arrive every PLATFORM_HEADWAY seconds)
+const GTFS_STOP_TIMES_PATH: Option<&str> = None;
+/// The GTFS stop_id of the simulated platform
+const GTFS_STOP_ID: &str = "";
+/// The period of the timetable to simulate, in seconds after midnight
+const GTFS_TIME_WINDOW: (f64, f64) = (7.0*3600.0, 8.0*3600.0);
+
+/// Time between train arrivals in the platform simulation when no timetable is given, in seconds
+const PLATFORM_HEADWAY: f64 = 180.0;
+/// Number of passengers alighting from each train in the platform simulation
+const PLATFORM_ALIGHTING_PER_TRAIN: usize = 60;
+/// Number of passengers waiting for each train in the platform simulation, who come onto the platform from either end, stand behind the tactile line and board once the train has arrived (None = nobody boards)
+const PLATFORM_BOARDING_PER_TRAIN: Option<usize> = None;
+/// How long before each train the waiting passengers come onto the platform, and how long each of them stands there for, in seconds
+const PLATFORM_WAITING_TIME: f64 = 90.0;
+/// How many waiting passengers can board each train through each door in the platform simulation. They walk to the doors while the alighting passengers are still stepping off,
+/// and try another door or wait for the next train when their door is full (None = waiting passengers board from where they stand).
+const PLATFORM_DOOR_CAPACITY: Option<usize> = None;
+/// Whether the platform simulation has platform screen doors along the edge, which only open when a train arrives
+const PLATFORM_SCREEN_DOORS: bool = false;
+/// How long each train stays at the platform in the platform simulation, with the platform screen doors open, after it arrives, in seconds
+const PLATFORM_DOOR_OPEN_TIME: f64 = 30.0;
+/// The multiplier applied to walking speeds on the stairs up from either end of the platform in the platform simulation (None = no stairs).
+/// Walking up stairs is roughly 0.6 times as fast as on the flat (Fruin, 1971).
+const PLATFORM_STAIRS_SPEED: Option<f64> = None;
+/// Simulation time at which a fire breaks out beside the middle of the platform in the platform simulation, spreading smoke that pedestrians keep away from, in seconds (None = no fire).
+/// Set EVACUATION_TIME as well for a fire egress study, and the `hazard_tolerance` parameter for the smoke to incapacitate pedestrians.
+const PLATFORM_FIRE_TIME: Option<f64> = None;
+/// How fast the smoke spreads in the platform simulation, in m/s, and the furthest it spreads from the fire, in metres
+const PLATFORM_SMOKE_SPREAD: (f64, f64) = (0.1, 6.0);
+
+/// The fraction of pedestrians in the ticket hall simulation that queue at a ticket machine on the way through
+const TICKET_BUYER_FRACTION: f64 = 0.2;
+/// How long each pedestrian takes to buy a ticket at a ticket machine, in seconds
+const TICKET_SERVICE_TIME: f64 = 8.0;
+
+/// How long each pedestrian takes to pass through a ticket gate in the gateline simulation, in seconds
+const GATE_SERVICE_TIME: f64 = 2.0;
+
+/// The fraction of pedestrians in the signage simulation that know the way through the doorway. The rest follow the crowd until they see a sign.
+const SIGNAGE_FAMILIARITY: f64 = 0.3;
+/// Where the sign pointing to the doorway is in the signage simulation, and how far away it can be read from, in metres (None = no sign)
+const SIGN_PLACEMENT: Option<((f64, f64), f64)> = Some(((10.0,8.0), 8.0));
+
+/// The speed of the moving walkway in the travelator simulation, in m/s
+const WALKWAY_SPEED: f64 = 0.65;
+/// Which side pedestrians stand on in the travelator simulation, and how many of them stand (None = everyone walks wherever they like)
+const WALKWAY_ETIQUETTE: Option<WalkwayEtiquette> = Some(WalkwayEtiquette {stand_side: WalkwaySide::Right, standing_fraction: 0.5});
+
+/// The cyclists crossing the corridor in the cycle crossing simulation: about 16 km/h on a typical bicycle
+const CYCLIST_PARAMS: VehicleParams = VehicleParams {speed: 4.5, length: 1.8, width: 0.6, max_turn_rate: 1.0, deceleration: 1.5};
+/// The time between cyclists entering the cycle path in each direction in the cycle crossing simulation, in seconds
+const CYCLIST_HEADWAY: f64 = 10.0;
+
+
+/// The minimum distance between a planned path and any wall or obstacle, in metres (for scenarios that use path planning)
+const PATH_PLANNING_CLEARANCE: f64 = 0.5;
+
+/// How pedestrians navigate in scenarios that route around geometry
+/// false = each pedestrian plans its own path over a visibility graph
+/// true = pedestrians follow a shared floor field
+const USE_FLOOR_FIELD: bool = false;
+/// The width and height of each floor field cell, in metres
+const FLOOR_FIELD_CELL_SIZE: f64 = 0.25;
+
+/// Only let pedestrians see the neighbours within a cutoff, using Verlet neighbour lists, to speed up large crowds: (cutoff, skin) in metres (None = see everyone).
+/// The cutoff should be at least the furthest pedestrians react to each other (the look-ahead radius, or the social force model's interaction cutoff).
+const NEIGHBOUR_LISTS: Option<(f64, f64)> = None;
+/// Find the neighbours each pedestrian sees within a cutoff, and the contact forces between them, with a compute shader on the GPU: the cutoff in metres (None = on the CPU).
+/// This needs the `gpu` feature, and takes the place of NEIGHBOUR_LISTS.
+const GPU_NEIGHBOURS: Option<f64> = None;
+
+/// The numbers of pedestrians to time in benchmark mode (run with `--bench`).
+/// Without NEIGHBOUR_LISTS every pedestrian looks at every other one, so the largest crowds take a long time.
+const BENCH_PEDESTRIAN_COUNTS: [usize; 7] = [100, 500, 1000, 2000, 5000, 10000, 20000];
+/// The simulation time to run each benchmark for, in seconds
+const BENCH_DURATION: f64 = 2.0;
+/// The density of pedestrians in the benchmark corridor, in pedestrians/m² (the corridor gets wider to fit more pedestrians)
+const BENCH_DENSITY: f64 = 0.5;
+
+/// true = time each phase of every timestep, and print a summary of where the time went after a headless run
+const PROFILE_PHASES: bool = false;
+
+/// Simulation timestep, in seconds. Rendered simulations take timesteps of the same length however fast the window draws, so their results don't depend on the frame rate.
 const TIME_SCALE: f64 = 0.02;
 
-/// The number of pedestrians to exclude from the temporal extremes of the results (the start & end).
+/// The number of threads used to run independent simulations concurrently (0 = one per logical CPU)
+const SIMULATION_THREADS: usize = 0;
+
+/// The number of pedestrians to exclude from the temporal extremes of the results (the start & end), when DETECT_STEADY_STATE is off.
 /// The pedestrians at the very beginning and end of the simulation will interact with fewer pedestrians, so their results are not useful.
 const TRIMMED_PEDESTRIANS: usize = 20;
 
-/// How many pixels in a metre
+/// true = find the warm-up & cool-down to exclude from each run's results separately, with MSER-5, instead of excluding a fixed number of pedestrians
+const DETECT_STEADY_STATE: bool = true;
+
+/// How the non-compliant pedestrians in SIM_TYPE 16 break the conventions everyone else follows
+const NON_COMPLIANCE: NonCompliance = NonCompliance::AgainstFlow;
+/// The number of non-compliant pedestrians added to each run of SIM_TYPE 16, all released at once
+const NON_COMPLIANT_COUNT: usize = 1;
+/// When the non-compliant pedestrians are released, in seconds (during the busiest part of the run)
+const NON_COMPLIANT_RELEASE_TIME: f64 = 300.0;
+
+/// The long timestep that the integrators are compared at by SIM_TYPE 17, in seconds. It is taken in one go, instead of being divided into sub-steps.
+const COARSE_TIME_SCALE: f64 = 0.2;
+
+/// How many pedestrians keep their distance in SIM_TYPE 23, and how far from their neighbours they keep it
+const DISTANCING: Distancing = Distancing {compliance: 0.8, pspace_radius: 2.0};
+/// The pedestrian add rate in SIM_TYPE 23, in pedestrians per second. It is high enough to fill the corridor, so that the throughput shows its capacity.
+const DISTANCING_WALKER_RATE: f64 = 2.0;
+
+/// The significance level that comparisons of repeated simulations are tested at (e.g. 0.05 for 95% confidence)
+const SIGNIFICANCE_LEVEL: f64 = 0.05;
+
+/// Print the travel times of each group & each behaviour model separately, with percentiles and a histogram with bins this wide, in seconds (None = don't)
+const RESULT_BREAKDOWN_BIN_WIDTH: Option<f64> = None;
+
+/// Accept commands (pause, rate, evacuate, snapshot, quit, ...) from stdin while running the simulation without rendering
+const ENABLE_CONSOLE: bool = false;
+/// The address to also accept console commands on over TCP, such as "127.0.0.1:7878" (None = stdin only)
+const CONSOLE_ADDRESS: Option<&str> = None;
+
+/// Tag pedestrians slower and faster than the middle of the target speed range as cohorts, and report the results of each cohort separately
+const TAG_SPEED_COHORTS: bool = true;
+
+/// Export the selected simulation as JuPedSim & Vadere input files (in the working directory) before running it
+const EXPORT_SCENARIO: bool = false;
+
+/// A directory to cache the results of repeated & swept simulations in, so that runs that have already been done are skipped (None = always run every simulation)
+const RESULT_CACHE_DIR: Option<&str> = None;
+
+/// A TOML scenario file to load the pedestrian model parameters (and optionally an arrival process) from (None = use the default parameters)
+const SCENARIO_FILE: Option<&str> = None;
+
+/// The model parameters to fit when calibrating (SIM_TYPE 15), by the names they have in a scenario file. The rest keep their values from SCENARIO_FILE, or the defaults.
+const CALIBRATION_PARAMS: [&str; 3] = ["pspace_radius", "opposing_repulsion", "speed_noise_factor"];
+/// The travel time statistics that calibration aims for: (mean, standard deviation) in seconds
+const CALIBRATION_GOAL: (f64, f64) = (18.57, 3.0);
+/// The number of seeded runs that each set of parameters is judged on when calibrating
+const CALIBRATION_REPETITIONS: usize = 4;
+/// Roughly the most sets of parameters to try when calibrating
+const CALIBRATION_MAX_EVALUATIONS: usize = 60;
+
+/// Fit the view to the environment when the window opens or is resized (true), or start with DRAW_SCALE and a fixed offset (false)
+const FIT_VIEW: bool = true;
+/// How many pixels in a metre, if the view isn't fitted to the environment
 pub const DRAW_SCALE: i32 = 40;
+/// The space left around the environment when the view is fitted to it, in pixels
+const FIT_VIEW_MARGIN: i32 = 20;
 
-/// Create a simulation for callibration purposes
-fn create_calibration_sim() -> CrowdSim {
-    /// Normalised ratio of left-, non-, and right-biased pedestrians
-    const BIAS_RATIOS: (f64, f64, f64) = (0.443877551020408, 0.520408163265306, 0.0357142857142857);
+/// The period of simulation time that the flow shown in the statistics panel is measured over, in seconds
+const HUD_FLOW_WINDOW: f64 = 10.0;
+/// The width of each bin of the travel time histogram in the statistics panel, in seconds
+const HUD_HISTOGRAM_BIN_WIDTH: f64 = 1.0;
+
+/// How close to a pedestrian a click has to be to inspect it, in metres
+const INSPECT_RADIUS: f64 = 1.0;
+
+/// Create the behaviour model for a pedestrian following the given etiquette
+fn behaviour_model(etiquette: Etiquette) -> Arc<dyn BehaviourModel> {
+    if USE_SOCIAL_FORCE {
+        return Arc::new(SocialForce::new(etiquette));
+    }
     
-    let simulated_area = create_testing_environment();
+    return Arc::new(etiquette);
+}
+
+/// Return the scenario loaded from SCENARIO_FILE, if one is set. The file is only read the first time.
+fn scenario_file() -> Option<&'static Scenario> {
+    static SCENARIO: OnceLock<Option<Scenario>> = OnceLock::new();
+    return SCENARIO.get_or_init(|| SCENARIO_FILE.map(|path| Scenario::from_file(Path::new(path)).expect("Failed to load scenario file"))).as_ref();
+}
+
+/// Create a new simulation in the given area, using the parameters from SCENARIO_FILE if one is set
+/// 
+/// * `run` - The experiment run the simulation is for, whose seed & settings are applied on top (None = not part of an experiment, see `run_experiment`)
+fn new_crowd_sim(area: SimArea, ped_add_rate: f64, run: Option<&ExperimentRun>) -> CrowdSim {
+    let mut crowd_simulation = CrowdSim::new(Arc::new(area), ped_add_rate);
     
-    let mut crowd_simulation = CrowdSim::new(Arc::new(simulated_area), WALKER_RATE);
+    if let Some(seed) = SIM_SEED {
+        crowd_simulation.set_seed(seed);
+    }
     
-    // Pedestrians moving left-to-right
-    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.0*0.5) as usize, 0, Etiquette::LeftBias);
-    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.1*0.5) as usize, 0, Etiquette::NoBias);
-    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.2*0.5) as usize, 0, Etiquette::RightBias);
+    if let Some(seed) = POISSON_ARRIVAL_SEED {
+        crowd_simulation.set_arrival_process(Box::new(Poisson::new(seed)));
+    }
     
-    // Pedestrians moving right-to-left
-    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.0*0.5) as usize, 1, Etiquette::LeftBias);
-    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.1*0.5) as usize, 1, Etiquette::NoBias);
-    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.2*0.5) as usize, 1, Etiquette::RightBias);
+    crowd_simulation.set_spawn_gate(SPAWN_GATE);
+    crowd_simulation.set_run_limits(MAX_SIM_TIME, MAX_WALL_TIME);
+    crowd_simulation.set_recirculation(RECIRCULATION);
     
-    crowd_simulation.randomise_pedestrian_order();
+    if let Some(scenario) = scenario_file() {
+        crowd_simulation.set_params(scenario.params);
+        crowd_simulation.set_param_variation(scenario.variation);
+        if let Some(arrivals) = &scenario.arrivals {
+            crowd_simulation.set_arrival_process(arrivals.build());
+        }
+        crowd_simulation.set_distraction(scenario.distraction);
+        crowd_simulation.set_conversations(scenario.conversations);
+        crowd_simulation.set_herding(scenario.herding);
+        crowd_simulation.set_distancing(scenario.distancing);
+        crowd_simulation.set_demographics(scenario.demographics.clone());
+        crowd_simulation.set_luggage(scenario.luggage.clone());
+        crowd_simulation.add_events(scenario.events.clone());
+    }
     
-    return crowd_simulation;
+    if let Some(run) = run {
+        run.apply(&mut crowd_simulation);
+    }
     
+    return crowd_simulation;
 }
 
-/// Create a simulation for testing all pedestrians with a left bias
-fn create_left_bias_sim(ped_add_rate: f64) -> CrowdSim {
+/// Create a simulation for callibration purposes
+fn create_calibration_sim(run: Option<&ExperimentRun>) -> CrowdSim {
+    /// Normalised ratio of left-, non-, and right-biased pedestrians
+    const BIAS_RATIOS: (f64, f64, f64) = (0.443877551020408, 0.520408163265306, 0.0357142857142857);
+    
     let simulated_area = create_testing_environment();
     
-    let mut crowd_simulation = CrowdSim::new(Arc::new(simulated_area), ped_add_rate);
+    let mut crowd_simulation = new_crowd_sim(simulated_area, WALKER_RATE, run);
     
     // Pedestrians moving left-to-right
-    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*0.5) as usize, 0, Etiquette::LeftBias);
+    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.0*0.5) as usize, 0, behaviour_model(Etiquette::LeftBias));
+    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.1*0.5) as usize, 0, behaviour_model(Etiquette::NoBias));
+    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.2*0.5) as usize, 0, behaviour_model(Etiquette::RightBias));
     
     // Pedestrians moving right-to-left
-    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*0.5) as usize, 1, Etiquette::LeftBias);
+    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.0*0.5) as usize, 1, behaviour_model(Etiquette::LeftBias));
+    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.1*0.5) as usize, 1, behaviour_model(Etiquette::NoBias));
+    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.2*0.5) as usize, 1, behaviour_model(Etiquette::RightBias));
     
     crowd_simulation.randomise_pedestrian_order();
     
@@ -95,17 +420,33 @@ fn create_left_bias_sim(ped_add_rate: f64) -> CrowdSim {
     
 }
 
+/// Create a simulation for testing all pedestrians with a left bias
+fn create_left_bias_sim(ped_add_rate: f64, run: Option<&ExperimentRun>) -> CrowdSim {
+    return create_etiquette_sim(ped_add_rate, Etiquette::LeftBias, None, run);
+}
+
 /// Create a simulation for testing all pedestrians with no bias
-fn create_no_bias_sim(ped_add_rate: f64) -> CrowdSim {
+fn create_no_bias_sim(ped_add_rate: f64, run: Option<&ExperimentRun>) -> CrowdSim {
+    return create_etiquette_sim(ped_add_rate, Etiquette::NoBias, None, run);
+}
+
+/// Create a simulation in the testing environment where every pedestrian follows the same etiquette
+/// 
+/// * `seed` - Seed for the simulation's random number generator, so that two simulations can be given the same pedestrians (None = SIM_SEED, or a random seed if that isn't set either)
+fn create_etiquette_sim(ped_add_rate: f64, etiquette: Etiquette, seed: Option<u64>, run: Option<&ExperimentRun>) -> CrowdSim {
     let simulated_area = create_testing_environment();
     
-    let mut crowd_simulation = CrowdSim::new(Arc::new(simulated_area), ped_add_rate);
+    let mut crowd_simulation = new_crowd_sim(simulated_area, ped_add_rate, run);
+    
+    if let Some(seed) = seed {
+        crowd_simulation.set_seed(seed);
+    }
     
     // Pedestrians moving left-to-right
-    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*0.5) as usize, 0, Etiquette::NoBias);
+    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*0.5) as usize, 0, behaviour_model(etiquette.clone()));
     
     // Pedestrians moving right-to-left
-    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*0.5) as usize, 1, Etiquette::NoBias);
+    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*0.5) as usize, 1, behaviour_model(etiquette));
     
     crowd_simulation.randomise_pedestrian_order();
     
@@ -126,6 +467,12 @@ fn create_testing_environment() -> SimArea {
     simulated_area.add_timing_boundary((3.0,0.0), (3.0,6.0));
     simulated_area.add_timing_boundary((28.0,0.0), (28.0,6.0));
     
+    // Fundamental diagram measurements in the middle of the corridor
+    simulated_area.add_measurement_area((12.0,0.0), (19.0,6.0));
+    
+    // Throughput of the middle of the corridor
+    simulated_area.add_counting_line((15.5,0.0), (15.5,6.0));
+    
     // Start & end group moving left-to-right
     simulated_area.add_start_end_group(
         vec![(0.0,1.0), (0.0,2.0), (0.0,3.0), (0.0,4.0), (0.0,5.0)],
@@ -141,45 +488,127 @@ fn create_testing_environment() -> SimArea {
 }
 
 
+/// Run simulations concurrently, reusing the results of any that have already been run if RESULT_CACHE_DIR is set
+/// 
+/// * `runs` - \[(scenario label, seed, simulation)]. The seed offsets POISSON_ARRIVAL_SEED; results are cached under the seed each simulation was given with `set_seed`, and simulations that weren't seeded are never cached (see `ResultCache::get_key`).
+/// * `thread_count` - The number of threads to run the simulations on (0 = one per logical CPU)
+/// 
+/// Returns the results of each simulation in order: \[(total time, pedestrian count, \[(travel time, group, finish time)])]
+fn simulate_with_cache(runs: Vec<(&str, u64, CrowdSim)>, thread_count: usize) -> Vec<RunResults> {
+    let cache = RESULT_CACHE_DIR.map(|dir| ResultCache::new(Path::new(dir)).expect("Failed to create result cache directory"));
+    
+    let mut results = vec![None; runs.len()];
+    let mut pool = SimulationPool::new(thread_count);
+    // The index in `runs` and cache key (None = not cached) of each simulation that needs to be run
+    let mut pending = Vec::new();
+    
+    for (i, (scenario, seed, mut crowd_sim)) in runs.into_iter().enumerate() {
+        // Runs with the same seed share their random arrivals, so that differences between them come from the pedestrians' behaviour
+        if let Some(arrival_seed) = POISSON_ARRIVAL_SEED {
+            crowd_sim.set_arrival_process(Box::new(Poisson::new(arrival_seed.wrapping_add(seed))));
+        }
+        
+        let key = ResultCache::get_key(scenario, &crowd_sim);
+        
+        match cache.as_ref().zip(key.as_ref()).and_then(|(cache, key)| cache.load(key)) {
+            Some(cached) => {results[i] = Some(cached)},
+            None => {
+                pool.add(crowd_sim);
+                pending.push((i, key));
+            }
+        }
+    }
+    
+    if cache.is_some() {
+        println!("Reusing {} cached results, running {} simulations", results.len() - pending.len(), pending.len());
+    }
+    
+    let report_progress = |finished: usize, total: usize| {
+        eprint!("\rFinished {}/{} simulations", finished, total);
+        if finished == total {
+            eprintln!();
+        }
+    };
+    
+    for ((i, key), (total_time, pedestrian_count, travel_times)) in pending.into_iter().zip(pool.simulate_full_with_progress(TIME_SCALE, report_progress)) {
+        let run_results = (total_time, pedestrian_count, travel_times.to_vec());
+        if let (Some(cache), Some(key)) = (&cache, &key) {
+            cache.store(key, &run_results).expect("Failed to write cached results");
+        }
+        results[i] = Some(run_results);
+    }
+    
+    return results.into_iter().map(|run_results| run_results.unwrap()).collect();
+}
+
+
 /// Run a simulation for many different pedestrian add rates
 fn test_varying_rates(sim_type: usize, lower_rate: f64, upper_rate: f64, increment: f64) {
+    let mut runs = Vec::new();
+    let mut add_rates = Vec::new();
+    
     let mut add_rate = lower_rate;
     while add_rate <= upper_rate {
         
-        let mut crowd_simulation;
         match sim_type {
-            1 => {crowd_simulation = create_left_bias_sim(add_rate)},
-            2 => {crowd_simulation = create_no_bias_sim(add_rate)},
+            1 => {runs.push(("left bias", 0, create_etiquette_sim(add_rate, Etiquette::LeftBias, Some(0), None)));},
+            2 => {runs.push(("no bias", 0, create_etiquette_sim(add_rate, Etiquette::NoBias, Some(0), None)));},
             _ => {return}
         }
-        
-        let results = crowd_simulation.simulate_full(TIME_SCALE);
-        let number_excluded = (add_rate * results.2[0].0 + 1.0) as usize;
-        let parsed_results = parse_results(results.2, number_excluded);
-        
-        println!("{}: {} ± {}s", add_rate, (parsed_results.1 * 100.0).round() / 100.0, (parsed_results.2 * 100.0).round() / 100.0);
+        add_rates.push(add_rate);
         
         // Increment add_rate while preventing rounding errors
         add_rate = ((add_rate + increment)*1000.0).round() / 1000.0;
     }
     
+    for (add_rate, results) in add_rates.into_iter().zip(simulate_with_cache(runs, SIMULATION_THREADS)) {
+        // Nothing to report if no pedestrians finished (e.g. the run was stopped by its limits)
+        let first_travel_time = match results.2.first() {
+            Some((travel_time, _, _)) => *travel_time,
+            None => {
+                println!("{}: no pedestrians finished", add_rate);
+                continue;
+            }
+        };
+        let number_excluded = (add_rate * first_travel_time + 1.0) as usize;
+        let parsed_results = parse_results(&results.2, number_excluded);
+        
+        println!("{}: {} ± {}s", add_rate, (parsed_results.1 * 100.0).round() / 100.0, (parsed_results.2 * 100.0).round() / 100.0);
+    }
+    
     return;
     
 }
 
 
 /// Run the test simulations against each other many times
+/// 
+/// Each repetition has its own seed (counting up from SIM_SEED, or 0), shared by both simulations so that they start with the same pedestrians.
 fn compare_simulations_repeatedly(iterations: usize) {
+    /// The width of the bins in the histograms of mean travel times, in seconds
+    const BIN_WIDTH: f64 = 0.1;
+    
     let mut left_bias_win_count = 0;
     let mut no_bias_win_count = 0;
+    let mut left_bias_means = Vec::new();
+    let mut no_bias_means = Vec::new();
+    
+    // Every pair of simulations is independent, so run them all at once: (left bias, no bias)
+    let mut runs = Vec::new();
+    for iteration in 0..iterations {
+        let seed = SIM_SEED.unwrap_or(0).wrapping_add(iteration as u64);
+        runs.push(("left bias", seed, create_etiquette_sim(WALKER_RATE, Etiquette::LeftBias, Some(seed), None)));
+        runs.push(("no bias", seed, create_etiquette_sim(WALKER_RATE, Etiquette::NoBias, Some(seed), None)));
+    }
+    let mut all_results = simulate_with_cache(runs, SIMULATION_THREADS).into_iter();
     
     for _ in 0..iterations {
         
-        let results_left_bias = create_left_bias_sim(WALKER_RATE).simulate_full(TIME_SCALE);
-        let parsed_results_left_bias = parse_results(results_left_bias.2, TRIMMED_PEDESTRIANS);
+        let results_left_bias = all_results.next().unwrap();
+        let parsed_results_left_bias = parse_results(&results_left_bias.2, TRIMMED_PEDESTRIANS);
         
-        let results_no_bias = create_no_bias_sim(WALKER_RATE).simulate_full(TIME_SCALE);
-        let parsed_results_no_bias = parse_results(results_no_bias.2, TRIMMED_PEDESTRIANS);
+        let results_no_bias = all_results.next().unwrap();
+        let parsed_results_no_bias = parse_results(&results_no_bias.2, TRIMMED_PEDESTRIANS);
         
         println!(
             "Left bias: {} ± {}s  |  No bias: {} ± {}s",
@@ -195,26 +624,404 @@ fn compare_simulations_repeatedly(iterations: usize) {
             no_bias_win_count += 1;
         }
         
+        left_bias_means.push(parsed_results_left_bias.1);
+        no_bias_means.push(parsed_results_no_bias.1);
+        
     }
     
     println!("Left bias won {} times.", left_bias_win_count);
     println!("No bias won {} times.", no_bias_win_count);
     
+    print_travel_time_stats("Left bias mean travel time per run", &TravelTimeStats::new(&left_bias_means, BIN_WIDTH));
+    print_travel_time_stats("No bias mean travel time per run", &TravelTimeStats::new(&no_bias_means, BIN_WIDTH));
+    
+    // Whether the difference between the mean travel times could just be chance
+    let test = WelchTest::new(&left_bias_means, &no_bias_means, 1.0 - SIGNIFICANCE_LEVEL);
+    println!(
+        "Left bias - no bias: {}s ({}% confidence interval {}s to {}s)  |  Welch's t = {} (df {}), p = {}",
+        (test.mean_difference * 1000.0).round() / 1000.0,
+        ((1.0 - SIGNIFICANCE_LEVEL) * 100.0).round(),
+        (test.confidence_interval.0 * 1000.0).round() / 1000.0,
+        (test.confidence_interval.1 * 1000.0).round() / 1000.0,
+        (test.t * 100.0).round() / 100.0,
+        (test.degrees_of_freedom * 10.0).round() / 10.0,
+        test.p_value
+    );
+    if !test.is_significant(SIGNIFICANCE_LEVEL) {
+        println!("The difference is not significant at the {} level.", SIGNIFICANCE_LEVEL);
+    } else if test.mean_difference < 0.0 {
+        println!("Left bias is significantly faster than no bias at the {} level.", SIGNIFICANCE_LEVEL);
+    } else {
+        println!("No bias is significantly faster than left bias at the {} level.", SIGNIFICANCE_LEVEL);
+    }
+    
+    return;
+}
+
+/// Run the left-bias simulation many times with and without NON_COMPLIANT_COUNT non-compliant pedestrians, and compare the travel times of everyone else.
+/// 
+/// As well as the mean over the whole run, the mean over just the pedestrians walking at the same time as the non-compliant ones is compared, since that is where the disruption is.
+fn compare_non_compliant_repeatedly(iterations: usize) {
+    let non_compliant = Arc::new(NonCompliant::new(NON_COMPLIANCE));
+    let name = non_compliant.name().to_string();
+    
+    // Every pair of simulations is independent, so run them all at once: (without, with)
+    let mut pool = SimulationPool::new(SIMULATION_THREADS);
+    for iteration in 0..iterations {
+        let seed = SIM_SEED.unwrap_or(0).wrapping_add(iteration as u64);
+        pool.add(create_etiquette_sim(WALKER_RATE, Etiquette::LeftBias, Some(seed), None));
+        
+        let mut crowd_simulation = create_etiquette_sim(WALKER_RATE, Etiquette::LeftBias, Some(seed), None);
+        crowd_simulation.add_pedestrian_pulse(NON_COMPLIANT_RELEASE_TIME, NON_COMPLIANT_COUNT, 0, non_compliant.clone());
+        pool.add(crowd_simulation);
+    }
+    pool.simulate_full_with_progress(TIME_SCALE, |finished, total| {
+        eprint!("\rFinished {}/{} simulations", finished, total);
+        if finished == total {
+            eprintln!();
+        }
+    });
+    
+    // The travel times of the compliant pedestrians, and the time that the non-compliant ones were walking (if they were timed)
+    let split_results = |crowd_simulation: &CrowdSim| {
+        let mut compliant = Vec::new();
+        let mut window: Option<(f64, f64)> = None;
+        for (result, behaviour) in crowd_simulation.get_travel_times().iter().zip(crowd_simulation.get_travel_time_behaviours()) {
+            if *behaviour == name {
+                let (travel_time, _, finish_time) = *result;
+                window = Some(window.map_or((finish_time - travel_time, finish_time), |(start, end)| (start.min(finish_time - travel_time), end.max(finish_time))));
+            } else {
+                compliant.push(*result);
+            }
+        }
+        return (compliant, window);
+    };
+    
+    // The mean travel time of the pedestrians that were walking at some point during a window
+    let overlapping_mean = |travel_times: &[(f64, usize, f64)], window: (f64, f64)| -> Option<f64> {
+        let overlapping = travel_times.iter()
+            .filter(|(travel_time, _, finish_time)| finish_time - travel_time < window.1 && *finish_time > window.0)
+            .map(|(travel_time, _, _)| *travel_time)
+            .collect::<Vec<_>>();
+        return if overlapping.is_empty() {None} else {Some(overlapping.iter().sum::<f64>() / (overlapping.len() as f64))};
+    };
+    
+    let mut means = (Vec::new(), Vec::new());
+    let mut overlapping_means = (Vec::new(), Vec::new());
+    for pair in pool.get_simulations().chunks(2) {
+        let (without, _) = split_results(&pair[0]);
+        let (with, window) = split_results(&pair[1]);
+        
+        let mean_without = parse_results(&without, TRIMMED_PEDESTRIANS).1;
+        let mean_with = parse_results(&with, TRIMMED_PEDESTRIANS).1;
+        means.0.push(mean_without);
+        means.1.push(mean_with);
+        
+        let overlapping = window.and_then(|window| overlapping_mean(&without, window).zip(overlapping_mean(&with, window)));
+        match overlapping {
+            Some((overlapping_without, overlapping_with)) => {
+                overlapping_means.0.push(overlapping_without);
+                overlapping_means.1.push(overlapping_with);
+                println!(
+                    "Without: {}s  |  With: {}s  |  Walking at the same time: {}s without, {}s with",
+                    (mean_without * 100.0).round() / 100.0,
+                    (mean_with * 100.0).round() / 100.0,
+                    (overlapping_without * 100.0).round() / 100.0,
+                    (overlapping_with * 100.0).round() / 100.0
+                );
+            },
+            None => {
+                println!(
+                    "Without: {}s  |  With: {}s  |  The non-compliant pedestrians weren't timed",
+                    (mean_without * 100.0).round() / 100.0,
+                    (mean_with * 100.0).round() / 100.0
+                );
+            }
+        }
+    }
+    
+    for (label, (without, with)) in [("Whole run", &means), ("Walking at the same time", &overlapping_means)] {
+        if without.len() < 2 {
+            continue;
+        }
+        
+        let test = WelchTest::new(with, without, 1.0 - SIGNIFICANCE_LEVEL);
+        println!(
+            "{}: with - without {}: {}s ({}% confidence interval {}s to {}s), p = {}{}",
+            label,
+            name,
+            (test.mean_difference * 1000.0).round() / 1000.0,
+            ((1.0 - SIGNIFICANCE_LEVEL) * 100.0).round(),
+            (test.confidence_interval.0 * 1000.0).round() / 1000.0,
+            (test.confidence_interval.1 * 1000.0).round() / 1000.0,
+            test.p_value,
+            if test.is_significant(SIGNIFICANCE_LEVEL) {""} else {" (not significant)"}
+        );
+    }
+}
+
+
+/// Run the crossroads simulation many times with each junction priority rule, and compare their throughput and delay
+fn compare_junction_rules(iterations: usize) {
+    let rules = [None, Some(PriorityRule::GiveWayToRight), Some(PriorityRule::GiveWayToLeft), Some(PriorityRule::FirstComeFirstServed)];
+    
+    // Every run is independent, so run them all at once, grouped by rule
+    let mut pool = SimulationPool::new(SIMULATION_THREADS);
+    for rule in rules {
+        for _ in 0..iterations {
+            pool.add(create_crossroads_sim(rule, None));
+        }
+    }
+    // (total time, pedestrian count, average travel time)
+    let all_results = pool.simulate_full(TIME_SCALE).into_iter()
+        .map(|(total_time, pedestrian_count, travel_times)| (total_time, pedestrian_count, parse_results(travel_times, TRIMMED_PEDESTRIANS).1))
+        .collect::<Vec<_>>();
+    
+    for (i, rule) in rules.iter().enumerate() {
+        let runs = i*iterations..(i+1)*iterations;
+        
+        let mut travel_time_sum = 0.0;
+        let mut throughput_sum = 0.0;
+        let mut delays = Vec::new();
+        for run in runs {
+            let results = &all_results[run];
+            travel_time_sum += results.2;
+            
+            // Pedestrians finished per minute
+            throughput_sum += (results.1 as f64) / results.0 * 60.0;
+            
+            delays.extend(pool.get(run).get_junction_delays().iter().map(|(delay, _)| *delay));
+        }
+        
+        let mean_delay = delays.iter().sum::<f64>() / (delays.len() as f64);
+        let gave_way_share = (delays.iter().filter(|delay| **delay > 0.0).count() as f64) / (delays.len() as f64);
+        
+        println!(
+            "{}: travel time {}s  |  throughput {} ped/min  |  junction delay {}s  |  {}% gave way",
+            rule.as_ref().map_or("no rule", |rule| rule.name()),
+            (travel_time_sum / (iterations as f64) * 100.0).round() / 100.0,
+            (throughput_sum / (iterations as f64) * 100.0).round() / 100.0,
+            (mean_delay * 100.0).round() / 100.0,
+            (gave_way_share * 100.0).round()
+        );
+    }
+    
+    return;
+}
+
+
+/// Run the left-bias and no-bias simulations many times with and without intent signalling, and compare their travel times
+/// 
+/// * `horizon` - How far ahead pedestrians signal the direction they intend to walk in, in seconds
+fn compare_intent_signalling(iterations: usize, horizon: f64) {
+    let scenarios = [("left bias", Etiquette::LeftBias), ("no bias", Etiquette::NoBias)];
+    
+    // Every run is independent, so run them all at once: (without signalling, with signalling) for each iteration of each scenario
+    // Both runs of an iteration are given the same pedestrians, so that the only difference between them is the signalling
+    let mut runs = Vec::new();
+    for (name, etiquette) in &scenarios {
+        for iteration in 0..iterations {
+            let seed = iteration as u64;
+            runs.push((*name, seed, create_etiquette_sim(WALKER_RATE, etiquette.clone(), Some(seed), None)));
+            
+            let mut signalling_sim = create_etiquette_sim(WALKER_RATE, etiquette.clone(), Some(seed), None);
+            signalling_sim.set_intent_signalling(Some(horizon));
+            runs.push((*name, seed, signalling_sim));
+        }
+    }
+    let mut all_results = simulate_with_cache(runs, SIMULATION_THREADS).into_iter();
+    
+    for (name, _) in scenarios {
+        let mut travel_time_sums = (0.0, 0.0);
+        let mut signalling_win_count = 0;
+        
+        for _ in 0..iterations {
+            let without_signalling = parse_results(&all_results.next().unwrap().2, TRIMMED_PEDESTRIANS).1;
+            let with_signalling = parse_results(&all_results.next().unwrap().2, TRIMMED_PEDESTRIANS).1;
+            
+            travel_time_sums.0 += without_signalling;
+            travel_time_sums.1 += with_signalling;
+            if with_signalling < without_signalling {
+                signalling_win_count += 1;
+            }
+        }
+        
+        println!(
+            "{}: without signalling {}s  |  with signalling {}s  |  signalling faster in {} of {} runs",
+            name,
+            (travel_time_sums.0 / (iterations as f64) * 100.0).round() / 100.0,
+            (travel_time_sums.1 / (iterations as f64) * 100.0).round() / 100.0,
+            signalling_win_count,
+            iterations
+        );
+    }
+    
+    return;
+}
+
+
+/// Run the left-bias simulation many times with timesteps of TIME_SCALE, then again with timesteps of COARSE_TIME_SCALE using each integrator,
+/// and report how far each integrator's travel times with the long timesteps are from those with the short ones
+fn compare_integrators(iterations: usize) {
+    let base = scenario_file().cloned().unwrap_or_default();
+    let integrators = [Integrator::Euler, Integrator::Midpoint];
+    
+    // Every run with the same seed starts with the same pedestrians
+    let create_sim = |seed: u64, integrator: Integrator, max_timestep: f64| {
+        let mut settings = base.clone();
+        settings.params.integrator = integrator;
+        settings.params.max_timestep = max_timestep;
+        let run = ExperimentRun {scenario: 0, values: Vec::new(), seed, settings, add_rate: None};
+        return create_simulation(1, Some(&run)).unwrap();
+    };
+    let seeds = (0..iterations as u64).map(|iteration| SIM_SEED.unwrap_or(0).wrapping_add(iteration)).collect::<Vec<_>>();
+    
+    // The mean travel time of each run in a pool
+    let mean_travel_times = |pool: &mut SimulationPool, time_scale: f64| {
+        return pool.simulate_full(time_scale).into_iter()
+            .map(|(_, _, travel_times)| parse_results(travel_times, TRIMMED_PEDESTRIANS).1)
+            .collect::<Vec<_>>();
+    };
+    
+    let mut reference_pool = SimulationPool::new(SIMULATION_THREADS);
+    for seed in &seeds {
+        reference_pool.add(create_sim(*seed, Integrator::Euler, base.params.max_timestep));
+    }
+    let reference = mean_travel_times(&mut reference_pool, TIME_SCALE);
+    let reference_mean = reference.iter().sum::<f64>() / (iterations as f64);
+    println!("{}s timesteps: {}s", TIME_SCALE, (reference_mean * 100.0).round() / 100.0);
+    
+    for integrator in integrators {
+        let mut pool = SimulationPool::new(SIMULATION_THREADS);
+        for seed in &seeds {
+            pool.add(create_sim(*seed, integrator, 0.0));
+        }
+        let coarse = mean_travel_times(&mut pool, COARSE_TIME_SCALE);
+        let coarse_mean = coarse.iter().sum::<f64>() / (iterations as f64);
+        
+        // The difference from the run with the same seed and short timesteps, averaged over the seeds
+        let mean_abs_difference = coarse.iter().zip(&reference).map(|(c, r)| (c - r).abs()).sum::<f64>() / (iterations as f64);
+        
+        println!(
+            "{}s timesteps, {:?} integrator: {}s  |  difference {}s ({}%)  |  mean difference per seed {}s",
+            COARSE_TIME_SCALE,
+            integrator,
+            (coarse_mean * 100.0).round() / 100.0,
+            ((coarse_mean - reference_mean) * 100.0).round() / 100.0,
+            ((coarse_mean - reference_mean) / reference_mean * 10000.0).round() / 100.0,
+            (mean_abs_difference * 100.0).round() / 100.0
+        );
+    }
+}
+
+
+/// Run the left-bias simulation many times with and without DISTANCING, at an add rate high enough to fill the corridor, and report how much throughput is lost to physical distancing.
+/// 
+/// Each pair of runs has the same seed and the same settings (from SCENARIO_FILE, if set), apart from the distancing.
+fn compare_distancing_repeatedly(iterations: usize) {
+    let base = scenario_file().cloned().unwrap_or_default();
+    
+    // Every pair of simulations is independent, so run them all at once: (baseline, distancing)
+    let mut pool = SimulationPool::new(SIMULATION_THREADS);
+    for iteration in 0..iterations {
+        let seed = SIM_SEED.unwrap_or(0).wrapping_add(iteration as u64);
+        for distancing in [None, Some(DISTANCING)] {
+            let mut settings = base.clone();
+            settings.distancing = distancing;
+            let run = ExperimentRun {scenario: 0, values: Vec::new(), seed, settings, add_rate: Some(DISTANCING_WALKER_RATE)};
+            pool.add(create_simulation(1, Some(&run)).unwrap());
+        }
+    }
+    // (pedestrians finished per minute, mean travel time)
+    let all_results = pool.simulate_full(TIME_SCALE).into_iter()
+        .map(|(total_time, pedestrian_count, travel_times)| ((pedestrian_count as f64) / total_time * 60.0, parse_results(travel_times, TRIMMED_PEDESTRIANS).1))
+        .collect::<Vec<_>>();
+    
+    let mut throughputs = (Vec::new(), Vec::new());
+    let mut travel_time_sums = (0.0, 0.0);
+    for pair in all_results.chunks(2) {
+        throughputs.0.push(pair[0].0);
+        throughputs.1.push(pair[1].0);
+        travel_time_sums.0 += pair[0].1;
+        travel_time_sums.1 += pair[1].1;
+    }
+    
+    // The share of the timed pedestrians in the distancing runs that kept their distance
+    let compliant_share = (0..iterations).map(|i| {
+        let crowd_simulation = pool.get(2*i + 1);
+        return (crowd_simulation.get_cohort_results(DISTANCING_COHORT).finished_count as f64) / (crowd_simulation.get_travel_times().len().max(1) as f64);
+    }).sum::<f64>() / (iterations as f64);
+    
+    let baseline = throughputs.0.iter().sum::<f64>() / (iterations as f64);
+    let distancing = throughputs.1.iter().sum::<f64>() / (iterations as f64);
+    println!(
+        "Baseline: throughput {} ped/min, travel time {}s  |  {}% keeping {}m apart: throughput {} ped/min, travel time {}s",
+        (baseline * 100.0).round() / 100.0,
+        (travel_time_sums.0 / (iterations as f64) * 100.0).round() / 100.0,
+        (compliant_share * 100.0).round(),
+        DISTANCING.pspace_radius,
+        (distancing * 100.0).round() / 100.0,
+        (travel_time_sums.1 / (iterations as f64) * 100.0).round() / 100.0
+    );
+    println!("Throughput lost to distancing: {}%", ((1.0 - distancing / baseline) * 10000.0).round() / 100.0);
+    
+    if iterations >= 2 {
+        let test = WelchTest::new(&throughputs.1, &throughputs.0, 1.0 - SIGNIFICANCE_LEVEL);
+        println!(
+            "Distancing - baseline: {} ped/min ({}% confidence interval {} to {} ped/min), p = {}{}",
+            (test.mean_difference * 100.0).round() / 100.0,
+            ((1.0 - SIGNIFICANCE_LEVEL) * 100.0).round(),
+            (test.confidence_interval.0 * 100.0).round() / 100.0,
+            (test.confidence_interval.1 * 100.0).round() / 100.0,
+            test.p_value,
+            if test.is_significant(SIGNIFICANCE_LEVEL) {""} else {" (not significant)"}
+        );
+    }
+    
     return;
 }
 
 
+/// Create one of the simulations that SIM_TYPE selects, or None if the type runs several simulations (e.g. a comparison) instead
+/// 
+/// * `run` - The experiment run the simulation is for, whose seed & settings it is created with (None = not part of an experiment)
+fn create_simulation(sim_type: usize, run: Option<&ExperimentRun>) -> Option<CrowdSim> {
+    return match sim_type {
+        0 => Some(create_calibration_sim(run)),
+        1 => Some(create_left_bias_sim(WALKER_RATE, run)),
+        2 => Some(create_no_bias_sim(WALKER_RATE, run)),
+        3 => Some(create_calibration_sim_vertical(run)),
+        4 => Some(create_diagonal_demo_sim(run)),
+        5 => Some(create_crossroads_sim(JUNCTION_RULE, run)),
+        8 => Some(create_platform_sim(run)),
+        9 => Some(create_corner_sim(run)),
+        10 => Some(create_rooms_sim(run)),
+        13 => Some(create_station_sim(run)),
+        18 => Some(create_gateline_sim(run)),
+        19 => Some(create_ticket_hall_sim(run)),
+        20 => Some(create_travelator_sim(run)),
+        21 => Some(create_cycle_crossing_sim(run)),
+        22 => Some(create_signage_sim(run)),
+        6 | 7 | 11 | 12 | 14 | 15 | 16 | 23 => None,
+        _ => Some(create_demo_sim_1(run))
+    };
+}
+
 fn main() {
     
+    if std::env::args().any(|arg| arg == "--bench") {
+        run_benchmark();
+        return;
+    }
+    
+    if let Some(path) = std::env::args().skip_while(|arg| arg != "--experiment").nth(1) {
+        run_experiment(Path::new(&path));
+        return;
+    }
+    
     let mut crowd_simulation;
     
     match SIM_TYPE {
-        0 => {crowd_simulation = create_calibration_sim()},
-        1 => {crowd_simulation = create_left_bias_sim(WALKER_RATE)},
-        2 => {crowd_simulation = create_no_bias_sim(WALKER_RATE)},
-        3 => {crowd_simulation = create_calibration_sim_vertical()},
-        4 => {crowd_simulation = create_diagonal_demo_sim()},
-        5 => {crowd_simulation = create_crossroads_sim()},
         6 => {
             println!("Varying pedestrian rates");
             println!("Simulation 1:");
@@ -228,87 +1035,1341 @@ fn main() {
             compare_simulations_repeatedly(100);
             return;
         },
-        _ => {crowd_simulation = create_demo_sim_1()}
+        11 => {
+            println!("Compare junction priority rules at the crossroads");
+            compare_junction_rules(10);
+            return;
+        },
+        12 => {
+            println!("Compare left-bias and no-bias with and without intent signalling many times");
+            compare_intent_signalling(20, INTENT_SIGNALLING_HORIZON.unwrap_or(0.5));
+            return;
+        },
+        14 => {
+            // Both simulations need the same seed to start with the same pedestrians
+            let seed = SIM_SEED.unwrap_or_else(rand::random);
+            println!("Compare left-bias and no-bias side by side (seed {})", seed);
+            run_side_by_side(
+                create_etiquette_sim(WALKER_RATE, Etiquette::LeftBias, Some(seed), None), "Left bias",
+                create_etiquette_sim(WALKER_RATE, Etiquette::NoBias, Some(seed), None), "No bias"
+            );
+            return;
+        },
+        15 => {
+            println!("Calibrate the model parameters");
+            calibrate_params();
+            return;
+        },
+        16 => {
+            println!("Measure the effect of non-compliant pedestrians many times");
+            compare_non_compliant_repeatedly(20);
+            return;
+        },
+        17 => {
+            println!("Compare the integrators with long timesteps many times");
+            compare_integrators(10);
+            return;
+        },
+        23 => {
+            println!("Measure the throughput lost to physical distancing many times");
+            compare_distancing_repeatedly(10);
+            return;
+        },
+        _ => {crowd_simulation = create_simulation(SIM_TYPE, None).expect("Not a single simulation")}
     }
     
-    if !RENDER {
-        let results = crowd_simulation.simulate_full(TIME_SCALE);
-        //println!("All results: {:?}", results);
-        
-        let parsed_results = parse_results(results.2, TRIMMED_PEDESTRIANS);
-        
-        println!("Average travel time: {} ± {}s", (parsed_results.1 * 100.0).round() / 100.0, (parsed_results.2 * 100.0).round() / 100.0);
-        println!("Total simulation time: {} hours", (results.0/3600.0 * 100.0).round() / 100.0);
-        println!("Total pedestrian time: {} man-hours", (parsed_results.0/3600.0 * 100.0).round() / 100.0);
-        
-        return;
+    if let Some(switch_time) = LEFT_BIAS_SWITCH_TIME {
+        let from = behaviour_model(Etiquette::LeftBias);
+        crowd_simulation.schedule_behaviour_switch(switch_time, Some(from.name()), behaviour_model(Etiquette::NoBias));
     }
     
+    if let Some(evacuation_time) = EVACUATION_TIME {
+        crowd_simulation.schedule_evacuation(evacuation_time);
+    }
     
-    let (mut rl, thread) = raylib::init()
-        .size(1500, 500)
-        .resizable() // If the window is not resizable it will float by default
-        .title("Pedestrian Simulator")
-        .vsync() // Ensure that the window has vsync enabled (unless overridden by system)
-        .msaa_4x()
-        .build();
+    crowd_simulation.set_intent_signalling(INTENT_SIGNALLING_HORIZON);
     
-    let mut frame_count: u64 = 0;
-    let mut curr_time = time::Instant::now();
+    if let Some(cell_size) = DENSITY_CELL_SIZE {
+        crowd_simulation.enable_density_recording(cell_size);
+    }
     
-    while !rl.window_should_close() {
-        // For calculating frametime
-        let prev_time = curr_time;
-        curr_time = time::Instant::now();
-        let frame_time = curr_time.duration_since(prev_time);
-        
-        let mut rl_handle = rl.begin_drawing(&thread);
-        
-        rl_handle.clear_background(Color::WHITE);
-        
-        // Simulate one timestep & draw the simulation
-        crowd_simulation.simulate_timestep(SIM_SPEED * frame_time.as_secs_f64());
-        crowd_simulation.draw(&mut rl_handle, (100,150), DRAW_SCALE);
-        
-        // Debug text
-        rl_handle.draw_text("Pedestrian Behaviour Simulator", 12, 12, 20, Color::BLACK);
-        rl_handle.draw_text(&format!("Frame count: {}", frame_count), 12, 36, 20, Color::BLACK);
-        rl_handle.draw_text(&format!("Frame time: {}ms", frame_time.as_millis()), 12, 60, 20, Color::BLACK);
-        rl_handle.draw_text(&format!("Simulation time: {}s", (crowd_simulation.time_elapsed*100.0).round()/100.0), 12, 84, 20, Color::BLACK);
-        rl_handle.draw_text(&format!("Available/Active/Finished: {}/{}/{}", crowd_simulation.get_pedestrian_counts().0, crowd_simulation.get_pedestrian_counts().1, crowd_simulation.get_pedestrian_counts().2), 12, 108, 20, Color::BLACK);
-        
-        frame_count += 1;
+    if let Some(cutoff_radius) = VORONOI_CUTOFF_RADIUS {
+        crowd_simulation.enable_voronoi_density(cutoff_radius);
+    }
+    
+    if let Some((cutoff, skin)) = NEIGHBOUR_LISTS {
+        crowd_simulation.enable_neighbour_lists(cutoff, skin);
+    }
+    
+    if let Some(cutoff) = GPU_NEIGHBOURS {
+        crowd_simulation.enable_gpu_neighbours(cutoff).expect("Failed to set up the GPU");
+    }
+    
+    if PROFILE_PHASES {
+        crowd_simulation.enable_profiling();
     }
     
+    if let Some(interval) = TRAJECTORY_INTERVAL {
+        crowd_simulation.enable_trajectory_recording(interval);
+    }
+    
+    if let Some(interval) = PRESSURE_INTERVAL {
+        crowd_simulation.enable_pressure_recording(interval, PRESSURE_CELL_SIZE, PRESSURE_THRESHOLDS);
+    }
+    
+    if let Some(distance) = CONTACT_DISTANCE {
+        crowd_simulation.enable_contact_recording(distance);
+    }
+    
+    if TAG_SPEED_COHORTS {
+        let speed_bounds = crowd_simulation.get_params().target_speed_bounds;
+        let middle_speed = (speed_bounds.0 + speed_bounds.1) / 2.0;
+        crowd_simulation.tag_cohort("slower walkers", |ped| ped.target_speed < middle_speed);
+        crowd_simulation.tag_cohort("faster walkers", |ped| ped.target_speed >= middle_speed);
+    }
+    
+    if EXPORT_SCENARIO {
+        let name = format!("sim_{}", SIM_TYPE);
+        export::write_vadere_scenario(&crowd_simulation, &name, Path::new(&format!("{}.scenario", name))).expect("Failed to write Vadere scenario");
+        export::write_jupedsim_project(&crowd_simulation, &name, Path::new(".")).expect("Failed to write JuPedSim project");
+    }
+    
+    if let Some(path) = REPLAY_FILE {
+        run_replay(&crowd_simulation, Path::new(path));
+        return;
+    }
+    
+    if let Some(path) = RESUME_CHECKPOINT {
+        let checkpoint = Checkpoint::load(Path::new(path)).expect("Failed to load checkpoint");
+        crowd_simulation.restore_checkpoint(checkpoint).expect("Failed to restore checkpoint");
+        println!("Resumed from {} at {}s", path, (crowd_simulation.time_elapsed * 100.0).round() / 100.0);
+    }
+    
+    let peak_occupancy = Arc::new(Mutex::new((0, 0.0)));
+    if REPORT_PEAK_OCCUPANCY {
+        crowd_simulation.add_observer(Box::new(PeakOccupancy {peak: peak_occupancy.clone()}));
+    }
+    
+    if !RENDER {
+        if ENABLE_CONSOLE && !run_with_console(&mut crowd_simulation) {
+            return;
+        }
+        
+        if let Some(checkpoint_time) = CHECKPOINT_TIME {
+            while crowd_simulation.time_elapsed < checkpoint_time {
+                crowd_simulation.simulate_timestep(TIME_SCALE);
+            }
+            let path = format!("sim_{}_checkpoint.toml", SIM_TYPE);
+            crowd_simulation.create_checkpoint().save(Path::new(&path)).expect("Failed to save checkpoint");
+            println!("Saved checkpoint at {}s to {}", (crowd_simulation.time_elapsed * 100.0).round() / 100.0, path);
+        }
+        
+        // Stop at the end of the current timestep on Ctrl-C, keeping the results so far
+        let cancel = CancelToken::new();
+        let handler_token = cancel.clone();
+        ctrlc::set_handler(move || handler_token.cancel()).expect("Failed to set the Ctrl-C handler");
+        
+        let (total_time, _, travel_times) = crowd_simulation.simulate_full_with_progress(TIME_SCALE, Some(&cancel), |progress| {
+            if SHOW_PROGRESS {
+                eprint!("\rProgress: {}% of {} pedestrians finished, {}s simulated ", progress.get_percent_finished().round(), progress.total, progress.sim_time.round());
+            }
+        });
+        if SHOW_PROGRESS {
+            eprintln!();
+        }
+        //println!("All results: {:?}", travel_times);
+        
+        let parsed_results = parse_results(travel_times, TRIMMED_PEDESTRIANS);
+        let trimmed_range = get_trimmed_range(travel_times, TRIMMED_PEDESTRIANS);
+        println!("Using results {} to {} of {}", trimmed_range.start + 1, trimmed_range.end, travel_times.len());
+        
+        let run_status = crowd_simulation.get_run_status();
+        if run_status != RunStatus::Completed {
+            let (available, active, _) = crowd_simulation.get_pedestrian_counts();
+            println!("Warning: the run was {}, with {} pedestrians still walking and {} yet to start. The results are partial.", run_status.name(), active, available);
+        }
+        
+        if crowd_simulation.get_recirculation().is_some() {
+            let (_, active, _) = crowd_simulation.get_pedestrian_counts();
+            println!("Cyclic routes: {} pedestrians walked {} laps", active, crowd_simulation.get_completed_laps());
+        }
+        
+        println!("Neighbour order: {}", crowd_simulation.get_params().neighbour_order.name());
+        if let Some(builds) = crowd_simulation.get_neighbour_list_builds() {
+            println!("Neighbour lists built {} times", builds);
+        }
+        
+        if let Some(profiler) = crowd_simulation.get_profiler() {
+            print_profile(profiler);
+        }
+        
+        println!("Average travel time: {} ± {}s", (parsed_results.1 * 100.0).round() / 100.0, (parsed_results.2 * 100.0).round() / 100.0);
+        println!("Total simulation time: {} hours", (total_time/3600.0 * 100.0).round() / 100.0);
+        println!("Total pedestrian time: {} man-hours", (parsed_results.0/3600.0 * 100.0).round() / 100.0);
+        
+        if crowd_simulation.get_distraction().is_some() {
+            let distracted_count = crowd_simulation.get_travel_time_behaviours().iter().filter(|name| name.starts_with("distracted")).count();
+            println!("Distracted pedestrians: {} of {}", distracted_count, crowd_simulation.get_travel_times().len());
+        }
+        
+        let stall_events = crowd_simulation.get_stall_events();
+        if !stall_events.is_empty() {
+            let stalled_count = stall_events.iter().map(|event| event.pedestrian_id).collect::<HashSet<_>>().len();
+            println!("Stalls: {} ({} pedestrians), recovering with {:?}", stall_events.len(), stalled_count, crowd_simulation.get_params().stall_recovery);
+            for event in stall_events.iter().take(MAX_REPORTED_STALLS) {
+                println!("  Pedestrian {} (group {}) at ({}, {}) after {}s", event.pedestrian_id, event.group,
+                    (event.position.0 * 10.0).round() / 10.0, (event.position.1 * 10.0).round() / 10.0, (event.sim_time * 10.0).round() / 10.0);
+            }
+        }
+        
+        let wayfinding_results = crowd_simulation.get_wayfinding_results();
+        if !wayfinding_results.is_empty() {
+            let count = wayfinding_results.len() as f64;
+            let mean_signs = wayfinding_results.iter().map(|result| result.signs_read).sum::<usize>() as f64 / count;
+            let mean_wrong_turns = wayfinding_results.iter().map(|result| result.wrong_turns).sum::<usize>() as f64 / count;
+            println!("Unfamiliar pedestrians: {} finished, reading {} signs and taking {} wrong turns on average", wayfinding_results.len(),
+                (mean_signs * 100.0).round() / 100.0, (mean_wrong_turns * 100.0).round() / 100.0);
+        }
+        
+        let hazard_casualties = crowd_simulation.get_hazard_casualties();
+        if !hazard_casualties.is_empty() {
+            println!("Incapacitated by hazards: {} pedestrians, the first after {}s", hazard_casualties.len(), (hazard_casualties[0].sim_time * 10.0).round() / 10.0);
+        }
+        
+        for queue in 0..crowd_simulation.get_area().queues.len() {
+            let queue_state = crowd_simulation.get_queue(queue);
+            if let Some((mean_wait, longest_wait)) = queue_state.get_waiting_time_summary() {
+                println!("Queue {}: {} served, waiting {}s on average (longest {}s), peak length {}", queue, queue_state.get_waits().len(),
+                    (mean_wait * 100.0).round() / 100.0, (longest_wait * 100.0).round() / 100.0, queue_state.get_peak_length());
+            }
+        }
+        if !crowd_simulation.get_area().queues.is_empty() {
+            let path = format!("sim_{}_queue_waits.csv", SIM_TYPE);
+            export::write_queue_waits(&crowd_simulation, Path::new(&path)).expect("Failed to write queue waits");
+            println!("Saved {}", path);
+        }
+        
+        for boarding in crowd_simulation.get_boardings() {
+            let train_summary = boarding.get_train_summary();
+            let boarding_times = train_summary.iter().filter_map(|(_, last)| *last).collect::<Vec<_>>();
+            if !boarding_times.is_empty() {
+                let mean_time = boarding_times.iter().sum::<f64>() / (boarding_times.len() as f64);
+                let longest_time = boarding_times.iter().copied().fold(0.0, f64::max);
+                println!("Boarding (group {}): {} boarded {} trains, the last of them {}s after each train arrived on average (longest {}s), {} left behind", boarding.get_group(),
+                    boarding.get_events().len(), boarding_times.len(), (mean_time * 100.0).round() / 100.0, (longest_time * 100.0).round() / 100.0, boarding.get_left_behind().len());
+            }
+        }
+        if !crowd_simulation.get_boardings().is_empty() {
+            let path = format!("sim_{}_boardings.csv", SIM_TYPE);
+            export::write_boardings(&crowd_simulation, Path::new(&path)).expect("Failed to write boardings");
+            println!("Saved {}", path);
+        }
+        
+        if !crowd_simulation.get_area().vehicle_lanes.is_empty() {
+            let traffic = crowd_simulation.get_vehicle_traffic();
+            if let Some((mean_delay, longest_delay)) = traffic.get_delay_summary() {
+                println!("Vehicles: {} finished, delayed {}s on average (longest {}s)", traffic.get_trips().len(),
+                    (mean_delay * 100.0).round() / 100.0, (longest_delay * 100.0).round() / 100.0);
+            }
+            println!("Conflicts between pedestrians and vehicles: {}", traffic.get_conflicts().len());
+            
+            let path = format!("sim_{}_vehicle_conflicts.csv", SIM_TYPE);
+            export::write_vehicle_conflicts(&crowd_simulation, Path::new(&path)).expect("Failed to write vehicle conflicts");
+            println!("Saved {}", path);
+        }
+        
+        let conversation_log = crowd_simulation.get_conversation_log();
+        if !conversation_log.is_empty() {
+            let total_duration = conversation_log.iter().map(|conversation| conversation.duration).sum::<f64>();
+            println!("Conversations: {}, lasting {}s on average", conversation_log.len(), (total_duration / (conversation_log.len() as f64) * 100.0).round() / 100.0);
+        }
+        
+        if REPORT_PEAK_OCCUPANCY {
+            let (peak_count, peak_time) = *peak_occupancy.lock().unwrap();
+            println!("Peak occupancy: {} pedestrians at {}s", peak_count, (peak_time * 100.0).round() / 100.0);
+        }
+        
+        if let Some(bin_width) = RESULT_BREAKDOWN_BIN_WIDTH {
+            let breakdown = ResultBreakdown::new(&crowd_simulation, TRIMMED_PEDESTRIANS, bin_width);
+            for (group, stats) in breakdown.by_group.iter().enumerate() {
+                print_travel_time_stats(&format!("Group {}", group), stats);
+            }
+            for (behaviour, stats) in &breakdown.by_behaviour {
+                print_travel_time_stats(&format!("Behaviour \"{}\"", behaviour), stats);
+            }
+        }
+        
+        let jitter_results = crowd_simulation.get_jitter_results();
+        if !jitter_results.is_empty() {
+            let average_jitter = jitter_results.iter().map(|(jitter, _)| jitter).sum::<f64>() / (jitter_results.len() as f64);
+            println!("Average heading jitter: {} rad", (average_jitter * 1000.0).round() / 1000.0);
+        }
+        
+        for cohort in crowd_simulation.get_cohort_names() {
+            let cohort_results = crowd_simulation.get_cohort_results(cohort);
+            if cohort_results.travel_times.is_empty() {
+                continue;
+            }
+            
+            // Trim each cohort in proportion to its share of the timed pedestrians
+            let trimmed = TRIMMED_PEDESTRIANS * cohort_results.travel_times.len() / crowd_simulation.get_travel_times().len();
+            let parsed_cohort_results = parse_results(&cohort_results.travel_times, trimmed);
+            let average_jitter = cohort_results.jitter_results.iter().map(|(jitter, _)| jitter).sum::<f64>() / (cohort_results.jitter_results.len().max(1) as f64);
+            let mean_delay = cohort_results.junction_delays.iter().map(|(delay, _)| delay).sum::<f64>() / (cohort_results.junction_delays.len().max(1) as f64);
+            
+            println!(
+                "Cohort \"{}\" ({} finished): {} ± {}s  |  heading jitter {} rad  |  junction delay {}s",
+                cohort,
+                cohort_results.finished_count,
+                (parsed_cohort_results.1 * 100.0).round() / 100.0,
+                (parsed_cohort_results.2 * 100.0).round() / 100.0,
+                (average_jitter * 1000.0).round() / 1000.0,
+                (mean_delay * 100.0).round() / 100.0
+            );
+        }
+        
+        for (time, description) in crowd_simulation.get_behaviour_switch_log() {
+            println!("Behaviour switch at {}s: {}", (time * 100.0).round() / 100.0, description);
+        }
+        
+        for (time, description) in crowd_simulation.get_event_log() {
+            println!("Event at {}s: {}", (time * 100.0).round() / 100.0, description);
+        }
+        
+        if let Some(evacuation) = crowd_simulation.get_evacuation_results() {
+            println!("Evacuation started at {}s with {} pedestrians walking", (evacuation.start_time * 100.0).round() / 100.0, evacuation.evacuee_count);
+            println!("Total egress time: {}s", (evacuation.egress_time.unwrap_or(f64::NAN) * 100.0).round() / 100.0);
+            for (i, (usage, peak_queue)) in evacuation.exit_usage.iter().zip(&evacuation.peak_exit_queues).enumerate() {
+                println!("Exit {}: {} pedestrians, peak queue of {}", i, usage, peak_queue);
+            }
+        }
+        
+        if SPAWN_GATE {
+            let (peak_queue, mean_queue) = crowd_simulation.get_spawn_queue_summary();
+            println!("Spawn queue: peak of {} pedestrians, {} on average", peak_queue, (mean_queue * 100.0).round() / 100.0);
+            
+            let path = format!("sim_{}_spawn_queue.csv", SIM_TYPE);
+            export::write_spawn_queue(&crowd_simulation, Path::new(&path)).expect("Failed to write spawn queue");
+            println!("Saved {}", path);
+        }
+        
+        for gate in 0..crowd_simulation.get_area().gates.len() {
+            let gate_state = crowd_simulation.get_gate(gate);
+            if let (Some(throughput), Some(mean_wait)) = (gate_state.get_throughput(), gate_state.get_mean_waiting_time()) {
+                println!("Gate {}: {} through ({} per minute), waiting {}s on average, peak queue of {}", gate, gate_state.get_passages().len(),
+                    (throughput * 10.0).round() / 10.0, (mean_wait * 100.0).round() / 100.0, gate_state.get_peak_length());
+            }
+        }
+        if !crowd_simulation.get_area().gates.is_empty() {
+            let path = format!("sim_{}_gate_passages.csv", SIM_TYPE);
+            export::write_gate_passages(&crowd_simulation, Path::new(&path)).expect("Failed to write gate passages");
+            println!("Saved {}", path);
+            let path = format!("sim_{}_gate_queues.csv", SIM_TYPE);
+            export::write_gate_queues(&crowd_simulation, Path::new(&path)).expect("Failed to write gate queues");
+            println!("Saved {}", path);
+        }
+        
+        if EXPORT_FUNDAMENTAL_DIAGRAM {
+            let path = format!("sim_{}_fundamental_diagram.csv", SIM_TYPE);
+            export::write_fundamental_diagram(&crowd_simulation, Path::new(&path)).expect("Failed to write fundamental diagram");
+            println!("Saved {}", path);
+        }
+        
+        if EXPORT_LINE_FLUX {
+            for line in 0..crowd_simulation.get_area().counting_lines.len() {
+                let crossings = crowd_simulation.get_line_crossings(line);
+                let forward = crossings.iter().filter(|(_, direction)| *direction == CrossingDirection::Forward).count();
+                let peak_flux = crowd_simulation.get_line_flux(line).iter().map(|(_, forward, backward)| forward + backward).fold(0.0, f64::max);
+                println!("Counting line {}: {} forward, {} backward, peak flux of {} pedestrians/s", line, forward, crossings.len() - forward, peak_flux);
+            }
+            
+            let name = format!("sim_{}_line", SIM_TYPE);
+            export::write_line_crossings(&crowd_simulation, Path::new(&format!("{}_crossings.csv", name))).expect("Failed to write line crossings");
+            export::write_line_flux(&crowd_simulation, Path::new(&format!("{}_flux.csv", name))).expect("Failed to write line flux");
+            println!("Saved {}_crossings.csv and {}_flux.csv", name, name);
+        }
+        
+        if SAVE_TRAVEL_TIMES {
+            let path = format!("sim_{}_travel_times.csv", SIM_TYPE);
+            export::write_travel_times(&crowd_simulation, Path::new(&path)).expect("Failed to write travel times");
+            println!("Saved {}", path);
+            
+            #[cfg(feature = "parquet")]
+            {
+                let path = format!("sim_{}_travel_times.parquet", SIM_TYPE);
+                export::write_travel_times_parquet(&crowd_simulation, Path::new(&path)).expect("Failed to write travel times");
+                println!("Saved {}", path);
+            }
+        }
+        
+        if let Some(trajectories) = crowd_simulation.get_trajectories() {
+            let path = format!("sim_{}_trajectories.csv", SIM_TYPE);
+            export::write_trajectories_csv(trajectories, Path::new(&path)).expect("Failed to write trajectories");
+            println!("Saved {} ({} samples)", path, trajectories.get_samples().len());
+            
+            #[cfg(feature = "parquet")]
+            {
+                let path = format!("sim_{}_trajectories.parquet", SIM_TYPE);
+                export::write_trajectories_parquet(trajectories, Path::new(&path)).expect("Failed to write trajectories");
+                println!("Saved {}", path);
+            }
+        }
+        
+        if let Some(pressure) = crowd_simulation.get_pressure() {
+            let (peak_pressure, peak_contact_force) = pressure.get_peaks();
+            println!("Peak crowd pressure: {} s^-2  |  peak contact force: {} m/s² per unit mass", (peak_pressure * 1000.0).round() / 1000.0, (peak_contact_force * 10.0).round() / 10.0);
+            
+            let episodes = pressure.get_episodes();
+            if episodes.is_empty() {
+                println!("No crush risk: the pressure never exceeded the thresholds");
+            } else {
+                println!("Crush risk flagged {} times:", episodes.len());
+                for episode in episodes {
+                    let end = episode.end.map(|end| format!("{}s", (end * 10.0).round() / 10.0)).unwrap_or("the end".to_string());
+                    println!("  ({}, {}) from {}s to {}: peak pressure {} s^-2, peak contact force {} m/s²",
+                        episode.centre.0, episode.centre.1, (episode.start * 10.0).round() / 10.0, end,
+                        (episode.peak_pressure * 1000.0).round() / 1000.0, (episode.peak_contact_force * 10.0).round() / 10.0);
+                }
+            }
+            
+            let name = format!("sim_{}", SIM_TYPE);
+            export::write_pressure_csv(pressure, Path::new(&format!("{}_pressure.csv", name))).expect("Failed to write crowd pressure");
+            export::write_risk_episodes_csv(pressure, Path::new(&format!("{}_crush_risk.csv", name))).expect("Failed to write crush risk");
+            println!("Saved {}_pressure.csv and {}_crush_risk.csv", name, name);
+        }
+        
+        if let Some(contacts) = crowd_simulation.get_contacts() {
+            let exposures = contacts.get_exposures();
+            let close_contacts = exposures.iter().filter(|exposure| exposure.duration >= CLOSE_CONTACT_TIME).count();
+            let longest = exposures.iter().map(|exposure| exposure.duration).fold(0.0, f64::max);
+            println!("Contacts within {}m: {} pairs, {} of them for at least {}s (longest {}s)  |  {} close contacts per pedestrian on average",
+                contacts.get_distance(), exposures.len(), close_contacts, CLOSE_CONTACT_TIME, (longest * 10.0).round() / 10.0,
+                (contacts.get_mean_degree(CLOSE_CONTACT_TIME) * 100.0).round() / 100.0);
+            
+            let path = format!("sim_{}_contacts.csv", SIM_TYPE);
+            export::write_contacts_csv(contacts, Path::new(&path)).expect("Failed to write contacts");
+            println!("Saved {}", path);
+        }
+        
+        if let Some(density_grid) = crowd_simulation.get_density_grid() {
+            println!("Peak average density: {} pedestrians/m²", (density_grid.get_peak_density() * 100.0).round() / 100.0);
+            
+            let name = format!("sim_{}_density", SIM_TYPE);
+            export::write_density_csv(density_grid, Path::new(&format!("{}.csv", name))).expect("Failed to write density grid");
+            export::write_density_png(density_grid, Path::new(&format!("{}.png", name))).expect("Failed to write density heatmap");
+            println!("Saved {}.csv and {}.png", name, name);
+        }
+        
+        return;
+    }
+    
+    
+    let (mut rl, thread) = open_window();
+    
+    let mut frame_count: u64 = 0;
+    let mut curr_time = time::Instant::now();
+    
+    // Annotations are kept alongside the other output files of this simulation, and any made on an earlier run are shown again
+    let annotation_path = annotation::get_annotation_path(&format!("sim_{}", SIM_TYPE));
+    let mut annotations = AnnotationLog::load(&annotation_path).expect("Failed to load annotations");
+    
+    // The view can be moved with the arrow keys or by dragging, & zoomed with the mouse wheel. The text at the top is kept clear.
+    let (mut view_offset, mut view_scale) = initial_view(&rl, crowd_simulation.get_area(), 160, 0);
+    let mut drag_from: Option<Vector2> = None;
+    
+    // The text & marker position of the annotation being typed, if any. The simulation is paused while typing.
+    let mut draft: Option<(String, (f64, f64))> = None;
+    // The index of the next annotation to jump to
+    let mut next_bookmark = 0;
+    // The ID of the pedestrian being inspected, if any
+    let mut inspected: Option<u64> = None;
+    
+    // The simulation can be paused & stepped through one timestep at a time, and sped up or slowed down
+    let mut paused = false;
+    let mut step_requested = false;
+    let mut sim_speed = SIM_SPEED;
+    // Simulation time that has passed in the window but not been simulated yet, in seconds (always less than one timestep after simulating)
+    let mut unsimulated_time = 0.0;
+    
+    // Live statistics are shown in a panel on the right, which can be hidden with S
+    let mut show_statistics = true;
+    
+    // Each overlay can be toggled with the number keys, in the order listed in the help text, and C changes what pedestrians are coloured by
+    let mut layers = DrawLayers::default();
+    
+    while !rl.window_should_close() {
+        // For calculating frametime
+        let prev_time = curr_time;
+        curr_time = time::Instant::now();
+        let frame_time = curr_time.duration_since(prev_time);
+        
+        if let Some((text, marker)) = &mut draft {
+            if type_text(&mut rl, text) {
+                if !text.is_empty() {
+                    annotations.add(Annotation {
+                        time: crowd_simulation.time_elapsed,
+                        text: text.clone(),
+                        marker: *marker,
+                        view_offset,
+                        view_scale
+                    });
+                    match annotations.save(&annotation_path) {
+                        Ok(()) => println!("Saved annotation to {}", annotation_path.display()),
+                        Err(e) => println!("Failed to save annotations: {}", e)
+                    }
+                }
+                draft = None;
+            }
+        } else {
+            if FIT_VIEW && rl.is_window_resized() {
+                (view_offset, view_scale) = fit_view(&rl, crowd_simulation.get_area(), 160, 0);
+            }
+            update_view(&rl, &mut view_offset, &mut view_scale, &mut drag_from);
+            
+            if rl.is_key_pressed(KeyboardKey::KEY_SPACE) {
+                paused = !paused;
+            }
+            if rl.is_key_pressed(KeyboardKey::KEY_PERIOD) {
+                // Stepping pauses the simulation, so that the step can be studied
+                paused = true;
+                step_requested = true;
+            }
+            if rl.is_key_pressed(KeyboardKey::KEY_EQUAL) || rl.is_key_pressed(KeyboardKey::KEY_KP_ADD) {
+                sim_speed = (sim_speed * 2.0).min(SIM_SPEED_BOUNDS.1);
+            }
+            if rl.is_key_pressed(KeyboardKey::KEY_MINUS) || rl.is_key_pressed(KeyboardKey::KEY_KP_SUBTRACT) {
+                sim_speed = (sim_speed / 2.0).max(SIM_SPEED_BOUNDS.0);
+            }
+            
+            if rl.is_key_pressed(KeyboardKey::KEY_ONE) {layers.vision_zones = !layers.vision_zones}
+            if rl.is_key_pressed(KeyboardKey::KEY_TWO) {layers.personal_space = !layers.personal_space}
+            if rl.is_key_pressed(KeyboardKey::KEY_THREE) {layers.velocity_vectors = !layers.velocity_vectors}
+            if rl.is_key_pressed(KeyboardKey::KEY_FOUR) {layers.target_lines = !layers.target_lines}
+            if rl.is_key_pressed(KeyboardKey::KEY_FIVE) {layers.grid = !layers.grid}
+            if rl.is_key_pressed(KeyboardKey::KEY_C) {layers.colour_mode = layers.colour_mode.next()}
+            if rl.is_key_pressed(KeyboardKey::KEY_S) {show_statistics = !show_statistics}
+            if rl.is_key_pressed(KeyboardKey::KEY_H) {
+                layers.heatmap = !layers.heatmap;
+                if layers.heatmap && crowd_simulation.get_density_grid().is_none() {
+                    crowd_simulation.enable_density_recording(LIVE_HEATMAP_CELL_SIZE);
+                }
+            }
+            
+            if rl.is_key_pressed(KeyboardKey::KEY_N) {
+                // Annotate the position under the mouse
+                let mouse = rl.get_mouse_position();
+                let marker = (
+                    ((mouse.x as i32 - view_offset.0) as f64) / (view_scale as f64),
+                    ((mouse.y as i32 - view_offset.1) as f64) / (view_scale as f64)
+                );
+                draft = Some((String::new(), marker));
+                
+                // Don't type the key that started the annotation
+                while rl.get_key_pressed_number().is_some() {}
+            } else if rl.is_mouse_button_pressed(MouseButton::MOUSE_LEFT_BUTTON) {
+                // Inspect the pedestrian closest to the mouse, or stop inspecting if there isn't one nearby
+                let mouse = rl.get_mouse_position();
+                let position = Vec2::new(
+                    ((mouse.x as i32 - view_offset.0) as f64) / (view_scale as f64),
+                    ((mouse.y as i32 - view_offset.1) as f64) / (view_scale as f64)
+                );
+                inspected = crowd_simulation.get_active_pedestrians().iter()
+                    .map(|ped| (ped.get_id(), (Vec2::new(ped.x, ped.y) - position).length()))
+                    .filter(|(_, dist)| *dist <= INSPECT_RADIUS)
+                    .min_by(|a, b| a.1.total_cmp(&b.1))
+                    .map(|(id, _)| id);
+            } else if rl.is_key_pressed(KeyboardKey::KEY_TAB) && !annotations.get_annotations().is_empty() {
+                // Jump to the view of the next annotation. The simulation time can't be rewound, so this only restores the view.
+                let bookmark = &annotations.get_annotations()[next_bookmark % annotations.get_annotations().len()];
+                view_offset = bookmark.view_offset;
+                view_scale = bookmark.view_scale;
+                println!("{}s: {}", (bookmark.time * 100.0).round() / 100.0, bookmark.text);
+                next_bookmark += 1;
+            }
+        }
+        
+        let mut rl_handle = rl.begin_drawing(&thread);
+        
+        rl_handle.clear_background(Color::WHITE);
+        
+        // Simulate the timesteps that have passed since the last frame & draw the simulation, with the pedestrians part of the way through the next timestep
+        if draft.is_none() {
+            if !paused {
+                simulate_frame(&mut [&mut crowd_simulation], &mut unsimulated_time, sim_speed * frame_time.as_secs_f64());
+            } else if step_requested {
+                crowd_simulation.simulate_timestep(TIME_SCALE);
+                unsimulated_time = 0.0;
+            }
+        }
+        step_requested = false;
+        let interpolation = if paused {1.0} else {unsimulated_time / TIME_SCALE};
+        crowd_simulation.draw(&mut rl_handle, view_offset, view_scale, layers, interpolation);
+        let legend_x = rl_handle.get_screen_width() - 260;
+        crowd_simulation.draw_colour_legend(&mut rl_handle, (legend_x, 12), layers.colour_mode);
+        if show_statistics {
+            let panel_x = rl_handle.get_screen_width() - 560;
+            draw_statistics_panel(&mut rl_handle, &crowd_simulation, (panel_x, 12));
+        }
+        annotations.draw(&mut rl_handle, view_offset, view_scale, crowd_simulation.time_elapsed);
+        
+        if let Some((text, marker)) = &draft {
+            rl_handle.draw_circle_lines(
+                view_offset.0 + ((view_scale as f64) * marker.0) as i32,
+                view_offset.1 + ((view_scale as f64) * marker.1) as i32,
+                8.0,
+                Color::BLACK
+            );
+            rl_handle.draw_text(&format!("Annotation (Enter to save): {}_", text), 12, 156, 20, Color::BLACK);
+        }
+        
+        if let Some(id) = inspected {
+            match crowd_simulation.get_active_pedestrians().iter().find(|ped| ped.get_id() == id) {
+                Some(ped) => {
+                    let (x, y) = ped.get_interpolated_position(interpolation);
+                    rl_handle.draw_circle_lines(
+                        view_offset.0 + ((view_scale as f64) * x) as i32,
+                        view_offset.1 + ((view_scale as f64) * y) as i32,
+                        (view_scale as f32) * (ped.get_params().radius as f32) + 4.0,
+                        Color::BLACK
+                    );
+                    rl_handle.draw_text(
+                        &format!(
+                            "Pedestrian {}: group {}  |  {} m/s (target {})  |  {}  |  cohorts: {}",
+                            id,
+                            ped.get_group(),
+                            (ped.inst_speed * 100.0).round() / 100.0,
+                            (ped.target_speed * 100.0).round() / 100.0,
+                            ped.get_behaviour().name(),
+                            ped.get_cohorts().join(", ")
+                        ),
+                        12, 180, 20, Color::BLACK
+                    );
+                },
+                None => rl_handle.draw_text(&format!("Pedestrian {} is no longer walking", id), 12, 180, 20, Color::BLACK)
+            }
+        }
+        
+        // Debug text
+        rl_handle.draw_text("Pedestrian Behaviour Simulator", 12, 12, 20, Color::BLACK);
+        rl_handle.draw_text(&format!("Frame count: {}", frame_count), 12, 36, 20, Color::BLACK);
+        rl_handle.draw_text(&format!("Frame time: {}ms", frame_time.as_millis()), 12, 60, 20, Color::BLACK);
+        rl_handle.draw_text(&format!("Simulation time: {}s  ({}x{})", (crowd_simulation.time_elapsed*100.0).round()/100.0, sim_speed, if paused {", paused"} else {""}), 12, 84, 20, Color::BLACK);
+        rl_handle.draw_text(&format!("Available/Active/Finished: {}/{}/{}", crowd_simulation.get_pedestrian_counts().0, crowd_simulation.get_pedestrian_counts().1, crowd_simulation.get_pedestrian_counts().2), 12, 108, 20, Color::BLACK);
+        rl_handle.draw_text("Space: pause  |  .: step  |  +/-: speed  |  N: annotate at the mouse  |  Tab: next annotation  |  Click: inspect a pedestrian  |  Arrows, right-drag & mouse wheel: move view", 12, 132, 20, Color::GRAY);
+        rl_handle.draw_text("1-5: toggle vision zones, personal space, velocity vectors, target lines & grid  |  C: colour pedestrians by group, behaviour, speed or density  |  H: density heatmap  |  S: statistics", 12, rl_handle.get_screen_height() - 28, 20, Color::GRAY);
+        
+        frame_count += 1;
+    }
+    
+}
+
+
+/// Return the view to start with: fitted to the environment if FIT_VIEW is set, or at DRAW_SCALE otherwise
+/// 
+/// Return format: (offset in pixels, pixels per metre)
+fn initial_view(rl: &RaylibHandle, area: &SimArea, top_margin: i32, bottom_margin: i32) -> ((i32, i32), i32) {
+    if FIT_VIEW {
+        return fit_view(rl, area, top_margin, bottom_margin);
+    }
+    return ((100, 150), DRAW_SCALE);
+}
+
+/// Return the view that fits the whole environment (walls, obstacles, start & end zones) in the window, centred in the space between the margins
+/// 
+/// * `top_margin` - Space to leave clear at the top of the window (e.g. for text), in pixels
+/// * `bottom_margin` - Space to leave clear at the bottom of the window, in pixels
+/// 
+/// Return format: (offset in pixels, pixels per metre)
+fn fit_view(rl: &RaylibHandle, area: &SimArea, top_margin: i32, bottom_margin: i32) -> ((i32, i32), i32) {
+    return fit_view_in(area, (0, top_margin, rl.get_screen_width(), rl.get_screen_height() - top_margin - bottom_margin));
+}
+
+/// Return the view that fits the whole environment in part of the window
+/// 
+/// * `viewport` - The part of the window to fit the environment in: (left, top, width, height), in pixels
+/// 
+/// Return format: (offset in pixels, pixels per metre)
+fn fit_view_in(area: &SimArea, viewport: (i32, i32, i32, i32)) -> ((i32, i32), i32) {
+    let ((min_x, min_y), (max_x, max_y)) = area.get_bounds();
+    if min_x > max_x || min_y > max_y {
+        // An empty environment has nothing to fit
+        return ((100, 150), DRAW_SCALE);
+    }
+    
+    let available = (
+        (viewport.2 - 2*FIT_VIEW_MARGIN).max(1) as f64,
+        (viewport.3 - 2*FIT_VIEW_MARGIN).max(1) as f64
+    );
+    // A single point or line would fit at any scale, so the scale is limited to the zoom range
+    let scale = (available.0 / (max_x - min_x)).min(available.1 / (max_y - min_y)).clamp(5.0, 200.0) as i32;
+    
+    let offset = (
+        viewport.0 + FIT_VIEW_MARGIN + ((available.0 - (max_x - min_x) * scale as f64) / 2.0 - min_x * scale as f64) as i32,
+        viewport.1 + FIT_VIEW_MARGIN + ((available.1 - (max_y - min_y) * scale as f64) / 2.0 - min_y * scale as f64) as i32
+    );
+    
+    return (offset, scale);
+}
+
+/// Move the view with the arrow keys or by dragging with the right (or middle) mouse button, and zoom it with the mouse wheel, keeping the point under the mouse in place
+/// 
+/// * `drag_from` - The mouse position the current drag last moved the view from (None = not dragging)
+fn update_view(rl: &RaylibHandle, view_offset: &mut (i32, i32), view_scale: &mut i32, drag_from: &mut Option<Vector2>) {
+    if rl.is_key_down(KeyboardKey::KEY_LEFT) {view_offset.0 += 10}
+    if rl.is_key_down(KeyboardKey::KEY_RIGHT) {view_offset.0 -= 10}
+    if rl.is_key_down(KeyboardKey::KEY_UP) {view_offset.1 += 10}
+    if rl.is_key_down(KeyboardKey::KEY_DOWN) {view_offset.1 -= 10}
+    
+    let mouse = rl.get_mouse_position();
+    
+    if rl.is_mouse_button_down(MouseButton::MOUSE_RIGHT_BUTTON) || rl.is_mouse_button_down(MouseButton::MOUSE_MIDDLE_BUTTON) {
+        if let Some(from) = drag_from {
+            view_offset.0 += (mouse.x - from.x) as i32;
+            view_offset.1 += (mouse.y - from.y) as i32;
+        }
+        *drag_from = Some(mouse);
+    } else {
+        *drag_from = None;
+    }
+    
+    let new_scale = (*view_scale + 5 * rl.get_mouse_wheel_move() as i32).clamp(5, 200);
+    if new_scale != *view_scale {
+        // The point under the mouse, in metres, stays under the mouse
+        let point = (
+            ((mouse.x as i32 - view_offset.0) as f64) / (*view_scale as f64),
+            ((mouse.y as i32 - view_offset.1) as f64) / (*view_scale as f64)
+        );
+        *view_offset = (
+            mouse.x as i32 - (point.0 * new_scale as f64) as i32,
+            mouse.y as i32 - (point.1 * new_scale as f64) as i32
+        );
+        *view_scale = new_scale;
+    }
+}
+
+/// Run two simulations side by side in the window, stepped together with the same timesteps, so that differences in behaviour can be seen as they happen.
+/// The left half of the window shows the first simulation & the right half the second, with the same view in each.
+/// 
+/// When not rendering, run both to completion and print their average travel times instead.
+fn run_side_by_side(mut sim_a: CrowdSim, label_a: &str, mut sim_b: CrowdSim, label_b: &str) {
+    if !RENDER {
+        for (label, crowd_simulation) in [(label_a, &mut sim_a), (label_b, &mut sim_b)] {
+            let results = crowd_simulation.simulate_full(TIME_SCALE);
+            let parsed_results = parse_results(results.2, TRIMMED_PEDESTRIANS);
+            println!("{}: {} ± {}s", label, (parsed_results.1 * 100.0).round() / 100.0, (parsed_results.2 * 100.0).round() / 100.0);
+            if crowd_simulation.get_run_status() != RunStatus::Completed {
+                println!("  ({})", crowd_simulation.get_run_status().name());
+            }
+        }
+        return;
+    }
+    
+    let (mut rl, thread) = open_window();
+    let mut curr_time = time::Instant::now();
+    
+    // The text at the top is kept clear, and the view offset is relative to the left edge of each half
+    const TOP_MARGIN: i32 = 116;
+    let half_viewport = |rl: &RaylibHandle| (0, TOP_MARGIN, rl.get_screen_width() / 2, rl.get_screen_height() - TOP_MARGIN - 36);
+    let (mut view_offset, mut view_scale) = if FIT_VIEW {fit_view_in(sim_a.get_area(), half_viewport(&rl))} else {((100, 150), DRAW_SCALE)};
+    let mut drag_from: Option<Vector2> = None;
+    
+    let mut paused = false;
+    let mut step_requested = false;
+    let mut sim_speed = SIM_SPEED;
+    let mut unsimulated_time = 0.0;
+    let mut layers = DrawLayers::default();
+    
+    while !rl.window_should_close() {
+        let prev_time = curr_time;
+        curr_time = time::Instant::now();
+        let frame_time = curr_time.duration_since(prev_time);
+        
+        let half_width = rl.get_screen_width() / 2;
+        
+        if FIT_VIEW && rl.is_window_resized() {
+            (view_offset, view_scale) = fit_view_in(sim_a.get_area(), half_viewport(&rl));
+        }
+        
+        // Zoom about the mouse in whichever half it is in
+        let mouse_in_right_half = rl.get_mouse_position().x as i32 >= half_width;
+        if mouse_in_right_half {view_offset.0 += half_width}
+        update_view(&rl, &mut view_offset, &mut view_scale, &mut drag_from);
+        if mouse_in_right_half {view_offset.0 -= half_width}
+        
+        if rl.is_key_pressed(KeyboardKey::KEY_SPACE) {
+            paused = !paused;
+        }
+        if rl.is_key_pressed(KeyboardKey::KEY_PERIOD) {
+            paused = true;
+            step_requested = true;
+        }
+        if rl.is_key_pressed(KeyboardKey::KEY_EQUAL) || rl.is_key_pressed(KeyboardKey::KEY_KP_ADD) {
+            sim_speed = (sim_speed * 2.0).min(SIM_SPEED_BOUNDS.1);
+        }
+        if rl.is_key_pressed(KeyboardKey::KEY_MINUS) || rl.is_key_pressed(KeyboardKey::KEY_KP_SUBTRACT) {
+            sim_speed = (sim_speed / 2.0).max(SIM_SPEED_BOUNDS.0);
+        }
+        
+        if rl.is_key_pressed(KeyboardKey::KEY_ONE) {layers.vision_zones = !layers.vision_zones}
+        if rl.is_key_pressed(KeyboardKey::KEY_TWO) {layers.personal_space = !layers.personal_space}
+        if rl.is_key_pressed(KeyboardKey::KEY_THREE) {layers.velocity_vectors = !layers.velocity_vectors}
+        if rl.is_key_pressed(KeyboardKey::KEY_FOUR) {layers.target_lines = !layers.target_lines}
+        if rl.is_key_pressed(KeyboardKey::KEY_FIVE) {layers.grid = !layers.grid}
+        if rl.is_key_pressed(KeyboardKey::KEY_C) {layers.colour_mode = layers.colour_mode.next()}
+        
+        // Both simulations are given the same timesteps, so they stay in lockstep
+        if !paused {
+            simulate_frame(&mut [&mut sim_a, &mut sim_b], &mut unsimulated_time, sim_speed * frame_time.as_secs_f64());
+        } else if step_requested {
+            sim_a.simulate_timestep(TIME_SCALE);
+            sim_b.simulate_timestep(TIME_SCALE);
+            unsimulated_time = 0.0;
+        }
+        step_requested = false;
+        let interpolation = if paused {1.0} else {unsimulated_time / TIME_SCALE};
+        
+        let mut rl_handle = rl.begin_drawing(&thread);
+        
+        rl_handle.clear_background(Color::WHITE);
+        
+        let screen_height = rl_handle.get_screen_height();
+        for (i, (label, crowd_simulation)) in [(label_a, &sim_a), (label_b, &sim_b)].into_iter().enumerate() {
+            let left = (i as i32) * half_width;
+            
+            // Clip each simulation to its half, so that panning one doesn't draw over the other
+            unsafe { raylib::ffi::BeginScissorMode(left, TOP_MARGIN, half_width, screen_height - TOP_MARGIN) };
+            crowd_simulation.draw(&mut rl_handle, (left + view_offset.0, view_offset.1), view_scale, layers, interpolation);
+            unsafe { raylib::ffi::EndScissorMode() };
+            
+            let (available, active, finished) = crowd_simulation.get_pedestrian_counts();
+            let mean_travel_time = if finished > 0 {parse_results(crowd_simulation.get_travel_times(), 0).1} else {f64::NAN};
+            rl_handle.draw_text(label, left + 12, 60, 20, Color::BLACK);
+            rl_handle.draw_text(
+                &format!("Available/Active/Finished: {}/{}/{}  |  Mean travel time: {}s", available, active, finished, (mean_travel_time * 100.0).round() / 100.0),
+                left + 12, 84, 20, Color::BLACK
+            );
+        }
+        rl_handle.draw_line(half_width, 60, half_width, screen_height, Color::GRAY);
+        
+        rl_handle.draw_text("Pedestrian Behaviour Simulator - side by side", 12, 12, 20, Color::BLACK);
+        rl_handle.draw_text(&format!("Simulation time: {}s  ({}x{})", (sim_a.time_elapsed*100.0).round()/100.0, sim_speed, if paused {", paused"} else {""}), 12, 36, 20, Color::BLACK);
+        rl_handle.draw_text("Space: pause  |  .: step  |  +/-: speed  |  1-5: toggle overlays  |  C: colour pedestrians  |  Arrows, right-drag & mouse wheel: move view", 12, screen_height - 28, 20, Color::GRAY);
+    }
+}
+
+/// Advance simulations by the time that has passed in a frame, in whole timesteps of TIME_SCALE, carrying the remainder over to the next frame
+/// 
+/// * `unsimulated_time` - Time carried over from earlier frames, in seconds
+/// * `elapsed` - Simulation time that passed during the frame, in seconds
+fn simulate_frame(crowd_simulations: &mut [&mut CrowdSim], unsimulated_time: &mut f64, elapsed: f64) {
+    *unsimulated_time += elapsed;
+    
+    let mut timesteps = 0;
+    while *unsimulated_time >= TIME_SCALE && timesteps < MAX_TIMESTEPS_PER_FRAME {
+        for crowd_simulation in crowd_simulations.iter_mut() {
+            crowd_simulation.simulate_timestep(TIME_SCALE);
+        }
+        *unsimulated_time -= TIME_SCALE;
+        timesteps += 1;
+    }
+    
+    // Drop any time that couldn't be caught up on (e.g. after a long frame), so the simulation doesn't keep trying to catch up
+    *unsimulated_time = unsimulated_time.min(TIME_SCALE);
+}
+
+/// Open the window that simulations are drawn in
+fn open_window() -> (RaylibHandle, RaylibThread) {
+    return raylib::init()
+        .size(1500, 500)
+        .resizable() // If the window is not resizable it will float by default
+        .title("Pedestrian Simulator")
+        .vsync() // Ensure that the window has vsync enabled (unless overridden by system)
+        .msaa_4x()
+        .build();
+}
+
+/// Play back a trajectory file recorded by an earlier run, drawn over the environment of the given simulation, with play/pause and a timeline to seek with.
+/// 
+/// When not rendering, print the number of pedestrians walking & their mean speed every second of the recording instead.
+fn run_replay(crowd_simulation: &CrowdSim, path: &Path) {
+    let replay = Replay::load(path).expect("Failed to load replay");
+    let (start_time, end_time) = replay.get_time_range();
+    println!("Loaded {} frames from {} ({}s to {}s)", replay.get_frame_count(), path.display(), (start_time * 100.0).round() / 100.0, (end_time * 100.0).round() / 100.0);
+    
+    if !RENDER {
+        let mut time = start_time;
+        while time <= end_time {
+            let frame = replay.get_frame(time);
+            let mean_speed = frame.iter().map(|sample| sample.speed).sum::<f64>() / (frame.len().max(1) as f64);
+            println!("{}s: {} walking, mean speed {} m/s", (time * 100.0).round() / 100.0, frame.len(), (mean_speed * 100.0).round() / 100.0);
+            time += 1.0;
+        }
+        return;
+    }
+    
+    let (mut rl, thread) = open_window();
+    let mut curr_time = time::Instant::now();
+    
+    let annotation_path = annotation::get_annotation_path(&format!("sim_{}", SIM_TYPE));
+    let annotations = AnnotationLog::load(&annotation_path).expect("Failed to load annotations");
+    
+    let (mut view_offset, mut view_scale) = initial_view(&rl, crowd_simulation.get_area(), 110, 48);
+    let mut drag_from: Option<Vector2> = None;
+    let mut next_bookmark = 0;
+    
+    let mut time = start_time;
+    let mut playing = true;
+    
+    while !rl.window_should_close() {
+        let prev_time = curr_time;
+        curr_time = time::Instant::now();
+        let frame_time = curr_time.duration_since(prev_time);
+        
+        // The timeline runs along the bottom of the window
+        let timeline = Rectangle::new(12.0, (rl.get_screen_height() - 36) as f32, (rl.get_screen_width() - 24) as f32, 12.0);
+        
+        if FIT_VIEW && rl.is_window_resized() {
+            (view_offset, view_scale) = fit_view(&rl, crowd_simulation.get_area(), 110, 48);
+        }
+        update_view(&rl, &mut view_offset, &mut view_scale, &mut drag_from);
+        
+        if rl.is_key_pressed(KeyboardKey::KEY_SPACE) {
+            playing = !playing;
+        }
+        
+        if rl.is_key_pressed(KeyboardKey::KEY_TAB) && !annotations.get_annotations().is_empty() {
+            // Jump to the time & view of the next annotation
+            let bookmark = &annotations.get_annotations()[next_bookmark % annotations.get_annotations().len()];
+            time = bookmark.time.clamp(start_time, end_time);
+            view_offset = bookmark.view_offset;
+            view_scale = bookmark.view_scale;
+            println!("{}s: {}", (bookmark.time * 100.0).round() / 100.0, bookmark.text);
+            next_bookmark += 1;
+        }
+        
+        // Seek by clicking or dragging along the timeline (with some leeway above & below it)
+        let mouse = rl.get_mouse_position();
+        if rl.is_mouse_button_down(MouseButton::MOUSE_LEFT_BUTTON) && (mouse.y - (timeline.y + timeline.height / 2.0)).abs() <= 16.0 {
+            let fraction = ((mouse.x - timeline.x) / timeline.width).clamp(0.0, 1.0) as f64;
+            time = start_time + fraction * (end_time - start_time);
+        } else if playing {
+            time = (time + SIM_SPEED * frame_time.as_secs_f64()).min(end_time);
+        }
+        
+        let mut rl_handle = rl.begin_drawing(&thread);
+        
+        rl_handle.clear_background(Color::WHITE);
+        
+        crowd_simulation.get_area().draw(&mut rl_handle, view_offset, view_scale, DrawLayers::default(), crowd_simulation.get_door_states());
+        replay.draw(&mut rl_handle, view_offset, view_scale, time, crowd_simulation.get_params().radius);
+        annotations.draw(&mut rl_handle, view_offset, view_scale, time);
+        
+        // Timeline, with a tick at the time of each annotation
+        let fraction = if end_time > start_time {((time - start_time) / (end_time - start_time)) as f32} else {1.0};
+        rl_handle.draw_rectangle_rec(timeline, Color::LIGHTGRAY);
+        rl_handle.draw_rectangle_rec(Rectangle::new(timeline.x, timeline.y, timeline.width * fraction, timeline.height), Color::GRAY);
+        for annotation in annotations.get_annotations() {
+            if end_time > start_time {
+                let x = timeline.x + timeline.width * (((annotation.time - start_time) / (end_time - start_time)).clamp(0.0, 1.0) as f32);
+                rl_handle.draw_line(x as i32, timeline.y as i32 - 4, x as i32, (timeline.y + timeline.height) as i32 + 4, Color::BLACK);
+            }
+        }
+        
+        rl_handle.draw_text(&format!("Replay: {}", path.display()), 12, 12, 20, Color::BLACK);
+        rl_handle.draw_text(&format!("Simulation time: {} / {}s{}", (time * 100.0).round() / 100.0, (end_time * 100.0).round() / 100.0, if playing {""} else {" (paused)"}), 12, 36, 20, Color::BLACK);
+        rl_handle.draw_text(&format!("Walking: {}", replay.get_frame(time).len()), 12, 60, 20, Color::BLACK);
+        rl_handle.draw_text("Space: play/pause  |  Click the timeline: seek  |  Tab: next annotation  |  Arrows, right-drag & mouse wheel: move view", 12, 84, 20, Color::GRAY);
+    }
+}
+
+/// Add the characters typed since the last frame to some text, with Backspace deleting the last character
+/// 
+/// Returns true if Enter was pressed.
+fn type_text(rl: &mut RaylibHandle, text: &mut String) -> bool {
+    let shift = rl.is_key_down(KeyboardKey::KEY_LEFT_SHIFT) || rl.is_key_down(KeyboardKey::KEY_RIGHT_SHIFT);
+    
+    while let Some(key) = rl.get_key_pressed_number() {
+        if key == KeyboardKey::KEY_ENTER as u32 {
+            return true;
+        } else if key == KeyboardKey::KEY_BACKSPACE as u32 {
+            text.pop();
+        } else if let Some(c) = char::from_u32(key).filter(|c| c.is_ascii_graphic() || *c == ' ') {
+            // Letter keys are reported as capitals
+            text.push(if shift {c} else {c.to_ascii_lowercase()});
+        }
+    }
+    return false;
+}
+
+
+/// Run the simulation until every pedestrian has finished, following commands from the console between timesteps
+/// 
+/// Returns false if the simulation was stopped early with the quit command.
+fn run_with_console(crowd_simulation: &mut CrowdSim) -> bool {
+    let console = Console::start(CONSOLE_ADDRESS).expect("Failed to start console");
+    let mut paused = false;
+    
+    println!("Console ready. Type \"help\" for a list of commands.");
+    
+    loop {
+        let (available, active, _) = crowd_simulation.get_pedestrian_counts();
+        if available + active == 0 {
+            return true;
+        }
+        
+        for received in console.poll() {
+            let command = match received {
+                Ok(command) => command,
+                Err(message) => {
+                    eprintln!("{}", message);
+                    continue;
+                }
+            };
+            
+            match command {
+                Command::Pause => {paused = true},
+                Command::Resume => {paused = false},
+                Command::SetRate(rate) => {crowd_simulation.set_pedestrian_add_rate(rate)},
+                Command::Evacuate => {crowd_simulation.start_evacuation()},
+                Command::Snapshot(path) => {
+                    let path = path.unwrap_or(format!("snapshot_{}.csv", crowd_simulation.time_elapsed.round()));
+                    match export::write_snapshot(crowd_simulation, Path::new(&path)) {
+                        Ok(()) => println!("Wrote {}", path),
+                        Err(e) => println!("Failed to write snapshot: {}", e)
+                    }
+                },
+                Command::Status => {
+                    let (available, active, finished) = crowd_simulation.get_pedestrian_counts();
+                    println!(
+                        "Simulation time: {}s{}  |  Available/Active/Finished: {}/{}/{}",
+                        (crowd_simulation.time_elapsed * 100.0).round() / 100.0,
+                        if paused {" (paused)"} else {""},
+                        available, active, finished
+                    );
+                },
+                Command::Quit(name) => {
+                    let name = name.unwrap_or(format!("sim_{}", SIM_TYPE));
+                    export::write_snapshot(crowd_simulation, Path::new(&format!("{}_snapshot.csv", name))).expect("Failed to write snapshot");
+                    export::write_travel_times(crowd_simulation, Path::new(&format!("{}_travel_times.csv", name))).expect("Failed to write travel times");
+                    println!("Saved {}_snapshot.csv and {}_travel_times.csv", name, name);
+                    return false;
+                }
+            }
+        }
+        
+        if paused {
+            thread::sleep(time::Duration::from_millis(100));
+        } else {
+            crowd_simulation.simulate_timestep(TIME_SCALE);
+        }
+    }
+}
+
+
+/// Return the range of the raw results from a full simulation that are used, leaving out the temporal extremes
+/// 
+/// * `trim_extremes_count` - The number of results to leave out at each end if DETECT_STEADY_STATE is off (0 = use every result, even if it is on)
+fn get_trimmed_range(sim_results: &[(f64, usize, f64)], trim_extremes_count: usize) -> std::ops::Range<usize> {
+    if trim_extremes_count == 0 {
+        return 0..sim_results.len();
+    }
+    
+    if DETECT_STEADY_STATE {
+        return statistics::get_steady_state_range(&sim_results.iter().map(|t| t.0).collect::<Vec<_>>());
+    }
+    
+    // Runs cut short (e.g. by an evacuation) may have too few results to trim the full amount
+    let trim_extremes_count = trim_extremes_count.min(sim_results.len() / 2);
+    return trim_extremes_count..(sim_results.len()-trim_extremes_count);
+}
+
+/// Parse the raw results from a full simulation
+/// 
+/// * `trim_extremes_count` - See `get_trimmed_range`
+/// 
+/// Returns (total travel time, average travel time, standard deviation)
+fn parse_results(sim_results: &[(f64, usize, f64)], trim_extremes_count: usize) -> (f64, f64, f64) {
+    
+    let trimmed_results = &sim_results[get_trimmed_range(sim_results, trim_extremes_count)];
+    
+    let total_travel_time = trimmed_results.iter().map(|t| t.0).sum::<f64>();
+    
+    let mean_travel_time: f64 = total_travel_time / (trimmed_results.len() as f64);
+    
+    let travel_time_variance = trimmed_results.iter().map(|t| {
+        let diff = mean_travel_time - t.0;
+        
+        diff*diff
+    }).sum::<f64>() / (trimmed_results.len() as f64);
+    
+    return (total_travel_time, mean_travel_time, travel_time_variance.sqrt());
+    
+}
+
+
+/// Fit the model parameters in CALIBRATION_PARAMS so that the calibration simulation's travel times match CALIBRATION_GOAL, using the Nelder–Mead method
+/// 
+/// Every set of parameters is judged on the same seeds, so that the optimiser compares parameters rather than luck.
+fn calibrate_params() {
+    let base = scenario_file().cloned().unwrap_or_default();
+    let start = CALIBRATION_PARAMS.iter().map(|name| base.params.get_param(name).expect("Invalid calibration parameter")).collect::<Vec<_>>();
+    // Start by trying each parameter 10% higher
+    let steps = start.iter().map(|value| if *value == 0.0 {0.1} else {value * 0.1}).collect::<Vec<_>>();
+    
+    let mut evaluation = 0;
+    let objective = |values: &[f64]| {
+        evaluation += 1;
+        
+        // Every parameter being fitted is a distance, rate or intensity
+        if values.iter().any(|value| *value < 0.0) {
+            return f64::INFINITY;
+        }
+        
+        let mut settings = base.clone();
+        for (name, value) in CALIBRATION_PARAMS.iter().zip(values) {
+            settings.params = settings.params.with_param(name, *value).expect("Invalid calibration parameter");
+        }
+        
+        let runs = (0..CALIBRATION_REPETITIONS as u64).map(|seed| {
+            let run = ExperimentRun {scenario: 0, values: values.to_vec(), seed, settings: settings.clone(), add_rate: None};
+            ("calibration", seed, create_simulation(0, Some(&run)).unwrap())
+        }).collect();
+        
+        let parsed_results = simulate_with_cache(runs, SIMULATION_THREADS).iter().map(|results| parse_results(&results.2, TRIMMED_PEDESTRIANS)).collect::<Vec<_>>();
+        let mean = parsed_results.iter().map(|parsed| parsed.1).sum::<f64>() / (parsed_results.len() as f64);
+        let std_dev = parsed_results.iter().map(|parsed| parsed.2).sum::<f64>() / (parsed_results.len() as f64);
+        
+        // The squared error of both statistics, in seconds²
+        let error = (mean - CALIBRATION_GOAL.0).powi(2) + (std_dev - CALIBRATION_GOAL.1).powi(2);
+        
+        println!(
+            "{}: {:?} -> {} ± {}s (error {})",
+            evaluation,
+            values.iter().map(|value| (value * 10000.0).round() / 10000.0).collect::<Vec<_>>(),
+            (mean * 100.0).round() / 100.0,
+            (std_dev * 100.0).round() / 100.0,
+            (error * 10000.0).round() / 10000.0
+        );
+        
+        error
+    };
+    
+    let (fitted, error) = calibration::nelder_mead(objective, &start, &steps, CALIBRATION_MAX_EVALUATIONS, 1e-4);
+    
+    println!("Fitted parameters (squared error {}), for the [params] table of a scenario file:", (error * 10000.0).round() / 10000.0);
+    for (name, value) in CALIBRATION_PARAMS.iter().zip(&fitted) {
+        println!("{} = {}", name, (value * 10000.0).round() / 10000.0);
+    }
+}
+
+/// Run every simulation in an experiment file, and write a row of results for each run to the experiment's CSV file
+fn run_experiment(path: &Path) {
+    let experiment = Experiment::from_file(path).expect("Failed to load experiment file");
+    let experiment_runs = experiment.get_runs().expect("Invalid experiment");
+    
+    let mut runs = Vec::new();
+    for run in &experiment_runs {
+        let scenario = &experiment.scenarios[run.scenario];
+        
+        let crowd_simulation = create_simulation(scenario.simulation, Some(run));
+        runs.push((scenario.name.as_str(), run.seed, crowd_simulation.expect("Experiment scenarios must be single simulations")));
+    }
+    
+    println!("Running {} simulations from {}", runs.len(), path.display());
+    let all_results = simulate_with_cache(runs, experiment.threads.unwrap_or(SIMULATION_THREADS));
+    
+    let outcomes = all_results.iter().map(|(total_time, pedestrian_count, travel_times)| {
+        let parsed_results = parse_results(travel_times, TRIMMED_PEDESTRIANS);
+        vec![
+            ("total_time", *total_time),
+            ("pedestrian_count", *pedestrian_count as f64),
+            ("timed_count", travel_times.len() as f64),
+            ("mean_travel_time", parsed_results.1),
+            ("std_dev", parsed_results.2)
+        ]
+    }).collect::<Vec<_>>();
+    
+    experiment.write_results(&experiment_runs, &outcomes).expect("Failed to write experiment results");
+    println!("Wrote {}", experiment.output);
+}
+
+
+/// Time the simulation of a corridor with more and more pedestrians in it, and report how long each simulated second takes to compute.
+/// 
+/// The pedestrians are all released at once into a corridor that is widened to keep the density the same, so the results show how the simulation scales with the size of the crowd.
+/// NEIGHBOUR_LISTS and GPU_NEIGHBOURS apply, so the benefit of neighbour lists or the GPU can be measured too.
+fn run_benchmark() {
+    println!("Benchmark: {}s of a corridor at {} pedestrians/m², {}s timesteps", BENCH_DURATION, BENCH_DENSITY, TIME_SCALE);
+    println!("{:>11} | {:>14} | {:>13}", "pedestrians", "ms/simulated s", "x real time");
+    
+    for count in BENCH_PEDESTRIAN_COUNTS {
+        let mut crowd_simulation = create_bench_sim(count);
+        
+        if let Some((cutoff, skin)) = NEIGHBOUR_LISTS {
+            crowd_simulation.enable_neighbour_lists(cutoff, skin);
+        }
+        if let Some(cutoff) = GPU_NEIGHBOURS {
+            crowd_simulation.enable_gpu_neighbours(cutoff).expect("Failed to set up the GPU");
+        }
+        
+        // Release the pedestrians before starting the clock, so that only walking is timed
+        crowd_simulation.simulate_timestep(TIME_SCALE);
+        let start_time = crowd_simulation.time_elapsed;
+        
+        let start = time::Instant::now();
+        while crowd_simulation.time_elapsed - start_time < BENCH_DURATION {
+            crowd_simulation.simulate_timestep(TIME_SCALE);
+        }
+        let elapsed = start.elapsed().as_secs_f64();
+        let simulated = crowd_simulation.time_elapsed - start_time;
+        
+        println!("{:>11} | {:>14.1} | {:>13.2}", count, elapsed * 1000.0 / simulated, simulated / elapsed);
+    }
+}
+
+/// Create a benchmark simulation: a 40 metre corridor, wide enough to hold `count` pedestrians at BENCH_DENSITY, with half of them walking each way
+fn create_bench_sim(count: usize) -> CrowdSim {
+    const LENGTH: f64 = 40.0;
+    let width = (count as f64 / (BENCH_DENSITY * LENGTH)).max(4.0);
+    
+    let mut simulated_area = SimArea::new();
+    
+    simulated_area.add_wall((-1.0,0.0), (LENGTH+1.0,0.0));
+    simulated_area.add_wall((-1.0,width), (LENGTH+1.0,width));
+    simulated_area.add_wall((-1.0,0.0), (-1.0,width));
+    simulated_area.add_wall((LENGTH+1.0,0.0), (LENGTH+1.0,width));
+    
+    // Each group starts in one half of the corridor and walks to the far end
+    simulated_area.add_start_end_zone_group(
+        vec![Zone::Rectangle(Vec2::new(0.0,0.5), Vec2::new(LENGTH/2.0,width-0.5))],
+        vec![Zone::Segment(Vec2::new(LENGTH,0.5), Vec2::new(LENGTH,width-0.5))]
+    );
+    simulated_area.add_start_end_zone_group(
+        vec![Zone::Rectangle(Vec2::new(LENGTH/2.0,0.5), Vec2::new(LENGTH,width-0.5))],
+        vec![Zone::Segment(Vec2::new(0.0,0.5), Vec2::new(0.0,width-0.5))]
+    );
+    
+    let mut crowd_simulation = CrowdSim::new(Arc::new(simulated_area), WALKER_RATE);
+    
+    // The same pedestrians every run, so that runs can be compared
+    crowd_simulation.set_seed(SIM_SEED.unwrap_or(0));
+    
+    crowd_simulation.add_pedestrian_pulse(0.0, count / 2, 0, behaviour_model(Etiquette::NoBias));
+    crowd_simulation.add_pedestrian_pulse(0.0, count - count / 2, 1, behaviour_model(Etiquette::NoBias));
+    
+    return crowd_simulation;
 }
 
 
+/// Print the time spent in each phase of the simulation, in total and per timestep
+fn print_profile(profiler: &Profiler) {
+    let overall = profiler.get_overall_total().as_secs_f64();
+    let timesteps = profiler.get_timestep_count().max(1) as f64;
+    println!("Profile over {} timesteps ({}s in total):", profiler.get_timestep_count(), (overall * 1000.0).round() / 1000.0);
+    for phase in Phase::ALL {
+        let total = profiler.get_total(phase).as_secs_f64();
+        println!(
+            "  {:<16} {:>9.3}s  {:>5.1}%  {:>8.2}µs/timestep",
+            phase.name(),
+            total,
+            if overall > 0.0 { total / overall * 100.0 } else { 0.0 },
+            total / timesteps * 1e6
+        );
+    }
+}
 
-/// Parse the raw results from a full simulation
-/// 
-/// Returns (total travel time, average travel time, standard deviation)
-fn parse_results(sim_results: Vec<(f64, usize, f64)>, trim_extremes_count: usize) -> (f64, f64, f64) {
-    
-    let trimmed_results = &sim_results[trim_extremes_count..(sim_results.len()-trim_extremes_count)];
+
+/// Print a summary of some travel times, with a histogram drawn as bars of '#' characters
+fn print_travel_time_stats(label: &str, stats: &TravelTimeStats) {
+    let percentiles = stats.percentiles.iter().map(|(p, t)| format!("p{} {}s", p, (t * 100.0).round() / 100.0)).collect::<Vec<_>>();
+    println!(
+        "{} ({} timed): mean {} ± {}s  |  median {}s  |  {}",
+        label,
+        stats.count,
+        (stats.mean * 100.0).round() / 100.0,
+        (stats.std_dev * 100.0).round() / 100.0,
+        (stats.median * 100.0).round() / 100.0,
+        percentiles.join(", ")
+    );
     
-    let total_travel_time = trimmed_results.iter().map(|t| t.0).sum::<f64>();
+    let largest_bin = stats.histogram.iter().map(|(_, count)| *count).max().unwrap_or(0).max(1);
+    for (bin_start, count) in &stats.histogram {
+        println!("  {:>7.2}s | {:<40} {}", bin_start, "#".repeat(count * 40 / largest_bin), count);
+    }
+}
+
+
+/// Draw live statistics of a running simulation with RayLib: the mean speed, the density & speed in each measurement area, the recent flow between the timing boundaries, and a histogram of the travel times so far
+/// 
+/// * `position` - The top-left corner of the panel, in pixels
+fn draw_statistics_panel(rl_handle: &mut RaylibDrawHandle, crowd_simulation: &CrowdSim, position: (i32, i32)) {
+    let mut lines = Vec::new();
+    
+    let active = crowd_simulation.get_active_pedestrians();
+    if !active.is_empty() {
+        let mean_speed = active.iter().map(|ped| ped.inst_speed).sum::<f64>() / (active.len() as f64);
+        lines.push(format!("Mean speed: {} m/s", (mean_speed * 100.0).round() / 100.0));
+    }
     
-    let mean_travel_time: f64 = total_travel_time / (trimmed_results.len() as f64);
+    for i in 0..crowd_simulation.get_area().measurement_areas.len() {
+        let (density, speed) = crowd_simulation.get_current_area_density(i);
+        lines.push(format!(
+            "Area {}: {} ped/m², {}",
+            i,
+            (density * 100.0).round() / 100.0,
+            speed.map_or(String::from("empty"), |speed| format!("{} m/s", (speed * 100.0).round() / 100.0))
+        ));
+    }
     
-    let travel_time_variance = trimmed_results.iter().map(|t| {
-        let diff = mean_travel_time - t.0;
-        
-        diff*diff
-    }).sum::<f64>() / (trimmed_results.len() as f64);
+    lines.push(format!("Flow (last {}s): {} ped/s", HUD_FLOW_WINDOW, (crowd_simulation.get_recent_flow(HUD_FLOW_WINDOW) * 100.0).round() / 100.0));
     
-    return (total_travel_time, mean_travel_time, travel_time_variance.sqrt());
+    for (i, line) in lines.iter().enumerate() {
+        rl_handle.draw_text(line, position.0, position.1 + 24 * (i as i32), 20, Color::BLACK);
+    }
     
+    // The travel time histogram is drawn as a bar chart below the text
+    let travel_times = crowd_simulation.get_travel_times().iter().map(|(time, _, _)| *time).collect::<Vec<_>>();
+    let stats = TravelTimeStats::new(&travel_times, HUD_HISTOGRAM_BIN_WIDTH);
+    if stats.histogram.is_empty() {
+        return;
+    }
+    let largest_bin = stats.histogram.iter().map(|(_, count)| *count).max().unwrap_or(0).max(1);
+    
+    let chart_top = position.1 + 24 * (lines.len() as i32) + 4;
+    let (chart_width, chart_height) = (260, 60);
+    let bar_width = (chart_width / stats.histogram.len() as i32).max(1);
+    for (i, (_, count)) in stats.histogram.iter().enumerate() {
+        let bar_height = ((*count * chart_height as usize) / largest_bin) as i32;
+        rl_handle.draw_rectangle(position.0 + bar_width * (i as i32), chart_top + chart_height - bar_height, (bar_width - 1).max(1), bar_height, Color::GRAY);
+    }
+    rl_handle.draw_text(
+        &format!("Travel times: {}s to {}s", stats.histogram[0].0, stats.histogram[stats.histogram.len() - 1].0 + HUD_HISTOGRAM_BIN_WIDTH),
+        position.0,
+        chart_top + chart_height + 4,
+        20,
+        Color::BLACK
+    );
+}
+
+/// Records the most pedestrians walking at once, and when
+struct PeakOccupancy {
+    /// (number of pedestrians, simulation time in seconds), shared with whoever reports it
+    peak: Arc<Mutex<(usize, f64)>>
 }
 
+impl Observer for PeakOccupancy {
+    fn on_timestep_completed(&mut self, crowd_sim: &CrowdSim) {
+        let active_count = crowd_sim.get_active_pedestrians().len();
+        let mut peak = self.peak.lock().unwrap();
+        if active_count > peak.0 {
+            *peak = (active_count, crowd_sim.time_elapsed);
+        }
+    }
+}
 
 
 /// Demonstration & debugging simulation
-fn create_demo_sim_1() -> CrowdSim {
+fn create_demo_sim_1(run: Option<&ExperimentRun>) -> CrowdSim {
     let mut simulated_area_1 = SimArea::new();
     
     simulated_area_1.add_wall((0.0,0.0), (20.0,0.0));
@@ -324,23 +2385,23 @@ fn create_demo_sim_1() -> CrowdSim {
         vec![(-1.0,1.0), (-1.0,3.0), (-1.0,5.0), (-1.0,7.0), (5.0, 4.0)]
     );
     
-    let mut crowd_simulation = CrowdSim::new(Arc::new(simulated_area_1), 4.0);
+    let mut crowd_simulation = new_crowd_sim(simulated_area_1, 4.0, run);
     
     // Pedestrians moving left-to-right
-    crowd_simulation.add_pedestrian(0, 3, 4, 1.35, Etiquette::LeftBias);
-    crowd_simulation.add_pedestrian(0, 0, 2, 1.35, Etiquette::LeftBias);
-    crowd_simulation.add_pedestrian(0, 1, 0, 1.35, Etiquette::LeftBias);
-    crowd_simulation.add_pedestrian(0, 2, 0, 1.35, Etiquette::LeftBias);
-    crowd_simulation.add_pedestrian(0, 2, 1, 1.35, Etiquette::LeftBias);
-    crowd_simulation.add_pedestrian(0, 2, 1, 2.5,  Etiquette::LeftBias);
-    crowd_simulation.add_pedestrian(0, 2, 1, 2.0,  Etiquette::LeftBias);
+    crowd_simulation.add_pedestrian(0, 3, 4, 1.35, behaviour_model(Etiquette::LeftBias));
+    crowd_simulation.add_pedestrian(0, 0, 2, 1.35, behaviour_model(Etiquette::LeftBias));
+    crowd_simulation.add_pedestrian(0, 1, 0, 1.35, behaviour_model(Etiquette::LeftBias));
+    crowd_simulation.add_pedestrian(0, 2, 0, 1.35, behaviour_model(Etiquette::LeftBias));
+    crowd_simulation.add_pedestrian(0, 2, 1, 1.35, behaviour_model(Etiquette::LeftBias));
+    crowd_simulation.add_pedestrian(0, 2, 1, 2.5,  behaviour_model(Etiquette::LeftBias));
+    crowd_simulation.add_pedestrian(0, 2, 1, 2.0,  behaviour_model(Etiquette::LeftBias));
     
     // Pedestrians moving right-to-left
-    crowd_simulation.add_pedestrian(1, 3, 4, 1.35, Etiquette::NoBias);
-    crowd_simulation.add_pedestrian(1, 0, 2, 1.35, Etiquette::NoBias);
-    crowd_simulation.add_pedestrian(1, 1, 0, 1.35, Etiquette::NoBias);
-    crowd_simulation.add_pedestrian(1, 2, 0, 1.35, Etiquette::NoBias);
-    crowd_simulation.add_pedestrian(1, 2, 1, 1.35, Etiquette::NoBias);
+    crowd_simulation.add_pedestrian(1, 3, 4, 1.35, behaviour_model(Etiquette::NoBias));
+    crowd_simulation.add_pedestrian(1, 0, 2, 1.35, behaviour_model(Etiquette::NoBias));
+    crowd_simulation.add_pedestrian(1, 1, 0, 1.35, behaviour_model(Etiquette::NoBias));
+    crowd_simulation.add_pedestrian(1, 2, 0, 1.35, behaviour_model(Etiquette::NoBias));
+    crowd_simulation.add_pedestrian(1, 2, 1, 1.35, behaviour_model(Etiquette::NoBias));
     
     crowd_simulation.randomise_pedestrian_order();
     
@@ -349,23 +2410,23 @@ fn create_demo_sim_1() -> CrowdSim {
 }
 
 /// Same as the calibration simulation, but using a vertical version of the environment
-fn create_calibration_sim_vertical() -> CrowdSim {
+fn create_calibration_sim_vertical(run: Option<&ExperimentRun>) -> CrowdSim {
     /// Normalised ratio of left-, non-, and right-biased pedestrians
     const BIAS_RATIOS: (f64, f64, f64) = (0.443877551020408, 0.520408163265306, 0.0357142857142857);
     
     let simulated_area = create_testing_environment_vertical();
     
-    let mut crowd_simulation = CrowdSim::new(Arc::new(simulated_area), WALKER_RATE);
+    let mut crowd_simulation = new_crowd_sim(simulated_area, WALKER_RATE, run);
     
     // Pedestrians moving left-to-right
-    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.0*0.5) as usize, 0, Etiquette::LeftBias);
-    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.1*0.5) as usize, 0, Etiquette::NoBias);
-    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.2*0.5) as usize, 0, Etiquette::RightBias);
+    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.0*0.5) as usize, 0, behaviour_model(Etiquette::LeftBias));
+    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.1*0.5) as usize, 0, behaviour_model(Etiquette::NoBias));
+    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.2*0.5) as usize, 0, behaviour_model(Etiquette::RightBias));
     
     // Pedestrians moving right-to-left
-    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.0*0.5) as usize, 1, Etiquette::LeftBias);
-    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.1*0.5) as usize, 1, Etiquette::NoBias);
-    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.2*0.5) as usize, 1, Etiquette::RightBias);
+    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.0*0.5) as usize, 1, behaviour_model(Etiquette::LeftBias));
+    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.1*0.5) as usize, 1, behaviour_model(Etiquette::NoBias));
+    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.2*0.5) as usize, 1, behaviour_model(Etiquette::RightBias));
     
     crowd_simulation.randomise_pedestrian_order();
     
@@ -386,6 +2447,12 @@ fn create_testing_environment_vertical() -> SimArea {
     simulated_area.add_timing_boundary((0.0,3.0), (6.0,3.0));
     simulated_area.add_timing_boundary((0.0,28.0), (6.0,28.0));
     
+    // Fundamental diagram measurements in the middle of the corridor
+    simulated_area.add_measurement_area((0.0,12.0), (6.0,19.0));
+    
+    // Throughput of the middle of the corridor
+    simulated_area.add_counting_line((0.0,15.5), (6.0,15.5));
+    
     // Start & end group moving top-to-bottom
     simulated_area.add_start_end_group(
         vec![(1.0,0.0), (2.0,0.0), (3.0,0.0), (4.0,0.0), (5.0,0.0)],
@@ -401,7 +2468,7 @@ fn create_testing_environment_vertical() -> SimArea {
 }
 
 /// Simulation to demonstrate that diagonal boundaries work
-fn create_diagonal_demo_sim() -> CrowdSim {
+fn create_diagonal_demo_sim(run: Option<&ExperimentRun>) -> CrowdSim {
     /// Normalised ratio of left-, non-, and right-biased pedestrians
     const BIAS_RATIOS: (f64, f64, f64) = (0.44, 0.52, 0.04);
     
@@ -424,17 +2491,17 @@ fn create_diagonal_demo_sim() -> CrowdSim {
     simulated_area_diagonal.add_timing_boundary((1.0,5.0), (5.0,1.0));
     simulated_area_diagonal.add_timing_boundary((11.0,15.0), (15.0,11.0));
     
-    let mut crowd_simulation = CrowdSim::new(Arc::new(simulated_area_diagonal), WALKER_RATE);
+    let mut crowd_simulation = new_crowd_sim(simulated_area_diagonal, WALKER_RATE, run);
     
     // Pedestrians moving left-to-right
-    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.0*0.5) as usize, 0, Etiquette::LeftBias);
-    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.1*0.5) as usize, 0, Etiquette::NoBias);
-    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.2*0.5) as usize, 0, Etiquette::RightBias);
+    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.0*0.5) as usize, 0, behaviour_model(Etiquette::LeftBias));
+    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.1*0.5) as usize, 0, behaviour_model(Etiquette::NoBias));
+    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.2*0.5) as usize, 0, behaviour_model(Etiquette::RightBias));
     
     // Pedestrians moving right-to-left
-    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.0*0.5) as usize, 1, Etiquette::LeftBias);
-    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.1*0.5) as usize, 1, Etiquette::NoBias);
-    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.2*0.5) as usize, 1, Etiquette::RightBias);
+    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.0*0.5) as usize, 1, behaviour_model(Etiquette::LeftBias));
+    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.1*0.5) as usize, 1, behaviour_model(Etiquette::NoBias));
+    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.2*0.5) as usize, 1, behaviour_model(Etiquette::RightBias));
     
     crowd_simulation.randomise_pedestrian_order();
     
@@ -443,7 +2510,9 @@ fn create_diagonal_demo_sim() -> CrowdSim {
 }
 
 /// Experimental simulation with two crossing pathways
-fn create_crossroads_sim() -> CrowdSim {
+/// 
+/// * `junction_rule` - The priority rule applied where the streams cross (None = no junction rule)
+fn create_crossroads_sim(junction_rule: Option<PriorityRule>, run: Option<&ExperimentRun>) -> CrowdSim {
     /// Normalised ratio of left-, non-, and right-biased pedestrians
     const BIAS_RATIOS: (f64, f64, f64) = (0.44, 0.52, 0.04);
     
@@ -496,27 +2565,493 @@ fn create_crossroads_sim() -> CrowdSim {
     simulated_area_crossroads.add_timing_boundary((12.5,3.0), (18.5,3.0));
     simulated_area_crossroads.add_timing_boundary((12.5,28.0), (18.5,28.0));
     
-    let mut crowd_simulation = CrowdSim::new(Arc::new(simulated_area_crossroads), WALKER_RATE);
+    // Exits at the end of each arm
+    simulated_area_crossroads.add_exit((-0.5,15.5));
+    simulated_area_crossroads.add_exit((31.5,15.5));
+    simulated_area_crossroads.add_exit((15.5,-0.5));
+    simulated_area_crossroads.add_exit((15.5,31.5));
+    
+    // Junction zone where the two streams cross
+    if let Some(rule) = junction_rule {
+        simulated_area_crossroads.add_junction_zone((12.5,12.5), (18.5,18.5), rule);
+    }
+    
+    let mut crowd_simulation = new_crowd_sim(simulated_area_crossroads, WALKER_RATE, run);
     
     // Pedestrians moving left-to-right
-    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.0*0.25) as usize, 0, Etiquette::LeftBias);
-    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.1*0.25) as usize, 0, Etiquette::NoBias);
-    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.2*0.25) as usize, 0, Etiquette::RightBias);
+    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.0*0.25) as usize, 0, behaviour_model(Etiquette::LeftBias));
+    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.1*0.25) as usize, 0, behaviour_model(Etiquette::NoBias));
+    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.2*0.25) as usize, 0, behaviour_model(Etiquette::RightBias));
     
     // Pedestrians moving right-to-left
-    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.0*0.25) as usize, 1, Etiquette::LeftBias);
-    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.1*0.25) as usize, 1, Etiquette::NoBias);
-    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.2*0.25) as usize, 1, Etiquette::RightBias);
+    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.0*0.25) as usize, 1, behaviour_model(Etiquette::LeftBias));
+    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.1*0.25) as usize, 1, behaviour_model(Etiquette::NoBias));
+    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.2*0.25) as usize, 1, behaviour_model(Etiquette::RightBias));
     
     //// Pedestrians moving top-to-bottom
-    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.0*0.25) as usize, 2, Etiquette::LeftBias);
-    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.1*0.25) as usize, 2, Etiquette::NoBias);
-    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.2*0.25) as usize, 2, Etiquette::RightBias);
+    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.0*0.25) as usize, 2, behaviour_model(Etiquette::LeftBias));
+    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.1*0.25) as usize, 2, behaviour_model(Etiquette::NoBias));
+    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.2*0.25) as usize, 2, behaviour_model(Etiquette::RightBias));
     //
     //// Pedestrians moving bottom-to-top
-    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.0*0.25) as usize, 3, Etiquette::LeftBias);
-    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.1*0.25) as usize, 3, Etiquette::NoBias);
-    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.2*0.25) as usize, 3, Etiquette::RightBias);
+    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.0*0.25) as usize, 3, behaviour_model(Etiquette::LeftBias));
+    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.1*0.25) as usize, 3, behaviour_model(Etiquette::NoBias));
+    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.2*0.25) as usize, 3, behaviour_model(Etiquette::RightBias));
+    
+    crowd_simulation.randomise_pedestrian_order();
+    
+    return crowd_simulation;
+    
+}
+
+/// Train platform where passengers alight in pulses and walk to exits at either end
+fn create_platform_sim(run: Option<&ExperimentRun>) -> CrowdSim {
+    /// Normalised ratio of left-, non-, and right-biased pedestrians
+    const BIAS_RATIOS: (f64, f64, f64) = (0.44, 0.52, 0.04);
+    
+    let mut simulated_area_platform = SimArea::new();
+    
+    // Platform edge (train side) & back wall
+    simulated_area_platform.add_wall((0.0,0.0), (40.0,0.0));
+    simulated_area_platform.add_wall((0.0,4.0), (40.0,4.0));
+    
+    // Tactile safety line along the platform edge
+    simulated_area_platform.add_marking((0.0,0.8), (40.0,0.8));
+    
+    // Roof pillars between the doors
+    simulated_area_platform.add_circular_obstacle((11.0,2.8), 0.25);
+    simulated_area_platform.add_circular_obstacle((20.0,2.8), 0.25);
+    simulated_area_platform.add_circular_obstacle((29.0,2.8), 0.25);
+    
+    // Alighting passengers: train doors to the exits at either end
+    simulated_area_platform.add_start_end_group(
+        vec![(6.0,0.5), (16.0,0.5), (24.0,0.5), (34.0,0.5)],
+        vec![(-1.0,2.0), (41.0,2.0)]
+    );
+    
+    // Boarding passengers: either end of the platform to the train doors (they board from where they stand instead of reaching the doors, unless PLATFORM_DOOR_CAPACITY is set).
+    // They start inside the timing boundaries at either end, so that they aren't timed as they come onto the platform.
+    if PLATFORM_BOARDING_PER_TRAIN.is_some() {
+        simulated_area_platform.add_start_end_group(
+            vec![(3.0,2.0), (37.0,2.0)],
+            vec![(6.0,0.5), (16.0,0.5), (24.0,0.5), (34.0,0.5)]
+        );
+    }
+    
+    // Exits at either end of the platform
+    simulated_area_platform.add_exit((-1.0,2.0));
+    simulated_area_platform.add_exit((41.0,2.0));
+    
+    // A fire against the back wall in the middle of the platform
+    if let Some(fire_time) = PLATFORM_FIRE_TIME {
+        simulated_area_platform.add_hazard((20.0,4.0), fire_time, PLATFORM_SMOKE_SPREAD.0, PLATFORM_SMOKE_SPREAD.1);
+    }
+    
+    // Stairs up to the exits, inside the timing boundaries at either end
+    if let Some(multiplier) = PLATFORM_STAIRS_SPEED {
+        simulated_area_platform.add_speed_zone(vec![(2.0,1.0), (6.0,1.0), (6.0,4.0), (2.0,4.0)], multiplier);
+        simulated_area_platform.add_speed_zone(vec![(34.0,1.0), (38.0,1.0), (38.0,4.0), (34.0,4.0)], multiplier);
+    }
+    
+    // Timing barriers: leaving the doors, and reaching either end of the platform
+    simulated_area_platform.add_timing_boundary((0.0,1.0), (40.0,1.0));
+    simulated_area_platform.add_timing_boundary((2.0,0.0), (2.0,4.0));
+    simulated_area_platform.add_timing_boundary((38.0,0.0), (38.0,4.0));
+    
+    // Train arrival times, either from a timetable or at a fixed headway
+    let pulses = match GTFS_STOP_TIMES_PATH {
+        Some(path) => {
+            let arrivals = gtfs::read_stop_arrivals(Path::new(path), GTFS_STOP_ID).expect("Failed to read GTFS stop times");
+            gtfs::arrivals_to_pulses(&arrivals, GTFS_TIME_WINDOW, PLATFORM_ALIGHTING_PER_TRAIN)
+        },
+        None => {
+            let train_count = (TOTAL_PEDESTRIANS as usize) / PLATFORM_ALIGHTING_PER_TRAIN;
+            (0..train_count).map(|i| (10.0 + (i as f64)*PLATFORM_HEADWAY, PLATFORM_ALIGHTING_PER_TRAIN)).collect()
+        }
+    };
+    
+    // Platform screen doors in line with the train doors, which open when each train arrives and close again once passengers have had time to get off
+    if PLATFORM_SCREEN_DOORS {
+        for (x1, x2) in [(0.0,5.0), (7.0,15.0), (17.0,23.0), (25.0,33.0), (35.0,40.0)] {
+            simulated_area_platform.add_wall((x1,0.9), (x2,0.9));
+        }
+        let schedule: Vec<(f64, bool)> = pulses.iter().flat_map(|&(time, _)| [(time, true), (time + PLATFORM_DOOR_OPEN_TIME, false)]).collect();
+        for (x1, x2) in [(5.0,7.0), (15.0,17.0), (23.0,25.0), (33.0,35.0)] {
+            simulated_area_platform.add_door((x1,0.9), (x2,0.9), false, schedule.clone());
+            
+            // The sides of the train doorway, so that alighting passengers can't get behind the screen
+            simulated_area_platform.add_wall((x1,0.0), (x1,0.9));
+            simulated_area_platform.add_wall((x2,0.0), (x2,0.9));
+        }
+    }
+    
+    // No continuous arrivals - all passengers come from trains
+    let mut crowd_simulation = new_crowd_sim(simulated_area_platform, WALKER_RATE, run);
+    
+    // Passengers step off the train once there is room, so that they don't start on top of each other in the doorways,
+    // and plan their way out through the doorways instead of walking into their sides
+    if PLATFORM_SCREEN_DOORS {
+        crowd_simulation.set_spawn_gate(true);
+        crowd_simulation.enable_path_planning(PATH_PLANNING_CLEARANCE);
+    }
+    
+    // Waiting passengers walk to the train doors and board there, getting in the way of the alighting passengers
+    if let (Some(capacity), Some(_)) = (PLATFORM_DOOR_CAPACITY, PLATFORM_BOARDING_PER_TRAIN) {
+        crowd_simulation.set_spawn_gate(true);
+        crowd_simulation.add_boarding(1, capacity, pulses.iter().map(|(time, _)| (*time, time + PLATFORM_DOOR_OPEN_TIME)).collect());
+    }
+    let after_waiting = if PLATFORM_DOOR_CAPACITY.is_some() {AfterDwell::Resume} else {AfterDwell::Leave};
+    
+    for (time, count) in pulses {
+        // The same number of passengers step off at each door
+        crowd_simulation.add_pedestrian_burst(time, ((count as f64)*BIAS_RATIOS.0) as usize, 0, behaviour_model(Etiquette::LeftBias));
+        crowd_simulation.add_pedestrian_burst(time, ((count as f64)*BIAS_RATIOS.1) as usize, 0, behaviour_model(Etiquette::NoBias));
+        crowd_simulation.add_pedestrian_burst(time, ((count as f64)*BIAS_RATIOS.2) as usize, 0, behaviour_model(Etiquette::RightBias));
+        
+        // Waiting passengers stand between the tactile line and the pillars, clear of the timing boundary along the doors
+        if let Some(boarding_count) = PLATFORM_BOARDING_PER_TRAIN {
+            let waiting_zone = Zone::Rectangle(Vec2::new(3.0,1.3), Vec2::new(37.0,2.0));
+            crowd_simulation.add_standing_pedestrians((time - PLATFORM_WAITING_TIME).max(0.0), boarding_count, 1, waiting_zone, (PLATFORM_WAITING_TIME, after_waiting), behaviour_model(Etiquette::LeftBias));
+        }
+    }
+    
+    return crowd_simulation;
+    
+}
+
+
+/// Create a simulation of an L-shaped corridor, where pedestrians follow a waypoint around the corner
+fn create_corner_sim(run: Option<&ExperimentRun>) -> CrowdSim {
+    /// Normalised ratio of left-, non-, and right-biased pedestrians
+    const BIAS_RATIOS: (f64, f64, f64) = (0.44, 0.52, 0.04);
+    
+    let mut simulated_area_corner = SimArea::new();
+    
+    // Horizontal leg
+    simulated_area_corner.add_wall((-1.0,0.0), (20.0,0.0));
+    simulated_area_corner.add_wall((-1.0,4.0), (16.0,4.0));
+    simulated_area_corner.add_wall((-1.0,0.0), (-1.0,4.0));
+    
+    // Vertical leg
+    simulated_area_corner.add_wall((20.0,0.0), (20.0,24.0));
+    simulated_area_corner.add_wall((16.0,4.0), (16.0,24.0));
+    simulated_area_corner.add_wall((16.0,24.0), (20.0,24.0));
+    
+    // Start & end group moving from the horizontal leg to the vertical leg
+    simulated_area_corner.add_start_end_group(
+        vec![(0.0,1.0), (0.0,2.0), (0.0,3.0)],
+        vec![(17.0,22.0), (18.0,22.0), (19.0,22.0)]
+    );
+    // Start & end group moving from the vertical leg to the horizontal leg
+    simulated_area_corner.add_start_end_group(
+        vec![(17.0,23.0), (18.0,23.0), (19.0,23.0)],
+        vec![(1.0,1.0), (1.0,2.0), (1.0,3.0)]
+    );
+    
+    // Both groups turn at the corner, instead of walking straight into the inside wall
+    simulated_area_corner.set_group_waypoints(0, vec![(18.0,2.0)]);
+    simulated_area_corner.set_group_waypoints(1, vec![(18.0,2.0)]);
+    
+    // Timing barriers
+    simulated_area_corner.add_timing_boundary((3.0,0.0), (3.0,4.0));
+    simulated_area_corner.add_timing_boundary((16.0,20.0), (20.0,20.0));
+    
+    let mut crowd_simulation = new_crowd_sim(simulated_area_corner, WALKER_RATE, run);
+    
+    for group in 0..2 {
+        crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.0*0.5) as usize, group, behaviour_model(Etiquette::LeftBias));
+        crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.1*0.5) as usize, group, behaviour_model(Etiquette::NoBias));
+        crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.2*0.5) as usize, group, behaviour_model(Etiquette::RightBias));
+    }
+    
+    crowd_simulation.randomise_pedestrian_order();
+    
+    return crowd_simulation;
+    
+}
+
+
+/// Create a simulation of a ticket hall, where some of the pedestrians walking through in either direction queue at one of two ticket machines on the way
+fn create_ticket_hall_sim(run: Option<&ExperimentRun>) -> CrowdSim {
+    /// Normalised ratio of left-, non-, and right-biased pedestrians
+    const BIAS_RATIOS: (f64, f64, f64) = (0.44, 0.52, 0.04);
+    
+    let mut simulated_area_hall = SimArea::new();
+    
+    // Hall walls
+    simulated_area_hall.add_wall((0.0,0.0), (30.0,0.0));
+    simulated_area_hall.add_wall((0.0,8.0), (30.0,8.0));
+    
+    // Ticket machines against the back wall
+    simulated_area_hall.add_circular_obstacle((10.0,8.0), 0.4);
+    simulated_area_hall.add_circular_obstacle((20.0,8.0), 0.4);
+    
+    // Groups 0 & 1 walk straight through, groups 2 & 3 buy a ticket on the way
+    for _ in 0..2 {
+        simulated_area_hall.add_start_end_group(vec![(-1.0,2.0), (-1.0,4.0), (-1.0,6.0)], vec![(31.0,2.0), (31.0,4.0), (31.0,6.0)]);
+        simulated_area_hall.add_start_end_group(vec![(31.0,2.0), (31.0,4.0), (31.0,6.0)], vec![(-1.0,2.0), (-1.0,4.0), (-1.0,6.0)]);
+    }
+    
+    // A queue at each machine, running along the back wall away from the other machine.
+    // Pedestrians walking from the left queue at the left machine, and those walking from the right at the right machine.
+    simulated_area_hall.add_queue(vec![(10.0,7.0), (2.0,7.0)], TICKET_SERVICE_TIME, vec![2]);
+    simulated_area_hall.add_queue(vec![(20.0,7.0), (28.0,7.0)], TICKET_SERVICE_TIME, vec![3]);
+    
+    // Timing barriers
+    simulated_area_hall.add_timing_boundary((1.0,0.0), (1.0,8.0));
+    simulated_area_hall.add_timing_boundary((29.0,0.0), (29.0,8.0));
+    
+    let mut crowd_simulation = new_crowd_sim(simulated_area_hall, WALKER_RATE, run);
+    
+    for group in 0..4 {
+        let fraction = if group < 2 {(1.0 - TICKET_BUYER_FRACTION) * 0.5} else {TICKET_BUYER_FRACTION * 0.5};
+        crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*fraction*BIAS_RATIOS.0) as usize, group, behaviour_model(Etiquette::LeftBias));
+        crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*fraction*BIAS_RATIOS.1) as usize, group, behaviour_model(Etiquette::NoBias));
+        crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*fraction*BIAS_RATIOS.2) as usize, group, behaviour_model(Etiquette::RightBias));
+    }
+    
+    crowd_simulation.randomise_pedestrian_order();
+    
+    return crowd_simulation;
+    
+}
+
+
+/// Create a simulation of a corridor with a moving walkway along one side, which one stream takes while the other stream walks beside it the other way
+fn create_travelator_sim(run: Option<&ExperimentRun>) -> CrowdSim {
+    /// Normalised ratio of left-, non-, and right-biased pedestrians
+    const BIAS_RATIOS: (f64, f64, f64) = (0.44, 0.52, 0.04);
+    
+    let mut simulated_area_travelator = SimArea::new();
+    
+    // Corridor walls
+    simulated_area_travelator.add_wall((-1.0,0.0), (41.0,0.0));
+    simulated_area_travelator.add_wall((-1.0,6.0), (41.0,6.0));
+    
+    // The walkway runs along the bottom wall, with a balustrade on the other side
+    simulated_area_travelator.add_moving_walkway((10.0,0.6), (30.0,0.6), 1.0, WALKWAY_SPEED, WALKWAY_ETIQUETTE);
+    simulated_area_travelator.add_wall((10.0,1.15), (30.0,1.15));
+    
+    // Start & end group taking the walkway, lining up with its entrance first
+    simulated_area_travelator.add_start_end_group(
+        vec![(-1.0,0.4), (-1.0,0.8)],
+        vec![(41.0,0.4), (41.0,0.8)]
+    );
+    simulated_area_travelator.set_group_waypoints(0, vec![(9.0,0.6)]);
+    
+    // Start & end group walking the other way beside the walkway
+    simulated_area_travelator.add_start_end_group(
+        vec![(41.0,2.5), (41.0,3.5), (41.0,4.5)],
+        vec![(-1.0,2.5), (-1.0,3.5), (-1.0,4.5)]
+    );
+    
+    // Timing barriers
+    simulated_area_travelator.add_timing_boundary((5.0,0.0), (5.0,6.0));
+    simulated_area_travelator.add_timing_boundary((35.0,0.0), (35.0,6.0));
+    
+    let mut crowd_simulation = new_crowd_sim(simulated_area_travelator, WALKER_RATE, run);
+    
+    for group in 0..2 {
+        crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.0*0.5) as usize, group, behaviour_model(Etiquette::LeftBias));
+        crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.1*0.5) as usize, group, behaviour_model(Etiquette::NoBias));
+        crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.2*0.5) as usize, group, behaviour_model(Etiquette::RightBias));
+    }
+    
+    crowd_simulation.randomise_pedestrian_order();
+    
+    return crowd_simulation;
+    
+}
+
+
+/// Create a simulation of a corridor crossed by a two-way cycle path, where pedestrians walking along the corridor give way to the cyclists
+fn create_cycle_crossing_sim(run: Option<&ExperimentRun>) -> CrowdSim {
+    /// Normalised ratio of left-, non-, and right-biased pedestrians
+    const BIAS_RATIOS: (f64, f64, f64) = (0.44, 0.52, 0.04);
+    
+    let mut simulated_area_crossing = SimArea::new();
+    
+    // Corridor walls. The cycle path crosses them through gates that pedestrians don't use, so they are left unbroken (vehicles don't see walls).
+    simulated_area_crossing.add_wall((-1.0,0.0), (41.0,0.0));
+    simulated_area_crossing.add_wall((-1.0,6.0), (41.0,6.0));
+    
+    // The cycle path crosses the corridor, with cyclists keeping to the left in each direction
+    simulated_area_crossing.add_vehicle_lane(vec![(19.4,-6.0), (19.4,12.0)], CYCLIST_HEADWAY, CYCLIST_PARAMS);
+    simulated_area_crossing.add_vehicle_lane(vec![(20.6,12.0), (20.6,-6.0)], CYCLIST_HEADWAY, CYCLIST_PARAMS);
+    
+    // Start & end groups walking each way along the corridor
+    simulated_area_crossing.add_start_end_group(
+        vec![(-1.0,1.5), (-1.0,3.0), (-1.0,4.5)],
+        vec![(41.0,1.5), (41.0,3.0), (41.0,4.5)]
+    );
+    simulated_area_crossing.add_start_end_group(
+        vec![(41.0,1.5), (41.0,3.0), (41.0,4.5)],
+        vec![(-1.0,1.5), (-1.0,3.0), (-1.0,4.5)]
+    );
+    
+    // Timing barriers
+    simulated_area_crossing.add_timing_boundary((5.0,0.0), (5.0,6.0));
+    simulated_area_crossing.add_timing_boundary((35.0,0.0), (35.0,6.0));
+    
+    let mut crowd_simulation = new_crowd_sim(simulated_area_crossing, WALKER_RATE, run);
+    
+    for group in 0..2 {
+        crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.0*0.5) as usize, group, behaviour_model(Etiquette::LeftBias));
+        crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.1*0.5) as usize, group, behaviour_model(Etiquette::NoBias));
+        crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.2*0.5) as usize, group, behaviour_model(Etiquette::RightBias));
+    }
+    
+    crowd_simulation.randomise_pedestrian_order();
+    
+    return crowd_simulation;
+    
+}
+
+
+/// Create a simulation of a station entrance, where everyone walking in passes through one of a line of ticket gates
+fn create_gateline_sim(run: Option<&ExperimentRun>) -> CrowdSim {
+    /// Normalised ratio of left-, non-, and right-biased pedestrians
+    const BIAS_RATIOS: (f64, f64, f64) = (0.44, 0.52, 0.04);
+    
+    let mut simulated_area_entrance = SimArea::new();
+    
+    // Hall walls
+    simulated_area_entrance.add_wall((0.0,0.0), (30.0,0.0));
+    simulated_area_entrance.add_wall((0.0,12.0), (30.0,12.0));
+    
+    // The gateline across the middle of the hall, with a 0.6m wide gate every 2m
+    let gate_ys = [3.0, 5.0, 7.0, 9.0];
+    simulated_area_entrance.add_wall((15.0,0.0), (15.0,gate_ys[0] - 0.3));
+    for pair in gate_ys.windows(2) {
+        simulated_area_entrance.add_wall((15.0,pair[0] + 0.3), (15.0,pair[1] - 0.3));
+    }
+    simulated_area_entrance.add_wall((15.0,gate_ys[gate_ys.len() - 1] + 0.3), (15.0,12.0));
+    for y in gate_ys {
+        simulated_area_entrance.add_ticket_gate((15.0,y), (1.0,0.0), GATE_SERVICE_TIME);
+    }
+    
+    // Start & end group walking in through the gates
+    simulated_area_entrance.add_start_end_group(
+        vec![(-1.0,2.0), (-1.0,4.0), (-1.0,6.0), (-1.0,8.0), (-1.0,10.0)],
+        vec![(31.0,2.0), (31.0,4.0), (31.0,6.0), (31.0,8.0), (31.0,10.0)]
+    );
+    
+    // Timing barriers
+    simulated_area_entrance.add_timing_boundary((1.0,0.0), (1.0,12.0));
+    simulated_area_entrance.add_timing_boundary((29.0,0.0), (29.0,12.0));
+    
+    let mut crowd_simulation = new_crowd_sim(simulated_area_entrance, WALKER_RATE, run);
+    
+    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.0) as usize, 0, behaviour_model(Etiquette::LeftBias));
+    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.1) as usize, 0, behaviour_model(Etiquette::NoBias));
+    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.2) as usize, 0, behaviour_model(Etiquette::RightBias));
+    
+    crowd_simulation.randomise_pedestrian_order();
+    
+    return crowd_simulation;
+    
+}
+
+
+/// Create a simulation of a hall split by a partition, where the pedestrians that don't know the way through the doorway at the top find it by following signs.
+/// Compare runs with different values of SIGN_PLACEMENT to see how much the sign helps.
+fn create_signage_sim(run: Option<&ExperimentRun>) -> CrowdSim {
+    /// Normalised ratio of left-, non-, and right-biased pedestrians
+    const BIAS_RATIOS: (f64, f64, f64) = (0.44, 0.52, 0.04);
+    
+    let mut simulated_area_hall = SimArea::new();
+    
+    // Hall walls
+    simulated_area_hall.add_wall((0.0,0.0), (30.0,0.0));
+    simulated_area_hall.add_wall((0.0,16.0), (30.0,16.0));
+    
+    // Partition with a doorway at the top
+    simulated_area_hall.add_wall((20.0,0.0), (20.0,12.0));
+    
+    // Start & end group walking from one side of the partition to the other. Pedestrians that know the way head for the doorway first.
+    simulated_area_hall.add_start_end_group(
+        vec![(-1.0,4.0), (-1.0,8.0), (-1.0,12.0)],
+        vec![(31.0,4.0), (31.0,8.0)]
+    );
+    simulated_area_hall.set_group_waypoints(0, vec![(20.0,14.0)]);
+    
+    // A sign pointing to the doorway, and another just past it showing the destination
+    if let Some((position, visibility_radius)) = SIGN_PLACEMENT {
+        simulated_area_hall.add_sign(position, visibility_radius, 0, Some((20.0,14.0)));
+    }
+    simulated_area_hall.add_sign((21.0,13.0), 10.0, 0, None);
+    
+    // Timing barriers
+    simulated_area_hall.add_timing_boundary((1.0,0.0), (1.0,16.0));
+    simulated_area_hall.add_timing_boundary((29.0,0.0), (29.0,16.0));
+    
+    let mut crowd_simulation = new_crowd_sim(simulated_area_hall, WALKER_RATE, run);
+    crowd_simulation.set_herding(Some(Herding {familiarity: SIGNAGE_FAMILIARITY, ..Herding::default()}));
+    
+    // Pedestrians that walk into the partition without finding the doorway wander off to look elsewhere
+    let mut params = *crowd_simulation.get_params();
+    params.stall_time = 10.0;
+    params.stall_recovery = StallRecovery::RandomWalk;
+    crowd_simulation.set_params(params);
+    
+    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.0) as usize, 0, behaviour_model(Etiquette::LeftBias));
+    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.1) as usize, 0, behaviour_model(Etiquette::NoBias));
+    crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.2) as usize, 0, behaviour_model(Etiquette::RightBias));
+    
+    crowd_simulation.randomise_pedestrian_order();
+    
+    return crowd_simulation;
+    
+}
+
+
+/// Create a simulation of two connected rooms, where pedestrians find their way through the doorways and around a pillar
+fn create_rooms_sim(run: Option<&ExperimentRun>) -> CrowdSim {
+    /// Normalised ratio of left-, non-, and right-biased pedestrians
+    const BIAS_RATIOS: (f64, f64, f64) = (0.44, 0.52, 0.04);
+    
+    let mut simulated_area_rooms = SimArea::new();
+    
+    // Outer walls
+    simulated_area_rooms.add_wall((0.0,0.0), (20.0,0.0));
+    simulated_area_rooms.add_wall((0.0,10.0), (20.0,10.0));
+    simulated_area_rooms.add_wall((0.0,0.0), (0.0,10.0));
+    simulated_area_rooms.add_wall((20.0,0.0), (20.0,10.0));
+    
+    // Dividing wall with a doorway at the bottom, and a partition in the right room with a gap at the top
+    simulated_area_rooms.add_wall((10.0,0.0), (10.0,7.5));
+    simulated_area_rooms.add_wall((15.0,3.0), (15.0,10.0));
+    
+    // Pillar in the left room
+    simulated_area_rooms.add_circular_obstacle((5.0,5.0), 0.5);
+    
+    // Start & end group moving from the top-left corner to the bottom-right corner
+    simulated_area_rooms.add_start_end_group(
+        vec![(2.0,2.0), (2.0,3.0), (3.0,2.0)],
+        vec![(18.0,8.0), (18.0,9.0)]
+    );
+    // Start & end group moving from the bottom-right corner to the top-left corner
+    simulated_area_rooms.add_start_end_group(
+        vec![(18.0,8.0), (18.0,9.0)],
+        vec![(2.0,2.0), (2.0,3.0), (3.0,2.0)]
+    );
+    
+    // Timing barriers: across each doorway
+    simulated_area_rooms.add_timing_boundary((10.0,7.5), (10.0,10.0));
+    simulated_area_rooms.add_timing_boundary((15.0,0.0), (15.0,3.0));
+    
+    let mut crowd_simulation = new_crowd_sim(simulated_area_rooms, WALKER_RATE, run);
+    if USE_FLOOR_FIELD {
+        crowd_simulation.enable_floor_field(FLOOR_FIELD_CELL_SIZE, PATH_PLANNING_CLEARANCE);
+    } else {
+        crowd_simulation.enable_path_planning(PATH_PLANNING_CLEARANCE);
+    }
+    
+    for group in 0..2 {
+        crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.0*0.1) as usize, group, behaviour_model(Etiquette::LeftBias));
+        crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.1*0.1) as usize, group, behaviour_model(Etiquette::NoBias));
+        crowd_simulation.add_pedestrian_set(((TOTAL_PEDESTRIANS as f64)*BIAS_RATIOS.2*0.1) as usize, group, behaviour_model(Etiquette::RightBias));
+    }
     
     crowd_simulation.randomise_pedestrian_order();
     
@@ -524,3 +3059,95 @@ fn create_crossroads_sim() -> CrowdSim {
     
 }
 
+
+/// Create a simulation of a station concourse with two street entrances and two platform staircases, with the demand between them given as an origin-destination matrix
+fn create_station_sim(run: Option<&ExperimentRun>) -> CrowdSim {
+    /// Normalised ratio of left-, non-, and right-biased pedestrians
+    const BIAS_RATIOS: (f64, f64, f64) = (0.44, 0.52, 0.04);
+    
+    /// The share of the pedestrians entering from the street that travel between each pair of gates: \[origin]\[destination].
+    /// Gates: 0 = west street, 1 = east street, 2 = platform 1 stairs, 3 = platform 2 stairs
+    const STREET_OD_SHARES: [[f64; 4]; 2] = [
+        [0.0, 0.15, 0.25, 0.15],
+        [0.1, 0.0, 0.15, 0.2]
+    ];
+    
+    /// Passengers arriving by train leave the platforms at this many per second, for TRAIN_UNLOADING_TIME after each train arrives
+    const TRAIN_UNLOADING_RATE: f64 = 1.5;
+    const TRAIN_UNLOADING_TIME: f64 = 40.0;
+    /// Time between trains at each platform, in seconds
+    const TRAIN_HEADWAY: f64 = 240.0;
+    
+    let mut simulated_area_station = SimArea::new();
+    
+    // Concourse walls
+    simulated_area_station.add_wall((-1.0,-1.0), (31.0,-1.0));
+    simulated_area_station.add_wall((-1.0,17.0), (31.0,17.0));
+    simulated_area_station.add_wall((-1.0,-1.0), (-1.0,17.0));
+    simulated_area_station.add_wall((31.0,-1.0), (31.0,17.0));
+    
+    // Ticket machines in the middle of the concourse
+    simulated_area_station.add_circular_obstacle((15.0,9.0), 0.6);
+    
+    // Each gate is a group: pedestrians start anywhere across the gate and finish at another one
+    // West street
+    simulated_area_station.add_start_end_zone_group(
+        vec![Zone::Segment(Vec2::new(0.0,6.5), Vec2::new(0.0,9.5))],
+        vec![Zone::Segment(Vec2::new(0.0,7.0), Vec2::new(0.0,9.0))]
+    );
+    // East street
+    simulated_area_station.add_start_end_zone_group(
+        vec![Zone::Segment(Vec2::new(30.0,6.5), Vec2::new(30.0,9.5))],
+        vec![Zone::Segment(Vec2::new(30.0,7.0), Vec2::new(30.0,9.0))]
+    );
+    // Platform 1 stairs
+    simulated_area_station.add_start_end_zone_group(
+        vec![Zone::Segment(Vec2::new(7.5,0.0), Vec2::new(10.5,0.0))],
+        vec![Zone::Segment(Vec2::new(8.0,0.0), Vec2::new(10.0,0.0))]
+    );
+    // Platform 2 stairs
+    simulated_area_station.add_start_end_zone_group(
+        vec![Zone::Segment(Vec2::new(19.5,0.0), Vec2::new(22.5,0.0))],
+        vec![Zone::Segment(Vec2::new(20.0,0.0), Vec2::new(22.0,0.0))]
+    );
+    
+    // Timing barriers: across each gate, so that every pedestrian is timed from its origin to its destination
+    simulated_area_station.add_timing_boundary((2.0,5.0), (2.0,11.0));
+    simulated_area_station.add_timing_boundary((28.0,5.0), (28.0,11.0));
+    simulated_area_station.add_timing_boundary((6.0,1.0), (12.0,1.0));
+    simulated_area_station.add_timing_boundary((18.0,1.0), (24.0,1.0));
+    
+    let mut crowd_simulation = new_crowd_sim(simulated_area_station, WALKER_RATE, run);
+    crowd_simulation.enable_path_planning(PATH_PLANNING_CLEARANCE);
+    
+    let biases = [(Etiquette::LeftBias, BIAS_RATIOS.0), (Etiquette::NoBias, BIAS_RATIOS.1), (Etiquette::RightBias, BIAS_RATIOS.2)];
+    
+    // Pedestrians entering from the street, added at the simulation's add rate
+    for (origin, shares) in STREET_OD_SHARES.iter().enumerate() {
+        for (destination, share) in shares.iter().enumerate() {
+            for (etiquette, ratio) in &biases {
+                let count = ((TOTAL_PEDESTRIANS as f64) * share * ratio) as usize;
+                crowd_simulation.add_od_demand(origin, destination, OdDemand::Count(count), behaviour_model(etiquette.clone()));
+            }
+        }
+    }
+    crowd_simulation.randomise_pedestrian_order();
+    
+    // Passengers leaving each train, split between the two streets. Trains at the two platforms are staggered.
+    let run_time = (TOTAL_PEDESTRIANS as f64) / WALKER_RATE;
+    for (platform, first_train) in [(2, 30.0), (3, 30.0 + TRAIN_HEADWAY/2.0)] {
+        let mut arrival = first_train;
+        while arrival < run_time {
+            for street in 0..2 {
+                for (etiquette, ratio) in &biases {
+                    let demand = OdDemand::Rate {rate: TRAIN_UNLOADING_RATE * ratio / 2.0, start_time: arrival, end_time: arrival + TRAIN_UNLOADING_TIME};
+                    crowd_simulation.add_od_demand(platform, street, demand, behaviour_model(etiquette.clone()));
+                }
+            }
+            arrival += TRAIN_HEADWAY;
+        }
+    }
+    
+    return crowd_simulation;
+    
+}
@@ -0,0 +1,95 @@
+pub mod annotation {
+    
+    use std::fs;
+    use std::io;
+    use std::path::{Path, PathBuf};
+    use serde::{Deserialize, Serialize};
+    use raylib::{drawing::{RaylibDrawHandle, RaylibDraw}, color::Color};
+    
+    
+    /// How long an annotation's text stays on screen either side of its time, in seconds of simulation time
+    const DISPLAY_WINDOW: f64 = 5.0;
+    
+    /// The radius of an annotation marker, in pixels
+    const MARKER_RADIUS: f32 = 8.0;
+    
+    
+    const ANNOTATION_COLOUR: &str = "D03A8C";
+    
+    
+    /// A note about something seen while watching a simulation, such as an incident worth revisiting
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    pub struct Annotation {
+        /// The simulation time the note was made at, in seconds
+        pub time: f64,
+        pub text: String,
+        /// The position the note is about, in metres
+        pub marker: (f64, f64),
+        /// The offset of the view when the note was made, in pixels
+        pub view_offset: (i32, i32),
+        /// The scale of the view when the note was made, in pixels per metre
+        pub view_scale: i32
+    }
+    
+    /// The annotations made on a run, in the order they were made.
+    /// 
+    /// They are stored in a TOML file next to the run's other output, so that they can be shared and loaded again when the run is watched later.
+    #[derive(Default, Serialize, Deserialize)]
+    pub struct AnnotationLog {
+        annotations: Vec<Annotation>
+    }
+    
+    impl AnnotationLog {
+        pub fn new() -> AnnotationLog {
+            return AnnotationLog::default();
+        }
+        
+        /// Load the annotations saved in a file, or return an empty log if the file doesn't exist
+        pub fn load(path: &Path) -> io::Result<AnnotationLog> {
+            if !path.exists() {
+                return Ok(AnnotationLog::new());
+            }
+            
+            let contents = fs::read_to_string(path)?;
+            return toml::from_str(&contents).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e));
+        }
+        
+        /// Save the annotations to a file, replacing its contents
+        pub fn save(&self, path: &Path) -> io::Result<()> {
+            let contents = toml::to_string(self).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            
+            return fs::write(path, contents);
+        }
+        
+        pub fn add(&mut self, annotation: Annotation) {
+            self.annotations.push(annotation);
+        }
+        
+        pub fn get_annotations(&self) -> &Vec<Annotation> {
+            return &self.annotations;
+        }
+        
+        /// Draw the marker of every annotation with RayLib, with the text of those made close to the current simulation time
+        pub fn draw(&self, rl_handle: &mut RaylibDrawHandle, offset: (i32, i32), draw_scale: i32, time: f64) {
+            let colour = Color::from_hex(ANNOTATION_COLOUR).unwrap();
+            
+            for annotation in &self.annotations {
+                let x = offset.0 + ((draw_scale as f64) * annotation.marker.0) as i32;
+                let y = offset.1 + ((draw_scale as f64) * annotation.marker.1) as i32;
+                
+                rl_handle.draw_circle_lines(x, y, MARKER_RADIUS, colour);
+                if (annotation.time - time).abs() <= DISPLAY_WINDOW {
+                    rl_handle.draw_text(&annotation.text, x + MARKER_RADIUS as i32 + 4, y - 8, 16, colour);
+                }
+            }
+        }
+    }
+    
+    /// Return the path of the annotation file kept alongside a run's output file, e.g. `sim_0_annotations.toml` for `sim_0_snapshot.csv`
+    /// 
+    /// * `name` - The name used as a prefix for the run's output files
+    pub fn get_annotation_path(name: &str) -> PathBuf {
+        return PathBuf::from(format!("{}_annotations.toml", name));
+    }
+    
+}
@@ -0,0 +1,349 @@
+pub mod arrivals {
+    
+    use std::fmt;
+    use serde::{Deserialize, Serialize};
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+    
+    use crate::simulation::pedestrian::pedestrian::Walker;
+    use crate::simulation::demand::demand::DemandProfile;
+    use crate::simulation::checkpoint::checkpoint::RngState;
+    
+    
+    /// What an arrival process can see of the simulation when deciding which pedestrians to add
+    pub struct ArrivalContext<'a> {
+        /// The current simulation time, in seconds
+        pub time: f64,
+        /// The simulation time since the process was last asked for arrivals, in seconds
+        pub elapsed: f64,
+        /// The simulation's pedestrian add rate, in pedestrians per second
+        pub add_rate: f64,
+        /// The number of pedestrians currently walking
+        pub active_count: usize,
+        /// The pedestrians waiting to be added, in reverse order (the last one is added next)
+        pub waiting: &'a [Walker]
+    }
+    
+    /// Decides when the pedestrians waiting in a simulation are added to it.
+    /// 
+    /// New demand models can be added by implementing this trait, without modifying `CrowdSim`.
+    /// The `Debug` representation is used to tell simulations apart in the result cache, so it should include every setting of the process.
+    pub trait ArrivalProcess: fmt::Debug + Send {
+        /// The name of the process, used to select it in scenario files
+        fn name(&self) -> &str;
+        
+        /// Return the pedestrians to add now, in order: the group of each one, or None for the next waiting pedestrian of any group.
+        /// 
+        /// This is called once per timestep, before the pedestrians are moved.
+        fn get_arrivals(&mut self, context: &ArrivalContext) -> Vec<Option<usize>>;
+        
+        /// Return the number of pedestrians the process is adding per second at a given time
+        fn get_rate(&self, _time: f64, add_rate: f64) -> f64 {
+            return add_rate;
+        }
+        
+        /// Return the expected number of arrivals between one pedestrian and the next in a stream released outside this process (such as `OdDemand::Rate`), so that the stream is spread out the same way
+        fn sample_gap(&mut self) -> f64 {
+            return 1.0;
+        }
+        
+        /// Return the state the process has built up since it was created, so that it can be saved in a checkpoint
+        fn save_state(&self) -> ArrivalState {
+            return ArrivalState::default();
+        }
+        
+        /// Continue from a state returned by `save_state`
+        fn restore_state(&mut self, _state: &ArrivalState) {}
+    }
+    
+    /// The state an arrival process has built up, in a form that can be saved in a checkpoint
+    #[derive(Clone, Debug, Default, Serialize, Deserialize)]
+    pub struct ArrivalState {
+        /// The progress towards the next arrivals, in whatever form the process keeps it
+        pub progress: Vec<f64>,
+        /// The state of the process's random number generator, if it has one
+        pub rng: Option<RngState>
+    }
+    
+    /// Adds pedestrians at perfectly regular intervals, at the simulation's add rate
+    #[derive(Debug, Default)]
+    pub struct Deterministic {
+        /// The expected number of arrivals since the last pedestrian was added, minus 1
+        progress: f64
+    }
+    
+    impl Deterministic {
+        pub fn new() -> Deterministic {
+            return Deterministic::default();
+        }
+    }
+    
+    impl ArrivalProcess for Deterministic {
+        fn name(&self) -> &str {
+            return "deterministic";
+        }
+        
+        fn get_arrivals(&mut self, context: &ArrivalContext) -> Vec<Option<usize>> {
+            self.progress += context.add_rate * context.elapsed;
+            
+            let mut arrivals = Vec::new();
+            while self.progress > 0.0 && arrivals.len() < context.waiting.len() {
+                arrivals.push(None);
+                self.progress -= 1.0;
+            }
+            return arrivals;
+        }
+        
+        fn save_state(&self) -> ArrivalState {
+            return ArrivalState {
+                progress: vec![self.progress],
+                rng: None
+            };
+        }
+        
+        fn restore_state(&mut self, state: &ArrivalState) {
+            self.progress = state.progress[0];
+        }
+    }
+    
+    /// Adds each group's pedestrians as an independent Poisson process (with exponentially distributed gaps), at the group's share of the simulation's add rate.
+    /// 
+    /// The shares are fixed by the number of pedestrians waiting in each group when the first arrivals are requested.
+    pub struct Poisson {
+        seed: u64,
+        rng: ChaCha8Rng,
+        /// The arrival stream of each group: (share of the add rate, expected arrivals since the group's last pedestrian was added, expected arrivals needed for the next one)
+        streams: Vec<(f64, f64, f64)>
+    }
+    
+    impl Poisson {
+        /// * `seed` - Seeds the random gaps between arrivals, so that the same arrivals can be repeated
+        pub fn new(seed: u64) -> Poisson {
+            return Poisson {
+                seed,
+                rng: ChaCha8Rng::seed_from_u64(seed),
+                streams: Vec::new()
+            };
+        }
+    }
+    
+    impl fmt::Debug for Poisson {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            return f.debug_struct("Poisson").field("seed", &self.seed).finish();
+        }
+    }
+    
+    impl ArrivalProcess for Poisson {
+        fn name(&self) -> &str {
+            return "poisson";
+        }
+        
+        fn get_arrivals(&mut self, context: &ArrivalContext) -> Vec<Option<usize>> {
+            if self.streams.is_empty() {
+                let group_count = context.waiting.iter().map(|ped| ped.get_group() + 1).max().unwrap_or(0);
+                let mut demand = vec![0; group_count];
+                for ped in context.waiting {
+                    demand[ped.get_group()] += 1;
+                }
+                let total_demand = demand.iter().sum::<usize>().max(1) as f64;
+                
+                for group_demand in demand {
+                    let gap = sample_exponential(&mut self.rng);
+                    self.streams.push(((group_demand as f64) / total_demand, 0.0, gap));
+                }
+            }
+            
+            let mut arrivals = Vec::new();
+            for (group, (share, progress, gap)) in self.streams.iter_mut().enumerate() {
+                *progress += context.add_rate * *share * context.elapsed;
+                
+                while *progress >= *gap {
+                    *progress -= *gap;
+                    *gap = sample_exponential(&mut self.rng);
+                    arrivals.push(Some(group));
+                }
+            }
+            return arrivals;
+        }
+        
+        fn sample_gap(&mut self) -> f64 {
+            return sample_exponential(&mut self.rng);
+        }
+        
+        fn save_state(&self) -> ArrivalState {
+            return ArrivalState {
+                progress: self.streams.iter().flat_map(|(share, progress, gap)| [*share, *progress, *gap]).collect(),
+                rng: Some(RngState::new(&self.rng))
+            };
+        }
+        
+        fn restore_state(&mut self, state: &ArrivalState) {
+            self.streams = state.progress.chunks_exact(3).map(|stream| (stream[0], stream[1], stream[2])).collect();
+            if let Some(rng) = &state.rng {
+                self.rng = rng.restore();
+            }
+        }
+    }
+    
+    /// Adds pedestrians at a rate that follows a demand profile (ignoring the simulation's add rate), such as a morning peak.
+    /// 
+    /// The gaps between arrivals are regular, or exponentially distributed (a Poisson process with a time-varying rate) if a seed is given.
+    pub struct Scheduled {
+        profile: DemandProfile,
+        seed: Option<u64>,
+        rng: Option<ChaCha8Rng>,
+        /// The expected number of arrivals since the last pedestrian was added
+        progress: f64,
+        /// The expected number of arrivals needed before the next pedestrian is added
+        gap: f64
+    }
+    
+    impl Scheduled {
+        /// * `seed` - Seeds the random gaps between arrivals (None = regular gaps)
+        pub fn new(profile: DemandProfile, seed: Option<u64>) -> Scheduled {
+            let mut rng = seed.map(ChaCha8Rng::seed_from_u64);
+            let gap = rng.as_mut().map_or(1.0, sample_exponential);
+            
+            return Scheduled {
+                profile,
+                seed,
+                rng,
+                progress: 0.0,
+                gap
+            };
+        }
+    }
+    
+    impl fmt::Debug for Scheduled {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            return f.debug_struct("Scheduled").field("profile", &self.profile).field("seed", &self.seed).finish();
+        }
+    }
+    
+    impl ArrivalProcess for Scheduled {
+        fn name(&self) -> &str {
+            return "scheduled";
+        }
+        
+        fn get_arrivals(&mut self, context: &ArrivalContext) -> Vec<Option<usize>> {
+            self.progress += self.profile.get_expected_arrivals(context.time - context.elapsed, context.time);
+            
+            let mut arrivals = Vec::new();
+            while self.progress >= self.gap && arrivals.len() < context.waiting.len() {
+                arrivals.push(None);
+                self.progress -= self.gap;
+                self.gap = self.sample_gap();
+            }
+            return arrivals;
+        }
+        
+        fn get_rate(&self, time: f64, _add_rate: f64) -> f64 {
+            return self.profile.get_rate(time);
+        }
+        
+        fn sample_gap(&mut self) -> f64 {
+            return self.rng.as_mut().map_or(1.0, sample_exponential);
+        }
+        
+        fn save_state(&self) -> ArrivalState {
+            return ArrivalState {
+                progress: vec![self.progress, self.gap],
+                rng: self.rng.as_ref().map(RngState::new)
+            };
+        }
+        
+        fn restore_state(&mut self, state: &ArrivalState) {
+            self.progress = state.progress[0];
+            self.gap = state.progress[1];
+            if let Some(rng) = &state.rng {
+                self.rng = Some(rng.restore());
+            }
+        }
+    }
+    
+    /// Adds pedestrians at regular intervals at the simulation's add rate, but holds them back while the simulation is full, like a gateline that closes when the area beyond it is crowded.
+    /// 
+    /// Pedestrians held back are added as soon as there is room, so a backlog clears at once.
+    #[derive(Debug)]
+    pub struct Backpressure {
+        /// The most pedestrians that can be walking at once
+        max_active: usize,
+        /// The expected number of arrivals that haven't been added yet, minus 1
+        progress: f64
+    }
+    
+    impl Backpressure {
+        pub fn new(max_active: usize) -> Backpressure {
+            return Backpressure {
+                max_active,
+                progress: 0.0
+            };
+        }
+    }
+    
+    impl ArrivalProcess for Backpressure {
+        fn name(&self) -> &str {
+            return "backpressure";
+        }
+        
+        fn get_arrivals(&mut self, context: &ArrivalContext) -> Vec<Option<usize>> {
+            self.progress += context.add_rate * context.elapsed;
+            
+            let room = self.max_active.saturating_sub(context.active_count).min(context.waiting.len());
+            let mut arrivals = Vec::new();
+            while self.progress > 0.0 && arrivals.len() < room {
+                arrivals.push(None);
+                self.progress -= 1.0;
+            }
+            return arrivals;
+        }
+        
+        fn save_state(&self) -> ArrivalState {
+            return ArrivalState {
+                progress: vec![self.progress],
+                rng: None
+            };
+        }
+        
+        fn restore_state(&mut self, state: &ArrivalState) {
+            self.progress = state.progress[0];
+        }
+    }
+    
+    /// An arrival process as selected by name in a scenario file, e.g. `process = "poisson"` in the `[arrivals]` table
+    #[derive(Clone, Debug, Deserialize)]
+    #[serde(tag = "process", rename_all = "snake_case")]
+    pub enum ArrivalSettings {
+        Deterministic,
+        Poisson {
+            seed: u64
+        },
+        Scheduled {
+            /// \[\[simulation time in seconds, pedestrians per second]]
+            breakpoints: DemandProfile,
+            /// Seeds random gaps between arrivals (left out = regular gaps)
+            seed: Option<u64>
+        },
+        Backpressure {
+            max_active: usize
+        }
+    }
+    
+    impl ArrivalSettings {
+        /// Create the arrival process these settings describe
+        pub fn build(&self) -> Box<dyn ArrivalProcess> {
+            return match self {
+                ArrivalSettings::Deterministic => Box::new(Deterministic::new()),
+                ArrivalSettings::Poisson {seed} => Box::new(Poisson::new(*seed)),
+                ArrivalSettings::Scheduled {breakpoints, seed} => Box::new(Scheduled::new(breakpoints.clone(), *seed)),
+                ArrivalSettings::Backpressure {max_active} => Box::new(Backpressure::new(*max_active))
+            };
+        }
+    }
+    
+    /// Sample an exponential distribution with a mean of 1
+    fn sample_exponential(rng: &mut ChaCha8Rng) -> f64 {
+        return -(1.0 - rng.gen::<f64>()).ln();
+    }
+    
+}
@@ -0,0 +1,292 @@
+pub mod behaviour {
+    
+    use std::f64::consts::{PI, TAU};
+    use rand::RngCore;
+    
+    use crate::simulation::pedestrian::pedestrian::Walker;
+    use crate::simulation::geom::geom::{nudge_angle, normalise_angle, wrap_angle};
+    use crate::simulation::params::params::ContactModel;
+    
+    
+    /// Describes how a pedestrian steers and adjusts its speed in response to its target, its neighbours and nearby walls.
+    /// 
+    /// Implement this trait to add a new behavioural rule without modifying `Walker`.
+    /// The default methods give plain target-seeking with wall avoidance and no reaction to neighbours.
+    pub trait BehaviourModel: Send + Sync {
+        /// A short name identifying the model, used to label results
+        fn name(&self) -> &str;
+        
+        /// Make any random decisions for the timestep (such as stopping), before accelerating
+        /// 
+        /// * `rng` - The source of randomness shared by the pedestrian's simulation
+        fn decide(&self, _walker: &mut Walker, _time_scale: f64, _rng: &mut dyn RngCore) {}
+        
+        /// Accelerate towards the preferred walking speed
+        fn accelerate(&self, walker: &mut Walker, time_scale: f64) {
+            let params = *walker.get_params();
+            
+            walker.inst_speed = walker.get_desired_speed().min(walker.inst_speed + params.accel * time_scale);
+        }
+        
+        /// Update the facing direction to be better aligned with the destination
+        /// 
+        /// * `target_angle` - The angle the pedestrian should be facing to reach its destination, between -π and π
+        fn steer_towards_target(&self, walker: &mut Walker, target_angle: f64, time_scale: f64) {
+            let params = *walker.get_params();
+            
+            walker.facing_direction = nudge_angle(walker.facing_direction, target_angle, params.direction_change_factor*time_scale);
+        }
+        
+        /// React to neighbouring pedestrians
+        /// 
+        /// * `other_pedestrians` - [(x, y, direction, radius, speed)]
+        fn react_to_neighbours(&self, _walker: &mut Walker, _time_scale: f64, _other_pedestrians: &[(f64, f64, f64, f64, f64)]) {}
+        
+        /// React to a nearby wall or obstacle, after any collision with it has been resolved
+        /// 
+        /// * `dist` - The distance from the pedestrian to the closest point on the wall (negative if it was inside an obstacle)
+        /// * `normal_angle` - The direction pointing from the wall to the pedestrian
+        fn react_to_wall(&self, walker: &mut Walker, dist: f64, normal_angle: f64, time_scale: f64) {
+            let params = *walker.get_params();
+            
+            // If the wall is within the pedestrian's personal space radius, nudge the direction vector away slightly
+            if dist < params.pspace_radius {
+                
+                // Nudge the direction of travel away from the wall
+                walker.facing_direction = nudge_angle(walker.facing_direction, normal_angle, params.wall_repulsion*time_scale);
+                
+            }
+        }
+    }
+    
+    
+    /// The rule-based etiquettes tested by the original simulation
+    #[derive(PartialEq)]
+    #[derive(Clone)]
+    pub enum Etiquette {
+        LeftBias,   // Stay to the left
+        RightBias,  // Stay to the right
+        NoBias      // Walk directly towards the destination
+    }
+    
+    impl BehaviourModel for Etiquette {
+        fn name(&self) -> &str {
+            match self {
+                Etiquette::LeftBias => "left bias",
+                Etiquette::RightBias => "right bias",
+                Etiquette::NoBias => "no bias"
+            }
+        }
+        
+        fn steer_towards_target(&self, walker: &mut Walker, target_angle: f64, time_scale: f64) {
+            let params = *walker.get_params();
+            
+            // Update the facing direction to be better aligned with the destination
+            walker.facing_direction = nudge_angle(walker.facing_direction, target_angle, params.direction_change_factor*time_scale);
+            
+            
+            // Add bias to movement direction depending on etiquette
+            if *self == Etiquette::LeftBias {
+                walker.facing_direction -= params.etiquette_bias_factor * time_scale;
+            } else if *self == Etiquette::RightBias {
+                walker.facing_direction += params.etiquette_bias_factor * time_scale;
+            }
+        }
+        
+        /// React to neighbouring pedestrians, considering specific etiquette rules
+        /// 
+        /// * `other_pedestrians` - [(x, y, direction, radius, speed)]
+        fn react_to_neighbours(&self, walker: &mut Walker, time_scale: f64, other_pedestrians: &[(f64, f64, f64, f64, f64)]) {
+            let params = *walker.get_params();
+            
+            // How far ahead the pedestrian looks, and how close an oncoming pedestrian gets before it slows down, at its current speed
+            let look_ahead_radius = walker.get_look_ahead_radius();
+            let pspace_radius = (params.pspace_radius * walker.get_look_ahead_scale()).max(params.radius);
+            
+            // Iterate through all neighbouring pedestrians and check for front-on collisions and side collisions.
+            
+            /* How this method works:
+             * Resolve collisions between pedestrians.
+             * * Move them apart so that hey are no longer touching.
+             * * Change direction of travel so that they are travelling perpendicular to the other pedestrian.
+             * * Reduce speed to 0.
+             * If a pedestrian is to the right or left: cancel right or left bias effects.
+             * If a pedestrian is in front:
+             * * If they are walking in the opposite direction:
+             * * * Move the direction of travel away from the oncoming pedestrian - either towards the perpendicular or towards the normal.
+             * * If they are walking in the same direction, reduce acceleration.
+             * * If they are also within the personal space radius, decelerate.
+             */
+            
+            for (n_x, n_y, n_dir, n_radius, _) in other_pedestrians {
+                let dist = ((walker.x - n_x)*(walker.x - n_x) + (walker.y - n_y)*(walker.y - n_y)).sqrt();
+                
+                // The direction the neighbour is in, between -π and π
+                let abs_neighbour_angle = (n_y - walker.y).atan2(n_x - walker.x);
+                
+                // The direction the neighbour is in, relative to the direction of travel of this pedestrian, between 0 and 2π
+                let travel_rel_angle = normalise_angle(abs_neighbour_angle - walker.facing_direction);
+                
+                // Intersecting hitbox (unless touching pedestrians push each other apart instead)
+                if params.contact_model == ContactModel::Separate && dist < params.radius + n_radius {
+                    //println!("Collision");
+                    
+                    // Compute the overlap between the two pedestrians
+                    let k = params.radius + n_radius - dist;
+                    
+                    // Move the pedestrian away from its neighbour
+                    walker.x -= abs_neighbour_angle.cos() * k;
+                    walker.y -= abs_neighbour_angle.sin() * k;
+                    
+                    // Only stop & turn around if the oncoming pedestrian is within the frontal field of view
+                    if travel_rel_angle <= params.look_ahead_fov/2.0 || travel_rel_angle >= TAU-params.look_ahead_fov/2.0 {
+                        
+                        // Set facing angle directly away from neighbour
+                        walker.facing_direction = abs_neighbour_angle + PI;
+                        
+                        // Set speed to 0
+                        walker.inst_speed = 0.0;
+                        
+                    }
+                    
+                }
+                
+                // Recalculate relative neighbour direction
+                let travel_rel_angle = normalise_angle(abs_neighbour_angle - walker.facing_direction);
+                
+                // Within view to the right
+                if dist < params.look_beside_radius && travel_rel_angle > params.look_ahead_fov/2.0 && travel_rel_angle < params.look_ahead_fov/2.0 + params.look_beside_fov {
+                    // Cancel right-bias
+                    if *self == Etiquette::RightBias {
+                        walker.facing_direction -= params.etiquette_bias_factor * time_scale;
+                    }
+                }
+                
+                // Within view to the left
+                if dist < params.look_beside_radius && travel_rel_angle < TAU-params.look_ahead_fov/2.0 && travel_rel_angle > TAU-(params.look_ahead_fov/2.0 + params.look_beside_fov) {
+                    // Cancel left-bias
+                    if *self == Etiquette::LeftBias {
+                        walker.facing_direction += params.etiquette_bias_factor * time_scale;
+                    }
+                }
+                
+                // Recalculate relative neighbour direction
+                let travel_rel_angle = normalise_angle(abs_neighbour_angle - walker.facing_direction);
+                
+                // Within view in front
+                if dist < look_ahead_radius && (travel_rel_angle <= params.look_ahead_fov/2.0 || travel_rel_angle >= TAU-params.look_ahead_fov/2.0) {
+                    let direction_difference = normalise_angle(walker.facing_direction - n_dir);
+                    
+                    if direction_difference > PI/2.0 && direction_difference < 3.0*PI/2.0 {
+                        // Oncoming
+                        
+                        if *self == Etiquette::LeftBias {
+                            // Apply bias
+                            //walker.facing_direction -= params.etiquette_bias_factor * time_scale / 2.0;
+                            
+                            // The angle that points away from the neighbouring pedestrian, between 0 and 2π
+                            //let away_angle = abs_neighbour_angle + PI;
+                            let away_angle = normalise_angle(abs_neighbour_angle - PI/2.0);
+                            
+                            // Nudge the direction of travel away from the neighbour
+                            walker.facing_direction = nudge_angle(walker.facing_direction, away_angle, params.opposing_repulsion*time_scale);
+                            
+                        } else if *self == Etiquette::RightBias {
+                            // Apply bias
+                            //walker.facing_direction += params.etiquette_bias_factor * time_scale / 2.0;
+                            
+                            // The angle that points away from the neighbouring pedestrian, between 0 and 2π
+                            //let away_angle = abs_neighbour_angle + PI;
+                            let away_angle = normalise_angle(abs_neighbour_angle + PI/2.0);
+                            
+                            // Nudge the direction of travel away from the neighbour
+                            walker.facing_direction = nudge_angle(walker.facing_direction, away_angle, params.opposing_repulsion*time_scale);
+                            
+                        } else {
+                            // No directional bias
+                            // Slow down a bit
+                            walker.inst_speed -= params.accel*time_scale/2.0;
+                            
+                            // The angle that points away from the neighbouring pedestrian, between 0 and 2π
+                            //let away_angle = abs_neighbour_angle + PI;
+                            
+                            // Nudge the direction of travel away from the neighbour
+                            //walker.facing_direction = nudge_angle(walker.facing_direction, away_angle, params.opposing_repulsion*time_scale);
+                            
+                        }
+                        
+                    } else if !walker.is_overtaking(*n_x, *n_y) {
+                        // Moving same direction - reduce acceleration
+                        walker.inst_speed = params.minimum_speed.max(walker.inst_speed - params.following_decel * time_scale);
+                    }
+                    
+                }
+                
+                // Recalculate relative neighbour direction
+                let travel_rel_angle = normalise_angle(abs_neighbour_angle - walker.facing_direction);
+                
+                // Within personal space
+                if dist < n_radius + pspace_radius {
+                    // Change the direction of travel to align better with the angle facing away from the neighbour
+                    let direction_difference = normalise_angle(walker.facing_direction - n_dir);
+                    
+                    // Within the collision avoidance zone and oncoming
+                    if (travel_rel_angle <= params.collision_avoidance_fov/2.0 || travel_rel_angle >= TAU-params.collision_avoidance_fov/2.0) && (direction_difference > PI/2.0 && direction_difference < 3.0*PI/2.0) {
+                        // Decelerate
+                        walker.inst_speed = params.minimum_speed.max(walker.inst_speed - params.opposing_decel * time_scale);
+                        
+                        // The angle that points away from the neighbouring pedestrian, between 0 and 2π
+                        let away_angle = abs_neighbour_angle + PI;
+                        
+                        // The direction this pedestrian is in, relative to the direction of travel of the neighbour, between 0 and 2π
+                        let neighbour_rel_angle = normalise_angle(away_angle - n_dir);
+                        
+                        let travel_rel_angle_sign = if travel_rel_angle < PI {-1.0} else {1.0};
+                        let neighbour_rel_angle_sign = if neighbour_rel_angle < PI {-1.0} else {1.0};
+                        
+                        if travel_rel_angle_sign == neighbour_rel_angle_sign {
+                            // Naturally travelling in different directions
+                            // Go around the oncoming pedestrian naturally
+                            walker.facing_direction = nudge_angle(walker.facing_direction, abs_neighbour_angle + travel_rel_angle_sign*PI/2.0, params.pspace_repulsion * time_scale);
+                            
+                            //println!("1, {}, {}", travel_rel_angle, neighbour_rel_angle);
+                            
+                        } else {
+                            // Both pedestrians are angled the same way - collision imminent
+                            
+                            // Constrain angles between -π and π
+                            let travel_rel_angle_signed = wrap_angle(travel_rel_angle);
+                            let neighbour_rel_angle_signed = wrap_angle(neighbour_rel_angle);
+                            
+                            //println!("2, {}, {}", travel_rel_angle, neighbour_rel_angle);
+                            //println!("2, {}, {}", travel_rel_angle_signed, neighbour_rel_angle_signed);
+                            //
+                            //println!("True: {}", travel_rel_angle_signed.signum() != neighbour_rel_angle_signed.signum());
+                            
+                            if travel_rel_angle_signed.abs() > neighbour_rel_angle_signed.abs() {
+                                // Go around the oncoming pedestrian naturally - they will have to turn
+                                walker.facing_direction = nudge_angle(walker.facing_direction, abs_neighbour_angle + travel_rel_angle_sign*PI/2.0, params.pspace_repulsion * time_scale);
+                                //println!("Natural");
+                            } else {
+                                // Turn to go around the oncoming pedestrian so that they don't have to turn
+                                //println!("Invert");
+                                walker.facing_direction = nudge_angle(walker.facing_direction, abs_neighbour_angle - travel_rel_angle_sign*PI/2.0, params.pspace_repulsion * time_scale);
+                            }
+                        }
+                        
+                    } else {
+                        // When the pedestrian is within the personal space radius, but behind this pedestrian
+                        
+                    }
+                    
+                } else {
+                    // Outside personal space radius
+                    
+                }
+                
+            }
+            
+        }
+    }
+    
+}
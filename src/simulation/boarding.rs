@@ -0,0 +1,194 @@
+pub mod boarding {
+    
+    use std::collections::HashSet;
+    
+    use crate::simulation::pedestrian::pedestrian::Walker;
+    use crate::simulation::simulator::simulator::TARGET_LOCATION_RADIUS;
+    use crate::simulation::geom::geom::{Vec2, Zone};
+    
+    
+    /// One pedestrian boarding a train
+    #[derive(Clone, Copy, Debug)]
+    pub struct BoardingEvent {
+        /// The ID of the pedestrian (see `Walker::get_id`)
+        pub id: u64,
+        /// The index of the door among the boarding group's end positions
+        pub door: usize,
+        /// The index of the train, in order of arrival
+        pub train: usize,
+        /// The simulation time the pedestrian boarded, in seconds
+        pub time: f64
+    }
+    
+    /// Trains that the pedestrians in one group board, through doors at the group's end positions, each taking a limited number of pedestrians per train.
+    /// 
+    /// A pedestrian reaching a door while a train is at the platform finishes by boarding if the door has room. Otherwise it heads for the nearest door with room,
+    /// or waits at the door for the next train if every door is full. Pedestrians still waiting once the last train has left are left behind, and removed without finishing.
+    /// Pedestrians in the group that are standing and waiting (see `Dwell`) while a train is at the platform stop waiting and head for the doors.
+    /// Boarding goes on at the same time as alighting, so the boarding pedestrians get in the way of the alighting ones at the doors and vice versa.
+    pub struct Boarding {
+        /// The group whose pedestrians board
+        group: usize,
+        /// The most pedestrians that can board each train through each door
+        capacity: usize,
+        /// The simulation times each train arrives and leaves, in order of arrival: \[(arrival, departure)]
+        trains: Vec<(f64, f64)>,
+        /// The index of the train at the platform, in order of arrival (None = no train has arrived yet)
+        train: Option<usize>,
+        /// The number of pedestrians that can still board the train at the platform through each door
+        remaining: Vec<usize>,
+        /// The IDs of the pedestrians that have boarded
+        boarded: HashSet<u64>,
+        /// Every pedestrian that has boarded, in order
+        events: Vec<BoardingEvent>,
+        /// The pedestrians left behind once the last train has left: \[(pedestrian ID, simulation time)]
+        left_behind: Vec<(u64, f64)>
+    }
+    
+    impl Boarding {
+        /// * `group` - The group whose pedestrians board, through doors at its end positions
+        /// * `door_count` - The number of end positions the group has
+        /// * `capacity` - The most pedestrians that can board each train through each door
+        /// * `trains` - The simulation times each train arrives and leaves, in seconds: \[(arrival, departure)]
+        pub fn new(group: usize, door_count: usize, capacity: usize, mut trains: Vec<(f64, f64)>) -> Boarding {
+            assert!(!trains.is_empty(), "Boarding needs at least one train");
+            trains.sort_by(|a, b| a.0.total_cmp(&b.0));
+            
+            return Boarding {
+                group,
+                capacity,
+                trains,
+                train: None,
+                remaining: vec![0; door_count],
+                boarded: HashSet::new(),
+                events: Vec::new(),
+                left_behind: Vec::new()
+            };
+        }
+        
+        /// Return the group whose pedestrians board
+        pub fn get_group(&self) -> usize {
+            return self.group;
+        }
+        
+        /// Let the pedestrians that have reached a door with room board, send the rest to a door with room, and leave the pedestrians behind once the last train has left
+        /// 
+        /// * `time` - The current simulation time, in seconds
+        /// * `time_scale` - The length of the timestep, in seconds
+        /// * `doors` - The end zones of the boarding group
+        pub fn update(&mut self, time: f64, time_scale: f64, doors: &[Zone], pedestrians: &mut [Walker]) {
+            // A train arriving renews the room at every door, and there is no room once it has left
+            let at_platform = self.trains.iter().position(|(arrival, departure)| *arrival < time + time_scale && *departure >= time + time_scale);
+            if at_platform != self.train {
+                self.train = at_platform;
+                self.remaining.fill(if at_platform.is_some() {self.capacity} else {0});
+            }
+            let last_train_left = self.trains.iter().all(|(_, departure)| *departure < time + time_scale);
+            
+            if self.train.is_some() {
+                for ped in pedestrians.iter_mut().filter(|ped| ped.get_destination_group() == self.group && ped.get_dwell().is_some()) {
+                    ped.end_dwell();
+                }
+            }
+            
+            for ped in pedestrians.iter_mut() {
+                // Pedestrians on their way to wait somewhere don't board the train they pass
+                if ped.get_destination_group() != self.group || ped.get_exit().is_some() || ped.get_dwell().is_some() || self.boarded.contains(&ped.get_id()) {
+                    continue;
+                }
+                if ped.get_destination_distance() >= TARGET_LOCATION_RADIUS {
+                    continue;
+                }
+                
+                let door = ped.get_target_location();
+                let position = Vec2::new(ped.x, ped.y);
+                let nearest_with_room = (0..doors.len()).filter(|i| self.remaining[*i] > 0)
+                    .min_by(|a, b| doors[*a].get_distance(position).total_cmp(&doors[*b].get_distance(position)));
+                
+                match (self.train, nearest_with_room) {
+                    (Some(train), Some(_)) if self.remaining[door] > 0 => {
+                        self.remaining[door] -= 1;
+                        self.boarded.insert(ped.get_id());
+                        self.events.push(BoardingEvent {id: ped.get_id(), door, train, time: time + time_scale});
+                    },
+                    (Some(_), Some(other)) => ped.set_target_location(other),
+                    _ if last_train_left => {
+                        self.left_behind.push((ped.get_id(), time + time_scale));
+                        ped.despawn();
+                    },
+                    // Wait at the door for the next train
+                    _ => {}
+                }
+            }
+        }
+        
+        /// Return whether a pedestrian has boarded a train, and so may finish
+        pub fn has_boarded(&self, id: u64) -> bool {
+            return self.boarded.contains(&id);
+        }
+        
+        /// Return every pedestrian that has boarded, in order
+        pub fn get_events(&self) -> &[BoardingEvent] {
+            return &self.events;
+        }
+        
+        /// Return the pedestrians left behind once the last train has left: \[(pedestrian ID, simulation time)]
+        pub fn get_left_behind(&self) -> &[(u64, f64)] {
+            return &self.left_behind;
+        }
+        
+        /// Return the simulation times each train arrives and leaves, in order of arrival: \[(arrival, departure)]
+        pub fn get_trains(&self) -> &[(f64, f64)] {
+            return &self.trains;
+        }
+        
+        /// Return the number of pedestrians that boarded each train and how long after the train arrived the last of them boarded, in seconds (None = nobody boarded it)
+        pub fn get_train_summary(&self) -> Vec<(usize, Option<f64>)> {
+            return self.trains.iter().enumerate().map(|(train, (arrival, _))| {
+                let boarded = self.events.iter().filter(|event| event.train == train);
+                let count = boarded.clone().count();
+                let last = boarded.map(|event| event.time - arrival).reduce(f64::max);
+                (count, last)
+            }).collect();
+        }
+    }
+    
+    
+    #[cfg(test)]
+    mod tests {
+        use std::sync::Arc;
+        use crate::simulation::simulator::simulator::{CrowdSim, SimArea};
+        use crate::simulation::behaviour::behaviour::Etiquette;
+        
+        #[test]
+        fn trains_take_their_capacity_and_leave_the_rest_behind() {
+            // Two doors at the end of a corridor, each taking two pedestrians per train
+            let mut area = SimArea::new();
+            area.add_wall((-1.0,0.0), (11.0,0.0));
+            area.add_wall((-1.0,4.0), (11.0,4.0));
+            area.add_start_end_group(vec![(0.0,1.0), (0.0,3.0)], vec![(10.0,1.0), (10.0,3.0)]);
+            
+            let mut crowd_sim = CrowdSim::new(Arc::new(area), 2.0);
+            crowd_sim.set_seed(0);
+            crowd_sim.set_run_limits(Some(120.0), None);
+            crowd_sim.add_boarding(0, 2, vec![(30.0, 40.0), (0.0, 20.0)]);
+            crowd_sim.add_pedestrian_set(10, 0, Arc::new(Etiquette::NoBias));
+            let (_, finished, _) = crowd_sim.simulate_full(0.1);
+            
+            // Each train takes four, in order of arrival, and the last two are left behind once the second train has left
+            let boarding = &crowd_sim.get_boardings()[0];
+            let summary = boarding.get_train_summary();
+            assert_eq!(summary.iter().map(|(count, _)| *count).collect::<Vec<usize>>(), vec![4, 4]);
+            assert_eq!(finished, 8);
+            assert_eq!(boarding.get_left_behind().len(), 2);
+            assert!(boarding.get_left_behind().iter().all(|(_, time)| *time > 40.0));
+            
+            for event in boarding.get_events() {
+                let (arrival, departure) = boarding.get_trains()[event.train];
+                assert!(event.time > arrival && event.time <= departure);
+                assert_eq!(boarding.get_events().iter().filter(|other| other.train == event.train && other.door == event.door).count(), 2);
+            }
+        }
+    }
+    
+}
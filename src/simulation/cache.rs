@@ -0,0 +1,171 @@
+pub mod cache {
+    
+    use std::fs;
+    use std::io;
+    use std::path::{Path, PathBuf};
+    use serde::{Deserialize, Serialize};
+    
+    use crate::simulation::simulator::simulator::CrowdSim;
+    
+    
+    /// The version of the pedestrian model, included in every cache key.
+    /// Increase this whenever a change to the model changes simulation results, so that results cached by older versions are ignored.
+    pub const MODEL_VERSION: u32 = 2;
+    
+    
+    /// The results of a full simulation: (total time, pedestrian count, \[(travel time, group, finish time)])
+    pub type RunResults = (f64, usize, Vec<(f64, usize, f64)>);
+    
+    /// Stores the results of finished simulations on disk, so that repeated experiments can skip runs that have already been done.
+    /// 
+    /// Each result is stored in its own TOML file, named after a hash of everything that determines it.
+    pub struct ResultCache {
+        /// The directory the result files are stored in
+        directory: PathBuf
+    }
+    
+    /// The results of a full simulation, in the form stored on disk
+    #[derive(Serialize, Deserialize)]
+    struct CachedResults {
+        total_time: f64,
+        pedestrian_count: usize,
+        /// \[(travel time, group, finish time)]
+        travel_times: Vec<(f64, usize, f64)>
+    }
+    
+    impl ResultCache {
+        /// Open a result cache, creating its directory if it doesn't exist
+        pub fn new(directory: &Path) -> io::Result<ResultCache> {
+            fs::create_dir_all(directory)?;
+            
+            return Ok(ResultCache {
+                directory: directory.to_path_buf()
+            });
+        }
+        
+        /// Return the cache key of a simulation that hasn't been run yet.
+        /// 
+        /// The key covers the model parameters and their variation, the pedestrian add rate and arrival process, the spawn gate, intent signalling,
+        /// the number of pedestrians in each group and the simulation's seed, which distinguishes repeated runs of the same configuration.
+        /// 
+        /// Return None if the simulation wasn't seeded with `CrowdSim::set_seed`: its results can't be repeated, so they mustn't be cached or reused.
+        /// 
+        /// * `scenario` - A label identifying the layout and the mix of behaviour models, which aren't otherwise part of the key
+        pub fn get_key(scenario: &str, crowd_sim: &CrowdSim) -> Option<String> {
+            let seed = crowd_sim.get_seed()?;
+            let description = format!(
+                "{}|{}|{:?}|{}|{:?}|{:?}|{:?}|{:?}|{}|{}",
+                scenario,
+                crowd_sim.get_pedestrian_add_rate(),
+                crowd_sim.get_arrival_process(),
+                crowd_sim.get_spawn_gate(),
+                crowd_sim.get_intent_signalling(),
+                crowd_sim.get_params(),
+                crowd_sim.get_param_variation(),
+                crowd_sim.get_group_demand(),
+                seed,
+                MODEL_VERSION
+            );
+            
+            return Some(format!("{:016x}", fnv1a_hash(description.as_bytes())));
+        }
+        
+        /// Return the cached results for a key, or None if there are none (or they can't be read)
+        /// 
+        /// Return format: (total time, pedestrian count, \[(travel time, group, finish time)])
+        pub fn load(&self, key: &str) -> Option<RunResults> {
+            let contents = fs::read_to_string(self.get_path(key)).ok()?;
+            let cached: CachedResults = toml::from_str(&contents).ok()?;
+            
+            return Some((cached.total_time, cached.pedestrian_count, cached.travel_times));
+        }
+        
+        /// Store the results of a simulation under a key, replacing any existing results
+        pub fn store(&self, key: &str, results: &RunResults) -> io::Result<()> {
+            let cached = CachedResults {
+                total_time: results.0,
+                pedestrian_count: results.1,
+                travel_times: results.2.clone()
+            };
+            let contents = toml::to_string(&cached).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            
+            return fs::write(self.get_path(key), contents);
+        }
+        
+        /// Return the path of the file that the results for a key are stored in
+        fn get_path(&self, key: &str) -> PathBuf {
+            return self.directory.join(format!("{}.toml", key));
+        }
+    }
+    
+    /// Hash some bytes with 64-bit FNV-1a, which (unlike the standard library's hasher) is guaranteed to give the same result on every platform and Rust version
+    fn fnv1a_hash(bytes: &[u8]) -> u64 {
+        let mut hash: u64 = 0xcbf29ce484222325;
+        for byte in bytes {
+            hash ^= *byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+        return hash;
+    }
+    
+    
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::sync::Arc;
+        use crate::simulation::simulator::simulator::SimArea;
+        use crate::simulation::behaviour::behaviour::Etiquette;
+        
+        /// A simulation of a few pedestrians walking down a corridor
+        fn create_sim(seed: Option<u64>) -> CrowdSim {
+            let mut area = SimArea::new();
+            area.add_start_end_group(vec![(0.0,1.0), (0.0,3.0)], vec![(10.0,1.0), (10.0,3.0)]);
+            
+            let mut crowd_sim = CrowdSim::new(Arc::new(area), 2.0);
+            if let Some(seed) = seed {
+                crowd_sim.set_seed(seed);
+            }
+            crowd_sim.add_pedestrian_set(4, 0, Arc::new(Etiquette::NoBias));
+            return crowd_sim;
+        }
+        
+        /// Open a cache in a directory of its own, emptied first
+        fn open_cache(name: &str) -> ResultCache {
+            let directory = std::env::temp_dir().join(format!("pedestrian_cache_test_{}_{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&directory);
+            return ResultCache::new(&directory).unwrap();
+        }
+        
+        #[test]
+        fn unseeded_simulations_are_never_cached() {
+            assert_eq!(ResultCache::get_key("corridor", &create_sim(None)), None);
+            assert!(ResultCache::get_key("corridor", &create_sim(Some(0))).is_some());
+        }
+        
+        #[test]
+        fn changed_params_or_seeds_miss() {
+            let cache = open_cache("miss");
+            let key = ResultCache::get_key("corridor", &create_sim(Some(1))).unwrap();
+            let results = (12.5, 4, vec![(10.0, 0, 10.0), (11.0, 0, 12.5)]);
+            cache.store(&key, &results).unwrap();
+            
+            // The same configuration and seed hits
+            assert_eq!(ResultCache::get_key("corridor", &create_sim(Some(1))), Some(key.clone()));
+            assert_eq!(cache.load(&key), Some(results));
+            
+            // Another seed, parameter or scenario label misses
+            let mut changed_params = create_sim(Some(1));
+            changed_params.set_params(changed_params.get_params().with_param("accel", 1.0).unwrap());
+            let other_keys = [
+                ResultCache::get_key("corridor", &create_sim(Some(2))).unwrap(),
+                ResultCache::get_key("corridor", &changed_params).unwrap(),
+                ResultCache::get_key("junction", &create_sim(Some(1))).unwrap()
+            ];
+            for other_key in &other_keys {
+                assert_ne!(*other_key, key);
+                assert_eq!(cache.load(other_key), None);
+            }
+        }
+    }
+    
+}
@@ -0,0 +1,91 @@
+pub mod calibration {
+    
+    
+    /// Find the point that minimises an objective function with the Nelder–Mead (downhill simplex) method, which only needs the value of the function, not its gradient.
+    /// 
+    /// This suits noisy, expensive objectives like the error of a simulation's results, as long as there are only a few dimensions.
+    /// 
+    /// * `objective` - The function to minimise. It may return infinity for points that aren't allowed.
+    /// * `start` - The initial guess
+    /// * `steps` - How far the initial simplex extends from `start` along each dimension
+    /// * `max_evaluations` - Stop once the objective has been evaluated this many times (a step that has already started is finished first)
+    /// * `tolerance` - Stop once the objective differs by less than this across the simplex
+    /// 
+    /// Returns (best point, objective at the best point)
+    pub fn nelder_mead<F: FnMut(&[f64]) -> f64>(mut objective: F, start: &[f64], steps: &[f64], max_evaluations: usize, tolerance: f64) -> (Vec<f64>, f64) {
+        // Reflection, expansion, contraction and shrink coefficients
+        const REFLECT: f64 = 1.0;
+        const EXPAND: f64 = 2.0;
+        const CONTRACT: f64 = 0.5;
+        const SHRINK: f64 = 0.5;
+        
+        let dimensions = start.len();
+        
+        // [(point, objective)], kept sorted from best to worst
+        let mut simplex = vec![(start.to_vec(), objective(start))];
+        for i in 0..dimensions {
+            let mut point = start.to_vec();
+            point[i] += steps[i];
+            let value = objective(&point);
+            simplex.push((point, value));
+        }
+        let mut evaluations = dimensions + 1;
+        
+        // A point along the line from the centroid of every point but the worst, through the centroid away from the worst point
+        let along = |centroid: &[f64], worst: &[f64], coefficient: f64| -> Vec<f64> {
+            centroid.iter().zip(worst).map(|(c, w)| c + coefficient * (c - w)).collect()
+        };
+        
+        loop {
+            simplex.sort_by(|a, b| a.1.total_cmp(&b.1));
+            
+            let spread = simplex[dimensions].1 - simplex[0].1;
+            if evaluations >= max_evaluations || spread.abs() < tolerance {
+                break;
+            }
+            
+            let centroid = (0..dimensions)
+                .map(|d| simplex[..dimensions].iter().map(|(point, _)| point[d]).sum::<f64>() / (dimensions as f64))
+                .collect::<Vec<_>>();
+            let worst = simplex[dimensions].clone();
+            
+            let reflected = along(&centroid, &worst.0, REFLECT);
+            let reflected_value = objective(&reflected);
+            evaluations += 1;
+            
+            if reflected_value < simplex[0].1 {
+                // The best so far, so see if going further is even better
+                let expanded = along(&centroid, &worst.0, EXPAND);
+                let expanded_value = objective(&expanded);
+                evaluations += 1;
+                simplex[dimensions] = if expanded_value < reflected_value {(expanded, expanded_value)} else {(reflected, reflected_value)};
+            } else if reflected_value < simplex[dimensions - 1].1 {
+                simplex[dimensions] = (reflected, reflected_value);
+            } else {
+                // Contract towards the better of the worst point and its reflection
+                let contracted = if reflected_value < worst.1 {
+                    along(&centroid, &worst.0, CONTRACT * REFLECT)
+                } else {
+                    along(&centroid, &worst.0, -CONTRACT)
+                };
+                let contracted_value = objective(&contracted);
+                evaluations += 1;
+                
+                if contracted_value < worst.1.min(reflected_value) {
+                    simplex[dimensions] = (contracted, contracted_value);
+                } else {
+                    // Nothing along the line helped, so shrink every point towards the best one
+                    let best = simplex[0].0.clone();
+                    for (point, value) in simplex.iter_mut().skip(1) {
+                        *point = best.iter().zip(point.iter()).map(|(b, p)| b + SHRINK * (p - b)).collect();
+                        *value = objective(point);
+                        evaluations += 1;
+                    }
+                }
+            }
+        }
+        
+        return simplex.swap_remove(0);
+    }
+    
+}
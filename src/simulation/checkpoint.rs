@@ -0,0 +1,206 @@
+pub mod checkpoint {
+    
+    use std::fs;
+    use std::io;
+    use std::path::Path;
+    use serde::{Deserialize, Serialize};
+    use rand_chacha::ChaCha8Rng;
+    use rand::SeedableRng;
+    
+    use crate::simulation::pedestrian::pedestrian::Walker;
+    use crate::simulation::simulator::simulator::{TimingEvent, StallEvent, CrossingDirection, AreaMeasurement, EvacuationResults};
+    use crate::simulation::arrivals::arrivals::ArrivalState;
+    
+    
+    /// The version of the checkpoint format.
+    /// Increase this whenever a change to `Checkpoint` or `Walker` stops older checkpoints from being restored correctly.
+    pub const CHECKPOINT_VERSION: u32 = 1;
+    
+    
+    /// The full state of a simulation at one moment, so that a long run can be saved to disk and resumed later, or a moment re-run with different parameters.
+    /// 
+    /// The environment, parameters, navigation and settings (arrival process, measurement areas, recorders, etc.) aren't saved: they are taken from the simulation the checkpoint is restored into (see `CrowdSim::restore_checkpoint`).
+    #[derive(Serialize, Deserialize)]
+    pub struct Checkpoint {
+        /// The version of the checkpoint format (see `CHECKPOINT_VERSION`)
+        pub version: u32,
+        /// The simulation time the checkpoint was taken at, in seconds
+        pub time_elapsed: f64,
+        pub pedestrian_add_rate: f64,
+        pub arrival_clock: f64,
+        /// The name of the simulation's arrival process (see `ArrivalProcess::name`)
+        pub arrival_process: String,
+        /// The state of the arrival process, which is only restored into an arrival process with the same name
+        pub arrival_state: ArrivalState,
+        pub next_pedestrian_id: u64,
+        /// The state of the simulation's random number generator
+        pub rng: RngState,
+        
+        pub available_pedestrians: Vec<WalkerCheckpoint>,
+        /// \[(release time, pedestrian)]
+        pub scheduled_pedestrians: Vec<(f64, WalkerCheckpoint)>,
+        pub held_pedestrians: Vec<WalkerCheckpoint>,
+        pub active_pedestrians: Vec<WalkerCheckpoint>,
+        pub finished_pedestrians: Vec<WalkerCheckpoint>,
+        
+        /// \[(travel time, group, finish time)]
+        pub travel_times: Vec<(f64, usize, f64)>,
+        pub timing_events: Vec<TimingEvent>,
+        #[serde(default)]
+        pub stall_events: Vec<StallEvent>,
+        pub travel_time_cohorts: Vec<Vec<String>>,
+        pub travel_time_behaviours: Vec<String>,
+        pub cohort_names: Vec<String>,
+        /// \[(jitter, group)]
+        pub jitter_results: Vec<(f64, usize)>,
+        /// \[(delay, group)]
+        pub junction_delays: Vec<(f64, usize)>,
+        /// \[(time, description)]
+        pub behaviour_switch_log: Vec<(f64, String)>,
+        /// \[(time, queue length)]
+        pub spawn_queue_lengths: Vec<(f64, usize)>,
+        /// Whether each door in the area is open
+        #[serde(default)]
+        pub door_states: Vec<bool>,
+        pub fundamental_diagrams: Vec<Vec<AreaMeasurement>>,
+        pub line_crossings: Vec<Vec<(f64, CrossingDirection)>>,
+        /// The simulation time the evacuation started at, in seconds, if it has started
+        pub evacuation_time: Option<f64>,
+        pub evacuation_results: Option<EvacuationResults>
+    }
+    
+    /// A pedestrian saved in a checkpoint, along with the name of the behaviour model it follows (models can't be serialised, so they are looked up by name when the checkpoint is restored)
+    #[derive(Serialize, Deserialize)]
+    pub struct WalkerCheckpoint {
+        pub behaviour: String,
+        pub walker: Walker
+    }
+    
+    /// The position of a ChaCha random number generator in its stream
+    #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+    pub struct RngState {
+        pub seed: [u8; 32],
+        pub stream: u64,
+        /// The number of 32-bit words generated so far
+        pub word_pos: u64
+    }
+    
+    impl Checkpoint {
+        /// Load a checkpoint from a TOML file, checking that it was written in the current format
+        pub fn load(path: &Path) -> io::Result<Checkpoint> {
+            let contents = fs::read_to_string(path)?;
+            let checkpoint: Checkpoint = toml::from_str(&contents).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            
+            if checkpoint.version != CHECKPOINT_VERSION {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Checkpoint version {} can't be restored (expected version {})", checkpoint.version, CHECKPOINT_VERSION)));
+            }
+            
+            return Ok(checkpoint);
+        }
+        
+        /// Save the checkpoint to a TOML file
+        pub fn save(&self, path: &Path) -> io::Result<()> {
+            let contents = toml::to_string(self).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            return fs::write(path, contents);
+        }
+    }
+    
+    impl RngState {
+        /// Record the position of a random number generator
+        pub fn new(rng: &ChaCha8Rng) -> RngState {
+            return RngState {
+                seed: rng.get_seed(),
+                stream: rng.get_stream(),
+                word_pos: rng.get_word_pos() as u64
+            };
+        }
+        
+        /// Create a random number generator that continues from the recorded position
+        pub fn restore(&self) -> ChaCha8Rng {
+            let mut rng = ChaCha8Rng::from_seed(self.seed);
+            rng.set_stream(self.stream);
+            rng.set_word_pos(self.word_pos as u128);
+            return rng;
+        }
+    }
+    
+    
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::sync::Arc;
+        use rand::Rng;
+        use crate::simulation::simulator::simulator::{CrowdSim, SimArea};
+        use crate::simulation::behaviour::behaviour::Etiquette;
+        
+        /// A corridor with pedestrians walking both ways, seeded so that every copy starts the same
+        fn create_sim() -> CrowdSim {
+            let mut area = SimArea::new();
+            area.add_wall((-1.0,0.0), (11.0,0.0));
+            area.add_wall((-1.0,4.0), (11.0,4.0));
+            area.add_start_end_group(vec![(0.0,1.0), (0.0,3.0)], vec![(10.0,1.0), (10.0,3.0)]);
+            area.add_start_end_group(vec![(10.0,1.0), (10.0,3.0)], vec![(0.0,1.0), (0.0,3.0)]);
+            
+            let mut crowd_sim = CrowdSim::new(Arc::new(area), 4.0);
+            crowd_sim.set_seed(7);
+            crowd_sim.add_pedestrian_set(6, 0, Arc::new(Etiquette::LeftBias));
+            crowd_sim.add_pedestrian_set(6, 1, Arc::new(Etiquette::NoBias));
+            return crowd_sim;
+        }
+        
+        /// The position, facing direction and speed of every walking pedestrian
+        fn get_state(crowd_sim: &CrowdSim) -> Vec<(u64, f64, f64, f64, f64)> {
+            return crowd_sim.get_active_pedestrians().iter().map(|ped| (ped.get_id(), ped.x, ped.y, ped.facing_direction, ped.inst_speed)).collect();
+        }
+        
+        #[test]
+        fn restored_checkpoint_reproduces_the_next_timestep() {
+            let mut original = create_sim();
+            for _ in 0..40 {
+                original.simulate_timestep(0.1);
+            }
+            
+            // Save to disk and load it back, into a fresh copy of the simulation
+            let path = std::env::temp_dir().join(format!("pedestrian_checkpoint_test_{}.toml", std::process::id()));
+            original.create_checkpoint().save(&path).unwrap();
+            let mut restored = create_sim();
+            restored.restore_checkpoint(Checkpoint::load(&path).unwrap()).unwrap();
+            let _ = fs::remove_file(&path);
+            
+            assert!(!get_state(&original).is_empty());
+            assert_eq!(get_state(&restored), get_state(&original));
+            
+            for _ in 0..5 {
+                original.simulate_timestep(0.1);
+                restored.simulate_timestep(0.1);
+                assert_eq!(get_state(&restored), get_state(&original));
+            }
+            assert_eq!(restored.get_travel_times(), original.get_travel_times());
+        }
+        
+        #[test]
+        fn rng_state_continues_the_stream() {
+            let mut rng = ChaCha8Rng::seed_from_u64(3);
+            for _ in 0..10 {
+                rng.gen::<f64>();
+            }
+            
+            let mut restored = RngState::new(&rng).restore();
+            for _ in 0..10 {
+                assert_eq!(restored.gen::<u64>(), rng.gen::<u64>());
+            }
+        }
+        
+        #[test]
+        fn other_versions_are_refused() {
+            let mut checkpoint = create_sim().create_checkpoint();
+            checkpoint.version = CHECKPOINT_VERSION + 1;
+            
+            let path = std::env::temp_dir().join(format!("pedestrian_checkpoint_version_test_{}.toml", std::process::id()));
+            checkpoint.save(&path).unwrap();
+            assert_eq!(Checkpoint::load(&path).err().map(|e| e.kind()), Some(io::ErrorKind::InvalidData));
+            let _ = fs::remove_file(&path);
+        }
+    }
+    
+}
@@ -0,0 +1,139 @@
+pub mod console {
+    
+    use std::io::{self, BufRead, BufReader, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::mpsc::{self, Receiver, Sender};
+    use std::thread;
+    
+    
+    /// The help text sent in reply to the `help` command or an unrecognised command
+    const HELP: &str = "commands: pause | resume | rate <pedestrians per second> | evacuate | snapshot [file] | status | quit [name]";
+    
+    
+    /// A command for a running simulation, sent from the console
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum Command {
+        /// Stop simulating until resumed
+        Pause,
+        /// Continue simulating after a pause
+        Resume,
+        /// Change the number of pedestrians added per second
+        SetRate(f64),
+        /// Start an evacuation straight away
+        Evacuate,
+        /// Write the positions of all walking pedestrians to a file (None = a default name)
+        Snapshot(Option<String>),
+        /// Print the simulation time and pedestrian counts
+        Status,
+        /// Save a snapshot and the results so far, then stop the simulation. The name is used as a prefix for the saved files (None = a default name).
+        Quit(Option<String>)
+    }
+    
+    impl Command {
+        /// Parse a single line of console input, such as `rate 1.5`
+        /// 
+        /// Returns an error message to show the user if the line isn't a valid command.
+        pub fn parse(line: &str) -> Result<Command, String> {
+            let mut words = line.split_whitespace();
+            let name = words.next().unwrap_or("");
+            let argument = words.next();
+            
+            return match (name, argument) {
+                ("pause", None) => Ok(Command::Pause),
+                ("resume", None) => Ok(Command::Resume),
+                ("rate", Some(rate)) => match rate.parse::<f64>() {
+                    Ok(rate) if rate > 0.0 => Ok(Command::SetRate(rate)),
+                    _ => Err(format!("invalid rate: {}", rate))
+                },
+                ("evacuate", None) => Ok(Command::Evacuate),
+                ("snapshot", path) => Ok(Command::Snapshot(path.map(|path| path.to_string()))),
+                ("status", None) => Ok(Command::Status),
+                ("quit", name) => Ok(Command::Quit(name.map(|name| name.to_string()))),
+                ("help", None) => Err(HELP.to_string()),
+                _ => Err(format!("unknown command: {}\n{}", line.trim(), HELP))
+            };
+        }
+    }
+    
+    /// Receives commands for a running simulation from stdin, and optionally from TCP clients, so that long headless runs can be steered without stopping them.
+    /// 
+    /// Input is read on background threads; the simulation collects the commands between timesteps with `poll`.
+    pub struct Console {
+        /// The commands read from stdin and TCP clients, or the error message for each line from stdin that isn't a valid command
+        receiver: Receiver<Result<Command, String>>
+    }
+    
+    impl Console {
+        /// Start reading commands from stdin, and from TCP clients if an address is given
+        /// 
+        /// * `address` - The address to listen for TCP clients on, such as "127.0.0.1:7878" (None = stdin only)
+        pub fn start(address: Option<&str>) -> io::Result<Console> {
+            let (sender, receiver) = mpsc::channel();
+            
+            let stdin_sender = sender.clone();
+            thread::spawn(move || {
+                for line in io::stdin().lock().lines() {
+                    let line = match line {
+                        Ok(line) => line,
+                        Err(_) => return
+                    };
+                    // Invalid lines are passed on too, so that the simulation can report them
+                    if stdin_sender.send(Command::parse(&line)).is_err() {
+                        return;
+                    }
+                }
+            });
+            
+            if let Some(address) = address {
+                let listener = TcpListener::bind(address)?;
+                thread::spawn(move || {
+                    for stream in listener.incoming().flatten() {
+                        let client_sender = sender.clone();
+                        thread::spawn(move || handle_client(stream, client_sender));
+                    }
+                });
+            }
+            
+            return Ok(Console {
+                receiver
+            });
+        }
+        
+        /// Return the commands received since the last poll, in order, without waiting for more
+        /// 
+        /// Lines from stdin that aren't valid commands are returned as the error message to show the user (TCP clients are sent theirs directly).
+        pub fn poll(&self) -> Vec<Result<Command, String>> {
+            return self.receiver.try_iter().collect();
+        }
+    }
+    
+    /// Read commands from a TCP client until it disconnects, replying to each line with "ok" or an error
+    fn handle_client(stream: TcpStream, sender: Sender<Result<Command, String>>) {
+        let mut writer = match stream.try_clone() {
+            Ok(writer) => writer,
+            Err(_) => return
+        };
+        
+        for line in BufReader::new(stream).lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(_) => return
+            };
+            
+            let reply = match Command::parse(&line) {
+                Ok(command) => {
+                    if sender.send(Ok(command)).is_err() {
+                        return;
+                    }
+                    "ok".to_string()
+                },
+                Err(message) => message
+            };
+            
+            if writeln!(writer, "{}", reply).is_err() {
+                return;
+            }
+        }
+    }
+    
+}
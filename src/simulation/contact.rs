@@ -0,0 +1,205 @@
+pub mod contact {
+    
+    use std::collections::{HashMap, HashSet};
+    
+    use crate::simulation::pedestrian::pedestrian::Walker;
+    
+    
+    /// The time a pair of pedestrians spent within the contact distance of each other
+    #[derive(Clone, Copy, Debug)]
+    pub struct ContactExposure {
+        /// The ID of the pedestrian with the lower ID (see `Walker::get_id`)
+        pub id_a: u64,
+        /// The ID of the pedestrian with the higher ID
+        pub id_b: u64,
+        pub group_a: usize,
+        pub group_b: usize,
+        /// The total time the pair spent within the contact distance, in seconds
+        pub duration: f64,
+        /// The simulation time the pair first came within the contact distance, in seconds
+        pub first_contact: f64,
+        /// The number of separate times the pair came within the contact distance
+        pub contacts: usize
+    }
+    
+    /// Records how long every pair of walking pedestrians spends within a set distance of each other, so that the contact graph of a run can be used
+    /// for proximity & epidemiological analyses (e.g. to compare how many close contacts different corridor layouts lead to).
+    /// 
+    /// Pairs are checked every timestep, measuring between the centres of the pedestrians, and walls aren't taken into account.
+    pub struct ContactRecorder {
+        /// The distance within which two pedestrians are in contact, in metres
+        distance: f64,
+        /// The exposure of every pair that has been in contact, by the IDs of the pair (lower ID first)
+        exposures: HashMap<(u64, u64), ContactExposure>,
+        /// The pairs in contact at the last timestep
+        in_contact: HashSet<(u64, u64)>,
+        /// The IDs of every pedestrian that has been walking while contacts were recorded
+        pedestrians: HashSet<u64>
+    }
+    
+    impl ContactRecorder {
+        /// * `distance` - The distance within which two pedestrians are in contact, in metres
+        pub fn new(distance: f64) -> ContactRecorder {
+            assert!(distance > 0.0, "The contact distance must be positive");
+            
+            return ContactRecorder {
+                distance,
+                exposures: HashMap::new(),
+                in_contact: HashSet::new(),
+                pedestrians: HashSet::new()
+            };
+        }
+        
+        /// Add the length of a timestep to the exposure of every pair of pedestrians within the contact distance of each other
+        /// 
+        /// * `time` - The simulation time at the start of the timestep, in seconds
+        /// * `time_scale` - The length of the timestep, in seconds
+        /// * `pedestrians` - Every walking pedestrian
+        pub fn record(&mut self, time: f64, time_scale: f64, pedestrians: &[Walker]) {
+            // Sweep along the x axis, so that only pedestrians less than the contact distance apart in x are compared
+            let mut order: Vec<usize> = (0..pedestrians.len()).collect();
+            order.sort_by(|a, b| pedestrians[*a].x.total_cmp(&pedestrians[*b].x));
+            
+            let mut in_contact = HashSet::new();
+            for (n, i) in order.iter().enumerate() {
+                let ped = &pedestrians[*i];
+                self.pedestrians.insert(ped.get_id());
+                
+                for j in &order[(n + 1)..] {
+                    let other = &pedestrians[*j];
+                    if other.x - ped.x >= self.distance {
+                        break;
+                    }
+                    if (other.x - ped.x).hypot(other.y - ped.y) >= self.distance {
+                        continue;
+                    }
+                    
+                    let (a, b) = if ped.get_id() < other.get_id() { (ped, other) } else { (other, ped) };
+                    let key = (a.get_id(), b.get_id());
+                    let exposure = self.exposures.entry(key).or_insert(ContactExposure {
+                        id_a: key.0,
+                        id_b: key.1,
+                        group_a: a.get_group(),
+                        group_b: b.get_group(),
+                        duration: 0.0,
+                        first_contact: time,
+                        contacts: 0
+                    });
+                    exposure.duration += time_scale;
+                    if !self.in_contact.contains(&key) {
+                        exposure.contacts += 1;
+                    }
+                    in_contact.insert(key);
+                }
+            }
+            
+            self.in_contact = in_contact;
+        }
+        
+        /// Return the distance within which two pedestrians are in contact, in metres
+        pub fn get_distance(&self) -> f64 {
+            return self.distance;
+        }
+        
+        /// Return the exposure of every pair of pedestrians that has been in contact (the edges of the contact graph), ordered by the IDs of the pairs
+        pub fn get_exposures(&self) -> Vec<ContactExposure> {
+            let mut exposures: Vec<ContactExposure> = self.exposures.values().copied().collect();
+            exposures.sort_by_key(|exposure| (exposure.id_a, exposure.id_b));
+            return exposures;
+        }
+        
+        /// Return the number of pedestrians that have been walking while contacts were recorded (the nodes of the contact graph)
+        pub fn get_pedestrian_count(&self) -> usize {
+            return self.pedestrians.len();
+        }
+        
+        /// Return the number of other pedestrians each pedestrian spent at least a minimum time in contact with, by pedestrian ID
+        /// (pedestrians without any such contacts are left out)
+        /// 
+        /// * `min_duration` - The shortest total time in contact that counts, in seconds
+        pub fn get_degrees(&self, min_duration: f64) -> HashMap<u64, usize> {
+            let mut degrees = HashMap::new();
+            for exposure in self.exposures.values().filter(|exposure| exposure.duration >= min_duration) {
+                *degrees.entry(exposure.id_a).or_insert(0) += 1;
+                *degrees.entry(exposure.id_b).or_insert(0) += 1;
+            }
+            return degrees;
+        }
+        
+        /// Return the mean number of other pedestrians each pedestrian spent at least a minimum time in contact with (0 if nobody has been walking)
+        /// 
+        /// * `min_duration` - The shortest total time in contact that counts, in seconds
+        pub fn get_mean_degree(&self, min_duration: f64) -> f64 {
+            if self.pedestrians.is_empty() {
+                return 0.0;
+            }
+            
+            let total: usize = self.get_degrees(min_duration).values().sum();
+            return (total as f64) / (self.pedestrians.len() as f64);
+        }
+    }
+    
+    
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::sync::Arc;
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha8Rng;
+        use crate::simulation::simulator::simulator::SimArea;
+        use crate::simulation::behaviour::behaviour::Etiquette;
+        use crate::simulation::params::params::SimParams;
+        
+        /// Create pedestrians with IDs 0, 1, 2, ... at the given positions
+        fn create_walkers(positions: &[(f64, f64)]) -> Vec<Walker> {
+            let mut area = SimArea::new();
+            area.add_start_end_group(vec![(0.0,0.0)], vec![(10.0,0.0)]);
+            let area = Arc::new(area);
+            
+            return positions.iter().enumerate().map(|(i, (x, y))| {
+                let mut walker = Walker::new(area.clone(), (0, 0), (0, 0), 1.3, Arc::new(Etiquette::NoBias), SimParams::default(), &mut ChaCha8Rng::seed_from_u64(0));
+                walker.set_id(i as u64);
+                walker.x = *x;
+                walker.y = *y;
+                walker
+            }).collect();
+        }
+        
+        #[test]
+        fn exposures_add_up_over_separate_contacts() {
+            let mut recorder = ContactRecorder::new(1.0);
+            let mut walkers = create_walkers(&[(0.0, 0.0), (0.5, 0.5), (5.0, 0.0)]);
+            
+            // Pedestrians 0 & 1 are in contact for 3 timesteps, apart for 1, then in contact again for 2
+            for step in 0..6 {
+                walkers[1].x = if step == 3 {2.0} else {0.5};
+                recorder.record((step as f64) * 0.5, 0.5, &walkers);
+            }
+            
+            let exposures = recorder.get_exposures();
+            assert_eq!(exposures.len(), 1);
+            assert_eq!((exposures[0].id_a, exposures[0].id_b), (0, 1));
+            assert_eq!(exposures[0].duration, 2.5);
+            assert_eq!(exposures[0].first_contact, 0.0);
+            assert_eq!(exposures[0].contacts, 2);
+            
+            // Pedestrian 2 never came close to anyone, but still counts towards the mean
+            assert_eq!(recorder.get_pedestrian_count(), 3);
+            assert_eq!(recorder.get_degrees(2.0), HashMap::from([(0, 1), (1, 1)]));
+            assert!(recorder.get_degrees(3.0).is_empty());
+            assert!((recorder.get_mean_degree(2.0) - 2.0/3.0).abs() < 1e-9);
+        }
+        
+        #[test]
+        fn pairs_are_found_across_the_sweep() {
+            // Pedestrians sorted between a pair in x don't stop the pair being found, and pairs apart in y aren't in contact
+            let mut recorder = ContactRecorder::new(1.0);
+            let walkers = create_walkers(&[(0.9, 0.0), (0.0, 0.0), (0.5, 3.0), (0.4, 3.5)]);
+            recorder.record(0.0, 0.1, &walkers);
+            
+            let pairs: Vec<(u64, u64)> = recorder.get_exposures().iter().map(|exposure| (exposure.id_a, exposure.id_b)).collect();
+            assert_eq!(pairs, vec![(0, 1), (2, 3)]);
+        }
+    }
+    
+}
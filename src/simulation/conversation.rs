@@ -0,0 +1,65 @@
+pub mod conversation {
+    
+    use serde::Deserialize;
+    
+    
+    /// Settings for pedestrians stopping to chat when they meet someone they know walking the other way.
+    /// 
+    /// In a scenario file, this is the `[conversations]` table. Any field that is left out keeps its default value.
+    #[derive(Clone, Copy, Debug, Deserialize)]
+    #[serde(default)]
+    pub struct Conversations {
+        /// The probability that two pedestrians know each other well enough to stop and chat when they meet, from 0 to 1
+        pub probability: f64,
+        /// How close two acquaintances need to be to stop, in metres
+        pub meeting_distance: f64,
+        /// The shortest and longest a conversation lasts, in seconds (uniformly distributed between them)
+        pub duration_bounds: (f64, f64),
+        /// Which pairs of pedestrians know each other is decided from this number and their IDs, so changing it gives different acquaintances
+        pub seed: u64
+    }
+    
+    impl Conversations {
+        /// Whether two pedestrians know each other, decided once for each pair from their IDs so that it doesn't change from one timestep to the next
+        pub fn are_acquainted(&self, first_id: u64, second_id: u64) -> bool {
+            // The same for either order of the pair
+            let pair = mix_bits(self.seed ^ first_id.min(second_id)) ^ first_id.max(second_id);
+            let uniform = (mix_bits(pair) >> 11) as f64 / ((1u64 << 53) as f64);
+            return uniform < self.probability;
+        }
+    }
+    
+    impl Default for Conversations {
+        /// Nobody knows anybody, but conversations last 5-30s once started
+        fn default() -> Conversations {
+            Conversations {
+                probability: 0.0,
+                meeting_distance: 1.0,
+                duration_bounds: (5.0, 30.0),
+                seed: 0
+            }
+        }
+    }
+    
+    /// A conversation that has started
+    #[derive(Clone, Copy, Debug)]
+    pub struct Conversation {
+        /// The simulation time the conversation started, in seconds
+        pub time: f64,
+        /// The IDs of the two pedestrians chatting
+        pub pedestrian_ids: (u64, u64),
+        /// The point halfway between them, in metres
+        pub position: (f64, f64),
+        /// How long the conversation lasts, in seconds
+        pub duration: f64
+    }
+    
+    /// Scramble the bits of a number (the SplitMix64 finaliser), so that similar inputs give unrelated outputs
+    fn mix_bits(x: u64) -> u64 {
+        let mut z = x.wrapping_add(0x9E3779B97F4A7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        return z ^ (z >> 31);
+    }
+    
+}
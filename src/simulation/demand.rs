@@ -0,0 +1,97 @@
+pub mod demand {
+    
+    use serde::Deserialize;
+    
+    
+    /// A pedestrian add rate that changes over the course of a simulation, such as a morning peak.
+    /// 
+    /// The rate is interpolated linearly between breakpoints, and held constant before the first and after the last one.
+    /// Two breakpoints at the same time make the rate change in a single step.
+    /// 
+    /// In a scenario file, a demand profile is written as a list of \[time, rate] breakpoints.
+    #[derive(Clone, Debug, Deserialize)]
+    #[serde(try_from = "Vec<(f64, f64)>")]
+    pub struct DemandProfile {
+        /// \[(simulation time in seconds, pedestrians per second)], in order of time
+        breakpoints: Vec<(f64, f64)>
+    }
+    
+    impl TryFrom<Vec<(f64, f64)>> for DemandProfile {
+        type Error = String;
+        
+        fn try_from(mut breakpoints: Vec<(f64, f64)>) -> Result<DemandProfile, String> {
+            if breakpoints.is_empty() {
+                return Err("a demand profile needs at least one breakpoint".to_string());
+            }
+            if breakpoints.iter().any(|(time, rate)| !time.is_finite() || !rate.is_finite() || *rate < 0.0) {
+                return Err("demand profile breakpoints need finite times and non-negative rates".to_string());
+            }
+            
+            // A stable sort keeps the order of breakpoints at the same time, so that steps go the way they were written
+            breakpoints.sort_by(|a, b| a.0.total_cmp(&b.0));
+            
+            return Ok(DemandProfile {
+                breakpoints
+            });
+        }
+    }
+    
+    impl DemandProfile {
+        /// Create a new demand profile
+        /// 
+        /// * `breakpoints` - \[(simulation time in seconds, pedestrians per second)]. There must be at least one, and no rate can be negative.
+        pub fn new(breakpoints: Vec<(f64, f64)>) -> DemandProfile {
+            return DemandProfile::try_from(breakpoints).expect("Invalid demand profile");
+        }
+        
+        /// Return the number of pedestrians added per second at a given simulation time
+        pub fn get_rate(&self, time: f64) -> f64 {
+            let first = self.breakpoints[0];
+            let last = self.breakpoints[self.breakpoints.len() - 1];
+            
+            if time <= first.0 {
+                return first.1;
+            }
+            if time >= last.0 {
+                return last.1;
+            }
+            
+            // The first breakpoint after the given time
+            let i = self.breakpoints.partition_point(|(t, _)| *t <= time);
+            let (t1, r1) = self.breakpoints[i - 1];
+            let (t2, r2) = self.breakpoints[i];
+            
+            return r1 + (r2 - r1) * (time - t1) / (t2 - t1);
+        }
+        
+        /// Return the expected number of pedestrians added between two simulation times (the integral of the rate)
+        pub fn get_expected_arrivals(&self, from: f64, to: f64) -> f64 {
+            return self.get_cumulative_arrivals(to) - self.get_cumulative_arrivals(from);
+        }
+        
+        /// Return the expected number of arrivals between the first breakpoint and a given simulation time (negative before the first breakpoint)
+        fn get_cumulative_arrivals(&self, time: f64) -> f64 {
+            let first = self.breakpoints[0];
+            if time <= first.0 {
+                return (time - first.0) * first.1;
+            }
+            
+            let mut total = 0.0;
+            for pair in self.breakpoints.windows(2) {
+                let (t1, r1) = pair[0];
+                let (t2, r2) = pair[1];
+                
+                if time <= t2 {
+                    // Part of a trapezium, ending at the rate at the given time
+                    let rate = r1 + (r2 - r1) * (time - t1) / (t2 - t1);
+                    return total + (time - t1) * (r1 + rate) / 2.0;
+                }
+                total += (t2 - t1) * (r1 + r2) / 2.0;
+            }
+            
+            let last = self.breakpoints[self.breakpoints.len() - 1];
+            return total + (time - last.0) * last.1;
+        }
+    }
+    
+}
@@ -0,0 +1,136 @@
+pub mod density {
+    
+    use raylib::{drawing::{RaylibDrawHandle, RaylibDraw}, color::Color};
+    
+    use crate::simulation::simulator::simulator::SimArea;
+    use crate::simulation::geom::geom::Vec2;
+    
+    
+    /// The colours of a heatmap, from no pedestrians to the highest density (white, yellow, orange, red, dark red)
+    const HEATMAP_COLOURS: [(f64, f64, f64); 5] = [(255.0, 255.0, 255.0), (255.0, 237.0, 160.0), (254.0, 178.0, 76.0), (240.0, 59.0, 32.0), (128.0, 0.0, 38.0)];
+    
+    /// How opaque the heatmap is when drawn over the environment, from 0 to 1
+    const HEATMAP_OVERLAY_OPACITY: f32 = 0.6;
+    
+    
+    /// Records how crowded each part of the environment has been, over a grid covering it.
+    /// 
+    /// Each cell accumulates the time pedestrians have spent in it, so that the average density can be found over any length of simulation.
+    pub struct DensityGrid {
+        /// The coordinates of the corner of the first cell
+        origin: Vec2,
+        /// The width and height of each cell, in metres
+        cell_size: f64,
+        /// The number of cells in the x and y directions
+        size: (usize, usize),
+        /// The total time pedestrians have spent in each cell, in pedestrian-seconds
+        occupancy: Vec<f64>,
+        /// The simulation time recorded so far, in seconds
+        duration: f64
+    }
+    
+    impl DensityGrid {
+        /// Create an empty grid covering an environment
+        /// 
+        /// * `cell_size` - The width and height of each cell, in metres
+        pub fn new(area: &SimArea, cell_size: f64) -> DensityGrid {
+            let ((min_x, min_y), (max_x, max_y)) = area.get_bounds();
+            let size = (
+                ((max_x - min_x) / cell_size).ceil().max(1.0) as usize,
+                ((max_y - min_y) / cell_size).ceil().max(1.0) as usize
+            );
+            
+            return DensityGrid {
+                origin: Vec2::new(min_x, min_y),
+                cell_size,
+                size,
+                occupancy: vec![0.0; size.0*size.1],
+                duration: 0.0
+            };
+        }
+        
+        /// Record the positions of the pedestrians during one timestep. Pedestrians outside the grid are ignored.
+        /// 
+        /// * `positions` - The position of every walking pedestrian
+        /// * `time_scale` - The length of the timestep, in seconds
+        pub fn record<I: Iterator<Item = (f64, f64)>>(&mut self, positions: I, time_scale: f64) {
+            for (x, y) in positions {
+                let cell_x = ((x - self.origin.x) / self.cell_size).floor();
+                let cell_y = ((y - self.origin.y) / self.cell_size).floor();
+                
+                if cell_x >= 0.0 && cell_y >= 0.0 && cell_x < self.size.0 as f64 && cell_y < self.size.1 as f64 {
+                    self.occupancy[(cell_y as usize)*self.size.0 + cell_x as usize] += time_scale;
+                }
+            }
+            self.duration += time_scale;
+        }
+        
+        /// Return the number of cells in the x and y directions
+        pub fn get_size(&self) -> (usize, usize) {
+            return self.size;
+        }
+        
+        /// Return the width and height of each cell, in metres
+        pub fn get_cell_size(&self) -> f64 {
+            return self.cell_size;
+        }
+        
+        /// Return the coordinates of the centre of a cell
+        pub fn get_cell_centre(&self, x: usize, y: usize) -> (f64, f64) {
+            return (self.origin.x + (x as f64 + 0.5) * self.cell_size, self.origin.y + (y as f64 + 0.5) * self.cell_size);
+        }
+        
+        /// Return the average density in a cell over the time recorded so far, in pedestrians per square metre
+        pub fn get_density(&self, x: usize, y: usize) -> f64 {
+            if self.duration <= 0.0 {
+                return 0.0;
+            }
+            return self.occupancy[y*self.size.0 + x] / (self.duration * self.cell_size * self.cell_size);
+        }
+        
+        /// Return the highest average density of any cell, in pedestrians per square metre
+        pub fn get_peak_density(&self) -> f64 {
+            if self.duration <= 0.0 {
+                return 0.0;
+            }
+            return self.occupancy.iter().copied().fold(0.0, f64::max) / (self.duration * self.cell_size * self.cell_size);
+        }
+        
+        /// Draw the average densities so far as a translucent heatmap with RayLib, scaled so that the busiest cell has the darkest colour.
+        /// Cells no pedestrian has been in are left clear.
+        pub fn draw(&self, rl_handle: &mut RaylibDrawHandle, offset: (i32, i32), draw_scale: i32) {
+            let peak_density = self.get_peak_density();
+            if peak_density <= 0.0 {
+                return;
+            }
+            
+            for y in 0..self.size.1 {
+                for x in 0..self.size.0 {
+                    let density = self.get_density(x, y);
+                    if density <= 0.0 {
+                        continue;
+                    }
+                    
+                    // Round both edges, so that neighbouring cells meet without gaps
+                    let left = offset.0 + ((draw_scale as f64) * (self.origin.x + (x as f64) * self.cell_size)).round() as i32;
+                    let top = offset.1 + ((draw_scale as f64) * (self.origin.y + (y as f64) * self.cell_size)).round() as i32;
+                    let right = offset.0 + ((draw_scale as f64) * (self.origin.x + ((x + 1) as f64) * self.cell_size)).round() as i32;
+                    let bottom = offset.1 + ((draw_scale as f64) * (self.origin.y + ((y + 1) as f64) * self.cell_size)).round() as i32;
+                    rl_handle.draw_rectangle(left, top, right - left, bottom - top, Color::fade(&get_heatmap_colour(density / peak_density), HEATMAP_OVERLAY_OPACITY));
+                }
+            }
+        }
+    }
+    
+    /// Return the heatmap colour of a density level from 0 (no pedestrians) to 1 (the highest density), interpolating between the colour stops
+    pub fn get_heatmap_colour(level: f64) -> Color {
+        let position = level.clamp(0.0, 1.0) * ((HEATMAP_COLOURS.len() - 1) as f64);
+        let i = (position.floor() as usize).min(HEATMAP_COLOURS.len() - 2);
+        let t = position - (i as f64);
+        
+        let (r1, g1, b1) = HEATMAP_COLOURS[i];
+        let (r2, g2, b2) = HEATMAP_COLOURS[i + 1];
+        return Color::new((r1 + (r2 - r1)*t) as u8, (g1 + (g2 - g1)*t) as u8, (b1 + (b2 - b1)*t) as u8, 255);
+    }
+    
+}
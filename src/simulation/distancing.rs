@@ -0,0 +1,88 @@
+pub mod distancing {
+    
+    use serde::Deserialize;
+    
+    use crate::simulation::params::params::SimParams;
+    
+    
+    /// The cohort that pedestrians keeping their distance are reported in
+    pub const DISTANCING_COHORT: &str = "distancing";
+    
+    
+    /// Settings for physical distancing: a fraction of the pedestrians keep a larger personal space around themselves, e.g. during an epidemic.
+    /// 
+    /// In a scenario file, this is the `[distancing]` table. Any field that is left out keeps its default value.
+    #[derive(Clone, Copy, Debug, Deserialize)]
+    #[serde(default)]
+    pub struct Distancing {
+        /// The fraction of pedestrians that keep their distance, from 0 to 1. Each pedestrian is chosen at random as it is added.
+        pub compliance: f64,
+        /// The personal space radius of a pedestrian keeping its distance, in metres. Pedestrians with a larger personal space already keep theirs.
+        pub pspace_radius: f64
+    }
+    
+    impl Distancing {
+        /// Return the parameters of a pedestrian keeping its distance, given the parameters it would have otherwise
+        pub fn apply(&self, params: SimParams) -> SimParams {
+            return SimParams {
+                pspace_radius: params.pspace_radius.max(self.pspace_radius),
+                ..params
+            };
+        }
+    }
+    
+    impl Default for Distancing {
+        /// Everyone keeps a 2m personal space (the distance most commonly advised during the COVID-19 pandemic)
+        fn default() -> Distancing {
+            Distancing {
+                compliance: 1.0,
+                pspace_radius: 2.0
+            }
+        }
+    }
+    
+    
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::sync::Arc;
+        use crate::simulation::simulator::simulator::{CrowdSim, SimArea};
+        use crate::simulation::behaviour::behaviour::Etiquette;
+        
+        #[test]
+        fn larger_personal_spaces_are_kept() {
+            let distancing: Distancing = toml::from_str("compliance = 0.5").unwrap();
+            assert_eq!(distancing.pspace_radius, 2.0);
+            
+            let params = SimParams::default();
+            assert_eq!(distancing.apply(params).pspace_radius, 2.0);
+            assert_eq!(distancing.apply(SimParams {pspace_radius: 3.0, ..params}).pspace_radius, 3.0);
+            assert_eq!(distancing.apply(params).radius, params.radius);
+        }
+        
+        #[test]
+        fn distancing_reduces_close_contact() {
+            // The total time pairs of pedestrians walking down a corridor spend within 1m of each other
+            let get_contact_time = |distancing: Option<Distancing>| {
+                let mut area = SimArea::new();
+                area.add_wall((-1.0,0.0), (21.0,0.0));
+                area.add_wall((-1.0,4.0), (21.0,4.0));
+                area.add_start_end_group(vec![(0.0,1.0), (0.0,3.0)], vec![(20.0,1.0), (20.0,3.0)]);
+                
+                let mut crowd_sim = CrowdSim::new(Arc::new(area), 2.0);
+                crowd_sim.set_seed(0);
+                crowd_sim.set_run_limits(Some(120.0), None);
+                crowd_sim.set_distancing(distancing);
+                crowd_sim.enable_contact_recording(1.0);
+                crowd_sim.add_pedestrian_set(10, 0, Arc::new(Etiquette::NoBias));
+                crowd_sim.simulate_full(0.1);
+                return crowd_sim.get_contacts().unwrap().get_exposures().iter().map(|exposure| exposure.duration).sum::<f64>();
+            };
+            
+            let crowded = get_contact_time(None);
+            let distanced = get_contact_time(Some(Distancing::default()));
+            assert!(distanced < 0.8 * crowded, "{}s in contact while distancing, {}s otherwise", distanced, crowded);
+        }
+    }
+    
+}
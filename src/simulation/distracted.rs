@@ -0,0 +1,122 @@
+pub mod distracted {
+    
+    use std::sync::Arc;
+    use rand::{Rng, RngCore};
+    use serde::Deserialize;
+    
+    use crate::simulation::pedestrian::pedestrian::Walker;
+    use crate::simulation::behaviour::behaviour::BehaviourModel;
+    use crate::simulation::params::params::SimParams;
+    
+    
+    /// How distracted pedestrians (e.g. walking while looking at a phone) differ from attentive ones, and how many of them there are.
+    /// 
+    /// In a scenario file, this is the `[distraction]` table. Any field that is left out keeps its default value.
+    #[derive(Clone, Copy, Debug, Deserialize)]
+    #[serde(default)]
+    pub struct Distraction {
+        /// The fraction of pedestrians that are distracted, from 0 to 1. Each pedestrian is chosen at random as it is added.
+        pub fraction: f64,
+        /// A multiplier applied to how far a distracted pedestrian looks ahead and to the side
+        pub perception_factor: f64,
+        /// A multiplier applied to a distracted pedestrian's fields of view
+        pub fov_factor: f64,
+        /// A multiplier applied to how strongly a distracted pedestrian turns away from and slows for its neighbours and walls
+        pub reaction_factor: f64,
+        /// The average number of times a distracted pedestrian stops per second of walking
+        pub stop_rate: f64,
+        /// How long each stop lasts, in seconds
+        pub stop_duration: f64
+    }
+    
+    impl Distraction {
+        /// Return the parameters of a distracted pedestrian, given the parameters it would have if it were paying attention
+        pub fn apply(&self, params: SimParams) -> SimParams {
+            return SimParams {
+                look_ahead_radius: params.look_ahead_radius * self.perception_factor,
+                look_ahead_time: params.look_ahead_time * self.perception_factor,
+                look_beside_radius: params.look_beside_radius * self.perception_factor,
+                look_ahead_fov: params.look_ahead_fov * self.fov_factor,
+                look_beside_fov: params.look_beside_fov * self.fov_factor,
+                collision_avoidance_fov: params.collision_avoidance_fov * self.fov_factor,
+                opposing_repulsion: params.opposing_repulsion * self.reaction_factor,
+                pspace_repulsion: params.pspace_repulsion * self.reaction_factor,
+                opposing_decel: params.opposing_decel * self.reaction_factor,
+                following_decel: params.following_decel * self.reaction_factor,
+                wall_repulsion: params.wall_repulsion * self.reaction_factor,
+                ..params
+            };
+        }
+    }
+    
+    impl Default for Distraction {
+        /// No distracted pedestrians, but halved perception and reactions for any that are made distracted
+        fn default() -> Distraction {
+            Distraction {
+                fraction: 0.0,
+                perception_factor: 0.5,
+                fov_factor: 0.5,
+                reaction_factor: 0.5,
+                stop_rate: 0.05,
+                stop_duration: 2.0
+            }
+        }
+    }
+    
+    
+    /// The behaviour of a distracted pedestrian: it follows another behaviour model, but stops every so often at random.
+    /// 
+    /// Its poorer perception and slower reactions come from its parameters (see `Distraction::apply`), which are set when it is added to a simulation.
+    pub struct Distracted {
+        /// The behaviour model followed while walking
+        base: Arc<dyn BehaviourModel>,
+        /// "distracted" followed by the name of the base model
+        name: String,
+        /// The average number of stops per second
+        stop_rate: f64,
+        /// How long each stop lasts, in seconds
+        stop_duration: f64
+    }
+    
+    impl Distracted {
+        /// Create a distracted version of a behaviour model
+        pub fn new(base: Arc<dyn BehaviourModel>, distraction: &Distraction) -> Distracted {
+            Distracted {
+                name: format!("distracted {}", base.name()),
+                base,
+                stop_rate: distraction.stop_rate,
+                stop_duration: distraction.stop_duration
+            }
+        }
+    }
+    
+    impl BehaviourModel for Distracted {
+        fn name(&self) -> &str {
+            return &self.name;
+        }
+        
+        /// Stop at random, at an average of `stop_rate` times per second while walking
+        fn decide(&self, walker: &mut Walker, time_scale: f64, rng: &mut dyn RngCore) {
+            if !walker.is_stopped() && rng.gen::<f64>() < self.stop_rate * time_scale {
+                walker.stop_for(self.stop_duration);
+            }
+        }
+        
+        fn accelerate(&self, walker: &mut Walker, time_scale: f64) {
+            self.base.accelerate(walker, time_scale);
+        }
+        
+        fn steer_towards_target(&self, walker: &mut Walker, target_angle: f64, time_scale: f64) {
+            self.base.steer_towards_target(walker, target_angle, time_scale);
+        }
+        
+        fn react_to_neighbours(&self, walker: &mut Walker, time_scale: f64, other_pedestrians: &[(f64, f64, f64, f64, f64)]) {
+            self.base.react_to_neighbours(walker, time_scale, other_pedestrians);
+        }
+        
+        fn react_to_wall(&self, walker: &mut Walker, dist: f64, normal_angle: f64, time_scale: f64) {
+            self.base.react_to_wall(walker, dist, normal_angle, time_scale);
+        }
+    }
+    
+}
@@ -0,0 +1,324 @@
+pub mod experiment {
+    
+    use std::fs;
+    use std::io;
+    use std::path::Path;
+    use serde::Deserialize;
+    
+    use crate::simulation::simulator::simulator::CrowdSim;
+    use crate::simulation::scenario::scenario::Scenario;
+    use crate::simulation::export::export::csv_field;
+    
+    
+    /// The name of the sweep parameter that changes the pedestrian add rate, rather than a model parameter
+    pub const ADD_RATE_PARAMETER: &str = "add_rate";
+    
+    /// A batch of simulation runs, described in a TOML file or built with `new` & the `add_*` methods.
+    /// 
+    /// Every scenario is run with every combination of the swept parameter values (a full factorial design), once per seed.
+    #[derive(Clone, Debug, Deserialize)]
+    pub struct Experiment {
+        /// The CSV file to write the results to (or a Parquet file, if it ends with ".parquet" and the `parquet` feature is enabled)
+        pub output: String,
+        /// The layout of the results
+        #[serde(default)]
+        pub format: ResultFormat,
+        /// The scenarios to run
+        pub scenarios: Vec<ExperimentScenario>,
+        /// The parameters to sweep. Every combination of their values is run.
+        #[serde(default)]
+        pub sweeps: Vec<Sweep>,
+        /// The number of times to run each combination, with seeds 0, 1, 2... (ignored if `seeds` is given)
+        #[serde(default = "default_repetitions")]
+        pub repetitions: usize,
+        /// The seed of each repetition of each combination
+        pub seeds: Option<Vec<u64>>,
+        /// The number of threads to run simulations on (None = the default, 0 = one per logical CPU)
+        pub threads: Option<usize>
+    }
+    
+    /// One of the scenarios in an experiment
+    #[derive(Clone, Debug, Deserialize)]
+    pub struct ExperimentScenario {
+        /// A label for the scenario's results
+        pub name: String,
+        /// The built-in simulation to run, numbered like SIM_TYPE in main.rs
+        pub simulation: usize,
+        /// A scenario file with the parameters to run it with (None = the default parameters)
+        pub scenario_file: Option<String>
+    }
+    
+    /// A parameter to sweep over a list or range of values
+    #[derive(Clone, Debug, Deserialize)]
+    pub struct Sweep {
+        /// The name of a numeric field of SimParams (as in the `[params]` table of a scenario file), or "add_rate"
+        pub parameter: String,
+        /// The values to try
+        #[serde(default)]
+        pub values: Vec<f64>,
+        /// Evenly spaced values to try as well: (first, last, step). The last value is only included if the steps land on it.
+        pub range: Option<(f64, f64, f64)>
+    }
+    
+    /// How the results of an experiment are laid out in its output file
+    #[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+    #[serde(rename_all = "snake_case")]
+    pub enum ResultFormat {
+        /// One row per run, with a column for each outcome
+        #[default]
+        Wide,
+        /// One row per outcome of each run, with the outcome's name and value in two columns ("tidy" data, for plotting tools)
+        Long
+    }
+    
+    /// A single run of an experiment
+    #[derive(Clone, Debug)]
+    pub struct ExperimentRun {
+        /// The index of the run's scenario in the experiment
+        pub scenario: usize,
+        /// The value of each swept parameter, in the order of the sweeps
+        pub values: Vec<f64>,
+        pub seed: u64,
+        /// The scenario's settings with the swept model parameters applied
+        pub settings: Scenario,
+        /// The swept pedestrian add rate (None = the simulation's own)
+        pub add_rate: Option<f64>
+    }
+    
+    fn default_repetitions() -> usize {
+        return 1;
+    }
+    
+    impl Sweep {
+        /// Return every value to try: the listed values, then the range
+        pub fn get_values(&self) -> Vec<f64> {
+            let mut values = self.values.clone();
+            if let Some((first, last, step)) = self.range {
+                // Allow for rounding errors in the number of steps
+                let step_count = ((last - first) / step + 1e-9).floor().max(0.0) as usize;
+                // Rounded, so that the values are written to the results as they would be typed
+                values.extend((0..=step_count).map(|i| ((first + step * (i as f64)) * 1e9).round() / 1e9));
+            }
+            
+            return values;
+        }
+    }
+    
+    impl Experiment {
+        /// Create an experiment with no scenarios or sweeps, that runs everything once (with seed 0) and writes its results to `output` in the wide format
+        pub fn new(output: &str) -> Experiment {
+            Experiment {
+                output: output.to_string(),
+                format: ResultFormat::Wide,
+                scenarios: Vec::new(),
+                sweeps: Vec::new(),
+                repetitions: 1,
+                seeds: None,
+                threads: None
+            }
+        }
+        
+        /// Add a scenario to run
+        /// 
+        /// * `simulation` - The built-in simulation to run, numbered like SIM_TYPE in main.rs
+        /// * `scenario_file` - A scenario file with the parameters to run it with (None = the default parameters)
+        pub fn add_scenario(&mut self, name: &str, simulation: usize, scenario_file: Option<&str>) {
+            self.scenarios.push(ExperimentScenario {
+                name: name.to_string(),
+                simulation,
+                scenario_file: scenario_file.map(|path| path.to_string())
+            });
+        }
+        
+        /// Sweep a parameter over a list of values
+        /// 
+        /// * `parameter` - The name of a numeric field of SimParams (as in the `[params]` table of a scenario file), or "add_rate"
+        pub fn add_sweep(&mut self, parameter: &str, values: Vec<f64>) {
+            self.sweeps.push(Sweep {parameter: parameter.to_string(), values, range: None});
+        }
+        
+        /// Sweep a parameter over evenly spaced values from `first` to `last`
+        pub fn add_range_sweep(&mut self, parameter: &str, first: f64, last: f64, step: f64) {
+            self.sweeps.push(Sweep {parameter: parameter.to_string(), values: Vec::new(), range: Some((first, last, step))});
+        }
+        
+        /// Load an experiment from a TOML file
+        pub fn from_file(path: &Path) -> io::Result<Experiment> {
+            let contents = fs::read_to_string(path)?;
+            
+            return toml::from_str(&contents).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()));
+        }
+        
+        /// Return the seed of each repetition of each combination
+        pub fn get_seeds(&self) -> Vec<u64> {
+            return match &self.seeds {
+                Some(seeds) => seeds.clone(),
+                None => (0..self.repetitions as u64).collect()
+            };
+        }
+        
+        /// Return every run of the experiment: each scenario in turn, with every combination of swept values (the last sweep changing fastest), and every seed
+        /// 
+        /// This loads the scenario files, and fails if one can't be read or a swept parameter isn't a numeric model parameter.
+        pub fn get_runs(&self) -> io::Result<Vec<ExperimentRun>> {
+            // Every combination of swept values
+            let mut combinations = vec![Vec::new()];
+            for sweep in &self.sweeps {
+                combinations = combinations.iter()
+                    .flat_map(|combination| sweep.get_values().into_iter().map(move |value| [combination.as_slice(), &[value]].concat()))
+                    .collect();
+            }
+            
+            let seeds = self.get_seeds();
+            let mut runs = Vec::new();
+            
+            for (i, scenario) in self.scenarios.iter().enumerate() {
+                let base = match &scenario.scenario_file {
+                    Some(path) => Scenario::from_file(Path::new(path))?,
+                    None => Scenario::default()
+                };
+                
+                for values in &combinations {
+                    let mut settings = base.clone();
+                    let mut add_rate = None;
+                    for (sweep, value) in self.sweeps.iter().zip(values) {
+                        if sweep.parameter == ADD_RATE_PARAMETER {
+                            add_rate = Some(*value);
+                        } else {
+                            settings.params = settings.params.with_param(&sweep.parameter, *value)?;
+                        }
+                    }
+                    
+                    for seed in &seeds {
+                        runs.push(ExperimentRun {
+                            scenario: i,
+                            values: values.clone(),
+                            seed: *seed,
+                            settings: settings.clone(),
+                            add_rate
+                        });
+                    }
+                }
+            }
+            
+            return Ok(runs);
+        }
+        
+        /// Write the outcomes of the experiment's runs to its output file, in its format
+        /// 
+        /// * `runs` - The runs, as returned by `get_runs`
+        /// * `outcomes` - The outcomes of each run, in the same order: \[(name, value)], with the same names in the same order for every run
+        pub fn write_results(&self, runs: &[ExperimentRun], outcomes: &[Vec<(&str, f64)>]) -> io::Result<()> {
+            if self.output.ends_with(".parquet") {
+                return self.write_results_parquet(runs, outcomes);
+            }
+            
+            let mut contents = String::from("scenario,simulation,seed");
+            for sweep in &self.sweeps {
+                contents.push_str(&format!(",{}", csv_field(&sweep.parameter)));
+            }
+            match self.format {
+                ResultFormat::Wide => {
+                    for (name, _) in outcomes.first().map(|o| o.as_slice()).unwrap_or(&[]) {
+                        contents.push_str(&format!(",{}", csv_field(name)));
+                    }
+                },
+                ResultFormat::Long => {contents.push_str(",outcome,value")}
+            }
+            contents.push('\n');
+            
+            for (run, run_outcomes) in runs.iter().zip(outcomes) {
+                let scenario = &self.scenarios[run.scenario];
+                let mut prefix = format!("{},{},{}", csv_field(&scenario.name), scenario.simulation, run.seed);
+                for value in &run.values {
+                    prefix.push_str(&format!(",{}", value));
+                }
+                
+                match self.format {
+                    ResultFormat::Wide => {
+                        contents.push_str(&prefix);
+                        for (_, value) in run_outcomes {
+                            contents.push_str(&format!(",{}", value));
+                        }
+                        contents.push('\n');
+                    },
+                    ResultFormat::Long => {
+                        for (name, value) in run_outcomes {
+                            contents.push_str(&format!("{},{},{}\n", prefix, csv_field(name), value));
+                        }
+                    }
+                }
+            }
+            
+            return fs::write(&self.output, contents);
+        }
+        
+        /// Write the outcomes of the experiment's runs to a Parquet file, with the same columns as the CSV file
+        #[cfg(feature = "parquet")]
+        fn write_results_parquet(&self, runs: &[ExperimentRun], outcomes: &[Vec<(&str, f64)>]) -> io::Result<()> {
+            use crate::simulation::export::export::{write_parquet, ParquetColumn};
+            
+            // Each run's row is repeated for each of its outcomes in the long format
+            let repeats = match self.format {
+                ResultFormat::Wide => 1,
+                ResultFormat::Long => outcomes.first().map(|o| o.len()).unwrap_or(0)
+            };
+            fn per_row<T: Clone>(runs: &[ExperimentRun], repeats: usize, get: impl Fn(&ExperimentRun) -> T) -> Vec<T> {
+                return runs.iter().flat_map(|run| std::iter::repeat_n(get(run), repeats)).collect();
+            }
+            
+            let mut columns = vec![
+                ("scenario", ParquetColumn::Text(per_row(runs, repeats, |run| self.scenarios[run.scenario].name.clone()))),
+                ("simulation", ParquetColumn::Int64(per_row(runs, repeats, |run| self.scenarios[run.scenario].simulation as i64))),
+                ("seed", ParquetColumn::Int64(per_row(runs, repeats, |run| run.seed as i64)))
+            ];
+            for (i, sweep) in self.sweeps.iter().enumerate() {
+                columns.push((sweep.parameter.as_str(), ParquetColumn::Double(per_row(runs, repeats, |run| run.values[i]))));
+            }
+            
+            match self.format {
+                ResultFormat::Wide => {
+                    for (i, (name, _)) in outcomes.first().map(|o| o.as_slice()).unwrap_or(&[]).iter().enumerate() {
+                        columns.push((name, ParquetColumn::Double(outcomes.iter().map(|run_outcomes| run_outcomes[i].1).collect())));
+                    }
+                },
+                ResultFormat::Long => {
+                    columns.push(("outcome", ParquetColumn::Text(outcomes.iter().flatten().map(|(name, _)| name.to_string()).collect())));
+                    columns.push(("value", ParquetColumn::Double(outcomes.iter().flatten().map(|(_, value)| *value).collect())));
+                }
+            }
+            
+            return write_parquet(Path::new(&self.output), "experiment", columns);
+        }
+        
+        /// Parquet files can only be written with the `parquet` feature
+        #[cfg(not(feature = "parquet"))]
+        fn write_results_parquet(&self, _runs: &[ExperimentRun], _outcomes: &[Vec<(&str, f64)>]) -> io::Result<()> {
+            return Err(io::Error::new(io::ErrorKind::Unsupported, "Writing Parquet files needs the parquet feature"));
+        }
+    }
+    
+    impl ExperimentRun {
+        /// Apply the run's seed and settings to a simulation.
+        /// 
+        /// Like `CrowdSim::set_params`, this should be done before any pedestrians are added.
+        pub fn apply(&self, crowd_sim: &mut CrowdSim) {
+            crowd_sim.set_seed(self.seed);
+            crowd_sim.set_params(self.settings.params);
+            crowd_sim.set_param_variation(self.settings.variation);
+            if let Some(arrivals) = &self.settings.arrivals {
+                crowd_sim.set_arrival_process(arrivals.build());
+            }
+            crowd_sim.set_distraction(self.settings.distraction);
+            crowd_sim.set_conversations(self.settings.conversations);
+            crowd_sim.set_distancing(self.settings.distancing);
+            crowd_sim.set_demographics(self.settings.demographics.clone());
+            crowd_sim.set_luggage(self.settings.luggage.clone());
+            crowd_sim.add_events(self.settings.events.clone());
+            if let Some(add_rate) = self.add_rate {
+                crowd_sim.set_pedestrian_add_rate(add_rate);
+            }
+        }
+    }
+    
+}
@@ -0,0 +1,743 @@
+pub mod export {
+    
+    use std::f64::consts::TAU;
+    use std::fs;
+    use std::io;
+    use std::path::Path;
+    
+    use raylib::{texture::Image, color::Color};
+    
+    use crate::simulation::simulator::simulator::{CrowdSim, CrossingDirection, SimArea, TARGET_LOCATION_RADIUS};
+    use crate::simulation::density::density::{DensityGrid, get_heatmap_colour};
+    use crate::simulation::trajectory::trajectory::TrajectoryRecorder;
+    use crate::simulation::pressure::pressure::PressureRecorder;
+    use crate::simulation::contact::contact::ContactRecorder;
+    use crate::simulation::params::params::ParamDistribution;
+    
+    
+    /// The half-width of the rectangle used to represent a start or end point, in metres
+    const POINT_HALF_WIDTH: f64 = 0.5;
+    
+    /// The thickness given to walls when they are exported as polygons, in metres
+    const WALL_THICKNESS: f64 = 0.1;
+    
+    /// The number of sides of the polygons used to approximate circular obstacles
+    const OBSTACLE_SEGMENTS: usize = 16;
+    
+    /// The width and height of each density grid cell in a heatmap image, in pixels
+    const HEATMAP_CELL_PIXELS: i32 = 10;
+    
+    
+    
+    /// Write the state of every walking pedestrian to a CSV file: ID, x, y, facing direction, speed, group and cohorts (separated by semicolons)
+    pub fn write_snapshot(crowd_sim: &CrowdSim, path: &Path) -> io::Result<()> {
+        let snapshot = crowd_sim.snapshot();
+        let mut contents = format!("# time = {}\nid,x,y,direction,speed,group,cohorts\n", snapshot.time);
+        for ped in &snapshot.pedestrians {
+            contents.push_str(&format!("{},{},{},{},{},{},{}\n", ped.id, ped.x, ped.y, ped.heading, ped.speed, ped.group, csv_field(&ped.cohorts.join(";"))));
+        }
+        
+        return fs::write(path, contents);
+    }
+    
+    /// Write the travel time of every pedestrian timed so far to a CSV file: pedestrian ID, travel time, group, finish time and cohorts (separated by semicolons)
+    pub fn write_travel_times(crowd_sim: &CrowdSim, path: &Path) -> io::Result<()> {
+        let mut contents = String::from("id,travel_time,group,finish_time,cohorts\n");
+        for (event, cohorts) in crowd_sim.get_timing_events().iter().zip(crowd_sim.get_travel_time_cohorts()) {
+            contents.push_str(&format!("{},{},{},{},{}\n", event.pedestrian_id, event.elapsed, event.group, event.sim_time, csv_field(&cohorts.join(";"))));
+        }
+        
+        return fs::write(path, contents);
+    }
+    
+    /// Write the number of pedestrians held back by the spawn gate over time to a CSV file: simulation time and queue length, each time the length changed
+    pub fn write_spawn_queue(crowd_sim: &CrowdSim, path: &Path) -> io::Result<()> {
+        let mut contents = String::from("time,queue_length\n");
+        for (time, length) in crowd_sim.get_spawn_queue_lengths() {
+            contents.push_str(&format!("{},{}\n", time, length));
+        }
+        
+        return fs::write(path, contents);
+    }
+    
+    /// Write every pedestrian served at every queue to a CSV file: queue, pedestrian ID, group, the simulation times it joined, started being served and left, and how long it waited
+    pub fn write_queue_waits(crowd_sim: &CrowdSim, path: &Path) -> io::Result<()> {
+        let mut contents = String::from("queue,id,group,joined,service_start,left,waiting_time\n");
+        for queue in 0..crowd_sim.get_area().queues.len() {
+            for wait in crowd_sim.get_queue(queue).get_waits() {
+                contents.push_str(&format!("{},{},{},{},{},{},{}\n", queue, wait.id, wait.group, wait.joined, wait.service_start, wait.left, wait.get_waiting_time()));
+            }
+        }
+        
+        return fs::write(path, contents);
+    }
+    
+    /// Write every pedestrian that has been through every ticket gate to a CSV file: gate, pedestrian ID, group, the simulation times it started waiting, stepped into the gate and was let through, and how long it waited
+    pub fn write_gate_passages(crowd_sim: &CrowdSim, path: &Path) -> io::Result<()> {
+        let mut contents = String::from("gate,id,group,arrived,service_start,left,waiting_time\n");
+        for gate in 0..crowd_sim.get_area().gates.len() {
+            for passage in crowd_sim.get_gate(gate).get_passages() {
+                contents.push_str(&format!("{},{},{},{},{},{},{}\n", gate, passage.id, passage.group, passage.arrived, passage.service_start, passage.left, passage.get_waiting_time()));
+            }
+        }
+        
+        return fs::write(path, contents);
+    }
+    
+    /// Write the number of pedestrians waiting for each ticket gate over time to a CSV file: gate, simulation time and queue length, each time the length changed
+    pub fn write_gate_queues(crowd_sim: &CrowdSim, path: &Path) -> io::Result<()> {
+        let mut contents = String::from("gate,time,queue_length\n");
+        for gate in 0..crowd_sim.get_area().gates.len() {
+            for (time, length) in crowd_sim.get_gate(gate).get_lengths() {
+                contents.push_str(&format!("{},{},{}\n", gate, time, length));
+            }
+        }
+        
+        return fs::write(path, contents);
+    }
+    
+    /// Write every pedestrian that has boarded a train to a CSV file: boarding group, pedestrian ID, door, train, the simulation time the train arrived and the time the pedestrian boarded
+    pub fn write_boardings(crowd_sim: &CrowdSim, path: &Path) -> io::Result<()> {
+        let mut contents = String::from("group,id,door,train,train_time,time\n");
+        for boarding in crowd_sim.get_boardings() {
+            for event in boarding.get_events() {
+                contents.push_str(&format!("{},{},{},{},{},{}\n", boarding.get_group(), event.id, event.door, event.train, boarding.get_trains()[event.train].0, event.time));
+            }
+        }
+        
+        return fs::write(path, contents);
+    }
+    
+    /// Write every conflict between a pedestrian and a vehicle to a CSV file: simulation time, vehicle ID, pedestrian ID, the pedestrian's position, the vehicle's speed and the distance between them
+    pub fn write_vehicle_conflicts(crowd_sim: &CrowdSim, path: &Path) -> io::Result<()> {
+        let mut contents = String::from("time,vehicle_id,pedestrian_id,x,y,vehicle_speed,distance\n");
+        for conflict in crowd_sim.get_vehicle_traffic().get_conflicts() {
+            contents.push_str(&format!("{},{},{},{},{},{},{}\n", conflict.time, conflict.vehicle_id, conflict.pedestrian_id,
+                conflict.position.0, conflict.position.1, conflict.vehicle_speed, conflict.distance));
+        }
+        
+        return fs::write(path, contents);
+    }
+    
+    /// Write every crossing of every counting line to a CSV file: counting line, simulation time and direction ("forward" or "backward")
+    pub fn write_line_crossings(crowd_sim: &CrowdSim, path: &Path) -> io::Result<()> {
+        let mut contents = String::from("line,time,direction\n");
+        for line in 0..crowd_sim.get_area().counting_lines.len() {
+            for (time, direction) in crowd_sim.get_line_crossings(line) {
+                let direction = match direction {
+                    CrossingDirection::Forward => "forward",
+                    CrossingDirection::Backward => "backward"
+                };
+                contents.push_str(&format!("{},{},{}\n", line, time, direction));
+            }
+        }
+        
+        return fs::write(path, contents);
+    }
+    
+    /// Write the flux through every counting line to a CSV file: counting line, start time of the interval, and forward, backward and net flux (pedestrians/s)
+    pub fn write_line_flux(crowd_sim: &CrowdSim, path: &Path) -> io::Result<()> {
+        let mut contents = String::from("line,time,forward,backward,net\n");
+        for line in 0..crowd_sim.get_area().counting_lines.len() {
+            for (time, forward, backward) in crowd_sim.get_line_flux(line) {
+                contents.push_str(&format!("{},{},{},{},{}\n", line, time, forward, backward, forward - backward));
+            }
+        }
+        
+        return fs::write(path, contents);
+    }
+    
+    /// Write the measurements taken in every measurement area to a CSV file, for comparison with empirical fundamental diagrams:
+    /// measurement area, simulation time, density (pedestrians/m²), mean speed (m/s) and specific flow (pedestrians/m/s), counted and with the Voronoi method.
+    /// 
+    /// Values that weren't measured (e.g. the speed in an empty area) are left empty.
+    pub fn write_fundamental_diagram(crowd_sim: &CrowdSim, path: &Path) -> io::Result<()> {
+        let mut contents = String::from("area,time,density,speed,flow,voronoi_density,voronoi_speed,voronoi_flow\n");
+        for area in 0..crowd_sim.get_area().measurement_areas.len() {
+            for measurement in crowd_sim.get_fundamental_diagram(area) {
+                let (speed, flow) = match measurement.mean_speed {
+                    Some(speed) => (speed.to_string(), (measurement.density * speed).to_string()),
+                    None => (String::new(), String::new())
+                };
+                let (voronoi_density, voronoi_speed, voronoi_flow) = match measurement.voronoi {
+                    Some((density, speed)) => (density.to_string(), speed.to_string(), (density * speed).to_string()),
+                    None => (String::new(), String::new(), String::new())
+                };
+                contents.push_str(&format!("{},{},{},{},{},{},{},{}\n", area, measurement.time, measurement.density, speed, flow, voronoi_density, voronoi_speed, voronoi_flow));
+            }
+        }
+        
+        return fs::write(path, contents);
+    }
+    
+    /// Write the average density recorded in each cell of a simulation's density grid to a CSV file: the x and y coordinates of the cell's centre, and the density in pedestrians per square metre
+    pub fn write_density_csv(density_grid: &DensityGrid, path: &Path) -> io::Result<()> {
+        let mut contents = format!("# cell size = {}\nx,y,density\n", density_grid.get_cell_size());
+        let (size_x, size_y) = density_grid.get_size();
+        for y in 0..size_y {
+            for x in 0..size_x {
+                let (centre_x, centre_y) = density_grid.get_cell_centre(x, y);
+                contents.push_str(&format!("{},{},{}\n", centre_x, centre_y, density_grid.get_density(x, y)));
+            }
+        }
+        
+        return fs::write(path, contents);
+    }
+    
+    /// Write every trajectory sample to a CSV file: simulation time, pedestrian ID, x, y, speed (m/s) and heading (radians)
+    pub fn write_trajectories_csv(trajectories: &TrajectoryRecorder, path: &Path) -> io::Result<()> {
+        let mut contents = format!("# interval = {}\ntime,id,x,y,speed,heading\n", trajectories.get_interval());
+        for sample in trajectories.get_samples() {
+            contents.push_str(&format!("{},{},{},{},{},{}\n", sample.time, sample.id, sample.x, sample.y, sample.speed, sample.heading));
+        }
+        
+        return fs::write(path, contents);
+    }
+    
+    /// Write the contact graph to a CSV file, one row per pair of pedestrians that have been in contact:
+    /// the IDs and groups of the pair, the total time they spent in contact (s), the simulation time they first came into contact (s), and the number of separate contacts
+    pub fn write_contacts_csv(contacts: &ContactRecorder, path: &Path) -> io::Result<()> {
+        let mut contents = format!("# distance = {}, pedestrians = {}\nid_a,id_b,group_a,group_b,duration,first_contact,contacts\n", contacts.get_distance(), contacts.get_pedestrian_count());
+        for exposure in contacts.get_exposures() {
+            contents.push_str(&format!("{},{},{},{},{},{},{}\n", exposure.id_a, exposure.id_b, exposure.group_a, exposure.group_b, exposure.duration, exposure.first_contact, exposure.contacts));
+        }
+        
+        return fs::write(path, contents);
+    }
+    
+    /// Write every crowd pressure sample to a CSV file: simulation time, pedestrian ID, x, y, local density (pedestrians/m²), crowd pressure (s^-2) and contact force per unit mass (m⋅s^-2)
+    pub fn write_pressure_csv(pressure: &PressureRecorder, path: &Path) -> io::Result<()> {
+        let mut contents = format!("# interval = {}\ntime,id,x,y,density,pressure,contact_force\n", pressure.get_interval());
+        for sample in pressure.get_samples() {
+            contents.push_str(&format!("{},{},{},{},{},{},{}\n", sample.time, sample.id, sample.x, sample.y, sample.density, sample.pressure, sample.contact_force));
+        }
+        
+        return fs::write(path, contents);
+    }
+    
+    /// Write every time & place the crowd pressure or contact force exceeded its safety threshold to a CSV file:
+    /// the x and y coordinates of the grid cell's centre, the start and end times (empty if it was still going on), and the peak pressure (s^-2) and contact force per unit mass (m⋅s^-2)
+    pub fn write_risk_episodes_csv(pressure: &PressureRecorder, path: &Path) -> io::Result<()> {
+        let thresholds = pressure.get_thresholds();
+        let mut contents = format!("# density threshold = {}, pressure threshold = {}, contact force threshold = {}\nx,y,start,end,peak_pressure,peak_contact_force\n", thresholds.density, thresholds.pressure, thresholds.contact_force);
+        for episode in pressure.get_episodes() {
+            let end = episode.end.map(|end| end.to_string()).unwrap_or_default();
+            contents.push_str(&format!("{},{},{},{},{},{}\n", episode.centre.0, episode.centre.1, episode.start, end, episode.peak_pressure, episode.peak_contact_force));
+        }
+        
+        return fs::write(path, contents);
+    }
+    
+    /// Write every trajectory sample to a Parquet file, with the same columns as `write_trajectories_csv`, for analysis tools that handle large data sets better in a columnar format
+    #[cfg(feature = "parquet")]
+    pub fn write_trajectories_parquet(trajectories: &TrajectoryRecorder, path: &Path) -> io::Result<()> {
+        let samples = trajectories.get_samples();
+        
+        return write_parquet(path, "trajectory", vec![
+            ("time", ParquetColumn::Double(samples.iter().map(|sample| sample.time).collect())),
+            ("id", ParquetColumn::Int64(samples.iter().map(|sample| sample.id as i64).collect())),
+            ("x", ParquetColumn::Double(samples.iter().map(|sample| sample.x).collect())),
+            ("y", ParquetColumn::Double(samples.iter().map(|sample| sample.y).collect())),
+            ("speed", ParquetColumn::Double(samples.iter().map(|sample| sample.speed).collect())),
+            ("heading", ParquetColumn::Double(samples.iter().map(|sample| sample.heading).collect()))
+        ]);
+    }
+    
+    /// Write the travel time of every pedestrian timed so far to a Parquet file, with the same columns as `write_travel_times`
+    #[cfg(feature = "parquet")]
+    pub fn write_travel_times_parquet(crowd_sim: &CrowdSim, path: &Path) -> io::Result<()> {
+        let events = crowd_sim.get_timing_events();
+        
+        return write_parquet(path, "travel_times", vec![
+            ("id", ParquetColumn::Int64(events.iter().map(|event| event.pedestrian_id as i64).collect())),
+            ("travel_time", ParquetColumn::Double(events.iter().map(|event| event.elapsed).collect())),
+            ("group", ParquetColumn::Int64(events.iter().map(|event| event.group as i64).collect())),
+            ("finish_time", ParquetColumn::Double(events.iter().map(|event| event.sim_time).collect())),
+            ("cohorts", ParquetColumn::Text(crowd_sim.get_travel_time_cohorts().iter().map(|cohorts| cohorts.join(";")).collect()))
+        ]);
+    }
+    
+    /// A column of a table to be written to a Parquet file
+    #[cfg(feature = "parquet")]
+    pub enum ParquetColumn {
+        Double(Vec<f64>),
+        Int64(Vec<i64>),
+        Text(Vec<String>)
+    }
+    
+    /// Write a table to a Parquet file, as a single row group
+    /// 
+    /// * `table_name` - The name of the table's schema
+    /// * `columns` - \[(column name, values)], every column with the same number of values
+    #[cfg(feature = "parquet")]
+    pub fn write_parquet(path: &Path, table_name: &str, columns: Vec<(&str, ParquetColumn)>) -> io::Result<()> {
+        use std::sync::Arc;
+        use parquet::basic::{ConvertedType, Repetition, Type as PhysicalType};
+        use parquet::data_type::{ByteArray, ByteArrayType, DoubleType, Int64Type};
+        use parquet::file::{properties::WriterProperties, writer::SerializedFileWriter};
+        use parquet::schema::types::Type;
+        
+        let fields = columns.iter().map(|(name, column)| {
+            let builder = match column {
+                ParquetColumn::Double(_) => Type::primitive_type_builder(name, PhysicalType::DOUBLE),
+                ParquetColumn::Int64(_) => Type::primitive_type_builder(name, PhysicalType::INT64),
+                ParquetColumn::Text(_) => Type::primitive_type_builder(name, PhysicalType::BYTE_ARRAY).with_converted_type(ConvertedType::UTF8)
+            };
+            builder.with_repetition(Repetition::REQUIRED).build().map(Arc::new)
+        }).collect::<Result<Vec<_>, _>>().map_err(io::Error::other)?;
+        let schema = Type::group_type_builder(table_name).with_fields(fields).build().map_err(io::Error::other)?;
+        
+        let file = fs::File::create(path)?;
+        let mut writer = SerializedFileWriter::new(file, Arc::new(schema), Arc::new(WriterProperties::builder().build())).map_err(io::Error::other)?;
+        let mut row_group = writer.next_row_group().map_err(io::Error::other)?;
+        
+        // The columns are written in the order of the schema
+        let mut columns = columns.into_iter();
+        while let Some(mut column_writer) = row_group.next_column().map_err(io::Error::other)? {
+            match columns.next().map(|(_, column)| column) {
+                Some(ParquetColumn::Double(values)) => column_writer.typed::<DoubleType>().write_batch(&values, None, None),
+                Some(ParquetColumn::Int64(values)) => column_writer.typed::<Int64Type>().write_batch(&values, None, None),
+                Some(ParquetColumn::Text(values)) => {
+                    let values = values.iter().map(|value| ByteArray::from(value.as_str())).collect::<Vec<_>>();
+                    column_writer.typed::<ByteArrayType>().write_batch(&values, None, None)
+                },
+                None => unreachable!("The schema has a field for every column")
+            }.map_err(io::Error::other)?;
+            column_writer.close().map_err(io::Error::other)?;
+        }
+        
+        row_group.close().map_err(io::Error::other)?;
+        writer.close().map_err(io::Error::other)?;
+        return Ok(());
+    }
+    
+    /// Write a heatmap of the average density recorded in each cell of a simulation's density grid to a PNG file.
+    /// 
+    /// The colours are scaled to the highest density, from white (no pedestrians) through yellow and red to dark red.
+    pub fn write_density_png(density_grid: &DensityGrid, path: &Path) -> io::Result<()> {
+        let (size_x, size_y) = density_grid.get_size();
+        let peak_density = density_grid.get_peak_density();
+        
+        let mut image = Image::gen_image_color((size_x as i32) * HEATMAP_CELL_PIXELS, (size_y as i32) * HEATMAP_CELL_PIXELS, Color::WHITE);
+        for y in 0..size_y {
+            for x in 0..size_x {
+                let level = if peak_density > 0.0 {density_grid.get_density(x, y) / peak_density} else {0.0};
+                image.draw_rectangle(
+                    (x as i32) * HEATMAP_CELL_PIXELS,
+                    (y as i32) * HEATMAP_CELL_PIXELS,
+                    HEATMAP_CELL_PIXELS,
+                    HEATMAP_CELL_PIXELS,
+                    get_heatmap_colour(level)
+                );
+            }
+        }
+        
+        let path_str = path.to_str().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "heatmap path is not valid UTF-8"))?;
+        // RayLib doesn't report whether the export worked, so check for the file instead
+        let _ = fs::remove_file(path);
+        image.export_image(path_str);
+        if !path.exists() {
+            return Err(io::Error::other(format!("failed to export {}", path_str)));
+        }
+        
+        return Ok(());
+    }
+    
+    /// Quote a CSV field if it contains a comma or a quote
+    pub fn csv_field(field: &str) -> String {
+        if field.contains(',') || field.contains('"') {
+            return format!("\"{}\"", field.replace('"', "\"\""));
+        }
+        return field.to_string();
+    }
+    
+    /// Write a Vadere scenario file describing the given simulation.
+    /// 
+    /// Walls become thin polygonal obstacles, each start group becomes a source spawning at that group's share of the add rate, and each end point becomes an absorbing target.
+    /// Vadere's y-axis points upward, so all coordinates are mirrored about the top of the environment.
+    /// 
+    /// * `crowd_sim` - The simulation to export. Only pedestrians waiting to be added are counted as demand, so export before running it.
+    /// * `name` - The scenario name written into the file
+    pub fn write_vadere_scenario(crowd_sim: &CrowdSim, name: &str, path: &Path) -> io::Result<()> {
+        let params = crowd_sim.get_params();
+        let area = crowd_sim.get_area();
+        let ((min_x, min_y), (max_x, max_y)) = area.get_bounds();
+        
+        // Convert a simulation coordinate into a Vadere coordinate
+        let convert = |(x, y): (f64, f64)| (x - min_x + 1.0, max_y - y + 1.0);
+        
+        let mut obstacles = Vec::new();
+        for wall in &area.boundaries {
+            let (p1, p2) = wall.get_points();
+            let points = wall_polygon(convert(p1), convert(p2)).iter()
+                .map(|(x, y)| format!("{{ \"x\" : {:.3}, \"y\" : {:.3} }}", x, y))
+                .collect::<Vec<_>>()
+                .join(", ");
+            obstacles.push(format!("{{\n        \"shape\" : {{ \"type\" : \"POLYGON\", \"points\" : [ {} ] }},\n        \"id\" : -1\n      }}", points));
+        }
+        for obstacle in &area.obstacles {
+            let (x, y) = convert(obstacle.get_centre());
+            obstacles.push(format!("{{\n        \"shape\" : {{ \"type\" : \"CIRCLE\", \"radius\" : {:.3}, \"center\" : {{ \"x\" : {:.3}, \"y\" : {:.3} }} }},\n        \"id\" : -1\n      }}", obstacle.get_radius(), x, y));
+        }
+        
+        // Each end point becomes its own target, so record the target IDs belonging to each group
+        let mut targets = Vec::new();
+        let mut group_target_ids = Vec::new();
+        for ends in &area.end_positions {
+            let mut ids = Vec::new();
+            for end in ends {
+                let id = targets.len() + 1;
+                targets.push(format!("{{\n        \"id\" : {},\n        \"absorbing\" : true,\n        \"shape\" : {},\n        \"waitingTime\" : 0.0,\n        \"waitingTimeYellowPhase\" : 0.0,\n        \"parallelWaiters\" : 0,\n        \"individualWaiting\" : true,\n        \"deletionDistance\" : {:.3},\n        \"startingWithRedLight\" : false,\n        \"nextSpeed\" : -1.0\n      }}", id, vadere_rectangle(convert(*end), POINT_HALF_WIDTH), TARGET_LOCATION_RADIUS));
+                ids.push(id.to_string());
+            }
+            group_target_ids.push(ids.join(", "));
+        }
+        
+        let demand = crowd_sim.get_group_demand();
+        let total_demand = demand.iter().sum::<usize>().max(1);
+        
+        let mut sources = Vec::new();
+        for (group, starts) in area.start_positions.iter().enumerate() {
+            if demand[group] == 0 {
+                continue;
+            }
+            
+            // Spawn this group at its share of the overall add rate
+            let group_rate = crowd_sim.get_pedestrian_add_rate() * (demand[group] as f64) / (total_demand as f64);
+            let ((s_min_x, s_min_y), (s_max_x, s_max_y)) = point_bounds(starts.iter().map(|p| convert(*p)));
+            
+            sources.push(format!("{{\n        \"id\" : {},\n        \"shape\" : {{ \"x\" : {:.3}, \"y\" : {:.3}, \"width\" : {:.3}, \"height\" : {:.3}, \"type\" : \"RECTANGLE\" }},\n        \"interSpawnTimeDistribution\" : \"constant\",\n        \"distributionParameters\" : {{ \"updateFrequency\" : {:.4} }},\n        \"spawnNumber\" : 1,\n        \"maxSpawnNumberTotal\" : {},\n        \"startTime\" : 0.0,\n        \"endTime\" : {:.3},\n        \"spawnAtRandomPositions\" : true,\n        \"spawnAtGridPositionsCA\" : false,\n        \"useFreeSpaceOnly\" : true,\n        \"targetIds\" : [ {} ],\n        \"groupSizeDistribution\" : [ 1.0 ],\n        \"dynamicElementType\" : \"PEDESTRIAN\"\n      }}",
+                1000 + group,
+                s_min_x - POINT_HALF_WIDTH, s_min_y - POINT_HALF_WIDTH,
+                s_max_x - s_min_x + 2.0*POINT_HALF_WIDTH, s_max_y - s_min_y + 2.0*POINT_HALF_WIDTH,
+                1.0 / group_rate,
+                demand[group],
+                (demand[group] as f64) / group_rate,
+                group_target_ids[group]
+            ));
+        }
+        
+        let (speed_mean, speed_sd) = target_speed_mean_sd(crowd_sim);
+        
+        let contents = format!(r#"{{
+  "name" : "{name}",
+  "description" : "Exported from rust_pedestrian_simulator",
+  "release" : "2.1",
+  "processWriters" : {{ "files" : [ ], "processors" : [ ], "isTimestamped" : true, "isWriteMetaData" : false }},
+  "scenario" : {{
+    "mainModel" : "org.vadere.simulator.models.osm.OptimalStepsModel",
+    "attributesModel" : {{ }},
+    "attributesSimulation" : {{
+      "finishTime" : {finish_time:.1},
+      "simTimeStepLength" : 0.4,
+      "realTimeSimTimeRatio" : 0.1,
+      "writeSimulationData" : true,
+      "visualizationEnabled" : true,
+      "printFPS" : false,
+      "digitsPerCoordinate" : 2,
+      "useFixedSeed" : true,
+      "fixedSeed" : 1,
+      "simulationSeed" : 0
+    }},
+    "topography" : {{
+      "attributes" : {{
+        "bounds" : {{ "x" : 0.0, "y" : 0.0, "width" : {width:.3}, "height" : {height:.3} }},
+        "boundingBoxWidth" : 0.5,
+        "bounded" : true,
+        "referenceCoordinateSystem" : null
+      }},
+      "obstacles" : [ {obstacles} ],
+      "measurementAreas" : [ ],
+      "stairs" : [ ],
+      "targets" : [ {targets} ],
+      "targetChangers" : [ ],
+      "absorbingAreas" : [ ],
+      "aerosolClouds" : [ ],
+      "droplets" : [ ],
+      "sources" : [ {sources} ],
+      "dynamicElements" : [ ],
+      "attributesPedestrian" : {{
+        "radius" : {radius:.3},
+        "densityDependentSpeed" : false,
+        "speedDistributionMean" : {speed_mean:.3},
+        "speedDistributionStandardDeviation" : {speed_sd:.3},
+        "minimumSpeed" : {min_speed:.3},
+        "maximumSpeed" : {max_speed:.3},
+        "acceleration" : {accel:.3},
+        "footstepHistorySize" : 4,
+        "searchRadius" : 1.0,
+        "walkingDirectionCalculation" : "BY_TARGET_CENTER",
+        "walkingDirectionSameIfAngleLessOrEqual" : 45.0
+      }},
+      "teleporter" : null,
+      "attributesCar" : null
+    }},
+    "stimulusInfos" : [ ]
+  }}
+}}
+"#,
+            name = name,
+            finish_time = expected_duration(crowd_sim),
+            width = max_x - min_x + 2.0,
+            height = max_y - min_y + 2.0,
+            obstacles = obstacles.join(", "),
+            targets = targets.join(", "),
+            sources = sources.join(", "),
+            radius = params.radius,
+            speed_mean = speed_mean,
+            speed_sd = speed_sd,
+            min_speed = params.target_speed_bounds.0,
+            max_speed = params.target_speed_bounds.1,
+            accel = params.accel
+        );
+        
+        return fs::write(path, contents);
+    }
+    
+    /// Write a JuPedSim (jpscore) geometry file and project ini file describing the given simulation.
+    /// 
+    /// The geometry is written as a single room with every wall as a two-vertex polygon, each group's end points are combined into one goal, and each group is spawned by an agent source at that group's share of the add rate.
+    /// 
+    /// * `crowd_sim` - The simulation to export. Only pedestrians waiting to be added are counted as demand, so export before running it.
+    /// * `name` - The project name written into the files
+    /// * `directory` - The directory to write `<name>_geometry.xml` and `<name>_ini.xml` into
+    pub fn write_jupedsim_project(crowd_sim: &CrowdSim, name: &str, directory: &Path) -> io::Result<()> {
+        let params = crowd_sim.get_params();
+        let area = crowd_sim.get_area();
+        let geometry_file = format!("{}_geometry.xml", name);
+        
+        fs::write(directory.join(&geometry_file), jupedsim_geometry(area, name))?;
+        
+        let mut goals = Vec::new();
+        for (group, ends) in area.end_positions.iter().enumerate() {
+            let ((e_min_x, e_min_y), (e_max_x, e_max_y)) = point_bounds(ends.iter().copied());
+            goals.push(format!(
+                "      <goal id=\"{}\" final=\"true\" caption=\"group {} exit\">\n        <polygon>\n{}        </polygon>\n      </goal>\n",
+                group, group,
+                jupedsim_vertices(&[
+                    (e_min_x - POINT_HALF_WIDTH, e_min_y - POINT_HALF_WIDTH),
+                    (e_max_x + POINT_HALF_WIDTH, e_min_y - POINT_HALF_WIDTH),
+                    (e_max_x + POINT_HALF_WIDTH, e_max_y + POINT_HALF_WIDTH),
+                    (e_min_x - POINT_HALF_WIDTH, e_max_y + POINT_HALF_WIDTH),
+                    (e_min_x - POINT_HALF_WIDTH, e_min_y - POINT_HALF_WIDTH)
+                ], "          ")
+            ));
+        }
+        
+        let demand = crowd_sim.get_group_demand();
+        let total_demand = demand.iter().sum::<usize>().max(1);
+        
+        let mut distributions = Vec::new();
+        let mut sources = Vec::new();
+        for (group, starts) in area.start_positions.iter().enumerate() {
+            if demand[group] == 0 {
+                continue;
+            }
+            
+            let group_rate = crowd_sim.get_pedestrian_add_rate() * (demand[group] as f64) / (total_demand as f64);
+            let ((s_min_x, s_min_y), (s_max_x, s_max_y)) = point_bounds(starts.iter().copied());
+            
+            distributions.push(format!(
+                "      <group group_id=\"{g}\" agent_parameter_id=\"1\" room_id=\"0\" subroom_id=\"0\" number=\"0\" router_id=\"1\" goal_id=\"{g}\" x_min=\"{:.3}\" x_max=\"{:.3}\" y_min=\"{:.3}\" y_max=\"{:.3}\"/>\n",
+                s_min_x - POINT_HALF_WIDTH, s_max_x + POINT_HALF_WIDTH, s_min_y - POINT_HALF_WIDTH, s_max_y + POINT_HALF_WIDTH,
+                g = group
+            ));
+            sources.push(format!(
+                "      <source id=\"{g}\" caption=\"group {g}\" frequency=\"{:.4}\" N_create=\"1\" agents_max=\"{}\" group_id=\"{g}\" greedy=\"false\"/>\n",
+                1.0 / group_rate,
+                demand[group],
+                g = group
+            ));
+        }
+        
+        let (speed_mean, speed_sd) = target_speed_mean_sd(crowd_sim);
+        
+        let ini = format!(r#"<?xml version="1.0" encoding="UTF-8" ?>
+<JuPedSim project="{name}" version="0.8" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+  <seed>1</seed>
+  <max_sim_time>{max_time:.0}</max_sim_time>
+  <geometry>{geometry_file}</geometry>
+  <trajectories format="plain" fps="8">
+    <file location="{name}_trajectories.txt"/>
+  </trajectories>
+  <routing>
+    <goals>
+{goals}    </goals>
+  </routing>
+  <agents operational_model_id="3">
+    <agents_distribution>
+{distributions}    </agents_distribution>
+    <agents_sources>
+{sources}    </agents_sources>
+  </agents>
+  <operational_models>
+    <model operational_model_id="3" description="Tordeux2015">
+      <model_parameters>
+        <stepsize>0.01</stepsize>
+        <exit_crossing_strategy>8</exit_crossing_strategy>
+        <linkedcells enabled="true" cell_size="30"/>
+        <force_ped a="5" D="0.1"/>
+        <force_wall a="5" D="0.02"/>
+      </model_parameters>
+      <agent_parameters agent_parameter_id="1">
+        <v0 mu="{speed_mean:.3}" sigma="{speed_sd:.3}"/>
+        <bmax mu="{radius:.3}" sigma="0.0"/>
+        <bmin mu="{radius:.3}" sigma="0.0"/>
+        <amin mu="{radius:.3}" sigma="0.0"/>
+        <tau mu="0.5" sigma="0.0"/>
+        <atau mu="0.0" sigma="0.0"/>
+        <T mu="1" sigma="0.0"/>
+      </agent_parameters>
+    </model>
+  </operational_models>
+  <route_choice_models>
+    <router router_id="1" description="ff_global_shortest"/>
+  </route_choice_models>
+</JuPedSim>
+"#,
+            name = name,
+            max_time = expected_duration(crowd_sim) * 2.0,
+            geometry_file = geometry_file,
+            goals = goals.concat(),
+            distributions = distributions.concat(),
+            sources = sources.concat(),
+            speed_mean = speed_mean,
+            speed_sd = speed_sd,
+            radius = params.radius
+        );
+        
+        return fs::write(directory.join(format!("{}_ini.xml", name)), ini);
+    }
+    
+    /// Create the contents of a JuPedSim geometry file for the given environment
+    fn jupedsim_geometry(area: &SimArea, name: &str) -> String {
+        let mut walls = String::new();
+        for wall in &area.boundaries {
+            let (p1, p2) = wall.get_points();
+            walls.push_str("        <polygon caption=\"wall\">\n");
+            walls.push_str(&jupedsim_vertices(&[p1, p2], "          "));
+            walls.push_str("        </polygon>\n");
+        }
+        
+        // JuPedSim has no circular obstacles, so approximate each one with a regular polygon
+        let mut obstacles = String::new();
+        for (id, obstacle) in area.obstacles.iter().enumerate() {
+            let ((x, y), r) = (obstacle.get_centre(), obstacle.get_radius());
+            let points = (0..=OBSTACLE_SEGMENTS)
+                .map(|i| {
+                    let angle = TAU * (i % OBSTACLE_SEGMENTS) as f64 / OBSTACLE_SEGMENTS as f64;
+                    (x + r * angle.cos(), y + r * angle.sin())
+                })
+                .collect::<Vec<_>>();
+            obstacles.push_str(&format!("        <obstacle id=\"{}\" caption=\"obstacle\" closed=\"1\">\n          <polygon>\n", id));
+            obstacles.push_str(&jupedsim_vertices(&points, "            "));
+            obstacles.push_str("          </polygon>\n        </obstacle>\n");
+        }
+        
+        return format!(r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<geometry version="0.8" caption="{}" unit="m">
+  <rooms>
+    <room id="0" caption="area">
+      <subroom id="0" closed="0" class="subroom">
+{}{}      </subroom>
+    </room>
+  </rooms>
+  <transitions/>
+</geometry>
+"#, name, walls, obstacles);
+    }
+    
+    /// Format a list of points as JuPedSim vertex elements, one per line
+    fn jupedsim_vertices(points: &[(f64, f64)], indent: &str) -> String {
+        return points.iter().map(|(x, y)| format!("{}<vertex px=\"{:.3}\" py=\"{:.3}\"/>\n", indent, x, y)).collect();
+    }
+    
+    /// Format a square centred on a point as a Vadere rectangle shape
+    fn vadere_rectangle(centre: (f64, f64), half_width: f64) -> String {
+        return format!(
+            "{{ \"x\" : {:.3}, \"y\" : {:.3}, \"width\" : {:.3}, \"height\" : {:.3}, \"type\" : \"RECTANGLE\" }}",
+            centre.0 - half_width, centre.1 - half_width, 2.0*half_width, 2.0*half_width
+        );
+    }
+    
+    /// Thicken a wall into a rectangular polygon
+    fn wall_polygon(p1: (f64, f64), p2: (f64, f64)) -> [(f64, f64); 4] {
+        let length = ((p2.0 - p1.0)*(p2.0 - p1.0) + (p2.1 - p1.1)*(p2.1 - p1.1)).sqrt().max(f64::EPSILON);
+        
+        // Unit normal to the wall, scaled to half the wall thickness
+        let n = (-(p2.1 - p1.1) / length * WALL_THICKNESS/2.0, (p2.0 - p1.0) / length * WALL_THICKNESS/2.0);
+        
+        return [
+            (p1.0 + n.0, p1.1 + n.1),
+            (p2.0 + n.0, p2.1 + n.1),
+            (p2.0 - n.0, p2.1 - n.1),
+            (p1.0 - n.0, p1.1 - n.1)
+        ];
+    }
+    
+    /// Find the bounding box of a set of points
+    fn point_bounds(points: impl Iterator<Item = (f64, f64)>) -> ((f64, f64), (f64, f64)) {
+        let mut min = (f64::INFINITY, f64::INFINITY);
+        let mut max = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for (x, y) in points {
+            min = (min.0.min(x), min.1.min(y));
+            max = (max.0.max(x), max.1.max(y));
+        }
+        return (min, max);
+    }
+    
+    /// Return the mean and standard deviation of the pedestrians' target speeds
+    fn target_speed_mean_sd(crowd_sim: &CrowdSim) -> (f64, f64) {
+        return match crowd_sim.get_param_variation().target_speed {
+            Some(distribution) => distribution.mean_sd(),
+            None => {
+                let (min, max) = crowd_sim.get_params().target_speed_bounds;
+                ParamDistribution::Uniform { min, max }.mean_sd()
+            }
+        };
+    }
+    
+    /// Estimate how long the exported scenario needs to run for, in seconds
+    fn expected_duration(crowd_sim: &CrowdSim) -> f64 {
+        let params = crowd_sim.get_params();
+        let total_demand = crowd_sim.get_group_demand().iter().sum::<usize>();
+        let ((min_x, min_y), (max_x, max_y)) = crowd_sim.get_area().get_bounds();
+        
+        // Time to release everybody, plus time to walk across the environment at the slowest target speed
+        let crossing_time = ((max_x - min_x).powi(2) + (max_y - min_y).powi(2)).sqrt() / params.target_speed_bounds.0;
+        return (total_demand as f64) / crowd_sim.get_pedestrian_add_rate() + 2.0*crossing_time;
+    }
+    
+    
+    #[cfg(test)]
+    mod tests {
+        
+        #[test]
+        #[cfg(feature = "parquet")]
+        fn parquet_tables_read_back() {
+            use super::*;
+            use parquet::file::reader::{FileReader, SerializedFileReader};
+            use parquet::record::RowAccessor;
+            
+            let path = std::env::temp_dir().join(format!("pedestrian_export_test_{}.parquet", std::process::id()));
+            write_parquet(&path, "table", vec![
+                ("time", ParquetColumn::Double(vec![0.0, 0.5, 1.0])),
+                ("id", ParquetColumn::Int64(vec![3, 1, 2])),
+                ("cohorts", ParquetColumn::Text(vec![String::new(), String::from("distancing"), String::from("distancing;unfamiliar")]))
+            ]).unwrap();
+            
+            let reader = SerializedFileReader::try_from(path.as_path()).unwrap();
+            let schema = reader.metadata().file_metadata().schema();
+            assert_eq!(schema.name(), "table");
+            assert_eq!(schema.get_fields().iter().map(|field| field.name()).collect::<Vec<&str>>(), vec!["time", "id", "cohorts"]);
+            
+            let rows = reader.get_row_iter(None).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+            fs::remove_file(&path).unwrap();
+            assert_eq!(rows.len(), 3);
+            assert_eq!(rows[1].get_double(0).unwrap(), 0.5);
+            assert_eq!(rows[0].get_long(1).unwrap(), 3);
+            assert_eq!(rows[2].get_string(2).unwrap(), "distancing;unfamiliar");
+        }
+    }
+    
+}
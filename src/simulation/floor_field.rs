@@ -0,0 +1,243 @@
+pub mod floor_field {
+    
+    use std::cmp::Ordering;
+    use std::collections::BinaryHeap;
+    use std::f64::consts::SQRT_2;
+    
+    use crate::simulation::simulator::simulator::SimArea;
+    use crate::simulation::geom::geom::{Vec2, Zone};
+    
+    
+    /// The offsets of the 8 cells surrounding a cell, and the distance to each in cells
+    const NEIGHBOURS: [(isize, isize, f64); 8] = [
+        (1, 0, 1.0), (-1, 0, 1.0), (0, 1, 1.0), (0, -1, 1.0),
+        (1, 1, SQRT_2), (1, -1, SQRT_2), (-1, 1, SQRT_2), (-1, -1, SQRT_2)
+    ];
+    
+    
+    /// Precomputed walking distances to every end position over a grid covering the environment.
+    /// 
+    /// Pedestrians descend the distance field towards their destination, so the cost of navigation doesn't depend on the number of pedestrians.
+    pub struct FloorField {
+        /// The coordinates of the corner of the first cell
+        origin: Vec2,
+        /// The width and height of each cell, in metres
+        cell_size: f64,
+        /// The number of cells in the x and y directions
+        size: (usize, usize),
+        /// Whether each cell is too close to a wall or obstacle to walk through
+        blocked: Vec<bool>,
+        /// The walking distance from each cell to each end position, indexed by \[group]\[end position]\[cell]
+        distances: Vec<Vec<Vec<f64>>>,
+        /// The walking distance from each cell to each designated exit, indexed by \[exit]\[cell]
+        exit_distances: Vec<Vec<f64>>
+    }
+    
+    impl FloorField {
+        /// Compute the floor field for every end position and exit in an environment
+        /// 
+        /// * `door_states` - Whether each door in the area is open, since closed doors block the cells around them
+        /// * `cell_size` - The width and height of each cell, in metres
+        /// * `clearance` - Cells closer than this to a wall or obstacle can't be walked through, in metres. This should be at least the pedestrian radius.
+        pub fn new(area: &SimArea, door_states: &[bool], cell_size: f64, clearance: f64) -> FloorField {
+            let ((min_x, min_y), (max_x, max_y)) = area.get_bounds();
+            let origin = Vec2::new(min_x - cell_size, min_y - cell_size);
+            let size = (
+                ((max_x - min_x) / cell_size).ceil() as usize + 2,
+                ((max_y - min_y) / cell_size).ceil() as usize + 2
+            );
+            
+            let mut field = FloorField {
+                origin,
+                cell_size,
+                size,
+                blocked: Vec::new(),
+                distances: Vec::new(),
+                exit_distances: Vec::new()
+            };
+            
+            field.blocked = (0..size.0*size.1).map(|i| area.get_clearance(field.cell_centre(i), door_states) < clearance).collect();
+            
+            field.distances = area.end_zones.iter().map(|ends| {
+                ends.iter().map(|end| field.compute_distances(end)).collect()
+            }).collect();
+            field.exit_distances = area.exits.iter().map(|exit| field.compute_distances(&Zone::Point((*exit).into()))).collect();
+            
+            return field;
+        }
+        
+        /// Return the direction a pedestrian at a given position should walk in to reach an end position, as a unit vector
+        /// 
+        /// Returns None if the position is outside the field, or the end position can't be reached from it.
+        pub fn get_direction(&self, group: usize, end: usize, position: Vec2) -> Option<Vec2> {
+            return self.descend(&self.distances[group][end], position);
+        }
+        
+        /// Return the direction a pedestrian at a given position should walk in to reach a designated exit, as a unit vector
+        /// 
+        /// Returns None if the position is outside the field, or the exit can't be reached from it.
+        pub fn get_exit_direction(&self, exit: usize, position: Vec2) -> Option<Vec2> {
+            return self.descend(&self.exit_distances[exit], position);
+        }
+        
+        /// Return the downhill direction of a distance field at a given position, as a unit vector
+        fn descend(&self, distances: &[f64], position: Vec2) -> Option<Vec2> {
+            let (x, y) = self.cell_coords(position)?;
+            let current = distances[y*self.size.0 + x];
+            
+            // Weight each downhill neighbour by how far downhill it is, which gives a smoother direction than the single steepest neighbour
+            let mut direction = Vec2::default();
+            let mut best_neighbour: Option<(f64, Vec2)> = None;
+            for (dx, dy, step) in NEIGHBOURS {
+                if let Some(i) = self.offset_index((x, y), (dx, dy)) {
+                    let offset = Vec2::new(dx as f64, dy as f64) * (1.0 / step);
+                    
+                    if distances[i].is_finite() && current.is_finite() && distances[i] < current {
+                        direction = direction + offset * ((current - distances[i]) / step);
+                    }
+                    if distances[i].is_finite() && best_neighbour.is_none_or(|(d, _)| distances[i] < d) {
+                        best_neighbour = Some((distances[i], offset));
+                    }
+                }
+            }
+            
+            // If the pedestrian has been pushed into a blocked cell, head for the best neighbouring cell instead
+            if !current.is_finite() {
+                return best_neighbour.and_then(|(_, offset)| offset.normalised());
+            }
+            
+            return direction.normalised();
+        }
+        
+        /// Return the walking distance from a position to an end position, in metres (infinite if it can't be reached)
+        pub fn get_distance(&self, group: usize, end: usize, position: Vec2) -> f64 {
+            return match self.cell_coords(position) {
+                Some((x, y)) => self.distances[group][end][y*self.size.0 + x],
+                None => f64::INFINITY
+            };
+        }
+        
+        /// Return the walking distance from a position to a designated exit, in metres (infinite if it can't be reached)
+        pub fn get_exit_distance(&self, exit: usize, position: Vec2) -> f64 {
+            return match self.cell_coords(position) {
+                Some((x, y)) => self.exit_distances[exit][y*self.size.0 + x],
+                None => f64::INFINITY
+            };
+        }
+        
+        /// Compute the walking distance from every cell to the nearest part of a target zone with Dijkstra's algorithm
+        fn compute_distances(&self, target: &Zone) -> Vec<f64> {
+            let mut distances = vec![f64::INFINITY; self.size.0*self.size.1];
+            let mut open = BinaryHeap::new();
+            
+            // The target is always reachable from the cell containing its centre, even if it is close to a wall
+            if let Some((x, y)) = self.cell_coords(target.get_centre()) {
+                let i = y*self.size.0 + x;
+                distances[i] = target.get_distance(self.cell_centre(i));
+                open.push(OpenCell { index: i, distance: distances[i] });
+            }
+            // The rest of a line or area is reached from the open cells it touches
+            if !matches!(target, Zone::Point(_)) {
+                for (i, cell_distance) in distances.iter_mut().enumerate() {
+                    let distance = target.get_distance(self.cell_centre(i));
+                    if !self.blocked[i] && distance <= self.cell_size*SQRT_2/2.0 && distance < *cell_distance {
+                        *cell_distance = distance;
+                        open.push(OpenCell { index: i, distance });
+                    }
+                }
+            }
+            
+            while let Some(OpenCell { index, distance }) = open.pop() {
+                if distance > distances[index] {
+                    continue;
+                }
+                
+                let coords = (index % self.size.0, index / self.size.0);
+                for (dx, dy, step) in NEIGHBOURS {
+                    let neighbour = match self.offset_index(coords, (dx, dy)) {
+                        Some(neighbour) => neighbour,
+                        None => continue
+                    };
+                    if self.blocked[neighbour] {
+                        continue;
+                    }
+                    
+                    // Don't cut diagonally past the corner of a blocked cell
+                    if dx != 0 && dy != 0 {
+                        let side_1 = self.offset_index(coords, (dx, 0));
+                        let side_2 = self.offset_index(coords, (0, dy));
+                        if side_1.is_none_or(|i| self.blocked[i]) || side_2.is_none_or(|i| self.blocked[i]) {
+                            continue;
+                        }
+                    }
+                    
+                    let new_distance = distance + step*self.cell_size;
+                    if new_distance < distances[neighbour] {
+                        distances[neighbour] = new_distance;
+                        open.push(OpenCell { index: neighbour, distance: new_distance });
+                    }
+                }
+            }
+            
+            return distances;
+        }
+        
+        /// Return the coordinates of the centre of a cell
+        fn cell_centre(&self, index: usize) -> Vec2 {
+            let (x, y) = (index % self.size.0, index / self.size.0);
+            return self.origin + Vec2::new((x as f64 + 0.5) * self.cell_size, (y as f64 + 0.5) * self.cell_size);
+        }
+        
+        /// Return the (x, y) cell containing a position, or None if it is outside the field
+        fn cell_coords(&self, position: Vec2) -> Option<(usize, usize)> {
+            let x = ((position.x - self.origin.x) / self.cell_size).floor();
+            let y = ((position.y - self.origin.y) / self.cell_size).floor();
+            
+            if x < 0.0 || y < 0.0 || x >= self.size.0 as f64 || y >= self.size.1 as f64 {
+                return None;
+            }
+            
+            return Some((x as usize, y as usize));
+        }
+        
+        /// Return the index of the cell at an offset from another cell, or None if it is outside the field
+        fn offset_index(&self, coords: (usize, usize), offset: (isize, isize)) -> Option<usize> {
+            let x = coords.0.checked_add_signed(offset.0)?;
+            let y = coords.1.checked_add_signed(offset.1)?;
+            
+            if x >= self.size.0 || y >= self.size.1 {
+                return None;
+            }
+            
+            return Some(y*self.size.0 + x);
+        }
+    }
+    
+    /// A cell waiting to be explored by Dijkstra's algorithm, ordered so that the closest cell is explored first
+    struct OpenCell {
+        index: usize,
+        distance: f64
+    }
+    
+    impl PartialEq for OpenCell {
+        fn eq(&self, other: &OpenCell) -> bool {
+            return self.distance == other.distance;
+        }
+    }
+    
+    impl Eq for OpenCell {}
+    
+    impl PartialOrd for OpenCell {
+        fn partial_cmp(&self, other: &OpenCell) -> Option<Ordering> {
+            return Some(self.cmp(other));
+        }
+    }
+    
+    impl Ord for OpenCell {
+        fn cmp(&self, other: &OpenCell) -> Ordering {
+            // Reversed, because BinaryHeap is a max-heap
+            return other.distance.partial_cmp(&self.distance).unwrap_or(Ordering::Equal);
+        }
+    }
+    
+}
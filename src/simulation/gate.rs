@@ -0,0 +1,281 @@
+pub mod gate {
+    
+    use std::collections::{HashMap, HashSet};
+    use raylib::{drawing::{RaylibDrawHandle, RaylibDraw}, color::Color};
+    
+    use crate::simulation::pedestrian::pedestrian::Walker;
+    use crate::simulation::geom::geom::Vec2;
+    use crate::simulation::queue::queue::QUEUE_PLACE_RADIUS;
+    
+    
+    /// How close a pedestrian needs to get to a gate, on the side it is entered from, to wait for its turn there, in metres
+    const GATE_APPROACH_DISTANCE: f64 = 2.0;
+    
+    /// How far in front of a gate the pedestrians waiting for it stand, in metres
+    const GATE_HOLD_DISTANCE: f64 = 0.8;
+    
+    
+    const GATE_COLOUR: &str = "2E8B57";
+    
+    
+    /// A turnstile or ticket gate, which only lets one pedestrian through at a time.
+    /// 
+    /// Pedestrians that come close to the gate on the side it is entered from wait in front of it for their turn. The waiting pedestrian closest to the gate
+    /// steps into it, stands there for the service time (e.g. to tap a ticket) and then carries on to its destination, so a queue forms upstream of a busy gate.
+    /// Gates let pedestrians through in one direction only: pedestrians coming from the other side walk through freely. The walls either side of the gate
+    /// are added to the area separately.
+    pub struct TicketGate {
+        /// The middle of the gate, where pedestrians stand while they pass through
+        position: Vec2,
+        /// The direction pedestrians pass through the gate in (a unit vector)
+        direction: Vec2,
+        /// How long it takes each pedestrian to pass through the gate, in seconds
+        service_time: f64
+    }
+    
+    impl TicketGate {
+        /// * `position` - The middle of the gate, where pedestrians stand while they pass through
+        /// * `direction` - The direction pedestrians pass through the gate in
+        /// * `service_time` - How long it takes each pedestrian to pass through the gate, in seconds
+        pub fn new(position: (f64, f64), direction: (f64, f64), service_time: f64) -> TicketGate {
+            assert!(service_time >= 0.0, "A gate's service time can't be negative");
+            
+            return TicketGate {
+                position: position.into(),
+                direction: Vec2::from(direction).normalised().expect("A gate needs a direction to be passed through in"),
+                service_time
+            };
+        }
+        
+        /// Return the middle of the gate, where pedestrians stand while they pass through
+        pub fn get_position(&self) -> Vec2 {
+            return self.position;
+        }
+        
+        /// Return where the pedestrians waiting for the gate stand
+        pub fn get_hold_point(&self) -> Vec2 {
+            return self.position - self.direction * GATE_HOLD_DISTANCE;
+        }
+        
+        /// Return how long it takes each pedestrian to pass through the gate, in seconds
+        pub fn get_service_time(&self) -> f64 {
+            return self.service_time;
+        }
+        
+        /// Return whether a point is close to the gate, on the side it is entered from
+        pub fn is_approaching(&self, point: Vec2) -> bool {
+            let offset = point - self.position;
+            return offset.dot(self.direction) < 0.0 && offset.length() < GATE_APPROACH_DISTANCE;
+        }
+        
+        /// Draw the gate as a circle where pedestrians pass through, with a line pointing the way it is passed through
+        pub fn draw(&self, rl_handle: &mut RaylibDrawHandle, offset: (i32, i32), draw_scale: i32) {
+            let colour = Color::from_hex(GATE_COLOUR).unwrap();
+            let to_screen = |point: Vec2| (offset.0 + ((draw_scale as f64) * point.x) as i32, offset.1 + ((draw_scale as f64) * point.y) as i32);
+            
+            let (centre, hold) = (to_screen(self.position), to_screen(self.get_hold_point()));
+            rl_handle.draw_circle_lines(centre.0, centre.1, ((draw_scale as f64) * QUEUE_PLACE_RADIUS) as f32, colour);
+            rl_handle.draw_line(hold.0, hold.1, centre.0, centre.1, Color::fade(&colour, 0.6));
+        }
+    }
+    
+    /// How long one pedestrian took to get through a gate
+    #[derive(Clone, Copy, Debug)]
+    pub struct GatePassage {
+        /// The ID of the pedestrian (see `Walker::get_id`)
+        pub id: u64,
+        pub group: usize,
+        /// The simulation time the pedestrian started waiting for the gate, in seconds
+        pub arrived: f64,
+        /// The simulation time the pedestrian stepped into the gate, in seconds
+        pub service_start: f64,
+        /// The simulation time the pedestrian was let through, in seconds
+        pub left: f64
+    }
+    
+    impl GatePassage {
+        /// Return how long the pedestrian waited before stepping into the gate, in seconds
+        pub fn get_waiting_time(&self) -> f64 {
+            return self.service_start - self.arrived;
+        }
+    }
+    
+    /// The pedestrians waiting for a gate, and everyone that has been through it
+    #[derive(Default)]
+    pub struct GateState {
+        /// The simulation time each pedestrian waiting for the gate started waiting, by pedestrian ID
+        arrival_times: HashMap<u64, f64>,
+        /// The pedestrian in the gate and the simulation time it stepped in: (pedestrian ID, time) (time is None while it is still walking into the gate)
+        passing: Option<(u64, Option<f64>)>,
+        /// The IDs of the pedestrians that have been through the gate, which don't wait for it again
+        passed: HashSet<u64>,
+        /// Every pedestrian that has been through the gate, in the order they were let through
+        passages: Vec<GatePassage>,
+        /// The number of pedestrians waiting for the gate every time it changed: \[(simulation time, length)]
+        lengths: Vec<(f64, usize)>
+    }
+    
+    impl GateState {
+        /// Let pedestrians arriving at the gate wait for it, let the pedestrian in the gate through, and call the next waiting pedestrian into the gate
+        /// 
+        /// Pedestrians that are already waiting somewhere else, such as in a queue or for another gate, aren't held back by the gate.
+        /// 
+        /// * `time` - The current simulation time, in seconds
+        /// * `time_scale` - The length of the timestep, in seconds
+        pub fn update(&mut self, gate: &TicketGate, time: f64, time_scale: f64, pedestrians: &mut [Walker]) {
+            let length = self.get_queue_length();
+            let indices: HashMap<u64, usize> = pedestrians.iter().enumerate().map(|(i, ped)| (ped.get_id(), i)).collect();
+            
+            // Forget the pedestrians that have left the simulation while waiting
+            self.arrival_times.retain(|id, _| indices.contains_key(id));
+            self.passing = self.passing.filter(|(id, _)| indices.contains_key(id));
+            
+            // Let the pedestrian in the gate through once it has been served
+            if let Some((id, start)) = self.passing {
+                let ped = &mut pedestrians[indices[&id]];
+                match start {
+                    None => if Vec2::new(ped.x, ped.y).distance(gate.get_position()) < QUEUE_PLACE_RADIUS {
+                        self.passing = Some((id, Some(time)));
+                    },
+                    Some(start) if time + time_scale - start >= gate.get_service_time() => {
+                        ped.set_queue_place(None);
+                        self.passages.push(GatePassage {
+                            id,
+                            group: ped.get_group(),
+                            arrived: self.arrival_times.remove(&id).unwrap_or(start),
+                            service_start: start,
+                            left: time + time_scale
+                        });
+                        self.passed.insert(id);
+                        self.passing = None;
+                    },
+                    Some(_) => {}
+                }
+            }
+            
+            // Pedestrians arriving at the gate wait in front of it
+            for ped in pedestrians.iter_mut() {
+                let id = ped.get_id();
+                if self.passed.contains(&id) || self.arrival_times.contains_key(&id) || ped.get_queue_place().is_some() {
+                    continue;
+                }
+                
+                if gate.is_approaching(Vec2::new(ped.x, ped.y)) {
+                    self.arrival_times.insert(id, time);
+                    ped.set_queue_place(Some(gate.get_hold_point().into()));
+                }
+            }
+            
+            // The waiting pedestrian closest to the gate steps into it once it is free
+            if self.passing.is_none() {
+                let next = self.arrival_times.keys()
+                    .map(|id| (*id, Vec2::new(pedestrians[indices[id]].x, pedestrians[indices[id]].y).distance(gate.get_position())))
+                    .min_by(|a, b| a.1.total_cmp(&b.1));
+                if let Some((id, _)) = next {
+                    pedestrians[indices[&id]].set_queue_place(Some(gate.get_position().into()));
+                    self.passing = Some((id, None));
+                }
+            }
+            
+            if self.get_queue_length() != length {
+                self.lengths.push((time + time_scale, self.get_queue_length()));
+            }
+        }
+        
+        /// Return the number of pedestrians waiting for the gate at the moment, not counting the one in it
+        pub fn get_queue_length(&self) -> usize {
+            return self.arrival_times.len() - (self.passing.is_some() as usize);
+        }
+        
+        /// Return every pedestrian that has been through the gate, in the order they were let through
+        pub fn get_passages(&self) -> &[GatePassage] {
+            return &self.passages;
+        }
+        
+        /// Return the number of pedestrians waiting for the gate every time it changed: \[(simulation time, length)]
+        pub fn get_lengths(&self) -> &[(f64, usize)] {
+            return &self.lengths;
+        }
+        
+        /// Return the most pedestrians there have been waiting for the gate at once
+        pub fn get_peak_length(&self) -> usize {
+            return self.lengths.iter().map(|(_, length)| *length).max().unwrap_or(0);
+        }
+        
+        /// Return the mean time the pedestrians that have been through the gate waited before stepping into it, in seconds (None = nobody has been through)
+        pub fn get_mean_waiting_time(&self) -> Option<f64> {
+            if self.passages.is_empty() {
+                return None;
+            }
+            
+            let total: f64 = self.passages.iter().map(|passage| passage.get_waiting_time()).sum();
+            return Some(total / (self.passages.len() as f64));
+        }
+        
+        /// Return the number of pedestrians let through the gate per minute, between the first pedestrian arriving and the last one being let through (None = nobody has been through)
+        pub fn get_throughput(&self) -> Option<f64> {
+            let first = self.passages.iter().map(|passage| passage.arrived).fold(f64::INFINITY, f64::min);
+            let last = self.passages.last()?.left;
+            if last <= first {
+                return None;
+            }
+            
+            return Some(60.0 * (self.passages.len() as f64) / (last - first));
+        }
+    }
+    
+    
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::sync::Arc;
+        use crate::simulation::simulator::simulator::{CrowdSim, SimArea};
+        use crate::simulation::behaviour::behaviour::Etiquette;
+        
+        #[test]
+        fn gates_are_entered_from_one_side() {
+            let gate = TicketGate::new((5.0, 2.0), (2.0, 0.0), 1.0);
+            
+            assert!(gate.is_approaching(Vec2::new(4.0, 2.5)));
+            assert!(!gate.is_approaching(Vec2::new(6.0, 2.5)));
+            assert!(!gate.is_approaching(Vec2::new(2.5, 2.0)));
+            assert_eq!(gate.get_hold_point(), Vec2::new(5.0 - GATE_HOLD_DISTANCE, 2.0));
+        }
+        
+        #[test]
+        fn throughput_stays_within_the_service_time() {
+            const SERVICE_TIME: f64 = 2.0;
+            
+            // A corridor blocked by a barrier with a gate in the middle
+            let mut area = SimArea::new();
+            area.add_wall((-1.0,0.0), (11.0,0.0));
+            area.add_wall((-1.0,4.0), (11.0,4.0));
+            area.add_wall((5.0,0.0), (5.0,1.6));
+            area.add_wall((5.0,2.4), (5.0,4.0));
+            area.add_ticket_gate((5.0, 2.0), (1.0, 0.0), SERVICE_TIME);
+            area.add_start_end_group(vec![(0.0,1.0), (0.0,2.0), (0.0,3.0)], vec![(10.0,1.0), (10.0,2.0), (10.0,3.0)]);
+            
+            let mut crowd_sim = CrowdSim::new(Arc::new(area), 3.0);
+            crowd_sim.set_seed(0);
+            crowd_sim.set_run_limits(Some(120.0), None);
+            crowd_sim.add_pedestrian_set(12, 0, Arc::new(Etiquette::NoBias));
+            crowd_sim.simulate_full(0.1);
+            
+            let gate = crowd_sim.get_gate(0);
+            let passages = gate.get_passages();
+            assert_eq!(passages.len(), 12);
+            
+            // One pedestrian at a time, each taking at least the service time, so a queue builds up
+            for passage in passages {
+                assert!(passage.left - passage.service_start >= SERVICE_TIME - 1e-9);
+            }
+            for pair in passages.windows(2) {
+                assert!(pair[1].service_start >= pair[0].left - 1e-9);
+            }
+            assert!(gate.get_throughput().unwrap() <= 60.0 / SERVICE_TIME);
+            assert!(gate.get_peak_length() > 1);
+            assert!(gate.get_mean_waiting_time().unwrap() > 0.0);
+        }
+    }
+    
+}
@@ -0,0 +1,548 @@
+pub mod geom {
+    
+    use std::f64::consts::{PI, TAU};
+    use std::ops::{Add, Sub, Mul, Neg};
+    
+    
+    /// A 2D vector or point, in metres. Note: the y-axis increases downward, so angles increase clockwise.
+    #[derive(Clone, Copy, Debug, Default, PartialEq)]
+    pub struct Vec2 {
+        pub x: f64,
+        pub y: f64
+    }
+    
+    impl Vec2 {
+        pub fn new(x: f64, y: f64) -> Vec2 {
+            Vec2 {
+                x, y
+            }
+        }
+        
+        /// Create a unit vector pointing in the given direction
+        /// 
+        /// * `angle` - Angle in radians
+        pub fn from_angle(angle: f64) -> Vec2 {
+            return Vec2::new(angle.cos(), angle.sin());
+        }
+        
+        /// Return the dot product of this vector and another
+        pub fn dot(self, other: Vec2) -> f64 {
+            return self.x*other.x + self.y*other.y;
+        }
+        
+        /// Return the square of the length of this vector
+        pub fn length_sq(self) -> f64 {
+            return self.dot(self);
+        }
+        
+        /// Return the length of this vector
+        pub fn length(self) -> f64 {
+            return self.length_sq().sqrt();
+        }
+        
+        /// Return the distance between this point and another
+        pub fn distance(self, other: Vec2) -> f64 {
+            return (other - self).length();
+        }
+        
+        /// Return the direction this vector points in, in radians between -π and π
+        pub fn angle(self) -> f64 {
+            return self.y.atan2(self.x);
+        }
+        
+        /// Return a vector with the same direction and a length of 1, or None if this vector has no length
+        pub fn normalised(self) -> Option<Vec2> {
+            let length = self.length();
+            
+            if length == 0.0 {
+                return None;
+            }
+            
+            return Some(self * (1.0 / length));
+        }
+    }
+    
+    impl Add for Vec2 {
+        type Output = Vec2;
+        
+        fn add(self, other: Vec2) -> Vec2 {
+            return Vec2::new(self.x + other.x, self.y + other.y);
+        }
+    }
+    
+    impl Sub for Vec2 {
+        type Output = Vec2;
+        
+        fn sub(self, other: Vec2) -> Vec2 {
+            return Vec2::new(self.x - other.x, self.y - other.y);
+        }
+    }
+    
+    impl Mul<f64> for Vec2 {
+        type Output = Vec2;
+        
+        fn mul(self, k: f64) -> Vec2 {
+            return Vec2::new(self.x * k, self.y * k);
+        }
+    }
+    
+    impl Neg for Vec2 {
+        type Output = Vec2;
+        
+        fn neg(self) -> Vec2 {
+            return Vec2::new(-self.x, -self.y);
+        }
+    }
+    
+    impl From<(f64, f64)> for Vec2 {
+        fn from(point: (f64, f64)) -> Vec2 {
+            return Vec2::new(point.0, point.1);
+        }
+    }
+    
+    impl From<Vec2> for (f64, f64) {
+        fn from(v: Vec2) -> (f64, f64) {
+            return (v.x, v.y);
+        }
+    }
+    
+    
+    /// Constrain an angle to be between 0 and 2π
+    pub fn normalise_angle(angle: f64) -> f64 {
+        let normalised = angle.rem_euclid(TAU);
+        
+        // rem_euclid can round up to exactly 2π for tiny negative angles
+        if normalised >= TAU {
+            return 0.0;
+        }
+        
+        return normalised;
+    }
+    
+    /// Constrain an angle to be between -π and π
+    pub fn wrap_angle(angle: f64) -> f64 {
+        return normalise_angle(angle + PI) - PI;
+    }
+    
+    /// Given an input angle and a target angle, move the input angle so that it is closer to the target angle
+    /// 
+    /// * `initial_angle` - Angle in radians
+    /// * `target_angle` - Angle in radians
+    /// * `nudge_ratio` - A multiplier for how much the angle is changed (change in angle = diff(target_angle, initial_angle) * nudge_ratio)
+    /// 
+    /// Returns an angle between 0 and 2π
+    pub fn nudge_angle(initial_angle: f64, target_angle: f64, nudge_ratio: f64) -> f64 {
+        
+        // The difference between the initial and target angles, the short way around
+        let angle_diff = wrap_angle(initial_angle - target_angle);
+        
+        // Return the new angle
+        return normalise_angle(initial_angle - angle_diff*nudge_ratio);
+    }
+    
+    /// Given a line segment AB and a point P, determine the vector that points from the closest point on the segment to P
+    /// 
+    /// Output form: (distance, normal vector). The normal vector has a length equal to the distance, and is zero if P is on the segment.
+    pub fn segment_normal_vector(a: Vec2, b: Vec2, p: Vec2) -> (f64, Vec2) {
+        let ap = p - a;
+        let ab = b - a;
+        
+        // Scalar projection of AP onto AB, limited to the ends of the segment
+        let ab_length_sq = ab.length_sq();
+        let t = if ab_length_sq == 0.0 {0.0} else {(ap.dot(ab) / ab_length_sq).clamp(0.0, 1.0)};
+        
+        // The point on the segment closest to P
+        let closest_point = a + ab*t;
+        
+        // normal_vec is the vector that points from closest_point on the line to P
+        let normal_vec = p - closest_point;
+        
+        return (normal_vec.length(), normal_vec);
+    }
+    
+    /// Given a circle moving in a straight line from `start` to `end`, find how far along its path it first touches line segment AB
+    /// 
+    /// Returns the fraction of the path travelled (from 0 to 1), or None if the circle doesn't touch the segment on the way.
+    /// A circle that is already touching the segment at the start touches it straight away (0) if it is moving further towards it.
+    pub fn swept_circle_segment(start: Vec2, end: Vec2, radius: f64, a: Vec2, b: Vec2) -> Option<f64> {
+        let (dist, normal) = segment_normal_vector(a, b, start);
+        if dist <= radius {
+            return if (end - start).dot(normal) < 0.0 {Some(0.0)} else {None};
+        }
+        
+        // The circle touches the segment when its centre reaches the capsule around the segment: either one of its flat sides, or one of the rounded ends
+        let mut first_contact = [swept_circle_circle(start, end, radius, a, 0.0), swept_circle_circle(start, end, radius, b, 0.0)]
+            .into_iter().flatten().fold(f64::INFINITY, f64::min);
+        
+        let ab = b - a;
+        let movement = end - start;
+        if let Some(direction) = ab.normalised() {
+            let normal = Vec2::new(-direction.y, direction.x);
+            let side = (start - a).dot(normal);
+            let approach = movement.dot(normal);
+            
+            // Only moving towards the segment can reach the flat side facing the start
+            if approach * side < 0.0 {
+                let t = (side - radius * side.signum()) / -approach;
+                let along = (start + movement*t - a).dot(direction);
+                if (0.0..=1.0).contains(&t) && along >= 0.0 && along <= ab.length() {
+                    first_contact = first_contact.min(t);
+                }
+            }
+        }
+        
+        return if first_contact.is_finite() {Some(first_contact)} else {None};
+    }
+    
+    /// Given a circle moving in a straight line from `start` to `end`, find how far along its path it first touches a stationary circle
+    /// 
+    /// Returns the fraction of the path travelled (from 0 to 1), or None if the circles don't touch on the way.
+    /// Circles that are already touching at the start touch straight away (0) if they are moving further together.
+    pub fn swept_circle_circle(start: Vec2, end: Vec2, radius: f64, centre: Vec2, other_radius: f64) -> Option<f64> {
+        let movement = end - start;
+        let from_centre = start - centre;
+        let contact_distance = radius + other_radius;
+        
+        // Solve |from_centre + movement*t| = contact_distance for the first t
+        let a = movement.length_sq();
+        let b = 2.0 * from_centre.dot(movement);
+        let c = from_centre.length_sq() - contact_distance*contact_distance;
+        if c <= 0.0 {
+            return if b < 0.0 {Some(0.0)} else {None};
+        }
+        if a == 0.0 {
+            return None;
+        }
+        
+        let discriminant = b*b - 4.0*a*c;
+        if discriminant < 0.0 {
+            return None;
+        }
+        
+        let t = (-b - discriminant.sqrt()) / (2.0 * a);
+        return if (0.0..=1.0).contains(&t) {Some(t)} else {None};
+    }
+    
+    /// Return the signed area enclosed by a polygon, given its corners in order: positive if they go clockwise (since the y-axis increases downward), negative if anticlockwise
+    fn signed_polygon_area(polygon: &[Vec2]) -> f64 {
+        let twice_area = polygon.iter().zip(polygon.iter().cycle().skip(1)).map(|(a, b)| a.x*b.y - b.x*a.y).sum::<f64>();
+        return twice_area / 2.0;
+    }
+    
+    /// Return the area enclosed by a polygon, given its corners in order (in either direction)
+    pub fn polygon_area(polygon: &[Vec2]) -> f64 {
+        return signed_polygon_area(polygon).abs();
+    }
+    
+    /// Check whether a point is inside a polygon, given its corners in order
+    pub fn polygon_contains(polygon: &[Vec2], p: Vec2) -> bool {
+        // Count how many edges a ray from the point in the +x direction crosses
+        let mut inside = false;
+        for (a, b) in polygon.iter().zip(polygon.iter().cycle().skip(1)) {
+            if (a.y > p.y) != (b.y > p.y) && p.x < a.x + (p.y - a.y) * (b.x - a.x) / (b.y - a.y) {
+                inside = !inside;
+            }
+        }
+        return inside;
+    }
+    
+    /// Cut away the part of a polygon on one side of a line (one step of the Sutherland-Hodgman algorithm)
+    /// 
+    /// * `point` - Any point on the line
+    /// * `normal` - A vector perpendicular to the line, pointing towards the side that is cut away
+    pub fn clip_polygon(polygon: &[Vec2], point: Vec2, normal: Vec2) -> Vec<Vec2> {
+        let mut clipped = Vec::new();
+        for (a, b) in polygon.iter().zip(polygon.iter().cycle().skip(1)) {
+            // Signed distances along the normal: corners with a positive distance are cut away
+            let dist_a = (*a - point).dot(normal);
+            let dist_b = (*b - point).dot(normal);
+            
+            if dist_a <= 0.0 {
+                clipped.push(*a);
+            }
+            if (dist_a < 0.0 && dist_b > 0.0) || (dist_a > 0.0 && dist_b < 0.0) {
+                clipped.push(*a + (*b - *a) * (dist_a / (dist_a - dist_b)));
+            }
+        }
+        return clipped;
+    }
+    
+    /// Return the part of a polygon that is inside a convex polygon (both given by their corners in order)
+    /// 
+    /// The first polygon doesn't need to be convex, but if it isn't, the result may include edges of zero width joining its separate parts. These don't change its area.
+    pub fn intersect_convex_polygon(polygon: &[Vec2], convex: &[Vec2]) -> Vec<Vec2> {
+        // The side of each edge that is outside the convex polygon depends on the direction its corners go in
+        let outward = if signed_polygon_area(convex) > 0.0 {1.0} else {-1.0};
+        
+        let mut clipped = polygon.to_vec();
+        for (a, b) in convex.iter().zip(convex.iter().cycle().skip(1)) {
+            if clipped.is_empty() {
+                break;
+            }
+            let edge = *b - *a;
+            clipped = clip_polygon(&clipped, *a, Vec2::new(edge.y, -edge.x) * outward);
+        }
+        return clipped;
+    }
+    
+    /// A place where pedestrians start or finish: a single point, or a line segment or rectangle that they are spread across
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum Zone {
+        Point(Vec2),
+        /// A line segment between two end points, such as a doorway
+        Segment(Vec2, Vec2),
+        /// A rectangle with sides parallel to the axes, between two opposite corners
+        Rectangle(Vec2, Vec2)
+    }
+    
+    impl Zone {
+        pub fn get_centre(&self) -> Vec2 {
+            return self.get_point((0.5, 0.5));
+        }
+        
+        /// Return a point in the zone, chosen by two numbers from 0 to 1 (e.g. (0.5, 0.5) is the centre).
+        /// 
+        /// Uniformly distributed numbers give points uniformly distributed over the zone. A segment only uses the first number.
+        pub fn get_point(&self, position: (f64, f64)) -> Vec2 {
+            return match *self {
+                Zone::Point(point) => point,
+                Zone::Segment(a, b) => a + (b - a)*position.0,
+                Zone::Rectangle(a, b) => Vec2::new(a.x + (b.x - a.x)*position.0, a.y + (b.y - a.y)*position.1)
+            };
+        }
+        
+        /// Return the distance from a point to the closest point in the zone (0 if the point is inside it)
+        pub fn get_distance(&self, p: Vec2) -> f64 {
+            return match *self {
+                Zone::Point(point) => point.distance(p),
+                Zone::Segment(a, b) => segment_normal_vector(a, b, p).0,
+                Zone::Rectangle(a, b) => {
+                    let dx = (a.x.min(b.x) - p.x).max(p.x - a.x.max(b.x)).max(0.0);
+                    let dy = (a.y.min(b.y) - p.y).max(p.y - a.y.max(b.y)).max(0.0);
+                    (dx*dx + dy*dy).sqrt()
+                }
+            };
+        }
+    }
+    
+    /// Check whether line segments PQ and AB cross each other
+    pub fn segments_intersect(p: Vec2, q: Vec2, a: Vec2, b: Vec2) -> bool {
+        // The z-component of the cross product of OA and OB
+        fn cross(o: Vec2, a: Vec2, b: Vec2) -> f64 {
+            return (a.x - o.x)*(b.y - o.y) - (a.y - o.y)*(b.x - o.x);
+        }
+        
+        let d1 = cross(a, b, p);
+        let d2 = cross(a, b, q);
+        let d3 = cross(p, q, a);
+        let d4 = cross(p, q, b);
+        
+        return ((d1 > 0.0 && d2 < 0.0) || (d1 < 0.0 && d2 > 0.0)) && ((d3 > 0.0 && d4 < 0.0) || (d3 < 0.0 && d4 > 0.0));
+    }
+    
+    
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        
+        const EPSILON: f64 = 1e-9;
+        
+        fn assert_close(a: f64, b: f64) {
+            assert!((a - b).abs() < EPSILON, "{} != {}", a, b);
+        }
+        
+        #[test]
+        fn vector_arithmetic() {
+            let a = Vec2::new(1.0, 2.0);
+            let b = Vec2::new(3.0, -1.0);
+            
+            assert_eq!(a + b, Vec2::new(4.0, 1.0));
+            assert_eq!(a - b, Vec2::new(-2.0, 3.0));
+            assert_eq!(a * 2.0, Vec2::new(2.0, 4.0));
+            assert_eq!(-a, Vec2::new(-1.0, -2.0));
+            assert_close(a.dot(b), 1.0);
+            assert_close(Vec2::new(3.0, 4.0).length(), 5.0);
+            assert_close(a.distance(b), 13.0_f64.sqrt());
+        }
+        
+        #[test]
+        fn vector_direction() {
+            assert_close(Vec2::from_angle(PI/2.0).x, 0.0);
+            assert_close(Vec2::from_angle(PI/2.0).y, 1.0);
+            assert_close(Vec2::new(-1.0, 0.0).angle(), PI);
+            
+            let unit = Vec2::new(3.0, 4.0).normalised().unwrap();
+            assert_close(unit.length(), 1.0);
+            assert_close(unit.x, 0.6);
+            assert!(Vec2::new(0.0, 0.0).normalised().is_none());
+        }
+        
+        #[test]
+        fn tuple_conversion() {
+            let v: Vec2 = (1.5, -2.0).into();
+            assert_eq!(v, Vec2::new(1.5, -2.0));
+            assert_eq!(<(f64, f64)>::from(v), (1.5, -2.0));
+        }
+        
+        #[test]
+        fn angle_normalisation() {
+            assert_close(normalise_angle(0.0), 0.0);
+            assert_close(normalise_angle(-PI/2.0), 3.0*PI/2.0);
+            assert_close(normalise_angle(5.0*PI), PI);
+            assert_close(normalise_angle(-3.0*TAU + 1.0), 1.0);
+            assert!(normalise_angle(-1e-20) < TAU);
+            
+            assert_close(wrap_angle(3.0*PI/2.0), -PI/2.0);
+            assert_close(wrap_angle(-3.0*PI/2.0), PI/2.0);
+            assert_close(wrap_angle(0.25), 0.25);
+        }
+        
+        #[test]
+        fn nudge_takes_the_short_way_around() {
+            // From just above 0 towards just below 2π should decrease through 0, not increase through π
+            let nudged = nudge_angle(0.1, TAU - 0.1, 0.5);
+            assert_close(nudged, 0.0);
+            
+            let nudged = nudge_angle(TAU - 0.1, 0.3, 0.5);
+            assert_close(nudged, 0.1);
+            
+            // A ratio of 1 reaches the target, and a ratio of 0 doesn't move
+            assert_close(nudge_angle(1.0, 2.0, 1.0), 2.0);
+            assert_close(nudge_angle(1.0, 2.0, 0.0), 1.0);
+            
+            // Targets outside 0 to 2π are accepted
+            assert_close(nudge_angle(PI/2.0, -PI/2.0 + 0.2, 1.0), 3.0*PI/2.0 + 0.2);
+        }
+        
+        #[test]
+        fn segment_distance() {
+            let a = Vec2::new(0.0, 0.0);
+            let b = Vec2::new(4.0, 0.0);
+            
+            // Beside the middle of the segment
+            let (dist, normal) = segment_normal_vector(a, b, Vec2::new(2.0, 3.0));
+            assert_close(dist, 3.0);
+            assert_eq!(normal, Vec2::new(0.0, 3.0));
+            
+            // Past either end, the closest point is the end point
+            let (dist, normal) = segment_normal_vector(a, b, Vec2::new(7.0, 4.0));
+            assert_close(dist, 5.0);
+            assert_eq!(normal, Vec2::new(3.0, 4.0));
+            let (dist, _) = segment_normal_vector(a, b, Vec2::new(-1.0, 0.0));
+            assert_close(dist, 1.0);
+            
+            // On the segment
+            let (dist, normal) = segment_normal_vector(a, b, Vec2::new(1.0, 0.0));
+            assert_close(dist, 0.0);
+            assert_eq!(normal, Vec2::new(0.0, 0.0));
+            
+            // A vertical segment
+            let (dist, normal) = segment_normal_vector(Vec2::new(1.0, -1.0), Vec2::new(1.0, 1.0), Vec2::new(-1.0, 0.5));
+            assert_close(dist, 2.0);
+            assert_eq!(normal, Vec2::new(-2.0, 0.0));
+            
+            // A segment with no length behaves like a point
+            let (dist, _) = segment_normal_vector(a, a, Vec2::new(0.0, 2.0));
+            assert_close(dist, 2.0);
+        }
+        
+        #[test]
+        fn swept_contact() {
+            let a = Vec2::new(0.0, 0.0);
+            let b = Vec2::new(4.0, 0.0);
+            
+            // Moving straight through the middle of the segment, the circle touches it one radius before reaching it
+            let t = swept_circle_segment(Vec2::new(2.0, 2.0), Vec2::new(2.0, -2.0), 0.5, a, b).unwrap();
+            assert_close(t, 0.375);
+            
+            // Passing the end of the segment, the circle touches the end point
+            let t = swept_circle_segment(Vec2::new(5.0, 2.0), Vec2::new(5.0, -2.0), 1.5, a, b).unwrap();
+            assert_close(Vec2::new(5.0, 2.0 - 4.0*t).distance(b), 1.5);
+            
+            // Moving parallel, moving away or stopping short doesn't count
+            assert!(swept_circle_segment(Vec2::new(0.0, 1.0), Vec2::new(4.0, 1.0), 0.5, a, b).is_none());
+            assert!(swept_circle_segment(Vec2::new(2.0, 1.0), Vec2::new(2.0, 3.0), 0.5, a, b).is_none());
+            assert!(swept_circle_segment(Vec2::new(2.0, 2.0), Vec2::new(2.0, 1.0), 0.5, a, b).is_none());
+            
+            // Already touching, it only counts if moving further in
+            assert_eq!(swept_circle_segment(Vec2::new(2.0, 0.4), Vec2::new(2.0, -2.0), 0.5, a, b), Some(0.0));
+            assert!(swept_circle_segment(Vec2::new(2.0, 0.4), Vec2::new(2.0, 2.0), 0.5, a, b).is_none());
+            
+            // Between circles, the centres are the sum of the radii apart at contact
+            let t = swept_circle_circle(Vec2::new(-3.0, 0.0), Vec2::new(3.0, 0.0), 0.5, a, 1.0).unwrap();
+            assert_close(t, 0.25);
+            assert!(swept_circle_circle(Vec2::new(-3.0, 2.0), Vec2::new(3.0, 2.0), 0.5, a, 1.0).is_none());
+            assert_eq!(swept_circle_circle(Vec2::new(-1.0, 0.0), Vec2::new(3.0, 0.0), 0.5, a, 1.0), Some(0.0));
+        }
+        
+        #[test]
+        fn polygon_measurements() {
+            let square = [Vec2::new(0.0, 0.0), Vec2::new(2.0, 0.0), Vec2::new(2.0, 2.0), Vec2::new(0.0, 2.0)];
+            let reversed = [square[3], square[2], square[1], square[0]];
+            assert_close(polygon_area(&square), 4.0);
+            assert_close(polygon_area(&reversed), 4.0);
+            
+            // An L shape, which isn't convex
+            let l_shape = [Vec2::new(0.0, 0.0), Vec2::new(2.0, 0.0), Vec2::new(2.0, 1.0), Vec2::new(1.0, 1.0), Vec2::new(1.0, 2.0), Vec2::new(0.0, 2.0)];
+            assert_close(polygon_area(&l_shape), 3.0);
+            assert!(polygon_contains(&l_shape, Vec2::new(0.5, 1.5)));
+            assert!(!polygon_contains(&l_shape, Vec2::new(1.5, 1.5)));
+            assert!(!polygon_contains(&l_shape, Vec2::new(-0.5, 0.5)));
+        }
+        
+        #[test]
+        fn polygon_clipping() {
+            let square = [Vec2::new(0.0, 0.0), Vec2::new(2.0, 0.0), Vec2::new(2.0, 2.0), Vec2::new(0.0, 2.0)];
+            
+            // Cut away everything right of x = 0.5
+            let clipped = clip_polygon(&square, Vec2::new(0.5, 0.0), Vec2::new(1.0, 0.0));
+            assert_close(polygon_area(&clipped), 1.0);
+            
+            // Cutting along a diagonal leaves a triangle
+            let clipped = clip_polygon(&square, Vec2::new(0.0, 0.0), Vec2::new(1.0, -1.0));
+            assert_close(polygon_area(&clipped), 2.0);
+            
+            // Overlapping squares, with the convex one in either direction
+            let offset = [Vec2::new(1.0, 1.0), Vec2::new(3.0, 1.0), Vec2::new(3.0, 3.0), Vec2::new(1.0, 3.0)];
+            let offset_reversed = [offset[3], offset[2], offset[1], offset[0]];
+            assert_close(polygon_area(&intersect_convex_polygon(&square, &offset)), 1.0);
+            assert_close(polygon_area(&intersect_convex_polygon(&square, &offset_reversed)), 1.0);
+            
+            // A non-convex polygon clipped by a convex one
+            let l_shape = [Vec2::new(0.0, 0.0), Vec2::new(2.0, 0.0), Vec2::new(2.0, 1.0), Vec2::new(1.0, 1.0), Vec2::new(1.0, 2.0), Vec2::new(0.0, 2.0)];
+            let right_half = [Vec2::new(0.5, -1.0), Vec2::new(3.0, -1.0), Vec2::new(3.0, 3.0), Vec2::new(0.5, 3.0)];
+            assert_close(polygon_area(&intersect_convex_polygon(&l_shape, &right_half)), 2.0);
+            
+            // No overlap
+            let far = [Vec2::new(5.0, 5.0), Vec2::new(6.0, 5.0), Vec2::new(6.0, 6.0)];
+            assert!(intersect_convex_polygon(&square, &far).is_empty());
+        }
+        
+        #[test]
+        fn zone_points() {
+            let point = Zone::Point(Vec2::new(1.0, 2.0));
+            assert_eq!(point.get_point((0.9, 0.1)), Vec2::new(1.0, 2.0));
+            
+            let segment = Zone::Segment(Vec2::new(0.0, 0.0), Vec2::new(4.0, 2.0));
+            assert_eq!(segment.get_centre(), Vec2::new(2.0, 1.0));
+            assert_eq!(segment.get_point((0.25, 0.9)), Vec2::new(1.0, 0.5));
+            
+            // The corners can be given in any order
+            let rectangle = Zone::Rectangle(Vec2::new(4.0, 0.0), Vec2::new(0.0, 2.0));
+            assert_eq!(rectangle.get_point((0.0, 0.0)), Vec2::new(4.0, 0.0));
+            assert_eq!(rectangle.get_point((0.5, 0.25)), Vec2::new(2.0, 0.5));
+        }
+        
+        #[test]
+        fn zone_distance() {
+            assert_close(Zone::Point(Vec2::new(0.0, 0.0)).get_distance(Vec2::new(3.0, 4.0)), 5.0);
+            assert_close(Zone::Segment(Vec2::new(0.0, 0.0), Vec2::new(0.0, 4.0)).get_distance(Vec2::new(2.0, 1.0)), 2.0);
+            
+            let rectangle = Zone::Rectangle(Vec2::new(4.0, 0.0), Vec2::new(0.0, 2.0));
+            assert_close(rectangle.get_distance(Vec2::new(1.0, 1.0)), 0.0);
+            assert_close(rectangle.get_distance(Vec2::new(2.0, 5.0)), 3.0);
+            assert_close(rectangle.get_distance(Vec2::new(7.0, 6.0)), 5.0);
+        }
+    }
+    
+}
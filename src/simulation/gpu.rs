@@ -0,0 +1,400 @@
+pub mod gpu {
+    
+    use wgpu::util::DeviceExt;
+    
+    use crate::simulation::neighbours::neighbours::NeighbourState;
+    
+    
+    /// The most neighbours each pedestrian perceives in a timestep (this must match the size of `distances` in the shader).
+    /// When more are within the cutoff, the closest ones are kept, so in a dense crowd a long cutoff leaves out neighbours the CPU would pass on.
+    const MAX_GPU_NEIGHBOURS: usize = 64;
+    
+    /// The number of pedestrians each workgroup of the compute shader handles (this must match `@workgroup_size` in the shader)
+    const WORKGROUP_SIZE: usize = 64;
+    
+    /// The compute shader, which handles one pedestrian per invocation: it finds the neighbours the pedestrian perceives, and adds up the contact impulse from every neighbour it overlaps.
+    /// The impulse is the same as `Walker::apply_contact_forces`, but uses the pedestrian's velocity from the start of the timestep.
+    const SHADER: &str = r#"
+        struct Pedestrian {
+            x: f32,
+            y: f32,
+            direction: f32,
+            radius: f32,
+            speed: f32,
+            stiffness: f32,
+            friction: f32,
+            padding: f32,
+        }
+        
+        struct Params {
+            count: u32,
+            max_neighbours: u32,
+            cutoff: f32,
+            time_scale: f32,
+        }
+        
+        @group(0) @binding(0) var<storage, read> pedestrians: array<Pedestrian>;
+        @group(0) @binding(1) var<uniform> params: Params;
+        @group(0) @binding(2) var<storage, read_write> neighbour_counts: array<u32>;
+        @group(0) @binding(3) var<storage, read_write> neighbour_indices: array<u32>;
+        @group(0) @binding(4) var<storage, read_write> impulses: array<vec2<f32>>;
+        
+        @compute @workgroup_size(64)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            let i = id.x;
+            if (i >= params.count) {
+                return;
+            }
+            
+            let me = pedestrians[i];
+            let position = vec2<f32>(me.x, me.y);
+            let velocity = vec2<f32>(cos(me.direction), sin(me.direction)) * me.speed;
+            let first = i * params.max_neighbours;
+            
+            var distances: array<f32, 64>;
+            var count = 0u;
+            var impulse = vec2<f32>(0.0, 0.0);
+            for (var j = 0u; j < params.count; j++) {
+                if (j == i) {
+                    continue;
+                }
+                
+                let other = pedestrians[j];
+                let offset = position - vec2<f32>(other.x, other.y);
+                let distance = length(offset);
+                
+                // Perception: keep the closest neighbours within the cutoff, replacing the furthest one kept once the list is full
+                if (distance <= params.cutoff) {
+                    if (count < params.max_neighbours) {
+                        neighbour_indices[first + count] = j;
+                        distances[count] = distance;
+                        count++;
+                    } else {
+                        var furthest = 0u;
+                        for (var k = 1u; k < count; k++) {
+                            if (distances[k] > distances[furthest]) {
+                                furthest = k;
+                            }
+                        }
+                        if (distance < distances[furthest]) {
+                            neighbour_indices[first + furthest] = j;
+                            distances[furthest] = distance;
+                        }
+                    }
+                }
+                
+                // Repulsion: the granular contact impulse from every neighbour the pedestrian overlaps
+                let overlap = me.radius + other.radius - distance;
+                if (overlap > 0.0) {
+                    var normal = vec2<f32>(1.0, 0.0);
+                    if (distance > 0.0) {
+                        normal = offset / distance;
+                    }
+                    let tangent = vec2<f32>(-normal.y, normal.x);
+                    let sliding = dot(vec2<f32>(cos(other.direction), sin(other.direction)) * other.speed - velocity, tangent);
+                    let friction_fraction = min(me.friction * overlap * params.time_scale, 1.0);
+                    impulse += normal * (me.stiffness * overlap * params.time_scale) + tangent * (sliding * friction_fraction);
+                }
+            }
+            
+            neighbour_counts[i] = count;
+            impulses[i] = impulse;
+        }
+    "#;
+    
+    
+    /// One pedestrian as the compute shader sees it (matches `Pedestrian` in the shader)
+    #[repr(C)]
+    #[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+    struct GpuPedestrian {
+        x: f32,
+        y: f32,
+        direction: f32,
+        radius: f32,
+        speed: f32,
+        stiffness: f32,
+        friction: f32,
+        padding: f32
+    }
+    
+    /// The values shared by every invocation of the compute shader (matches `Params` in the shader)
+    #[repr(C)]
+    #[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+    struct GpuParams {
+        count: u32,
+        max_neighbours: u32,
+        cutoff: f32,
+        time_scale: f32
+    }
+    
+    /// The buffers the compute shader reads and writes, sized for a number of pedestrians
+    struct GpuBuffers {
+        /// The number of pedestrians the buffers have room for
+        capacity: usize,
+        pedestrians: wgpu::Buffer,
+        params: wgpu::Buffer,
+        neighbour_counts: wgpu::Buffer,
+        neighbour_indices: wgpu::Buffer,
+        impulses: wgpu::Buffer,
+        /// Copies of the three output buffers that can be read back on the CPU
+        readback_counts: wgpu::Buffer,
+        readback_indices: wgpu::Buffer,
+        readback_impulses: wgpu::Buffer,
+        bind_group: wgpu::BindGroup
+    }
+    
+    /// A compute-shader backend for the pairwise part of each timestep, for very large crowds.
+    /// 
+    /// Every timestep, the positions of the walking pedestrians are uploaded and each pedestrian is compared with every other one on the GPU.
+    /// Two things are read back: the neighbours each pedestrian perceives (those within the cutoff, only the closest MAX_GPU_NEIGHBOURS if there are more),
+    /// which are passed to the steering rules in place of the full list of pedestrians, and the contact impulse from the neighbours it overlaps,
+    /// which replaces `Walker::apply_contact_forces` for pedestrians using the granular contact model.
+    /// The shader leaves the neighbours in no particular order, so they are put back in the order of the simulation's list first, as `NeighbourOrder` expects.
+    /// The impulses use each pedestrian's walking velocity from the start of the timestep, without its push velocity, where the CPU uses the velocity
+    /// the earlier steering rules have left it with, so sliding friction comes out slightly different.
+    /// The shader works in single precision, so results differ slightly from the CPU.
+    pub struct GpuNeighbours {
+        /// The furthest a pedestrian perceives its neighbours, in metres
+        cutoff: f64,
+        
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        pipeline: wgpu::ComputePipeline,
+        bind_group_layout: wgpu::BindGroupLayout,
+        /// The buffers used by the last timestep (None = nothing has been uploaded yet)
+        buffers: Option<GpuBuffers>,
+        
+        /// The number of neighbours each pedestrian perceives, read back from the last timestep
+        neighbour_counts: Vec<u32>,
+        /// The indices of the neighbours each pedestrian perceives, read back from the last timestep, in blocks of MAX_GPU_NEIGHBOURS
+        neighbour_indices: Vec<u32>,
+        /// The contact impulse on each pedestrian, read back from the last timestep
+        impulses: Vec<(f64, f64)>,
+        
+        /// Scratch space for the neighbours of one pedestrian, split into those before & after it in the simulation's list: \[(x, y, direction, radius, speed)]
+        before: Vec<NeighbourState>,
+        after: Vec<NeighbourState>
+    }
+    
+    impl GpuNeighbours {
+        /// Set up the compute shader on the first GPU available
+        /// 
+        /// * `cutoff` - The furthest a pedestrian perceives its neighbours, in metres
+        pub fn new(cutoff: f64) -> Result<GpuNeighbours, String> {
+            let instance = wgpu::Instance::default();
+            let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+                .ok_or("No GPU adapter is available")?;
+            let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None))
+                .map_err(|error| format!("Failed to open the GPU: {}", error))?;
+                
+            let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("neighbours"),
+                source: wgpu::ShaderSource::Wgsl(SHADER.into())
+            });
+            
+            let storage_entry = |binding: u32, read_only: bool| wgpu::BindGroupLayoutEntry {
+                binding,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Storage {read_only},
+                    has_dynamic_offset: false,
+                    min_binding_size: None
+                },
+                count: None
+            };
+            let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("neighbours"),
+                entries: &[
+                    storage_entry(0, true),
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None
+                        },
+                        count: None
+                    },
+                    storage_entry(2, false),
+                    storage_entry(3, false),
+                    storage_entry(4, false)
+                ]
+            });
+            let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("neighbours"),
+                bind_group_layouts: &[&bind_group_layout],
+                push_constant_ranges: &[]
+            });
+            let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some("neighbours"),
+                layout: Some(&pipeline_layout),
+                module: &shader,
+                entry_point: Some("main"),
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                cache: None
+            });
+            
+            return Ok(GpuNeighbours {
+                cutoff,
+                device,
+                queue,
+                pipeline,
+                bind_group_layout,
+                buffers: None,
+                neighbour_counts: Vec::new(),
+                neighbour_indices: Vec::new(),
+                impulses: Vec::new(),
+                before: Vec::new(),
+                after: Vec::new()
+            });
+        }
+        
+        /// Compare every walking pedestrian with every other one on the GPU at the start of a timestep, and read back what each of them perceives and the contact impulse on it
+        /// 
+        /// * `positions` - The positions of the walking pedestrians, in the order of the simulation's list: \[(x, y, direction, radius, speed)]
+        /// * `contact_params` - The contact stiffness and friction of the same pedestrians, in the same order
+        /// * `time_scale` - The length of the timestep, in seconds
+        pub fn update(&mut self, positions: &[NeighbourState], contact_params: impl Iterator<Item = (f64, f64)>, time_scale: f64) {
+            let count = positions.len();
+            self.neighbour_counts.clear();
+            self.neighbour_indices.clear();
+            self.impulses.clear();
+            if count == 0 {
+                return;
+            }
+            
+            if self.buffers.as_ref().is_none_or(|buffers| buffers.capacity < count) {
+                self.buffers = Some(self.create_buffers(count.next_power_of_two()));
+            }
+            let buffers = self.buffers.as_ref().unwrap();
+            
+            let pedestrians = positions.iter().zip(contact_params).map(|(&(x, y, direction, radius, speed), (stiffness, friction))| GpuPedestrian {
+                x: x as f32,
+                y: y as f32,
+                direction: direction as f32,
+                radius: radius as f32,
+                speed: speed as f32,
+                stiffness: stiffness as f32,
+                friction: friction as f32,
+                padding: 0.0
+            }).collect::<Vec<_>>();
+            let params = GpuParams {
+                count: count as u32,
+                max_neighbours: MAX_GPU_NEIGHBOURS as u32,
+                cutoff: self.cutoff as f32,
+                time_scale: time_scale as f32
+            };
+            self.queue.write_buffer(&buffers.pedestrians, 0, bytemuck::cast_slice(&pedestrians));
+            self.queue.write_buffer(&buffers.params, 0, bytemuck::bytes_of(&params));
+            
+            let counts_size = (count * size_of::<u32>()) as u64;
+            let indices_size = (count * MAX_GPU_NEIGHBOURS * size_of::<u32>()) as u64;
+            let impulses_size = (count * 2 * size_of::<f32>()) as u64;
+            
+            let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {label: Some("neighbours")});
+            {
+                let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {label: Some("neighbours"), timestamp_writes: None});
+                pass.set_pipeline(&self.pipeline);
+                pass.set_bind_group(0, &buffers.bind_group, &[]);
+                pass.dispatch_workgroups(count.div_ceil(WORKGROUP_SIZE) as u32, 1, 1);
+            }
+            encoder.copy_buffer_to_buffer(&buffers.neighbour_counts, 0, &buffers.readback_counts, 0, counts_size);
+            encoder.copy_buffer_to_buffer(&buffers.neighbour_indices, 0, &buffers.readback_indices, 0, indices_size);
+            encoder.copy_buffer_to_buffer(&buffers.impulses, 0, &buffers.readback_impulses, 0, impulses_size);
+            self.queue.submit(Some(encoder.finish()));
+            
+            let readbacks = [(&buffers.readback_counts, counts_size), (&buffers.readback_indices, indices_size), (&buffers.readback_impulses, impulses_size)];
+            for (buffer, size) in readbacks {
+                buffer.slice(0..size).map_async(wgpu::MapMode::Read, |result| result.expect("Failed to read back from the GPU"));
+            }
+            self.device.poll(wgpu::Maintain::Wait);
+            
+            self.neighbour_counts.extend_from_slice(bytemuck::cast_slice(&buffers.readback_counts.slice(0..counts_size).get_mapped_range()));
+            self.neighbour_indices.extend_from_slice(bytemuck::cast_slice(&buffers.readback_indices.slice(0..indices_size).get_mapped_range()));
+            self.impulses.extend(bytemuck::cast_slice::<u8, [f32; 2]>(&buffers.readback_impulses.slice(0..impulses_size).get_mapped_range())
+                .iter().map(|[x, y]| (*x as f64, *y as f64)));
+            for (buffer, _) in readbacks {
+                buffer.unmap();
+            }
+        }
+        
+        /// Create buffers with room for a number of pedestrians
+        fn create_buffers(&self, capacity: usize) -> GpuBuffers {
+            let storage = |label: &str, size: usize, usage: wgpu::BufferUsages| self.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size: size as u64,
+                usage,
+                mapped_at_creation: false
+            });
+            let output = wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC;
+            let readback = wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST;
+            
+            let pedestrians = storage("pedestrians", capacity * size_of::<GpuPedestrian>(), wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST);
+            let params = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("params"),
+                contents: bytemuck::bytes_of(&GpuParams {count: 0, max_neighbours: MAX_GPU_NEIGHBOURS as u32, cutoff: 0.0, time_scale: 0.0}),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST
+            });
+            let neighbour_counts = storage("neighbour_counts", capacity * size_of::<u32>(), output);
+            let neighbour_indices = storage("neighbour_indices", capacity * MAX_GPU_NEIGHBOURS * size_of::<u32>(), output);
+            let impulses = storage("impulses", capacity * 2 * size_of::<f32>(), output);
+            
+            let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("neighbours"),
+                layout: &self.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {binding: 0, resource: pedestrians.as_entire_binding()},
+                    wgpu::BindGroupEntry {binding: 1, resource: params.as_entire_binding()},
+                    wgpu::BindGroupEntry {binding: 2, resource: neighbour_counts.as_entire_binding()},
+                    wgpu::BindGroupEntry {binding: 3, resource: neighbour_indices.as_entire_binding()},
+                    wgpu::BindGroupEntry {binding: 4, resource: impulses.as_entire_binding()}
+                ]
+            });
+            
+            return GpuBuffers {
+                capacity,
+                readback_counts: storage("readback_counts", capacity * size_of::<u32>(), readback),
+                readback_indices: storage("readback_indices", capacity * MAX_GPU_NEIGHBOURS * size_of::<u32>(), readback),
+                readback_impulses: storage("readback_impulses", capacity * 2 * size_of::<f32>(), readback),
+                pedestrians,
+                params,
+                neighbour_counts,
+                neighbour_indices,
+                impulses,
+                bind_group
+            };
+        }
+        
+        /// Return the neighbours a pedestrian perceived in the last update, split into those before & after it in the simulation's list (each in list order), in the same form as the full lists of pedestrians
+        /// 
+        /// * `index` - The index of the pedestrian in the simulation's list
+        /// * `positions` - The positions of every walking pedestrian, in the order of the simulation's list, as given to `update`
+        pub fn get_neighbours(&mut self, index: usize, positions: &[NeighbourState]) -> (&[NeighbourState], &[NeighbourState]) {
+            let first = index * MAX_GPU_NEIGHBOURS;
+            let neighbours = &mut self.neighbour_indices[first..first + (self.neighbour_counts[index] as usize)];
+            // The shader fills the slots in no particular order once a closer neighbour has replaced a further one, so put them back in list order
+            neighbours.sort_unstable();
+            
+            self.before.clear();
+            self.after.clear();
+            for n_index in neighbours.iter().map(|n_index| *n_index as usize) {
+                if n_index < index {
+                    self.before.push(positions[n_index]);
+                } else {
+                    self.after.push(positions[n_index]);
+                }
+            }
+            
+            return (&self.before, &self.after);
+        }
+        
+        /// Return the contact impulse on a pedestrian from the neighbours it overlapped in the last update, in m/s
+        /// 
+        /// * `index` - The index of the pedestrian in the simulation's list
+        pub fn get_contact_impulse(&self, index: usize) -> (f64, f64) {
+            return self.impulses[index];
+        }
+    }
+    
+}
@@ -0,0 +1,165 @@
+pub mod gtfs {
+    
+    use std::fs;
+    use std::io;
+    use std::path::Path;
+    
+    
+    /// Read the arrival time of every trip that stops at a given stop from a GTFS `stop_times.txt` file.
+    /// 
+    /// Returns the arrival times in seconds after midnight, sorted in ascending order.
+    /// Times past midnight (e.g. "25:10:00") are kept as-is, as allowed by GTFS.
+    /// Every trip in the file is included - filtering by service day (calendar.txt) is left to the caller.
+    /// 
+    /// * `path` - The path of the `stop_times.txt` file
+    /// * `stop_id` - The `stop_id` of the platform or station of interest
+    pub fn read_stop_arrivals(path: &Path, stop_id: &str) -> io::Result<Vec<f64>> {
+        let contents = fs::read_to_string(path)?;
+        let mut lines = contents.lines();
+        
+        // Find the columns of interest from the header, which may start with a byte order mark
+        let header = split_csv_line(lines.next().unwrap_or("").trim_start_matches('\u{feff}'));
+        let column = |name: &str| header.iter().position(|c| c == name).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("stop_times.txt has no \"{}\" column", name))
+        });
+        let stop_column = column("stop_id")?;
+        let arrival_column = column("arrival_time")?;
+        let departure_column = column("departure_time")?;
+        
+        let mut arrivals = Vec::new();
+        
+        for line in lines {
+            if line.trim().is_empty() {
+                continue;
+            }
+            
+            let fields = split_csv_line(line);
+            if fields.get(stop_column).map(|s| s.as_str()) != Some(stop_id) {
+                continue;
+            }
+            
+            // Arrival times are optional for stops that aren't timepoints, so fall back to the departure time
+            let time = fields.get(arrival_column).and_then(|t| parse_gtfs_time(t))
+                .or_else(|| fields.get(departure_column).and_then(|t| parse_gtfs_time(t)));
+            
+            if let Some(time) = time {
+                arrivals.push(time);
+            }
+        }
+        
+        arrivals.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        
+        return Ok(arrivals);
+    }
+    
+    /// Convert a list of train arrival times into spawn pulses for a platform simulation.
+    /// 
+    /// Only arrivals inside the time window are kept, and their times are made relative to the start of the window.
+    /// 
+    /// Output form: \[(release time, number of pedestrians)]
+    /// 
+    /// * `arrivals` - Arrival times in seconds after midnight, as returned by `read_stop_arrivals`
+    /// * `window` - The (start, end) of the period to simulate, in seconds after midnight
+    /// * `alighting_per_train` - The number of pedestrians released by each arrival
+    pub fn arrivals_to_pulses(arrivals: &[f64], window: (f64, f64), alighting_per_train: usize) -> Vec<(f64, usize)> {
+        return arrivals.iter()
+            .filter(|t| **t >= window.0 && **t < window.1)
+            .map(|t| (t - window.0, alighting_per_train))
+            .collect();
+    }
+    
+    /// Parse a GTFS time of the form "HH:MM:SS" into seconds after midnight (None if it is malformed or too large to represent)
+    fn parse_gtfs_time(time: &str) -> Option<f64> {
+        let parts = time.trim().split(':').map(|p| p.parse::<u32>().ok()).collect::<Option<Vec<_>>>()?;
+        
+        if parts.len() != 3 {
+            return None;
+        }
+        
+        let seconds = parts[0].checked_mul(3600)?.checked_add(parts[1].checked_mul(60)?)?.checked_add(parts[2])?;
+        
+        return Some(seconds as f64);
+    }
+    
+    /// Split a line of a CSV file into fields, handling double-quoted fields
+    fn split_csv_line(line: &str) -> Vec<String> {
+        let mut fields = Vec::new();
+        let mut field = String::new();
+        let mut in_quotes = false;
+        let mut chars = line.trim_end_matches('\r').chars().peekable();
+        
+        while let Some(c) = chars.next() {
+            match c {
+                '"' if in_quotes && chars.peek() == Some(&'"') => {
+                    // Escaped quote
+                    field.push('"');
+                    chars.next();
+                },
+                '"' => in_quotes = !in_quotes,
+                ',' if !in_quotes => fields.push(std::mem::take(&mut field)),
+                _ => field.push(c)
+            }
+        }
+        fields.push(field);
+        
+        return fields.into_iter().map(|f| f.trim().to_string()).collect();
+    }
+    
+    
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        
+        #[test]
+        fn gtfs_times() {
+            assert_eq!(parse_gtfs_time("08:30:15"), Some(30615.0));
+            assert_eq!(parse_gtfs_time(" 7:05:00 "), Some(25500.0));
+            
+            // Trips running past midnight keep counting up
+            assert_eq!(parse_gtfs_time("25:10:00"), Some(90600.0));
+            
+            assert_eq!(parse_gtfs_time(""), None);
+            assert_eq!(parse_gtfs_time("08:30"), None);
+            assert_eq!(parse_gtfs_time("08:30:15:00"), None);
+            assert_eq!(parse_gtfs_time("8h:30:15"), None);
+            assert_eq!(parse_gtfs_time("-1:00:00"), None);
+            
+            // Too many hours to count in seconds, instead of overflowing
+            assert_eq!(parse_gtfs_time("4294967295:00:00"), None);
+            assert_eq!(parse_gtfs_time("1193047:00:00"), None);
+        }
+        
+        #[test]
+        fn quoted_csv_fields() {
+            assert_eq!(split_csv_line("a, b ,c\r"), vec!["a", "b", "c"]);
+            assert_eq!(split_csv_line("\"Platform 1, north\",\"say \"\"hi\"\"\",,x"), vec!["Platform 1, north", "say \"hi\"", "", "x"]);
+        }
+        
+        #[test]
+        fn stop_arrivals() {
+            // A byte order mark before the header, columns in an unusual order, a quoted stop ID and a stop with only a departure time
+            let contents = "\u{feff}trip_id,departure_time,stop_id,arrival_time,stop_sequence\r\n\
+                            t1,08:01:00,A,08:00:00,1\r\n\
+                            t1,08:06:00,\"B\",08:05:30,2\r\n\
+                            t2,24:15:00,A,,1\r\n\
+                            \r\n\
+                            t3,07:59:00,A,07:58:00,1\r\n\
+                            t4,,A,,1\r\n";
+            let path = std::env::temp_dir().join(format!("pedestrian_gtfs_test_{}.txt", std::process::id()));
+            fs::write(&path, contents).unwrap();
+            
+            let arrivals = read_stop_arrivals(&path, "A").unwrap();
+            assert_eq!(arrivals, vec![28680.0, 28800.0, 87300.0]);
+            assert_eq!(read_stop_arrivals(&path, "B").unwrap(), vec![29130.0]);
+            
+            assert_eq!(arrivals_to_pulses(&arrivals, (28700.0, 90000.0), 20), vec![(100.0, 20), (58600.0, 20)]);
+            
+            // A file without the columns needed can't be read
+            fs::write(&path, "trip_id,stop_id\nt1,A\n").unwrap();
+            assert_eq!(read_stop_arrivals(&path, "A").unwrap_err().kind(), io::ErrorKind::InvalidData);
+            
+            let _ = fs::remove_file(&path);
+        }
+    }
+    
+}
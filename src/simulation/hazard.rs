@@ -0,0 +1,155 @@
+pub mod hazard {
+    
+    use raylib::{drawing::{RaylibDrawHandle, RaylibDraw}, color::Color};
+    
+    use crate::simulation::geom::geom::Vec2;
+    
+    
+    /// The width of the fringe around a hazard's core, over which its intensity falls from 1 to 0, in metres
+    const HAZARD_FRINGE_WIDTH: f64 = 1.5;
+    
+    
+    const HAZARD_COLOUR: &str = "7F5F5F";
+    
+    
+    /// A hazard spreading out from a source, such as smoke from a fire.
+    /// 
+    /// Its intensity is a scalar field: 1 inside a core that grows at a constant rate from the start time up to a maximum radius, falling linearly to 0
+    /// across a fringe around the core. Pedestrians are pushed away from it, harder the more intense it is where they are,
+    /// and the exposure they build up inside it can incapacitate them (see the `hazard_tolerance` parameter).
+    pub struct HazardSource {
+        /// The centre of the hazard
+        position: Vec2,
+        /// The simulation time the hazard appears at, in seconds
+        start_time: f64,
+        /// How fast the core of the hazard grows, in m/s
+        spread_rate: f64,
+        /// The largest the core of the hazard grows, in metres
+        max_radius: f64
+    }
+    
+    impl HazardSource {
+        /// * `position` - The centre of the hazard
+        /// * `start_time` - The simulation time the hazard appears at, in seconds
+        /// * `spread_rate` - How fast the core of the hazard grows, in m/s
+        /// * `max_radius` - The largest the core of the hazard grows, in metres
+        pub fn new(position: (f64, f64), start_time: f64, spread_rate: f64, max_radius: f64) -> HazardSource {
+            assert!(spread_rate >= 0.0 && max_radius >= 0.0, "A hazard can't shrink");
+            
+            return HazardSource {
+                position: position.into(),
+                start_time,
+                spread_rate,
+                max_radius
+            };
+        }
+        
+        /// Return the centre of the hazard
+        pub fn get_position(&self) -> Vec2 {
+            return self.position;
+        }
+        
+        /// Return the radius of the core of the hazard at a simulation time, in metres (None = it hasn't appeared yet)
+        pub fn get_radius(&self, time: f64) -> Option<f64> {
+            if time < self.start_time {
+                return None;
+            }
+            
+            return Some((self.spread_rate * (time - self.start_time)).min(self.max_radius));
+        }
+        
+        /// Return the intensity of the hazard at a point and simulation time, from 0 (clear of it) to 1 (inside the core)
+        pub fn get_intensity(&self, p: Vec2, time: f64) -> f64 {
+            return match self.get_radius(time) {
+                Some(radius) => (1.0 - (p.distance(self.position) - radius) / HAZARD_FRINGE_WIDTH).clamp(0.0, 1.0),
+                None => 0.0
+            };
+        }
+        
+        /// Draw the core of the hazard, and the outer edge of its fringe, as they are at a simulation time
+        pub fn draw(&self, rl_handle: &mut RaylibDrawHandle, offset: (i32, i32), draw_scale: i32, time: f64) {
+            let radius = match self.get_radius(time) {
+                Some(radius) => radius,
+                None => return
+            };
+            
+            let colour = Color::from_hex(HAZARD_COLOUR).unwrap();
+            let centre = (offset.0 + ((draw_scale as f64) * self.position.x) as i32, offset.1 + ((draw_scale as f64) * self.position.y) as i32);
+            rl_handle.draw_circle(centre.0, centre.1, ((draw_scale as f64) * radius) as f32, Color::fade(&colour, 0.35));
+            rl_handle.draw_circle_lines(centre.0, centre.1, ((draw_scale as f64) * (radius + HAZARD_FRINGE_WIDTH)) as f32, Color::fade(&colour, 0.6));
+        }
+    }
+    
+    /// Records a pedestrian being incapacitated by a hazard, after building up more exposure than the `hazard_tolerance` parameter
+    #[derive(Clone, Copy, Debug)]
+    pub struct HazardCasualty {
+        /// The ID of the pedestrian (see `Walker::get_id`)
+        pub pedestrian_id: u64,
+        /// The group the pedestrian is part of
+        pub group: usize,
+        /// Where the pedestrian was incapacitated
+        pub position: (f64, f64),
+        /// The simulation time the pedestrian was incapacitated at, in seconds
+        pub sim_time: f64
+    }
+    
+    
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::sync::Arc;
+        use crate::simulation::simulator::simulator::{CrowdSim, SimArea};
+        use crate::simulation::behaviour::behaviour::Etiquette;
+        
+        #[test]
+        fn intensity_spreads_from_the_source() {
+            let hazard = HazardSource::new((0.0, 0.0), 2.0, 0.5, 1.0);
+            
+            assert_eq!(hazard.get_radius(1.0), None);
+            assert_eq!(hazard.get_intensity(Vec2::new(0.0, 0.0), 1.0), 0.0);
+            
+            // The core grows to its largest 2s after it appears, and the intensity falls to 0 across the fringe around it
+            assert_eq!(hazard.get_radius(3.0), Some(0.5));
+            assert_eq!(hazard.get_radius(10.0), Some(1.0));
+            assert_eq!(hazard.get_intensity(Vec2::new(0.9, 0.0), 10.0), 1.0);
+            assert!((hazard.get_intensity(Vec2::new(0.0, 1.75), 10.0) - 0.5).abs() < 1e-9);
+            assert_eq!(hazard.get_intensity(Vec2::new(2.5, 0.0), 10.0), 0.0);
+        }
+        
+        /// Run six pedestrians down a corridor with a hazard in it, who are incapacitated after 1s in its core. Return the simulation and the number that finished.
+        fn run_corridor(position: (f64, f64), max_radius: f64) -> (CrowdSim, usize) {
+            let mut area = SimArea::new();
+            area.add_wall((-1.0,0.0), (11.0,0.0));
+            area.add_wall((-1.0,4.0), (11.0,4.0));
+            area.add_start_end_group(vec![(0.0,1.0), (0.0,3.0)], vec![(10.0,1.0), (10.0,3.0)]);
+            area.add_hazard(position, 0.0, 1.0, max_radius);
+            
+            let mut crowd_sim = CrowdSim::new(Arc::new(area), 2.0);
+            crowd_sim.set_seed(0);
+            crowd_sim.set_run_limits(Some(120.0), None);
+            crowd_sim.set_params(crowd_sim.get_params().with_param("hazard_tolerance", 1.0).unwrap());
+            crowd_sim.add_pedestrian_set(6, 0, Arc::new(Etiquette::NoBias));
+            let (_, finished, _) = crowd_sim.simulate_full(0.1);
+            return (crowd_sim, finished);
+        }
+        
+        #[test]
+        fn pedestrians_avoid_or_succumb_to_hazards() {
+            // Pedestrians are pushed round a small hazard against one wall, without building up enough exposure to be harmed
+            let (crowd_sim, finished) = run_corridor((5.0, 4.0), 0.5);
+            assert!(crowd_sim.get_hazard_casualties().is_empty());
+            assert_eq!(finished, 6);
+            
+            // A hazard filling the corridor can't be avoided, and incapacitates everyone passing into it
+            let (crowd_sim, finished) = run_corridor((5.0, 2.0), 3.0);
+            assert_eq!(finished, 0);
+            let casualties = crowd_sim.get_hazard_casualties();
+            assert_eq!(casualties.len(), 6);
+            for casualty in casualties {
+                let distance = Vec2::from(casualty.position).distance(Vec2::new(5.0, 2.0));
+                assert!(distance < 3.0 + HAZARD_FRINGE_WIDTH, "incapacitated {}m from the hazard", distance);
+            }
+        }
+    }
+    
+}
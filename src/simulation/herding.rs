@@ -0,0 +1,113 @@
+pub mod herding {
+    
+    use serde::{Deserialize, Serialize};
+    
+    use crate::simulation::geom::geom::Vec2;
+    
+    
+    /// The cohort that pedestrians unfamiliar with their route are reported in
+    pub const UNFAMILIAR_COHORT: &str = "unfamiliar";
+    
+    /// The slowest a neighbour can walk and still be followed, in m/s (pedestrians standing still don't show the way)
+    const MIN_FOLLOWED_SPEED: f64 = 0.1;
+    
+    
+    /// Settings for herding: pedestrians that don't know their way follow the crowd around them instead of heading for their own destination.
+    /// 
+    /// An unfamiliar pedestrian steers by the average heading of the moving neighbours it can see, blended with the direction of its own destination
+    /// by `weight` (Helbing et al., 2000). With nobody in sight to follow, it heads for its destination as usual.
+    /// 
+    /// In a scenario file, this is the `[herding]` table. Any field that is left out keeps its default value.
+    #[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+    #[serde(default)]
+    pub struct Herding {
+        /// The fraction of pedestrians that know their way, from 0 to 1. Each of the rest is chosen at random as it is added.
+        pub familiarity: f64,
+        /// How far away an unfamiliar pedestrian looks for neighbours to follow, in metres
+        pub radius: f64,
+        /// How much an unfamiliar pedestrian follows its neighbours rather than heading for its own destination, from 0 (not at all) to 1 (only its neighbours)
+        pub weight: f64
+    }
+    
+    impl Herding {
+        /// Return the direction an unfamiliar pedestrian steers in, in radians, between its own heading for its destination and the average heading of the moving neighbours it can see
+        /// 
+        /// * `target_angle` - The direction of the pedestrian's own destination, in radians
+        /// * `neighbours` - The headings of the neighbours within `radius` that the pedestrian can see: \[(direction, speed)]
+        pub fn get_heading(&self, target_angle: f64, neighbours: impl Iterator<Item = (f64, f64)>) -> f64 {
+            let crowd_heading = neighbours.filter(|(_, speed)| *speed > MIN_FOLLOWED_SPEED)
+                .map(|(direction, _)| Vec2::from_angle(direction))
+                .fold(Vec2::new(0.0, 0.0), |sum, heading| sum + heading);
+            
+            // Nobody to follow, or the crowd is heading every which way
+            let crowd_heading = match crowd_heading.normalised() {
+                Some(heading) => heading,
+                None => return target_angle
+            };
+            
+            let heading = Vec2::from_angle(target_angle) * (1.0 - self.weight) + crowd_heading * self.weight;
+            return heading.normalised().map_or(target_angle, |heading| heading.angle());
+        }
+    }
+    
+    impl Default for Herding {
+        /// Everyone knows their way, but an unfamiliar pedestrian follows only the neighbours within 5m
+        fn default() -> Herding {
+            Herding {
+                familiarity: 1.0,
+                radius: 5.0,
+                weight: 1.0
+            }
+        }
+    }
+    
+    
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::f64::consts::PI;
+        use std::sync::Arc;
+        use crate::simulation::simulator::simulator::{CrowdSim, SimArea};
+        use crate::simulation::behaviour::behaviour::Etiquette;
+        
+        #[test]
+        fn heading_follows_the_moving_crowd() {
+            let herding = Herding {familiarity: 0.0, radius: 5.0, weight: 0.5};
+            
+            // Nobody to follow, only neighbours standing still, or a crowd heading every which way
+            assert_eq!(herding.get_heading(0.0, std::iter::empty()), 0.0);
+            assert_eq!(herding.get_heading(0.0, [(PI/2.0, 0.05)].into_iter()), 0.0);
+            assert_eq!(herding.get_heading(0.0, [(PI/2.0, 1.0), (-PI/2.0, 1.0)].into_iter()), 0.0);
+            
+            // Half way between the destination and the crowd
+            assert!((herding.get_heading(0.0, [(PI/2.0, 1.0), (PI/2.0, 0.5)].into_iter()) - PI/4.0).abs() < 1e-9);
+            
+            // Only the crowd
+            let follower = Herding {weight: 1.0, ..herding};
+            assert!((follower.get_heading(0.0, [(PI/2.0, 1.0)].into_iter()) - PI/2.0).abs() < 1e-9);
+        }
+        
+        #[test]
+        fn only_unfamiliar_pedestrians_are_recorded() {
+            let run = |familiarity: f64| {
+                let mut area = SimArea::new();
+                area.add_wall((-1.0,0.0), (11.0,0.0));
+                area.add_wall((-1.0,4.0), (11.0,4.0));
+                area.add_start_end_group(vec![(0.0,1.0), (0.0,3.0)], vec![(10.0,1.0), (10.0,3.0)]);
+                
+                let mut crowd_sim = CrowdSim::new(Arc::new(area), 2.0);
+                crowd_sim.set_seed(0);
+                crowd_sim.set_run_limits(Some(120.0), None);
+                crowd_sim.set_herding(Some(Herding {familiarity, radius: 5.0, weight: 0.5}));
+                crowd_sim.add_pedestrian_set(6, 0, Arc::new(Etiquette::NoBias));
+                let (_, finished, _) = crowd_sim.simulate_full(0.1);
+                return (finished, crowd_sim.get_wayfinding_results().len());
+            };
+            
+            // Pedestrians following a crowd that is heading the same way still find their way
+            assert_eq!(run(1.0), (6, 0));
+            assert_eq!(run(0.0), (6, 6));
+        }
+    }
+    
+}
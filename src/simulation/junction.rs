@@ -0,0 +1,172 @@
+pub mod junction {
+    
+    use std::f64::consts::{PI, FRAC_1_SQRT_2};
+    use raylib::{drawing::{RaylibDrawHandle, RaylibDraw}, color::Color};
+    
+    use crate::simulation::geom::geom::{Vec2, wrap_angle};
+    
+    
+    /// How far from a junction zone pedestrians start to check for conflicting pedestrians, in metres
+    const APPROACH_DISTANCE: f64 = 2.0;
+    
+    /// How far to either side of a pedestrian's path another pedestrian can be and still be in its way, in metres
+    const CONFLICT_WIDTH: f64 = 1.0;
+    
+    /// How long a pedestrian waits for a conflicting pedestrian before going anyway, in seconds.
+    /// This stops every pedestrian waiting for another forever when all approaches are busy.
+    pub const GIVE_WAY_PATIENCE: f64 = 3.0;
+    
+    /// How long a pedestrian giving way takes to come to a stop at the edge of the junction zone, in seconds
+    const GIVE_WAY_STOP_TIME: f64 = 1.0;
+    
+    
+    const JUNCTION_COLOUR: &str = "D9B23A";
+    
+    
+    /// Decides which of two pedestrians in crossing streams goes first at a junction
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum PriorityRule {
+        /// Give way to pedestrians approaching from the right
+        GiveWayToRight,
+        /// Give way to pedestrians approaching from the left
+        GiveWayToLeft,
+        /// The pedestrian closest to the junction goes first
+        FirstComeFirstServed
+    }
+    
+    impl PriorityRule {
+        /// A short name identifying the rule, used to label results
+        pub fn name(&self) -> &str {
+            match self {
+                PriorityRule::GiveWayToRight => "give way to the right",
+                PriorityRule::GiveWayToLeft => "give way to the left",
+                PriorityRule::FirstComeFirstServed => "first come first served"
+            }
+        }
+    }
+    
+    /// A rectangular area where streams of pedestrians cross, such as the middle of a crossroads.
+    /// 
+    /// Pedestrians approaching the zone give way to pedestrians in crossing streams according to the zone's priority rule.
+    /// Pedestrians never give way once they are inside the zone, so that the junction is cleared as quickly as possible.
+    pub struct JunctionZone {
+        /// The corner of the zone with the smallest coordinates
+        min: Vec2,
+        /// The corner of the zone with the largest coordinates
+        max: Vec2,
+        rule: PriorityRule
+    }
+    
+    impl JunctionZone {
+        /// Create a new junction zone from two opposite corners
+        pub fn new(corner1: (f64, f64), corner2: (f64, f64), rule: PriorityRule) -> JunctionZone {
+            JunctionZone {
+                min: Vec2::new(corner1.0.min(corner2.0), corner1.1.min(corner2.1)),
+                max: Vec2::new(corner1.0.max(corner2.0), corner1.1.max(corner2.1)),
+                rule
+            }
+        }
+        
+        pub fn get_rule(&self) -> PriorityRule {
+            return self.rule;
+        }
+        
+        /// Return the distance from a point to the zone (0 if the point is inside it)
+        pub fn get_distance(&self, p: Vec2) -> f64 {
+            let dx = (self.min.x - p.x).max(p.x - self.max.x).max(0.0);
+            let dy = (self.min.y - p.y).max(p.y - self.max.y).max(0.0);
+            
+            return (dx*dx + dy*dy).sqrt();
+        }
+        
+        /// Return how far a pedestrian would walk in a straight line before entering the zone (0 if it is already inside), or None if it would miss the zone
+        /// 
+        /// * `direction` - The pedestrian's direction of travel, in radians
+        pub fn get_entry_distance(&self, position: Vec2, direction: f64) -> Option<f64> {
+            let heading = Vec2::from_angle(direction);
+            
+            // Intersect the ray with the slab between each pair of opposite sides
+            let mut entry: f64 = 0.0;
+            let mut exit = f64::INFINITY;
+            for (p, d, min, max) in [(position.x, heading.x, self.min.x, self.max.x), (position.y, heading.y, self.min.y, self.max.y)] {
+                if d == 0.0 {
+                    if p < min || p > max {
+                        return None;
+                    }
+                } else {
+                    let t1 = (min - p) / d;
+                    let t2 = (max - p) / d;
+                    entry = entry.max(t1.min(t2));
+                    exit = exit.min(t1.max(t2));
+                }
+            }
+            
+            if entry > exit {
+                return None;
+            }
+            
+            return Some(entry);
+        }
+        
+        /// Check whether a pedestrian should give way to another pedestrian before entering the zone
+        /// 
+        /// * `position`, `direction` - The position and direction of travel of the pedestrian that might give way
+        /// * `other_position`, `other_direction` - The position and direction of travel of the other pedestrian
+        pub fn must_give_way(&self, position: Vec2, direction: f64, other_position: Vec2, other_direction: f64) -> bool {
+            // Only pedestrians about to enter the zone give way, and only to pedestrians in it or about to enter it
+            let entry = match self.get_entry_distance(position, direction) {
+                Some(entry) if entry > 0.0 && entry <= APPROACH_DISTANCE => entry,
+                _ => return false
+            };
+            let other_entry = match self.get_entry_distance(other_position, other_direction) {
+                Some(other_entry) if other_entry <= APPROACH_DISTANCE => other_entry,
+                _ => return false
+            };
+            
+            // Pedestrians walking along or against the same stream don't conflict
+            let relative_direction = wrap_angle(other_direction - direction);
+            if relative_direction.sin().abs() < FRAC_1_SQRT_2 {
+                return false;
+            }
+            
+            // Ignore pedestrians that have already crossed this pedestrian's path
+            let lateral = Vec2::from_angle(direction + PI/2.0);
+            let offset = lateral.dot(other_position - position);
+            if offset * lateral.dot(Vec2::from_angle(other_direction)) > 0.0 && offset.abs() > CONFLICT_WIDTH {
+                return false;
+            }
+            
+            // Always give way to a pedestrian that is already crossing
+            if other_entry == 0.0 {
+                return true;
+            }
+            
+            // Both pedestrians are approaching: apply the priority rule.
+            // Angles increase clockwise, so a pedestrian coming from the right is heading 90° anticlockwise of this pedestrian's direction.
+            return match self.rule {
+                PriorityRule::GiveWayToRight => relative_direction < 0.0,
+                PriorityRule::GiveWayToLeft => relative_direction > 0.0,
+                PriorityRule::FirstComeFirstServed => entry > other_entry
+            };
+        }
+        
+        /// Return the fastest a pedestrian giving way can walk while still being able to stop before entering the zone, in m/s
+        /// 
+        /// * `radius` - The radius of the pedestrian, in metres
+        pub fn get_give_way_speed(&self, position: Vec2, radius: f64) -> f64 {
+            return (self.get_distance(position) - radius).max(0.0) / GIVE_WAY_STOP_TIME;
+        }
+        
+        /// Draw this junction zone with RayLib
+        pub fn draw(&self, rl_handle: &mut RaylibDrawHandle, offset: (i32, i32), draw_scale: i32) {
+            rl_handle.draw_rectangle(
+                offset.0 + ((draw_scale as f64) * self.min.x) as i32,
+                offset.1 + ((draw_scale as f64) * self.min.y) as i32,
+                ((draw_scale as f64) * (self.max.x - self.min.x)) as i32,
+                ((draw_scale as f64) * (self.max.y - self.min.y)) as i32,
+                Color::fade(&Color::from_hex(JUNCTION_COLOUR).unwrap(), 0.15)
+            );
+        }
+    }
+    
+}
@@ -0,0 +1,107 @@
+pub mod luggage {
+    
+    use serde::{Deserialize, Serialize};
+    
+    use crate::simulation::geom::geom::{Vec2, segment_normal_vector};
+    
+    
+    /// Where a pedestrian's luggage is, relative to the direction it is facing
+    #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum LuggagePlacement {
+        /// Pushed in front, like a pram or a luggage trolley
+        Ahead,
+        /// Pulled behind, like a wheeled suitcase
+        Behind
+    }
+    
+    
+    /// Something bulky that a pedestrian takes along, such as a suitcase or a pram, which makes its footprint longer than its body.
+    /// 
+    /// The item is a capsule (a rectangle with rounded ends) lined up with the direction the pedestrian is facing, touching its body.
+    /// Other pedestrians keep clear of it like a short wall, and the pedestrian taking it along has a personal space enlarged by its length and turns more slowly.
+    /// 
+    /// In a scenario file, each kind of item is a `[[luggage]]` table.
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    pub struct Luggage {
+        /// The name of the item, which pedestrians taking it along are tagged with as a cohort so that their results can be reported separately
+        pub name: String,
+        /// The fraction of pedestrians that take this item along, from 0 to 1. Each pedestrian takes at most one item.
+        #[serde(default)]
+        pub fraction: f64,
+        /// Whether the item is pushed in front or pulled behind
+        pub placement: LuggagePlacement,
+        /// The length of the item along the direction the pedestrian is facing, in metres
+        pub length: f64,
+        /// The width of the item, in metres
+        pub width: f64,
+        /// A multiplier applied to how quickly a pedestrian taking the item along turns towards its destination
+        #[serde(default = "default_turn_factor")]
+        pub turn_factor: f64
+    }
+    
+    impl Luggage {
+        /// A wheeled suitcase pulled behind, taken along by a fraction of pedestrians
+        pub fn suitcase(fraction: f64) -> Luggage {
+            Luggage {
+                name: "suitcase".to_string(),
+                fraction,
+                placement: LuggagePlacement::Behind,
+                length: 0.6,
+                width: 0.4,
+                turn_factor: 0.7
+            }
+        }
+        
+        /// A pram pushed in front, taken along by a fraction of pedestrians
+        pub fn pram(fraction: f64) -> Luggage {
+            Luggage {
+                name: "pram".to_string(),
+                fraction,
+                placement: LuggagePlacement::Ahead,
+                length: 1.0,
+                width: 0.6,
+                turn_factor: 0.5
+            }
+        }
+        
+        /// Return the space taken up by the item for a pedestrian at a position, facing in a direction: (one end of the capsule's centre line, the other end, radius)
+        /// 
+        /// * `body_radius` - The radius of the pedestrian's body, which the item touches
+        pub fn get_capsule(&self, position: Vec2, direction: f64, body_radius: f64) -> (Vec2, Vec2, f64) {
+            let radius = self.width/2.0;
+            let axis = match self.placement {
+                LuggagePlacement::Ahead => Vec2::from_angle(direction),
+                LuggagePlacement::Behind => -Vec2::from_angle(direction)
+            };
+            
+            // An item shorter than it is wide is round
+            let near_end = position + axis*(body_radius + radius);
+            let far_end = position + axis*(body_radius + self.length.max(self.width) - radius);
+            
+            return (near_end, far_end, radius);
+        }
+    }
+    
+    /// Pedestrians taking an item along turn half as quickly as on their own, unless given otherwise
+    fn default_turn_factor() -> f64 {
+        return 0.5;
+    }
+    
+    /// Given a capsule and a point P, determine the vector that points from the closest point on the edge of the capsule to P
+    /// 
+    /// Output form: (distance, normal vector). The distance is negative if P is inside the capsule, and the normal vector has a length equal to its magnitude.
+    /// 
+    /// * `capsule` - (one end of the centre line, the other end, radius)
+    pub fn capsule_normal_vector(capsule: (Vec2, Vec2, f64), p: Vec2) -> (f64, Vec2) {
+        let (a, b, radius) = capsule;
+        let (centre_dist, normal) = segment_normal_vector(a, b, p);
+        
+        let dist = centre_dist - radius;
+        return match normal.normalised() {
+            Some(direction) => (dist, direction*dist.abs()),
+            None => (dist, Vec2::new(0.0, 0.0))
+        };
+    }
+    
+}
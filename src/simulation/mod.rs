@@ -1,2 +1,48 @@
 pub mod pedestrian;
-pub mod simulator;
\ No newline at end of file
+pub mod simulator;
+pub mod behaviour;
+pub mod social_force;
+pub mod export;
+pub mod gtfs;
+pub mod params;
+pub mod scenario;
+pub mod pool;
+pub mod geom;
+pub mod navigation;
+pub mod floor_field;
+pub mod junction;
+pub mod cache;
+pub mod console;
+pub mod demand;
+pub mod arrivals;
+pub mod annotation;
+pub mod density;
+pub mod voronoi;
+pub mod statistics;
+pub mod trajectory;
+pub mod replay;
+pub mod checkpoint;
+pub mod observer;
+pub mod snapshot;
+pub mod neighbours;
+pub mod profiling;
+pub mod experiment;
+pub mod calibration;
+pub mod distracted;
+pub mod noncompliant;
+pub mod conversation;
+pub mod luggage;
+pub mod pressure;
+pub mod queue;
+pub mod gate;
+pub mod walkway;
+pub mod vehicle;
+pub mod boarding;
+pub mod timeline;
+pub mod hazard;
+pub mod herding;
+pub mod signage;
+pub mod contact;
+pub mod distancing;
+#[cfg(feature = "gpu")]
+pub mod gpu;
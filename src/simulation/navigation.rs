@@ -0,0 +1,247 @@
+pub mod navigation {
+    
+    use std::cmp::Ordering;
+    use std::collections::BinaryHeap;
+    use std::f64::consts::{PI, TAU};
+    use std::sync::Arc;
+    
+    use crate::simulation::simulator::simulator::SimArea;
+    use crate::simulation::geom::geom::{Vec2, segment_normal_vector, segments_intersect};
+    use crate::simulation::floor_field::floor_field::FloorField;
+    
+    
+    /// The number of nodes placed around each circular obstacle
+    const OBSTACLE_NODE_COUNT: usize = 8;
+    
+    /// How far nodes are placed from the geometry, as a multiple of the clearance
+    const NODE_OFFSET_FACTOR: f64 = 1.5;
+    
+    
+    /// The ways a pedestrian can find its way around walls and obstacles
+    #[derive(Clone)]
+    pub enum Navigation {
+        /// Each pedestrian plans its own path with A*, and replans if it is blocked
+        PathPlanning(Arc<VisibilityGraph>),
+        /// Pedestrians descend a shared distance field towards their destination
+        FloorField(Arc<FloorField>)
+    }
+    
+    /// A visibility graph over the free space of an environment, used to plan paths around walls and obstacles with A*.
+    /// 
+    /// Nodes are placed just outside the ends of walls and around obstacles, and two nodes are connected if a pedestrian could walk directly between them while keeping its distance from all geometry.
+    pub struct VisibilityGraph {
+        /// Line segments that paths must keep clear of: (start, end)
+        segments: Vec<(Vec2, Vec2)>,
+        /// Circles that paths must keep clear of: (centre, radius)
+        circles: Vec<(Vec2, f64)>,
+        /// The minimum distance between a path and any geometry, in metres
+        clearance: f64,
+        /// The position of each node
+        nodes: Vec<Vec2>,
+        /// The nodes visible from each node, and the distance to them
+        edges: Vec<Vec<(usize, f64)>>
+    }
+    
+    impl VisibilityGraph {
+        /// Build a visibility graph from the walls and obstacles of an environment.
+        /// 
+        /// * `clearance` - The minimum distance between a planned path and any wall or obstacle, in metres. This should be at least the pedestrian radius.
+        pub fn new(area: &SimArea, clearance: f64) -> VisibilityGraph {
+            let segments = area.boundaries.iter().map(|wall| {
+                let (p1, p2) = wall.get_points();
+                (Vec2::from(p1), Vec2::from(p2))
+            }).collect::<Vec<_>>();
+            let circles = area.obstacles.iter().map(|obstacle| (Vec2::from(obstacle.get_centre()), obstacle.get_radius())).collect::<Vec<_>>();
+            
+            let mut graph = VisibilityGraph {
+                segments,
+                circles,
+                clearance,
+                nodes: Vec::new(),
+                edges: Vec::new()
+            };
+            
+            // Candidate nodes diagonally outside each wall end, and evenly spaced around each obstacle
+            let offset = clearance * NODE_OFFSET_FACTOR;
+            let mut candidates = Vec::new();
+            for (a, b) in &graph.segments {
+                for end in [*a, *b] {
+                    for angle in [PI/4.0, 3.0*PI/4.0, 5.0*PI/4.0, 7.0*PI/4.0] {
+                        candidates.push(end + Vec2::from_angle(angle) * (offset * 2.0_f64.sqrt()));
+                    }
+                }
+            }
+            for (centre, radius) in &graph.circles {
+                for i in 0..OBSTACLE_NODE_COUNT {
+                    let angle = TAU * (i as f64) / (OBSTACLE_NODE_COUNT as f64);
+                    candidates.push(*centre + Vec2::from_angle(angle) * ((radius + offset) / (PI / OBSTACLE_NODE_COUNT as f64).cos()));
+                }
+            }
+            
+            // Keep the candidates that are far enough from all geometry, skipping duplicates
+            for candidate in candidates {
+                if graph.clearance_at(candidate) >= clearance && graph.nodes.iter().all(|node| node.distance(candidate) > clearance / 2.0) {
+                    graph.nodes.push(candidate);
+                }
+            }
+            
+            // Connect every pair of nodes that can see each other
+            graph.edges = vec![Vec::new(); graph.nodes.len()];
+            for i in 0..graph.nodes.len() {
+                for j in (i+1)..graph.nodes.len() {
+                    if graph.is_visible(graph.nodes[i], graph.nodes[j]) {
+                        let dist = graph.nodes[i].distance(graph.nodes[j]);
+                        graph.edges[i].push((j, dist));
+                        graph.edges[j].push((i, dist));
+                    }
+                }
+            }
+            
+            return graph;
+        }
+        
+        /// Return the position of every node
+        pub fn get_nodes(&self) -> &[Vec2] {
+            return &self.nodes;
+        }
+        
+        /// Return the distance from a point to the closest wall or obstacle
+        pub fn clearance_at(&self, p: Vec2) -> f64 {
+            let wall_dist = self.segments.iter().map(|(a, b)| segment_normal_vector(*a, *b, p).0);
+            let obstacle_dist = self.circles.iter().map(|(centre, radius)| centre.distance(p) - radius);
+            
+            return wall_dist.chain(obstacle_dist).fold(f64::INFINITY, f64::min);
+        }
+        
+        /// Check whether a pedestrian could walk in a straight line between two points while keeping its distance from all geometry.
+        /// 
+        /// The required distance is relaxed near either end if that end is already closer to the geometry, so that paths can start and finish next to walls.
+        pub fn is_visible(&self, p: Vec2, q: Vec2) -> bool {
+            // Allow a small tolerance so that nodes placed exactly at the clearance can see each other
+            let required = self.clearance.min(self.clearance_at(p)).min(self.clearance_at(q)) * 0.99;
+            
+            for (a, b) in &self.segments {
+                if segment_distance(p, q, *a, *b) < required {
+                    return false;
+                }
+            }
+            for (centre, radius) in &self.circles {
+                if segment_normal_vector(p, q, *centre).0 - radius < required {
+                    return false;
+                }
+            }
+            
+            return true;
+        }
+        
+        /// Find the shortest path between two points with A*.
+        /// 
+        /// Returns the intermediate points to walk through, not including the start and end, or None if the end can't be reached.
+        pub fn find_path(&self, start: Vec2, end: Vec2) -> Option<Vec<Vec2>> {
+            if self.is_visible(start, end) {
+                return Some(Vec::new());
+            }
+            
+            // The start and end are added as two extra nodes after the graph's nodes
+            let start_index = self.nodes.len();
+            let end_index = self.nodes.len() + 1;
+            let position = |i: usize| if i == start_index {start} else if i == end_index {end} else {self.nodes[i]};
+            
+            // Nodes visible from the end, which can be connected to it
+            let end_visible = (0..self.nodes.len()).map(|i| self.is_visible(self.nodes[i], end)).collect::<Vec<_>>();
+            
+            let mut cost = vec![f64::INFINITY; self.nodes.len() + 2];
+            let mut previous = vec![None; self.nodes.len() + 2];
+            let mut open = BinaryHeap::new();
+            
+            cost[start_index] = 0.0;
+            open.push(OpenNode { index: start_index, estimate: start.distance(end) });
+            
+            while let Some(OpenNode { index, estimate }) = open.pop() {
+                if index == end_index {
+                    break;
+                }
+                
+                // Skip stale entries that have since been reached more cheaply
+                if estimate > cost[index] + position(index).distance(end) {
+                    continue;
+                }
+                
+                // Neighbours of the start are found on demand, as it isn't part of the graph
+                let neighbours = if index == start_index {
+                    (0..self.nodes.len())
+                        .filter(|i| self.is_visible(start, self.nodes[*i]))
+                        .map(|i| (i, start.distance(self.nodes[i])))
+                        .collect::<Vec<_>>()
+                } else {
+                    let mut neighbours = self.edges[index].clone();
+                    if end_visible[index] {
+                        neighbours.push((end_index, self.nodes[index].distance(end)));
+                    }
+                    neighbours
+                };
+                
+                for (neighbour, dist) in neighbours {
+                    let new_cost = cost[index] + dist;
+                    if new_cost < cost[neighbour] {
+                        cost[neighbour] = new_cost;
+                        previous[neighbour] = Some(index);
+                        open.push(OpenNode { index: neighbour, estimate: new_cost + position(neighbour).distance(end) });
+                    }
+                }
+            }
+            
+            // Walk back from the end to recover the path (if the end was reached at all)
+            let mut path = Vec::new();
+            let mut current = previous[end_index]?;
+            while current != start_index {
+                path.push(self.nodes[current]);
+                current = previous[current].unwrap();
+            }
+            path.reverse();
+            
+            return Some(path);
+        }
+    }
+    
+    /// A node waiting to be explored by A*, ordered so that the lowest estimated total cost is explored first
+    struct OpenNode {
+        index: usize,
+        /// Cost so far plus the straight-line distance to the end
+        estimate: f64
+    }
+    
+    impl PartialEq for OpenNode {
+        fn eq(&self, other: &OpenNode) -> bool {
+            return self.estimate == other.estimate;
+        }
+    }
+    
+    impl Eq for OpenNode {}
+    
+    impl PartialOrd for OpenNode {
+        fn partial_cmp(&self, other: &OpenNode) -> Option<Ordering> {
+            return Some(self.cmp(other));
+        }
+    }
+    
+    impl Ord for OpenNode {
+        fn cmp(&self, other: &OpenNode) -> Ordering {
+            // Reversed, because BinaryHeap is a max-heap
+            return other.estimate.partial_cmp(&self.estimate).unwrap_or(Ordering::Equal);
+        }
+    }
+    
+    /// Return the shortest distance between line segments PQ and AB
+    fn segment_distance(p: Vec2, q: Vec2, a: Vec2, b: Vec2) -> f64 {
+        if segments_intersect(p, q, a, b) {
+            return 0.0;
+        }
+        
+        return segment_normal_vector(a, b, p).0
+            .min(segment_normal_vector(a, b, q).0)
+            .min(segment_normal_vector(p, q, a).0)
+            .min(segment_normal_vector(p, q, b).0);
+    }
+    
+}
@@ -0,0 +1,256 @@
+pub mod neighbours {
+    
+    use std::collections::HashMap;
+    
+    
+    /// What a pedestrian can see of a neighbour: (x, y, direction, radius, speed)
+    pub type NeighbourState = (f64, f64, f64, f64, f64);
+    
+    /// Verlet neighbour lists: for each walking pedestrian, the pedestrians that were within a cutoff distance plus a "skin" margin when the lists were last built.
+    /// 
+    /// As long as no pedestrian has moved more than half the skin since then, every pedestrian within the cutoff is still in the lists, so they only need rebuilding occasionally instead of scanning every pair of pedestrians every timestep.
+    /// They are also rebuilt whenever a pedestrian starts walking, so that it has a list of its own.
+    pub struct NeighbourLists {
+        /// The furthest a pedestrian reacts to its neighbours, in metres
+        cutoff: f64,
+        /// The extra margin the lists include beyond the cutoff, in metres
+        skin: f64,
+        
+        /// The position of each pedestrian when the lists were last built, by ID
+        built_positions: HashMap<u64, (f64, f64)>,
+        /// The IDs of the neighbours of each pedestrian when the lists were last built, by ID
+        lists: HashMap<u64, Vec<u64>>,
+        /// The number of times the lists have been built
+        build_count: usize,
+        
+        /// The index of each walking pedestrian in the simulation's list during the current timestep, by ID
+        current_indices: HashMap<u64, usize>,
+        /// Scratch space for the indices of one pedestrian's neighbours
+        neighbour_indices: Vec<usize>,
+        /// Scratch space for the neighbours of one pedestrian, split into those before & after it in the simulation's list: \[(x, y, direction, radius, speed)]
+        before: Vec<NeighbourState>,
+        after: Vec<NeighbourState>
+    }
+    
+    impl NeighbourLists {
+        /// Create empty neighbour lists, which are built on the first update
+        /// 
+        /// * `cutoff` - The furthest a pedestrian reacts to its neighbours, in metres
+        /// * `skin` - The extra margin to include beyond the cutoff, in metres. A larger skin means fewer rebuilds but longer lists.
+        pub fn new(cutoff: f64, skin: f64) -> NeighbourLists {
+            NeighbourLists {
+                cutoff,
+                skin,
+                built_positions: HashMap::new(),
+                lists: HashMap::new(),
+                build_count: 0,
+                current_indices: HashMap::new(),
+                neighbour_indices: Vec::new(),
+                before: Vec::new(),
+                after: Vec::new()
+            }
+        }
+        
+        /// Bring the lists up to date with the walking pedestrians at the start of a timestep, rebuilding them if a pedestrian has started walking or moved too far
+        /// 
+        /// * `ids` - The ID of each walking pedestrian, in the order of the simulation's list
+        /// * `positions` - The positions of the same pedestrians, in the same order: \[(x, y, direction, radius, speed)]
+        pub fn update(&mut self, ids: impl Iterator<Item = u64>, positions: &[NeighbourState]) {
+            self.current_indices.clear();
+            self.current_indices.extend(ids.enumerate().map(|(i, id)| (id, i)));
+            
+            let max_displacement_sq = (self.skin / 2.0) * (self.skin / 2.0);
+            let outdated = self.current_indices.iter().any(|(id, i)| {
+                match self.built_positions.get(id) {
+                    Some((x, y)) => (positions[*i].0 - x).powi(2) + (positions[*i].1 - y).powi(2) > max_displacement_sq,
+                    None => true
+                }
+            });
+            
+            if outdated {
+                self.build(positions);
+            }
+        }
+        
+        /// Build the lists from scratch, by checking every pair of pedestrians
+        fn build(&mut self, positions: &[NeighbourState]) {
+            let range_sq = (self.cutoff + self.skin) * (self.cutoff + self.skin);
+            
+            // The IDs in the order of the simulation's list
+            let mut ids = vec![0; positions.len()];
+            for (id, i) in &self.current_indices {
+                ids[*i] = *id;
+            }
+            
+            self.built_positions.clear();
+            self.lists.clear();
+            for (i, (x, y, _, _, _)) in positions.iter().enumerate() {
+                let neighbours = positions.iter().enumerate()
+                    .filter(|(j, (n_x, n_y, _, _, _))| *j != i && (n_x - x).powi(2) + (n_y - y).powi(2) <= range_sq)
+                    .map(|(j, _)| ids[j])
+                    .collect();
+                self.lists.insert(ids[i], neighbours);
+                self.built_positions.insert(ids[i], (*x, *y));
+            }
+            
+            self.build_count += 1;
+        }
+        
+        /// Return the neighbours of a pedestrian that are still walking, split into those before & after it in the simulation's list (each in list order), in the same form as the full lists of pedestrians
+        /// 
+        /// * `id` - The ID of the pedestrian
+        /// * `positions` - The positions of every walking pedestrian, in the order of the simulation's list, as given to `update`
+        pub fn get_neighbours(&mut self, id: u64, positions: &[NeighbourState]) -> (&[NeighbourState], &[NeighbourState]) {
+            self.neighbour_indices.clear();
+            if let Some(list) = self.lists.get(&id) {
+                // Pedestrians that have finished since the lists were built are left out
+                self.neighbour_indices.extend(list.iter().filter_map(|n_id| self.current_indices.get(n_id)));
+            }
+            self.neighbour_indices.sort_unstable();
+            
+            let index = self.current_indices[&id];
+            self.before.clear();
+            self.after.clear();
+            for n_index in &self.neighbour_indices {
+                if *n_index < index {
+                    self.before.push(positions[*n_index]);
+                } else {
+                    self.after.push(positions[*n_index]);
+                }
+            }
+            
+            return (&self.before, &self.after);
+        }
+        
+        /// Return the number of times the lists have been built
+        pub fn get_build_count(&self) -> usize {
+            return self.build_count;
+        }
+    }
+    
+    
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::sync::Arc;
+        use rand::{Rng, SeedableRng};
+        use rand_chacha::ChaCha8Rng;
+        use crate::simulation::simulator::simulator::{CrowdSim, SimArea};
+        use crate::simulation::behaviour::behaviour::Etiquette;
+        
+        const CUTOFF: f64 = 2.0;
+        const SKIN: f64 = 0.5;
+        
+        /// Scatter pedestrians over a 10m square
+        fn random_positions(count: usize, rng: &mut ChaCha8Rng) -> Vec<NeighbourState> {
+            return (0..count).map(|_| (rng.gen::<f64>() * 10.0, rng.gen::<f64>() * 10.0, 0.0, 0.2, 1.0)).collect();
+        }
+        
+        /// Check that every pedestrian within the cutoff of each pedestrian is in its lists, on the same side and in the same order as in the full list of pedestrians
+        fn assert_matches_brute_force(lists: &mut NeighbourLists, positions: &[NeighbourState]) {
+            let within_cutoff = |p: &NeighbourState, n: &NeighbourState| (n.0 - p.0).powi(2) + (n.1 - p.1).powi(2) <= CUTOFF * CUTOFF;
+            
+            for (i, position) in positions.iter().enumerate() {
+                let expected_before = positions[0..i].iter().filter(|n| within_cutoff(position, n)).copied().collect::<Vec<_>>();
+                let expected_after = positions[i+1..].iter().filter(|n| within_cutoff(position, n)).copied().collect::<Vec<_>>();
+                
+                let (before, after) = lists.get_neighbours(i as u64, positions);
+                assert_eq!(before.iter().filter(|n| within_cutoff(position, n)).copied().collect::<Vec<_>>(), expected_before, "pedestrian {}", i);
+                assert_eq!(after.iter().filter(|n| within_cutoff(position, n)).copied().collect::<Vec<_>>(), expected_after, "pedestrian {}", i);
+            }
+        }
+        
+        #[test]
+        fn lists_match_brute_force() {
+            let mut rng = ChaCha8Rng::seed_from_u64(1);
+            let mut positions = random_positions(100, &mut rng);
+            let mut lists = NeighbourLists::new(CUTOFF, SKIN);
+            
+            lists.update(0..100, &positions);
+            assert_matches_brute_force(&mut lists, &positions);
+            
+            // Every pedestrian wanders a little further each step, so the lists go out of date and are rebuilt along the way
+            for _ in 0..20 {
+                for position in &mut positions {
+                    position.0 += (rng.gen::<f64>() - 0.5) * SKIN / 2.0;
+                    position.1 += (rng.gen::<f64>() - 0.5) * SKIN / 2.0;
+                }
+                lists.update(0..100, &positions);
+                assert_matches_brute_force(&mut lists, &positions);
+            }
+            assert!(lists.get_build_count() > 1);
+        }
+        
+        #[test]
+        fn rebuilds_after_moving_half_the_skin() {
+            let mut positions = vec![(0.0, 0.0, 0.0, 0.2, 1.0), (1.0, 0.0, 0.0, 0.2, 1.0)];
+            let mut lists = NeighbourLists::new(CUTOFF, SKIN);
+            
+            lists.update(0..2, &positions);
+            assert_eq!(lists.get_build_count(), 1);
+            
+            // Just under half the skin: the lists are still valid
+            positions[0].0 = SKIN / 2.0 - 0.01;
+            lists.update(0..2, &positions);
+            assert_eq!(lists.get_build_count(), 1);
+            
+            // Just over half the skin, measured from where the lists were built
+            positions[0].0 = SKIN / 2.0 + 0.01;
+            lists.update(0..2, &positions);
+            assert_eq!(lists.get_build_count(), 2);
+            
+            // A pedestrian that has only just started walking has no list yet
+            positions.push((5.0, 5.0, 0.0, 0.2, 1.0));
+            lists.update(0..3, &positions);
+            assert_eq!(lists.get_build_count(), 3);
+            
+            // Finishing doesn't invalidate the lists of the others
+            positions.remove(1);
+            lists.update([0, 2].into_iter(), &positions);
+            assert_eq!(lists.get_build_count(), 3);
+            let (before, after) = lists.get_neighbours(2, &positions);
+            assert!(before.is_empty() && after.is_empty());
+        }
+        
+        /// Simulate a busy two-way corridor for a minute, with or without neighbour lists
+        fn simulate_corridor(neighbour_lists: Option<(f64, f64)>) -> CrowdSim {
+            let mut area = SimArea::new();
+            area.add_wall((-1.0,0.0), (32.0,0.0));
+            area.add_wall((-1.0,6.0), (32.0,6.0));
+            area.add_start_end_group(vec![(0.0,1.0), (0.0,3.0), (0.0,5.0)], vec![(30.0,1.0), (30.0,3.0), (30.0,5.0)]);
+            area.add_start_end_group(vec![(31.0,1.0), (31.0,3.0), (31.0,5.0)], vec![(1.0,1.0), (1.0,3.0), (1.0,5.0)]);
+            
+            let mut crowd_sim = CrowdSim::new(Arc::new(area), 2.0);
+            crowd_sim.set_seed(3);
+            if let Some((cutoff, skin)) = neighbour_lists {
+                crowd_sim.enable_neighbour_lists(cutoff, skin);
+            }
+            crowd_sim.add_pedestrian_set(40, 0, Arc::new(Etiquette::NoBias));
+            crowd_sim.add_pedestrian_set(40, 1, Arc::new(Etiquette::NoBias));
+            crowd_sim.randomise_pedestrian_order();
+            
+            for _ in 0..600 {
+                crowd_sim.simulate_timestep(0.1);
+            }
+            
+            return crowd_sim;
+        }
+        
+        #[test]
+        fn simulation_matches_brute_force() {
+            // The default parameters react to neighbours up to look_ahead_radius (1.8m) away
+            let brute_force = simulate_corridor(None);
+            let verlet = simulate_corridor(Some((2.0, 0.5)));
+            
+            let positions = |crowd_sim: &CrowdSim| crowd_sim.get_active_pedestrians().iter().map(|ped| (ped.get_id(), ped.x, ped.y)).collect::<Vec<_>>();
+            assert!(!brute_force.get_active_pedestrians().is_empty());
+            assert_eq!(positions(&verlet), positions(&brute_force));
+            assert_eq!(verlet.get_travel_times(), brute_force.get_travel_times());
+            
+            // The lists were reused between rebuilds, rather than rebuilt every timestep
+            let builds = verlet.get_neighbour_list_builds().unwrap();
+            assert!(builds > 1 && builds < 600, "{} builds", builds);
+        }
+    }
+    
+}
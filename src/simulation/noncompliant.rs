@@ -0,0 +1,77 @@
+pub mod noncompliant {
+    
+    use std::f64::consts::{PI, TAU};
+    
+    use crate::simulation::pedestrian::pedestrian::Walker;
+    use crate::simulation::behaviour::behaviour::BehaviourModel;
+    use crate::simulation::geom::geom::{nudge_angle, normalise_angle};
+    use crate::simulation::params::params::ContactModel;
+    
+    
+    /// The ways a non-compliant pedestrian breaks the usual conventions of walking in a crowd
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum NonCompliance {
+        /// Steer into oncoming pedestrians instead of out of their way
+        AgainstFlow,
+        /// Walk straight at the target, ignoring personal space and only stepping back from actual contact
+        IgnorePersonalSpace
+    }
+    
+    
+    /// A deliberately uncooperative pedestrian, as a stress test for the etiquette rules followed by everyone else.
+    /// 
+    /// It never slows down or turns aside for its neighbours, and is only kept from walking through them by being pushed apart on contact.
+    pub struct NonCompliant {
+        /// How the pedestrian breaks the conventions
+        rule: NonCompliance
+    }
+    
+    impl NonCompliant {
+        /// Create a new non-compliant behaviour model
+        pub fn new(rule: NonCompliance) -> NonCompliant {
+            NonCompliant {
+                rule
+            }
+        }
+    }
+    
+    impl BehaviourModel for NonCompliant {
+        fn name(&self) -> &str {
+            match self.rule {
+                NonCompliance::AgainstFlow => "non-compliant (against flow)",
+                NonCompliance::IgnorePersonalSpace => "non-compliant (ignores personal space)"
+            }
+        }
+        
+        fn react_to_neighbours(&self, walker: &mut Walker, time_scale: f64, other_pedestrians: &[(f64, f64, f64, f64, f64)]) {
+            let params = *walker.get_params();
+            
+            for (n_x, n_y, n_dir, n_radius, _) in other_pedestrians {
+                let dist = ((walker.x - n_x)*(walker.x - n_x) + (walker.y - n_y)*(walker.y - n_y)).sqrt();
+                
+                // The direction the neighbour is in, between -π and π
+                let abs_neighbour_angle = (n_y - walker.y).atan2(n_x - walker.x);
+                
+                // Intersecting hitbox: move apart, without stopping (unless touching pedestrians push each other apart instead)
+                if params.contact_model == ContactModel::Separate && dist < params.radius + n_radius {
+                    let k = params.radius + n_radius - dist;
+                    walker.x -= abs_neighbour_angle.cos() * k;
+                    walker.y -= abs_neighbour_angle.sin() * k;
+                }
+                
+                if self.rule == NonCompliance::AgainstFlow && dist < walker.get_look_ahead_radius() {
+                    // The direction the neighbour is in, relative to the direction of travel of this pedestrian, between 0 and 2π
+                    let travel_rel_angle = normalise_angle(abs_neighbour_angle - walker.facing_direction);
+                    let direction_difference = normalise_angle(walker.facing_direction - n_dir);
+                    
+                    // Oncoming and within view in front: head straight for them
+                    let in_front = travel_rel_angle <= params.look_ahead_fov/2.0 || travel_rel_angle >= TAU-params.look_ahead_fov/2.0;
+                    if in_front && direction_difference > PI/2.0 && direction_difference < 3.0*PI/2.0 {
+                        walker.facing_direction = nudge_angle(walker.facing_direction, abs_neighbour_angle, params.opposing_repulsion*time_scale);
+                    }
+                }
+            }
+        }
+    }
+    
+}
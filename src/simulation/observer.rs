@@ -0,0 +1,32 @@
+pub mod observer {
+    
+    use crate::simulation::pedestrian::pedestrian::Walker;
+    use crate::simulation::simulator::simulator::{CrowdSim, TimingEvent, StallEvent};
+    
+    
+    /// Receives callbacks as things happen in a simulation, so that custom metrics can be collected without modifying `CrowdSim`.
+    /// 
+    /// Observers are registered with `CrowdSim::add_observer`. Every callback does nothing by default, so an observer only needs to implement the ones it uses.
+    /// Since the simulation takes ownership of its observers, an observer that needs to hand its results back can share them, e.g. through an `Arc<Mutex<...>>`.
+    pub trait Observer: Send {
+        /// Called when a pedestrian starts walking, after it has been placed at its start position
+        /// 
+        /// * `time` - The current simulation time, in seconds
+        fn on_pedestrian_spawned(&mut self, _time: f64, _ped: &Walker) {}
+        
+        /// Called when a pedestrian reaches its destination, just before it is removed from the walking pedestrians
+        /// 
+        /// * `time` - The current simulation time, in seconds
+        fn on_pedestrian_finished(&mut self, _time: f64, _ped: &Walker) {}
+        
+        /// Called when a pedestrian passes its second timing boundary, with the event recorded for it
+        fn on_timing_boundary_crossed(&mut self, _event: &TimingEvent, _ped: &Walker) {}
+        
+        /// Called when a pedestrian stalls (see the `stall_time` parameter), once it has started recovering, with the event recorded for it
+        fn on_pedestrian_stalled(&mut self, _event: &StallEvent, _ped: &Walker) {}
+        
+        /// Called at the end of every timestep, once the simulation time has advanced and finished pedestrians have been removed
+        fn on_timestep_completed(&mut self, _crowd_sim: &CrowdSim) {}
+    }
+    
+}
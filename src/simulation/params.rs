@@ -0,0 +1,436 @@
+pub mod params {
+    
+    use std::f64::consts::{PI, TAU};
+    use std::io;
+    use serde::{Deserialize, Serialize};
+    use rand::Rng;
+    
+    
+    /// Tunable parameters of the pedestrian model.
+    /// 
+    /// Every field can be set from the `[params]` table of a scenario file; any field that is left out keeps its default value.
+    #[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+    #[serde(default)]
+    pub struct SimParams {
+        /// The acceleration of a pedestrian, in m⋅s^-2
+        pub accel: f64,
+        
+        /// The lower and upper bounds for the pedestrian target speeds to be selected from
+        pub target_speed_bounds: (f64, f64),
+        
+        /// The slowest a pedestrian will go when avoiding a collision or slowing for someone in front
+        pub minimum_speed: f64,
+        
+        /// A multiplier applied to destination alignment
+        pub direction_change_factor: f64,
+        
+        /// The radius of a pedestrian's body, in metres
+        pub radius: f64,
+        
+        /// Personal space: compressible radius of collision-avoidance, in metres
+        pub pspace_radius: f64,
+        
+        /// The distance a pedestrian looks ahead for obstacles, in metres
+        pub look_ahead_radius: f64,
+        /// How far ahead in time a pedestrian looks for obstacles, in seconds: its look-ahead distance is this times its current speed,
+        /// and how close an oncoming pedestrian gets before it slows down is scaled with it (0 = always look `look_ahead_radius` ahead)
+        pub look_ahead_time: f64,
+        /// The distance a pedestrian looks side-to-side for obstacles, in metres
+        pub look_beside_radius: f64,
+        
+        /// The angle-range a pedestrian looks ahead for obstacles, in radians
+        pub look_ahead_fov: f64,
+        /// The angle-range a pedestrian looks side-to-side for obstacles, in radians
+        pub look_beside_fov: f64,
+        /// The angle-range a pedestrian looks ahead to avoid imminent collisions, in radians
+        pub collision_avoidance_fov: f64,
+        
+        /// Intensity of which a pedestrian changes its facing direction when another pedestrian is in front and travelling in the opposite direction
+        pub opposing_repulsion: f64,
+        
+        /// The speed at which a pedestrian changes its facing direction when within the personal space radius
+        pub pspace_repulsion: f64,
+        
+        /// The deceleration of a pedestrian when another pedestrian is oncoming, in m⋅s^-2
+        pub opposing_decel: f64,
+        
+        /// The deceleration of a pedestrian when behind another pedestrian, in m⋅s^-2
+        pub following_decel: f64,
+        
+        /// The intensity of repulsion from a wall within the personal space radius
+        pub wall_repulsion: f64,
+        
+        /// Intensity of random noise added to pedestrian speed
+        pub speed_noise_factor: f64,
+        /// Intensity of random noise added to pedestrian facing direction
+        pub direction_noise_factor: f64,
+        
+        /// Intensity of bias (to facing direction) caused by Etiquette::LeftBias or Etiquette::RightBias
+        pub etiquette_bias_factor: f64,
+        
+        /// The length of the sliding window that heading jitter is measured over, in seconds
+        pub heading_window: f64,
+        /// Time constant of the exponential smoothing applied to the drawn facing direction, in seconds (0 = draw the raw facing direction).
+        /// This only changes how pedestrians are drawn, not how they move.
+        pub heading_smoothing_time: f64,
+        
+        /// How a pedestrian chooses between the end positions of its group, or between exits during an evacuation
+        pub exit_choice: ExitChoice,
+        /// How often a pedestrian reconsiders its choice of end position, in seconds (0 = only choose once)
+        pub exit_choice_interval: f64,
+        
+        /// The order a pedestrian reacts to its neighbours in, and which of them it reacts to
+        pub neighbour_order: NeighbourOrder,
+        /// Whether a pedestrian only perceives the neighbours it has a line of sight to, past every wall (false = it perceives them through walls, e.g. around corners)
+        pub wall_occlusion: bool,
+        
+        /// Whether a pedestrian held up behind a slower one walking the same way swings out to pass it, and on which side
+        pub overtaking: Overtaking,
+        /// A pedestrian only overtakes one that is walking slower than this fraction of its own target speed
+        pub overtake_speed_ratio: f64,
+        
+        /// How far ahead a pedestrian predicts collisions with its neighbours, by extrapolating their current velocities, in seconds (0 = no anticipation).
+        /// The sooner a predicted collision, the more the pedestrian turns away from where it would happen and slows down.
+        pub anticipation_horizon: f64,
+        
+        /// The fastest a pedestrian can turn, in rad/s (0 = no limit). This applies to every change of facing direction during a timestep, including turning away from walls.
+        pub max_turn_rate: f64,
+        
+        /// How a pedestrian's position is advanced from its velocity each timestep
+        pub integrator: Integrator,
+        
+        /// How long a pedestrian can go without getting any closer to where it is heading before it counts as stalled, in seconds (0 = never)
+        pub stall_time: f64,
+        /// What a pedestrian does once it has stalled
+        pub stall_recovery: StallRecovery,
+        
+        /// How much exposure to hazards, such as smoke, a pedestrian can take before it is incapacitated, in seconds at full intensity (0 = never)
+        pub hazard_tolerance: f64,
+        
+        /// How pedestrians that touch each other are kept apart
+        pub contact_model: ContactModel,
+        /// The body compression force between touching pedestrians per metre of overlap, per unit mass, in s^-2 (ContactModel::Granular only)
+        pub contact_stiffness: f64,
+        /// The sliding friction between touching pedestrians per metre of overlap and per m/s of sliding, per unit mass, in m^-1⋅s^-1 (ContactModel::Granular only)
+        pub contact_friction: f64,
+        
+        /// The longest timestep that is simulated in one go, in seconds (0 = no limit).
+        /// Longer timesteps are divided into equal sub-steps, so that pedestrians don't jump through walls or past timing boundaries.
+        pub max_timestep: f64
+    }
+    
+    /// A strategy for choosing between several end positions (or exits)
+    #[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum ExitChoice {
+        /// Chosen at random when the pedestrian is added, and never reconsidered
+        Random,
+        /// The closest, by walking distance if a floor field is available or straight-line distance otherwise
+        Nearest,
+        /// The quickest to reach, allowing for the pedestrians already crowded around each one
+        LeastCongested,
+        /// The closest of those the pedestrian can see past walls and obstacles (the current choice is kept if none are visible)
+        NearestVisible
+    }
+    
+    /// The order a pedestrian reacts to its neighbours in during a timestep.
+    /// 
+    /// Each reaction changes the pedestrian's direction and speed before the next one, so the order affects the result.
+    /// Neighbours are always seen where they were at the start of the timestep, however they have moved since.
+    /// In a scenario file, this is written as `"two_pass"`, `"by_distance"` or `{ nearest = 6 }`.
+    #[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum NeighbourOrder {
+        /// The pedestrians simulated after this one in the simulation's list, then those simulated before it (the original order).
+        /// This depends on the order pedestrians happen to be stored in.
+        TwoPass,
+        /// Every neighbour in a single pass, nearest first, which doesn't depend on the order pedestrians are stored in
+        ByDistance,
+        /// Only the given number of nearest neighbours, nearest first
+        Nearest(usize)
+    }
+    
+    impl NeighbourOrder {
+        /// A short description of the order, used to label results
+        pub fn name(&self) -> String {
+            return match self {
+                NeighbourOrder::TwoPass => "two pass".to_string(),
+                NeighbourOrder::ByDistance => "by distance".to_string(),
+                NeighbourOrder::Nearest(count) => format!("{} nearest", count)
+            };
+        }
+    }
+    
+    /// Whether pedestrians overtake slower pedestrians walking the same way, and which side they pass on
+    #[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum Overtaking {
+        /// Slow down and follow behind (the original behaviour)
+        Off,
+        /// Pass on the left, as is usual where people keep right
+        Left,
+        /// Pass on the right, as is usual where people keep left
+        Right
+    }
+    
+    /// How pedestrians that touch each other are kept apart
+    #[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum ContactModel {
+        /// Move apart straight away, and stop if the other pedestrian is in front (the original behaviour)
+        Separate,
+        /// Push each other apart with a body compression force, and rub against each other with sliding friction, both in proportion to how much they overlap
+        /// (the granular forces of Helbing, Farkas & Vicsek, 2000). Pushes build up and are passed on through a dense crowd, as in a crush.
+        Granular
+    }
+    
+    /// What a pedestrian does once it has stalled: made no progress towards where it is heading for the `stall_time` parameter
+    #[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum StallRecovery {
+        /// Plan a new path to where it is heading (with path planning - otherwise the same as RandomWalk)
+        Replan,
+        /// Walk in a random direction for a short while, to break out of a deadlock
+        RandomWalk,
+        /// Leave the simulation, so that the run can still end
+        Despawn
+    }
+    
+    /// How a pedestrian's position is advanced from its velocity each timestep
+    #[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum Integrator {
+        /// Move at the velocity decided on during the timestep, i.e. semi-implicit Euler (the original behaviour)
+        Euler,
+        /// Move at the average of the velocity at the start of the timestep and the velocity decided on, i.e. second-order Runge-Kutta (RK2).
+        /// This is more accurate with long timesteps, where pedestrians speed up, slow down or turn a lot within one step.
+        Midpoint
+    }
+    
+    /// A distribution that a per-pedestrian parameter is sampled from
+    #[derive(Clone, Copy, Debug, Deserialize)]
+    #[serde(tag = "type", rename_all = "snake_case")]
+    pub enum ParamDistribution {
+        /// Every value between min and max is equally likely
+        Uniform { min: f64, max: f64 },
+        /// Normally distributed, resampled until the value is within 2 standard deviations of the mean
+        Normal { mean: f64, sd: f64 },
+        /// Log-normally distributed, with the given mean and standard deviation of the values themselves (not of their logarithm).
+        /// Always positive and skewed towards higher values, like measured walking speeds.
+        LogNormal { mean: f64, sd: f64 },
+        /// Normally distributed, resampled until the value is between min and max
+        TruncatedNormal { mean: f64, sd: f64, min: f64, max: f64 }
+    }
+    
+    /// Distributions that individual pedestrians' parameters are sampled from when they are added to a simulation.
+    /// 
+    /// Any parameter without a distribution keeps the shared value from `SimParams`.
+    #[derive(Clone, Copy, Debug, Default, Deserialize)]
+    #[serde(default)]
+    pub struct ParamVariation {
+        /// The target speed of a pedestrian, in m/s, instead of a uniform draw from `target_speed_bounds` (never below `minimum_speed`)
+        pub target_speed: Option<ParamDistribution>,
+        /// The radius of a pedestrian's body, in metres
+        pub radius: Option<ParamDistribution>,
+        /// The acceleration of a pedestrian, in m⋅s^-2
+        pub accel: Option<ParamDistribution>,
+        /// Personal space: compressible radius of collision-avoidance, in metres
+        pub pspace_radius: Option<ParamDistribution>,
+        /// The speed at which a pedestrian changes its facing direction when within the personal space radius
+        pub pspace_repulsion: Option<ParamDistribution>,
+        /// Intensity of which a pedestrian changes its facing direction when another pedestrian is in front and travelling in the opposite direction
+        pub opposing_repulsion: Option<ParamDistribution>
+    }
+    
+    /// A class of pedestrians with its own walking speed and body, such as children or wheelchair users.
+    /// 
+    /// In a scenario file, each class is a `[[demographics]]` table. When there are any classes, every pedestrian added is put in one of them at random, instead of drawing its speed from `target_speed_bounds`.
+    #[derive(Clone, Debug, Deserialize)]
+    pub struct Demographic {
+        /// The name of the class, which its pedestrians are tagged with as a cohort so that their results can be reported separately
+        pub name: String,
+        /// The share of pedestrians in this class. The shares are divided by their total, so they don't have to add up to 1.
+        pub fraction: f64,
+        /// The distribution that the target speed of each pedestrian in the class is sampled from, in m/s
+        pub speed: ParamDistribution,
+        /// The radius of the body of pedestrians in the class, in metres (None = the usual radius)
+        #[serde(default)]
+        pub radius: Option<f64>,
+        /// The acceleration of pedestrians in the class, in m⋅s^-2 (None = the usual acceleration)
+        #[serde(default)]
+        pub accel: Option<f64>
+    }
+    
+    impl ParamDistribution {
+        /// Draw a single value from the distribution
+        pub fn sample<R: Rng>(&self, rng: &mut R) -> f64 {
+            match *self {
+                ParamDistribution::Uniform { min, max } => {
+                    if max <= min {
+                        return min;
+                    }
+                    return rng.gen_range(min..max);
+                },
+                ParamDistribution::Normal { mean, sd } => {
+                    if sd <= 0.0 {
+                        return mean;
+                    }
+                    loop {
+                        let z = standard_normal(rng);
+                        
+                        if z.abs() <= 2.0 {
+                            return mean + sd * z;
+                        }
+                    }
+                },
+                ParamDistribution::LogNormal { mean, sd } => {
+                    if sd <= 0.0 || mean <= 0.0 {
+                        return mean;
+                    }
+                    // The mean and standard deviation of the logarithm of the values
+                    let sigma_squared = (1.0 + (sd*sd)/(mean*mean)).ln();
+                    let mu = mean.ln() - sigma_squared/2.0;
+                    
+                    return (mu + sigma_squared.sqrt() * standard_normal(rng)).exp();
+                },
+                ParamDistribution::TruncatedNormal { mean, sd, min, max } => {
+                    if sd <= 0.0 || max <= min {
+                        return mean.clamp(min.min(max), max.max(min));
+                    }
+                    // Give up after many attempts if the range is far out in a tail, rather than looping (almost) forever
+                    for _ in 0..TRUNCATION_ATTEMPTS {
+                        let value = mean + sd * standard_normal(rng);
+                        
+                        if value >= min && value <= max {
+                            return value;
+                        }
+                    }
+                    return mean.clamp(min, max);
+                }
+            }
+        }
+        
+        /// Return the mean and standard deviation of the distribution, ignoring any truncation
+        pub fn mean_sd(&self) -> (f64, f64) {
+            match *self {
+                ParamDistribution::Uniform { min, max } => ((min + max) / 2.0, (max - min).abs() / 12.0_f64.sqrt()),
+                ParamDistribution::Normal { mean, sd } |
+                ParamDistribution::LogNormal { mean, sd } |
+                ParamDistribution::TruncatedNormal { mean, sd, .. } => (mean, sd)
+            }
+        }
+    }
+    
+    /// The number of times a value is drawn from a truncated distribution before settling for the nearest bound
+    const TRUNCATION_ATTEMPTS: usize = 1000;
+    
+    /// Draw a single value from the standard normal distribution, with the Box-Muller transform
+    fn standard_normal<R: Rng>(rng: &mut R) -> f64 {
+        let u1 = 1.0 - rng.gen::<f64>();
+        let u2 = rng.gen::<f64>();
+        return (-2.0 * u1.ln()).sqrt() * (TAU * u2).cos();
+    }
+    
+    impl SimParams {
+        /// Return a copy of these parameters with some parameters sampled individually, for a single pedestrian
+        pub fn sample_individual<R: Rng>(&self, variation: &ParamVariation, rng: &mut R) -> SimParams {
+            let mut params = *self;
+            
+            if let Some(distribution) = variation.radius {
+                params.radius = distribution.sample(rng);
+            }
+            if let Some(distribution) = variation.accel {
+                params.accel = distribution.sample(rng);
+            }
+            if let Some(distribution) = variation.pspace_radius {
+                params.pspace_radius = distribution.sample(rng);
+            }
+            if let Some(distribution) = variation.pspace_repulsion {
+                params.pspace_repulsion = distribution.sample(rng);
+            }
+            if let Some(distribution) = variation.opposing_repulsion {
+                params.opposing_repulsion = distribution.sample(rng);
+            }
+            
+            return params;
+        }
+        
+        /// Return the value of a numeric parameter, by the name it has in a scenario file
+        pub fn get_param(&self, name: &str) -> io::Result<f64> {
+            let table = self.get_table()?;
+            return match table.get(name) {
+                Some(toml::Value::Float(value)) => Ok(*value),
+                Some(toml::Value::Integer(value)) => Ok(*value as f64),
+                Some(_) => Err(io::Error::new(io::ErrorKind::InvalidData, format!("Parameter \"{}\" isn't a number", name))),
+                None => Err(io::Error::new(io::ErrorKind::InvalidData, format!("Unknown parameter \"{}\"", name)))
+            };
+        }
+        
+        /// Return a copy of these parameters with one numeric parameter changed, by the name it has in a scenario file
+        pub fn with_param(&self, name: &str, value: f64) -> io::Result<SimParams> {
+            let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+            
+            let mut table = self.get_table()?;
+            match table.get_mut(name) {
+                Some(toml::Value::Float(field)) => {*field = value},
+                Some(toml::Value::Integer(field)) => {*field = value.round() as i64},
+                Some(_) => {return Err(invalid(format!("Parameter \"{}\" isn't a number", name)))},
+                None => {return Err(invalid(format!("Unknown parameter \"{}\"", name)))}
+            }
+            
+            return table.try_into().map_err(|e: toml::de::Error| invalid(e.to_string()));
+        }
+        
+        /// Return the parameters as they would be written in a scenario file
+        fn get_table(&self) -> io::Result<toml::Table> {
+            return toml::Table::try_from(self).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()));
+        }
+    }
+    
+    impl Default for SimParams {
+        /// The calibrated parameters of the original simulation
+        fn default() -> SimParams {
+            SimParams {
+                accel: 0.8,
+                target_speed_bounds: (1.25, 1.5),
+                minimum_speed: 0.4,
+                direction_change_factor: 1.0,
+                radius: 0.205,
+                pspace_radius: 0.856,
+                look_ahead_radius: 1.8,
+                look_ahead_time: 0.0,
+                look_beside_radius: 1.2,
+                look_ahead_fov: PI/2.0,
+                look_beside_fov: PI/2.0,
+                collision_avoidance_fov: PI,
+                opposing_repulsion: 0.4,
+                pspace_repulsion: 1.5,
+                opposing_decel: 0.8 * 1.1,
+                following_decel: 0.8 * 1.1,
+                wall_repulsion: 0.2,
+                speed_noise_factor: 0.8,
+                direction_noise_factor: 0.4,
+                etiquette_bias_factor: 0.25,
+                heading_window: 2.0,
+                heading_smoothing_time: 0.0,
+                exit_choice: ExitChoice::Random,
+                exit_choice_interval: 2.0,
+                neighbour_order: NeighbourOrder::TwoPass,
+                wall_occlusion: false,
+                overtaking: Overtaking::Off,
+                overtake_speed_ratio: 0.9,
+                anticipation_horizon: 0.0,
+                max_turn_rate: 0.0,
+                integrator: Integrator::Euler,
+                stall_time: 0.0,
+                stall_recovery: StallRecovery::Replan,
+                hazard_tolerance: 0.0,
+                contact_model: ContactModel::Separate,
+                contact_stiffness: 1500.0,
+                contact_friction: 3000.0,
+                max_timestep: 0.05
+            }
+        }
+    }
+    
+}
@@ -2,85 +2,138 @@ pub mod pedestrian {
     
     use std::f64::consts::{PI, TAU};
     use std::sync::Arc;
+    use std::collections::VecDeque;
     use raylib::{drawing::{RaylibDrawHandle, RaylibDraw}, color::Color, math::Vector2};
-    use rand;
+    use rand::Rng;
+    use serde::{Deserialize, Serialize};
     
-    use crate::simulation::simulator::simulator::SimArea;
+    use crate::simulation::simulator::simulator::{SimArea, DrawLayers, EXIT_QUEUE_RADIUS};
+    use crate::simulation::behaviour::behaviour::{BehaviourModel, Etiquette};
+    use crate::simulation::params::params::{SimParams, ExitChoice, NeighbourOrder, Overtaking, Integrator, ContactModel, StallRecovery};
+    use crate::simulation::geom::geom::{Vec2, Zone, nudge_angle, normalise_angle, wrap_angle};
+    use crate::simulation::navigation::navigation::Navigation;
+    use crate::simulation::junction::junction::GIVE_WAY_PATIENCE;
+    use crate::simulation::luggage::luggage::{Luggage, capsule_normal_vector};
+    use crate::simulation::queue::queue::QUEUE_PLACE_RADIUS;
+    use crate::simulation::walkway::walkway::WalkwaySide;
+    use crate::simulation::vehicle::vehicle::VehicleFootprint;
+    use crate::simulation::herding::herding::Herding;
     
     
-    /// The acceleration of a pedestrian, in m⋅s^-2
-    const PEDESTRIAN_ACCEL: f64 = 0.8;
+    /// The distance from a waypoint that a pedestrian needs to be to move on to the next one, in metres
+    const WAYPOINT_RADIUS: f64 = 1.0;
     
-    /// The lower and upper bounds for the pedestrian target speeds to be selected from
-    pub const PEDESTRIAN_TARGET_SPEED_BOUNDS: (f64, f64) = (1.25, 1.5);
+    /// How often a pedestrian following a planned path checks that it can still see where it is heading, in seconds
+    const REPLAN_INTERVAL: f64 = 1.0;
     
-    /// The slowest a pedestrian will go when avoiding a collision or slowing for someone in front
-    pub const PEDESTRIAN_MINIMUM_SPEED: f64 = 0.4;
+    /// How far ahead along the floor field a pedestrian steers towards, in metres
+    const FLOOR_FIELD_LOOK_AHEAD: f64 = 1.0;
     
-    /// A multiplier applied to destination alignment
-    const PEDESTRIAN_DIRECTION_CHANGE_FACTOR: f64 = 1.0;
+    /// The time each pedestrian queueing at an end position is expected to delay a pedestrian heading for it, in seconds (for ExitChoice::LeastCongested)
+    const QUEUE_DELAY_PER_PEDESTRIAN: f64 = 1.0;
     
-    /// The radius of a pedestrian's body, in metres
-    const PEDESTRIAN_RADIUS: f64 = 0.205;
+    /// The furthest a signalled intended direction can differ from the facing direction, in radians
+    const MAX_SIGNALLED_TURN: f64 = PI/2.0;
     
-    /// Personal space: compressible radius of collision-avoidance, in metres
-    const PEDESTRIAN_PSPACE_RADIUS: f64 = 0.856;
+    /// The most times a pedestrian can slide off one wall or obstacle into another in a single timestep
+    const MAX_WALL_SLIDES: usize = 3;
     
-    /// The distance a pedestrian looks ahead for obstacles, in metres
-    const PEDESTRIAN_LOOK_AHEAD_RADIUS: f64 = 1.8;
-    /// The distance a pedestrian looks side-to-side for obstacles, in metres
-    const PEDESTRIAN_LOOK_BESIDE_RADIUS: f64 = 1.2;
+    /// How close a pedestrian needs to get to the place it stands still at, in metres
+    const DWELL_RADIUS: f64 = 0.3;
     
-    /// The angle-range a pedestrian looks ahead for obstacles, in metres
-    const PEDESTRIAN_LOOK_AHEAD_FOV: f64 = PI/2.0;
-    /// The angle-range a pedestrian looks side-to-side for obstacles, in metres
-    const PEDESTRIAN_LOOK_BESIDE_FOV: f64 = PI/2.0;
-    /// The angle-range a pedestrian looks ahead to avoid imminent collisions, in metres
-    const PEDESTRIAN_COLLISION_AVOIDANCE_FOV: f64 = PI;
+    /// How long a pedestrian keeps trying to reach the place it stands still at before standing where it is instead, in seconds (e.g. if someone else is already standing there)
+    const DWELL_PATIENCE: f64 = 60.0;
     
-    /// Intensity of which a pedestrian changes its facing direction when another pedestrian is in front and travelling in the opposite direction
-    const PEDESTRIAN_OPPOSING_REPULSION: f64 = 0.4;
+    /// How far off its target direction a pedestrian turns to swing out and overtake, in radians
+    const OVERTAKE_ANGLE: f64 = PI/6.0;
     
-    /// The speed at which a pedestrian changes its facing direction when within the personal space radius
-    const PEDESTRIAN_PSPACE_REPULSION: f64 = 1.5;
+    /// The rate at which an overtaking pedestrian turns to swing out, as a fraction of the remaining turn per second
+    const OVERTAKE_TURN_RATE: f64 = 2.0;
     
-    /// The deceleration of a pedestrian when another pedestrian is oncoming
-    const PEDESTRIAN_OPPOSING_DECEL: f64 = PEDESTRIAN_ACCEL * 1.1;
+    /// The gap left between an overtaking pedestrian and the pedestrian it is passing, in metres
+    const OVERTAKE_CLEARANCE: f64 = 0.2;
     
-    /// The deceleration of a pedestrian when behind another pedestrian
-    const PEDESTRIAN_FOLLOWING_DECEL: f64 = PEDESTRIAN_ACCEL * 1.1;
+    /// How long a pedestrian keeps trying to overtake before falling back in behind, in seconds
+    const OVERTAKE_PATIENCE: f64 = 6.0;
     
-    /// The intensity of repulsion from a wall within the personal space radius
-    const WALL_REPULSION: f64 = 0.2;
+    /// How quickly a pedestrian turns away from a collision it predicts is about to happen, in rad/s (less for collisions further off)
+    const ANTICIPATION_TURN_RATE: f64 = 1.5;
     
-    /// Intensity of random noise added to pedestrian speed
-    const PEDESTRIAN_SPEED_NOISE_FACTOR: f64 = 0.8;
-    /// Intensity of random noise added to pedestrian facing direction
-    const PEDESTRIAN_DIRECTION_NOISE_FACTOR: f64 = 0.4;
+    /// The fraction of its speed a pedestrian sheds per second when it predicts a collision is about to happen (less for collisions further off)
+    const ANTICIPATION_DECEL: f64 = 1.0;
     
-    /// Intensity of bias (to facing direction) caused by Etiquette::LEFT_BIAS or Etiquette::RIGHT_BIAS
-    const PEDESTRIAN_ETIQUETTE_BIAS_FACTOR: f64 = 0.25;
+    /// How long it takes for the velocity a pedestrian has been pushed with by its neighbours to die away once they stop touching, in seconds (ContactModel::Granular only)
+    const PUSH_RELAXATION_TIME: f64 = 0.5;
     
+    /// The shortest a pedestrian's look-ahead distance gets as it slows down, as a fraction of the `look_ahead_radius` parameter, so that a stopped pedestrian still notices what is just ahead of it
+    const MIN_LOOK_AHEAD_SCALE: f64 = 0.25;
     
-    /// Whether or not to draw extra zones or lines tied to pedestrians
-    const DRAW_EXTRA_PEDESTRIAN_INFO: bool = true;
+    /// How much closer a pedestrian needs to get to where it is heading for it to count as making progress, in metres (see the `stall_time` parameter)
+    const STALL_PROGRESS: f64 = 0.5;
+    
+    /// How fast a pedestrian inside a hazard is pushed away from it, as a multiple of its desired speed at full intensity
+    const HAZARD_REPULSION: f64 = 3.0;
+    
+    /// How long a stalled pedestrian walks in a random direction for, in seconds (StallRecovery::RandomWalk)
+    const RANDOM_WALK_DURATION: f64 = 2.0;
+    
+    /// The fastest a followed pedestrian is expected to move, in m/s. It is only recognised again at the next timestep if it has moved less than this allows.
+    const OVERTAKE_TRACKING_SPEED: f64 = 3.0;
     
     
     const PEDESTRIAN_COLOUR: &str = "505050";
     const PEDESTRIAN_ZONE_COLOUR: &str = "7D7D7D";
+    const LUGGAGE_COLOUR: &str = "8C6A3F";
     const FACING_LINE_COLOUR: &str = "920B07";
     const TARGET_LINE_COLOUR: &str = "2D8183";
     
     
-    // Etiquette option enum
-    #[derive(PartialEq)]
-    #[derive(Clone)]
-    pub enum Etiquette {
-        LeftBias,   // Stay to the left
-        RightBias,  // Stay to the right
-        NoBias      // Walk directly towards the destination
+    /// What a pedestrian takes into account when steering during a timestep, passed to each of the steering rules
+    struct Surroundings<'a> {
+        /// The length of the timestep, in seconds
+        time_scale: f64,
+        /// The angle the pedestrian should be facing to reach its next waypoint or destination, between -π and π
+        target_angle: f64,
+        /// The behaviour model the pedestrian follows
+        behaviour: &'a dyn BehaviourModel,
+        /// Whether each door in the area is open
+        door_states: &'a [bool],
+        /// The other pedestrians before this one in the simulation's list, as they were at the start of the timestep: \[(x, y, direction, radius, speed)]
+        other_pedestrians_before: &'a [(f64, f64, f64, f64, f64)],
+        /// The other pedestrians after this one in the simulation's list, as they were at the start of the timestep: \[(x, y, direction, radius, speed)]
+        other_pedestrians_after: &'a [(f64, f64, f64, f64, f64)]
     }
     
+    /// A rule that adjusts the velocity (facing direction & speed) a pedestrian decides on, in response to its surroundings
+    type SteeringRule = fn(&mut Walker, &Surroundings);
+    
+    /// The steering rules, in the order they are applied each timestep. Each rule adjusts the velocity left by the ones before it,
+    /// so a new rule can be added by putting it in the right place in this list.
+    const STEERING_RULES: [SteeringRule; 6] = [
+        // Turn to be better aligned with the destination
+        |walker, s| s.behaviour.steer_towards_target(walker, s.target_angle, s.time_scale),
+        // Swing out to pass a slower pedestrian ahead, before reacting to it
+        |walker, s| walker.update_overtaking(s.time_scale, s.target_angle, s.door_states, s.other_pedestrians_before, s.other_pedestrians_after),
+        // Turn and slow down ahead of time for the neighbour it is predicted to collide with first
+        |walker, s| if walker.params.anticipation_horizon > 0.0 {
+            walker.anticipate_collisions(s.time_scale, s.other_pedestrians_before, s.other_pedestrians_after);
+        },
+        |walker, s| walker.react_to_neighbours(s.behaviour, s.time_scale, s.other_pedestrians_before, s.other_pedestrians_after),
+        |walker, s| walker.give_way_at_junctions(s.time_scale, s.other_pedestrians_before, s.other_pedestrians_after),
+        // Be pushed by the neighbours it is touching (instead of simply moving apart from them in react_to_neighbours)
+        |walker, s| if walker.params.contact_model == ContactModel::Granular {
+            match walker.contact_impulse.take() {
+                Some((impulse_x, impulse_y)) => walker.push_velocity = (walker.push_velocity.0 + impulse_x, walker.push_velocity.1 + impulse_y),
+                None => walker.apply_contact_forces(s.time_scale, s.other_pedestrians_before, s.other_pedestrians_after)
+            }
+        }
+    ];
+    
+    
+    /// A simulated pedestrian.
+    /// 
+    /// Walkers can be saved in checkpoints: everything but their environment, navigation and behaviour model is serialised, and those are attached again with `attach` when the checkpoint is restored.
+    #[derive(Clone, Serialize, Deserialize)]
     pub struct Walker {
         /// Absolute x-coordinate the pedestrian, in metres.
         pub x: f64,
@@ -90,349 +143,1523 @@ pub mod pedestrian {
         pub facing_direction: f64,
         
         /// Preferred walking speed, in m/s.
-        target_speed: f64,
+        pub target_speed: f64,
         /// Instantaneous walking speed, in m/s.
-        inst_speed: f64,
+        pub inst_speed: f64,
         
         /// The 2D environment that the pedestrian is within
+        #[serde(skip, default = "detached_environment")]
         environment: Arc<SimArea>,
-        /// The group that the pedestrian is a part of
+        /// Identifies the pedestrian within its simulation, so that its results can be joined back to its attributes
+        id: u64,
+        /// The group that the pedestrian is a part of, which it starts from
         group: usize,
+        /// The group whose end positions the pedestrian walks to (the same as `group` unless it was added with origin-destination demand)
+        destination_group: usize,
+        /// The names of the cohorts the pedestrian has been tagged with, for filtering results
+        cohorts: Vec<String>,
+        /// The ID of the start zone the pedestrian starts in
+        start_location: usize,
         /// The ID of the target location that the pedestrian walks towards
         target_location: usize,
+        /// Where in its target location's end zone the pedestrian walks to, as two numbers from 0 to 1 (see `Zone::get_point`)
+        end_point: (f64, f64),
+        /// The designated exit the pedestrian is heading for instead of its target location (None = not evacuating)
+        exit: Option<usize>,
+        /// The time until the pedestrian next reconsiders which end position (or exit) to head for, in seconds
+        exit_choice_timer: f64,
+        /// Intermediate points still to be passed through before walking to the target location, in order
+        waypoints: VecDeque<(f64, f64)>,
+        /// How the pedestrian finds its way around geometry (None = walk straight towards each waypoint)
+        #[serde(skip)]
+        navigation: Option<Navigation>,
+        /// Points on the planned path to the next waypoint (or the target location), in order
+        planned_path: VecDeque<(f64, f64)>,
+        /// The time since the planned path was last checked, in seconds
+        replan_timer: f64,
+        
+        /// How long the pedestrian has been giving way at a junction without a break, in seconds
+        give_way_timer: f64,
+        /// The total time the pedestrian has been held back giving way at junctions, in seconds
+        junction_delay: f64,
+        
+        /// The pedestrian ahead that this pedestrian is following or overtaking (only tracked if the `overtaking` parameter is enabled)
+        #[serde(default)]
+        overtaking: Option<OvertakeState>,
+        
+        /// How much longer the pedestrian stays stopped for, in seconds (0 = not stopped)
+        #[serde(default)]
+        stop_timer: f64,
+        /// Whether the pedestrian has stopped to chat with someone it met (each pedestrian only stops once)
+        #[serde(default)]
+        has_chatted: bool,
+        /// Where the pedestrian stands still for a while before carrying on or leaving (None = it walks straight to its destination)
+        #[serde(default)]
+        dwell: Option<Dwell>,
+        /// The bulky item the pedestrian takes along, such as a suitcase or a pram (None = nothing)
+        #[serde(default)]
+        luggage: Option<Luggage>,
+        /// Where the pedestrian stands in, or joins, the queue it is waiting in (None = it isn't queueing). This is set by the queue every timestep.
+        #[serde(default)]
+        queue_place: Option<(f64, f64)>,
+        /// Whether the pedestrian stands, rather than walks, on the moving walkway it is on (None = it isn't on a walkway with an etiquette)
+        #[serde(default)]
+        walkway_standing: Option<bool>,
+        /// How the pedestrian follows the crowd because it doesn't know its way (None = it knows its way)
+        #[serde(default)]
+        herding: Option<Herding>,
+        /// The indices of the signs in the area that the pedestrian has read
+        #[serde(default)]
+        signs_read: Vec<usize>,
+        /// The waypoint the last sign the pedestrian read sent it to (None = it hasn't read a sign with one)
+        #[serde(default)]
+        sign_waypoint: Option<(f64, f64)>,
+        /// The number of times the pedestrian has turned to head more than 90° away from the way to its destination while following the crowd
+        #[serde(default)]
+        wrong_turns: usize,
+        /// Whether the pedestrian was heading more than 90° away from the way to its destination in the last timestep
+        #[serde(default)]
+        heading_wrong: bool,
+        
+        /// The rate at which the pedestrian decided to turn in the last timestep, excluding noise, in rad/s
+        turn_rate: f64,
+        /// The facing direction at the start of the current timestep, which the `max_turn_rate` parameter limits turning from
+        #[serde(default)]
+        timestep_start_direction: f64,
+        /// The velocity the pedestrian has been pushed with by the neighbours it is touching, on top of its own walking (ContactModel::Granular only): (x, y), in m/s
+        #[serde(default)]
+        push_velocity: (f64, f64),
+        /// The contact impulse on the pedestrian this timestep, when it has already been worked out elsewhere (see `set_contact_impulse`): (x, y), in m/s
+        #[serde(skip)]
+        contact_impulse: Option<(f64, f64)>,
+        
+        /// The point the pedestrian is heading for and the closest it has got to it since it last made progress, in metres (None = not measured yet)
+        #[serde(default)]
+        stall_progress: Option<((f64, f64), f64)>,
+        /// How long the pedestrian has gone without making progress, in seconds
+        #[serde(default)]
+        stall_timer: f64,
+        /// Walking in a random direction to recover from a stall: (the time left, in seconds, the direction)
+        #[serde(default)]
+        random_walk: Option<(f64, f64)>,
+        /// Whether the pedestrian has stalled and is to be removed from the simulation (StallRecovery::Despawn)
+        #[serde(default)]
+        despawned: bool,
+        /// The hazard intensity the pedestrian has been exposed to, integrated over time, in seconds at full intensity
+        #[serde(default)]
+        hazard_exposure: f64,
+        /// The number of times the pedestrian has reached its destination and started its route again (see `restart_route`)
+        #[serde(default)]
+        laps: usize,
         
         /// Whether or not each timing boundary has been hit
         timing_boundary_states: Vec<bool>,
         /// The time since passing the first timing boundary
         timing_boundary_elapsed: Option<f64>,
         
+        /// The facing direction used when drawing, smoothed according to the `heading_smoothing_time` parameter
+        rendered_direction: f64,
+        /// The position at the start of the last timestep, for drawing the pedestrian between timesteps (None = it hasn't moved yet)
+        #[serde(skip)]
+        previous_position: Option<(f64, f64)>,
+        
+        /// Recent facing directions within the heading window: (timestep length, direction)
+        heading_history: VecDeque<(f64, f64)>,
+        /// The total duration of the timesteps in heading_history, in seconds
+        heading_history_duration: f64,
+        /// The windowed heading jitter integrated over time, and the time it was integrated over
+        jitter_integral: (f64, f64),
+        
         /// The tested behavioural rule that this pedestrian follows
-        etiquette: Etiquette
+        #[serde(skip, default = "detached_behaviour")]
+        behaviour: Arc<dyn BehaviourModel>,
+        /// The parameters of the pedestrian model
+        params: SimParams
+    }
+    
+    /// What a pedestrian does once it has finished standing still
+    #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+    pub enum AfterDwell {
+        /// Carry on to its destination
+        Resume,
+        /// Leave the simulation where it stands, e.g. by boarding a train
+        Leave
+    }
+    
+    /// A place where a pedestrian stands still for a while, such as a spot on a platform or in front of a sign.
+    /// 
+    /// Standing pedestrians are obstacles that other pedestrians have to walk around, so standing crowds can be mixed with through-flow.
+    #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+    pub struct Dwell {
+        /// Where the pedestrian stands, which it walks straight to
+        pub location: (f64, f64),
+        /// How long it stands there, in seconds
+        pub duration: f64,
+        /// What it does afterwards
+        pub after: AfterDwell,
+        /// Whether the pedestrian has started standing still
+        started: bool,
+        /// How long the pedestrian has been walking to the location, in seconds
+        approach_time: f64
+    }
+    
+    impl Dwell {
+        /// Create a place to stand still at, which the pedestrian hasn't reached yet
+        pub fn new(location: (f64, f64), duration: f64, after: AfterDwell) -> Dwell {
+            Dwell {
+                location,
+                duration,
+                after,
+                started: false,
+                approach_time: 0.0
+            }
+        }
+    }
+    
+    /// The pedestrian ahead that a pedestrian is following or overtaking
+    #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+    struct OvertakeState {
+        /// Where the pedestrian being followed was at the start of the timestep
+        position: (f64, f64),
+        /// Whether it is being overtaken, rather than followed
+        passing: bool,
+        /// How long it has been overtaken for, in seconds
+        elapsed: f64
     }
     
     impl Walker {
         /// Create a new Walker object.
         /// 
         /// * `area` - A `SimArea` object describing the space for the simulation to be set in.
-        pub fn new(environment: Arc<SimArea>, group: usize, start: usize, end: usize, target_speed: f64, etiquette: Etiquette) -> Walker {
+        /// * `origin` - (group, start): The group the pedestrian is part of, and which of the group's start positions it starts at
+        /// * `destination` - (group, end): The group whose end positions the pedestrian walks to, and which of them it walks to
+        /// * `params` - The parameters of the pedestrian model
+        /// * `rng` - Chooses the points in the start and end zones that the pedestrian starts at and walks to
+        pub fn new<R: Rng>(environment: Arc<SimArea>, origin: (usize, usize), destination: (usize, usize), target_speed: f64, behaviour: Arc<dyn BehaviourModel>, params: SimParams, rng: &mut R) -> Walker {
+            let (group, start) = origin;
+            let (destination_group, end) = destination;
             let timing_boundary_count = environment.timing_boundaries.len();
-            let start_coords = environment.start_positions[group][start];
-            let end_coords = environment.end_positions[group][end];
+            let start_coords = environment.start_zones[group][start].get_point((rng.gen(), rng.gen()));
+            let end_point = (rng.gen(), rng.gen());
+            
+            // A group's waypoints lead to its own end positions, so pedestrians heading for another group's end positions don't use them
+            let waypoints = if destination_group == group {
+                environment.group_waypoints[group].iter().copied().collect::<VecDeque<_>>()
+            } else {
+                VecDeque::new()
+            };
+            
+            // Initially point towards the first waypoint, or the destination if there are none
+            let first_target = waypoints.front().copied().unwrap_or(environment.end_zones[destination_group][end].get_point(end_point).into());
+            let facing_direction = normalise_angle((first_target.1 - start_coords.y).atan2(first_target.0 - start_coords.x));
             
             Walker {
-                x: start_coords.0,
-                y: start_coords.1,
-                // Initially point towards destination
-                facing_direction: ((end_coords.1 - start_coords.1).atan2(end_coords.0 - start_coords.0) + TAU) % TAU,
+                x: start_coords.x,
+                y: start_coords.y,
+                facing_direction,
                 target_speed,
-                inst_speed: PEDESTRIAN_MINIMUM_SPEED,
+                inst_speed: params.minimum_speed,
                 environment,
+                id: 0,
                 group,
+                destination_group,
+                cohorts: Vec::new(),
+                start_location: start,
                 target_location: end,
+                end_point,
+                exit: None,
+                exit_choice_timer: 0.0,
+                waypoints,
+                navigation: None,
+                planned_path: VecDeque::new(),
+                replan_timer: 0.0,
+                give_way_timer: 0.0,
+                junction_delay: 0.0,
+                overtaking: None,
+                stop_timer: 0.0,
+                dwell: None,
+                luggage: None,
+                queue_place: None,
+                walkway_standing: None,
+                herding: None,
+                signs_read: Vec::new(),
+                sign_waypoint: None,
+                wrong_turns: 0,
+                heading_wrong: false,
+                has_chatted: false,
+                turn_rate: 0.0,
+                timestep_start_direction: 0.0,
+                push_velocity: (0.0, 0.0),
+                contact_impulse: None,
+                stall_progress: None,
+                stall_timer: 0.0,
+                random_walk: None,
+                despawned: false,
+                hazard_exposure: 0.0,
+                laps: 0,
                 timing_boundary_states: vec![false; timing_boundary_count],
                 timing_boundary_elapsed: None,
-                etiquette
+                rendered_direction: facing_direction,
+                previous_position: None,
+                heading_history: VecDeque::new(),
+                heading_history_duration: 0.0,
+                jitter_integral: (0.0, 0.0),
+                behaviour,
+                params
             }
         }
         
         /// Simulate a small period of time in a single step.
         /// 
         /// `time_scale`: The amount of time (in seconds) that passes during each timestep
-        /// `other_pedestrians_before`: A list of pedestrian positions (that have already been simulated)
-        /// `other_pedestrians_after`: A list of pedestrian positions (that are yet to be simulated)
-        pub fn simulate_timestep(&mut self, time_scale: f64, other_pedestrians_before: &[(f64, f64, f64)], other_pedestrians_after: &[(f64, f64, f64)]) {
+        /// `rng`: The source of the random fluctuations in the pedestrian's direction and speed
+        /// `door_states`: Whether each door in the area is open (see `CrowdSim::get_door_states`)
+        /// `other_pedestrians_before`: A list of pedestrian positions at the start of the timestep (those before this pedestrian in the simulation's list)
+        /// `other_pedestrians_after`: A list of pedestrian positions at the start of the timestep (those after this pedestrian in the simulation's list)
+        pub fn simulate_timestep<R: Rng>(&mut self, time_scale: f64, rng: &mut R, door_states: &[bool], other_pedestrians_before: &[(f64, f64, f64, f64, f64)], other_pedestrians_after: &[(f64, f64, f64, f64, f64)]) {
             //println!("Simulating one pedestrian timestep...");
             
-            // Apply acceleration/deceleration to change velocity
-            self.inst_speed = self.target_speed.min(self.inst_speed + PEDESTRIAN_ACCEL * time_scale);
+            let start = self.move_freely(time_scale, rng, door_states, other_pedestrians_before, other_pedestrians_after);
             
-            // Coordinates of the destination
-            let target_x = self.environment.end_positions[self.group][self.target_location].0;
-            let target_y = self.environment.end_positions[self.group][self.target_location].1;
+            self.finish_movement(start, time_scale, door_states);
             
-            // The angle the pedestrian should be facing to reach their destination (between 0 and 2π)
-            let target_angle = (target_y - self.y).atan2(target_x - self.x);
+        }
+        
+        /// The first half of a timestep: react to the destination & neighbours and move, without regard for walls
+        /// 
+        /// Return the position at the start of the timestep, to be passed to `finish_movement`.
+        pub fn move_freely<R: Rng>(&mut self, time_scale: f64, rng: &mut R, door_states: &[bool], other_pedestrians_before: &[(f64, f64, f64, f64, f64)], other_pedestrians_after: &[(f64, f64, f64, f64, f64)]) -> Vec2 {
+            // Everything that moves the pedestrian during the timestep (including being pushed apart from its neighbours) is checked against walls from here
+            let start = Vec2::new(self.x, self.y);
+            self.previous_position = Some((self.x, self.y));
+            self.timestep_start_direction = self.facing_direction;
+            let start_velocity = Vec2::from_angle(self.facing_direction) * self.inst_speed;
+            
+            let velocity = self.compute_desired_velocity(time_scale, rng, door_states, other_pedestrians_before, other_pedestrians_after);
+            
+            self.integrate(start_velocity, velocity, time_scale);
+            
+            return start;
+            
+        }
+        
+        /// The steering phase of a timestep: decide where to head for, then apply each of the steering rules in turn to the pedestrian's facing direction and speed.
+        /// 
+        /// Return the velocity the pedestrian has decided on, to be passed to `integrate`.
+        fn compute_desired_velocity<R: Rng>(&mut self, time_scale: f64, rng: &mut R, door_states: &[bool], other_pedestrians_before: &[(f64, f64, f64, f64, f64)], other_pedestrians_after: &[(f64, f64, f64, f64, f64)]) -> Vec2 {
+            // The behaviour model is shared, so hold a separate reference to it while this pedestrian is being modified
+            let behaviour = self.behaviour.clone();
+            
+            behaviour.decide(self, time_scale, rng);
             
-            // Update the facing direction to be better aligned with the destination
-            self.facing_direction = nudge_angle(self.facing_direction, target_angle, PEDESTRIAN_DIRECTION_CHANGE_FACTOR*time_scale);
+            self.update_walkway(rng);
+            
+            // Apply acceleration/deceleration to change velocity
+            behaviour.accelerate(self, time_scale);
             
+            let initial_direction = self.facing_direction;
             
-            // Add bias to movement direction depending on etiquette
-            if self.etiquette == Etiquette::LeftBias {
-                self.facing_direction -= PEDESTRIAN_ETIQUETTE_BIAS_FACTOR * time_scale;
-            } else if self.etiquette == Etiquette::RightBias {
-                self.facing_direction += PEDESTRIAN_ETIQUETTE_BIAS_FACTOR * time_scale;
+            let surroundings = Surroundings {
+                time_scale,
+                target_angle: self.update_destination(time_scale, door_states, other_pedestrians_before, other_pedestrians_after),
+                behaviour: behaviour.as_ref(),
+                door_states,
+                other_pedestrians_before,
+                other_pedestrians_after
+            };
+            for rule in STEERING_RULES {
+                rule(self, &surroundings);
             }
             
+            // Remember how the pedestrian chose to turn, so that it can signal where it is heading next
+            self.turn_rate = wrap_angle(self.facing_direction - initial_direction) / time_scale;
             
-            self.react_to_neighbours(time_scale, other_pedestrians_after);
-            self.react_to_neighbours(time_scale, other_pedestrians_before);
+            self.apply_noise(time_scale, rng);
             
-            self.apply_noise(time_scale);
+            // A stopped pedestrian still turns, but stays where it is
+            if self.stop_timer > 0.0 {
+                self.stop_timer = (self.stop_timer - time_scale).max(0.0);
+                self.inst_speed = 0.0;
+            }
             
+            // A pedestrian slows down as it nears its place in a queue, so that it can turn onto the place instead of circling round it
+            if let Some(place) = self.queue_place {
+                let distance = Vec2::new(self.x, self.y).distance(place.into());
+                self.inst_speed = self.inst_speed.min(0.5 * distance * self.params.direction_change_factor);
+            }
             
-            // Apply velocity to change position
-            self.x += self.inst_speed * self.facing_direction.cos() * time_scale;
-            self.y += self.inst_speed * self.facing_direction.sin() * time_scale;
+            // A pedestrian waits at its place in a queue until it moves up
+            if self.is_waiting_in_queue() {
+                self.inst_speed = 0.0;
+            }
             
-            self.resolve_wall_collisions(time_scale);
+            // A pedestrian standing on a moving walkway is carried along once it is in the standing lane
+            if self.is_standing_on_walkway() {
+                self.inst_speed = 0.0;
+            }
             
+            self.limit_turn(time_scale);
+            
+            return Vec2::from_angle(self.facing_direction) * self.inst_speed;
         }
         
-        /// React to neighbouring pedestrians, considering specific etiquette rules
+        /// Decide where to head for during this timestep: which end position, which waypoint, and whether to stand still somewhere on the way.
         /// 
-        /// * `other_pedestrians` - [(x, y, direction)]
-        fn react_to_neighbours(&mut self, time_scale: f64, other_pedestrians: &[(f64, f64, f64)]) {
-            
-            // Iterate through all neighbouring pedestrians and check for front-on collisions and side collisions.
-            
-            /* How this method works:
-             * Resolve collisions between pedestrians.
-             * * Move them apart so that hey are no longer touching.
-             * * Change direction of travel so that they are travelling perpendicular to the other pedestrian.
-             * * Reduce speed to 0.
-             * If a pedestrian is to the right or left: cancel right or left bias effects.
-             * If a pedestrian is in front:
-             * * If they are walking in the opposite direction:
-             * * * Move the direction of travel away from the oncoming pedestrian - either towards the perpendicular or towards the normal.
-             * * If they are walking in the same direction, reduce acceleration.
-             * * If they are also within the personal space radius, decelerate.
-             */
-            
-            for (n_x, n_y, n_dir) in other_pedestrians {
-                let dist = ((self.x - n_x)*(self.x - n_x) + (self.y - n_y)*(self.y - n_y)).sqrt();
-                
-                // The direction the neighbour is in, between -π and π
-                let abs_neighbour_angle = (n_y - self.y).atan2(n_x - self.x);
-                
-                // The direction the neighbour is in, relative to the direction of travel of this pedestrian, between 0 and 2π
-                let travel_rel_angle = (abs_neighbour_angle - self.facing_direction + TAU + TAU) % TAU;
-                
-                // Intersecting hitbox
-                if dist < 2.0*PEDESTRIAN_RADIUS {
-                    //println!("Collision");
+        /// Return the angle the pedestrian should be facing to reach its next waypoint or destination, between -π and π.
+        fn update_destination(&mut self, time_scale: f64, door_states: &[bool], other_pedestrians_before: &[(f64, f64, f64, f64, f64)], other_pedestrians_after: &[(f64, f64, f64, f64, f64)]) -> f64 {
+            // Reconsider which end position to head for
+            self.update_exit_choice(time_scale, door_states, other_pedestrians_before, other_pedestrians_after);
+            
+            // Move on to the next waypoint if the current one has been reached, and replan the path if needed
+            self.update_waypoints(time_scale);
+            
+            // Pick up waypoints from any signs the pedestrian can see, if it doesn't know its way
+            self.read_signs(door_states);
+            
+            // Start or finish standing still
+            self.update_dwell(time_scale);
+            
+            // Keep walking in a random direction while recovering from a stall
+            if let Some((time_left, direction)) = self.random_walk {
+                self.random_walk = if time_left > time_scale {Some((time_left - time_scale, direction))} else {None};
+                return wrap_angle(direction);
+            }
+            
+            // Coordinates of the next waypoint or the destination
+            let (target_x, target_y) = self.get_steering_target();
+            let target_angle = (target_y - self.y).atan2(target_x - self.x);
+            
+            // A pedestrian that doesn't know its way follows the crowd, unless it is walking to a place in a queue, a place to stand still or where a sign sent it
+            if let Some(herding) = self.herding {
+                let following_sign = self.sign_waypoint.is_some_and(|waypoint| self.waypoints.front() == Some(&waypoint));
+                if self.queue_place.is_none() && self.dwell.as_ref().is_none_or(|dwell| dwell.started) && !following_sign {
+                    let position = Vec2::new(self.x, self.y);
+                    let neighbours = other_pedestrians_before.iter().chain(other_pedestrians_after)
+                        .filter(|(n_x, n_y, _, _, _)| {
+                            let neighbour = Vec2::new(*n_x, *n_y);
+                            position.distance(neighbour) < herding.radius && self.environment.is_in_sight(position, neighbour, door_states)
+                        })
+                        .map(|(_, _, direction, _, speed)| (*direction, *speed));
+                    let heading = herding.get_heading(target_angle, neighbours);
                     
-                    // Compute the overlap between the two pedestrians
-                    let k = 2.0*PEDESTRIAN_RADIUS - dist;
+                    // Count each time the crowd leads the pedestrian well away from the way to its destination
+                    let heading_wrong = wrap_angle(heading - target_angle).abs() > PI / 2.0;
+                    if heading_wrong && !self.heading_wrong {
+                        self.wrong_turns += 1;
+                    }
+                    self.heading_wrong = heading_wrong;
                     
-                    // Move the pedestrian away from its neighbour
-                    self.x -= abs_neighbour_angle.cos() * k;
-                    self.y -= abs_neighbour_angle.sin() * k;
+                    return heading;
+                }
+            }
+            
+            return target_angle;
+        }
+        
+        /// React to the neighbouring pedestrians according to the pedestrian's behaviour model, in the order set by the `neighbour_order` parameter
+        fn react_to_neighbours(&mut self, behaviour: &dyn BehaviourModel, time_scale: f64, other_pedestrians_before: &[(f64, f64, f64, f64, f64)], other_pedestrians_after: &[(f64, f64, f64, f64, f64)]) {
+            match self.params.neighbour_order {
+                NeighbourOrder::TwoPass => {
+                    behaviour.react_to_neighbours(self, time_scale, other_pedestrians_after);
+                    behaviour.react_to_neighbours(self, time_scale, other_pedestrians_before);
+                },
+                NeighbourOrder::ByDistance => {
+                    let neighbours = self.get_neighbours_by_distance(other_pedestrians_before, other_pedestrians_after, usize::MAX);
+                    behaviour.react_to_neighbours(self, time_scale, &neighbours);
+                },
+                NeighbourOrder::Nearest(count) => {
+                    let neighbours = self.get_neighbours_by_distance(other_pedestrians_before, other_pedestrians_after, count);
+                    behaviour.react_to_neighbours(self, time_scale, &neighbours);
+                }
+            }
+        }
+        
+        /// The integration phase of a timestep: move for the length of the timestep, according to the `integrator` parameter
+        /// 
+        /// * `start_velocity` - The velocity at the start of the timestep
+        /// * `velocity` - The velocity decided on during the timestep
+        fn integrate(&mut self, start_velocity: Vec2, velocity: Vec2, time_scale: f64) {
+            let velocity = match self.params.integrator {
+                Integrator::Euler => velocity,
+                Integrator::Midpoint => (start_velocity + velocity) * 0.5
+            };
+            
+            // A moving walkway carries the pedestrian along on top of its own walking
+            let walkway_velocity = self.environment.get_walkway(Vec2::new(self.x, self.y)).map_or(Vec2::new(0.0, 0.0), |walkway| walkway.get_velocity());
+            
+            self.x += (velocity.x + walkway_velocity.x) * time_scale;
+            self.y += (velocity.y + walkway_velocity.y) * time_scale;
+            
+            if self.params.contact_model == ContactModel::Granular {
+                let (push_x, push_y) = self.push_velocity;
+                self.x += push_x * time_scale;
+                self.y += push_y * time_scale;
+                
+                let decay = (-time_scale / PUSH_RELAXATION_TIME).exp();
+                self.push_velocity = (push_x * decay, push_y * decay);
+            }
+        }
+        
+        /// The second half of a timestep: resolve any collisions with walls & obstacles since `start`, then record the new heading
+        /// 
+        /// * `door_states` - Whether each door in the area is open, since closed doors are walls
+        pub fn finish_movement(&mut self, start: Vec2, time_scale: f64, door_states: &[bool]) {
+            self.resolve_wall_collisions(start, time_scale, door_states);
+            
+            self.limit_turn(time_scale);
+            
+            self.update_heading_history(time_scale);
+        }
+        
+        /// Undo any turning beyond the `max_turn_rate` parameter since the start of the timestep (if there is a limit)
+        fn limit_turn(&mut self, time_scale: f64) {
+            if self.params.max_turn_rate <= 0.0 {
+                return;
+            }
+            
+            let max_turn = self.params.max_turn_rate * time_scale;
+            let turn = wrap_angle(self.facing_direction - self.timestep_start_direction).clamp(-max_turn, max_turn);
+            self.facing_direction = normalise_angle(self.timestep_start_direction + turn);
+        }
+        
+        /// Slow down before entering a junction zone if a pedestrian in a crossing stream has priority, according to the zone's rule
+        /// 
+        /// A pedestrian that has waited for longer than GIVE_WAY_PATIENCE goes anyway.
+        fn give_way_at_junctions(&mut self, time_scale: f64, other_pedestrians_before: &[(f64, f64, f64, f64, f64)], other_pedestrians_after: &[(f64, f64, f64, f64, f64)]) {
+            let position = Vec2::new(self.x, self.y);
+            
+            let mut conflict = false;
+            for junction in &self.environment.junctions {
+                let junction_conflict = other_pedestrians_before.iter().chain(other_pedestrians_after).any(|(n_x, n_y, n_dir, _, _)| {
+                    junction.must_give_way(position, self.facing_direction, Vec2::new(*n_x, *n_y), *n_dir)
+                });
+                
+                if junction_conflict && self.give_way_timer < GIVE_WAY_PATIENCE {
+                    self.inst_speed = self.inst_speed.min(junction.get_give_way_speed(position, self.params.radius));
+                }
+                conflict |= junction_conflict;
+            }
+            
+            // The timer keeps running after patience runs out, so that the pedestrian doesn't start giving way again until the conflict has cleared
+            if conflict {
+                if self.give_way_timer < GIVE_WAY_PATIENCE {
+                    self.junction_delay += time_scale;
+                }
+                self.give_way_timer += time_scale;
+            } else {
+                self.give_way_timer = 0.0;
+            }
+        }
+        
+        /// Follow the pedestrian ahead, and overtake it if it is slower and the lane beside it is clear (according to the `overtaking` parameter)
+        /// 
+        /// Neighbours are only known by position, so the pedestrian being followed is recognised at each timestep as the closest one to where it was before, and its speed is estimated from how far it has moved.
+        /// Once it has been passed, the pedestrian steers back towards its target as usual, merging back in ahead of it.
+        fn update_overtaking(&mut self, time_scale: f64, target_angle: f64, door_states: &[bool], other_pedestrians_before: &[(f64, f64, f64, f64, f64)], other_pedestrians_after: &[(f64, f64, f64, f64, f64)]) {
+            let side = match self.params.overtaking {
+                Overtaking::Off => return,
+                Overtaking::Left => -1.0,
+                Overtaking::Right => 1.0
+            };
+            
+            let position = Vec2::new(self.x, self.y);
+            let heading = Vec2::new(self.facing_direction.cos(), self.facing_direction.sin());
+            let radius = self.params.radius;
+            let look_ahead_radius = self.get_look_ahead_radius();
+            let facing_direction = self.facing_direction;
+            
+            // How far a point is ahead of this pedestrian, and how far it is to the right (negative = to the left)
+            let relative = |x: f64, y: f64| -> (f64, f64) {
+                let offset = Vec2::new(x, y) - position;
+                return (offset.dot(heading), heading.x*offset.y - heading.y*offset.x);
+            };
+            let neighbours = || other_pedestrians_before.iter().chain(other_pedestrians_after).copied();
+            
+            // Find the pedestrian tracked at the last timestep, and estimate its speed
+            let tracked = self.overtaking.and_then(|state| {
+                let (x, y) = state.position;
+                return neighbours().map(|n| (n, (n.0 - x).hypot(n.1 - y)))
+                    .filter(|(_, dist)| *dist <= OVERTAKE_TRACKING_SPEED * time_scale)
+                    .min_by(|a, b| a.1.total_cmp(&b.1))
+                    .map(|(n, dist)| (state, n, dist / time_scale));
+            });
+            
+            match tracked {
+                Some((state, (n_x, n_y, _, n_radius, _), _)) if state.passing => {
+                    let (forward, lateral) = relative(n_x, n_y);
+                    if forward < -(radius + n_radius) || state.elapsed > OVERTAKE_PATIENCE {
+                        // Passed (or given up), so steer back towards the target as usual
+                        self.overtaking = None;
+                        return;
+                    }
                     
-                    // Only stop & turn around if the oncoming pedestrian is within the frontal field of view
-                    if travel_rel_angle <= PEDESTRIAN_LOOK_AHEAD_FOV/2.0 || travel_rel_angle >= TAU-PEDESTRIAN_LOOK_AHEAD_FOV/2.0 {
-                        
-                        // Set facing angle directly away from neighbour
-                        self.facing_direction = abs_neighbour_angle + PI;
-                        
-                        // Set speed to 0
-                        self.inst_speed = 0.0;
-                        
+                    self.overtaking = Some(OvertakeState {position: (n_x, n_y), passing: true, elapsed: state.elapsed + time_scale});
+                    
+                    // Swing out until there is room to walk alongside
+                    if -lateral*side < radius + n_radius + OVERTAKE_CLEARANCE {
+                        self.facing_direction = nudge_angle(self.facing_direction, target_angle + side*OVERTAKE_ANGLE, OVERTAKE_TURN_RATE*time_scale);
                     }
+                },
+                _ => {
+                    // Whether a neighbour is walking the same way, directly ahead
+                    let in_the_way = |(n_x, n_y, n_dir, n_radius, _): (f64, f64, f64, f64, f64)| -> bool {
+                        let (forward, lateral) = relative(n_x, n_y);
+                        return forward > 0.0 && forward < look_ahead_radius && lateral.abs() < radius + n_radius && wrap_angle(n_dir - facing_direction).abs() < PI/2.0;
+                    };
+                    
+                    // Keep following the tracked pedestrian while it is still in the way, otherwise start following the closest one in the way
+                    let (blocker, speed) = match tracked {
+                        Some((_, n, speed)) if in_the_way(n) => (Some(n), Some(speed)),
+                        _ => (neighbours().filter(|n| in_the_way(*n)).min_by(|a, b| relative(a.0, a.1).0.total_cmp(&relative(b.0, b.1).0)), None)
+                    };
                     
+                    self.overtaking = blocker.map(|blocker| {
+                        let passing = speed.is_some_and(|speed| speed < self.target_speed * self.params.overtake_speed_ratio)
+                            && self.is_overtaking_lane_clear(side, blocker, door_states, other_pedestrians_before, other_pedestrians_after);
+                        return OvertakeState {position: (blocker.0, blocker.1), passing, elapsed: 0.0};
+                    });
+                }
+            }
+        }
+        
+        /// Add the granular contact forces from every neighbour this pedestrian is touching to its push velocity:
+        /// a body compression force pushing it away from the neighbour, and a sliding friction force opposing their relative motion along the point of contact,
+        /// both in proportion to how much their bodies overlap.
+        fn apply_contact_forces(&mut self, time_scale: f64, other_pedestrians_before: &[(f64, f64, f64, f64, f64)], other_pedestrians_after: &[(f64, f64, f64, f64, f64)]) {
+            let position = Vec2::new(self.x, self.y);
+            let velocity = Vec2::from_angle(self.facing_direction) * self.inst_speed + Vec2::new(self.push_velocity.0, self.push_velocity.1);
+            
+            let mut impulse = Vec2::new(0.0, 0.0);
+            for &(n_x, n_y, n_direction, n_radius, n_speed) in other_pedestrians_before.iter().chain(other_pedestrians_after.iter()) {
+                let offset = position - Vec2::new(n_x, n_y);
+                let overlap = self.params.radius + n_radius - offset.length();
+                if overlap <= 0.0 {
+                    continue;
                 }
                 
-                // Recalculate relative neighbour direction
-                let travel_rel_angle = (abs_neighbour_angle - self.facing_direction + TAU + TAU) % TAU;
+                // Pedestrians standing on top of each other are pushed apart in an arbitrary direction
+                let normal = offset.normalised().unwrap_or(Vec2::new(1.0, 0.0));
+                let tangent = Vec2::new(-normal.y, normal.x);
                 
-                // Within view to the right
-                if dist < PEDESTRIAN_LOOK_BESIDE_RADIUS && travel_rel_angle > PEDESTRIAN_LOOK_AHEAD_FOV/2.0 && travel_rel_angle < PEDESTRIAN_LOOK_AHEAD_FOV/2.0 + PEDESTRIAN_LOOK_BESIDE_FOV {
-                    // Cancel right-bias
-                    if self.etiquette == Etiquette::RightBias {
-                        self.facing_direction -= PEDESTRIAN_ETIQUETTE_BIAS_FACTOR * time_scale;
-                    }
+                let sliding = (Vec2::from_angle(n_direction) * n_speed - velocity).dot(tangent);
+                
+                // Friction can at most stop the sliding within a timestep, not reverse it
+                let friction_fraction = (self.params.contact_friction * overlap * time_scale).min(1.0);
+                
+                impulse = impulse + normal * (self.params.contact_stiffness * overlap * time_scale) + tangent * (sliding * friction_fraction);
+            }
+            
+            self.push_velocity.0 += impulse.x;
+            self.push_velocity.1 += impulse.y;
+        }
+        
+        /// Find the neighbour this pedestrian will collide with first if everyone keeps their current velocity, and if that is within the anticipation horizon,
+        /// turn away from where they would be closest and slow down, more strongly the sooner the collision.
+        fn anticipate_collisions(&mut self, time_scale: f64, other_pedestrians_before: &[(f64, f64, f64, f64, f64)], other_pedestrians_after: &[(f64, f64, f64, f64, f64)]) {
+            let position = Vec2::new(self.x, self.y);
+            let velocity = Vec2::from_angle(self.facing_direction) * self.inst_speed;
+            
+            // (time to collision, position of the neighbour relative to this pedestrian when they are closest)
+            let mut first_collision: Option<(f64, Vec2)> = None;
+            
+            for &(n_x, n_y, n_dir, n_radius, n_speed) in other_pedestrians_before.iter().chain(other_pedestrians_after) {
+                let relative_position = Vec2::new(n_x, n_y) - position;
+                let relative_velocity = Vec2::from_angle(n_dir) * n_speed - velocity;
+                let combined_radius = self.params.radius + n_radius;
+                
+                // Solve |relative_position + relative_velocity*t| = combined_radius for the first time t that they touch
+                let a = relative_velocity.length_sq();
+                let b = relative_velocity.dot(relative_position);
+                let c = relative_position.length_sq() - combined_radius*combined_radius;
+                let discriminant = b*b - a*c;
+                
+                // Already touching (dealt with by the behaviour model), moving apart, or passing without touching
+                if c <= 0.0 || b >= 0.0 || discriminant <= 0.0 {
+                    continue;
                 }
                 
-                // Within view to the left
-                if dist < PEDESTRIAN_LOOK_BESIDE_RADIUS && travel_rel_angle < TAU-PEDESTRIAN_LOOK_AHEAD_FOV/2.0 && travel_rel_angle > TAU-(PEDESTRIAN_LOOK_AHEAD_FOV/2.0 + PEDESTRIAN_LOOK_BESIDE_FOV) {
-                    // Cancel left-bias
-                    if self.etiquette == Etiquette::LeftBias {
-                        self.facing_direction += PEDESTRIAN_ETIQUETTE_BIAS_FACTOR * time_scale;
-                    }
+                let time_to_collision = (-b - discriminant.sqrt()) / a;
+                if time_to_collision > self.params.anticipation_horizon || first_collision.is_some_and(|(t, _)| t <= time_to_collision) {
+                    continue;
+                }
+                
+                let time_of_closest_approach = -b / a;
+                first_collision = Some((time_to_collision, relative_position + relative_velocity*time_of_closest_approach));
+            }
+            
+            let (time_to_collision, closest_approach) = match first_collision {
+                Some(collision) => collision,
+                None => return
+            };
+            
+            // 1 for a collision that is about to happen, down to 0 at the anticipation horizon
+            let urgency = 1.0 - time_to_collision / self.params.anticipation_horizon;
+            
+            // Turn so that the neighbour passes on the side it is already predicted to pass on (to the right for a head-on collision)
+            let forward = Vec2::from_angle(self.facing_direction);
+            let side = forward.x*closest_approach.y - forward.y*closest_approach.x;
+            let turn = if side > 0.0 {-1.0} else {1.0};
+            self.facing_direction = normalise_angle(self.facing_direction + turn * ANTICIPATION_TURN_RATE * urgency * time_scale);
+            
+            self.inst_speed *= (1.0 - ANTICIPATION_DECEL * urgency * time_scale).max(0.0);
+        }
+        
+        /// Whether there is room to pass a pedestrian on one side (-1 = left, 1 = right): no walls, obstacles or other pedestrians in the way
+        fn is_overtaking_lane_clear(&self, side: f64, blocker: (f64, f64, f64, f64, f64), door_states: &[bool], other_pedestrians_before: &[(f64, f64, f64, f64, f64)], other_pedestrians_after: &[(f64, f64, f64, f64, f64)]) -> bool {
+            let position = Vec2::new(self.x, self.y);
+            let heading = Vec2::new(self.facing_direction.cos(), self.facing_direction.sin());
+            let radius = self.params.radius;
+            let look_ahead_radius = self.get_look_ahead_radius();
+            
+            // The distance between the paths of the two pedestrians while passing
+            let lane_offset = radius + blocker.3 + OVERTAKE_CLEARANCE;
+            
+            let pedestrian_in_lane = other_pedestrians_before.iter().chain(other_pedestrians_after).any(|&(n_x, n_y, _, n_radius, _)| {
+                if (n_x, n_y) == (blocker.0, blocker.1) {
+                    return false;
+                }
+                let offset = Vec2::new(n_x, n_y) - position;
+                let forward = offset.dot(heading);
+                let lateral = (heading.x*offset.y - heading.y*offset.x) * side;
+                return forward > -2.0*radius && forward < look_ahead_radius && lateral > 0.0 && lateral < lane_offset + radius + n_radius;
+            });
+            if pedestrian_in_lane {
+                return false;
+            }
+            
+            // Walk sideways into the lane, then along it
+            let lane_start = position + Vec2::new(-heading.y, heading.x)*(lane_offset*side);
+            let lane_end = lane_start + heading*look_ahead_radius;
+            return [(position, lane_start), (lane_start, lane_end)].iter().all(|&(start, end)| {
+                self.environment.get_walls(door_states).all(|wall| wall.get_contact_time(start, end, radius).is_none())
+                    && self.environment.obstacles.iter().all(|obstacle| obstacle.get_contact_time(start, end, radius).is_none())
+            });
+        }
+        
+        /// Stop walking for a while, e.g. to look at a phone
+        pub fn stop_for(&mut self, duration: f64) {
+            self.stop_timer = self.stop_timer.max(duration);
+        }
+        
+        /// Check whether the pedestrian has stalled: gone for the `stall_time` parameter without getting STALL_PROGRESS closer to where it is heading (standing still on purpose doesn't count).
+        /// If it has, start recovering according to the `stall_recovery` parameter and return true.
+        pub fn check_stalled<R: Rng>(&mut self, time_scale: f64, rng: &mut R) -> bool {
+            if self.params.stall_time <= 0.0 {
+                return false;
+            }
+            
+            let target = self.get_steering_target();
+            let dist = Vec2::new(self.x, self.y).distance(target.into());
+            match self.stall_progress {
+                Some((progress_target, closest)) if progress_target == target && dist > closest - STALL_PROGRESS => {},
+                // Heading somewhere new, or making progress
+                _ => {
+                    self.stall_progress = Some((target, dist));
+                    self.stall_timer = 0.0;
+                }
+            }
+            
+            if self.is_stopped() || self.queue_place.is_some() || self.random_walk.is_some() {
+                self.stall_timer = 0.0;
+                return false;
+            }
+            
+            self.stall_timer += time_scale;
+            if self.stall_timer < self.params.stall_time {
+                return false;
+            }
+            
+            self.stall_timer = 0.0;
+            self.stall_progress = None;
+            match self.params.stall_recovery {
+                StallRecovery::Replan if matches!(self.navigation, Some(Navigation::PathPlanning(_))) => self.plan_path(),
+                StallRecovery::Replan | StallRecovery::RandomWalk => self.random_walk = Some((RANDOM_WALK_DURATION, rng.gen::<f64>() * TAU)),
+                StallRecovery::Despawn => self.despawned = true
+            }
+            
+            return true;
+        }
+        
+        /// Return whether the pedestrian has stalled, or been incapacitated by a hazard, and is to be removed from the simulation (see `check_stalled` and `react_to_hazards`)
+        pub fn is_despawned(&self) -> bool {
+            return self.despawned;
+        }
+        
+        /// Remove the pedestrian from the simulation without finishing, e.g. a passenger left behind by the last train
+        pub fn despawn(&mut self) {
+            self.despawned = true;
+        }
+        
+        /// Return the speed the pedestrian wants to walk at where it is: its target speed, adjusted by any speed zones it is in (e.g. slower on stairs)
+        pub fn get_desired_speed(&self) -> f64 {
+            return self.target_speed * self.environment.get_speed_multiplier(Vec2::new(self.x, self.y));
+        }
+        
+        /// Whether the pedestrian has stopped walking for a while (see `stop_for`)
+        pub fn is_stopped(&self) -> bool {
+            return self.stop_timer > 0.0;
+        }
+        
+        /// Stop to chat with someone, facing them, for a while
+        /// 
+        /// * `direction` - The direction of the other pedestrian, in radians
+        pub fn start_chat(&mut self, direction: f64, duration: f64) {
+            self.has_chatted = true;
+            self.facing_direction = normalise_angle(direction);
+            self.stop_for(duration);
+        }
+        
+        /// Whether the pedestrian has already stopped to chat with someone
+        pub fn has_chatted(&self) -> bool {
+            return self.has_chatted;
+        }
+        
+        /// Make the pedestrian take along a bulky item, such as a suitcase or a pram.
+        /// 
+        /// Its personal space is enlarged by the length of the item, and it turns towards its destination more slowly.
+        pub fn set_luggage(&mut self, luggage: Luggage) {
+            self.params.pspace_radius += luggage.length;
+            self.params.direction_change_factor *= luggage.turn_factor;
+            self.luggage = Some(luggage);
+        }
+        
+        /// Return the bulky item the pedestrian takes along, if it has one
+        pub fn get_luggage(&self) -> Option<&Luggage> {
+            return self.luggage.as_ref();
+        }
+        
+        /// Return the space taken up by the pedestrian's luggage, if it has any: (one end of the capsule's centre line, the other end, radius)
+        pub fn get_luggage_capsule(&self) -> Option<(Vec2, Vec2, f64)> {
+            return self.luggage.as_ref().map(|luggage| luggage.get_capsule(Vec2::new(self.x, self.y), self.facing_direction, self.params.radius));
+        }
+        
+        /// Keep clear of other pedestrians' luggage, treating each item like a short wall, and keep this pedestrian's own luggage out of its neighbours, walls and obstacles.
+        /// 
+        /// This is done between `move_freely` and `finish_movement`, so that any movement is still checked against walls.
+        /// 
+        /// * `luggage` - The space taken up by every active pedestrian's luggage at the start of the timestep: \[(pedestrian ID, one end of the capsule's centre line, the other end, radius)]
+        /// * `door_states` - Whether each door in the area is open, since closed doors are walls
+        pub fn react_to_luggage(&mut self, time_scale: f64, luggage: &[(u64, Vec2, Vec2, f64)], door_states: &[bool], other_pedestrians_before: &[(f64, f64, f64, f64, f64)], other_pedestrians_after: &[(f64, f64, f64, f64, f64)]) {
+            // Hold separate references to the environment & behaviour model while this pedestrian is being modified
+            let environment = self.environment.clone();
+            let behaviour = self.behaviour.clone();
+            
+            for &(id, a, b, radius) in luggage {
+                if id == self.id {
+                    continue;
                 }
                 
-                // Recalculate relative neighbour direction
-                let travel_rel_angle = (abs_neighbour_angle - self.facing_direction + TAU + TAU) % TAU;
+                let (dist, normal) = capsule_normal_vector((a, b, radius), Vec2::new(self.x, self.y));
                 
-                // Within view in front
-                if dist < PEDESTRIAN_LOOK_AHEAD_RADIUS && (travel_rel_angle <= PEDESTRIAN_LOOK_AHEAD_FOV/2.0 || travel_rel_angle >= TAU-PEDESTRIAN_LOOK_AHEAD_FOV/2.0) {
-                    let direction_difference = (self.facing_direction - n_dir + TAU) % TAU;
+                // Edge case: if the pedestrian is on the centre line, don't do anything
+                if dist > self.get_look_ahead_radius() || normal.length_sq() == 0.0 {
+                    continue;
+                }
+                
+                self.resolve_boundary_collision(dist, normal, behaviour.as_ref(), time_scale);
+            }
+            
+            // The pedestrian is moved back whenever its own luggage runs into something
+            if let Some((a, b, radius)) = self.get_luggage_capsule() {
+                for &(n_x, n_y, _, n_radius, _) in other_pedestrians_before.iter().chain(other_pedestrians_after) {
+                    let (dist, normal) = capsule_normal_vector((a, b, radius), Vec2::new(n_x, n_y));
                     
-                    if direction_difference > PI/2.0 && direction_difference < 3.0*PI/2.0 {
-                        // Oncoming
-                        
-                        if self.etiquette == Etiquette::LeftBias {
-                            // Apply bias
-                            //self.facing_direction -= PEDESTRIAN_ETIQUETTE_BIAS_FACTOR * time_scale / 2.0;
-                            
-                            // The angle that points away from the neighbouring pedestrian, between 0 and 2π
-                            //let away_angle = abs_neighbour_angle + PI;
-                            let away_angle = (abs_neighbour_angle - PI/2.0 + TAU) % TAU;
-                            
-                            // Nudge the direction of travel away from the neighbour
-                            self.facing_direction = nudge_angle(self.facing_direction, away_angle, PEDESTRIAN_OPPOSING_REPULSION*time_scale);
-                            
-                        } else if self.etiquette == Etiquette::RightBias {
-                            // Apply bias
-                            //self.facing_direction += PEDESTRIAN_ETIQUETTE_BIAS_FACTOR * time_scale / 2.0;
-                            
-                            // The angle that points away from the neighbouring pedestrian, between 0 and 2π
-                            //let away_angle = abs_neighbour_angle + PI;
-                            let away_angle = (abs_neighbour_angle + PI/2.0 + TAU) % TAU;
-                            
-                            // Nudge the direction of travel away from the neighbour
-                            self.facing_direction = nudge_angle(self.facing_direction, away_angle, PEDESTRIAN_OPPOSING_REPULSION*time_scale);
-                            
-                        } else {
-                            // No directional bias
-                            // Slow down a bit
-                            self.inst_speed -= PEDESTRIAN_ACCEL*time_scale/2.0;
-                            
-                            // The angle that points away from the neighbouring pedestrian, between 0 and 2π
-                            //let away_angle = abs_neighbour_angle + PI;
-                            
-                            // Nudge the direction of travel away from the neighbour
-                            //self.facing_direction = nudge_angle(self.facing_direction, away_angle, PEDESTRIAN_OPPOSING_REPULSION*time_scale);
-                            
+                    if dist < n_radius {
+                        if let Some(direction) = normal.normalised() {
+                            self.x -= direction.x * (n_radius - dist);
+                            self.y -= direction.y * (n_radius - dist);
                         }
-                        
-                    } else {
-                        // Moving same direction - reduce acceleration
-                        self.inst_speed = PEDESTRIAN_MINIMUM_SPEED.max(self.inst_speed - PEDESTRIAN_FOLLOWING_DECEL * time_scale);
                     }
-                    
                 }
                 
-                // Recalculate relative neighbour direction
-                let travel_rel_angle = (abs_neighbour_angle - self.facing_direction + TAU + TAU) % TAU;
-                
-                // Within personal space
-                if dist < PEDESTRIAN_RADIUS + PEDESTRIAN_PSPACE_RADIUS {
-                    // Change the direction of travel to align better with the angle facing away from the neighbour
-                    let direction_difference = (self.facing_direction - n_dir + TAU) % TAU;
-                    
-                    // Within the collision avoidance zone and oncoming
-                    if (travel_rel_angle <= PEDESTRIAN_COLLISION_AVOIDANCE_FOV/2.0 || travel_rel_angle >= TAU-PEDESTRIAN_COLLISION_AVOIDANCE_FOV/2.0) && (direction_difference > PI/2.0 && direction_difference < 3.0*PI/2.0) {
-                        // Decelerate
-                        self.inst_speed = PEDESTRIAN_MINIMUM_SPEED.max(self.inst_speed - PEDESTRIAN_OPPOSING_DECEL * time_scale);
-                        
-                        // The angle that points away from the neighbouring pedestrian, between 0 and 2π
-                        let away_angle = abs_neighbour_angle + PI;
-                        
-                        // The direction this pedestrian is in, relative to the direction of travel of the neighbour, between 0 and 2π
-                        let neighbour_rel_angle = (away_angle - n_dir + TAU) % TAU;
-                        
-                        let travel_rel_angle_sign = if travel_rel_angle < PI {-1.0} else {1.0};
-                        let neighbour_rel_angle_sign = if neighbour_rel_angle < PI {-1.0} else {1.0};
-                        
-                        if travel_rel_angle_sign == neighbour_rel_angle_sign {
-                            // Naturally travelling in different directions
-                            // Go around the oncoming pedestrian naturally
-                            self.facing_direction = nudge_angle(self.facing_direction, abs_neighbour_angle + travel_rel_angle_sign*PI/2.0, PEDESTRIAN_PSPACE_REPULSION * time_scale);
-                            
-                            //println!("1, {}, {}", travel_rel_angle, neighbour_rel_angle);
-                            
-                        } else {
-                            // Both pedestrians are angled the same way - collision imminent
-                            
-                            // Constrain angles between -π and π
-                            let travel_rel_angle_signed = (travel_rel_angle + PI) % TAU - PI;
-                            let neighbour_rel_angle_signed = (neighbour_rel_angle + PI) % TAU - PI;
-                            
-                            //println!("2, {}, {}", travel_rel_angle, neighbour_rel_angle);
-                            //println!("2, {}, {}", travel_rel_angle_signed, neighbour_rel_angle_signed);
-                            //
-                            //println!("True: {}", travel_rel_angle_signed.signum() != neighbour_rel_angle_signed.signum());
-                            
-                            if travel_rel_angle_signed.abs() > neighbour_rel_angle_signed.abs() {
-                                // Go around the oncoming pedestrian naturally - they will have to turn
-                                self.facing_direction = nudge_angle(self.facing_direction, abs_neighbour_angle + travel_rel_angle_sign*PI/2.0, PEDESTRIAN_PSPACE_REPULSION * time_scale);
-                                //println!("Natural");
-                            } else {
-                                // Turn to go around the oncoming pedestrian so that they don't have to turn
-                                //println!("Invert");
-                                self.facing_direction = nudge_angle(self.facing_direction, abs_neighbour_angle - travel_rel_angle_sign*PI/2.0, PEDESTRIAN_PSPACE_REPULSION * time_scale);
-                            }
-                        }
-                        
-                    } else {
-                        // When the pedestrian is within the personal space radius, but behind this pedestrian
-                        
+            }
+            
+            // Only the far end is checked against walls and obstacles, since the near end is kept away from them along with the pedestrian's body
+            if let Some((_, far_end, radius)) = self.get_luggage_capsule() {
+                let boundaries = environment.get_walls(door_states).map(|wall| wall.get_normal_vector(far_end))
+                    .chain(environment.obstacles.iter().map(|obstacle| obstacle.get_normal_vector(far_end)));
+                for (dist, normal) in boundaries {
+                    if dist != 0.0 && dist < radius {
+                        let k = (radius - dist) / dist.abs();
+                        self.x += normal.x * k;
+                        self.y += normal.y * k;
                     }
-                    
-                } else {
-                    // Outside personal space radius
-                    
+                }
+            }
+        }
+        
+        /// Make the pedestrian walk to a place and stand still there for a while, before carrying on to its destination or leaving
+        pub fn set_dwell(&mut self, dwell: Dwell) {
+            self.dwell = Some(dwell);
+        }
+        
+        /// Stop standing still straight away, or before reaching the place to stand, e.g. a waiting passenger once its train has arrived.
+        /// The pedestrian then carries on or leaves, as it would have at the end of the dwell.
+        pub fn end_dwell(&mut self) {
+            if let Some(dwell) = &mut self.dwell {
+                dwell.started = true;
+                self.stop_timer = 0.0;
+            }
+        }
+        
+        /// Return where the pedestrian stands still, if it hasn't finished standing there yet
+        pub fn get_dwell(&self) -> Option<&Dwell> {
+            return self.dwell.as_ref();
+        }
+        
+        /// Use a contact impulse worked out elsewhere (such as on the GPU) this timestep, instead of working it out from the neighbours it is touching (ContactModel::Granular only)
+        pub fn set_contact_impulse(&mut self, impulse: Option<(f64, f64)>) {
+            self.contact_impulse = impulse;
+        }
+        
+        /// Make the pedestrian walk straight to a place in a queue and wait there (None = it has left the queue)
+        pub fn set_queue_place(&mut self, place: Option<(f64, f64)>) {
+            self.queue_place = place;
+        }
+        
+        /// Return where the pedestrian stands in, or joins, the queue it is waiting in (None = it isn't queueing)
+        pub fn get_queue_place(&self) -> Option<(f64, f64)> {
+            return self.queue_place;
+        }
+        
+        /// Whether the pedestrian has reached its place in a queue, and is waiting there
+        pub fn is_waiting_in_queue(&self) -> bool {
+            return self.queue_place.is_some_and(|place| Vec2::new(self.x, self.y).distance(place.into()) < QUEUE_PLACE_RADIUS);
+        }
+        
+        /// Decide whether to stand or walk on stepping onto a moving walkway with an etiquette, and forget the decision on stepping off
+        fn update_walkway<R: Rng>(&mut self, rng: &mut R) {
+            let etiquette = self.environment.get_walkway(Vec2::new(self.x, self.y)).and_then(|walkway| walkway.get_etiquette());
+            self.walkway_standing = match (etiquette, self.walkway_standing) {
+                (Some(_), Some(standing)) => Some(standing),
+                (Some(etiquette), None) => Some(rng.gen::<f64>() < etiquette.standing_fraction),
+                (None, _) => None
+            };
+        }
+        
+        /// Return the lane of the moving walkway the pedestrian is on that it keeps to (None = it isn't on a walkway with an etiquette)
+        fn get_walkway_lane(&self) -> Option<WalkwaySide> {
+            let etiquette = self.environment.get_walkway(Vec2::new(self.x, self.y))?.get_etiquette()?;
+            let standing = self.walkway_standing?;
+            
+            return Some(match (standing, etiquette.stand_side) {
+                (true, side) => side,
+                (false, WalkwaySide::Left) => WalkwaySide::Right,
+                (false, WalkwaySide::Right) => WalkwaySide::Left
+            });
+        }
+        
+        /// Whether the pedestrian is standing in the standing lane of a moving walkway, being carried along
+        pub fn is_standing_on_walkway(&self) -> bool {
+            if self.walkway_standing != Some(true) {
+                return false;
+            }
+            
+            let position = Vec2::new(self.x, self.y);
+            return self.environment.get_walkway(position).zip(self.get_walkway_lane()).is_some_and(|(walkway, side)| walkway.is_in_lane(position, side));
+        }
+        
+        /// Make the pedestrian follow the crowd around it rather than head for its own destination, because it doesn't know its way (None = it knows its way)
+        pub fn set_herding(&mut self, herding: Option<Herding>) {
+            self.herding = herding;
+        }
+        
+        /// Return how the pedestrian follows the crowd because it doesn't know its way (None = it knows its way)
+        pub fn get_herding(&self) -> Option<&Herding> {
+            return self.herding.as_ref();
+        }
+        
+        /// Read every sign for the pedestrian's destination that it can see and hasn't read yet, if it doesn't know its way (see `Sign`)
+        fn read_signs(&mut self, door_states: &[bool]) {
+            if self.herding.is_none() || self.exit.is_some() {
+                return;
+            }
+            
+            let environment = self.environment.clone();
+            let position = Vec2::new(self.x, self.y);
+            for (i, sign) in environment.signs.iter().enumerate() {
+                if sign.get_destination_group() != self.destination_group || self.signs_read.contains(&i) {
+                    continue;
+                }
+                if !sign.is_within_range(position) || !environment.is_in_sight(position, sign.get_position(), door_states) {
+                    continue;
                 }
                 
+                self.signs_read.push(i);
+                match sign.get_waypoint() {
+                    Some(waypoint) => {
+                        self.waypoints.push_front(waypoint);
+                        self.sign_waypoint = Some(waypoint);
+                        self.plan_path();
+                    },
+                    // The sign shows the pedestrian its destination, so it knows its way from here on
+                    None => {
+                        self.herding = None;
+                        return;
+                    }
+                }
+            }
+        }
+        
+        /// Return the number of signs the pedestrian has read
+        pub fn get_signs_read(&self) -> usize {
+            return self.signs_read.len();
+        }
+        
+        /// Return the number of times the pedestrian has turned to head more than 90° away from the way to its destination while following the crowd.
+        /// The way to its destination is along its planned path or floor field if it has one, or else straight towards its next waypoint or destination.
+        pub fn get_wrong_turns(&self) -> usize {
+            return self.wrong_turns;
+        }
+        
+        /// Whether the pedestrian has finished standing still and leaves the simulation where it stands, rather than walking to its destination
+        pub fn has_left(&self) -> bool {
+            return self.dwell.is_some_and(|dwell| dwell.started && dwell.after == AfterDwell::Leave) && !self.is_stopped();
+        }
+        
+        /// Start standing still on reaching the dwell location (or running out of patience), and go back to walking once the time is up (unless the pedestrian leaves)
+        fn update_dwell(&mut self, time_scale: f64) {
+            if let Some(dwell) = &mut self.dwell {
+                if !dwell.started {
+                    dwell.approach_time += time_scale;
+                    if Vec2::new(self.x, self.y).distance(dwell.location.into()) < DWELL_RADIUS || dwell.approach_time > DWELL_PATIENCE {
+                        dwell.started = true;
+                        let duration = dwell.duration;
+                        self.stop_for(duration);
+                    }
+                } else if dwell.after == AfterDwell::Resume && !self.is_stopped() {
+                    self.dwell = None;
+                }
+            }
+        }
+        
+        /// Whether the pedestrian is overtaking the neighbour at (x, y), so shouldn't slow down for it
+        pub fn is_overtaking(&self, x: f64, y: f64) -> bool {
+            return self.overtaking.is_some_and(|state| state.passing && state.position == (x, y));
+        }
+        
+        /// Record the current facing direction, update the jitter measurement and smooth the drawn facing direction
+        fn update_heading_history(&mut self, time_scale: f64) {
+            
+            self.heading_history.push_back((time_scale, self.facing_direction));
+            self.heading_history_duration += time_scale;
+            
+            // Drop directions that have left the window
+            while self.heading_history.len() > 1 && self.heading_history_duration - self.heading_history[0].0 >= self.params.heading_window {
+                self.heading_history_duration -= self.heading_history.pop_front().unwrap().0;
+            }
+            
+            // Only measure jitter once the window is full, so that a short history doesn't understate it
+            if self.heading_history_duration >= self.params.heading_window {
+                self.jitter_integral.0 += circular_standard_deviation(&self.heading_history) * time_scale;
+                self.jitter_integral.1 += time_scale;
+            }
+            
+            if self.params.heading_smoothing_time > 0.0 {
+                let alpha = 1.0 - (-time_scale / self.params.heading_smoothing_time).exp();
+                let x = (1.0 - alpha) * self.rendered_direction.cos() + alpha * self.facing_direction.cos();
+                let y = (1.0 - alpha) * self.rendered_direction.sin() + alpha * self.facing_direction.sin();
+                self.rendered_direction = y.atan2(x);
+            } else {
+                self.rendered_direction = self.facing_direction;
             }
             
         }
         
         /// Apply some small random fluctuations to the facing direction and current speed
-        fn apply_noise(&mut self, time_scale: f64) {
+        fn apply_noise<R: Rng>(&mut self, time_scale: f64, rng: &mut R) {
             
-            self.facing_direction += (2.0 * rand::random::<f64>() - 1.0) * PEDESTRIAN_DIRECTION_NOISE_FACTOR * time_scale;
-            self.inst_speed += (2.0 * rand::random::<f64>() - 1.0) * PEDESTRIAN_SPEED_NOISE_FACTOR * time_scale;
+            self.facing_direction += (2.0 * rng.gen::<f64>() - 1.0) * self.params.direction_noise_factor * time_scale;
+            self.inst_speed += (2.0 * rng.gen::<f64>() - 1.0) * self.params.speed_noise_factor * time_scale;
             
         }
         
-        /// Check all walls in the relevant environment and resolve any collisions.
-        fn resolve_wall_collisions(&mut self, time_scale: f64) {
+        /// Check all walls and obstacles in the relevant environment and resolve any collisions.
+        /// 
+        /// The path taken during the timestep is checked as well as the end position, so that a fast pedestrian (or a long timestep) can't pass straight through a thin wall.
+        /// 
+        /// * `start` - The position at the start of the timestep, before any movement
+        fn resolve_wall_collisions(&mut self, start: Vec2, time_scale: f64, door_states: &[bool]) {
             
-            for wall in &self.environment.boundaries {
-                // Get the normal vector to the wall
-                let (dist, normal) = wall.get_normal_vector((self.x, self.y));
+            // Hold separate references to the environment & behaviour model while this pedestrian is being modified
+            let environment = self.environment.clone();
+            let behaviour = self.behaviour.clone();
+            
+            // Stop at the first boundary touched on the way, and slide along it for the rest of the timestep.
+            // The slide is checked in the same way, in case it runs into another boundary (e.g. in a corner).
+            let mut start = start;
+            let mut end = Vec2::new(self.x, self.y);
+            for _ in 0..MAX_WALL_SLIDES {
+                let first_contact = environment.get_walls(door_states).filter_map(|wall| wall.get_contact_time(start, end, self.params.radius))
+                    .chain(environment.obstacles.iter().filter_map(|obstacle| obstacle.get_contact_time(start, end, self.params.radius)))
+                    .fold(f64::INFINITY, f64::min);
+                if !first_contact.is_finite() {
+                    break;
+                }
                 
-                let normal_angle = normal.1.atan2(normal.0);
+                let contact = start + (end - start)*first_contact;
+                
+                // The boundary closest to the contact point is the one touched
+                let normal = environment.get_walls(door_states).map(|wall| wall.get_normal_vector(contact))
+                    .chain(environment.obstacles.iter().map(|obstacle| obstacle.get_normal_vector(contact)))
+                    .min_by(|a, b| a.0.total_cmp(&b.0))
+                    .and_then(|(_, normal)| normal.normalised());
+                    
+                let remaining = end - contact;
+                let slide = match normal {
+                    Some(normal) if remaining.dot(normal) < 0.0 => remaining - normal*remaining.dot(normal),
+                    _ => remaining
+                };
+                start = contact;
+                end = contact + slide;
+            }
+            self.x = end.x;
+            self.y = end.y;
+            
+            for wall in environment.get_walls(door_states) {
+                // Get the normal vector to the wall
+                let (dist, normal) = wall.get_normal_vector(Vec2::new(self.x, self.y));
                 
                 // Edge case: if the pedestrian is on the line, don't do anything
                 if dist == 0.0 {
                     return;
                 }
                 
-                // Check for collision
-                if dist < PEDESTRIAN_RADIUS {
-                    // Pedestrian needs to be nudged away from the wall by some multiple (k) of the normal vector
-                    let k = PEDESTRIAN_RADIUS/dist - 1.0;
-                    
-                    // Move the pedestrian away from the wall
-                    self.x += normal.0 * k;
-                    self.y += normal.1 * k;
-                    
-                    
-                    // The angle the pedestrian should be facing to reach their destination (between 0 and 2π)
-                    let target_angle = (self.environment.end_positions[self.group][self.target_location].1 - self.y).atan2(self.environment.end_positions[self.group][self.target_location].0 - self.x);
-                    
-                    // Find the difference between the direction of travel and the target direction
-                    let direction_difference = (target_angle - self.facing_direction + TAU + TAU) % TAU;
-                    if direction_difference > PI/2.0 && direction_difference < 3.0*PI/2.0 {
-                        // Facing away from target
-                        // Need to face away from wall
-                        self.facing_direction = normal_angle;
-                    } else {
-                        // Facing toward target
-                        // Nudge the direction of travel away from the wall
-                        self.facing_direction = nudge_angle(self.facing_direction, normal_angle, time_scale);
+                self.resolve_boundary_collision(dist, normal, behaviour.as_ref(), time_scale);
+            }
+            
+            for obstacle in &environment.obstacles {
+                // Get the normal vector to the edge of the obstacle
+                let (dist, normal) = obstacle.get_normal_vector(Vec2::new(self.x, self.y));
+                
+                // Edge case: if the pedestrian is on the edge or the centre, don't do anything
+                if dist == 0.0 {
+                    return;
+                }
+                
+                self.resolve_boundary_collision(dist, normal, behaviour.as_ref(), time_scale);
+            }
+            
+        }
+        
+        /// Give way to vehicles: step out of the path ahead of each vehicle at up to twice walking pace (so a pedestrian about to walk into it waits at its edge),
+        /// and keep clear of the vehicle itself like a wall.
+        /// 
+        /// This is done between `move_freely` and `finish_movement`, so that any movement is still checked against walls.
+        /// 
+        /// * `vehicles` - The space taken up by every vehicle and the path ahead of it at the start of the timestep (see `Vehicle::get_body` and `Vehicle::get_path`): \[(body, path)]
+        pub fn react_to_vehicles(&mut self, time_scale: f64, vehicles: &[VehicleFootprint]) {
+            // Hold a separate reference to the behaviour model while this pedestrian is being modified
+            let behaviour = self.behaviour.clone();
+            
+            for &(body, path) in vehicles {
+                let (dist, normal) = capsule_normal_vector(path, Vec2::new(self.x, self.y));
+                if dist < self.params.radius {
+                    // A pedestrian right in the middle of the path steps to the vehicle's left
+                    let along = path.1 - path.0;
+                    if let Some(direction) = normal.normalised().or(Vec2::new(along.y, -along.x).normalised()) {
+                        let step = (self.params.radius - dist).min(2.0 * self.get_desired_speed() * time_scale);
+                        self.x += direction.x * step;
+                        self.y += direction.y * step;
                     }
-                    
                 }
                 
-                // If the wall is within the pedestrian's personal space radius, nudge the direction vector away slightly
-                if dist < PEDESTRIAN_PSPACE_RADIUS {
-                    
+                let (dist, normal) = capsule_normal_vector(body, Vec2::new(self.x, self.y));
+                if dist < self.params.radius && normal.length_sq() != 0.0 {
+                    self.resolve_boundary_collision(dist, normal, behaviour.as_ref(), time_scale);
+                }
+            }
+        }
+        
+        /// Get pushed away from any hazards the pedestrian is in, and build up exposure to them.
+        /// If the exposure reaches the `hazard_tolerance` parameter, the pedestrian is incapacitated and is to be removed from the simulation: return true.
+        /// 
+        /// This is done between `move_freely` and `finish_movement`, so that any movement is still checked against walls.
+        /// 
+        /// * `time` - The simulation time at the start of the timestep, in seconds
+        pub fn react_to_hazards(&mut self, time_scale: f64, time: f64) -> bool {
+            let position = Vec2::new(self.x, self.y);
+            let strongest = self.environment.hazards.iter()
+                .map(|hazard| (hazard.get_intensity(position, time), hazard.get_position()))
+                .max_by(|a, b| a.0.total_cmp(&b.0));
+            let (intensity, source) = match strongest {
+                Some((intensity, source)) if intensity > 0.0 => (intensity, source),
+                _ => return false
+            };
+            
+            // A pedestrian right at the source is pushed out in the direction it is facing
+            let direction = (position - source).normalised().unwrap_or(Vec2::from_angle(self.facing_direction));
+            let step = HAZARD_REPULSION * intensity * self.get_desired_speed() * time_scale;
+            self.x += direction.x * step;
+            self.y += direction.y * step;
+            
+            self.hazard_exposure += intensity * time_scale;
+            if self.params.hazard_tolerance > 0.0 && self.hazard_exposure >= self.params.hazard_tolerance && !self.despawned {
+                self.despawned = true;
+                return true;
+            }
+            
+            return false;
+        }
+        
+        /// Return the hazard intensity the pedestrian has been exposed to, integrated over time, in seconds at full intensity
+        pub fn get_hazard_exposure(&self) -> f64 {
+            return self.hazard_exposure;
+        }
+        
+        /// Resolve a collision with a single wall or obstacle, then react to it according to the pedestrian's behaviour model
+        /// 
+        /// * `dist` - The distance from the pedestrian to the closest point on the boundary (negative if inside an obstacle)
+        /// * `normal` - The vector pointing away from the boundary, with a length of |dist|
+        fn resolve_boundary_collision(&mut self, dist: f64, normal: Vec2, behaviour: &dyn BehaviourModel, time_scale: f64) {
+            
+            let normal_angle = normal.angle();
+            
+            // Check for collision
+            if dist < self.params.radius {
+                // Pedestrian needs to be nudged away from the boundary until it is one radius away
+                let k = (self.params.radius - dist) / dist.abs();
+                
+                // Move the pedestrian away from the boundary
+                self.x += normal.x * k;
+                self.y += normal.y * k;
+                
+                
+                // The angle the pedestrian should be facing to reach their destination (between 0 and 2π)
+                let (target_x, target_y) = self.get_steering_target();
+                let target_angle = (target_y - self.y).atan2(target_x - self.x);
+                
+                // Find the difference between the direction of travel and the target direction
+                let direction_difference = normalise_angle(target_angle - self.facing_direction);
+                if direction_difference > PI/2.0 && direction_difference < 3.0*PI/2.0 {
+                    // Facing away from target
+                    // Need to face away from wall
+                    self.facing_direction = normal_angle;
+                } else {
+                    // Facing toward target
                     // Nudge the direction of travel away from the wall
-                    self.facing_direction = nudge_angle(self.facing_direction, normal_angle, WALL_REPULSION*time_scale);
-                    
+                    self.facing_direction = nudge_angle(self.facing_direction, normal_angle, time_scale);
                 }
                 
             }
             
+            // React to the boundary according to the pedestrian's behaviour model
+            behaviour.react_to_wall(self, dist, normal_angle, time_scale);
+            
+        }
+        
+        /// Return the nearest neighbours, nearest first
+        /// 
+        /// * `count` - The most neighbours to return
+        fn get_neighbours_by_distance(&self, other_pedestrians_before: &[(f64, f64, f64, f64, f64)], other_pedestrians_after: &[(f64, f64, f64, f64, f64)], count: usize) -> Vec<(f64, f64, f64, f64, f64)> {
+            let position = Vec2::new(self.x, self.y);
+            let mut neighbours = other_pedestrians_before.iter().chain(other_pedestrians_after).copied().collect::<Vec<_>>();
+            neighbours.sort_by(|a, b| {
+                position.distance(Vec2::new(a.0, a.1)).total_cmp(&position.distance(Vec2::new(b.0, b.1)))
+            });
+            neighbours.truncate(count);
+            
+            return neighbours;
         }
         
-        /// Return destination coordinates
+        /// Return destination coordinates: the exit the pedestrian is evacuating to, or its target location
         pub fn get_dest_coords(&self) -> (f64, f64) {
-            return self.environment.end_positions[self.group][self.target_location];
+            return match self.exit {
+                Some(exit) => self.environment.exits[exit],
+                None => self.environment.end_zones[self.destination_group][self.target_location].get_point(self.end_point).into()
+            };
+        }
+        
+        /// Return the distance from the pedestrian to the nearest part of its destination: the exit it is evacuating to, or its target location's end zone
+        pub fn get_destination_distance(&self) -> f64 {
+            let position = Vec2::new(self.x, self.y);
+            return match self.exit {
+                Some(exit) => position.distance(self.environment.exits[exit].into()),
+                None => self.environment.end_zones[self.destination_group][self.target_location].get_distance(position)
+            };
+        }
+        
+        /// Abandon the destination and any remaining waypoints, and head for the nearest exit instead
+        /// 
+        /// Unless the pedestrian chooses at random, its exit choice strategy is applied straight away and may pick a different exit.
+        /// Does nothing if the environment has no exits.
+        pub fn evacuate(&mut self) {
+            let position = Vec2::new(self.x, self.y);
+            let nearest = (0..self.environment.exits.len()).min_by(|a, b| {
+                let dist_a = position.distance(self.environment.exits[*a].into());
+                let dist_b = position.distance(self.environment.exits[*b].into());
+                dist_a.total_cmp(&dist_b)
+            });
+            
+            if let Some(exit) = nearest {
+                self.exit = Some(exit);
+                self.exit_choice_timer = 0.0;
+                self.waypoints.clear();
+                self.plan_path();
+            }
+        }
+        
+        /// Periodically reconsider which end position (or exit, when evacuating) to head for, according to the pedestrian's exit choice strategy
+        /// 
+        /// * `door_states` - Whether each door in the area is open, since closed doors block the view of an end position
+        /// * `other_pedestrians_before`, `other_pedestrians_after` - The other pedestrians, used to judge congestion: \[(x, y, direction, radius, speed)]
+        fn update_exit_choice(&mut self, time_scale: f64, door_states: &[bool], other_pedestrians_before: &[(f64, f64, f64, f64, f64)], other_pedestrians_after: &[(f64, f64, f64, f64, f64)]) {
+            if self.params.exit_choice == ExitChoice::Random {
+                return;
+            }
+            
+            self.exit_choice_timer -= time_scale;
+            if self.exit_choice_timer > 0.0 {
+                return;
+            }
+            self.exit_choice_timer = if self.params.exit_choice_interval > 0.0 {self.params.exit_choice_interval} else {f64::INFINITY};
+            
+            let environment = self.environment.clone();
+            let candidates = match self.exit {
+                Some(_) => &environment.exits,
+                None => &environment.end_positions[self.destination_group]
+            };
+            let position = Vec2::new(self.x, self.y);
+            
+            // The walking distance to each candidate, along the floor field if there is one
+            let distances = candidates.iter().enumerate().map(|(i, candidate)| {
+                let field_distance = match (&self.navigation, self.exit) {
+                    (Some(Navigation::FloorField(floor_field)), Some(_)) => floor_field.get_exit_distance(i, position),
+                    (Some(Navigation::FloorField(floor_field)), None) => floor_field.get_distance(self.destination_group, i, position),
+                    _ => f64::INFINITY
+                };
+                if field_distance.is_finite() {field_distance} else {position.distance((*candidate).into())}
+            }).collect::<Vec<_>>();
+            
+            let scores = candidates.iter().zip(&distances).map(|(candidate, distance)| {
+                match self.params.exit_choice {
+                    ExitChoice::LeastCongested => {
+                        let queue = other_pedestrians_before.iter().chain(other_pedestrians_after).filter(|(n_x, n_y, _, _, _)| {
+                            Vec2::new(*n_x, *n_y).distance((*candidate).into()) < EXIT_QUEUE_RADIUS
+                        }).count();
+                        distance / self.target_speed + (queue as f64) * QUEUE_DELAY_PER_PEDESTRIAN
+                    },
+                    ExitChoice::NearestVisible => {
+                        if environment.is_visible(position, (*candidate).into(), door_states) {*distance} else {f64::INFINITY}
+                    },
+                    _ => *distance
+                }
+            }).collect::<Vec<_>>();
+            
+            let best = (0..scores.len()).filter(|i| scores[*i].is_finite()).min_by(|a, b| scores[*a].total_cmp(&scores[*b]));
+            let current = self.exit.unwrap_or(self.target_location);
+            
+            if let Some(best) = best {
+                if best != current {
+                    match self.exit {
+                        Some(_) => {self.exit = Some(best)},
+                        None => {self.target_location = best}
+                    }
+                    self.plan_path();
+                }
+            }
+        }
+        
+        /// Return the index of the end position the pedestrian is heading for, among its destination group's end positions
+        pub fn get_target_location(&self) -> usize {
+            return self.target_location;
+        }
+        
+        /// Head for a different end position of the destination group instead, e.g. a train door with more room
+        pub fn set_target_location(&mut self, target_location: usize) {
+            self.target_location = target_location;
+            self.plan_path();
+        }
+        
+        /// Return the exit the pedestrian is evacuating to, or None if it isn't evacuating
+        pub fn get_exit(&self) -> Option<usize> {
+            return self.exit;
+        }
+        
+        /// Return the coordinates the pedestrian is currently steering towards: the next point on its planned path, the next waypoint, or the destination
+        /// 
+        /// When following a floor field to the destination, this is a point a short distance ahead along the field.
+        pub fn get_steering_target(&self) -> (f64, f64) {
+            // Walk straight to the place in the queue
+            if let Some(place) = self.queue_place {
+                return place;
+            }
+            
+            // Walk straight to the place to stand still first
+            if let Some(dwell) = &self.dwell {
+                if !dwell.started {
+                    return dwell.location;
+                }
+            }
+            
+            // Keep to the standing or the walking lane of a moving walkway
+            if let Some(side) = self.get_walkway_lane() {
+                let walkway = self.environment.get_walkway(Vec2::new(self.x, self.y)).unwrap();
+                return walkway.get_lane_end(side).into();
+            }
+            
+            if let Some(point) = self.planned_path.front() {
+                return *point;
+            }
+            
+            if let (Some(Navigation::FloorField(floor_field)), true) = (&self.navigation, self.waypoints.is_empty()) {
+                let position = Vec2::new(self.x, self.y);
+                let direction = match self.exit {
+                    Some(exit) => floor_field.get_exit_direction(exit, position),
+                    None => floor_field.get_direction(self.destination_group, self.target_location, position)
+                };
+                if let Some(direction) = direction {
+                    return (position + direction * FLOOR_FIELD_LOOK_AHEAD).into();
+                }
+            }
+            
+            return self.get_next_goal();
+        }
+        
+        /// Return the next waypoint, or the destination if there are none left
+        fn get_next_goal(&self) -> (f64, f64) {
+            return self.waypoints.front().copied().unwrap_or(self.get_dest_coords());
+        }
+        
+        /// Return the waypoints that the pedestrian is yet to reach, in order
+        pub fn get_waypoints(&self) -> &VecDeque<(f64, f64)> {
+            return &self.waypoints;
+        }
+        
+        /// Replace the waypoints that the pedestrian walks through before heading to its destination
+        pub fn set_waypoints(&mut self, waypoints: Vec<(f64, f64)>) {
+            self.waypoints = waypoints.into();
+            self.plan_path();
+        }
+        
+        /// Set how the pedestrian finds its way around geometry. With path planning, a path to the next waypoint is planned straight away.
+        pub fn set_navigation(&mut self, navigation: Navigation) {
+            self.navigation = Some(navigation);
+            self.plan_path();
+        }
+        
+        /// Attach a pedestrian loaded from a checkpoint to the simulation it is restored into, keeping any path it had already planned
+        pub fn attach(&mut self, environment: Arc<SimArea>, behaviour: Arc<dyn BehaviourModel>, navigation: Option<Navigation>) {
+            self.environment = environment;
+            self.behaviour = behaviour;
+            self.navigation = navigation;
+        }
+        
+        /// Plan a path from the current position to the next waypoint (or the destination), if path planning is enabled
+        /// 
+        /// If no path can be found, the pedestrian walks straight towards the next waypoint instead.
+        fn plan_path(&mut self) {
+            self.replan_timer = 0.0;
+            
+            if let Some(Navigation::PathPlanning(navigation)) = &self.navigation {
+                self.planned_path = navigation.find_path(Vec2::new(self.x, self.y), self.get_next_goal().into())
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|point| point.into())
+                    .collect();
+            }
+        }
+        
+        /// Remove any planned path points and waypoints that have been reached, and replan the path if the pedestrian can no longer see where it is heading
+        fn update_waypoints(&mut self, time_scale: f64) {
+            let position = Vec2::new(self.x, self.y);
+            
+            while let Some(point) = self.planned_path.front() {
+                if position.distance((*point).into()) < WAYPOINT_RADIUS {
+                    self.planned_path.pop_front();
+                } else {
+                    break;
+                }
+            }
+            
+            let mut reached_waypoint = false;
+            while let Some(waypoint) = self.waypoints.front() {
+                if self.planned_path.is_empty() && position.distance((*waypoint).into()) < WAYPOINT_RADIUS {
+                    self.waypoints.pop_front();
+                    reached_waypoint = true;
+                } else {
+                    break;
+                }
+            }
+            
+            if reached_waypoint {
+                self.plan_path();
+                return;
+            }
+            
+            // Periodically check that the path isn't blocked (e.g. after being pushed around a corner by other pedestrians)
+            if let Some(Navigation::PathPlanning(navigation)) = &self.navigation {
+                self.replan_timer += time_scale;
+                if self.replan_timer >= REPLAN_INTERVAL {
+                    self.replan_timer = 0.0;
+                    if !navigation.is_visible(position, self.get_steering_target().into()) {
+                        self.plan_path();
+                    }
+                }
+            }
+        }
+        
+        /// Return the behaviour model this pedestrian follows
+        pub fn get_behaviour(&self) -> &Arc<dyn BehaviourModel> {
+            return &self.behaviour;
+        }
+        
+        /// Change the behaviour model this pedestrian follows
+        pub fn set_behaviour(&mut self, behaviour: Arc<dyn BehaviourModel>) {
+            self.behaviour = behaviour;
+        }
+        
+        /// Return the parameters of the pedestrian model used by this pedestrian
+        pub fn get_params(&self) -> &SimParams {
+            return &self.params;
+        }
+        
+        /// Return the average heading jitter of this pedestrian so far: the circular standard deviation of its facing direction over the heading window, in radians.
+        /// 
+        /// Returns None if the pedestrian hasn't walked for a full heading window yet.
+        pub fn get_jitter(&self) -> Option<f64> {
+            if self.jitter_integral.1 == 0.0 {
+                return None;
+            }
+            
+            return Some(self.jitter_integral.0 / self.jitter_integral.1);
+        }
+        
+        /// Return the direction the pedestrian intends to be walking in a short time from now, as read from its body language by neighbouring pedestrians.
+        /// 
+        /// This is its facing direction turned at its current turn rate for the given time, up to MAX_SIGNALLED_TURN.
+        /// 
+        /// * `horizon` - How far ahead the intention is signalled, in seconds
+        pub fn get_intended_direction(&self, horizon: f64) -> f64 {
+            return normalise_angle(self.facing_direction + (self.turn_rate * horizon).clamp(-MAX_SIGNALLED_TURN, MAX_SIGNALLED_TURN));
+        }
+        
+        /// Return the total time the pedestrian has been held back giving way at junctions, in seconds
+        pub fn get_junction_delay(&self) -> f64 {
+            return self.junction_delay;
+        }
+        
+        /// Return the multiplier applied to how far the pedestrian looks ahead, and to how close an oncoming pedestrian gets before it slows down, at its current speed (see the `look_ahead_time` parameter)
+        pub fn get_look_ahead_scale(&self) -> f64 {
+            if self.params.look_ahead_time <= 0.0 {
+                return 1.0;
+            }
+            return (self.params.look_ahead_time * self.inst_speed / self.params.look_ahead_radius).max(MIN_LOOK_AHEAD_SCALE);
+        }
+        
+        /// Return how far the pedestrian looks ahead for obstacles at its current speed, in metres
+        pub fn get_look_ahead_radius(&self) -> f64 {
+            return self.params.look_ahead_radius * self.get_look_ahead_scale();
+        }
+        
+        /// Return the ID of the pedestrian, which is unique within its simulation
+        pub fn get_id(&self) -> u64 {
+            return self.id;
+        }
+        
+        /// Set the ID of the pedestrian. Simulations number pedestrians in the order they are added.
+        pub fn set_id(&mut self, id: u64) {
+            self.id = id;
         }
         
         /// Return group ID
@@ -440,7 +1667,96 @@ pub mod pedestrian {
             return self.group;
         }
         
-        /// Check for collisions with timing boundaries, and log the time taken to travel between two of them
+        /// Return the ID of the group whose end positions the pedestrian walks to
+        pub fn get_destination_group(&self) -> usize {
+            return self.destination_group;
+        }
+        
+        /// Return the start zone the pedestrian starts in
+        pub fn get_start_zone(&self) -> Zone {
+            return self.environment.start_zones[self.group][self.start_location];
+        }
+        
+        /// Move the pedestrian to a different starting position, facing its first waypoint or its destination. Only use this before it starts walking.
+        pub fn set_start_position(&mut self, position: (f64, f64)) {
+            self.x = position.0;
+            self.y = position.1;
+            
+            let first_target = self.get_next_goal();
+            self.facing_direction = normalise_angle((first_target.1 - self.y).atan2(first_target.0 - self.x));
+            self.rendered_direction = self.facing_direction;
+            self.previous_position = None;
+        }
+        
+        /// Start the pedestrian's route again once it has reached its destination, so that it keeps walking (see `CrowdSim::set_recirculation`).
+        /// Its waypoints are restored and it can be timed between the timing boundaries again.
+        /// 
+        /// * `respawn` - Whether to move the pedestrian back to a random point in its start zone first (false = it walks its route again from where it is)
+        pub fn restart_route<R: Rng>(&mut self, respawn: bool, rng: &mut R) {
+            if respawn {
+                let start_coords = self.get_start_zone().get_point((rng.gen(), rng.gen()));
+                self.x = start_coords.x;
+                self.y = start_coords.y;
+                self.previous_position = None;
+            }
+            
+            self.waypoints = if self.destination_group == self.group {
+                self.environment.group_waypoints[self.group].iter().copied().collect()
+            } else {
+                VecDeque::new()
+            };
+            self.end_point = (rng.gen(), rng.gen());
+            self.timing_boundary_states.fill(false);
+            self.timing_boundary_elapsed = None;
+            self.stall_progress = None;
+            self.stall_timer = 0.0;
+            self.laps += 1;
+            self.plan_path();
+            
+            if respawn {
+                let first_target = self.get_next_goal();
+                self.facing_direction = normalise_angle((first_target.1 - self.y).atan2(first_target.0 - self.x));
+                self.rendered_direction = self.facing_direction;
+            }
+        }
+        
+        /// Return the number of times the pedestrian has reached its destination and started its route again
+        pub fn get_laps(&self) -> usize {
+            return self.laps;
+        }
+        
+        /// Return the position to draw the pedestrian at, part of the way through the next timestep
+        /// 
+        /// * `interpolation` - How far from the position at the start of the last timestep to the current position to go, from 0 to 1
+        pub fn get_interpolated_position(&self, interpolation: f64) -> (f64, f64) {
+            return match self.previous_position {
+                Some((previous_x, previous_y)) => (
+                    previous_x + (self.x - previous_x) * interpolation,
+                    previous_y + (self.y - previous_y) * interpolation
+                ),
+                None => (self.x, self.y)
+            };
+        }
+        
+        /// Return the names of the cohorts this pedestrian has been tagged with
+        pub fn get_cohorts(&self) -> &[String] {
+            return &self.cohorts;
+        }
+        
+        /// Check whether this pedestrian has been tagged with a cohort
+        pub fn in_cohort(&self, cohort: &str) -> bool {
+            return self.cohorts.iter().any(|name| name == cohort);
+        }
+        
+        /// Tag this pedestrian with a cohort (if it isn't already tagged with it)
+        pub fn add_to_cohort(&mut self, cohort: &str) {
+            if !self.in_cohort(cohort) {
+                self.cohorts.push(cohort.to_string());
+            }
+        }
+        
+        /// Check for collisions with timing boundaries, and return the time taken to travel between two of them once the second is reached.
+        /// The simulation records it as a `TimingEvent`.
         pub fn check_timing_boundaries(&mut self, time_scale: f64) -> Option<f64> {
             
             // Increment time elapsed
@@ -451,9 +1767,9 @@ pub mod pedestrian {
             let mut touched_boundary_count = 0;
             
             // Check each timing boundary
-            for (i, wall) in self.environment.timing_boundaries.iter().enumerate() {
+            for (i, line) in self.environment.timing_boundaries.iter().enumerate() {
                 
-                if !self.timing_boundary_states[i] && wall.get_normal_vector((self.x, self.y)).0 <= PEDESTRIAN_RADIUS {
+                if !self.timing_boundary_states[i] && line.get_distance(Vec2::new(self.x, self.y)) <= self.params.radius {
                     self.timing_boundary_states[i] = true;
                     if self.timing_boundary_elapsed.is_none() {
                         self.timing_boundary_elapsed = Some(0.0);
@@ -468,7 +1784,6 @@ pub mod pedestrian {
             if self.timing_boundary_elapsed.is_some() && touched_boundary_count == 2 {
                 let travel_time = self.timing_boundary_elapsed.unwrap();
                 self.timing_boundary_elapsed = None;
-                //println!("Time: {}s, Group: {}", (travel_time*100.0).round()/100.0, self.group);
                 return Some(travel_time);
             }
             
@@ -477,75 +1792,95 @@ pub mod pedestrian {
         }
         
         /// Draw this pedestrian with RayLib
-        pub fn draw(&self, rl_handle: &mut RaylibDrawHandle, offset: (i32, i32), draw_scale: i32) {
+        /// 
+        /// * `colour` - The colour to fill the pedestrian with (None = the usual grey)
+        /// * `interpolation` - How far through the next timestep to draw the pedestrian (see `get_interpolated_position`)
+        pub fn draw(&self, rl_handle: &mut RaylibDrawHandle, offset: (i32, i32), draw_scale: i32, layers: DrawLayers, colour: Option<Color>, interpolation: f64) {
+            
+            let (x, y) = self.get_interpolated_position(interpolation);
             
-            if DRAW_EXTRA_PEDESTRIAN_INFO {
+            if layers.vision_zones {
                 // Look-ahead zone
                 rl_handle.draw_circle_sector(
-                    Vector2::new(offset.0 as f32 + (draw_scale as f32)*(self.x as f32), offset.1 as f32 + (draw_scale as f32)*(self.y as f32)),
-                    (draw_scale as f32) * (PEDESTRIAN_LOOK_AHEAD_RADIUS as f32),
-                    ((PI/2.0 - self.facing_direction + PEDESTRIAN_LOOK_AHEAD_FOV/2.0)/TAU*360.0) as f32,
-                    ((PI/2.0 - self.facing_direction - PEDESTRIAN_LOOK_AHEAD_FOV/2.0)/TAU*360.0) as f32,
+                    Vector2::new(offset.0 as f32 + (draw_scale as f32)*(x as f32), offset.1 as f32 + (draw_scale as f32)*(y as f32)),
+                    (draw_scale as f32) * (self.get_look_ahead_radius() as f32),
+                    ((PI/2.0 - self.rendered_direction + self.params.look_ahead_fov/2.0)/TAU*360.0) as f32,
+                    ((PI/2.0 - self.rendered_direction - self.params.look_ahead_fov/2.0)/TAU*360.0) as f32,
                     10,
                     Color::fade(&Color::from_hex(PEDESTRIAN_ZONE_COLOUR).unwrap(), 0.2)
                 );
                 
                 // Look-beside zone
                 rl_handle.draw_circle_sector(
-                    Vector2::new(offset.0 as f32 + (draw_scale as f32)*(self.x as f32), offset.1 as f32 + (draw_scale as f32)*(self.y as f32)),
-                    (draw_scale as f32) * (PEDESTRIAN_LOOK_BESIDE_RADIUS as f32),
-                    ((PI/2.0 - self.facing_direction + PEDESTRIAN_LOOK_AHEAD_FOV/2.0 + PEDESTRIAN_LOOK_BESIDE_FOV)/TAU*360.0) as f32,
-                    ((PI/2.0 - self.facing_direction + PEDESTRIAN_LOOK_AHEAD_FOV/2.0)/TAU*360.0) as f32,
+                    Vector2::new(offset.0 as f32 + (draw_scale as f32)*(x as f32), offset.1 as f32 + (draw_scale as f32)*(y as f32)),
+                    (draw_scale as f32) * (self.params.look_beside_radius as f32),
+                    ((PI/2.0 - self.rendered_direction + self.params.look_ahead_fov/2.0 + self.params.look_beside_fov)/TAU*360.0) as f32,
+                    ((PI/2.0 - self.rendered_direction + self.params.look_ahead_fov/2.0)/TAU*360.0) as f32,
                     10,
                     Color::fade(&Color::from_hex(PEDESTRIAN_ZONE_COLOUR).unwrap(), 0.2)
                 );
                 rl_handle.draw_circle_sector(
-                    Vector2::new(offset.0 as f32 + (draw_scale as f32)*(self.x as f32), offset.1 as f32 + (draw_scale as f32)*(self.y as f32)),
-                    (draw_scale as f32) * (PEDESTRIAN_LOOK_BESIDE_RADIUS as f32),
-                    ((PI/2.0 - self.facing_direction - PEDESTRIAN_LOOK_AHEAD_FOV/2.0)/TAU*360.0) as f32,
-                    ((PI/2.0 - self.facing_direction - PEDESTRIAN_LOOK_AHEAD_FOV/2.0 - PEDESTRIAN_LOOK_BESIDE_FOV)/TAU*360.0) as f32,
+                    Vector2::new(offset.0 as f32 + (draw_scale as f32)*(x as f32), offset.1 as f32 + (draw_scale as f32)*(y as f32)),
+                    (draw_scale as f32) * (self.params.look_beside_radius as f32),
+                    ((PI/2.0 - self.rendered_direction - self.params.look_ahead_fov/2.0)/TAU*360.0) as f32,
+                    ((PI/2.0 - self.rendered_direction - self.params.look_ahead_fov/2.0 - self.params.look_beside_fov)/TAU*360.0) as f32,
                     10,
                     Color::fade(&Color::from_hex(PEDESTRIAN_ZONE_COLOUR).unwrap(), 0.2)
                 );
-                
-                // Personal space
+            }
+            
+            if layers.personal_space {
                 rl_handle.draw_circle(
-                    offset.0 + ((draw_scale as f64)*self.x) as i32,
-                    offset.1 + ((draw_scale as f64)*self.y) as i32,
-                    (draw_scale as f32) * (PEDESTRIAN_PSPACE_RADIUS as f32),
+                    offset.0 + ((draw_scale as f64)*x) as i32,
+                    offset.1 + ((draw_scale as f64)*y) as i32,
+                    (draw_scale as f32) * (self.params.pspace_radius as f32),
                     Color::fade(&Color::from_hex(PEDESTRIAN_ZONE_COLOUR).unwrap(), 0.2)
                 );
             }
             
+            if let Some(luggage) = &self.luggage {
+                let (a, b, radius) = luggage.get_capsule(Vec2::new(x, y), self.rendered_direction, self.params.radius);
+                let (a, b) = (
+                    Vector2::new(offset.0 as f32 + (draw_scale as f32)*(a.x as f32), offset.1 as f32 + (draw_scale as f32)*(a.y as f32)),
+                    Vector2::new(offset.0 as f32 + (draw_scale as f32)*(b.x as f32), offset.1 as f32 + (draw_scale as f32)*(b.y as f32))
+                );
+                let colour = Color::from_hex(LUGGAGE_COLOUR).unwrap();
+                
+                rl_handle.draw_line_ex(a, b, (draw_scale as f32) * (2.0*radius as f32), colour);
+                rl_handle.draw_circle_v(a, (draw_scale as f32) * (radius as f32), colour);
+                rl_handle.draw_circle_v(b, (draw_scale as f32) * (radius as f32), colour);
+            }
+            
             // Collision hitbox
             rl_handle.draw_ellipse(
-                offset.0 + ((draw_scale as f64)*self.x) as i32,
-                offset.1 + ((draw_scale as f64)*self.y) as i32,
-                (draw_scale as f32) * (PEDESTRIAN_RADIUS as f32),
-                (draw_scale as f32) * (PEDESTRIAN_RADIUS as f32),
-                Color::from_hex(PEDESTRIAN_COLOUR).unwrap()
+                offset.0 + ((draw_scale as f64)*x) as i32,
+                offset.1 + ((draw_scale as f64)*y) as i32,
+                (draw_scale as f32) * (self.params.radius as f32),
+                (draw_scale as f32) * (self.params.radius as f32),
+                colour.unwrap_or(Color::from_hex(PEDESTRIAN_COLOUR).unwrap())
             );
             
-            if DRAW_EXTRA_PEDESTRIAN_INFO {
-                let target_x = self.environment.end_positions[self.group][self.target_location].0;
-                let target_y = self.environment.end_positions[self.group][self.target_location].1;
-                let target_angle = ((target_y - self.y).atan2(target_x - self.x) + TAU) % TAU;
+            if layers.target_lines {
+                let (target_x, target_y) = self.get_steering_target();
+                let target_angle = normalise_angle((target_y - y).atan2(target_x - x));
                 
                 // Direction of destination
                 rl_handle.draw_line(
-                    offset.0 + ((draw_scale as f64)*self.x) as i32,
-                    offset.1 + ((draw_scale as f64)*self.y) as i32,
-                    offset.0 + ((draw_scale as f64)*(self.x + target_angle.cos())) as i32,
-                    offset.1 + ((draw_scale as f64)*(self.y + target_angle.sin())) as i32,
+                    offset.0 + ((draw_scale as f64)*x) as i32,
+                    offset.1 + ((draw_scale as f64)*y) as i32,
+                    offset.0 + ((draw_scale as f64)*(x + target_angle.cos())) as i32,
+                    offset.1 + ((draw_scale as f64)*(y + target_angle.sin())) as i32,
                     Color::from_hex(TARGET_LINE_COLOUR).unwrap()
                 );
-                
+            }
+            
+            if layers.velocity_vectors {
                 // Direction of travel
                 rl_handle.draw_line(
-                    offset.0 + ((draw_scale as f64)*self.x) as i32,
-                    offset.1 + ((draw_scale as f64)*self.y) as i32,
-                    offset.0 + ((draw_scale as f64)*(self.x + self.inst_speed * self.facing_direction.cos())) as i32,
-                    offset.1 + ((draw_scale as f64)*(self.y + self.inst_speed * self.facing_direction.sin())) as i32,
+                    offset.0 + ((draw_scale as f64)*x) as i32,
+                    offset.1 + ((draw_scale as f64)*y) as i32,
+                    offset.0 + ((draw_scale as f64)*(x + self.inst_speed * self.rendered_direction.cos())) as i32,
+                    offset.1 + ((draw_scale as f64)*(y + self.inst_speed * self.rendered_direction.sin())) as i32,
                     Color::from_hex(FACING_LINE_COLOUR).unwrap()
                 );
             }
@@ -554,21 +1889,262 @@ pub mod pedestrian {
         
     }
     
-    /// Given an input angle and a target angle, move the input angle so that it is closer to the target angle
+    /// Return the circular standard deviation of a set of angles weighted by duration, in radians
     /// 
-    /// * `initial_angle` - Angle in radians, between 0 and 2π
-    /// * `target_angle` - Angle in radians, between -π and 2π
-    /// * `nudge_ratio` - A multiplier for how much the angle is changed (change in angle = diff(target_angle, initial_angle) * nudge_ratio)
-    fn nudge_angle(initial_angle: f64, target_angle: f64, nudge_ratio: f64) -> f64 {
+    /// * `angles` - \[(weight, angle)]
+    fn circular_standard_deviation(angles: &VecDeque<(f64, f64)>) -> f64 {
+        let total_weight = angles.iter().map(|(w, _)| w).sum::<f64>();
+        let mean_x = angles.iter().map(|(w, a)| w * a.cos()).sum::<f64>() / total_weight;
+        let mean_y = angles.iter().map(|(w, a)| w * a.sin()).sum::<f64>() / total_weight;
+        
+        // Length of the mean resultant vector, limited to avoid taking the log of 0 or of a rounding error above 1
+        let r = (mean_x*mean_x + mean_y*mean_y).sqrt().clamp(f64::MIN_POSITIVE, 1.0);
+        
+        return (-2.0 * r.ln()).sqrt();
+    }
+    
+    /// A placeholder for the environment of a pedestrian loaded from a checkpoint, until it is attached to a simulation
+    fn detached_environment() -> Arc<SimArea> {
+        return Arc::new(SimArea::new());
+    }
+    
+    /// A placeholder for the behaviour model of a pedestrian loaded from a checkpoint, until it is attached to a simulation
+    fn detached_behaviour() -> Arc<dyn BehaviourModel> {
+        return Arc::new(Etiquette::NoBias);
+    }
+    
+    
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha8Rng;
+        use crate::simulation::junction::junction::PriorityRule;
+        
+        const EPSILON: f64 = 1e-9;
+        
+        /// The length of every timestep in these tests, in seconds
+        const TIME_SCALE: f64 = 0.1;
+        
+        fn assert_close(a: f64, b: f64) {
+            assert!((a - b).abs() < EPSILON, "{} != {}", a, b);
+        }
+        
+        /// Create an open area for a pedestrian to walk from the origin in the +x direction
+        fn create_area() -> SimArea {
+            let mut area = SimArea::new();
+            area.add_start_end_group(vec![(0.0,0.0)], vec![(10.0,0.0)]);
+            return area;
+        }
+        
+        /// Create a pedestrian at the origin, walking in the +x direction at 1 m/s
+        fn create_walker(params: SimParams, area: SimArea) -> Walker {
+            let mut walker = Walker::new(Arc::new(area), (0, 0), (0, 0), 1.3, detached_behaviour(), params, &mut ChaCha8Rng::seed_from_u64(0));
+            walker.x = 0.0;
+            walker.y = 0.0;
+            walker.facing_direction = 0.0;
+            walker.inst_speed = 1.0;
+            
+            return walker;
+        }
+        
+        /// Apply a single steering rule, with the pedestrian heading for a target in the +x direction unless given otherwise
+        fn apply_rule(rule: usize, walker: &mut Walker, target_angle: f64, before: &[(f64, f64, f64, f64, f64)], after: &[(f64, f64, f64, f64, f64)]) {
+            let behaviour = Etiquette::NoBias;
+            let surroundings = Surroundings {
+                time_scale: TIME_SCALE,
+                target_angle,
+                behaviour: &behaviour,
+                door_states: &[],
+                other_pedestrians_before: before,
+                other_pedestrians_after: after
+            };
+            STEERING_RULES[rule](walker, &surroundings);
+        }
+        
+        #[test]
+        fn steer_towards_target() {
+            let params = SimParams::default();
+            let mut walker = create_walker(params, create_area());
+            
+            // Already facing the target
+            apply_rule(0, &mut walker, 0.0, &[], &[]);
+            assert_close(walker.facing_direction, 0.0);
+            
+            // Turns part of the way towards a target to the side
+            apply_rule(0, &mut walker, PI/2.0, &[], &[]);
+            assert_close(walker.facing_direction, nudge_angle(0.0, PI/2.0, params.direction_change_factor * TIME_SCALE));
+            assert!(walker.facing_direction > 0.0 && walker.facing_direction < PI/2.0);
+        }
+        
+        #[test]
+        fn overtake_slower_pedestrian() {
+            // Walking at 0.5 m/s, directly ahead
+            let slower = |time: f64| (1.0 + 0.5*time, 0.0, 0.0, 0.205, 0.5);
+            
+            let mut walker = create_walker(SimParams {overtaking: Overtaking::Off, ..SimParams::default()}, create_area());
+            for step in 0..3 {
+                apply_rule(1, &mut walker, 0.0, &[], &[slower(step as f64 * TIME_SCALE)]);
+            }
+            assert!(walker.overtaking.is_none());
+            assert_close(walker.facing_direction, 0.0);
+            
+            let mut walker = create_walker(SimParams {overtaking: Overtaking::Left, ..SimParams::default()}, create_area());
+            // First the pedestrian ahead is followed, then once its speed is known to be slow enough it is passed
+            apply_rule(1, &mut walker, 0.0, &[], &[slower(0.0)]);
+            assert!(walker.overtaking.is_some_and(|state| !state.passing));
+            apply_rule(1, &mut walker, 0.0, &[], &[slower(TIME_SCALE)]);
+            assert!(walker.overtaking.is_some_and(|state| state.passing));
+            assert_close(walker.facing_direction, 0.0);
+            
+            // Swings out to the left (anticlockwise)
+            apply_rule(1, &mut walker, 0.0, &[], &[slower(2.0*TIME_SCALE)]);
+            assert!(wrap_angle(walker.facing_direction) < 0.0);
+        }
+        
+        #[test]
+        fn anticipate_head_on_collision() {
+            // Walking towards the pedestrian at 1 m/s, 0.1m to the right of its path, so that they touch when 0.41m apart
+            let oncoming = (2.0, 0.1, PI, 0.205, 1.0);
+            let time_to_collision = (2.0 - (0.41_f64*0.41 - 0.1*0.1).sqrt()) / 2.0;
+            
+            let mut walker = create_walker(SimParams::default(), create_area());
+            apply_rule(2, &mut walker, 0.0, &[oncoming], &[]);
+            assert_close(walker.facing_direction, 0.0);
+            assert_close(walker.inst_speed, 1.0);
+            
+            let mut walker = create_walker(SimParams {anticipation_horizon: 2.0, ..SimParams::default()}, create_area());
+            apply_rule(2, &mut walker, 0.0, &[oncoming], &[]);
+            let urgency = 1.0 - time_to_collision / 2.0;
+            // Turns left, so that the pedestrian passes on the right as it already would
+            assert_close(wrap_angle(walker.facing_direction), -ANTICIPATION_TURN_RATE * urgency * TIME_SCALE);
+            assert_close(walker.inst_speed, 1.0 - ANTICIPATION_DECEL * urgency * TIME_SCALE);
+            
+            // Nothing to anticipate with a pedestrian walking away
+            let mut walker = create_walker(SimParams {anticipation_horizon: 2.0, ..SimParams::default()}, create_area());
+            apply_rule(2, &mut walker, 0.0, &[(2.0, 0.0, 0.0, 0.205, 1.5)], &[]);
+            assert_close(walker.facing_direction, 0.0);
+            assert_close(walker.inst_speed, 1.0);
+        }
         
-        // The difference between the initial and target angles
-        let mut angle_diff = initial_angle - target_angle;
+        #[test]
+        fn react_to_neighbours() {
+            let params = SimParams::default();
+            
+            // Oncoming, within the look-ahead radius and personal space: slow down, decelerate and turn away (to the left)
+            let mut walker = create_walker(params, create_area());
+            apply_rule(3, &mut walker, 0.0, &[], &[(1.0, 0.0, PI, 0.205, 1.0)]);
+            assert_close(walker.inst_speed, params.minimum_speed.max(1.0 - params.accel*TIME_SCALE/2.0 - params.opposing_decel*TIME_SCALE));
+            assert_close(walker.facing_direction, nudge_angle(0.0, -PI/2.0, params.pspace_repulsion*TIME_SCALE));
+            
+            // Touching a pedestrian ahead: moved apart and stopped, facing away from it
+            let mut walker = create_walker(params, create_area());
+            apply_rule(3, &mut walker, 0.0, &[(0.3, 0.0, 0.0, 0.205, 0.0)], &[]);
+            assert_close(walker.x, 0.3 - 0.41);
+            assert_close(walker.inst_speed, 0.0);
+            assert_close(normalise_angle(walker.facing_direction), PI);
+            
+            // Out of sight behind
+            let mut walker = create_walker(params, create_area());
+            apply_rule(3, &mut walker, 0.0, &[(-1.0, 0.0, 0.0, 0.205, 1.0)], &[]);
+            assert_close(walker.inst_speed, 1.0);
+            assert_close(walker.facing_direction, 0.0);
+        }
         
-        // Constrain angle_diff between -π and π
-        angle_diff = (angle_diff + TAU + PI) % TAU - PI;
+        #[test]
+        fn give_way_at_junction() {
+            // The junction zone starts 1m ahead
+            let mut area = create_area();
+            area.add_junction_zone((1.0, -1.0), (3.0, 1.0), PriorityRule::FirstComeFirstServed);
+            // Already crossing the zone, across this pedestrian's path
+            let crossing = (2.0, -0.5, PI/2.0, 0.205, 1.0);
+            let params = SimParams::default();
+            
+            let mut walker = create_walker(params, area);
+            apply_rule(4, &mut walker, 0.0, &[crossing], &[]);
+            // Slow enough to stop at the edge of the zone within GIVE_WAY_STOP_TIME (1s)
+            assert_close(walker.inst_speed, 1.0 - params.radius);
+            assert_close(walker.give_way_timer, TIME_SCALE);
+            
+            // Once the conflict has cleared, the timer is reset
+            apply_rule(4, &mut walker, 0.0, &[], &[]);
+            assert_close(walker.give_way_timer, 0.0);
+            
+            // No junction, so nothing to give way at
+            let mut walker = create_walker(params, create_area());
+            apply_rule(4, &mut walker, 0.0, &[crossing], &[]);
+            assert_close(walker.inst_speed, 1.0);
+        }
         
-        // Return the new angle
-        return (initial_angle - angle_diff*nudge_ratio + TAU) % TAU;
+        #[test]
+        fn granular_contact_forces() {
+            // Overlapping by 0.11m, standing still directly ahead
+            let touching = (0.3, 0.0, 0.0, 0.205, 0.0);
+            
+            let mut walker = create_walker(SimParams::default(), create_area());
+            apply_rule(5, &mut walker, 0.0, &[touching], &[]);
+            assert_eq!(walker.push_velocity, (0.0, 0.0));
+            
+            // Pushed straight back; there is no sliding, so no friction
+            let params = SimParams {contact_model: ContactModel::Granular, ..SimParams::default()};
+            let mut walker = create_walker(params, create_area());
+            apply_rule(5, &mut walker, 0.0, &[touching], &[]);
+            assert_close(walker.push_velocity.0, -params.contact_stiffness * 0.11 * TIME_SCALE);
+            assert_close(walker.push_velocity.1, 0.0);
+            
+            // An impulse worked out elsewhere (e.g. on the GPU) is used instead, once
+            let mut walker = create_walker(params, create_area());
+            walker.set_contact_impulse(Some((1.0, 2.0)));
+            apply_rule(5, &mut walker, 0.0, &[touching], &[]);
+            assert_eq!(walker.push_velocity, (1.0, 2.0));
+            assert!(walker.contact_impulse.is_none());
+        }
+        
+        /// The GPU backend finds the same neighbours as the CPU neighbour lists, and the same contact impulses as `apply_contact_forces` for pedestrians that haven't steered yet.
+        /// Skipped when there is no GPU adapter.
+        #[cfg(feature = "gpu")]
+        #[test]
+        fn gpu_matches_cpu_neighbours_and_contact_forces() {
+            use crate::simulation::gpu::gpu::GpuNeighbours;
+            use crate::simulation::neighbours::neighbours::NeighbourLists;
+            
+            const CUTOFF: f64 = 1.2;
+            
+            let mut gpu_neighbours = match GpuNeighbours::new(CUTOFF) {
+                Ok(gpu_neighbours) => gpu_neighbours,
+                Err(error) => {
+                    println!("Skipped: {}", error);
+                    return;
+                }
+            };
+            
+            // A tightly packed, slightly skewed grid, so that neighbours overlap and slide past each other at different speeds
+            let params = SimParams {contact_model: ContactModel::Granular, ..SimParams::default()};
+            let positions = (0..25).map(|i| {
+                let (row, col) = ((i / 5) as f64, (i % 5) as f64);
+                return (col*0.37 + row*0.03, row*0.38, row - col, params.radius, 0.2*col);
+            }).collect::<Vec<_>>();
+            
+            gpu_neighbours.update(&positions, positions.iter().map(|_| (params.contact_stiffness, params.contact_friction)), TIME_SCALE);
+            let mut neighbour_lists = NeighbourLists::new(CUTOFF, 0.0);
+            neighbour_lists.update(0..positions.len() as u64, &positions);
+            
+            for (i, &(x, y, direction, _, speed)) in positions.iter().enumerate() {
+                let (cpu_before, cpu_after) = neighbour_lists.get_neighbours(i as u64, &positions);
+                let (cpu_before, cpu_after) = (cpu_before.to_vec(), cpu_after.to_vec());
+                let (gpu_before, gpu_after) = gpu_neighbours.get_neighbours(i, &positions);
+                assert_eq!((gpu_before, gpu_after), (&cpu_before[..], &cpu_after[..]), "neighbours of pedestrian {}", i);
+                
+                let mut walker = create_walker(params, create_area());
+                (walker.x, walker.y, walker.facing_direction, walker.inst_speed) = (x, y, direction, speed);
+                walker.apply_contact_forces(TIME_SCALE, &positions[..i], &positions[i+1..]);
+                
+                let (impulse_x, impulse_y) = gpu_neighbours.get_contact_impulse(i);
+                let tolerance = 1e-4 * (1.0 + walker.push_velocity.0.abs() + walker.push_velocity.1.abs());
+                assert!((impulse_x - walker.push_velocity.0).abs() < tolerance && (impulse_y - walker.push_velocity.1).abs() < tolerance,
+                    "contact impulse on pedestrian {}: GPU {:?}, CPU {:?}", i, (impulse_x, impulse_y), walker.push_velocity);
+            }
+        }
     }
     
 }
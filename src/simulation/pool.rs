@@ -0,0 +1,100 @@
+pub mod pool {
+    
+    use rayon::{ThreadPool, ThreadPoolBuilder};
+    use rayon::prelude::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    
+    use crate::simulation::simulator::simulator::CrowdSim;
+    
+    
+    /// The results of one full simulation: (total time, pedestrian count, \[(travel time, group, finish time)])
+    pub type FullResults<'a> = (f64, usize, &'a [(f64, usize, f64)]);
+    
+    /// Steps many independent simulations concurrently on a thread pool.
+    /// 
+    /// Every simulation is self-contained, so they can be simulated in any order without affecting each other.
+    pub struct SimulationPool {
+        /// The simulations in the pool, in the order they were added
+        simulations: Vec<CrowdSim>,
+        /// The threads that the simulations are stepped on
+        thread_pool: ThreadPool
+    }
+    
+    impl SimulationPool {
+        /// Create a new, empty SimulationPool.
+        /// 
+        /// * `thread_count` - The number of worker threads to use (0 = one per logical CPU)
+        pub fn new(thread_count: usize) -> SimulationPool {
+            SimulationPool {
+                simulations: Vec::new(),
+                thread_pool: ThreadPoolBuilder::new().num_threads(thread_count).build().expect("Failed to create thread pool")
+            }
+        }
+        
+        /// Add a simulation to the pool, returning its index
+        pub fn add(&mut self, crowd_sim: CrowdSim) -> usize {
+            self.simulations.push(crowd_sim);
+            return self.simulations.len() - 1;
+        }
+        
+        /// Return the number of simulations in the pool
+        pub fn len(&self) -> usize {
+            return self.simulations.len();
+        }
+        
+        /// Return true if there are no simulations in the pool
+        pub fn is_empty(&self) -> bool {
+            return self.simulations.is_empty();
+        }
+        
+        /// Simulate a single timestep of every simulation concurrently
+        /// 
+        /// * `time_scale` - The amount of time (in seconds) that passes during each timestep
+        pub fn simulate_timestep(&mut self, time_scale: f64) {
+            let simulations = &mut self.simulations;
+            self.thread_pool.install(|| {
+                simulations.par_iter_mut().for_each(|crowd_sim| crowd_sim.simulate_timestep(time_scale));
+            });
+        }
+        
+        /// Run every simulation until all of its pedestrians have finished, returning the results of each in the order they were added.
+        /// A simulation with run limits (see `CrowdSim::set_run_limits`) may stop early, which its `get_run_status` tells.
+        /// 
+        /// Return format: \[(total time, pedestrian count, \[(travel time, group, finish time)])]
+        pub fn simulate_full(&mut self, time_scale: f64) -> Vec<FullResults<'_>> {
+            return self.simulate_full_with_progress(time_scale, |_, _| {});
+        }
+        
+        /// Run every simulation until all of its pedestrians have finished, like `simulate_full`, reporting progress as each simulation finishes
+        /// 
+        /// * `on_finished` - Called with (number of simulations finished, total number of simulations) each time a simulation finishes, from whichever thread ran it
+        pub fn simulate_full_with_progress<F: Fn(usize, usize) + Sync>(&mut self, time_scale: f64, on_finished: F) -> Vec<FullResults<'_>> {
+            let total = self.simulations.len();
+            let finished = AtomicUsize::new(0);
+            let simulations = &mut self.simulations;
+            return self.thread_pool.install(|| {
+                simulations.par_iter_mut().map(|crowd_sim| {
+                    let results = crowd_sim.simulate_full(time_scale);
+                    on_finished(finished.fetch_add(1, Ordering::Relaxed) + 1, total);
+                    results
+                }).collect()
+            });
+        }
+        
+        /// Return a simulation in the pool
+        pub fn get(&self, index: usize) -> &CrowdSim {
+            return &self.simulations[index];
+        }
+        
+        /// Return all simulations in the pool, in the order they were added
+        pub fn get_simulations(&self) -> &[CrowdSim] {
+            return &self.simulations;
+        }
+        
+        /// Remove all simulations from the pool, returning them in the order they were added
+        pub fn take_simulations(&mut self) -> Vec<CrowdSim> {
+            return std::mem::take(&mut self.simulations);
+        }
+    }
+    
+}
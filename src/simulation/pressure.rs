@@ -0,0 +1,238 @@
+pub mod pressure {
+    
+    use std::f64::consts::PI;
+    use std::collections::{HashMap, VecDeque};
+    
+    use crate::simulation::pedestrian::pedestrian::Walker;
+    use crate::simulation::geom::geom::Vec2;
+    
+    
+    /// The radius of the circle around each pedestrian that its local density and velocity variance are measured over, in metres
+    const PRESSURE_RADIUS: f64 = 1.0;
+    
+    /// The length of time that the variance of the local velocity is measured over, in seconds
+    const PRESSURE_WINDOW: f64 = 3.0;
+    
+    
+    /// The levels above which a part of the crowd is flagged as at risk of a crush
+    #[derive(Clone, Copy, Debug)]
+    pub struct PressureThresholds {
+        /// The local density below which the crowd pressure isn't a risk, because there is room to get out of the way, in pedestrians/m²
+        pub density: f64,
+        /// The crowd pressure (local density × variance of the local velocity over time) above which crowd turbulence sets in, in s^-2
+        pub pressure: f64,
+        /// The summed body compression force from the neighbours a pedestrian is touching, per unit mass, above which it is being crushed, in m⋅s^-2
+        pub contact_force: f64
+    }
+    
+    impl Default for PressureThresholds {
+        /// Crowd turbulence was seen to start at a crowd pressure of about 0.02 s^-2 at the Jamarat Bridge (Helbing, Johansson & Al-Abideen, 2007), in crowds of at least 5 pedestrians/m².
+        /// A contact force of 55 m⋅s^-2 per unit mass is about 4500 N for an 80 kg pedestrian, the force that has been found to bend steel railings in crowd crushes (Fruin, 1993).
+        fn default() -> PressureThresholds {
+            return PressureThresholds {
+                density: 5.0,
+                pressure: 0.02,
+                contact_force: 55.0
+            };
+        }
+    }
+    
+    /// The crowd pressure on one pedestrian at one moment
+    #[derive(Clone, Copy, Debug)]
+    pub struct PressureSample {
+        /// The simulation time, in seconds
+        pub time: f64,
+        /// The ID of the pedestrian (see `Walker::get_id`)
+        pub id: u64,
+        pub x: f64,
+        pub y: f64,
+        /// The density of pedestrians within PRESSURE_RADIUS, in pedestrians/m²
+        pub density: f64,
+        /// The crowd pressure: the local density multiplied by the variance over the last PRESSURE_WINDOW of the mean velocity within PRESSURE_RADIUS, in s^-2
+        pub pressure: f64,
+        /// The summed body compression force from the neighbours the pedestrian is touching (see the `contact_stiffness` parameter), per unit mass, in m⋅s^-2
+        pub contact_force: f64
+    }
+    
+    /// A time during which some pedestrians in one grid cell were above a safety threshold
+    #[derive(Clone, Copy, Debug)]
+    pub struct RiskEpisode {
+        /// The coordinates of the centre of the cell
+        pub centre: (f64, f64),
+        /// The simulation time the first sample above a threshold was taken, in seconds
+        pub start: f64,
+        /// The simulation time of the first sample afterwards with nobody in the cell above a threshold, in seconds (None = still going on)
+        pub end: Option<f64>,
+        /// The highest crowd pressure of any pedestrian in the cell during the episode, in s^-2
+        pub peak_pressure: f64,
+        /// The highest contact force on any pedestrian in the cell during the episode, per unit mass, in m⋅s^-2
+        pub peak_contact_force: f64
+    }
+    
+    /// Measures the crowd pressure on every walking pedestrian at regular intervals, and flags where and when it exceeds safety thresholds,
+    /// so that a layout can be checked for places at risk of a crush.
+    pub struct PressureRecorder {
+        /// The simulation time between samples, in seconds
+        interval: f64,
+        /// The simulation time the next samples are due at
+        next_sample_time: f64,
+        /// The width and height of the grid cells that risk episodes are flagged in, in metres
+        cell_size: f64,
+        thresholds: PressureThresholds,
+        /// Every sample so far, in the order they were taken
+        samples: Vec<PressureSample>,
+        /// Every risk episode so far, in the order they started
+        episodes: Vec<RiskEpisode>,
+        /// The index in `episodes` of the episode going on in each cell
+        open_episodes: HashMap<(i64, i64), usize>,
+        /// The mean velocity around each walking pedestrian at each sample within the last PRESSURE_WINDOW, oldest first, by pedestrian ID
+        local_velocities: HashMap<u64, VecDeque<Vec2>>
+    }
+    
+    impl PressureRecorder {
+        /// * `interval` - The simulation time between samples, in seconds. Samples are taken at the first timestep after each interval.
+        /// * `cell_size` - The width and height of the grid cells that risk episodes are flagged in, in metres
+        pub fn new(interval: f64, cell_size: f64, thresholds: PressureThresholds) -> PressureRecorder {
+            assert!(interval > 0.0, "The pressure sampling interval must be positive");
+            assert!(cell_size > 0.0, "The pressure grid cell size must be positive");
+            
+            return PressureRecorder {
+                interval,
+                next_sample_time: 0.0,
+                cell_size,
+                thresholds,
+                samples: Vec::new(),
+                episodes: Vec::new(),
+                open_episodes: HashMap::new(),
+                local_velocities: HashMap::new()
+            };
+        }
+        
+        /// Measure the pressure on the pedestrians if the next samples are due, and start or finish risk episodes
+        /// 
+        /// * `time` - The current simulation time, in seconds
+        /// * `pedestrians` - Every walking pedestrian
+        pub fn record(&mut self, time: f64, pedestrians: &[Walker]) {
+            // Allow for rounding errors in the simulation time
+            if time + 1e-9 < self.next_sample_time {
+                return;
+            }
+            while self.next_sample_time <= time + 1e-9 {
+                self.next_sample_time += self.interval;
+            }
+            
+            let positions: Vec<Vec2> = pedestrians.iter().map(|ped| Vec2::new(ped.x, ped.y)).collect();
+            let velocities: Vec<Vec2> = pedestrians.iter().map(|ped| Vec2::from_angle(ped.facing_direction) * ped.inst_speed).collect();
+            
+            // Forget the pedestrians that have finished
+            self.local_velocities.retain(|id, _| pedestrians.iter().any(|ped| ped.get_id() == *id));
+            let window_samples = ((PRESSURE_WINDOW / self.interval).round() as usize).max(2);
+            
+            let mut flagged_cells = HashMap::new();
+            for (i, ped) in pedestrians.iter().enumerate() {
+                // The pedestrian itself is always within the radius
+                let mut count = 0;
+                let mut velocity_sum = Vec2::new(0.0, 0.0);
+                let mut contact_force = 0.0;
+                for (j, other) in pedestrians.iter().enumerate() {
+                    let dist = (positions[j] - positions[i]).length();
+                    if dist < PRESSURE_RADIUS {
+                        count += 1;
+                        velocity_sum = velocity_sum + velocities[j];
+                    }
+                    
+                    let overlap = ped.get_params().radius + other.get_params().radius - dist;
+                    if i != j && overlap > 0.0 {
+                        contact_force += ped.get_params().contact_stiffness * overlap;
+                    }
+                }
+                
+                let density = (count as f64) / (PI * PRESSURE_RADIUS * PRESSURE_RADIUS);
+                
+                // Steady flows (even in opposite directions) have a steady local velocity, whereas stop-and-go waves and crowd turbulence make it fluctuate,
+                // so the pressure is the density times the variance of the local velocity over time (Helbing, Johansson & Al-Abideen, 2007), following the pedestrian
+                let history = self.local_velocities.entry(ped.get_id()).or_default();
+                history.push_back(velocity_sum * (1.0 / (count as f64)));
+                if history.len() > window_samples {
+                    history.pop_front();
+                }
+                let mean = history.iter().fold(Vec2::new(0.0, 0.0), |sum, &v| sum + v) * (1.0 / (history.len() as f64));
+                let variance = history.iter().map(|&v| (v - mean).length_sq()).sum::<f64>() / (history.len() as f64);
+                let pressure = density * variance;
+                
+                self.samples.push(PressureSample {
+                    time,
+                    id: ped.get_id(),
+                    x: ped.x,
+                    y: ped.y,
+                    density,
+                    pressure,
+                    contact_force
+                });
+                
+                if (density >= self.thresholds.density && pressure > self.thresholds.pressure) || contact_force > self.thresholds.contact_force {
+                    let cell = ((ped.x / self.cell_size).floor() as i64, (ped.y / self.cell_size).floor() as i64);
+                    let (peak_pressure, peak_contact_force) = flagged_cells.entry(cell).or_insert((0.0, 0.0));
+                    *peak_pressure = f64::max(*peak_pressure, pressure);
+                    *peak_contact_force = f64::max(*peak_contact_force, contact_force);
+                }
+            }
+            
+            // Finish the episodes in cells that are no longer above a threshold
+            let episodes = &mut self.episodes;
+            self.open_episodes.retain(|cell, &mut index| {
+                if flagged_cells.contains_key(cell) {
+                    return true;
+                }
+                episodes[index].end = Some(time);
+                return false;
+            });
+            
+            for (cell, (peak_pressure, peak_contact_force)) in flagged_cells {
+                match self.open_episodes.get(&cell) {
+                    Some(&index) => {
+                        let episode = &mut self.episodes[index];
+                        episode.peak_pressure = episode.peak_pressure.max(peak_pressure);
+                        episode.peak_contact_force = episode.peak_contact_force.max(peak_contact_force);
+                    },
+                    None => {
+                        self.open_episodes.insert(cell, self.episodes.len());
+                        self.episodes.push(RiskEpisode {
+                            centre: ((cell.0 as f64 + 0.5) * self.cell_size, (cell.1 as f64 + 0.5) * self.cell_size),
+                            start: time,
+                            end: None,
+                            peak_pressure,
+                            peak_contact_force
+                        });
+                    }
+                }
+            }
+        }
+        
+        /// Return the simulation time between samples, in seconds
+        pub fn get_interval(&self) -> f64 {
+            return self.interval;
+        }
+        
+        /// Return the levels above which a part of the crowd is flagged as at risk
+        pub fn get_thresholds(&self) -> PressureThresholds {
+            return self.thresholds;
+        }
+        
+        /// Return every sample so far, in the order they were taken (by time, then by pedestrian)
+        pub fn get_samples(&self) -> &[PressureSample] {
+            return &self.samples;
+        }
+        
+        /// Return every risk episode so far, in the order they started
+        pub fn get_episodes(&self) -> &[RiskEpisode] {
+            return &self.episodes;
+        }
+        
+        /// Return the highest crowd pressure and contact force (per unit mass) of any sample so far: (pressure in s^-2, contact force in m⋅s^-2)
+        pub fn get_peaks(&self) -> (f64, f64) {
+            return self.samples.iter().fold((0.0, 0.0), |(pressure, force), sample| (f64::max(pressure, sample.pressure), f64::max(force, sample.contact_force)));
+        }
+    }
+    
+}
@@ -0,0 +1,97 @@
+pub mod profiling {
+    
+    use std::time::{Duration, Instant};
+    
+    
+    /// The phases of a simulation timestep that are timed separately
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Phase {
+        /// Switching behaviours, evacuation and starting new pedestrians walking
+        Activation,
+        /// Collecting the positions of pedestrians and finding each pedestrian's neighbours
+        NeighbourSearch,
+        /// Steering, reacting to neighbours and moving each pedestrian
+        Behaviour,
+        /// Resolving collisions with walls & obstacles
+        WallResolution,
+        /// Counting lines, timing boundaries, density, measurement areas, trajectories and observers
+        Recording,
+        /// Removing pedestrians that have finished and updating exit queues
+        FinishChecks
+    }
+    
+    impl Phase {
+        /// Every phase, in the order they happen during a timestep
+        pub const ALL: [Phase; 6] = [Phase::Activation, Phase::NeighbourSearch, Phase::Behaviour, Phase::WallResolution, Phase::Recording, Phase::FinishChecks];
+        
+        /// Return a short name for the phase, for summaries
+        pub fn name(&self) -> &'static str {
+            return match self {
+                Phase::Activation => "activation",
+                Phase::NeighbourSearch => "neighbour search",
+                Phase::Behaviour => "behaviour",
+                Phase::WallResolution => "wall resolution",
+                Phase::Recording => "recording",
+                Phase::FinishChecks => "finish checks"
+            };
+        }
+    }
+    
+    /// The total wall-clock time spent in each phase of the timesteps simulated so far
+    /// 
+    /// Time is measured between laps: each call to `lap` adds the time since the previous lap (or `start_timestep`) to a phase.
+    pub struct Profiler {
+        /// The total time spent in each phase, in the order of Phase::ALL
+        totals: [Duration; 6],
+        /// The number of timesteps (including sub-steps) that have been timed
+        timestep_count: usize,
+        /// The end of the previous lap
+        last_lap: Instant
+    }
+    
+    impl Profiler {
+        /// Create a profiler with no time recorded
+        pub fn new() -> Profiler {
+            Profiler {
+                totals: [Duration::ZERO; 6],
+                timestep_count: 0,
+                last_lap: Instant::now()
+            }
+        }
+        
+        /// Start timing a new timestep
+        pub fn start_timestep(&mut self) {
+            self.timestep_count += 1;
+            self.last_lap = Instant::now();
+        }
+        
+        /// Add the time since the previous lap to a phase
+        pub fn lap(&mut self, phase: Phase) {
+            let now = Instant::now();
+            self.totals[phase as usize] += now - self.last_lap;
+            self.last_lap = now;
+        }
+        
+        /// Return the total time spent in a phase
+        pub fn get_total(&self, phase: Phase) -> Duration {
+            return self.totals[phase as usize];
+        }
+        
+        /// Return the total time spent in every phase
+        pub fn get_overall_total(&self) -> Duration {
+            return self.totals.iter().sum();
+        }
+        
+        /// Return the number of timesteps (including sub-steps) that have been timed
+        pub fn get_timestep_count(&self) -> usize {
+            return self.timestep_count;
+        }
+    }
+    
+    impl Default for Profiler {
+        fn default() -> Self {
+            return Profiler::new();
+        }
+    }
+    
+}
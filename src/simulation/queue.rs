@@ -0,0 +1,288 @@
+pub mod queue {
+    
+    use std::collections::{HashMap, HashSet, VecDeque};
+    use raylib::{drawing::{RaylibDrawHandle, RaylibDraw}, color::Color};
+    
+    use crate::simulation::pedestrian::pedestrian::Walker;
+    use crate::simulation::geom::geom::Vec2;
+    
+    
+    /// The distance between the places of neighbouring pedestrians in a queue, in metres
+    pub const QUEUE_SPACING: f64 = 0.7;
+    
+    /// How close a pedestrian needs to get to its place in a queue to stand still there, in metres
+    pub const QUEUE_PLACE_RADIUS: f64 = 0.3;
+    
+    /// How close a pedestrian needs to get to the tail of a queue to join it, in metres
+    const QUEUE_JOIN_DISTANCE: f64 = 1.0;
+    
+    
+    const QUEUE_COLOUR: &str = "8E6BB8";
+    
+    
+    /// A line of pedestrians waiting to be served one at a time, such as at a ticket machine, a security check or a kiosk.
+    /// 
+    /// The queue runs along a path from the service point at its head. Pedestrians in the queue's groups walk to the tail and join it,
+    /// move up as the pedestrian at the head is served, and carry on to their destinations once they have been served themselves.
+    /// A queue longer than its path carries on in the direction of the path's last segment.
+    pub struct ServiceQueue {
+        /// The points along the queue, starting with the service point at the head
+        path: Vec<Vec2>,
+        /// How long it takes to serve each pedestrian at the head, in seconds
+        service_time: f64,
+        /// The groups whose pedestrians join the queue
+        groups: Vec<usize>
+    }
+    
+    impl ServiceQueue {
+        /// * `path` - The points along the queue, starting with the service point at the head (at least two)
+        /// * `service_time` - How long it takes to serve each pedestrian at the head, in seconds
+        /// * `groups` - The groups whose pedestrians join the queue
+        pub fn new(path: Vec<(f64, f64)>, service_time: f64, groups: Vec<usize>) -> ServiceQueue {
+            assert!(path.len() >= 2, "A queue's path needs at least two points");
+            assert!(service_time >= 0.0, "A queue's service time can't be negative");
+            
+            return ServiceQueue {
+                path: path.into_iter().map(|point| point.into()).collect(),
+                service_time,
+                groups
+            };
+        }
+        
+        /// Return where the pedestrian at a position in the queue stands (0 = the head)
+        pub fn get_place(&self, index: usize) -> Vec2 {
+            let mut distance = (index as f64) * QUEUE_SPACING;
+            for segment in self.path.windows(2) {
+                let length = segment[0].distance(segment[1]);
+                if distance <= length {
+                    return segment[0] + (segment[1] - segment[0]) * (distance / length);
+                }
+                distance -= length;
+            }
+            
+            // Carry on past the end of the path
+            let (second_last, last) = (self.path[self.path.len() - 2], self.path[self.path.len() - 1]);
+            let direction = (last - second_last).normalised().unwrap_or(Vec2::new(0.0, 0.0));
+            return last + direction * distance;
+        }
+        
+        /// Return the points along the queue, starting with the service point at the head
+        pub fn get_path(&self) -> &[Vec2] {
+            return &self.path;
+        }
+        
+        /// Return how long it takes to serve each pedestrian at the head, in seconds
+        pub fn get_service_time(&self) -> f64 {
+            return self.service_time;
+        }
+        
+        /// Return whether pedestrians in a group join the queue
+        pub fn is_for_group(&self, group: usize) -> bool {
+            return self.groups.contains(&group);
+        }
+        
+        /// Draw the queue's path, with a circle at the service point
+        pub fn draw(&self, rl_handle: &mut RaylibDrawHandle, offset: (i32, i32), draw_scale: i32) {
+            let colour = Color::fade(&Color::from_hex(QUEUE_COLOUR).unwrap(), 0.6);
+            let to_screen = |point: Vec2| (offset.0 + ((draw_scale as f64) * point.x) as i32, offset.1 + ((draw_scale as f64) * point.y) as i32);
+            
+            for segment in self.path.windows(2) {
+                let (start, end) = (to_screen(segment[0]), to_screen(segment[1]));
+                rl_handle.draw_line(start.0, start.1, end.0, end.1, colour);
+            }
+            let head = to_screen(self.path[0]);
+            rl_handle.draw_circle_lines(head.0, head.1, ((draw_scale as f64) * QUEUE_PLACE_RADIUS) as f32, colour);
+        }
+    }
+    
+    /// How long one pedestrian spent in a queue
+    #[derive(Clone, Copy, Debug)]
+    pub struct QueueWait {
+        /// The ID of the pedestrian (see `Walker::get_id`)
+        pub id: u64,
+        pub group: usize,
+        /// The simulation time the pedestrian joined the tail, in seconds
+        pub joined: f64,
+        /// The simulation time the pedestrian reached the head and started being served, in seconds
+        pub service_start: f64,
+        /// The simulation time the pedestrian finished being served and left the queue, in seconds
+        pub left: f64
+    }
+    
+    impl QueueWait {
+        /// Return how long the pedestrian waited before being served, in seconds
+        pub fn get_waiting_time(&self) -> f64 {
+            return self.service_start - self.joined;
+        }
+    }
+    
+    /// The pedestrians in a queue, and how long each pedestrian that has been through it waited
+    #[derive(Default)]
+    pub struct QueueState {
+        /// The IDs of the pedestrians in the queue, starting at the head
+        members: VecDeque<u64>,
+        /// The simulation time each pedestrian in the queue joined it, by pedestrian ID
+        join_times: HashMap<u64, f64>,
+        /// The pedestrian being served and the simulation time it started being served: (pedestrian ID, time) (None = the head hasn't reached the service point yet)
+        serving: Option<(u64, f64)>,
+        /// The IDs of the pedestrians that have been served, which don't join again
+        served: HashSet<u64>,
+        /// Every pedestrian that has been served, in the order they left the queue
+        waits: Vec<QueueWait>,
+        /// The number of pedestrians in the queue every time it changed: \[(simulation time, length)]
+        lengths: Vec<(f64, usize)>
+    }
+    
+    impl QueueState {
+        /// Let pedestrians join the tail of the queue, serve the pedestrian at the head, and tell every pedestrian in or heading for the queue where to stand
+        /// 
+        /// * `time` - The current simulation time, in seconds
+        /// * `time_scale` - The length of the timestep, in seconds
+        pub fn update(&mut self, queue: &ServiceQueue, time: f64, time_scale: f64, pedestrians: &mut [Walker]) {
+            let length = self.members.len();
+            let indices: HashMap<u64, usize> = pedestrians.iter().enumerate().map(|(i, ped)| (ped.get_id(), i)).collect();
+            
+            // Forget the pedestrians that have left the simulation while queueing
+            self.members.retain(|id| indices.contains_key(id));
+            self.join_times.retain(|id, _| indices.contains_key(id));
+            
+            // Serve the pedestrian at the head once it has reached the service point
+            if let Some(&head) = self.members.front() {
+                let ped = &mut pedestrians[indices[&head]];
+                match self.serving.filter(|(id, _)| *id == head) {
+                    None => if Vec2::new(ped.x, ped.y).distance(queue.get_place(0)) < QUEUE_PLACE_RADIUS {
+                        self.serving = Some((head, time));
+                    },
+                    Some((_, start)) if time + time_scale - start >= queue.get_service_time() => {
+                        ped.set_queue_place(None);
+                        self.waits.push(QueueWait {
+                            id: head,
+                            group: ped.get_group(),
+                            joined: self.join_times.remove(&head).unwrap_or(start),
+                            service_start: start,
+                            left: time + time_scale
+                        });
+                        self.served.insert(head);
+                        self.members.pop_front();
+                        self.serving = None;
+                    },
+                    Some(_) => {}
+                }
+            }
+            
+            // Pedestrians heading for the queue join it once they reach the tail
+            for ped in pedestrians.iter_mut() {
+                let id = ped.get_id();
+                if !queue.is_for_group(ped.get_group()) || self.served.contains(&id) || self.join_times.contains_key(&id) {
+                    continue;
+                }
+                
+                let tail = queue.get_place(self.members.len());
+                if Vec2::new(ped.x, ped.y).distance(tail) < QUEUE_JOIN_DISTANCE {
+                    self.members.push_back(id);
+                    self.join_times.insert(id, time);
+                } else {
+                    ped.set_queue_place(Some(tail.into()));
+                }
+            }
+            
+            // Everyone in the queue moves up to their place
+            for (index, id) in self.members.iter().enumerate() {
+                pedestrians[indices[id]].set_queue_place(Some(queue.get_place(index).into()));
+            }
+            
+            if self.members.len() != length {
+                self.lengths.push((time + time_scale, self.members.len()));
+            }
+        }
+        
+        /// Return the number of pedestrians in the queue at the moment
+        pub fn get_length(&self) -> usize {
+            return self.members.len();
+        }
+        
+        /// Return every pedestrian that has been served, in the order they left the queue
+        pub fn get_waits(&self) -> &[QueueWait] {
+            return &self.waits;
+        }
+        
+        /// Return the number of pedestrians in the queue every time it changed: \[(simulation time, length)]
+        pub fn get_lengths(&self) -> &[(f64, usize)] {
+            return &self.lengths;
+        }
+        
+        /// Return the mean and longest time the served pedestrians waited before being served, in seconds (None = nobody has been served)
+        pub fn get_waiting_time_summary(&self) -> Option<(f64, f64)> {
+            if self.waits.is_empty() {
+                return None;
+            }
+            
+            let total: f64 = self.waits.iter().map(|wait| wait.get_waiting_time()).sum();
+            let longest = self.waits.iter().map(|wait| wait.get_waiting_time()).fold(0.0, f64::max);
+            return Some((total / (self.waits.len() as f64), longest));
+        }
+        
+        /// Return the most pedestrians there have been in the queue at once
+        pub fn get_peak_length(&self) -> usize {
+            return self.lengths.iter().map(|(_, length)| *length).max().unwrap_or(0);
+        }
+    }
+    
+    
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::sync::Arc;
+        use crate::simulation::simulator::simulator::{CrowdSim, SimArea};
+        use crate::simulation::behaviour::behaviour::Etiquette;
+        
+        fn assert_close(a: Vec2, b: Vec2) {
+            assert!(a.distance(b) < 1e-9, "{:?} != {:?}", a, b);
+        }
+        
+        #[test]
+        fn places_follow_the_path() {
+            let queue = ServiceQueue::new(vec![(5.0,2.0), (5.0-QUEUE_SPACING,2.0), (5.0-QUEUE_SPACING,5.0)], 1.0, vec![0]);
+            
+            assert_close(queue.get_place(0), Vec2::new(5.0, 2.0));
+            assert_close(queue.get_place(1), Vec2::new(5.0 - QUEUE_SPACING, 2.0));
+            assert_close(queue.get_place(2), Vec2::new(5.0 - QUEUE_SPACING, 2.0 + QUEUE_SPACING));
+            
+            // Past the end of the path, the queue carries on in the same direction
+            assert_close(queue.get_place(10), Vec2::new(5.0 - QUEUE_SPACING, 2.0 + 9.0*QUEUE_SPACING));
+        }
+        
+        #[test]
+        fn pedestrians_are_served_in_the_order_they_join() {
+            const SERVICE_TIME: f64 = 1.5;
+            
+            let mut area = SimArea::new();
+            area.add_wall((-1.0,0.0), (11.0,0.0));
+            area.add_wall((-1.0,4.0), (11.0,4.0));
+            area.add_queue(vec![(5.0,2.0), (1.0,2.0)], SERVICE_TIME, vec![0]);
+            area.add_start_end_group(vec![(0.0,1.0), (0.0,3.0)], vec![(10.0,1.0), (10.0,3.0)]);
+            
+            let mut crowd_sim = CrowdSim::new(Arc::new(area), 2.0);
+            crowd_sim.set_seed(0);
+            crowd_sim.set_run_limits(Some(120.0), None);
+            crowd_sim.add_pedestrian_set(6, 0, Arc::new(Etiquette::NoBias));
+            crowd_sim.simulate_full(0.1);
+            
+            let queue = crowd_sim.get_queue(0);
+            let waits = queue.get_waits();
+            assert_eq!(waits.len(), 6);
+            assert_eq!(queue.get_length(), 0);
+            
+            // First come, first served, one at a time
+            for pair in waits.windows(2) {
+                assert!(pair[0].joined <= pair[1].joined, "served out of order: {:?}", waits);
+                assert!(pair[1].service_start >= pair[0].left - 1e-9);
+            }
+            for wait in waits {
+                assert!(wait.left - wait.service_start >= SERVICE_TIME - 1e-9);
+            }
+            assert!(queue.get_peak_length() > 1);
+        }
+    }
+    
+}
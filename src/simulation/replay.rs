@@ -0,0 +1,107 @@
+pub mod replay {
+    
+    use std::fs;
+    use std::io;
+    use std::path::Path;
+    use raylib::{drawing::{RaylibDrawHandle, RaylibDraw}, color::Color};
+    
+    use crate::simulation::trajectory::trajectory::TrajectorySample;
+    
+    
+    const REPLAY_PEDESTRIAN_COLOUR: &str = "505050";
+    const REPLAY_HEADING_COLOUR: &str = "920B07";
+    
+    
+    /// A recorded run that can be played back without re-simulating it, loaded from a trajectory file (see `export::write_trajectories_csv`)
+    pub struct Replay {
+        /// The samples taken at each moment, in order of time: (simulation time, samples). Moments when no pedestrians were walking have no frame.
+        frames: Vec<(f64, Vec<TrajectorySample>)>,
+        /// The simulation time between samples, in seconds
+        interval: f64
+    }
+    
+    impl Replay {
+        /// Load a trajectory CSV file written by `export::write_trajectories_csv`
+        pub fn load(path: &Path) -> io::Result<Replay> {
+            let contents = fs::read_to_string(path)?;
+            let mut frames: Vec<(f64, Vec<TrajectorySample>)> = Vec::new();
+            
+            let interval = contents.lines()
+                .find_map(|line| line.strip_prefix("# interval = "))
+                .and_then(|value| value.trim().parse::<f64>().ok())
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Missing \"# interval = ...\" line in trajectory file"))?;
+            
+            // Skip comments & the header line
+            for line in contents.lines().filter(|line| !line.starts_with('#')).skip(1) {
+                let fields = line.split(',').map(|field| field.trim().parse::<f64>()).collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("Invalid trajectory line \"{}\": {}", line, e)))?;
+                if fields.len() != 6 {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Expected 6 fields in trajectory line \"{}\"", line)));
+                }
+                
+                let sample = TrajectorySample {
+                    time: fields[0],
+                    id: fields[1] as u64,
+                    x: fields[2],
+                    y: fields[3],
+                    speed: fields[4],
+                    heading: fields[5]
+                };
+                
+                // Samples are written in order of time, so a new time starts a new frame
+                match frames.last_mut() {
+                    Some((time, samples)) if *time == sample.time => samples.push(sample),
+                    _ => frames.push((sample.time, vec![sample]))
+                }
+            }
+            
+            return Ok(Replay {
+                frames,
+                interval
+            });
+        }
+        
+        /// Return the simulation times of the first and last frames, in seconds
+        pub fn get_time_range(&self) -> (f64, f64) {
+            return (
+                self.frames.first().map_or(0.0, |(time, _)| *time),
+                self.frames.last().map_or(0.0, |(time, _)| *time)
+            );
+        }
+        
+        /// Return the number of frames recorded
+        pub fn get_frame_count(&self) -> usize {
+            return self.frames.len();
+        }
+        
+        /// Return the samples of the last frame at or before a simulation time, or nothing if no pedestrians were walking then
+        pub fn get_frame(&self, time: f64) -> &[TrajectorySample] {
+            let index = self.frames.partition_point(|(t, _)| *t <= time);
+            return match index.checked_sub(1).map(|i| &self.frames[i]) {
+                // A frame more than one interval old is followed by a gap with no pedestrians
+                Some((frame_time, samples)) if time - frame_time < self.interval * 1.5 => samples,
+                _ => &[]
+            };
+        }
+        
+        /// Draw the pedestrians of the frame at a simulation time with RayLib, with a line showing each one's heading & speed
+        /// 
+        /// * `radius` - The radius to draw pedestrians with, in metres
+        pub fn draw(&self, rl_handle: &mut RaylibDrawHandle, offset: (i32, i32), draw_scale: i32, time: f64, radius: f64) {
+            for sample in self.get_frame(time) {
+                let x = offset.0 + ((draw_scale as f64)*sample.x) as i32;
+                let y = offset.1 + ((draw_scale as f64)*sample.y) as i32;
+                
+                rl_handle.draw_circle(x, y, (draw_scale as f32) * (radius as f32), Color::from_hex(REPLAY_PEDESTRIAN_COLOUR).unwrap());
+                rl_handle.draw_line(
+                    x,
+                    y,
+                    offset.0 + ((draw_scale as f64)*(sample.x + sample.speed * sample.heading.cos())) as i32,
+                    offset.1 + ((draw_scale as f64)*(sample.y + sample.speed * sample.heading.sin())) as i32,
+                    Color::from_hex(REPLAY_HEADING_COLOUR).unwrap()
+                );
+            }
+        }
+    }
+    
+}
@@ -0,0 +1,55 @@
+pub mod scenario {
+    
+    use std::fs;
+    use std::io;
+    use std::path::Path;
+    use serde::Deserialize;
+    
+    use crate::simulation::params::params::{SimParams, ParamVariation, Demographic};
+    use crate::simulation::arrivals::arrivals::ArrivalSettings;
+    use crate::simulation::distracted::distracted::Distraction;
+    use crate::simulation::conversation::conversation::Conversations;
+    use crate::simulation::luggage::luggage::Luggage;
+    use crate::simulation::timeline::timeline::ScenarioEvent;
+    use crate::simulation::herding::herding::Herding;
+    use crate::simulation::distancing::distancing::Distancing;
+    
+    
+    /// Settings loaded from a scenario file, so that experiments can be changed without recompiling.
+    /// 
+    /// Scenario files are written in TOML. Every table is optional, and anything left out keeps its default value.
+    #[derive(Clone, Debug, Default, Deserialize)]
+    #[serde(default)]
+    pub struct Scenario {
+        /// Parameters of the pedestrian model
+        pub params: SimParams,
+        /// Distributions that individual pedestrians' parameters are sampled from
+        pub variation: ParamVariation,
+        /// The process that decides when pedestrians are added, selected by name (None = keep the simulation's process)
+        pub arrivals: Option<ArrivalSettings>,
+        /// How many pedestrians are distracted, and how they differ from the rest (None = nobody is distracted)
+        pub distraction: Option<Distraction>,
+        /// How often pedestrians stop to chat with acquaintances walking the other way (None = never)
+        pub conversations: Option<Conversations>,
+        /// How many pedestrians don't know their way, and follow the crowd around them instead (None = everyone knows their way)
+        pub herding: Option<Herding>,
+        /// How many pedestrians keep a larger personal space around themselves (None = nobody keeps their distance)
+        pub distancing: Option<Distancing>,
+        /// The classes of pedestrians with their own speeds and bodies, as `[[demographics]]` tables (empty = every pedestrian's speed is drawn from `target_speed_bounds`)
+        pub demographics: Vec<Demographic>,
+        /// The bulky items, such as suitcases or prams, that a fraction of the pedestrians take along, as `[[luggage]]` tables (empty = nobody takes anything along)
+        pub luggage: Vec<Luggage>,
+        /// Things that happen at set times during the run, such as bursts of pedestrians, doors opening & closing or an evacuation, as `[[events]]` tables
+        pub events: Vec<ScenarioEvent>
+    }
+    
+    impl Scenario {
+        /// Load a scenario from a TOML file
+        pub fn from_file(path: &Path) -> io::Result<Scenario> {
+            let contents = fs::read_to_string(path)?;
+            
+            return toml::from_str(&contents).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()));
+        }
+    }
+    
+}
@@ -0,0 +1,130 @@
+pub mod signage {
+    
+    use raylib::{drawing::{RaylibDrawHandle, RaylibDraw}, color::Color};
+    
+    use crate::simulation::geom::geom::Vec2;
+    
+    
+    /// The width & height a sign is drawn with, in metres
+    const SIGN_DRAW_SIZE: f64 = 0.4;
+    
+    
+    const SIGN_COLOUR: &str = "1F7A4D";
+    
+    
+    /// A wayfinding sign, pointing pedestrians that don't know their way towards a destination.
+    /// 
+    /// Pedestrians unfamiliar with their route (see `Herding`) read a sign for their destination group once they are within its visibility radius and can see it past the walls.
+    /// A sign with a waypoint sends them to that waypoint, such as a doorway, after which they are on their own again until they see another sign.
+    /// A sign without one shows them their destination, after which they know their way.
+    pub struct Sign {
+        /// Where the sign is
+        position: Vec2,
+        /// How far away the sign can be read from, in metres
+        visibility_radius: f64,
+        /// The destination group the sign points the way to
+        destination_group: usize,
+        /// The point the sign sends pedestrians to on the way (None = it shows them their destination)
+        waypoint: Option<(f64, f64)>
+    }
+    
+    impl Sign {
+        /// * `position` - Where the sign is
+        /// * `visibility_radius` - How far away the sign can be read from, in metres
+        /// * `destination_group` - The destination group the sign points the way to
+        /// * `waypoint` - The point the sign sends pedestrians to on the way (None = it shows them their destination)
+        pub fn new(position: (f64, f64), visibility_radius: f64, destination_group: usize, waypoint: Option<(f64, f64)>) -> Sign {
+            return Sign {
+                position: position.into(),
+                visibility_radius,
+                destination_group,
+                waypoint
+            };
+        }
+        
+        /// Return where the sign is
+        pub fn get_position(&self) -> Vec2 {
+            return self.position;
+        }
+        
+        /// Return the destination group the sign points the way to
+        pub fn get_destination_group(&self) -> usize {
+            return self.destination_group;
+        }
+        
+        /// Return the point the sign sends pedestrians to on the way (None = it shows them their destination)
+        pub fn get_waypoint(&self) -> Option<(f64, f64)> {
+            return self.waypoint;
+        }
+        
+        /// Return whether a point is close enough to read the sign from, ignoring walls
+        pub fn is_within_range(&self, p: Vec2) -> bool {
+            return p.distance(self.position) < self.visibility_radius;
+        }
+        
+        /// Draw the sign as a square, with a line to the waypoint it points to and a circle showing how far away it can be read from
+        pub fn draw(&self, rl_handle: &mut RaylibDrawHandle, offset: (i32, i32), draw_scale: i32) {
+            let colour = Color::from_hex(SIGN_COLOUR).unwrap();
+            let to_screen = |point: Vec2| (offset.0 + ((draw_scale as f64) * point.x) as i32, offset.1 + ((draw_scale as f64) * point.y) as i32);
+            
+            let centre = to_screen(self.position);
+            let size = ((draw_scale as f64) * SIGN_DRAW_SIZE) as i32;
+            rl_handle.draw_rectangle(centre.0 - size / 2, centre.1 - size / 2, size, size, colour);
+            rl_handle.draw_circle_lines(centre.0, centre.1, ((draw_scale as f64) * self.visibility_radius) as f32, Color::fade(&colour, 0.25));
+            if let Some(waypoint) = self.waypoint {
+                let end = to_screen(waypoint.into());
+                rl_handle.draw_line(centre.0, centre.1, end.0, end.1, Color::fade(&colour, 0.6));
+            }
+        }
+    }
+    
+    /// How a pedestrian unfamiliar with its route found its way
+    #[derive(Clone, Copy, Debug)]
+    pub struct WayfindingResult {
+        /// The group the pedestrian is part of
+        pub group: usize,
+        /// The number of signs the pedestrian read
+        pub signs_read: usize,
+        /// The number of times the pedestrian turned to head more than 90° away from the way to its destination (see `Walker::get_wrong_turns`)
+        pub wrong_turns: usize
+    }
+    
+    
+    #[cfg(test)]
+    mod tests {
+        use std::sync::Arc;
+        use crate::simulation::simulator::simulator::{CrowdSim, SimArea};
+        use crate::simulation::behaviour::behaviour::Etiquette;
+        use crate::simulation::herding::herding::Herding;
+        
+        #[test]
+        fn signs_are_read_once_in_sight() {
+            let mut area = SimArea::new();
+            area.add_wall((-1.0,0.0), (11.0,0.0));
+            area.add_wall((-1.0,4.0), (11.0,4.0));
+            area.add_start_end_group(vec![(0.0,1.0), (0.0,3.0)], vec![(10.0,1.0), (10.0,3.0)]);
+            // A sign sending pedestrians through the middle of the corridor, then one showing them their destination
+            area.add_sign((3.0,2.0), 3.0, 0, Some((5.0,2.0)));
+            area.add_sign((8.0,2.0), 3.0, 0, None);
+            // A sign on the other side of the wall, and a sign for another destination
+            area.add_sign((5.0,-1.0), 10.0, 0, Some((5.0,-1.0)));
+            area.add_sign((5.0,2.0), 10.0, 1, Some((5.0,3.5)));
+            
+            let mut crowd_sim = CrowdSim::new(Arc::new(area), 2.0);
+            crowd_sim.set_seed(0);
+            crowd_sim.set_run_limits(Some(120.0), None);
+            crowd_sim.set_herding(Some(Herding {familiarity: 0.0, ..Herding::default()}));
+            crowd_sim.add_pedestrian_set(6, 0, Arc::new(Etiquette::NoBias));
+            let (_, finished, _) = crowd_sim.simulate_full(0.1);
+            
+            // Everyone reads the two signs they can see for their destination, and nothing else
+            assert_eq!(finished, 6);
+            let results = crowd_sim.get_wayfinding_results();
+            assert_eq!(results.len(), 6);
+            for result in results {
+                assert_eq!(result.signs_read, 2, "{:?}", result);
+            }
+        }
+    }
+    
+}
@@ -1,46 +1,528 @@
 pub mod simulator {
     
+    use std::collections::VecDeque;
+    use std::f64::consts::PI;
+    use std::io;
     use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Instant;
     use raylib::{drawing::{RaylibDrawHandle, RaylibDraw}, color::Color};
-    use rand::{thread_rng, seq::SliceRandom, Rng, distributions::Uniform};
+    use rand::{seq::SliceRandom, Rng, SeedableRng, distributions::Uniform};
+    use rand_chacha::ChaCha8Rng;
+    use serde::{Deserialize, Serialize};
     
-    use crate::simulation::pedestrian::pedestrian;
+    use crate::simulation::pedestrian::pedestrian::{self, AfterDwell, Dwell};
+    use crate::simulation::behaviour::behaviour::BehaviourModel;
+    use crate::simulation::params::params::{SimParams, ParamVariation, ParamDistribution, Demographic, StallRecovery};
+    use crate::simulation::geom::geom::{Vec2, Zone, normalise_angle, segment_normal_vector, swept_circle_segment, swept_circle_circle, segments_intersect, polygon_area, polygon_contains};
+    use crate::simulation::navigation::navigation::{Navigation, VisibilityGraph};
+    use crate::simulation::floor_field::floor_field::FloorField;
+    use crate::simulation::junction::junction::{JunctionZone, PriorityRule};
+    use crate::simulation::arrivals::arrivals::{ArrivalProcess, ArrivalContext, Deterministic};
+    use crate::simulation::density::density::DensityGrid;
+    use crate::simulation::voronoi::voronoi;
+    use crate::simulation::trajectory::trajectory::TrajectoryRecorder;
+    use crate::simulation::pressure::pressure::{PressureRecorder, PressureThresholds};
+    use crate::simulation::contact::contact::ContactRecorder;
+    use crate::simulation::queue::queue::{ServiceQueue, QueueState};
+    use crate::simulation::gate::gate::{TicketGate, GateState};
+    use crate::simulation::hazard::hazard::{HazardSource, HazardCasualty};
+    use crate::simulation::herding::herding::{Herding, UNFAMILIAR_COHORT};
+    use crate::simulation::distancing::distancing::{Distancing, DISTANCING_COHORT};
+    use crate::simulation::signage::signage::{Sign, WayfindingResult};
+    use crate::simulation::walkway::walkway::{MovingWalkway, WalkwayEtiquette};
+    use crate::simulation::vehicle::vehicle::{VehicleLane, VehicleParams, VehicleTraffic};
+    use crate::simulation::boarding::boarding::Boarding;
+    use crate::simulation::timeline::timeline::{ScenarioEvent, EventAction};
+    use crate::simulation::observer::observer::Observer;
+    use crate::simulation::neighbours::neighbours::NeighbourLists;
+    #[cfg(feature = "gpu")]
+    use crate::simulation::gpu::gpu::GpuNeighbours;
+    use crate::simulation::profiling::profiling::{Phase, Profiler};
+    use crate::simulation::distracted::distracted::{Distraction, Distracted};
+    use crate::simulation::luggage::luggage::Luggage;
+    use crate::simulation::conversation::conversation::{Conversations, Conversation};
+    use crate::simulation::snapshot::snapshot::{SimSnapshot, PedestrianState};
+    use crate::simulation::checkpoint::checkpoint::{Checkpoint, RngState, WalkerCheckpoint, CHECKPOINT_VERSION};
     
     
     /// The distance from a target location that a pedestrian needs to be to qualify as having reached it
     pub const TARGET_LOCATION_RADIUS: f64 = 1.5;
     
+    /// Pedestrians within this distance of an exit (or end position) are counted as queueing at it, in metres
+    pub const EXIT_QUEUE_RADIUS: f64 = 3.0;
+    
+    /// How many random positions in its start zone a new pedestrian tries before settling for the least crowded one
+    const SPAWN_ATTEMPTS: usize = 10;
+    
+    /// The length of the intervals that crossings of counting lines are binned into for flux time series, in seconds
+    pub const FLUX_INTERVAL: f64 = 1.0;
+    
+    /// How often `simulate_full_with_progress` reports its progress, in seconds of simulation time
+    const PROGRESS_INTERVAL: f64 = 10.0;
+    
+    /// How long `simulate_full` runs pedestrians walking cyclic routes for when no maximum simulated time has been set, in seconds
+    pub const DEFAULT_RECIRCULATION_TIME: f64 = 3600.0;
+    
     
     const START_COLOUR: &str = "F48154";
     const END_COLOUR: &str = "2D8183";
     const END_ZONE_COLOUR: &str = "83D3D4";
     const TIMING_BOUND_COLOUR: &str = "F48154";
+    const MARKING_COLOUR: &str = "D9B23A";
+    const DOOR_COLOUR: &str = "4C6EB1";
+    const SPEED_ZONE_COLOUR: &str = "7A9E4B";
+    const WAYPOINT_COLOUR: &str = "8A6FB5";
+    const EXIT_COLOUR: &str = "C0392B";
+    const MEASUREMENT_AREA_COLOUR: &str = "3A7BD5";
+    const COUNTING_LINE_COLOUR: &str = "3AA86B";
+    
+    /// The colours given to groups & behaviour models when pedestrians are coloured by them, in order (repeating if there are more)
+    const CATEGORY_COLOURS: [&str; 8] = ["1F77B4", "FF7F0E", "2CA02C", "D62728", "9467BD", "8C564B", "E377C2", "17BECF"];
+    /// The colours of the scale used when pedestrians are coloured by speed, from stopped to walking at their target speed (red, yellow, green)
+    const SPEED_SCALE_COLOURS: [&str; 3] = ["D62728", "E8C33A", "2CA02C"];
+    /// The colours of the scale used when pedestrians are coloured by density, from empty to COLOUR_DENSITY_MAX (green, yellow, red)
+    const DENSITY_SCALE_COLOURS: [&str; 3] = ["2CA02C", "E8C33A", "D62728"];
+    /// The radius of the circle around each pedestrian that the density it experiences is measured over, when pedestrians are coloured by density, in metres
+    const COLOUR_DENSITY_RADIUS: f64 = 1.0;
+    /// The density at the top of the colour scale when pedestrians are coloured by density, in pedestrians/m²
+    const COLOUR_DENSITY_MAX: f64 = 4.0;
     
     
     /// Contains all information related to a crowd simulation
     pub struct CrowdSim {
         /// The 2D space where the simulation takes place
         area: Arc<SimArea>,
+        /// The parameters of the pedestrian model, copied into each pedestrian when it is added
+        params: SimParams,
+        /// Distributions that each pedestrian's parameters are sampled from when it is added
+        param_variation: ParamVariation,
+        /// How many of the pedestrians added are distracted, and how they differ from the rest (None = nobody is distracted)
+        distraction: Option<Distraction>,
+        /// How many pedestrians don't know their way and follow the crowd instead (None = everyone knows their way)
+        herding: Option<Herding>,
+        /// How many pedestrians keep a larger personal space around themselves (None = nobody keeps their distance)
+        distancing: Option<Distancing>,
+        /// The bulky items that a fraction of the pedestrians added take along, such as suitcases or prams
+        luggage: Vec<Luggage>,
+        /// The classes of pedestrians with their own speeds and bodies (empty = every target speed is drawn from `target_speed_bounds`)
+        demographics: Vec<Demographic>,
+        /// How pedestrians find their way around geometry (None = walk straight towards each waypoint)
+        navigation: Option<Navigation>,
         /// The amount of time simulated, in seconds
         pub time_elapsed: f64,
+        /// The source of all randomness in the simulation (other than the arrival process, which has its own seed), so that a run can be repeated with `set_seed` or resumed from a checkpoint
+        rng: ChaCha8Rng,
+        /// The seed given to `set_seed` (None = seeded from entropy, so the run can't be repeated)
+        seed: Option<u64>,
         /// All the walkers contained in the simulation
         available_pedestrians: Vec<pedestrian::Walker>,
+        /// Walkers released together at a set time (e.g. alighting from a train), with their release times, in order of release
+        scheduled_pedestrians: Vec<(f64, pedestrian::Walker)>,
+        /// Walkers whose turn to start walking has come, held back by the spawn gate until there is room at their start position, in the order they arrived
+        held_pedestrians: Vec<pedestrian::Walker>,
         /// All the walkers currently walking
         active_pedestrians: Vec<pedestrian::Walker>,
         /// All the walkers that have reached their destinations
         finished_pedestrians: Vec<pedestrian::Walker>,
         /// The number of pedestrians added to the simulation per second
         pedestrian_add_rate: f64,
+        /// Decides when waiting pedestrians are added to the simulation
+        arrival_process: Box<dyn ArrivalProcess>,
+        /// The simulation time at which the arrival process was last asked for arrivals
+        arrival_clock: f64,
         /// The travel time, group ID, and finish time, per pedestrian
-        travel_times: Vec<(f64, usize, f64)>
+        travel_times: Vec<(f64, usize, f64)>,
+        /// A record of every pedestrian timed between the timing boundaries, in the order they were timed
+        timing_events: Vec<TimingEvent>,
+        /// A record of every time a pedestrian stalled (see the `stall_time` parameter), in order
+        stall_events: Vec<StallEvent>,
+        /// A record of every pedestrian incapacitated by a hazard, in order (casualties aren't saved in checkpoints)
+        hazard_casualties: Vec<HazardCasualty>,
+        /// The ID given to the next pedestrian added
+        next_pedestrian_id: u64,
+        /// The cohorts of the pedestrian behind each travel time, in the same order as travel_times
+        travel_time_cohorts: Vec<Vec<String>>,
+        /// The name of the behaviour model followed by the pedestrian behind each travel time, when it was timed
+        travel_time_behaviours: Vec<String>,
+        /// The names of every cohort pedestrians have been tagged with, in the order they were first used
+        cohort_names: Vec<String>,
+        /// The average heading jitter and group ID, per finished pedestrian
+        jitter_results: Vec<(f64, usize)>,
+        /// The time spent giving way at junctions and group ID, per finished pedestrian
+        junction_delays: Vec<(f64, usize)>,
+        /// How each finished pedestrian that didn't know its way found it (wayfinding results aren't saved in checkpoints)
+        wayfinding_results: Vec<WayfindingResult>,
+        /// Changes of behaviour model that are yet to happen, in order of time
+        behaviour_switches: VecDeque<BehaviourSwitch>,
+        /// The simulation time and a description of each behaviour switch that has happened
+        behaviour_switch_log: Vec<(f64, String)>,
+        /// Scripted events that are yet to happen, in order of time
+        events: Vec<ScenarioEvent>,
+        /// The simulation time and a description of each scripted event that has happened (or been skipped)
+        event_log: Vec<(f64, String)>,
+        /// How often pedestrians stop to chat with acquaintances walking the other way (None = never)
+        conversations: Option<Conversations>,
+        /// Every conversation that has started, in order
+        conversation_log: Vec<Conversation>,
+        /// How far ahead pedestrians signal the direction they intend to walk in to their neighbours, in seconds (None = no signalling)
+        intent_horizon: Option<f64>,
+        /// Whether pedestrians are held back until there is room for them at their start position
+        spawn_gate: bool,
+        /// What pedestrians do when they reach their destination, if they walk cyclic routes instead of finishing (None = they finish)
+        recirculation: Option<Recirculation>,
+        /// The number of times a pedestrian has reached its destination and started its route again (laps aren't saved in checkpoints)
+        completed_laps: usize,
+        /// The simulation time and the number of held back pedestrians, each time the number changes
+        spawn_queue_lengths: Vec<(f64, usize)>,
+        /// The longest `simulate_full` runs for: (simulated time, wall-clock time), in seconds (None = no limit)
+        run_limits: (Option<f64>, Option<f64>),
+        /// How the last call to `simulate_full` ended
+        run_status: RunStatus,
+        /// How crowded each part of the area has been (None = not recorded)
+        density_grid: Option<DensityGrid>,
+        /// The measurements taken in each measurement area, every timestep that it has pedestrians in or near it
+        fundamental_diagrams: Vec<Vec<AreaMeasurement>>,
+        /// The pedestrians in each queue at a service point, and how long the pedestrians that have been through it waited (queues aren't saved in checkpoints)
+        queue_states: Vec<QueueState>,
+        /// Whether each door in the area is open at the moment
+        door_states: Vec<bool>,
+        /// The pedestrians waiting for each ticket gate, and everyone that has been through it (gates aren't saved in checkpoints)
+        gate_states: Vec<GateState>,
+        /// The vehicles travelling along the area's vehicle lanes, and the results of the ones that have finished (vehicles aren't saved in checkpoints)
+        vehicle_traffic: VehicleTraffic,
+        /// The trains that pedestrians in some groups board, and who has boarded them (boarding isn't saved in checkpoints)
+        boardings: Vec<Boarding>,
+        /// Samples of every pedestrian's trajectory (None = not recorded)
+        trajectories: Option<TrajectoryRecorder>,
+        /// Samples of the crowd pressure on every pedestrian, and where it has been dangerously high (None = not recorded)
+        pressure: Option<PressureRecorder>,
+        /// How long every pair of pedestrians has spent close to each other (None = not recorded)
+        contacts: Option<ContactRecorder>,
+        /// Every crossing of each counting line: \[(simulation time, direction)]
+        line_crossings: Vec<Vec<(f64, CrossingDirection)>>,
+        /// The radius that pedestrians' Voronoi cells are cut off at when measuring density with the Voronoi method, in metres (None = not measured)
+        voronoi_cutoff: Option<f64>,
+        /// Which pedestrians each pedestrian can see, so that they don't all have to look at every other pedestrian every timestep (None = see everyone)
+        neighbour_lists: Option<NeighbourLists>,
+        /// The compute shader that finds each pedestrian's neighbours and contact impulse on the GPU instead (None = on the CPU)
+        #[cfg(feature = "gpu")]
+        gpu_neighbours: Option<GpuNeighbours>,
+        /// The time spent in each phase of the timesteps (None = not timed)
+        profiler: Option<Profiler>,
+        /// The simulation time at which every pedestrian heads for the nearest exit (None = no evacuation)
+        evacuation_time: Option<f64>,
+        /// Measurements of the evacuation, once it has started
+        evacuation_results: Option<EvacuationResults>,
+        /// Receive callbacks as things happen in the simulation (see `add_observer`)
+        observers: Vec<Box<dyn Observer>>,
+        
+        /// Scratch space for the positions of the active pedestrians during a timestep, kept so that it isn't reallocated every timestep: \[(x, y, direction, radius, speed)]
+        /// 
+        /// This is all that pedestrians read about their neighbours, packed together so that scanning every neighbour doesn't touch the rest of each `Walker`.
+        /// The steering rules read all five values of each neighbour they look at, so they are kept side by side rather than in separate arrays.
+        position_buffer: Vec<(f64, f64, f64, f64, f64)>,
+        /// Scratch space for the positions & speeds of the active pedestrians while measuring areas, kept for the same reason
+        measurement_buffer: Vec<(Vec2, f64)>
+    }
+    
+    /// The results of the pedestrians in a single cohort, in the same formats as the results of the whole simulation
+    #[derive(Clone, Debug)]
+    pub struct CohortResults {
+        /// The number of pedestrians in the cohort that have reached their destinations
+        pub finished_count: usize,
+        /// \[(travel time, group, finish time)]
+        pub travel_times: Vec<(f64, usize, f64)>,
+        /// \[(jitter, group)]. See `CrowdSim::get_jitter_results`.
+        pub jitter_results: Vec<(f64, usize)>,
+        /// \[(delay, group)]. See `CrowdSim::get_junction_delays`.
+        pub junction_delays: Vec<(f64, usize)>
+    }
+    
+    /// Records a pedestrian passing both timing boundaries
+    #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+    pub struct TimingEvent {
+        /// The ID of the pedestrian (see `Walker::get_id`)
+        pub pedestrian_id: u64,
+        /// The group the pedestrian is part of
+        pub group: usize,
+        /// The time the pedestrian took to travel between the timing boundaries, in seconds
+        pub elapsed: f64,
+        /// The simulation time the pedestrian reached the second timing boundary, in seconds
+        pub sim_time: f64
+    }
+    
+    /// What pedestrians walking cyclic routes do when they reach their destination, so that a constant number of them keep walking (see `CrowdSim::set_recirculation`)
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Recirculation {
+        /// Go back to a random point in their start zone and walk the route again
+        Respawn,
+        /// Walk their group's waypoints again from where they are, which need to lead around a loop back to the destination (e.g. in a ring-shaped corridor).
+        /// Pedestrians in groups without waypoints respawn instead.
+        Loop
+    }
+    
+    /// How a run of a simulation with `simulate_full` ended
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum RunStatus {
+        /// The simulation hasn't been run with `simulate_full` yet
+        NotRun,
+        /// Every pedestrian finished (or, with cyclic routes, the maximum simulated time was reached)
+        Completed,
+        /// The run was stopped at the maximum simulated time, with some pedestrians yet to finish
+        SimTimeLimit,
+        /// The run was stopped at the maximum wall-clock time, with some pedestrians yet to finish
+        WallClockLimit,
+        /// The run was cancelled with a `CancelToken`, with some pedestrians yet to finish
+        Cancelled
+    }
+    
+    impl RunStatus {
+        /// Return a short description of the status
+        pub fn name(self) -> &'static str {
+            return match self {
+                RunStatus::NotRun => "not run",
+                RunStatus::Completed => "completed",
+                RunStatus::SimTimeLimit => "stopped at the simulated time limit",
+                RunStatus::WallClockLimit => "stopped at the wall-clock time limit",
+                RunStatus::Cancelled => "cancelled"
+            };
+        }
+    }
+    
+    /// A flag that stops a run of `CrowdSim::simulate_full_with_progress` at the end of the current timestep once it is set, e.g. from a Ctrl-C handler or a GUI thread.
+    /// Clones share the same flag.
+    #[derive(Clone, Debug, Default)]
+    pub struct CancelToken {
+        cancelled: Arc<AtomicBool>
+    }
+    
+    impl CancelToken {
+        pub fn new() -> CancelToken {
+            return CancelToken::default();
+        }
+        
+        /// Ask the run to stop
+        pub fn cancel(&self) {
+            self.cancelled.store(true, Ordering::Relaxed);
+        }
+        
+        /// Return whether the run has been asked to stop
+        pub fn is_cancelled(&self) -> bool {
+            return self.cancelled.load(Ordering::Relaxed);
+        }
+    }
+    
+    /// How far a run of `CrowdSim::simulate_full_with_progress` has got
+    #[derive(Clone, Copy, Debug)]
+    pub struct RunProgress {
+        /// The number of pedestrians that have finished
+        pub finished: usize,
+        /// The number of pedestrians that have finished, are walking or are yet to start
+        pub total: usize,
+        /// The simulation time, in seconds
+        pub sim_time: f64
+    }
+    
+    impl RunProgress {
+        /// Return the percentage of pedestrians that have finished
+        pub fn get_percent_finished(&self) -> f64 {
+            if self.total == 0 {
+                return 100.0;
+            }
+            return 100.0 * (self.finished as f64) / (self.total as f64);
+        }
+    }
+    
+    /// Records a pedestrian stalling: going for the `stall_time` parameter without getting any closer to where it is heading
+    #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+    pub struct StallEvent {
+        /// The ID of the pedestrian (see `Walker::get_id`)
+        pub pedestrian_id: u64,
+        /// The group the pedestrian is part of
+        pub group: usize,
+        /// Where the pedestrian stalled
+        pub position: (f64, f64),
+        /// The simulation time the pedestrian stalled at, in seconds
+        pub sim_time: f64,
+        /// What the pedestrian did to recover
+        pub recovery: StallRecovery
+    }
+    
+    /// The direction a pedestrian crossed a counting line in (see `MeasurementLine::get_crossing`)
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+    pub enum CrossingDirection {
+        Forward,
+        Backward
+    }
+    
+    /// The density and speed of the pedestrians in a measurement area at one timestep
+    #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+    pub struct AreaMeasurement {
+        /// The simulation time, in seconds
+        pub time: f64,
+        /// The number of pedestrians in the area divided by its size, in pedestrians/m²
+        pub density: f64,
+        /// The mean speed of the pedestrians in the area, in m/s (None = the area is empty)
+        pub mean_speed: Option<f64>,
+        /// (density in pedestrians/m², speed in m/s) measured with the Voronoi method, if it is enabled and any pedestrian's Voronoi cell reaches into the area
+        pub voronoi: Option<(f64, f64)>
+    }
+    
+    /// The number of pedestrians travelling from one group's start positions to another group's end positions
+    #[derive(Clone, Copy, Debug)]
+    pub enum OdDemand {
+        /// A number of pedestrians, added alongside all other pedestrians at the simulation's add rate
+        Count(usize),
+        /// Pedestrians released separately at a number per second, between two simulation times (in seconds).
+        /// The gaps between them are spread out the same way as the simulation's arrival process spreads out its arrivals.
+        Rate {
+            rate: f64,
+            start_time: f64,
+            end_time: f64
+        }
+    }
+    
+    /// Measurements of an evacuation
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    pub struct EvacuationResults {
+        /// The simulation time at which the evacuation started, in seconds
+        pub start_time: f64,
+        /// The number of pedestrians that were walking when the evacuation started
+        pub evacuee_count: usize,
+        /// The time between the start of the evacuation and the last pedestrian leaving, in seconds (None = still in progress)
+        pub egress_time: Option<f64>,
+        /// The number of pedestrians that have left through each exit
+        pub exit_usage: Vec<usize>,
+        /// The largest number of pedestrians queueing at each exit at once
+        pub peak_exit_queues: Vec<usize>
+    }
+    
+    /// What pedestrians are coloured by when they are drawn, so that flow structure (e.g. lane formation) can be seen
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum ColourMode {
+        /// Every pedestrian is the same colour
+        Uniform,
+        /// The group the pedestrian is part of
+        Group,
+        /// The behaviour model (e.g. etiquette) the pedestrian follows
+        Behaviour,
+        /// The pedestrian's speed as a fraction of its target speed
+        Speed,
+        /// The density of pedestrians around the pedestrian (see COLOUR_DENSITY_RADIUS)
+        Density
+    }
+    
+    /// How the simulation is drawn: which of the optional overlays are shown (so that dense scenes can be kept readable), and what pedestrians are coloured by
+    #[derive(Clone, Copy, Debug)]
+    pub struct DrawLayers {
+        /// Each pedestrian's look-ahead & look-beside zones
+        pub vision_zones: bool,
+        /// Each pedestrian's personal space
+        pub personal_space: bool,
+        /// A line from each pedestrian in its direction of travel, as long as its speed
+        pub velocity_vectors: bool,
+        /// A line from each pedestrian towards the point it is steering for
+        pub target_lines: bool,
+        /// Gridlines every metre
+        pub grid: bool,
+        /// The density recorded so far (if density recording is enabled), underneath everything else
+        pub heatmap: bool,
+        pub colour_mode: ColourMode
+    }
+    
+    impl Default for DrawLayers {
+        /// Every overlay but the heatmap is drawn, and every pedestrian is the same colour
+        fn default() -> DrawLayers {
+            return DrawLayers {
+                vision_zones: true,
+                personal_space: true,
+                velocity_vectors: true,
+                target_lines: true,
+                grid: true,
+                heatmap: false,
+                colour_mode: ColourMode::Uniform
+            };
+        }
+    }
+    
+    impl ColourMode {
+        /// Return the mode after this one, for cycling through every mode
+        pub fn next(self) -> ColourMode {
+            return match self {
+                ColourMode::Uniform => ColourMode::Group,
+                ColourMode::Group => ColourMode::Behaviour,
+                ColourMode::Behaviour => ColourMode::Speed,
+                ColourMode::Speed => ColourMode::Density,
+                ColourMode::Density => ColourMode::Uniform
+            };
+        }
+        
+        /// Return a short description of the mode
+        pub fn name(self) -> &'static str {
+            return match self {
+                ColourMode::Uniform => "none",
+                ColourMode::Group => "group",
+                ColourMode::Behaviour => "behaviour",
+                ColourMode::Speed => "speed / target speed",
+                ColourMode::Density => "density (ped/m²)"
+            };
+        }
+    }
+    
+    /// A scheduled change of the behaviour model followed by some or all pedestrians
+    struct BehaviourSwitch {
+        /// The simulation time at which the switch happens, in seconds
+        time: f64,
+        /// Only pedestrians following a model with this name are switched (None = all pedestrians)
+        from: Option<String>,
+        /// The model that switched pedestrians follow afterwards
+        to: Arc<dyn BehaviourModel>
     }
     
     /// Describes a 2 dimensional environment where a simulation takes place
+    /// 
+    /// Only `boundaries` and `obstacles` are physical: pedestrians collide with them.
+    /// Timing boundaries are only used for measurement, and markings are only drawn - pedestrians walk straight through both.
     pub struct SimArea {
         pub boundaries: Vec<Wall>,
+        pub obstacles: Vec<CircularObstacle>,
+        /// The centre of each start zone of each group
         pub start_positions: Vec<Vec<(f64, f64)>>,
+        /// The centre of each end zone of each group
         pub end_positions: Vec<Vec<(f64, f64)>>,
-        pub timing_boundaries: Vec<Wall>
+        /// The places where pedestrians in each group start, which can be points, or lines or areas they are spread across
+        pub start_zones: Vec<Vec<Zone>>,
+        /// The places where pedestrians in each group finish, which can be points, or lines or areas they are spread across
+        pub end_zones: Vec<Vec<Zone>>,
+        /// Intermediate points that pedestrians in each group pass through, in order, before heading to their end positions
+        pub group_waypoints: Vec<Vec<(f64, f64)>>,
+        pub timing_boundaries: Vec<MeasurementLine>,
+        /// Lines that every crossing of is logged, for measuring the flow through a corridor or gate
+        pub counting_lines: Vec<MeasurementLine>,
+        /// Areas that the density and speed of pedestrians are measured over, for fundamental diagrams
+        pub measurement_areas: Vec<MeasurementArea>,
+        pub markings: Vec<Marking>,
+        /// Areas where crossing streams of pedestrians give way to each other
+        pub junctions: Vec<JunctionZone>,
+        /// Designated exits that every pedestrian heads for during an evacuation
+        pub exits: Vec<(f64, f64)>,
+        /// Walls that can be opened to let pedestrians through
+        pub doors: Vec<Door>,
+        /// Lines that pedestrians in some groups wait in to be served, before carrying on to their destinations
+        pub queues: Vec<ServiceQueue>,
+        /// Turnstiles and ticket gates, which only let one pedestrian through at a time
+        pub gates: Vec<TicketGate>,
+        /// Moving walkways and escalators, which carry the pedestrians on them along
+        pub walkways: Vec<MovingWalkway>,
+        /// Areas where pedestrians walk slower or faster than usual, such as stairs, ramps or grass
+        pub speed_zones: Vec<SpeedZone>,
+        /// Routes that vehicles such as bicycles follow through the area, which pedestrians give way to
+        pub vehicle_lanes: Vec<VehicleLane>,
+        /// Hazards such as smoke from a fire, which spread out over time and which pedestrians keep away from
+        pub hazards: Vec<HazardSource>,
+        /// Wayfinding signs, which point pedestrians that don't know their way towards their destinations
+        pub signs: Vec<Sign>
     }
     
     /// Describes an impassable linear barrier with a start and end point
@@ -51,191 +533,2339 @@ pub mod simulator {
         y2: f64,
     }
     
+    /// A wall that can be opened to let pedestrians through, such as a platform screen door or a gate.
+    /// 
+    /// It opens & closes by itself on a schedule, or when `CrowdSim::set_door_open` is called. Pedestrians collide with it and can't see through it while it is closed, but path planning treats it as always open.
+    /// Whether it is open at the moment is part of each simulation's state (see `CrowdSim::get_door_states`), so simulations sharing an area open & close their doors separately.
+    pub struct Door {
+        wall: Wall,
+        /// Whether the door is open at the start of a simulation
+        open: bool,
+        /// The times the door opens or closes: \[(simulation time, open)], in order of time
+        schedule: Vec<(f64, bool)>
+    }
+    
+    /// Describes a polygonal area where pedestrians walk slower or faster than usual, such as stairs, a ramp or a grass verge
+    pub struct SpeedZone {
+        /// The corners of the polygon, in order
+        points: Vec<Vec2>,
+        /// The multiplier applied to the speed pedestrians want to walk at inside the zone
+        multiplier: f64
+    }
+    
+    /// Describes a virtual line that pedestrians are timed or counted crossing. It has no effect on pedestrian movement.
+    pub struct MeasurementLine {
+        x1: f64,
+        y1: f64,
+        x2: f64,
+        y2: f64,
+    }
+    
+    /// Describes a virtual polygon that the density and speed of the pedestrians inside are measured over. It has no effect on pedestrian movement.
+    pub struct MeasurementArea {
+        /// The corners of the polygon, in order
+        points: Vec<Vec2>
+    }
+    
+    /// Describes a line painted on the ground, which is drawn but has no effect on pedestrians
+    pub struct Marking {
+        x1: f64,
+        y1: f64,
+        x2: f64,
+        y2: f64,
+    }
+    
+    /// Describes an impassable circular obstacle, such as a pillar or a tree
+    pub struct CircularObstacle {
+        x: f64,
+        y: f64,
+        radius: f64
+    }
+    
     impl CrowdSim {
         /// Create a new CrowdSim object.
         /// 
         /// * `area` - A `SimArea` object describing the space for the simulation to be set in.
         /// * `pedestrian_add_rate` - The number of pedestrians added to the simulation per second.
         pub fn new(area: Arc<SimArea>, pedestrian_add_rate: f64) -> CrowdSim {
+            let measurement_area_count = area.measurement_areas.len();
+            let counting_line_count = area.counting_lines.len();
+            let queue_count = area.queues.len();
+            let gate_count = area.gates.len();
+            let door_states = area.doors.iter().map(|door| door.is_initially_open()).collect();
+            
             CrowdSim {
                 area,
+                params: SimParams::default(),
+                param_variation: ParamVariation::default(),
+                distraction: None,
+                herding: None,
+                distancing: None,
+                luggage: Vec::new(),
+                demographics: Vec::new(),
+                navigation: None,
                 time_elapsed: 0.0,
+                rng: ChaCha8Rng::from_entropy(),
+                seed: None,
                 available_pedestrians: Vec::new(),
+                scheduled_pedestrians: Vec::new(),
+                held_pedestrians: Vec::new(),
                 active_pedestrians: Vec::new(),
                 finished_pedestrians: Vec::new(),
                 pedestrian_add_rate,
-                travel_times: Vec::new()
+                arrival_process: Box::new(Deterministic::new()),
+                arrival_clock: 0.0,
+                travel_times: Vec::new(),
+                timing_events: Vec::new(),
+                stall_events: Vec::new(),
+                hazard_casualties: Vec::new(),
+                next_pedestrian_id: 0,
+                travel_time_cohorts: Vec::new(),
+                travel_time_behaviours: Vec::new(),
+                cohort_names: Vec::new(),
+                jitter_results: Vec::new(),
+                junction_delays: Vec::new(),
+                wayfinding_results: Vec::new(),
+                behaviour_switches: VecDeque::new(),
+                behaviour_switch_log: Vec::new(),
+                events: Vec::new(),
+                event_log: Vec::new(),
+                conversations: None,
+                conversation_log: Vec::new(),
+                intent_horizon: None,
+                spawn_gate: false,
+                recirculation: None,
+                completed_laps: 0,
+                spawn_queue_lengths: Vec::new(),
+                run_limits: (None, None),
+                run_status: RunStatus::NotRun,
+                density_grid: None,
+                fundamental_diagrams: vec![Vec::new(); measurement_area_count],
+                queue_states: (0..queue_count).map(|_| QueueState::default()).collect(),
+                door_states,
+                gate_states: (0..gate_count).map(|_| GateState::default()).collect(),
+                vehicle_traffic: VehicleTraffic::default(),
+                boardings: Vec::new(),
+                trajectories: None,
+                pressure: None,
+                contacts: None,
+                line_crossings: vec![Vec::new(); counting_line_count],
+                voronoi_cutoff: None,
+                neighbour_lists: None,
+                #[cfg(feature = "gpu")]
+                gpu_neighbours: None,
+                profiler: None,
+                evacuation_time: None,
+                evacuation_results: None,
+                observers: Vec::new(),
+                position_buffer: Vec::new(),
+                measurement_buffer: Vec::new()
+            }
+        }
+        
+        /// Set the parameters of the pedestrian model.
+        /// 
+        /// Pedestrians copy the parameters when they are added, so this should be called before any pedestrians are added.
+        pub fn set_params(&mut self, params: SimParams) {
+            self.params = params;
+        }
+        
+        /// Set the distributions that each pedestrian's parameters are sampled from, so that pedestrians can differ in size, comfort distance, etc.
+        /// 
+        /// Like `set_params`, this only affects pedestrians added afterwards.
+        pub fn set_param_variation(&mut self, variation: ParamVariation) {
+            self.param_variation = variation;
+        }
+        
+        /// Return the distributions that each pedestrian's parameters are sampled from
+        pub fn get_param_variation(&self) -> &ParamVariation {
+            return &self.param_variation;
+        }
+        
+        /// Make a fraction of the pedestrians added from now on distracted, following a `Distracted` version of their behaviour model with reduced perception & reactions (None = nobody is distracted)
+        pub fn set_distraction(&mut self, distraction: Option<Distraction>) {
+            self.distraction = distraction;
+        }
+        
+        /// Return how many of the pedestrians added are distracted, and how they differ from the rest
+        pub fn get_distraction(&self) -> Option<&Distraction> {
+            return self.distraction.as_ref();
+        }
+        
+        /// Make a fraction of the pedestrians added from now on unfamiliar with their route, so that they follow the crowd around them instead of heading for their destinations
+        /// (None = everyone knows their way).
+        /// 
+        /// Unfamiliar pedestrians are reported as the "unfamiliar" cohort (see `get_cohort_results`).
+        pub fn set_herding(&mut self, herding: Option<Herding>) {
+            if herding.is_some() && !self.cohort_names.iter().any(|name| name == UNFAMILIAR_COHORT) {
+                self.cohort_names.push(UNFAMILIAR_COHORT.to_string());
+            }
+            self.herding = herding;
+        }
+        
+        /// Return how many of the pedestrians added don't know their way, and how they follow the crowd
+        pub fn get_herding(&self) -> Option<&Herding> {
+            return self.herding.as_ref();
+        }
+        
+        /// Set how many of the pedestrians added from now on keep a larger personal space around themselves (None = nobody keeps their distance).
+        /// 
+        /// Pedestrians keeping their distance are reported as the "distancing" cohort.
+        pub fn set_distancing(&mut self, distancing: Option<Distancing>) {
+            if distancing.is_some() && !self.cohort_names.iter().any(|name| name == DISTANCING_COHORT) {
+                self.cohort_names.push(DISTANCING_COHORT.to_string());
+            }
+            self.distancing = distancing;
+        }
+        
+        /// Return how many pedestrians keep their distance, or None if nobody does
+        pub fn get_distancing(&self) -> Option<&Distancing> {
+            return self.distancing.as_ref();
+        }
+        
+        /// Make a fraction of the pedestrians added from now on take along a bulky item, such as a suitcase or a pram, for each kind of item (empty = nobody takes anything along).
+        /// 
+        /// Each kind of item is also a cohort, so the results of the pedestrians taking it along can be reported with `get_cohort_results`.
+        pub fn set_luggage(&mut self, luggage: Vec<Luggage>) {
+            for item in &luggage {
+                if !self.cohort_names.contains(&item.name) {
+                    self.cohort_names.push(item.name.clone());
+                }
+            }
+            self.luggage = luggage;
+        }
+        
+        /// Return the kinds of bulky item that pedestrians take along
+        pub fn get_luggage(&self) -> &[Luggage] {
+            return &self.luggage;
+        }
+        
+        /// Divide the pedestrians added from now on into demographic classes, each with its own speed distribution and body (empty = every target speed is drawn from `target_speed_bounds`).
+        /// 
+        /// Each class is also a cohort, so its results can be reported with `get_cohort_results`.
+        pub fn set_demographics(&mut self, demographics: Vec<Demographic>) {
+            for demographic in &demographics {
+                if !self.cohort_names.contains(&demographic.name) {
+                    self.cohort_names.push(demographic.name.clone());
+                }
+            }
+            self.demographics = demographics;
+        }
+        
+        /// Return the demographic classes that new pedestrians are put in
+        pub fn get_demographics(&self) -> &[Demographic] {
+            return &self.demographics;
+        }
+        
+        /// Choose the demographic class of a new pedestrian at random (if there are any classes), and sample its target speed.
+        /// 
+        /// Without demographic classes, the target speed comes from the `target_speed` distribution of the parameter variation, or a uniform draw from `target_speed_bounds`.
+        fn sample_target_speed(&mut self) -> (f64, Option<usize>) {
+            if self.demographics.is_empty() {
+                if let Some(distribution) = self.param_variation.target_speed {
+                    return (distribution.sample(&mut self.rng).max(self.params.minimum_speed), None);
+                }
+                return (self.params.target_speed_bounds.0 + self.rng.gen::<f64>() * (self.params.target_speed_bounds.1 - self.params.target_speed_bounds.0), None);
+            }
+            
+            let total_fraction = self.demographics.iter().map(|demographic| demographic.fraction).sum::<f64>();
+            let mut choice = self.rng.gen::<f64>() * total_fraction;
+            let mut index = self.demographics.len() - 1;
+            for (i, demographic) in self.demographics.iter().enumerate() {
+                if choice < demographic.fraction {
+                    index = i;
+                    break;
+                }
+                choice -= demographic.fraction;
+            }
+            
+            let target_speed = self.demographics[index].speed.sample(&mut self.rng).max(self.params.minimum_speed);
+            return (target_speed, Some(index));
+        }
+        
+        /// Sample the parameters of a new pedestrian in a demographic class (if any), and decide whether it is distracted
+        fn sample_pedestrian(&mut self, behaviour: Arc<dyn BehaviourModel>, demographic: Option<usize>) -> (Arc<dyn BehaviourModel>, SimParams) {
+            let mut params = self.params.sample_individual(&self.param_variation, &mut self.rng);
+            
+            if let Some(index) = demographic {
+                let demographic = &self.demographics[index];
+                params.radius = demographic.radius.unwrap_or(params.radius);
+                params.accel = demographic.accel.unwrap_or(params.accel);
+            }
+            
+            if let Some(distraction) = &self.distraction {
+                if self.rng.gen::<f64>() < distraction.fraction {
+                    return (Arc::new(Distracted::new(behaviour, distraction)), distraction.apply(params));
+                }
+            }
+            
+            return (behaviour, params);
+        }
+        
+        /// Make pedestrians plan their paths around walls and obstacles with A*, instead of walking straight towards each waypoint.
+        /// 
+        /// Each pedestrian plans its path when it starts walking.
+        /// 
+        /// * `clearance` - The minimum distance between a planned path and any wall or obstacle, in metres
+        pub fn enable_path_planning(&mut self, clearance: f64) {
+            self.navigation = Some(Navigation::PathPlanning(Arc::new(VisibilityGraph::new(&self.area, clearance))));
+        }
+        
+        /// Make pedestrians follow precomputed floor fields to their destinations, instead of walking straight towards them.
+        /// 
+        /// This is cheaper than path planning when there are many pedestrians sharing a few destinations. Waypoints are still walked to directly.
+        /// 
+        /// * `cell_size` - The width and height of each cell of the floor field, in metres
+        /// * `clearance` - The minimum distance between a walkable cell and any wall or obstacle, in metres
+        pub fn enable_floor_field(&mut self, cell_size: f64, clearance: f64) {
+            self.navigation = Some(Navigation::FloorField(Arc::new(FloorField::new(&self.area, &self.door_states, cell_size, clearance))));
+        }
+        
+        /// Make pedestrians signal the direction they intend to walk in, as body language would, instead of only showing the direction they are facing.
+        /// 
+        /// Neighbouring pedestrians see the intended direction in place of the facing direction, so their avoidance can anticipate turns (e.g. which side an oncoming pedestrian is stepping towards).
+        /// 
+        /// * `horizon` - How far ahead the intended direction is signalled, in seconds (None = no signalling)
+        pub fn set_intent_signalling(&mut self, horizon: Option<f64>) {
+            self.intent_horizon = horizon;
+        }
+        
+        /// Return how far ahead pedestrians signal the direction they intend to walk in, in seconds (None = no signalling)
+        pub fn get_intent_signalling(&self) -> Option<f64> {
+            return self.intent_horizon;
+        }
+        
+        /// Hold pedestrians back when their turn to start walking comes, until there is room for them at their start position, instead of letting them start on top of other pedestrians.
+        /// 
+        /// Held back pedestrians queue in the order they arrived, but a pedestrian waiting for one start position doesn't hold back pedestrians starting elsewhere.
+        /// The length of the queue is recorded over time (see `get_spawn_queue_lengths`).
+        pub fn set_spawn_gate(&mut self, enabled: bool) {
+            self.spawn_gate = enabled;
+        }
+        
+        /// Return whether pedestrians are held back until there is room for them at their start position
+        pub fn get_spawn_gate(&self) -> bool {
+            return self.spawn_gate;
+        }
+        
+        /// Make pedestrians walk cyclic routes: instead of finishing when they reach their destination (after all their waypoints), they start their route again,
+        /// so that once everyone has started, a constant number of pedestrians keep walking (e.g. to measure fundamental diagrams in a steady state).
+        /// 
+        /// Pedestrians never finish, so a run with `simulate_full` stops at the maximum simulated time (see `set_run_limits`), or after DEFAULT_RECIRCULATION_TIME if there isn't one.
+        /// Pedestrians evacuating still finish at their exits.
+        /// 
+        /// * `recirculation` - What pedestrians do when they reach their destination (None = they finish)
+        pub fn set_recirculation(&mut self, recirculation: Option<Recirculation>) {
+            self.recirculation = recirculation;
+        }
+        
+        /// Return what pedestrians do when they reach their destination, if they walk cyclic routes (None = they finish)
+        pub fn get_recirculation(&self) -> Option<Recirculation> {
+            return self.recirculation;
+        }
+        
+        /// Return the number of times a pedestrian has reached its destination and started its route again, with cyclic routes
+        pub fn get_completed_laps(&self) -> usize {
+            return self.completed_laps;
+        }
+        
+        /// Stop `simulate_full` early once it has simulated up to a simulation time, or run for a wall-clock time, even if some pedestrians haven't finished (e.g. because they are stuck).
+        /// The results so far are returned as usual, and `get_run_status` tells why the run ended.
+        /// 
+        /// * `max_sim_time` - The simulation time to stop at, in seconds (None = no limit)
+        /// * `max_wall_time` - The longest a call to `simulate_full` can take, in seconds of real time (None = no limit)
+        pub fn set_run_limits(&mut self, max_sim_time: Option<f64>, max_wall_time: Option<f64>) {
+            self.run_limits = (max_sim_time, max_wall_time);
+        }
+        
+        /// Open or close a door straight away (it still opens & closes at the next times on its schedule)
+        /// 
+        /// * `door` - The index of the door in the area
+        pub fn set_door_open(&mut self, door: usize, open: bool) {
+            self.door_states[door] = open;
+        }
+        
+        /// Return whether each door in the area is open at the moment
+        pub fn get_door_states(&self) -> &[bool] {
+            return &self.door_states;
+        }
+        
+        /// Let pedestrians join, move up in and leave the queues at service points
+        fn update_queues(&mut self, time_scale: f64) {
+            for (queue, state) in self.area.queues.iter().zip(&mut self.queue_states) {
+                state.update(queue, self.time_elapsed, time_scale, &mut self.active_pedestrians);
+            }
+        }
+        
+        /// Return the pedestrians waiting in a queue at a service point, and how long the pedestrians that have been through it waited
+        /// 
+        /// * `queue` - The index of the queue in the area
+        pub fn get_queue(&self, queue: usize) -> &QueueState {
+            return &self.queue_states[queue];
+        }
+        
+        /// Have the pedestrians in a group board trains through doors at the group's end positions, instead of finishing as soon as they reach them.
+        /// Each door takes at most `capacity` pedestrians per train, and pedestrians that find their door full head for another one with room or wait for the next train (see `Boarding`).
+        /// 
+        /// * `trains` - The simulation times each train arrives and leaves, in seconds (at least one train): \[(arrival, departure)]
+        pub fn add_boarding(&mut self, group: usize, capacity: usize, trains: Vec<(f64, f64)>) {
+            self.boardings.push(
+                Boarding::new(group, self.area.end_positions[group].len(), capacity, trains)
+            );
+        }
+        
+        /// Return the trains that pedestrians board, in the order they were added, with who has boarded them
+        pub fn get_boardings(&self) -> &[Boarding] {
+            return &self.boardings;
+        }
+        
+        /// Let pedestrians that have reached a train door board, if the door has room
+        fn update_boardings(&mut self, time_scale: f64) {
+            for boarding in &mut self.boardings {
+                boarding.update(self.time_elapsed, time_scale, &self.area.end_zones[boarding.get_group()], &mut self.active_pedestrians);
+            }
+        }
+        
+        /// Return whether a pedestrian finishes when it reaches its destination: pedestrians boarding trains only finish once they have boarded
+        fn can_finish_at_destination(&self, ped: &pedestrian::Walker) -> bool {
+            return ped.get_exit().is_some() || self.boardings.iter()
+                .filter(|boarding| boarding.get_group() == ped.get_destination_group())
+                .all(|boarding| boarding.has_boarded(ped.get_id()));
+        }
+        
+        /// Move the vehicles along their lanes, letting new vehicles in
+        fn update_vehicles(&mut self, time_scale: f64) {
+            if !self.area.vehicle_lanes.is_empty() {
+                self.vehicle_traffic.update(&self.area.vehicle_lanes, self.time_elapsed, time_scale, &self.active_pedestrians);
+            }
+        }
+        
+        /// Return the vehicles travelling along the area's vehicle lanes, and the results of the ones that have finished
+        pub fn get_vehicle_traffic(&self) -> &VehicleTraffic {
+            return &self.vehicle_traffic;
+        }
+        
+        /// Open or close the doors that are scheduled to open or close during this timestep
+        fn update_doors(&mut self, time_scale: f64) {
+            for (door, state) in self.area.doors.iter().zip(&mut self.door_states) {
+                let mut changes = door.get_schedule().iter().filter(|(time, _)| *time >= self.time_elapsed && *time < self.time_elapsed + time_scale);
+                if let Some((_, open)) = changes.next_back() {
+                    *state = *open;
+                }
+            }
+        }
+        
+        /// Let pedestrians wait for and pass through the ticket gates, one at a time at each gate
+        fn update_gates(&mut self, time_scale: f64) {
+            for (gate, state) in self.area.gates.iter().zip(&mut self.gate_states) {
+                state.update(gate, self.time_elapsed, time_scale, &mut self.active_pedestrians);
+            }
+        }
+        
+        /// Return the pedestrians waiting for a ticket gate, and everyone that has been through it
+        /// 
+        /// * `gate` - The index of the gate in the area
+        pub fn get_gate(&self, gate: usize) -> &GateState {
+            return &self.gate_states[gate];
+        }
+        
+        /// Return how the last call to `simulate_full` ended
+        pub fn get_run_status(&self) -> RunStatus {
+            return self.run_status;
+        }
+        
+        /// Return how many pedestrians have finished so far, out of how many, and the simulation time
+        pub fn get_run_progress(&self) -> RunProgress {
+            let (available, active, finished) = self.get_pedestrian_counts();
+            return RunProgress {
+                finished,
+                total: available + active + finished,
+                sim_time: self.time_elapsed
+            };
+        }
+        
+        /// Start recording how crowded each part of the area is, on a grid of square cells, so that congestion can be mapped (see `export::write_density_png`).
+        /// 
+        /// Any density recorded so far is discarded.
+        /// 
+        /// * `cell_size` - The width and height of each cell, in metres
+        pub fn enable_density_recording(&mut self, cell_size: f64) {
+            self.density_grid = Some(DensityGrid::new(&self.area, cell_size));
+        }
+        
+        /// Return the density recorded so far, or None if it isn't being recorded
+        pub fn get_density_grid(&self) -> Option<&DensityGrid> {
+            return self.density_grid.as_ref();
+        }
+        
+        /// Return every crossing of a counting line so far, in the order they happened
+        /// 
+        /// Return format: \[(simulation time, direction)]
+        pub fn get_line_crossings(&self, counting_line: usize) -> &[(f64, CrossingDirection)] {
+            return &self.line_crossings[counting_line];
+        }
+        
+        /// Return the flux through a counting line over each `FLUX_INTERVAL` of the simulation so far, in pedestrians per second.
+        /// 
+        /// Return format: \[(start time of the interval, forward flux, backward flux)]
+        pub fn get_line_flux(&self, counting_line: usize) -> Vec<(f64, f64, f64)> {
+            let interval_count = (self.time_elapsed / FLUX_INTERVAL).ceil() as usize;
+            let mut counts = vec![(0, 0); interval_count];
+            
+            for (time, direction) in &self.line_crossings[counting_line] {
+                // A crossing at the very end of the simulation is counted in the last interval
+                let interval = ((time / FLUX_INTERVAL) as usize).min(interval_count.saturating_sub(1));
+                match direction {
+                    CrossingDirection::Forward => counts[interval].0 += 1,
+                    CrossingDirection::Backward => counts[interval].1 += 1
+                }
             }
+            
+            return counts.iter().enumerate().map(|(i, (forward, backward))| {
+                ((i as f64) * FLUX_INTERVAL, (*forward as f64) / FLUX_INTERVAL, (*backward as f64) / FLUX_INTERVAL)
+            }).collect();
+        }
+        
+        /// Start recording every pedestrian's trajectory, sampling the position, speed and heading of every walking pedestrian at regular intervals (see `export::write_trajectories_csv`).
+        /// 
+        /// Any trajectories recorded so far are discarded.
+        /// 
+        /// * `interval` - The simulation time between samples, in seconds
+        pub fn enable_trajectory_recording(&mut self, interval: f64) {
+            self.trajectories = Some(TrajectoryRecorder::new(interval));
+        }
+        
+        /// Return the trajectories recorded so far, or None if they aren't being recorded
+        pub fn get_trajectories(&self) -> Option<&TrajectoryRecorder> {
+            return self.trajectories.as_ref();
+        }
+        
+        /// Start measuring the crowd pressure on every walking pedestrian at regular intervals, and flagging where and when it exceeds safety thresholds (see `export::write_pressure_csv` and `export::write_risk_episodes_csv`).
+        /// 
+        /// Any pressure recorded so far is discarded.
+        /// 
+        /// * `interval` - The simulation time between samples, in seconds
+        /// * `cell_size` - The width and height of the grid cells that dangerous pressure is flagged in, in metres
+        pub fn enable_pressure_recording(&mut self, interval: f64, cell_size: f64, thresholds: PressureThresholds) {
+            self.pressure = Some(PressureRecorder::new(interval, cell_size, thresholds));
+        }
+        
+        /// Return the crowd pressure recorded so far, or None if it isn't being recorded
+        pub fn get_pressure(&self) -> Option<&PressureRecorder> {
+            return self.pressure.as_ref();
+        }
+        
+        /// Start recording how long every pair of walking pedestrians spends within a distance of each other, checking every timestep (see `export::write_contacts_csv`).
+        /// 
+        /// Any contacts recorded so far are discarded.
+        /// 
+        /// * `distance` - The distance within which two pedestrians are in contact, in metres
+        pub fn enable_contact_recording(&mut self, distance: f64) {
+            self.contacts = Some(ContactRecorder::new(distance));
+        }
+        
+        /// Return the contacts recorded so far, or None if they aren't being recorded
+        pub fn get_contacts(&self) -> Option<&ContactRecorder> {
+            return self.contacts.as_ref();
+        }
+        
+        /// Also measure the density and speed in each measurement area with the Voronoi method, which is much less noisy than counting pedestrians when there are few of them.
+        /// 
+        /// * `cutoff_radius` - The furthest a pedestrian's Voronoi cell can reach, in metres, so that isolated pedestrians don't get huge cells
+        pub fn enable_voronoi_density(&mut self, cutoff_radius: f64) {
+            self.voronoi_cutoff = Some(cutoff_radius);
+        }
+        
+        /// Only let pedestrians see the neighbours within a cutoff distance, found with Verlet neighbour lists that are only rebuilt once someone has moved far enough to change them.
+        /// This makes large crowds much faster to simulate.
+        /// 
+        /// The lists can also hold some pedestrians up to `skin` beyond the cutoff, depending on when they were last rebuilt, so the results are only the same as without the lists
+        /// if the cutoff is at least the furthest a pedestrian reacts to its neighbours. That is the largest of:
+        /// * the `look_ahead_radius`, `look_beside_radius` and `pspace_radius` parameters (including any per-pedestrian variation, luggage or physical distancing),
+        /// * the `look_ahead_time` parameter times the fastest walking speed,
+        /// * the combined radii of two pedestrians plus twice the fastest walking speed times the `anticipation_horizon` parameter, for pedestrians closing on each other head-on,
+        /// * the herding radius, if pedestrians are herding.
+        /// 
+        /// With a smaller cutoff, some reactions depend on when the lists were rebuilt and the results differ.
+        /// Pedestrians also use their neighbours to judge how congested each exit is (see `ExitChoice::LeastCongested`) and to give way at junctions, and they won't see anyone beyond the cutoff for those.
+        /// 
+        /// * `cutoff` - The furthest a pedestrian can see its neighbours, in metres
+        /// * `skin` - The extra margin kept in the lists beyond the cutoff, in metres. A larger skin means fewer rebuilds but longer lists.
+        pub fn enable_neighbour_lists(&mut self, cutoff: f64, skin: f64) {
+            self.neighbour_lists = Some(NeighbourLists::new(cutoff, skin));
+        }
+        
+        /// Compare every pair of pedestrians on the GPU each timestep instead of the CPU, for very large crowds (see `GpuNeighbours`).
+        /// This takes the place of neighbour lists.
+        /// 
+        /// Pedestrians only see the neighbours within the cutoff, and no more than the closest 64 of them (MAX_GPU_NEIGHBOURS in the `gpu` module),
+        /// so in a dense crowd they can miss neighbours they would have reacted to on the CPU.
+        /// The neighbours they do see are passed to them in the order of the simulation's list, as on the CPU, so `NeighbourOrder` applies the same way.
+        /// The contact impulses of the granular contact model are worked out on the GPU too, from every neighbour a pedestrian overlaps.
+        /// These use each pedestrian's walking velocity from the start of the timestep, without the push velocity it already has,
+        /// whereas `Walker::apply_contact_forces` uses its velocity as the earlier steering rules left it, so sliding friction differs slightly.
+        /// 
+        /// Return an error if there is no GPU to use.
+        /// 
+        /// * `cutoff` - The furthest a pedestrian can see its neighbours, in metres
+        #[cfg(feature = "gpu")]
+        pub fn enable_gpu_neighbours(&mut self, cutoff: f64) -> Result<(), String> {
+            self.gpu_neighbours = Some(GpuNeighbours::new(cutoff)?);
+            return Ok(());
+        }
+        
+        /// Neighbours can only be found on the GPU with the `gpu` feature
+        #[cfg(not(feature = "gpu"))]
+        pub fn enable_gpu_neighbours(&mut self, _cutoff: f64) -> Result<(), String> {
+            return Err(String::from("Finding neighbours on the GPU needs the gpu feature"));
+        }
+        
+        /// Return the number of times the neighbour lists have been built (None = not using neighbour lists)
+        pub fn get_neighbour_list_builds(&self) -> Option<usize> {
+            return self.neighbour_lists.as_ref().map(|lists| lists.get_build_count());
+        }
+        
+        /// Time each phase of every timestep from now on, to find out where the simulation spends its time
+        /// 
+        /// Timing every pedestrian separately adds a little overhead, so this is off by default.
+        pub fn enable_profiling(&mut self) {
+            self.profiler = Some(Profiler::new());
+        }
+        
+        /// Return the time spent in each phase of the timesteps so far (None = not profiling)
+        pub fn get_profiler(&self) -> Option<&Profiler> {
+            return self.profiler.as_ref();
+        }
+        
+        /// Return the parameters of the pedestrian model
+        pub fn get_params(&self) -> &SimParams {
+            return &self.params;
+        }
+        
+        /// Randomise the order of the pedestrians
+        pub fn randomise_pedestrian_order(&mut self) {
+            self.available_pedestrians.shuffle(&mut self.rng);
+        }
+        
+        /// Seed the simulation's randomness, so that the run can be repeated exactly.
+        /// 
+        /// This affects everything random from now on, so it should be called before any pedestrians are added.
+        /// The arrival process has its own seed (see `ArrivalProcess`).
+        pub fn set_seed(&mut self, seed: u64) {
+            self.rng = ChaCha8Rng::seed_from_u64(seed);
+            self.seed = Some(seed);
+        }
+        
+        /// Return the seed given to `set_seed` (None = the simulation was seeded from entropy, so it can't be repeated)
+        pub fn get_seed(&self) -> Option<u64> {
+            return self.seed;
+        }
+        
+        /// Save the full state of the simulation, so that it can be resumed later with `restore_checkpoint`
+        pub fn create_checkpoint(&self) -> Checkpoint {
+            let save = |ped: &pedestrian::Walker| WalkerCheckpoint {
+                behaviour: ped.get_behaviour().name().to_string(),
+                walker: ped.clone()
+            };
+            
+            return Checkpoint {
+                version: CHECKPOINT_VERSION,
+                time_elapsed: self.time_elapsed,
+                pedestrian_add_rate: self.pedestrian_add_rate,
+                arrival_clock: self.arrival_clock,
+                arrival_process: self.arrival_process.name().to_string(),
+                arrival_state: self.arrival_process.save_state(),
+                next_pedestrian_id: self.next_pedestrian_id,
+                rng: RngState::new(&self.rng),
+                available_pedestrians: self.available_pedestrians.iter().map(save).collect(),
+                scheduled_pedestrians: self.scheduled_pedestrians.iter().map(|(time, ped)| (*time, save(ped))).collect(),
+                held_pedestrians: self.held_pedestrians.iter().map(save).collect(),
+                active_pedestrians: self.active_pedestrians.iter().map(save).collect(),
+                finished_pedestrians: self.finished_pedestrians.iter().map(save).collect(),
+                travel_times: self.travel_times.clone(),
+                timing_events: self.timing_events.clone(),
+                stall_events: self.stall_events.clone(),
+                travel_time_cohorts: self.travel_time_cohorts.clone(),
+                travel_time_behaviours: self.travel_time_behaviours.clone(),
+                cohort_names: self.cohort_names.clone(),
+                jitter_results: self.jitter_results.clone(),
+                junction_delays: self.junction_delays.clone(),
+                behaviour_switch_log: self.behaviour_switch_log.clone(),
+                spawn_queue_lengths: self.spawn_queue_lengths.clone(),
+                door_states: self.door_states.clone(),
+                fundamental_diagrams: self.fundamental_diagrams.clone(),
+                line_crossings: self.line_crossings.clone(),
+                evacuation_time: self.evacuation_results.as_ref().and(self.evacuation_time),
+                evacuation_results: self.evacuation_results.clone()
+            };
+        }
+        
+        /// Replace the pedestrians, results and random state of the simulation with those saved in a checkpoint, so that the run continues from the moment it was taken.
+        /// 
+        /// Everything else is kept from this simulation, so a checkpoint can be restored into a simulation set up with different parameters to re-run the moment with them.
+        /// The simulation should have the same environment as the one the checkpoint was taken from, and its pedestrians (or behaviour switches) must include every behaviour model named in the checkpoint, since the models themselves aren't saved.
+        /// Behaviour switches scheduled before the checkpoint are dropped, since they have already happened. An evacuation that hadn't started by the checkpoint keeps this simulation's schedule.
+        /// The state of the arrival process is only restored if this simulation uses the same kind of process; a different one starts afresh.
+        pub fn restore_checkpoint(&mut self, checkpoint: Checkpoint) -> io::Result<()> {
+            if checkpoint.fundamental_diagrams.len() != self.area.measurement_areas.len() || checkpoint.line_crossings.len() != self.area.counting_lines.len() || checkpoint.door_states.len() != self.area.doors.len() {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "The checkpoint was taken in an environment with different measurement areas, counting lines or doors"));
+            }
+            
+            // Behaviour models can only be matched up by name, to the models this simulation already uses
+            let behaviours = self.available_pedestrians.iter()
+                .chain(self.scheduled_pedestrians.iter().map(|(_, ped)| ped))
+                .chain(self.held_pedestrians.iter())
+                .chain(self.active_pedestrians.iter())
+                .chain(self.finished_pedestrians.iter())
+                .map(|ped| ped.get_behaviour().clone())
+                .chain(self.behaviour_switches.iter().map(|switch| switch.to.clone()))
+                .collect::<Vec<_>>();
+            
+            let restore = |saved: WalkerCheckpoint, navigation: Option<Navigation>| -> io::Result<pedestrian::Walker> {
+                let behaviour = behaviours.iter().find(|behaviour| behaviour.name() == saved.behaviour)
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("Unknown behaviour model \"{}\" in checkpoint", saved.behaviour)))?;
+                let mut ped = saved.walker;
+                ped.attach(self.area.clone(), behaviour.clone(), navigation);
+                return Ok(ped);
+            };
+            
+            // Only pedestrians that have started walking have been given navigation
+            let available_pedestrians = checkpoint.available_pedestrians.into_iter().map(|ped| restore(ped, None)).collect::<io::Result<Vec<_>>>()?;
+            let scheduled_pedestrians = checkpoint.scheduled_pedestrians.into_iter().map(|(time, ped)| Ok((time, restore(ped, None)?))).collect::<io::Result<Vec<_>>>()?;
+            let held_pedestrians = checkpoint.held_pedestrians.into_iter().map(|ped| restore(ped, None)).collect::<io::Result<Vec<_>>>()?;
+            let active_pedestrians = checkpoint.active_pedestrians.into_iter().map(|ped| restore(ped, self.navigation.clone())).collect::<io::Result<Vec<_>>>()?;
+            let finished_pedestrians = checkpoint.finished_pedestrians.into_iter().map(|ped| restore(ped, self.navigation.clone())).collect::<io::Result<Vec<_>>>()?;
+            
+            self.available_pedestrians = available_pedestrians;
+            self.scheduled_pedestrians = scheduled_pedestrians;
+            self.held_pedestrians = held_pedestrians;
+            self.active_pedestrians = active_pedestrians;
+            self.finished_pedestrians = finished_pedestrians;
+            
+            self.time_elapsed = checkpoint.time_elapsed;
+            self.pedestrian_add_rate = checkpoint.pedestrian_add_rate;
+            self.arrival_clock = checkpoint.arrival_clock;
+            if checkpoint.arrival_process == self.arrival_process.name() {
+                self.arrival_process.restore_state(&checkpoint.arrival_state);
+            }
+            self.next_pedestrian_id = checkpoint.next_pedestrian_id;
+            self.rng = checkpoint.rng.restore();
+            
+            self.travel_times = checkpoint.travel_times;
+            self.timing_events = checkpoint.timing_events;
+            self.stall_events = checkpoint.stall_events;
+            self.travel_time_cohorts = checkpoint.travel_time_cohorts;
+            self.travel_time_behaviours = checkpoint.travel_time_behaviours;
+            self.cohort_names = checkpoint.cohort_names;
+            self.jitter_results = checkpoint.jitter_results;
+            self.junction_delays = checkpoint.junction_delays;
+            self.behaviour_switch_log = checkpoint.behaviour_switch_log;
+            self.spawn_queue_lengths = checkpoint.spawn_queue_lengths;
+            self.door_states = checkpoint.door_states;
+            self.fundamental_diagrams = checkpoint.fundamental_diagrams;
+            self.line_crossings = checkpoint.line_crossings;
+            
+            self.behaviour_switches.retain(|switch| switch.time > checkpoint.time_elapsed);
+            self.events.retain(|event| event.time > checkpoint.time_elapsed);
+            
+            if checkpoint.evacuation_results.is_some() {
+                self.evacuation_time = checkpoint.evacuation_time;
+                self.evacuation_results = checkpoint.evacuation_results;
+            }
+            
+            return Ok(());
+        }
+        
+        /// Simulate a small period of time.
+        /// 
+        /// If the period is longer than the `max_timestep` parameter, it is divided into equal sub-steps that are each simulated in turn (observers are called after every sub-step).
+        /// 
+        /// * `time_scale` - The amount of time (in seconds) that passes during each timestep
+        pub fn simulate_timestep(&mut self, time_scale: f64) {
+            let max_timestep = self.params.max_timestep;
+            if max_timestep <= 0.0 || time_scale <= max_timestep {
+                self.simulate_substep(time_scale);
+                return;
+            }
+            
+            // Allow for rounding error, so that e.g. 0.06s is divided into 3 steps of 0.02s rather than 4
+            let substeps = ((time_scale / max_timestep) - 1e-9).ceil() as usize;
+            for _ in 0..substeps {
+                self.simulate_substep(time_scale / (substeps as f64));
+            }
+        }
+        
+        /// Simulate a small period of time in a single step, however long it is
+        /// 
+        /// * `time_scale` - The amount of time (in seconds) that passes during the step
+        fn simulate_substep(&mut self, time_scale: f64) {
+            //println!("Simulating one timestep...");
+            
+            if let Some(profiler) = &mut self.profiler {
+                profiler.start_timestep();
+            }
+            
+            self.update_events();
+            
+            self.update_behaviour_switches();
+            
+            self.update_doors(time_scale);
+            
+            self.update_queues(time_scale);
+            
+            self.update_gates(time_scale);
+            
+            self.update_vehicles(time_scale);
+            
+            self.update_evacuation();
+            
+            self.update_active();
+            
+            self.update_conversations();
+            
+            if let Some(profiler) = &mut self.profiler {
+                profiler.lap(Phase::Activation);
+            }
+            
+            // Collect the position and facing direction of every pedestrian to pass to Walker.simulate_timestep(), so that a pedestrian can see its neighbours.
+            // This is an ugly way to do this, but I don't have time to implement a "nice" way right now.
+            // The positions are collected once, before any pedestrian moves, so every pedestrian reacts to where its neighbours were at the start of the timestep
+            // (the update is synchronous). Only the order it reacts to them in depends on the order of the list - see NeighbourOrder.
+            // (x, y, direction, radius, speed)
+            let mut pedestrian_positions = std::mem::take(&mut self.position_buffer);
+            pedestrian_positions.clear();
+            pedestrian_positions.extend(self.active_pedestrians.iter().map(|ped| {
+                let direction = match self.intent_horizon {
+                    Some(horizon) => ped.get_intended_direction(horizon),
+                    None => ped.facing_direction
+                };
+                (ped.x, ped.y, direction, ped.get_params().radius, ped.inst_speed)
+            }));
+            
+            // The space taken up by each pedestrian's luggage, also from the start of the timestep: (pedestrian ID, one end, the other end, radius)
+            let luggage_capsules = self.active_pedestrians.iter()
+                .filter_map(|ped| ped.get_luggage_capsule().map(|(a, b, radius)| (ped.get_id(), a, b, radius)))
+                .collect::<Vec<_>>();
+            
+            // The space taken up by each vehicle, and the path ahead of it that pedestrians step out of: (body, path)
+            let vehicle_capsules = self.vehicle_traffic.get_vehicles().iter()
+                .map(|vehicle| (vehicle.get_body(), vehicle.get_path()))
+                .collect::<Vec<_>>();
+            
+            if let Some(neighbour_lists) = &mut self.neighbour_lists {
+                neighbour_lists.update(self.active_pedestrians.iter().map(|ped| ped.get_id()), &pedestrian_positions);
+            }
+            #[cfg(feature = "gpu")]
+            if let Some(gpu_neighbours) = &mut self.gpu_neighbours {
+                let contact_params = self.active_pedestrians.iter().map(|ped| (ped.get_params().contact_stiffness, ped.get_params().contact_friction));
+                gpu_neighbours.update(&pedestrian_positions, contact_params, time_scale);
+            }
+            if let Some(profiler) = &mut self.profiler {
+                profiler.lap(Phase::NeighbourSearch);
+            }
+            
+            // The neighbours each pedestrian can see, if walls block its view
+            let mut visible_before = Vec::new();
+            let mut visible_after = Vec::new();
+            
+            for (i, ped) in self.active_pedestrians.iter_mut().enumerate() {
+                let (mut before, mut after) = match &mut self.neighbour_lists {
+                    Some(neighbour_lists) => neighbour_lists.get_neighbours(ped.get_id(), &pedestrian_positions),
+                    None => (&pedestrian_positions[0..i], &pedestrian_positions[i+1..])
+                };
+                #[cfg(feature = "gpu")]
+                if let Some(gpu_neighbours) = &mut self.gpu_neighbours {
+                    ped.set_contact_impulse(Some(gpu_neighbours.get_contact_impulse(i)));
+                    (before, after) = gpu_neighbours.get_neighbours(i, &pedestrian_positions);
+                }
+                if ped.get_params().wall_occlusion {
+                    let position = Vec2::new(ped.x, ped.y);
+                    let in_sight = |neighbour: &&(f64, f64, f64, f64, f64)| self.area.is_in_sight(position, Vec2::new(neighbour.0, neighbour.1), &self.door_states);
+                    visible_before.clear();
+                    visible_before.extend(before.iter().filter(in_sight));
+                    visible_after.clear();
+                    visible_after.extend(after.iter().filter(in_sight));
+                    before = &visible_before;
+                    after = &visible_after;
+                }
+                if let Some(profiler) = &mut self.profiler {
+                    profiler.lap(Phase::NeighbourSearch);
+                }
+                
+                let start = ped.move_freely(time_scale, &mut self.rng, &self.door_states, before, after);
+                if !luggage_capsules.is_empty() {
+                    ped.react_to_luggage(time_scale, &luggage_capsules, &self.door_states, before, after);
+                }
+                if !vehicle_capsules.is_empty() {
+                    ped.react_to_vehicles(time_scale, &vehicle_capsules);
+                }
+                if !self.area.hazards.is_empty() && ped.react_to_hazards(time_scale, self.time_elapsed) {
+                    self.hazard_casualties.push(HazardCasualty {
+                        pedestrian_id: ped.get_id(),
+                        group: ped.get_group(),
+                        position: (ped.x, ped.y),
+                        sim_time: self.time_elapsed
+                    });
+                }
+                if let Some(profiler) = &mut self.profiler {
+                    profiler.lap(Phase::Behaviour);
+                }
+                
+                ped.finish_movement(start, time_scale, &self.door_states);
+                if let Some(profiler) = &mut self.profiler {
+                    profiler.lap(Phase::WallResolution);
+                }
+                
+                let (old_x, old_y, _, _, _) = pedestrian_positions[i];
+                for (line, crossings) in self.area.counting_lines.iter().zip(&mut self.line_crossings) {
+                    if let Some(direction) = line.get_crossing(Vec2::new(old_x, old_y), Vec2::new(ped.x, ped.y)) {
+                        crossings.push((self.time_elapsed + time_scale, direction));
+                    }
+                }
+                
+                if ped.check_stalled(time_scale, &mut self.rng) {
+                    let event = StallEvent {
+                        pedestrian_id: ped.get_id(),
+                        group: ped.get_group(),
+                        position: (ped.x, ped.y),
+                        sim_time: self.time_elapsed,
+                        recovery: ped.get_params().stall_recovery
+                    };
+                    for observer in &mut self.observers {
+                        observer.on_pedestrian_stalled(&event, ped);
+                    }
+                    self.stall_events.push(event);
+                }
+                
+                if let Some(travel_time) = ped.check_timing_boundaries(time_scale) {
+                    self.travel_times.push((travel_time, ped.get_group(), self.time_elapsed));
+                    let event = TimingEvent {
+                        pedestrian_id: ped.get_id(),
+                        group: ped.get_group(),
+                        elapsed: travel_time,
+                        sim_time: self.time_elapsed
+                    };
+                    for observer in &mut self.observers {
+                        observer.on_timing_boundary_crossed(&event, ped);
+                    }
+                    self.timing_events.push(event);
+                    self.travel_time_cohorts.push(ped.get_cohorts().to_vec());
+                    self.travel_time_behaviours.push(ped.get_behaviour().name().to_string());
+                }
+                
+                if let Some(profiler) = &mut self.profiler {
+                    profiler.lap(Phase::Recording);
+                }
+            }
+            self.position_buffer = pedestrian_positions;
+            
+            if let Some(density_grid) = &mut self.density_grid {
+                density_grid.record(self.active_pedestrians.iter().map(|ped| (ped.x, ped.y)), time_scale);
+            }
+            
+            if let Some(contacts) = &mut self.contacts {
+                contacts.record(self.time_elapsed, time_scale, &self.active_pedestrians);
+            }
+            
+            self.update_measurement_areas();
+            
+            self.time_elapsed += time_scale;
+            
+            if let Some(trajectories) = &mut self.trajectories {
+                trajectories.record(self.time_elapsed, self.active_pedestrians.iter());
+            }
+            
+            if let Some(pressure) = &mut self.pressure {
+                pressure.record(self.time_elapsed, &self.active_pedestrians);
+            }
+            
+            if let Some(profiler) = &mut self.profiler {
+                profiler.lap(Phase::Recording);
+            }
+            
+            self.update_boardings(time_scale);
+            
+            self.update_finished();
+            
+            self.update_exit_queues();
+            
+            if let Some(profiler) = &mut self.profiler {
+                profiler.lap(Phase::FinishChecks);
+            }
+            
+            // The observers are taken out while they are called, so that they can see the whole simulation
+            let mut observers = std::mem::take(&mut self.observers);
+            for observer in &mut observers {
+                observer.on_timestep_completed(self);
+            }
+            self.observers = observers;
+            
+            if let Some(profiler) = &mut self.profiler {
+                profiler.lap(Phase::Recording);
+            }
+            
+        }
+        
+        /// Register an observer to receive callbacks as things happen in the simulation, from now on
+        pub fn add_observer(&mut self, observer: Box<dyn Observer>) {
+            self.observers.push(observer);
+        }
+        
+        /// Run the simulation until all pedestrians have finished (or a limit set with `set_run_limits` is reached), returning timing results.
+        /// How the run ended can be checked with `get_run_status`.
+        /// 
+        /// Return format: (total time, pedestrian count, \[(travel time, group, finish time)])
+        pub fn simulate_full(&mut self, time_scale: f64) -> (f64, usize, &[(f64, usize, f64)]) {
+            return self.simulate_full_with_progress(time_scale, None, |_| {});
+        }
+        
+        /// Run the simulation like `simulate_full`, reporting progress every PROGRESS_INTERVAL of simulation time (and at the end), and stopping early if it is cancelled.
+        /// A cancelled run returns the results so far, and `get_run_status` returns `RunStatus::Cancelled`.
+        /// 
+        /// * `cancel` - A token that stops the run at the end of the current timestep once it is cancelled (None = can't be cancelled)
+        /// * `on_progress` - Called with the progress so far
+        pub fn simulate_full_with_progress<F: FnMut(RunProgress)>(&mut self, time_scale: f64, cancel: Option<&CancelToken>, mut on_progress: F) -> (f64, usize, &[(f64, usize, f64)]) {
+            let start = Instant::now();
+            let mut next_report_time = self.time_elapsed;
+            let (max_sim_time, max_wall_time) = self.run_limits;
+            // Pedestrians walking cyclic routes never finish, so without a limit they are stopped after a default time
+            let max_sim_time = match (max_sim_time, self.recirculation) {
+                (None, Some(_)) => Some(self.time_elapsed + DEFAULT_RECIRCULATION_TIME),
+                _ => max_sim_time
+            };
+            
+            self.run_status = RunStatus::Completed;
+            // Scripted bursts of pedestrians that are yet to happen keep the run going too
+            while self.available_pedestrians.len() + self.scheduled_pedestrians.len() + self.held_pedestrians.len() + self.active_pedestrians.len() > 0
+                || self.events.iter().any(|event| matches!(event.action, EventAction::SpawnBurst {..})) {
+                if max_sim_time.is_some_and(|limit| self.time_elapsed >= limit) {
+                    // Pedestrians walking cyclic routes are meant to be stopped by the time limit
+                    if self.recirculation.is_none() {
+                        self.run_status = RunStatus::SimTimeLimit;
+                    }
+                    break;
+                }
+                if max_wall_time.is_some_and(|limit| start.elapsed().as_secs_f64() >= limit) {
+                    self.run_status = RunStatus::WallClockLimit;
+                    break;
+                }
+                if cancel.is_some_and(|token| token.is_cancelled()) {
+                    self.run_status = RunStatus::Cancelled;
+                    break;
+                }
+                
+                if self.time_elapsed >= next_report_time {
+                    on_progress(self.get_run_progress());
+                    next_report_time += PROGRESS_INTERVAL;
+                }
+                
+                self.simulate_timestep(time_scale);
+            }
+            on_progress(self.get_run_progress());
+            
+            return (self.time_elapsed, self.finished_pedestrians.len(), &self.travel_times);
+            
+        }
+        
+        /// Add pedestrians to the simulation in bulk
+        /// 
+        /// * `behaviour` - The behaviour model shared by every pedestrian in the set
+        pub fn add_pedestrian_set(&mut self, number: usize, group: usize, behaviour: Arc<dyn BehaviourModel>) {
+            
+            for _ in 0..number {
+                let start = self.rng.sample(Uniform::new(0,self.area.start_positions[group].len()));
+                let end = self.rng.sample(Uniform::new(0,self.area.end_positions[group].len()));
+                let target_speed = self.sample_target_speed();
+                let ped = self.create_pedestrian((group, start), (group, end), target_speed, behaviour.clone());
+                self.available_pedestrians.push(ped);
+            }
+            
+        }
+        
+        /// Add pedestrians to the simulation in bulk, with target speeds sampled from a given distribution instead of the usual one (or the demographic classes).
+        /// 
+        /// The speeds are drawn from the simulation's random number generator, so they are repeated by `set_seed`.
+        /// 
+        /// * `speeds` - The distribution of the target speeds, in m/s (never below `minimum_speed`)
+        /// * `behaviour` - The behaviour model shared by every pedestrian in the set
+        pub fn add_pedestrian_set_with_speeds(&mut self, number: usize, group: usize, speeds: ParamDistribution, behaviour: Arc<dyn BehaviourModel>) {
+            
+            for _ in 0..number {
+                let start = self.rng.sample(Uniform::new(0,self.area.start_positions[group].len()));
+                let end = self.rng.sample(Uniform::new(0,self.area.end_positions[group].len()));
+                let target_speed = speeds.sample(&mut self.rng).max(self.params.minimum_speed);
+                let ped = self.create_pedestrian((group, start), (group, end), (target_speed, None), behaviour.clone());
+                self.available_pedestrians.push(ped);
+            }
+            
+        }
+        
+        /// Add a new pedestrian to the simulation
+        pub fn add_pedestrian(&mut self, group: usize, start: usize, end: usize, target_speed: f64, behaviour: Arc<dyn BehaviourModel>) {
+            let ped = self.create_pedestrian((group, start), (group, end), (target_speed, None), behaviour);
+            self.available_pedestrians.push(ped);
+        }
+        
+        /// Create a new pedestrian walking between the given start and end positions (each a group ID and position index), with a target speed and the demographic class it was sampled for
+        fn create_pedestrian(&mut self, start: (usize, usize), end: (usize, usize), target_speed: (f64, Option<usize>), behaviour: Arc<dyn BehaviourModel>) -> pedestrian::Walker {
+            let (target_speed, demographic) = target_speed;
+            let (behaviour, mut params) = self.sample_pedestrian(behaviour, demographic);
+            
+            let distancing = self.distancing.filter(|distancing| self.rng.gen::<f64>() < distancing.compliance);
+            if let Some(distancing) = distancing {
+                params = distancing.apply(params);
+            }
+            
+            let mut ped = pedestrian::Walker::new(self.area.clone(), start, end, target_speed, behaviour, params, &mut self.rng);
+            if let Some(index) = demographic {
+                ped.add_to_cohort(&self.demographics[index].name);
+            }
+            if distancing.is_some() {
+                ped.add_to_cohort(DISTANCING_COHORT);
+            }
+            
+            if !self.luggage.is_empty() {
+                let mut choice = self.rng.gen::<f64>();
+                for item in &self.luggage {
+                    if choice < item.fraction {
+                        ped.set_luggage(item.clone());
+                        ped.add_to_cohort(&item.name);
+                        break;
+                    }
+                    choice -= item.fraction;
+                }
+            }
+            
+            if let Some(herding) = self.herding {
+                if self.rng.gen::<f64>() >= herding.familiarity {
+                    ped.set_herding(Some(herding));
+                    ped.add_to_cohort(UNFAMILIAR_COHORT);
+                    // Where there are signs, pedestrians that don't know their way only pick up waypoints from them
+                    if !self.area.signs.is_empty() {
+                        ped.set_waypoints(Vec::new());
+                    }
+                }
+            }
+            return self.assign_id(ped);
+        }
+        
+        /// Give a new pedestrian the next unused ID
+        fn assign_id(&mut self, mut ped: pedestrian::Walker) -> pedestrian::Walker {
+            ped.set_id(self.next_pedestrian_id);
+            self.next_pedestrian_id += 1;
+            return ped;
+        }
+        
+        /// Add a group of pedestrians that are all released at once, such as passengers alighting from a train
+        /// 
+        /// * `time` - The simulation time at which the pedestrians are released, in seconds
+        /// * `behaviour` - The behaviour model shared by every pedestrian in the pulse
+        pub fn add_pedestrian_pulse(&mut self, time: f64, number: usize, group: usize, behaviour: Arc<dyn BehaviourModel>) {
+            
+            for _ in 0..number {
+                let ped = self.new_group_pedestrian(group, behaviour.clone());
+                self.schedule_pedestrian(time, ped);
+            }
+            
+        }
+        
+        /// Add a group of pedestrians that are all released at once, divided evenly between the group's start positions, such as passengers alighting from every door of a train.
+        /// 
+        /// Unlike `add_pedestrian_pulse`, which starts each pedestrian at a random start position, every start position gets the same number of pedestrians (give or take one).
+        /// Turn on the spawn gate (see `set_spawn_gate`) to have them step out as there is room, instead of starting on top of each other.
+        /// 
+        /// * `time` - The simulation time at which the pedestrians are released, in seconds
+        /// * `behaviour` - The behaviour model shared by every pedestrian in the burst
+        pub fn add_pedestrian_burst(&mut self, time: f64, number: usize, group: usize, behaviour: Arc<dyn BehaviourModel>) {
+            let start_count = self.area.start_positions[group].len();
+            
+            for i in 0..number {
+                let end = self.rng.sample(Uniform::new(0,self.area.end_positions[group].len()));
+                let target_speed = self.sample_target_speed();
+                let ped = self.create_pedestrian((group, i % start_count), (group, end), target_speed, behaviour.clone());
+                self.schedule_pedestrian(time, ped);
+            }
+            
+        }
+        
+        /// Add a group of pedestrians that are all released at once, and each walk to a random point in a zone and stand still there for a while.
+        /// 
+        /// Afterwards they either carry on to their group's end positions, or leave the simulation where they stand (e.g. waiting passengers boarding a train).
+        /// 
+        /// * `time` - The simulation time at which the pedestrians are released, in seconds
+        /// * `standing_zone` - Where the pedestrians stand
+        /// * `dwell` - (duration, after): How long each pedestrian stands still for once it reaches its place (in seconds), and what it does afterwards
+        /// * `behaviour` - The behaviour model shared by every pedestrian
+        pub fn add_standing_pedestrians(&mut self, time: f64, number: usize, group: usize, standing_zone: Zone, dwell: (f64, AfterDwell), behaviour: Arc<dyn BehaviourModel>) {
+            let (duration, after) = dwell;
+            
+            for _ in 0..number {
+                let location = standing_zone.get_point((self.rng.gen(), self.rng.gen()));
+                let mut ped = self.new_group_pedestrian(group, behaviour.clone());
+                ped.set_dwell(Dwell::new(location.into(), duration, after));
+                self.schedule_pedestrian(time, ped);
+            }
+            
+        }
+        
+        /// Create a pedestrian at a random start position of a group, heading for a random end position of the same group
+        fn new_group_pedestrian(&mut self, group: usize, behaviour: Arc<dyn BehaviourModel>) -> pedestrian::Walker {
+            let start = self.rng.sample(Uniform::new(0,self.area.start_positions[group].len()));
+            let end = self.rng.sample(Uniform::new(0,self.area.end_positions[group].len()));
+            let target_speed = self.sample_target_speed();
+            
+            return self.create_pedestrian((group, start), (group, end), target_speed, behaviour);
+        }
+        
+        /// Release a pedestrian at a given simulation time, keeping the schedule in order of release time
+        fn schedule_pedestrian(&mut self, time: f64, ped: pedestrian::Walker) {
+            let index = self.scheduled_pedestrians.partition_point(|(t, _)| *t <= time);
+            self.scheduled_pedestrians.insert(index, (time, ped));
+        }
+        
+        /// Add the pedestrians travelling between a specific origin and destination, one entry of an origin-destination matrix.
+        /// 
+        /// This allows places with several entrances and exits, such as stations, to be described by the demand between each pair.
+        /// The pedestrians start at the origin group's start positions and walk to the destination group's end positions, and their results are recorded under the origin group.
+        /// 
+        /// * `origin_group` - The group whose start positions the pedestrians start at
+        /// * `destination_group` - The group whose end positions the pedestrians walk to
+        /// * `behaviour` - The behaviour model shared by every pedestrian
+        pub fn add_od_demand(&mut self, origin_group: usize, destination_group: usize, demand: OdDemand, behaviour: Arc<dyn BehaviourModel>) {
+            
+            match demand {
+                OdDemand::Count(number) => {
+                    for _ in 0..number {
+                        let ped = self.new_od_pedestrian(origin_group, destination_group, behaviour.clone());
+                        self.available_pedestrians.push(ped);
+                    }
+                },
+                OdDemand::Rate {rate, start_time, end_time} => {
+                    let mut time = start_time + self.arrival_process.sample_gap() / rate;
+                    while time <= end_time {
+                        let ped = self.new_od_pedestrian(origin_group, destination_group, behaviour.clone());
+                        
+                        // Keep the schedule in order of release time
+                        let index = self.scheduled_pedestrians.partition_point(|(t, _)| *t <= time);
+                        self.scheduled_pedestrians.insert(index, (time, ped));
+                        
+                        time += self.arrival_process.sample_gap() / rate;
+                    }
+                }
+            }
+            
+        }
+        
+        /// Create a pedestrian at a random start position of one group, heading for a random end position of another
+        fn new_od_pedestrian(&mut self, origin_group: usize, destination_group: usize, behaviour: Arc<dyn BehaviourModel>) -> pedestrian::Walker {
+            let start = self.rng.sample(Uniform::new(0,self.area.start_positions[origin_group].len()));
+            let end = self.rng.sample(Uniform::new(0,self.area.end_positions[destination_group].len()));
+            let target_speed = self.sample_target_speed();
+            
+            return self.create_pedestrian((origin_group, start), (destination_group, end), target_speed, behaviour);
+        }
+        
+        /// Tag every pedestrian that hasn't started walking yet and matches a filter with a named cohort, so that its results can be picked out later with `get_cohort_results`.
+        /// 
+        /// Cohorts can overlap: a pedestrian can be in any number of them. Call this after adding the pedestrians to tag.
+        /// 
+        /// * `filter` - Decides whether a pedestrian belongs to the cohort, e.g. by its group, behaviour model, target speed or parameters
+        /// 
+        /// Returns the number of pedestrians tagged.
+        pub fn tag_cohort<F: Fn(&pedestrian::Walker) -> bool>(&mut self, cohort: &str, filter: F) -> usize {
+            let mut tagged_count = 0;
+            for ped in self.available_pedestrians.iter_mut().chain(self.scheduled_pedestrians.iter_mut().map(|(_, ped)| ped)).chain(self.held_pedestrians.iter_mut()) {
+                if filter(ped) {
+                    ped.add_to_cohort(cohort);
+                    tagged_count += 1;
+                }
+            }
+            
+            if !self.cohort_names.iter().any(|name| name == cohort) {
+                self.cohort_names.push(cohort.to_string());
+            }
+            
+            return tagged_count;
+        }
+        
+        /// Return the names of every cohort pedestrians have been tagged with
+        pub fn get_cohort_names(&self) -> &[String] {
+            return &self.cohort_names;
+        }
+        
+        /// Return the results of only the pedestrians in a cohort
+        pub fn get_cohort_results(&self, cohort: &str) -> CohortResults {
+            let travel_times = self.travel_times.iter().zip(&self.travel_time_cohorts)
+                .filter(|(_, cohorts)| cohorts.iter().any(|name| name == cohort))
+                .map(|(travel_time, _)| *travel_time)
+                .collect();
+            
+            // Jitter and junction delays are recorded as pedestrians finish, so they are in the same order as the finished pedestrians
+            let finished = self.finished_pedestrians.iter().filter(|ped| ped.in_cohort(cohort));
+            
+            return CohortResults {
+                finished_count: finished.clone().count(),
+                travel_times,
+                jitter_results: finished.clone().filter_map(|ped| ped.get_jitter().map(|jitter| (jitter, ped.get_group()))).collect(),
+                junction_delays: finished.map(|ped| (ped.get_junction_delay(), ped.get_group())).collect()
+            };
+        }
+        
+        /// Return the cohorts of the pedestrian behind each travel time, in the same order as `get_travel_times`
+        pub fn get_travel_time_cohorts(&self) -> &[Vec<String>] {
+            return &self.travel_time_cohorts;
+        }
+        
+        /// Return the name of the behaviour model followed by the pedestrian behind each travel time when it was timed, in the same order as `get_travel_times`
+        pub fn get_travel_time_behaviours(&self) -> &[String] {
+            return &self.travel_time_behaviours;
+        }
+        
+        /// Switch the behaviour model of some or all pedestrians at a given time, so that the effect of a rule change can be observed within a single run.
+        /// 
+        /// The switch applies to every pedestrian in the simulation, including those yet to be added, and is recorded in the behaviour switch log when it happens.
+        /// 
+        /// * `time` - The simulation time at which the switch happens, in seconds
+        /// * `from` - Only switch pedestrians following a model with this name (None = all pedestrians)
+        /// * `to` - The model that switched pedestrians follow afterwards
+        pub fn schedule_behaviour_switch(&mut self, time: f64, from: Option<&str>, to: Arc<dyn BehaviourModel>) {
+            let index = self.behaviour_switches.partition_point(|switch| switch.time <= time);
+            self.behaviour_switches.insert(index, BehaviourSwitch {
+                time,
+                from: from.map(|name| name.to_string()),
+                to
+            });
+        }
+        
+        /// Make pairs of pedestrians that know each other stop to chat for a while when they meet walking in opposite directions, from now on (None = never).
+        /// 
+        /// Every pair of active pedestrians is checked at each timestep, so this is slow with very many pedestrians.
+        pub fn set_conversations(&mut self, conversations: Option<Conversations>) {
+            self.conversations = conversations;
+        }
+        
+        /// Return every conversation that has started, in order
+        pub fn get_conversation_log(&self) -> &[Conversation] {
+            return &self.conversation_log;
+        }
+        
+        /// Start a conversation between each pair of acquaintances that have just met walking in opposite directions
+        fn update_conversations(&mut self) {
+            let conversations = match self.conversations {
+                Some(conversations) => conversations,
+                None => return
+            };
+            
+            for i in 0..self.active_pedestrians.len() {
+                for j in (i + 1)..self.active_pedestrians.len() {
+                    let (first, second) = (&self.active_pedestrians[i], &self.active_pedestrians[j]);
+                    if first.has_chatted() || second.has_chatted() {
+                        continue;
+                    }
+                    
+                    let dist = (second.x - first.x).hypot(second.y - first.y);
+                    let direction_difference = normalise_angle(first.facing_direction - second.facing_direction);
+                    let opposite = direction_difference > PI/2.0 && direction_difference < 3.0*PI/2.0;
+                    if dist > conversations.meeting_distance || !opposite || !conversations.are_acquainted(first.get_id(), second.get_id()) {
+                        continue;
+                    }
+                    
+                    let (min_duration, max_duration) = conversations.duration_bounds;
+                    let duration = min_duration + self.rng.gen::<f64>() * (max_duration - min_duration);
+                    let direction = (second.y - first.y).atan2(second.x - first.x);
+                    self.conversation_log.push(Conversation {
+                        time: self.time_elapsed,
+                        pedestrian_ids: (first.get_id(), second.get_id()),
+                        position: ((first.x + second.x) / 2.0, (first.y + second.y) / 2.0),
+                        duration
+                    });
+                    
+                    self.active_pedestrians[i].start_chat(direction, duration);
+                    self.active_pedestrians[j].start_chat(direction + PI, duration);
+                }
+            }
+        }
+        
+        /// Return the simulation time and a description of each behaviour switch that has happened
+        pub fn get_behaviour_switch_log(&self) -> &[(f64, String)] {
+            return &self.behaviour_switch_log;
+        }
+        
+        /// Apply any behaviour switches that are due
+        fn update_behaviour_switches(&mut self) {
+            while !self.behaviour_switches.is_empty() && self.behaviour_switches[0].time <= self.time_elapsed {
+                let switch = self.behaviour_switches.pop_front().unwrap();
+                
+                let walkers = self.available_pedestrians.iter_mut()
+                    .chain(self.scheduled_pedestrians.iter_mut().map(|(_, ped)| ped))
+                    .chain(self.held_pedestrians.iter_mut())
+                    .chain(self.active_pedestrians.iter_mut());
+                
+                let mut switched_count = 0;
+                for ped in walkers {
+                    if switch.from.as_ref().is_none_or(|name| ped.get_behaviour().name() == name) {
+                        ped.set_behaviour(switch.to.clone());
+                        switched_count += 1;
+                    }
+                }
+                
+                self.behaviour_switch_log.push((
+                    self.time_elapsed,
+                    format!("{} -> {} ({} pedestrians)", switch.from.as_deref().unwrap_or("all"), switch.to.name(), switched_count)
+                ));
+            }
+        }
+        
+        /// Schedule scripted events, such as those in a scenario file, to happen during the run.
+        /// Each event happens at the start of the first timestep at or after its time, and is recorded in the event log.
+        pub fn add_events(&mut self, events: Vec<ScenarioEvent>) {
+            for event in events {
+                let index = self.events.partition_point(|other| other.time <= event.time);
+                self.events.insert(index, event);
+            }
+        }
+        
+        /// Return the simulation time and a description of each scripted event that has happened, or been skipped because it couldn't happen
+        pub fn get_event_log(&self) -> &[(f64, String)] {
+            return &self.event_log;
+        }
+        
+        /// Carry out any scripted events that are due
+        fn update_events(&mut self) {
+            while !self.events.is_empty() && self.events[0].time <= self.time_elapsed {
+                let event = self.events.remove(0);
+                
+                let description = match event.action {
+                    EventAction::SpawnBurst {group, count, behaviour} => {
+                        match self.find_behaviour(behaviour.as_deref()) {
+                            Some(model) if group < self.area.start_positions.len() => {
+                                let name = model.name().to_string();
+                                self.add_pedestrian_burst(self.time_elapsed, count, group, model);
+                                format!("{} pedestrians ({}) released in group {}", count, name, group)
+                            },
+                            Some(_) => format!("burst skipped: there is no group {}", group),
+                            None => format!("burst skipped: no pedestrian follows the model \"{}\"", behaviour.as_deref().unwrap_or("any"))
+                        }
+                    },
+                    EventAction::OpenDoor {door} | EventAction::CloseDoor {door} if door >= self.area.doors.len() => {
+                        format!("door skipped: there is no door {}", door)
+                    },
+                    EventAction::OpenDoor {door} => {
+                        self.set_door_open(door, true);
+                        format!("door {} opened", door)
+                    },
+                    EventAction::CloseDoor {door} => {
+                        self.set_door_open(door, false);
+                        format!("door {} closed", door)
+                    },
+                    EventAction::SetArrivalRate {rate} => {
+                        self.set_pedestrian_add_rate(rate);
+                        format!("arrival rate set to {} pedestrians/s", rate)
+                    },
+                    EventAction::Evacuate => {
+                        self.start_evacuation();
+                        String::from("evacuation started")
+                    }
+                };
+                
+                self.event_log.push((self.time_elapsed, description));
+            }
+        }
+        
+        /// Return the behaviour model with a name that some pedestrian in the simulation follows, or the model the first pedestrian follows (None = nobody follows it)
+        fn find_behaviour(&self, name: Option<&str>) -> Option<Arc<dyn BehaviourModel>> {
+            let mut walkers = self.available_pedestrians.iter()
+                .chain(self.scheduled_pedestrians.iter().map(|(_, ped)| ped))
+                .chain(self.held_pedestrians.iter())
+                .chain(self.active_pedestrians.iter())
+                .chain(self.finished_pedestrians.iter());
+            
+            return walkers.find(|ped| name.is_none_or(|name| ped.get_behaviour().name() == name))
+                .map(|ped| ped.get_behaviour().clone());
+        }
+        
+        /// Start an evacuation at a given time: every walking pedestrian abandons its destination and heads for the nearest of the area's exits.
+        /// 
+        /// Pedestrians that haven't started walking by then never enter the simulation. If the area has no exits, pedestrians keep walking to their destinations.
+        /// 
+        /// * `time` - The simulation time at which the evacuation starts, in seconds
+        pub fn schedule_evacuation(&mut self, time: f64) {
+            self.evacuation_time = Some(time);
+        }
+        
+        /// Start an evacuation at the next timestep, unless one has already started
+        pub fn start_evacuation(&mut self) {
+            if self.evacuation_results.is_none() {
+                self.evacuation_time = Some(self.time_elapsed);
+            }
+        }
+        
+        /// Return measurements of the evacuation, or None if it hasn't started
+        pub fn get_evacuation_results(&self) -> Option<&EvacuationResults> {
+            return self.evacuation_results.as_ref();
+        }
+        
+        /// Start the evacuation if it is due
+        fn update_evacuation(&mut self) {
+            if self.evacuation_results.is_some() || !self.evacuation_time.is_some_and(|time| time <= self.time_elapsed) {
+                return;
+            }
+            
+            self.available_pedestrians.clear();
+            self.scheduled_pedestrians.clear();
+            self.held_pedestrians.clear();
+            self.record_spawn_queue_length();
+            
+            for ped in &mut self.active_pedestrians {
+                ped.evacuate();
+            }
+            
+            self.evacuation_results = Some(EvacuationResults {
+                start_time: self.time_elapsed,
+                evacuee_count: self.active_pedestrians.len(),
+                egress_time: None,
+                exit_usage: vec![0; self.area.exits.len()],
+                peak_exit_queues: vec![0; self.area.exits.len()]
+            });
+        }
+        
+        /// Record the number of pedestrians queueing at each exit, and the egress time once everyone has left
+        fn update_exit_queues(&mut self) {
+            let results = match &mut self.evacuation_results {
+                Some(results) if results.egress_time.is_none() => results,
+                _ => return
+            };
+            
+            let mut queues = vec![0; self.area.exits.len()];
+            for ped in &self.active_pedestrians {
+                if let Some(exit) = ped.get_exit() {
+                    let (exit_x, exit_y) = self.area.exits[exit];
+                    if ((ped.x - exit_x)*(ped.x - exit_x) + (ped.y - exit_y)*(ped.y - exit_y)).sqrt() < EXIT_QUEUE_RADIUS {
+                        queues[exit] += 1;
+                    }
+                }
+            }
+            for (peak, queue) in results.peak_exit_queues.iter_mut().zip(queues) {
+                *peak = (*peak).max(queue);
+            }
+            
+            if self.active_pedestrians.is_empty() {
+                results.egress_time = Some(self.time_elapsed - results.start_time);
+            }
+        }
+        
+        /// Make some number of pedestrians active, depending on the arrival process and the release times of scheduled pedestrians
+        fn update_active(&mut self) {
+            let first_new = self.active_pedestrians.len();
+            
+            let context = ArrivalContext {
+                time: self.time_elapsed,
+                elapsed: self.time_elapsed - self.arrival_clock,
+                add_rate: self.pedestrian_add_rate,
+                active_count: self.active_pedestrians.len(),
+                waiting: &self.available_pedestrians
+            };
+            let arrivals = self.arrival_process.get_arrivals(&context);
+            self.arrival_clock = self.time_elapsed;
+            
+            let mut starting = Vec::new();
+            for group in arrivals {
+                let index = match group {
+                    // Take the group's pedestrians in the same order they would otherwise be added in
+                    Some(group) => self.available_pedestrians.iter().rposition(|ped| ped.get_group() == group),
+                    None => self.available_pedestrians.len().checked_sub(1)
+                };
+                if let Some(i) = index {
+                    starting.push(self.available_pedestrians.remove(i));
+                }
+            }
+            
+            // Release any scheduled pedestrians that are due
+            let due_count = self.scheduled_pedestrians.partition_point(|(t, _)| *t <= self.time_elapsed);
+            starting.extend(self.scheduled_pedestrians.drain(..due_count).map(|(_, ped)| ped));
+            
+            if self.spawn_gate {
+                // Pedestrians already held back go first
+                self.held_pedestrians.append(&mut starting);
+                starting = std::mem::take(&mut self.held_pedestrians);
+            }
+            
+            for mut ped in starting {
+                let (position, clearance) = self.find_start_position(&ped);
+                if self.spawn_gate && clearance < 0.0 {
+                    self.held_pedestrians.push(ped);
+                } else {
+                    ped.set_start_position(position.into());
+                    self.active_pedestrians.push(ped);
+                }
+            }
+            
+            if self.spawn_gate {
+                self.record_spawn_queue_length();
+            }
+            
+            // Pedestrians that have just started walking plan their paths
+            if let Some(navigation) = &self.navigation {
+                for ped in &mut self.active_pedestrians[first_new..] {
+                    ped.set_navigation(navigation.clone());
+                }
+            }
+            
+            for ped in &self.active_pedestrians[first_new..] {
+                for observer in &mut self.observers {
+                    observer.on_pedestrian_spawned(self.time_elapsed, ped);
+                }
+            }
+        }
+        
+        /// Find a position in a pedestrian's start zone that doesn't overlap the walking pedestrians.
+        /// 
+        /// Random positions are tried until one is clear, falling back on the one furthest from overlapping if there is no room.
+        /// Pedestrians starting at a single point keep their position.
+        /// 
+        /// Return format: (position, distance between the pedestrian's edge and the closest walking pedestrian's edge (negative if they overlap))
+        fn find_start_position(&mut self, ped: &pedestrian::Walker) -> (Vec2, f64) {
+            let radius = ped.get_params().radius;
+            let get_clearance = |position: Vec2| {
+                self.active_pedestrians.iter()
+                    .map(|other| position.distance(Vec2::new(other.x, other.y)) - radius - other.get_params().radius)
+                    .fold(f64::INFINITY, f64::min)
+            };
+            
+            let mut best = Vec2::new(ped.x, ped.y);
+            let mut best_clearance = get_clearance(best);
+            
+            let zone = ped.get_start_zone();
+            if let Zone::Point(_) = zone {
+                return (best, best_clearance);
+            }
+            
+            for _ in 1..SPAWN_ATTEMPTS {
+                if best_clearance >= 0.0 {
+                    break;
+                }
+                let candidate = zone.get_point((self.rng.gen(), self.rng.gen()));
+                let clearance = get_clearance(candidate);
+                if clearance > best_clearance {
+                    best = candidate;
+                    best_clearance = clearance;
+                }
+            }
+            
+            return (best, best_clearance);
+        }
+        
+        /// Measure the density and mean speed of the pedestrians in each measurement area
+        fn update_measurement_areas(&mut self) {
+            if self.area.measurement_areas.is_empty() {
+                return;
+            }
+            
+            let mut pedestrians = std::mem::take(&mut self.measurement_buffer);
+            pedestrians.clear();
+            pedestrians.extend(self.active_pedestrians.iter().map(|ped| (Vec2::new(ped.x, ped.y), ped.inst_speed)));
+            
+            for (measurement_area, samples) in self.area.measurement_areas.iter().zip(&mut self.fundamental_diagrams) {
+                // (number of pedestrians inside, sum of their speeds)
+                let (count, speed_sum) = pedestrians.iter()
+                    .filter(|(position, _)| measurement_area.contains(*position))
+                    .fold((0, 0.0), |(count, sum), (_, speed)| (count + 1, sum + speed));
+                let voronoi = self.voronoi_cutoff.and_then(|cutoff| voronoi::measure(measurement_area.get_points(), &pedestrians, cutoff));
+                
+                if count > 0 || voronoi.is_some() {
+                    samples.push(AreaMeasurement {
+                        time: self.time_elapsed,
+                        density: (count as f64) / measurement_area.get_size(),
+                        // The mean speed of an empty area is undefined
+                        mean_speed: if count == 0 {None} else {Some(speed_sum / (count as f64))},
+                        voronoi
+                    });
+                }
+            }
+            
+            self.measurement_buffer = pedestrians;
+        }
+        
+        /// Record the number of held back pedestrians, if it has changed
+        fn record_spawn_queue_length(&mut self) {
+            let length = self.held_pedestrians.len();
+            if self.spawn_queue_lengths.last().map_or(length > 0, |(_, last)| *last != length) {
+                self.spawn_queue_lengths.push((self.time_elapsed, length));
+            }
+        }
+        
+        /// Check all active pedestrians and remove any that have reached their destinations
+        fn update_finished(&mut self) {
+            let mut i = 0;
+            while i < self.active_pedestrians.len() {
+                let ped = &self.active_pedestrians[i];
+                
+                // Pedestrians walking cyclic routes start again once they have been through all their waypoints, unless they are evacuating
+                if let Some(recirculation) = self.recirculation {
+                    if ped.get_exit().is_none() && !ped.has_left() && ped.get_waypoints().is_empty()
+                        && ped.get_destination_distance() < TARGET_LOCATION_RADIUS && self.can_finish_at_destination(ped) {
+                        let respawn = recirculation == Recirculation::Respawn || self.area.group_waypoints[ped.get_group()].is_empty();
+                        self.active_pedestrians[i].restart_route(respawn, &mut self.rng);
+                        self.completed_laps += 1;
+                        i += 1;
+                        continue;
+                    }
+                }
+                
+                if (ped.get_destination_distance() < TARGET_LOCATION_RADIUS && self.can_finish_at_destination(ped)) || ped.has_left() {
+                    if let Some(jitter) = ped.get_jitter() {
+                        self.jitter_results.push((jitter, ped.get_group()));
+                    }
+                    self.junction_delays.push((ped.get_junction_delay(), ped.get_group()));
+                    if ped.in_cohort(UNFAMILIAR_COHORT) {
+                        self.wayfinding_results.push(WayfindingResult {
+                            group: ped.get_group(),
+                            signs_read: ped.get_signs_read(),
+                            wrong_turns: ped.get_wrong_turns()
+                        });
+                    }
+                    if let (Some(exit), Some(results)) = (ped.get_exit(), &mut self.evacuation_results) {
+                        results.exit_usage[exit] += 1;
+                    }
+                    for observer in &mut self.observers {
+                        observer.on_pedestrian_finished(self.time_elapsed, ped);
+                    }
+                    self.finished_pedestrians.push( self.active_pedestrians.remove(i) );
+                } else if ped.is_despawned() {
+                    // Stalled and incapacitated pedestrians are taken out without finishing, so they have no results
+                    self.active_pedestrians.remove(i);
+                } else {
+                    i += 1;
+                }
+            }
+        }
+        
+        /// Return the average heading jitter (in radians) and group ID of each finished pedestrian that walked for at least a full heading window
+        /// 
+        /// Return format: \[(jitter, group)]
+        pub fn get_jitter_results(&self) -> &[(f64, usize)] {
+            return &self.jitter_results;
+        }
+        
+        /// Return the time each finished pedestrian spent giving way at junctions (in seconds) and its group ID
+        /// 
+        /// Return format: \[(delay, group)]
+        pub fn get_junction_delays(&self) -> &[(f64, usize)] {
+            return &self.junction_delays;
+        }
+        
+        /// Return how each finished pedestrian that didn't know its way (see `set_herding`) found it: the signs it read and the wrong turns it took
+        pub fn get_wayfinding_results(&self) -> &[WayfindingResult] {
+            return &self.wayfinding_results;
+        }
+        
+        /// Return the numbers of: (available, active, finished) pedestrians
+        /// 
+        /// Scheduled pedestrians that haven't been released yet, and pedestrians held back by the spawn gate, are counted as available.
+        pub fn get_pedestrian_counts(&self) -> (usize, usize, usize) {
+            return (self.available_pedestrians.len() + self.scheduled_pedestrians.len() + self.held_pedestrians.len(), self.active_pedestrians.len(), self.finished_pedestrians.len());
+        }
+        
+        /// Return the measurements taken in a measurement area, every timestep that it had pedestrians in it (or, with the Voronoi method, near it).
+        /// The flow through the area per metre of width is the density multiplied by the speed.
+        pub fn get_fundamental_diagram(&self, measurement_area: usize) -> &[AreaMeasurement] {
+            return &self.fundamental_diagrams[measurement_area];
+        }
+        
+        /// Return the number of pedestrians in a measurement area right now divided by its size (in pedestrians/m²), and their mean speed in m/s (None = the area is empty)
+        pub fn get_current_area_density(&self, measurement_area: usize) -> (f64, Option<f64>) {
+            let measurement_area = &self.area.measurement_areas[measurement_area];
+            let speeds = self.active_pedestrians.iter()
+                .filter(|ped| measurement_area.contains(Vec2::new(ped.x, ped.y)))
+                .map(|ped| ped.inst_speed)
+                .collect::<Vec<_>>();
+            
+            let mean_speed = if speeds.is_empty() {None} else {Some(speeds.iter().sum::<f64>() / (speeds.len() as f64))};
+            return ((speeds.len() as f64) / measurement_area.get_size(), mean_speed);
+        }
+        
+        /// Return the number of pedestrians held back by the spawn gate over time, each time it changed.
+        /// The number stays the same until the next entry, and is 0 before the first.
+        /// 
+        /// Return format: \[(simulation time, number of held back pedestrians)]
+        pub fn get_spawn_queue_lengths(&self) -> &[(f64, usize)] {
+            return &self.spawn_queue_lengths;
+        }
+        
+        /// Return the longest the spawn gate's queue has been, and its average length over the simulation so far
+        /// 
+        /// Return format: (peak length, mean length)
+        pub fn get_spawn_queue_summary(&self) -> (usize, f64) {
+            let peak = self.spawn_queue_lengths.iter().map(|(_, length)| *length).max().unwrap_or(0);
+            
+            // Integrate the queue length over time, as a step function
+            let mut total = 0.0;
+            for (i, (time, length)) in self.spawn_queue_lengths.iter().enumerate() {
+                let next_time = self.spawn_queue_lengths.get(i + 1).map_or(self.time_elapsed, |(t, _)| *t);
+                total += (next_time - time) * (*length as f64);
+            }
+            let mean = if self.time_elapsed > 0.0 {total / self.time_elapsed} else {0.0};
+            
+            return (peak, mean);
+        }
+        
+        /// Return the number of pedestrians that have not yet been added to the simulation, per group
+        pub fn get_group_demand(&self) -> Vec<usize> {
+            let mut demand = vec![0; self.area.start_positions.len()];
+            for ped in &self.available_pedestrians {
+                demand[ped.get_group()] += 1;
+            }
+            return demand;
+        }
+        
+        /// Return the number of pedestrians currently being added to the simulation per second
+        pub fn get_pedestrian_add_rate(&self) -> f64 {
+            return self.arrival_process.get_rate(self.time_elapsed, self.pedestrian_add_rate);
+        }
+        
+        /// Change the number of pedestrians added to the simulation per second, from now on.
+        /// 
+        /// Arrival processes that don't follow the add rate, such as a scheduled process, ignore it.
+        pub fn set_pedestrian_add_rate(&mut self, pedestrian_add_rate: f64) {
+            self.pedestrian_add_rate = pedestrian_add_rate;
+        }
+        
+        /// Choose how waiting pedestrians are added to the simulation, from now on.
+        /// 
+        /// Pedestrians are added at regular intervals (`Deterministic`) unless another process is set.
+        pub fn set_arrival_process(&mut self, arrival_process: Box<dyn ArrivalProcess>) {
+            self.arrival_process = arrival_process;
+            self.arrival_clock = self.time_elapsed;
+        }
+        
+        /// Return the process that decides how waiting pedestrians are added to the simulation
+        pub fn get_arrival_process(&self) -> &dyn ArrivalProcess {
+            return self.arrival_process.as_ref();
+        }
+        
+        /// Return the pedestrians that are currently walking
+        pub fn get_active_pedestrians(&self) -> &[pedestrian::Walker] {
+            return &self.active_pedestrians;
+        }
+        
+        /// Return the current state of the simulation: the position, heading & speed of every walking pedestrian, and the number of pedestrians at each stage
+        pub fn snapshot(&self) -> SimSnapshot {
+            let (waiting_count, active_count, finished_count) = self.get_pedestrian_counts();
+            
+            return SimSnapshot {
+                time: self.time_elapsed,
+                waiting_count,
+                active_count,
+                finished_count,
+                pedestrians: self.active_pedestrians.iter().map(|ped| PedestrianState {
+                    id: ped.get_id(),
+                    group: ped.get_group(),
+                    x: ped.x,
+                    y: ped.y,
+                    heading: ped.facing_direction,
+                    speed: ped.inst_speed,
+                    target_speed: ped.target_speed,
+                    behaviour: ped.get_behaviour().name().to_string(),
+                    cohorts: ped.get_cohorts().to_vec()
+                }).collect()
+            };
+        }
+        
+        /// Return the travel time, group ID, and finish time of every pedestrian that has been timed so far
+        pub fn get_travel_times(&self) -> &[(f64, usize, f64)] {
+            return &self.travel_times;
+        }
+        
+        /// Return a record of every pedestrian timed between the timing boundaries so far, in the order they were timed
+        pub fn get_timing_events(&self) -> &[TimingEvent] {
+            return &self.timing_events;
+        }
+        
+        /// Return a record of every time a pedestrian has stalled so far, in order
+        pub fn get_stall_events(&self) -> &[StallEvent] {
+            return &self.stall_events;
+        }
+        
+        /// Return a record of every pedestrian incapacitated by a hazard (see the `hazard_tolerance` parameter), in order
+        pub fn get_hazard_casualties(&self) -> &[HazardCasualty] {
+            return &self.hazard_casualties;
+        }
+        
+        /// Return the number of pedestrians timed between the timing boundaries per second, over a recent period of simulation time (or since the start, if that is shorter)
+        /// 
+        /// * `window` - The length of the period, ending now, in seconds
+        pub fn get_recent_flow(&self, window: f64) -> f64 {
+            // Events are recorded in order of time, so only the latest need to be checked
+            let recent_count = self.timing_events.iter().rev().take_while(|event| event.sim_time > self.time_elapsed - window).count();
+            return (recent_count as f64) / window.min(self.time_elapsed).max(f64::EPSILON);
+        }
+        
+        /// Return the environment this simulation is set in
+        pub fn get_area(&self) -> &Arc<SimArea> {
+            return &self.area;
+        }
+        
+        /// Draw this simulation with RayLib
+        /// 
+        /// * `rl_handle` - The RaylibDrawHandle used to draw the objects
+        /// * `offset` - The x and y offset of this object, in pixels
+        /// * `layers` - Which of the optional overlays to draw
+        /// * `interpolation` - How far through the next timestep to draw the pedestrians, from 0 (where they were at the start of the last timestep) to 1 (where they are now)
+        pub fn draw(&self, rl_handle: &mut RaylibDrawHandle, offset: (i32, i32), draw_scale: i32, layers: DrawLayers, interpolation: f64) {
+            
+            if layers.heatmap {
+                if let Some(density_grid) = &self.density_grid {
+                    density_grid.draw(rl_handle, offset, draw_scale);
+                }
+            }
+            
+            self.area.draw(rl_handle, offset, draw_scale, layers, &self.door_states);
+            
+            // Hazards are drawn here rather than with the area, since they spread over time
+            for hazard in &self.area.hazards {
+                hazard.draw(rl_handle, offset, draw_scale, self.time_elapsed);
+            }
+            
+            let colours = self.get_pedestrian_colours(layers.colour_mode);
+            for (ped, colour) in self.active_pedestrians.iter().zip(colours) {
+                ped.draw(rl_handle, offset, draw_scale, layers, colour, interpolation);
+            }
+            
+            for vehicle in self.vehicle_traffic.get_vehicles() {
+                vehicle.draw(rl_handle, offset, draw_scale);
+            }
+            
+        }
+        
+        /// Draw the legend of a colouring mode with RayLib, as a column of labelled swatches (nothing is drawn for `ColourMode::Uniform`)
+        /// 
+        /// * `position` - The top-left corner of the legend, in pixels
+        pub fn draw_colour_legend(&self, rl_handle: &mut RaylibDrawHandle, position: (i32, i32), mode: ColourMode) {
+            if mode == ColourMode::Uniform {
+                return;
+            }
+            
+            rl_handle.draw_text(&format!("Colour: {}", mode.name()), position.0, position.1, 20, Color::BLACK);
+            for (i, (label, colour)) in self.get_colour_legend(mode).iter().enumerate() {
+                let y = position.1 + 24 * (i as i32 + 1);
+                rl_handle.draw_rectangle(position.0, y + 2, 16, 16, colour);
+                rl_handle.draw_text(label, position.0 + 24, y, 20, Color::BLACK);
+            }
+        }
+        
+        /// Return the entries of the legend of a colouring mode: \[(label, colour)]
+        pub fn get_colour_legend(&self, mode: ColourMode) -> Vec<(String, Color)> {
+            return match mode {
+                ColourMode::Uniform => Vec::new(),
+                ColourMode::Group => (0..self.area.start_positions.len()).map(|group| {
+                    (format!("Group {}", group), get_category_colour(group))
+                }).collect(),
+                ColourMode::Behaviour => self.get_behaviour_names().into_iter().enumerate().map(|(i, name)| {
+                    (name, get_category_colour(i))
+                }).collect(),
+                ColourMode::Speed => [0.0, 0.5, 1.0].iter().map(|ratio| {
+                    (format!("{}%", ratio * 100.0), get_scale_colour(&SPEED_SCALE_COLOURS, *ratio))
+                }).collect(),
+                ColourMode::Density => (0..=4).map(|i| {
+                    let density = COLOUR_DENSITY_MAX * (i as f64) / 4.0;
+                    (format!("{}", density), get_scale_colour(&DENSITY_SCALE_COLOURS, density / COLOUR_DENSITY_MAX))
+                }).collect()
+            };
+        }
+        
+        /// Return the colour of each walking pedestrian under a colouring mode, in the same order as `get_active_pedestrians` (None = the usual colour)
+        fn get_pedestrian_colours(&self, mode: ColourMode) -> Vec<Option<Color>> {
+            return match mode {
+                ColourMode::Uniform => vec![None; self.active_pedestrians.len()],
+                ColourMode::Group => self.active_pedestrians.iter().map(|ped| Some(get_category_colour(ped.get_group()))).collect(),
+                ColourMode::Behaviour => {
+                    let names = self.get_behaviour_names();
+                    self.active_pedestrians.iter().map(|ped| {
+                        names.iter().position(|name| name == ped.get_behaviour().name()).map(get_category_colour)
+                    }).collect()
+                },
+                ColourMode::Speed => self.active_pedestrians.iter().map(|ped| {
+                    Some(get_scale_colour(&SPEED_SCALE_COLOURS, ped.inst_speed / ped.target_speed))
+                }).collect(),
+                ColourMode::Density => self.active_pedestrians.iter().map(|ped| {
+                    // The pedestrian itself is counted, so that the density is that of the crowd it is part of
+                    let count = self.active_pedestrians.iter()
+                        .filter(|other| (other.x - ped.x).powi(2) + (other.y - ped.y).powi(2) <= COLOUR_DENSITY_RADIUS * COLOUR_DENSITY_RADIUS)
+                        .count();
+                    let density = (count as f64) / (PI * COLOUR_DENSITY_RADIUS * COLOUR_DENSITY_RADIUS);
+                    Some(get_scale_colour(&DENSITY_SCALE_COLOURS, density / COLOUR_DENSITY_MAX))
+                }).collect()
+            };
+        }
+        
+        /// Return the names of the behaviour models followed by any pedestrian (or switched to later) in alphabetical order, so that each model keeps the same colour while the simulation runs
+        fn get_behaviour_names(&self) -> Vec<String> {
+            let mut names = self.available_pedestrians.iter()
+                .chain(self.scheduled_pedestrians.iter().map(|(_, ped)| ped))
+                .chain(self.held_pedestrians.iter())
+                .chain(self.active_pedestrians.iter())
+                .chain(self.finished_pedestrians.iter())
+                .map(|ped| ped.get_behaviour().name().to_string())
+                .chain(self.behaviour_switches.iter().map(|switch| switch.to.name().to_string()))
+                .collect::<Vec<_>>();
+            names.sort();
+            names.dedup();
+            return names;
+        }
+        
+    }
+    
+    impl SimArea {
+        pub fn new() -> SimArea {
+            SimArea {
+                boundaries: Vec::new(),
+                obstacles: Vec::new(),
+                start_positions: Vec::new(),
+                end_positions: Vec::new(),
+                start_zones: Vec::new(),
+                end_zones: Vec::new(),
+                group_waypoints: Vec::new(),
+                timing_boundaries: Vec::new(),
+                counting_lines: Vec::new(),
+                measurement_areas: Vec::new(),
+                markings: Vec::new(),
+                junctions: Vec::new(),
+                exits: Vec::new(),
+                doors: Vec::new(),
+                queues: Vec::new(),
+                gates: Vec::new(),
+                walkways: Vec::new(),
+                speed_zones: Vec::new(),
+                vehicle_lanes: Vec::new(),
+                hazards: Vec::new(),
+                signs: Vec::new()
+            }
+        }
+        
+        /// Return the bounding box of all walls, obstacles, start zones, end zones and exits
+        /// 
+        /// Output form: ((min x, min y), (max x, max y))
+        pub fn get_bounds(&self) -> ((f64, f64), (f64, f64)) {
+            let wall_points = self.boundaries.iter().flat_map(|wall| {
+                let (p1, p2) = wall.get_points();
+                [p1, p2]
+            });
+            let obstacle_points = self.obstacles.iter().flat_map(|obstacle| {
+                let ((x, y), r) = (obstacle.get_centre(), obstacle.get_radius());
+                [(x - r, y - r), (x + r, y + r)]
+            });
+            let zone_points = self.start_zones.iter().chain(&self.end_zones).flatten().flat_map(|zone| {
+                [zone.get_point((0.0, 0.0)).into(), zone.get_point((1.0, 1.0)).into()]
+            });
+            let points = wall_points.chain(obstacle_points).chain(zone_points).chain(self.exits.iter().copied());
+            
+            let mut min = (f64::INFINITY, f64::INFINITY);
+            let mut max = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+            for (x, y) in points {
+                min = (min.0.min(x), min.1.min(y));
+                max = (max.0.max(x), max.1.max(y));
+            }
+            
+            return (min, max);
+        }
+        
+        /// Return the distance from a point to the closest wall or obstacle (negative if the point is inside an obstacle)
+        /// 
+        /// * `door_states` - Whether each door is open (see `CrowdSim::get_door_states`)
+        pub fn get_clearance(&self, p: Vec2, door_states: &[bool]) -> f64 {
+            let wall_dist = self.get_walls(door_states).map(|wall| wall.get_normal_vector(p).0);
+            let obstacle_dist = self.obstacles.iter().map(|obstacle| obstacle.get_normal_vector(p).0);
+            
+            return wall_dist.chain(obstacle_dist).fold(f64::INFINITY, f64::min);
+        }
+        
+        /// Check whether there is a clear line of sight between two points past every wall, ignoring obstacles (which are too narrow to hide a pedestrian behind)
+        /// 
+        /// * `door_states` - Whether each door is open (see `CrowdSim::get_door_states`)
+        pub fn is_in_sight(&self, p: Vec2, q: Vec2, door_states: &[bool]) -> bool {
+            return !self.get_walls(door_states).any(|wall| {
+                let (a, b) = wall.get_points();
+                segments_intersect(p, q, a.into(), b.into())
+            });
+        }
+        
+        /// Check whether there is a clear line of sight between two points, past every wall and obstacle
+        /// 
+        /// * `door_states` - Whether each door is open (see `CrowdSim::get_door_states`)
+        pub fn is_visible(&self, p: Vec2, q: Vec2, door_states: &[bool]) -> bool {
+            let blocked_by_wall = self.get_walls(door_states).any(|wall| {
+                let (a, b) = wall.get_points();
+                segments_intersect(p, q, a.into(), b.into())
+            });
+            let blocked_by_obstacle = self.obstacles.iter().any(|obstacle| {
+                segment_normal_vector(p, q, obstacle.get_centre().into()).0 < obstacle.get_radius()
+            });
+            
+            return !blocked_by_wall && !blocked_by_obstacle;
+        }
+        
+        /// Return every wall pedestrians collide with at the moment: the walls, and the doors that are closed
+        /// 
+        /// * `door_states` - Whether each door is open (see `CrowdSim::get_door_states`)
+        pub fn get_walls<'a>(&'a self, door_states: &'a [bool]) -> impl Iterator<Item = &'a Wall> {
+            return self.boundaries.iter().chain(self.doors.iter().zip(door_states).filter(|(_, open)| !**open).map(|(door, _)| door.get_wall()));
+        }
+        
+        /// Add a door, which acts as a wall while it is closed
+        /// 
+        /// * `open` - Whether the door is open at the start
+        /// * `schedule` - The times the door opens or closes: \[(simulation time, open)]
+        pub fn add_door(&mut self, point1: (f64, f64), point2: (f64, f64), open: bool, schedule: Vec<(f64, bool)>) {
+            self.doors.push(
+                Door::new(Wall::new(point1.0, point1.1, point2.0, point2.1), open, schedule)
+            );
+        }
+        
+        /// Add a turnstile or ticket gate, which lets one pedestrian through at a time so that a queue forms in front of it when it is busy.
+        /// The walls either side of the gate need to be added separately.
+        /// 
+        /// * `position` - The middle of the gate, where pedestrians stand while they pass through
+        /// * `direction` - The direction pedestrians pass through the gate in (pedestrians going the other way walk through freely)
+        /// * `service_time` - How long it takes each pedestrian to pass through the gate, in seconds
+        pub fn add_ticket_gate(&mut self, position: (f64, f64), direction: (f64, f64), service_time: f64) {
+            self.gates.push(
+                TicketGate::new(position, direction, service_time)
+            );
+        }
+        
+        pub fn add_wall(&mut self, point1: (f64, f64), point2: (f64, f64)) {
+            self.boundaries.push(
+                Wall::new(point1.0, point1.1, point2.0, point2.1)
+            );
+        }
+        
+        /// Add a circular obstacle, such as a pillar or a tree
+        pub fn add_circular_obstacle(&mut self, center: (f64, f64), radius: f64) {
+            self.obstacles.push(
+                CircularObstacle::new(center.0, center.1, radius)
+            );
         }
         
-        /// Randomise the order of the pedestrians
-        pub fn randomise_pedestrian_order(&mut self) {
-            self.available_pedestrians.shuffle(&mut thread_rng());
+        pub fn add_start_end_group(&mut self, starts: Vec<(f64, f64)>, ends: Vec<(f64, f64)>) {
+            self.add_start_end_zone_group(
+                starts.into_iter().map(|start| Zone::Point(start.into())).collect(),
+                ends.into_iter().map(|end| Zone::Point(end.into())).collect()
+            );
         }
         
-        /// Simulate a small period of time in a single step.
+        /// Add a group whose pedestrians start and finish in zones, such as across the width of a doorway, instead of at fixed points.
         /// 
-        /// * `time_scale` - The amount of time (in seconds) that passes during each timestep
-        pub fn simulate_timestep(&mut self, time_scale: f64) {
-            //println!("Simulating one timestep...");
-            
-            self.update_active();
-            
-            // Collect the position and facing direction of every pedestrian to pass to Walker.simulate_timestep(), so that a pedestrian can see its neighbours.
-            // This is an ugly way to do this, but I don't have time to implement a "nice" way right now.
-            // (x, y, direction)
-            let pedestrian_positions = self.active_pedestrians.iter().map(|ped| (ped.x, ped.y, ped.facing_direction)).collect::<Vec<_>>();
-            
-            for (i, ped) in self.active_pedestrians.iter_mut().enumerate() {
-                ped.simulate_timestep(time_scale, &pedestrian_positions[0..i], &pedestrian_positions[i+1..]);
-                
-                let travel_time = ped.check_timing_boundaries(time_scale);
-                if travel_time.is_some() {
-                    self.travel_times.push((travel_time.unwrap(), ped.get_group(), self.time_elapsed));
-                }
-                
-            }
-            
-            self.time_elapsed += time_scale;
-            
-            self.update_finished();
-            
+        /// Each pedestrian starts at a random point in one of the start zones, away from other pedestrians if there is room,
+        /// and walks to a random point in one of the end zones. It finishes when it gets close to any part of the end zone.
+        pub fn add_start_end_zone_group(&mut self, starts: Vec<Zone>, ends: Vec<Zone>) {
+            self.start_positions.push(starts.iter().map(|zone| zone.get_centre().into()).collect());
+            self.end_positions.push(ends.iter().map(|zone| zone.get_centre().into()).collect());
+            self.start_zones.push(starts);
+            self.end_zones.push(ends);
+            self.group_waypoints.push(Vec::new());
         }
         
-        /// Run the simulation until all pedestrians have finished, returning timing results
+        /// Set the waypoints that pedestrians in a group walk through, in order, on the way to their end positions
         /// 
-        /// Return format: (total time, pedestrian count, \[(travel time, group, finish time)])
-        pub fn simulate_full(&mut self, time_scale: f64) -> (f64, usize, Vec<(f64, usize, f64)>) {
-            
-            while self.available_pedestrians.len() + self.active_pedestrians.len() > 0 {
-                self.simulate_timestep(time_scale);
-            }
-            
-            return (self.time_elapsed, self.finished_pedestrians.len(), self.travel_times.clone());
-            
+        /// Use this to route pedestrians around geometry that would block a straight path, such as a corner.
+        pub fn set_group_waypoints(&mut self, group: usize, waypoints: Vec<(f64, f64)>) {
+            self.group_waypoints[group] = waypoints;
         }
         
-        /// Add pedestrians to the simulation in bulk
-        pub fn add_pedestrian_set(&mut self, number: usize, group: usize, etiquette: pedestrian::Etiquette) {
-            
-            let mut rng = thread_rng();
-            
-            for _ in 0..number {
-                let start = rng.sample(Uniform::new(0,self.area.start_positions[group].len()));
-                let end = rng.sample(Uniform::new(0,self.area.end_positions[group].len()));
-                let target_speed = pedestrian::PEDESTRIAN_TARGET_SPEED_BOUNDS.0 + rand::random::<f64>() * (pedestrian::PEDESTRIAN_TARGET_SPEED_BOUNDS.1 - pedestrian::PEDESTRIAN_TARGET_SPEED_BOUNDS.0);
-                self.add_pedestrian(group, start, end, target_speed, etiquette.clone())
-            }
-            
+        pub fn add_timing_boundary(&mut self, point1: (f64, f64), point2: (f64, f64)) {
+            self.timing_boundaries.push(
+                MeasurementLine::new(point1.0, point1.1, point2.0, point2.1)
+            );
         }
         
-        /// Add a new pedestrian to the simulation
-        pub fn add_pedestrian(&mut self, group: usize, start: usize, end: usize, target_speed: f64, etiquette: pedestrian::Etiquette) {
-            self.available_pedestrians.push(
-                pedestrian::Walker::new(self.area.clone(), group, start, end, target_speed, etiquette)
+        /// Add a line that every pedestrian crossing is logged at, with its direction (see `MeasurementLine::get_crossing`)
+        pub fn add_counting_line(&mut self, point1: (f64, f64), point2: (f64, f64)) {
+            self.counting_lines.push(
+                MeasurementLine::new(point1.0, point1.1, point2.0, point2.1)
             );
         }
         
-        /// Make some number of pedestrians active, depending on pedestrian_add_rate
-        fn update_active(&mut self) {
-            while self.available_pedestrians.len() > 0 && self.time_elapsed > ((self.active_pedestrians.len() + self.finished_pedestrians.len()) as f64) / self.pedestrian_add_rate {
-                self.active_pedestrians.push(self.available_pedestrians.pop().unwrap());
-            }
+        /// Add a rectangular area, between two opposite corners, where the local density and mean speed are measured every timestep for fundamental diagrams
+        pub fn add_measurement_area(&mut self, corner1: (f64, f64), corner2: (f64, f64)) {
+            self.add_measurement_polygon(vec![corner1, (corner2.0, corner1.1), corner2, (corner1.0, corner2.1)]);
         }
         
-        /// Check all active pedestrians and remove any that have reached their destinations
-        fn update_finished(&mut self) {
-            let mut i = 0;
-            while i < self.active_pedestrians.len() {
-                let ped = &self.active_pedestrians[i];
-                let dest = ped.get_dest_coords();
-                if ((ped.x - dest.0)*(ped.x - dest.0) + (ped.y - dest.1)*(ped.y - dest.1)).sqrt() < TARGET_LOCATION_RADIUS {
-                    self.finished_pedestrians.push( self.active_pedestrians.remove(i) );
-                } else {
-                    i += 1;
-                }
-            }
+        /// Add a polygonal area, given by its corners in order, where the local density and mean speed are measured every timestep for fundamental diagrams
+        pub fn add_measurement_polygon(&mut self, points: Vec<(f64, f64)>) {
+            self.measurement_areas.push(
+                MeasurementArea::new(points)
+            );
         }
         
-        /// Return the numbers of: (available, active, finished) pedestrians
-        pub fn get_pedestrian_counts(&self) -> (usize, usize, usize) {
-            return (self.available_pedestrians.len(), self.active_pedestrians.len(), self.finished_pedestrians.len());
+        /// Add a visual-only line, such as a painted platform edge or a lane divider
+        pub fn add_marking(&mut self, point1: (f64, f64), point2: (f64, f64)) {
+            self.markings.push(
+                Marking::new(point1.0, point1.1, point2.0, point2.1)
+            );
         }
         
-        /// Draw this simulation with RayLib
+        /// Add a queue at a service point, such as a ticket machine, which pedestrians in some groups wait in and are served one at a time before carrying on to their end positions
         /// 
-        /// * `rl_handle` - The RaylibDrawHandle used to draw the objects
-        /// * `offset` - The x and y offset of this object, in pixels
-        pub fn draw(&self, rl_handle: &mut RaylibDrawHandle, offset: (i32, i32), draw_scale: i32) {
-            
-            self.area.draw(rl_handle, offset, draw_scale);
-            
-            for ped in &self.active_pedestrians {
-                ped.draw(rl_handle, offset, draw_scale);
-            }
-            
+        /// * `path` - The points along the queue, starting with the service point at the head (at least two). A longer queue carries on in the direction of the last segment.
+        /// * `service_time` - How long it takes to serve each pedestrian, in seconds
+        /// * `groups` - The groups whose pedestrians join the queue
+        pub fn add_queue(&mut self, path: Vec<(f64, f64)>, service_time: f64, groups: Vec<usize>) {
+            self.queues.push(
+                ServiceQueue::new(path, service_time, groups)
+            );
         }
         
-    }
-    
-    impl SimArea {
-        pub fn new() -> SimArea {
-            SimArea {
-                boundaries: Vec::new(),
-                start_positions: Vec::new(),
-                end_positions: Vec::new(),
-                timing_boundaries: Vec::new()
-            }
+        /// Add a moving walkway or an escalator, which carries the pedestrians on it along on top of their own walking
+        /// 
+        /// * `start` - The middle of the end pedestrians step on at
+        /// * `end` - The middle of the end pedestrians step off at
+        /// * `width` - The width of the walkway, in metres
+        /// * `speed` - The speed the walkway carries pedestrians along at, in m/s (the horizontal part of the speed, for an escalator)
+        /// * `etiquette` - Which side pedestrians stand on and how many of them stand, so that the rest can walk past on the other side (None = everyone walks wherever they like)
+        pub fn add_moving_walkway(&mut self, start: (f64, f64), end: (f64, f64), width: f64, speed: f64, etiquette: Option<WalkwayEtiquette>) {
+            self.walkways.push(
+                MovingWalkway::new(start, end, width, speed, etiquette)
+            );
         }
         
-        pub fn add_wall(&mut self, point1: (f64, f64), point2: (f64, f64)) {
-            self.boundaries.push(
-                Wall::new(point1.0, point1.1, point2.0, point2.1)
+        /// Add a polygonal area, given by its corners in order, where pedestrians walk slower or faster than usual, such as stairs, a ramp or a grass verge
+        /// 
+        /// * `multiplier` - The multiplier applied to the speed pedestrians want to walk at inside the zone (e.g. 0.6 for going up stairs)
+        pub fn add_speed_zone(&mut self, points: Vec<(f64, f64)>, multiplier: f64) {
+            self.speed_zones.push(
+                SpeedZone::new(points, multiplier)
             );
         }
         
-        pub fn add_start_end_group(&mut self, starts: Vec<(f64, f64)>, ends: Vec<(f64, f64)>) {
-            self.start_positions.push(starts);
-            self.end_positions.push(ends);
+        /// Add a route that vehicles such as bicycles or carts follow through the area, entering at a regular interval.
+        /// Vehicles ignore walls & obstacles, and stop for pedestrians that don't step out of their way.
+        /// 
+        /// * `route` - The points along the route, starting where vehicles enter
+        /// * `headway` - The time between vehicles entering, in seconds
+        /// * `params` - The size and handling of the vehicles
+        pub fn add_vehicle_lane(&mut self, route: Vec<(f64, f64)>, headway: f64, params: VehicleParams) {
+            self.vehicle_lanes.push(
+                VehicleLane::new(route, headway, params)
+            );
         }
         
-        pub fn add_timing_boundary(&mut self, point1: (f64, f64), point2: (f64, f64)) {
-            self.timing_boundaries.push(
-                Wall::new(point1.0, point1.1, point2.0, point2.1)
+        /// Add a hazard such as smoke from a fire, which spreads out from a point and which pedestrians keep away from.
+        /// Pedestrians that stay in it for too long are incapacitated (see the `hazard_tolerance` parameter).
+        /// 
+        /// * `start_time` - The simulation time the hazard appears at, in seconds
+        /// * `spread_rate` - How fast the core of the hazard grows, in m/s
+        /// * `max_radius` - The largest the core of the hazard grows, in metres
+        pub fn add_hazard(&mut self, position: (f64, f64), start_time: f64, spread_rate: f64, max_radius: f64) {
+            self.hazards.push(
+                HazardSource::new(position, start_time, spread_rate, max_radius)
+            );
+        }
+        
+        /// Add a wayfinding sign, which pedestrians that don't know their way (see `CrowdSim::set_herding`) read once they can see it
+        /// 
+        /// * `visibility_radius` - How far away the sign can be read from, in metres
+        /// * `destination_group` - The destination group the sign points the way to
+        /// * `waypoint` - The point the sign sends pedestrians to on the way, such as a doorway (None = the sign shows them their destination)
+        pub fn add_sign(&mut self, position: (f64, f64), visibility_radius: f64, destination_group: usize, waypoint: Option<(f64, f64)>) {
+            self.signs.push(
+                Sign::new(position, visibility_radius, destination_group, waypoint)
+            );
+        }
+        
+        /// Return the multiplier applied to the speed pedestrians want to walk at a point: the product of the multipliers of every speed zone it is in (1 = outside them all)
+        pub fn get_speed_multiplier(&self, p: Vec2) -> f64 {
+            return self.speed_zones.iter().filter(|zone| zone.contains(p)).map(|zone| zone.get_multiplier()).product();
+        }
+        
+        /// Return the moving walkway a point is on, if any
+        pub fn get_walkway(&self, p: Vec2) -> Option<&MovingWalkway> {
+            return self.walkways.iter().find(|walkway| walkway.contains(p));
+        }
+        
+        /// Add a designated exit, which every pedestrian heads for during an evacuation if it is the closest
+        pub fn add_exit(&mut self, point: (f64, f64)) {
+            self.exits.push(point);
+        }
+        
+        /// Add a rectangular junction zone, where pedestrians in crossing streams give way to each other according to a priority rule
+        pub fn add_junction_zone(&mut self, corner1: (f64, f64), corner2: (f64, f64), rule: PriorityRule) {
+            self.junctions.push(
+                JunctionZone::new(corner1, corner2, rule)
             );
         }
         
         /// Draw this environment with RayLib
-        pub fn draw(&self, rl_handle: &mut RaylibDrawHandle, offset: (i32, i32), draw_scale: i32) {
+        /// 
+        /// * `door_states` - Whether each door is open (see `CrowdSim::get_door_states`)
+        pub fn draw(&self, rl_handle: &mut RaylibDrawHandle, offset: (i32, i32), draw_scale: i32, layers: DrawLayers, door_states: &[bool]) {
             
             // Add metre gridlines
-            let max_x = self.boundaries.iter().map(|wall| wall.x1.max(wall.x2) as i32).max().unwrap();
-            let max_y = self.boundaries.iter().map(|wall| wall.y1.max(wall.y2) as i32).max().unwrap();
-            for x in 0..max_x {
-                rl_handle.draw_line(
-                    offset.0 + draw_scale*x,
-                    offset.1,
-                    offset.0 + draw_scale*x,
-                    offset.1 + draw_scale*max_y,
-                    Color::fade(&Color::from_hex("b0b0b0").unwrap(), 0.5)
-                );
-            }
-            for y in 0..max_y {
-                rl_handle.draw_line(
-                    offset.0,
-                    offset.1 + draw_scale*y,
-                    offset.0 + draw_scale*max_x,
-                    offset.1 + draw_scale*y,
-                    Color::fade(&Color::from_hex("b0b0b0").unwrap(), 0.5)
-                );
+            if layers.grid {
+                let max_x = self.boundaries.iter().map(|wall| wall.x1.max(wall.x2) as i32).max().unwrap();
+                let max_y = self.boundaries.iter().map(|wall| wall.y1.max(wall.y2) as i32).max().unwrap();
+                for x in 0..max_x {
+                    rl_handle.draw_line(
+                        offset.0 + draw_scale*x,
+                        offset.1,
+                        offset.0 + draw_scale*x,
+                        offset.1 + draw_scale*max_y,
+                        Color::fade(&Color::from_hex("b0b0b0").unwrap(), 0.5)
+                    );
+                }
+                for y in 0..max_y {
+                    rl_handle.draw_line(
+                        offset.0,
+                        offset.1 + draw_scale*y,
+                        offset.0 + draw_scale*max_x,
+                        offset.1 + draw_scale*y,
+                        Color::fade(&Color::from_hex("b0b0b0").unwrap(), 0.5)
+                    );
+                }
             }
             
             
+            // Draw the junction zones and markings first, so that everything else is drawn on top of them
+            for junction in &self.junctions {
+                junction.draw(rl_handle, offset, draw_scale);
+            }
+            for queue in &self.queues {
+                queue.draw(rl_handle, offset, draw_scale);
+            }
+            for gate in &self.gates {
+                gate.draw(rl_handle, offset, draw_scale);
+            }
+            for sign in &self.signs {
+                sign.draw(rl_handle, offset, draw_scale);
+            }
+            for zone in &self.speed_zones {
+                zone.draw(rl_handle, offset, draw_scale);
+            }
+            for walkway in &self.walkways {
+                walkway.draw(rl_handle, offset, draw_scale);
+            }
+            for lane in &self.vehicle_lanes {
+                lane.draw(rl_handle, offset, draw_scale);
+            }
+            for marking in &self.markings {
+                marking.draw(rl_handle, offset, draw_scale, Color::from_hex(MARKING_COLOUR).unwrap());
+            }
+            
             // Draw the walls
             for wall in &self.boundaries {
                 wall.draw(rl_handle, offset, draw_scale, Color::from_hex("000000").unwrap());
             }
             
+            // Draw the doors, faded while they are open
+            for (door, open) in self.doors.iter().zip(door_states) {
+                let opacity = if *open {0.25} else {1.0};
+                door.get_wall().draw(rl_handle, offset, draw_scale, Color::fade(&Color::from_hex(DOOR_COLOUR).unwrap(), opacity));
+            }
+            
+            // Draw the obstacles
+            for obstacle in &self.obstacles {
+                obstacle.draw(rl_handle, offset, draw_scale, Color::from_hex("000000").unwrap());
+            }
+            
             // Draw the end points & zones
             for (x,y) in (&self.end_positions).iter().flatten() {
                 rl_handle.draw_ellipse(
@@ -263,14 +2893,78 @@ pub mod simulator {
                     Color::from_hex(START_COLOUR).unwrap()
                 );
             }
+            // Draw the outlines of start & end zones that aren't single points
+            for zone in self.start_zones.iter().flatten() {
+                draw_zone_outline(rl_handle, zone, offset, draw_scale, Color::from_hex(START_COLOUR).unwrap());
+            }
+            for zone in self.end_zones.iter().flatten() {
+                draw_zone_outline(rl_handle, zone, offset, draw_scale, Color::from_hex(END_COLOUR).unwrap());
+            }
+            
+            // Draw the exits
+            for (x,y) in &self.exits {
+                rl_handle.draw_rectangle(
+                    offset.0 + ((draw_scale as f64) * (*x - 0.3)) as i32,
+                    offset.1 + ((draw_scale as f64) * (*y - 0.3)) as i32,
+                    ((draw_scale as f64) * 0.6) as i32,
+                    ((draw_scale as f64) * 0.6) as i32,
+                    Color::from_hex(EXIT_COLOUR).unwrap()
+                );
+            }
+            
+            // Draw the waypoints
+            for (x,y) in self.group_waypoints.iter().flatten() {
+                rl_handle.draw_ellipse(
+                    offset.0 + ((draw_scale as f64) * *x) as i32,
+                    offset.1 + ((draw_scale as f64)* *y) as i32,
+                    (draw_scale as f32)*0.1,
+                    (draw_scale as f32)*0.1,
+                    Color::from_hex(WAYPOINT_COLOUR).unwrap()
+                );
+            }
             
-            // Draw the timing boundaries
-            for wall in &self.timing_boundaries {
-                wall.draw(rl_handle, offset, draw_scale, Color::from_hex(TIMING_BOUND_COLOUR).unwrap());
+            // Draw the timing boundaries & measurement areas
+            for line in &self.timing_boundaries {
+                line.draw(rl_handle, offset, draw_scale, Color::from_hex(TIMING_BOUND_COLOUR).unwrap());
+            }
+            for measurement_area in &self.measurement_areas {
+                measurement_area.draw(rl_handle, offset, draw_scale);
             }
+            for line in &self.counting_lines {
+                line.draw(rl_handle, offset, draw_scale, Color::from_hex(COUNTING_LINE_COLOUR).unwrap());
+            }
+            
+        }
+        
+    }
+    
+    impl Door {
+        /// * `open` - Whether the door is open at the start
+        /// * `schedule` - The times the door opens or closes: \[(simulation time, open)]
+        pub fn new(wall: Wall, open: bool, mut schedule: Vec<(f64, bool)>) -> Door {
+            schedule.sort_by(|a, b| a.0.total_cmp(&b.0));
             
+            return Door {
+                wall,
+                open,
+                schedule
+            };
+        }
+        
+        /// Return the wall the door forms while it is closed
+        pub fn get_wall(&self) -> &Wall {
+            return &self.wall;
         }
         
+        /// Return whether the door is open at the start of a simulation
+        pub fn is_initially_open(&self) -> bool {
+            return self.open;
+        }
+        
+        /// Return the times the door opens or closes: \[(simulation time, open)], in order of time
+        pub fn get_schedule(&self) -> &[(f64, bool)] {
+            return &self.schedule;
+        }
     }
     
     impl Wall {
@@ -280,76 +2974,337 @@ pub mod simulator {
             }
         }
         
+        /// Return the start and end points of the wall
+        pub fn get_points(&self) -> ((f64, f64), (f64, f64)) {
+            return ((self.x1, self.y1), (self.x2, self.y2));
+        }
+        
         /// Given a point P, determine the vector that points from the closest point on the line to P
         /// 
-        /// Output form: (distance, (normal x, normal y))
-        pub fn get_normal_vector(&self, p: (f64, f64)) -> (f64, (f64, f64)) {
-            // Define some useful vector functions
-            fn vec_dot(v1: (f64, f64), v2: (f64, f64)) -> f64 { v1.0*v2.0 + v1.1*v2.1 }
-            fn vec_add(v1: (f64, f64), v2: (f64, f64)) -> (f64, f64) { (v1.0 + v2.0, v1.1 + v2.1) }
-            fn vec_sub(v1: (f64, f64), v2: (f64, f64)) -> (f64, f64) { (v1.0 - v2.0, v1.1 - v2.1) }
-            fn vec_mul(v: (f64, f64), k: f64) -> (f64, f64) { (v.0 * k, v.1 * k) }
-            /// Find the square of the distance between two points P1 and P2
-            fn vec_dist_sq(p1: (f64, f64), p2: (f64, f64)) -> f64 {
-                (p2.0 - p1.0)*(p2.0 - p1.0) + (p2.1 - p1.1)*(p2.1 - p1.1)
+        /// Output form: (distance, normal vector)
+        pub fn get_normal_vector(&self, p: Vec2) -> (f64, Vec2) {
+            return segment_normal_vector(Vec2::new(self.x1, self.y1), Vec2::new(self.x2, self.y2), p);
+        }
+        
+        /// Find how far along a straight path from `start` to `end` a pedestrian of the given radius first touches the wall (see `swept_circle_segment`)
+        pub fn get_contact_time(&self, start: Vec2, end: Vec2, radius: f64) -> Option<f64> {
+            return swept_circle_segment(start, end, radius, Vec2::new(self.x1, self.y1), Vec2::new(self.x2, self.y2));
+        }
+        
+        /// Draw this wall with RayLib
+        pub fn draw(&self, rl_handle: &mut RaylibDrawHandle, offset: (i32, i32), draw_scale: i32, color: impl Into<raylib::ffi::Color>) {
+            draw_segment(rl_handle, offset, draw_scale, ((self.x1, self.y1), (self.x2, self.y2)), color);
+        }
+        
+    }
+    
+    impl MeasurementLine {
+        pub fn new(x1: f64, y1: f64, x2: f64, y2: f64) -> MeasurementLine {
+            MeasurementLine {
+                x1, x2, y1, y2
+            }
+        }
+        
+        /// Return the start and end points of the line
+        pub fn get_points(&self) -> ((f64, f64), (f64, f64)) {
+            return ((self.x1, self.y1), (self.x2, self.y2));
+        }
+        
+        /// Return the distance from a point P to the closest point on the line
+        pub fn get_distance(&self, p: Vec2) -> f64 {
+            return segment_normal_vector(Vec2::new(self.x1, self.y1), Vec2::new(self.x2, self.y2), p).0;
+        }
+        
+        /// Return the direction a pedestrian crossed the line in while moving from one point to another, or None if it didn't cross it.
+        /// 
+        /// Crossing forwards means moving along the line's normal (y2 - y1, x1 - x2), e.g. left to right over a line from (3, 0) to (3, 6).
+        pub fn get_crossing(&self, from: Vec2, to: Vec2) -> Option<CrossingDirection> {
+            if !segments_intersect(from, to, Vec2::new(self.x1, self.y1), Vec2::new(self.x2, self.y2)) {
+                return None;
+            }
+            
+            let normal = Vec2::new(self.y2 - self.y1, self.x1 - self.x2);
+            return Some(if (to - from).dot(normal) > 0.0 {CrossingDirection::Forward} else {CrossingDirection::Backward});
+        }
+        
+        /// Draw this line with RayLib
+        pub fn draw(&self, rl_handle: &mut RaylibDrawHandle, offset: (i32, i32), draw_scale: i32, color: impl Into<raylib::ffi::Color>) {
+            draw_segment(rl_handle, offset, draw_scale, ((self.x1, self.y1), (self.x2, self.y2)), color);
+        }
+        
+    }
+    
+    impl MeasurementArea {
+        /// Create a new measurement area from the corners of a polygon, in order
+        pub fn new(points: Vec<(f64, f64)>) -> MeasurementArea {
+            MeasurementArea {
+                points: points.into_iter().map(Vec2::from).collect()
+            }
+        }
+        
+        /// Return the corners of the polygon, in order
+        pub fn get_points(&self) -> &[Vec2] {
+            return &self.points;
+        }
+        
+        /// Check whether a point is inside the area
+        pub fn contains(&self, p: Vec2) -> bool {
+            return polygon_contains(&self.points, p);
+        }
+        
+        /// Return the size of the area, in square metres
+        pub fn get_size(&self) -> f64 {
+            return polygon_area(&self.points);
+        }
+        
+        /// Draw the outline of this area with RayLib
+        pub fn draw(&self, rl_handle: &mut RaylibDrawHandle, offset: (i32, i32), draw_scale: i32) {
+            for (a, b) in self.points.iter().zip(self.points.iter().cycle().skip(1)) {
+                draw_segment(rl_handle, offset, draw_scale, ((*a).into(), (*b).into()), Color::from_hex(MEASUREMENT_AREA_COLOUR).unwrap());
             }
+        }
+    }
+    
+    impl SpeedZone {
+        /// Create a new speed zone from the corners of a polygon, in order
+        pub fn new(points: Vec<(f64, f64)>, multiplier: f64) -> SpeedZone {
+            assert!(multiplier > 0.0, "A speed zone's multiplier must be positive");
             
-            // A denotes the first point (x1,y1) of the line
-            let a = (self.x1, self.y1);
-            // B denotes the second point (x2,y2) of the line
-            let b = (self.x2, self.y2);
+            SpeedZone {
+                points: points.into_iter().map(Vec2::from).collect(),
+                multiplier
+            }
+        }
+        
+        /// Return the corners of the polygon, in order
+        pub fn get_points(&self) -> &[Vec2] {
+            return &self.points;
+        }
+        
+        /// Return the multiplier applied to the speed pedestrians want to walk at inside the zone
+        pub fn get_multiplier(&self) -> f64 {
+            return self.multiplier;
+        }
+        
+        /// Check whether a point is inside the zone
+        pub fn contains(&self, p: Vec2) -> bool {
+            return polygon_contains(&self.points, p);
+        }
+        
+        /// Draw the outline of this zone with RayLib
+        pub fn draw(&self, rl_handle: &mut RaylibDrawHandle, offset: (i32, i32), draw_scale: i32) {
+            for (a, b) in self.points.iter().zip(self.points.iter().cycle().skip(1)) {
+                draw_segment(rl_handle, offset, draw_scale, ((*a).into(), (*b).into()), Color::fade(&Color::from_hex(SPEED_ZONE_COLOUR).unwrap(), 0.6));
+            }
+        }
+    }
+    
+    impl Marking {
+        pub fn new(x1: f64, y1: f64, x2: f64, y2: f64) -> Marking {
+            Marking {
+                x1, x2, y1, y2
+            }
+        }
+        
+        /// Return the start and end points of the marking
+        pub fn get_points(&self) -> ((f64, f64), (f64, f64)) {
+            return ((self.x1, self.y1), (self.x2, self.y2));
+        }
+        
+        /// Draw this marking with RayLib
+        pub fn draw(&self, rl_handle: &mut RaylibDrawHandle, offset: (i32, i32), draw_scale: i32, color: impl Into<raylib::ffi::Color>) {
+            draw_segment(rl_handle, offset, draw_scale, ((self.x1, self.y1), (self.x2, self.y2)), color);
+        }
+        
+    }
+    
+    impl CircularObstacle {
+        pub fn new(x: f64, y: f64, radius: f64) -> CircularObstacle {
+            CircularObstacle {
+                x, y, radius
+            }
+        }
+        
+        /// Return the centre of the obstacle
+        pub fn get_centre(&self) -> (f64, f64) {
+            return (self.x, self.y);
+        }
+        
+        /// Return the radius of the obstacle
+        pub fn get_radius(&self) -> f64 {
+            return self.radius;
+        }
+        
+        /// Find how far along a straight path from `start` to `end` a pedestrian of the given radius first touches the obstacle (see `swept_circle_circle`)
+        pub fn get_contact_time(&self, start: Vec2, end: Vec2, radius: f64) -> Option<f64> {
+            return swept_circle_circle(start, end, radius, Vec2::new(self.x, self.y), self.radius);
+        }
+        
+        /// Given a point P, determine the vector that points from the closest point on the edge of the obstacle to P
+        /// 
+        /// If P is inside the obstacle, the distance is negative and the vector still points outward.
+        /// 
+        /// Output form: (distance, normal vector)
+        pub fn get_normal_vector(&self, p: Vec2) -> (f64, Vec2) {
+            let from_centre = p - Vec2::new(self.x, self.y);
             
-            let ap = vec_sub(p,a);
-            let ab = vec_sub(b,a);
+            // Edge case: given point lies on the centre, so there is no single closest point
+            let direction = match from_centre.normalised() {
+                Some(direction) => direction,
+                None => return (0.0, Vec2::default())
+            };
             
-            // Scalar projection of AP onto AB
-            let scalar_proj_ap_onto_ab = vec_dot(ap,ab)/vec_dot(ab,ab);
+            let dist = from_centre.length() - self.radius;
             
-            // D is the point on the line AB closest to P
-            let d = vec_add(a, vec_mul(ab, scalar_proj_ap_onto_ab));
-            let ad = vec_sub(d,a);
+            // The normal vector has the same length as the distance, as for a wall, but always points away from the centre
+            return (dist, direction * dist.abs());
+        }
+        
+        /// Draw this obstacle with RayLib
+        pub fn draw(&self, rl_handle: &mut RaylibDrawHandle, offset: (i32, i32), draw_scale: i32, color: impl Into<raylib::ffi::Color>) {
             
-            // Solve AD = λ * AB for λ
-            let λ = if ab.0.abs() > ab.1.abs() {ad.0 / ab.0} else {ad.1 / ab.1};
+            rl_handle.draw_circle_lines(
+                offset.0 + ((draw_scale as f64)*self.x) as i32,
+                offset.1 + ((draw_scale as f64)*self.y) as i32,
+                (draw_scale as f32) * (self.radius as f32),
+                color
+            );
             
-            // Find closest point on the line to P
-            let closest_point;
-            if λ <= 0.0 {
-                closest_point = a;
-            } else if λ >= 1.0 {
-                closest_point = b;
-            } else {
-                closest_point = d;
+        }
+        
+    }
+    
+    /// Draw a line segment with RayLib
+    fn draw_segment(rl_handle: &mut RaylibDrawHandle, offset: (i32, i32), draw_scale: i32, points: ((f64, f64), (f64, f64)), color: impl Into<raylib::ffi::Color>) {
+        
+        rl_handle.draw_line(
+            offset.0 + ((draw_scale as f64)*points.0.0) as i32,
+            offset.1 + ((draw_scale as f64)*points.0.1) as i32,
+            offset.0 + ((draw_scale as f64)*points.1.0) as i32,
+            offset.1 + ((draw_scale as f64)*points.1.1) as i32,
+            color
+        );
+        
+    }
+    
+    /// Draw the outline of a start or end zone with RayLib (nothing is drawn for a single point)
+    fn draw_zone_outline(rl_handle: &mut RaylibDrawHandle, zone: &Zone, offset: (i32, i32), draw_scale: i32, color: Color) {
+        match *zone {
+            Zone::Point(_) => {},
+            Zone::Segment(a, b) => draw_segment(rl_handle, offset, draw_scale, (a.into(), b.into()), color),
+            Zone::Rectangle(a, b) => {
+                let corners = [(a.x, a.y), (b.x, a.y), (b.x, b.y), (a.x, b.y)];
+                for (i, corner) in corners.iter().enumerate() {
+                    draw_segment(rl_handle, offset, draw_scale, (*corner, corners[(i + 1) % 4]), color);
+                }
             }
+        }
+    }
+    
+    /// Return the colour of a group or behaviour model, by its index
+    fn get_category_colour(index: usize) -> Color {
+        return Color::from_hex(CATEGORY_COLOURS[index % CATEGORY_COLOURS.len()]).unwrap();
+    }
+    
+    /// Return the colour of a value on a scale, interpolating between its colours (values outside 0 to 1 take the colour at the nearest end)
+    fn get_scale_colour(colours: &[&str], value: f64) -> Color {
+        let position = value.clamp(0.0, 1.0) * ((colours.len() - 1) as f64);
+        let lower = Color::from_hex(colours[position.floor() as usize]).unwrap();
+        let upper = Color::from_hex(colours[position.ceil() as usize]).unwrap();
+        let t = position - position.floor();
+        let mix = |a: u8, b: u8| ((a as f64) + ((b as f64) - (a as f64)) * t).round() as u8;
+        
+        return Color::new(mix(lower.r, upper.r), mix(lower.g, upper.g), mix(lower.b, upper.b), 255);
+    }
+    
+    
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::simulation::behaviour::behaviour::Etiquette;
+        
+        #[test]
+        fn cyclic_routes_stop_without_a_time_limit() {
+            let mut area = SimArea::new();
+            area.add_wall((-1.0,0.0), (11.0,0.0));
+            area.add_wall((-1.0,4.0), (11.0,4.0));
+            area.add_start_end_group(vec![(0.0,1.0), (0.0,3.0)], vec![(10.0,1.0), (10.0,3.0)]);
+            
+            let mut crowd_sim = CrowdSim::new(Arc::new(area), 2.0);
+            crowd_sim.set_seed(0);
+            crowd_sim.set_recirculation(Some(Recirculation::Respawn));
+            crowd_sim.add_pedestrian_set(4, 0, Arc::new(Etiquette::NoBias));
+            
+            let (total_time, _, _) = crowd_sim.simulate_full(0.1);
             
-            // Distance from closest_point to P
-            let dist = vec_dist_sq(closest_point, p).sqrt();
+            // The run stops at the default time instead of never finishing, with everyone still walking
+            assert!((DEFAULT_RECIRCULATION_TIME..DEFAULT_RECIRCULATION_TIME + 0.2).contains(&total_time), "stopped after {}s", total_time);
+            assert_eq!(crowd_sim.get_run_status(), RunStatus::Completed);
+            assert_eq!(crowd_sim.get_active_pedestrians().len(), 4);
+        }
+        
+        #[test]
+        fn door_states_belong_to_each_simulation() {
+            let mut area = SimArea::new();
+            area.add_wall((-1.0,0.0), (11.0,0.0));
+            area.add_door((5.0,0.0), (5.0,4.0), false, vec![(1.0, true)]);
+            let area = Arc::new(area);
             
-            // Edge case: given point lies on the line
-            if dist == 0.0 {
-                return (0.0, (0.0,0.0));
+            let mut sim_a = CrowdSim::new(area.clone(), 2.0);
+            let sim_b = CrowdSim::new(area.clone(), 2.0);
+            for _ in 0..15 {
+                sim_a.simulate_timestep(0.1);
             }
             
-            // normal_vec is the vector that points from closest_point on the line to P
-            let normal_vec = vec_sub(p, closest_point);
+            // Opening a door in one simulation leaves it closed in the others using the same area
+            assert_eq!(sim_a.get_door_states(), &[true]);
+            assert_eq!(sim_b.get_door_states(), &[false]);
+            assert_eq!(area.get_walls(sim_a.get_door_states()).count(), 1);
+            assert_eq!(area.get_walls(sim_b.get_door_states()).count(), 2);
             
-            return (dist, normal_vec);
+            // The door stays open when the checkpoint is restored into a fresh simulation
+            let checkpoint = sim_a.create_checkpoint();
+            let mut restored = CrowdSim::new(area.clone(), 2.0);
+            restored.restore_checkpoint(checkpoint).unwrap();
+            assert_eq!(restored.get_door_states(), &[true]);
             
+            // A checkpoint from an area without the door can't be restored
+            let mut doorless = CrowdSim::new(Arc::new(SimArea::new()), 2.0);
+            assert!(doorless.restore_checkpoint(sim_a.create_checkpoint()).is_err());
         }
         
-        /// Draw this wall with RayLib
-        pub fn draw(&self, rl_handle: &mut RaylibDrawHandle, offset: (i32, i32), draw_scale: i32, color: impl Into<raylib::ffi::Color>) {
+        #[test]
+        fn speed_zones_slow_pedestrians_down() {
+            let create_area = |zones: &[(f64, f64)]| {
+                let mut area = SimArea::new();
+                area.add_wall((-1.0,0.0), (21.0,0.0));
+                area.add_wall((-1.0,2.0), (21.0,2.0));
+                area.add_start_end_group(vec![(0.0,1.0)], vec![(20.0,1.0)]);
+                for &(end, multiplier) in zones {
+                    area.add_speed_zone(vec![(5.0,0.0), (end,0.0), (end,2.0), (5.0,2.0)], multiplier);
+                }
+                return area;
+            };
+            let get_total_time = |area: SimArea| {
+                let mut crowd_sim = CrowdSim::new(Arc::new(area), 1.0);
+                crowd_sim.set_seed(0);
+                crowd_sim.set_run_limits(Some(120.0), None);
+                crowd_sim.add_pedestrian_set(1, 0, Arc::new(Etiquette::NoBias));
+                return crowd_sim.simulate_full(0.1).0;
+            };
             
-            rl_handle.draw_line(
-                offset.0 + ((draw_scale as f64)*self.x1) as i32,
-                offset.1 + ((draw_scale as f64)*self.y1) as i32,
-                offset.0 + ((draw_scale as f64)*self.x2) as i32,
-                offset.1 + ((draw_scale as f64)*self.y2) as i32,
-                color
-            );
+            // Overlapping zones multiply together
+            let area = create_area(&[(15.0, 0.5), (10.0, 0.5)]);
+            assert_eq!(area.get_speed_multiplier(Vec2::new(2.0, 1.0)), 1.0);
+            assert_eq!(area.get_speed_multiplier(Vec2::new(7.0, 1.0)), 0.25);
+            assert_eq!(area.get_speed_multiplier(Vec2::new(12.0, 1.0)), 0.5);
             
+            // Walking the 10m of the zone at half speed takes about as long again as walking it normally
+            let unhindered = get_total_time(create_area(&[]));
+            let hindered = get_total_time(create_area(&[(15.0, 0.5)]));
+            let delay = hindered - unhindered;
+            assert!((5.5..10.0).contains(&delay), "{}s without the zone, {}s with it", unhindered, hindered);
         }
-        
     }
     
 }
@@ -0,0 +1,42 @@
+pub mod snapshot {
+    
+    use serde::{Deserialize, Serialize};
+    
+    
+    /// The state of a simulation at one moment, returned by `CrowdSim::snapshot`, so that external visualisers, tests and analysis tools can read it without depending on the simulator's internals
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    pub struct SimSnapshot {
+        /// The simulation time, in seconds
+        pub time: f64,
+        /// The number of pedestrians yet to start walking, including scheduled pedestrians and those held back by the spawn gate
+        pub waiting_count: usize,
+        /// The number of pedestrians walking
+        pub active_count: usize,
+        /// The number of pedestrians that have reached their destination
+        pub finished_count: usize,
+        /// Every walking pedestrian, in the order they are simulated
+        pub pedestrians: Vec<PedestrianState>
+    }
+    
+    /// The state of one walking pedestrian in a `SimSnapshot`
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    pub struct PedestrianState {
+        /// The ID of the pedestrian (see `Walker::get_id`)
+        pub id: u64,
+        /// The group the pedestrian is part of
+        pub group: usize,
+        pub x: f64,
+        pub y: f64,
+        /// The facing direction, in radians
+        pub heading: f64,
+        /// The instantaneous walking speed, in m/s
+        pub speed: f64,
+        /// The preferred walking speed, in m/s
+        pub target_speed: f64,
+        /// The name of the behaviour model the pedestrian follows
+        pub behaviour: String,
+        /// The cohorts the pedestrian has been tagged with
+        pub cohorts: Vec<String>
+    }
+    
+}
@@ -0,0 +1,149 @@
+pub mod social_force {
+    
+    use std::f64::consts::PI;
+    
+    use crate::simulation::pedestrian::pedestrian::Walker;
+    use crate::simulation::behaviour::behaviour::{BehaviourModel, Etiquette};
+    use crate::simulation::geom::geom::normalise_angle;
+    
+    
+    /// Relaxation time for a pedestrian to reach its desired velocity, in seconds
+    const RELAXATION_TIME: f64 = 0.5;
+    
+    /// Strength of the repulsion between two pedestrians, in m⋅s^-2
+    const PEDESTRIAN_REPULSION_STRENGTH: f64 = 2.1;
+    /// Range of the repulsion between two pedestrians, in metres
+    const PEDESTRIAN_REPULSION_RANGE: f64 = 0.3;
+    
+    /// Weight given to pedestrians behind, relative to those directly in front (0 = ignore pedestrians behind, 1 = isotropic)
+    const ANISOTROPY: f64 = 0.35;
+    
+    /// Strength of the repulsion from a wall, in m⋅s^-2
+    const WALL_REPULSION_STRENGTH: f64 = 5.0;
+    /// Range of the repulsion from a wall, in metres
+    const WALL_REPULSION_RANGE: f64 = 0.1;
+    
+    /// Neighbours further away than this are ignored, in metres
+    const INTERACTION_CUTOFF: f64 = 3.0;
+    
+    /// The fastest a pedestrian can walk, as a multiple of its target speed
+    const MAXIMUM_SPEED_RATIO: f64 = 1.3;
+    
+    /// Strength of the sideways force caused by Etiquette::LeftBias or Etiquette::RightBias, in m⋅s^-2
+    const ETIQUETTE_BIAS_STRENGTH: f64 = 0.1;
+    
+    
+    /// Helbing-style social force model.
+    /// 
+    /// Each pedestrian is driven towards its target at its preferred speed, and pushed away from neighbours and walls by exponentially decaying repulsive forces.
+    /// Unlike the rule-based etiquettes, all steering comes from summing forces on the pedestrian's velocity.
+    pub struct SocialForce {
+        /// Which side of the walkway (if any) the pedestrian prefers to keep to
+        etiquette: Etiquette
+    }
+    
+    impl SocialForce {
+        /// Create a new social force model.
+        /// 
+        /// * `etiquette` - `LeftBias` or `RightBias` add a small constant sideways force towards that side; `NoBias` gives the standard model.
+        pub fn new(etiquette: Etiquette) -> SocialForce {
+            SocialForce {
+                etiquette
+            }
+        }
+    }
+    
+    impl BehaviourModel for SocialForce {
+        fn name(&self) -> &str {
+            match self.etiquette {
+                Etiquette::LeftBias => "social force (left bias)",
+                Etiquette::RightBias => "social force (right bias)",
+                Etiquette::NoBias => "social force"
+            }
+        }
+        
+        fn accelerate(&self, _walker: &mut Walker, _time_scale: f64) {
+            // Acceleration comes from the driving force instead
+        }
+        
+        fn steer_towards_target(&self, walker: &mut Walker, target_angle: f64, time_scale: f64) {
+            let (v_x, v_y) = velocity(walker);
+            
+            // Driving force: relax towards the desired velocity
+            let desired_speed = walker.get_desired_speed();
+            let mut f_x = (desired_speed * target_angle.cos() - v_x) / RELAXATION_TIME;
+            let mut f_y = (desired_speed * target_angle.sin() - v_y) / RELAXATION_TIME;
+            
+            // Sideways bias, perpendicular to the desired direction
+            let bias_angle = match self.etiquette {
+                Etiquette::LeftBias => Some(target_angle - PI/2.0),
+                Etiquette::RightBias => Some(target_angle + PI/2.0),
+                Etiquette::NoBias => None
+            };
+            if let Some(bias_angle) = bias_angle {
+                f_x += ETIQUETTE_BIAS_STRENGTH * bias_angle.cos();
+                f_y += ETIQUETTE_BIAS_STRENGTH * bias_angle.sin();
+            }
+            
+            apply_force(walker, (f_x, f_y), time_scale);
+        }
+        
+        fn react_to_neighbours(&self, walker: &mut Walker, time_scale: f64, other_pedestrians: &[(f64, f64, f64, f64, f64)]) {
+            let mut f_x = 0.0;
+            let mut f_y = 0.0;
+            
+            for (n_x, n_y, _, n_radius, _) in other_pedestrians {
+                let dist = ((walker.x - n_x)*(walker.x - n_x) + (walker.y - n_y)*(walker.y - n_y)).sqrt();
+                
+                if dist > INTERACTION_CUTOFF || dist == 0.0 {
+                    continue;
+                }
+                
+                // Unit vector pointing from the neighbour to this pedestrian
+                let n = ((walker.x - n_x) / dist, (walker.y - n_y) / dist);
+                
+                // Cosine of the angle between the direction of travel and the direction of the neighbour
+                let cos_phi = -n.0 * walker.facing_direction.cos() - n.1 * walker.facing_direction.sin();
+                let anisotropy = ANISOTROPY + (1.0 - ANISOTROPY) * (1.0 + cos_phi) / 2.0;
+                
+                let magnitude = PEDESTRIAN_REPULSION_STRENGTH * ((walker.get_params().radius + n_radius - dist) / PEDESTRIAN_REPULSION_RANGE).exp() * anisotropy;
+                
+                f_x += magnitude * n.0;
+                f_y += magnitude * n.1;
+            }
+            
+            apply_force(walker, (f_x, f_y), time_scale);
+        }
+        
+        fn react_to_wall(&self, walker: &mut Walker, dist: f64, normal_angle: f64, time_scale: f64) {
+            if dist > INTERACTION_CUTOFF {
+                return;
+            }
+            
+            let magnitude = WALL_REPULSION_STRENGTH * ((walker.get_params().radius - dist) / WALL_REPULSION_RANGE).exp();
+            
+            apply_force(walker, (magnitude * normal_angle.cos(), magnitude * normal_angle.sin()), time_scale);
+        }
+    }
+    
+    /// Return the velocity of a pedestrian as an (x, y) vector
+    fn velocity(walker: &Walker) -> (f64, f64) {
+        return (walker.inst_speed * walker.facing_direction.cos(), walker.inst_speed * walker.facing_direction.sin());
+    }
+    
+    /// Apply a force (per unit mass) to a pedestrian's velocity for one timestep, limiting the resulting speed
+    fn apply_force(walker: &mut Walker, force: (f64, f64), time_scale: f64) {
+        let (v_x, v_y) = velocity(walker);
+        let v_x = v_x + force.0 * time_scale;
+        let v_y = v_y + force.1 * time_scale;
+        
+        let speed = (v_x*v_x + v_y*v_y).sqrt();
+        
+        // Keep the current facing direction when stationary
+        if speed > 0.0 {
+            walker.facing_direction = normalise_angle(v_y.atan2(v_x));
+        }
+        walker.inst_speed = speed.min(MAXIMUM_SPEED_RATIO * walker.get_desired_speed());
+    }
+    
+}
@@ -0,0 +1,540 @@
+pub mod statistics {
+    
+    use std::ops::Range;
+    
+    use crate::simulation::simulator::simulator::CrowdSim;
+    
+    
+    /// The percentiles of the travel times included in every summary
+    pub const PERCENTILES: [f64; 4] = [5.0, 25.0, 75.0, 95.0];
+    
+    /// The number of values averaged into each batch by MSER (MSER-5)
+    pub const MSER_BATCH_SIZE: usize = 5;
+    
+    
+    /// Summary statistics of a set of travel times
+    #[derive(Clone, Debug)]
+    pub struct TravelTimeStats {
+        /// The number of travel times summarised
+        pub count: usize,
+        /// The mean travel time, in seconds
+        pub mean: f64,
+        /// The median travel time, in seconds
+        pub median: f64,
+        /// The standard deviation of the travel times, in seconds
+        pub std_dev: f64,
+        /// \[(percentile, travel time in seconds)], for each of `PERCENTILES`
+        pub percentiles: Vec<(f64, f64)>,
+        /// \[(start of the bin in seconds, number of travel times in the bin)], from the bin containing the shortest travel time to the bin containing the longest
+        pub histogram: Vec<(f64, usize)>
+    }
+    
+    /// The travel times of a simulation, summarised overall and split by group and by behaviour model (e.g. etiquette)
+    #[derive(Clone, Debug)]
+    pub struct ResultBreakdown {
+        pub overall: TravelTimeStats,
+        /// The statistics of each group, indexed by group ID
+        pub by_group: Vec<TravelTimeStats>,
+        /// \[(behaviour model name, statistics)], in the order the behaviour models first appear in the results
+        pub by_behaviour: Vec<(String, TravelTimeStats)>
+    }
+    
+    /// The result of Welch's t-test, comparing the means of two samples that may have different variances (e.g. the mean travel times of repeated runs of two simulations)
+    #[derive(Clone, Debug)]
+    pub struct WelchTest {
+        /// The mean of the first sample minus the mean of the second
+        pub mean_difference: f64,
+        /// The t statistic
+        pub t: f64,
+        /// The Welch–Satterthwaite approximation of the degrees of freedom
+        pub degrees_of_freedom: f64,
+        /// The two-sided p-value: the probability of a difference at least this large if the two means were really the same
+        pub p_value: f64,
+        /// The confidence interval of the difference between the means, at the confidence level the test was run with
+        pub confidence_interval: (f64, f64)
+    }
+    
+    impl TravelTimeStats {
+        /// Summarise a set of travel times. Every statistic of an empty set is NaN, and its histogram is empty.
+        /// 
+        /// * `bin_width` - The width of each histogram bin, in seconds
+        pub fn new(travel_times: &[f64], bin_width: f64) -> TravelTimeStats {
+            let mut sorted = travel_times.to_vec();
+            sorted.sort_by(|a, b| a.total_cmp(b));
+            
+            let count = sorted.len();
+            let mean = sorted.iter().sum::<f64>() / (count as f64);
+            let variance = sorted.iter().map(|t| (t - mean)*(t - mean)).sum::<f64>() / (count as f64);
+            
+            let mut histogram = Vec::new();
+            if let (Some(first), Some(last)) = (sorted.first(), sorted.last()) {
+                let first_bin = (first / bin_width).floor();
+                let bin_count = (last / bin_width).floor() - first_bin + 1.0;
+                histogram = (0..bin_count as usize).map(|i| ((first_bin + i as f64) * bin_width, 0)).collect();
+                
+                let last_bin = histogram.len() - 1;
+                for t in &sorted {
+                    let bin = ((t / bin_width).floor() - first_bin) as usize;
+                    histogram[bin.min(last_bin)].1 += 1;
+                }
+            }
+            
+            return TravelTimeStats {
+                count,
+                mean,
+                median: get_percentile(&sorted, 50.0),
+                std_dev: variance.sqrt(),
+                percentiles: PERCENTILES.iter().map(|p| (*p, get_percentile(&sorted, *p))).collect(),
+                histogram
+            };
+        }
+    }
+    
+    impl ResultBreakdown {
+        /// Summarise the travel times of a simulation overall, by group and by behaviour model.
+        /// 
+        /// As with the headline results, the first and last travel times recorded are left out of every summary, since they are from pedestrians who walked through a nearly empty area.
+        /// 
+        /// * `trim_extremes_count` - The number of travel times to leave out at each end
+        /// * `bin_width` - The width of each histogram bin, in seconds
+        pub fn new(crowd_sim: &CrowdSim, trim_extremes_count: usize, bin_width: f64) -> ResultBreakdown {
+            let travel_times = crowd_sim.get_travel_times();
+            let behaviours = crowd_sim.get_travel_time_behaviours();
+            
+            // Runs cut short (e.g. by an evacuation) may have too few results to trim the full amount
+            let trim_extremes_count = trim_extremes_count.min(travel_times.len() / 2);
+            let range = trim_extremes_count..(travel_times.len() - trim_extremes_count);
+            let trimmed = travel_times[range.clone()].iter().zip(&behaviours[range]).collect::<Vec<_>>();
+            
+            let group_count = travel_times.iter().map(|(_, group, _)| group + 1).max().unwrap_or(0);
+            let by_group = (0..group_count).map(|group| {
+                let times = trimmed.iter().filter(|((_, g, _), _)| *g == group).map(|((t, _, _), _)| *t).collect::<Vec<_>>();
+                TravelTimeStats::new(&times, bin_width)
+            }).collect();
+            
+            let mut behaviour_names: Vec<&String> = Vec::new();
+            for (_, name) in &trimmed {
+                if !behaviour_names.contains(name) {
+                    behaviour_names.push(name);
+                }
+            }
+            let by_behaviour = behaviour_names.iter().map(|name| {
+                let times = trimmed.iter().filter(|(_, b)| b == name).map(|((t, _, _), _)| *t).collect::<Vec<_>>();
+                (name.to_string(), TravelTimeStats::new(&times, bin_width))
+            }).collect();
+            
+            return ResultBreakdown {
+                overall: TravelTimeStats::new(&trimmed.iter().map(|((t, _, _), _)| *t).collect::<Vec<_>>(), bin_width),
+                by_group,
+                by_behaviour
+            };
+        }
+    }
+    
+    impl WelchTest {
+        /// Compare the means of two samples. Every statistic is NaN if either sample has fewer than two values.
+        /// 
+        /// * `confidence` - The confidence level of the interval, e.g. 0.95
+        pub fn new(first: &[f64], second: &[f64], confidence: f64) -> WelchTest {
+            let (first_mean, first_variance) = get_mean_and_sample_variance(first);
+            let (second_mean, second_variance) = get_mean_and_sample_variance(second);
+            
+            let first_error = first_variance / (first.len() as f64);
+            let second_error = second_variance / (second.len() as f64);
+            let standard_error = (first_error + second_error).sqrt();
+            
+            let mean_difference = first_mean - second_mean;
+            let t = mean_difference / standard_error;
+            let degrees_of_freedom = (first_error + second_error).powi(2)
+                / (first_error.powi(2) / (first.len() as f64 - 1.0) + second_error.powi(2) / (second.len() as f64 - 1.0));
+            
+            let margin = get_t_critical_value(1.0 - confidence, degrees_of_freedom) * standard_error;
+            
+            return WelchTest {
+                mean_difference,
+                t,
+                degrees_of_freedom,
+                p_value: get_t_two_sided_p_value(t, degrees_of_freedom),
+                confidence_interval: (mean_difference - margin, mean_difference + margin)
+            };
+        }
+        
+        /// Return true if the difference between the means is significant at a significance level, e.g. 0.05
+        pub fn is_significant(&self, significance_level: f64) -> bool {
+            return self.p_value < significance_level;
+        }
+    }
+    
+    /// Return the mean and the sample variance (divided by n - 1) of some values
+    fn get_mean_and_sample_variance(values: &[f64]) -> (f64, f64) {
+        let count = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / count;
+        let variance = values.iter().map(|v| (v - mean)*(v - mean)).sum::<f64>() / (count - 1.0);
+        
+        return (mean, variance);
+    }
+    
+    /// Return the probability that Student's t-distribution is at least |t| away from 0, on either side
+    pub fn get_t_two_sided_p_value(t: f64, degrees_of_freedom: f64) -> f64 {
+        if t.is_infinite() {
+            return 0.0;
+        }
+        
+        return get_regularised_incomplete_beta(degrees_of_freedom / 2.0, 0.5, degrees_of_freedom / (degrees_of_freedom + t*t));
+    }
+    
+    /// Return the value of t that Student's t-distribution is only at least that far from 0 (on either side) with a given probability, e.g. ~1.96 for 0.05 with many degrees of freedom
+    pub fn get_t_critical_value(probability: f64, degrees_of_freedom: f64) -> f64 {
+        if probability.is_nan() || degrees_of_freedom.is_nan() {
+            return f64::NAN;
+        }
+        
+        // The p-value falls as t rises, so find t by bisection, after finding an upper bound
+        let mut lower = 0.0;
+        let mut upper = 1.0;
+        while get_t_two_sided_p_value(upper, degrees_of_freedom) > probability && upper < 1e12 {
+            lower = upper;
+            upper *= 2.0;
+        }
+        for _ in 0..100 {
+            let middle = (lower + upper) / 2.0;
+            if get_t_two_sided_p_value(middle, degrees_of_freedom) > probability {
+                lower = middle;
+            } else {
+                upper = middle;
+            }
+        }
+        
+        return (lower + upper) / 2.0;
+    }
+    
+    /// Return the regularised incomplete beta function I_x(a, b), using its continued fraction (from Numerical Recipes)
+    fn get_regularised_incomplete_beta(a: f64, b: f64, x: f64) -> f64 {
+        if x <= 0.0 {
+            return 0.0;
+        }
+        if x >= 1.0 {
+            return 1.0;
+        }
+        
+        let front = (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp();
+        
+        // The continued fraction converges quickly on this side of the mean, so use the symmetry I_x(a, b) = 1 - I_(1-x)(b, a) on the other side
+        if x < (a + 1.0) / (a + b + 2.0) {
+            return front * get_beta_continued_fraction(a, b, x) / a;
+        } else {
+            return 1.0 - front * get_beta_continued_fraction(b, a, 1.0 - x) / b;
+        }
+    }
+    
+    /// Evaluate the continued fraction for the incomplete beta function with the modified Lentz method
+    fn get_beta_continued_fraction(a: f64, b: f64, x: f64) -> f64 {
+        const MAX_ITERATIONS: usize = 300;
+        const EPSILON: f64 = 1e-15;
+        const TINY: f64 = 1e-300;
+        
+        let non_zero = |value: f64| if value.abs() < TINY {TINY} else {value};
+        
+        let mut c = 1.0;
+        let mut d = 1.0 / non_zero(1.0 - (a + b) * x / (a + 1.0));
+        let mut result = d;
+        
+        for m in 1..=MAX_ITERATIONS {
+            let m = m as f64;
+            
+            // The even step of the recurrence
+            let numerator = m * (b - m) * x / ((a + 2.0*m - 1.0) * (a + 2.0*m));
+            d = 1.0 / non_zero(1.0 + numerator * d);
+            c = non_zero(1.0 + numerator / c);
+            result *= d * c;
+            
+            // The odd step
+            let numerator = -(a + m) * (a + b + m) * x / ((a + 2.0*m) * (a + 2.0*m + 1.0));
+            d = 1.0 / non_zero(1.0 + numerator * d);
+            c = non_zero(1.0 + numerator / c);
+            let change = d * c;
+            result *= change;
+            
+            if (change - 1.0).abs() < EPSILON {
+                break;
+            }
+        }
+        
+        return result;
+    }
+    
+    /// Return the natural logarithm of the gamma function, for x > 0 (Lanczos approximation)
+    fn ln_gamma(x: f64) -> f64 {
+        const G: f64 = 7.0;
+        const COEFFICIENTS: [f64; 9] = [
+            0.999_999_999_999_809_9, 676.520_368_121_885_1, -1_259.139_216_722_402_8, 771.323_428_777_653_1, -176.615_029_162_140_6,
+            12.507_343_278_686_905, -0.138_571_095_265_720_12, 9.984_369_578_019_572e-6, 1.505_632_735_149_311_6e-7
+        ];
+        
+        if x < 0.5 {
+            // Reflection formula
+            return (std::f64::consts::PI / (std::f64::consts::PI * x).sin()).ln() - ln_gamma(1.0 - x);
+        }
+        
+        let x = x - 1.0;
+        let mut sum = COEFFICIENTS[0];
+        for (i, coefficient) in COEFFICIENTS.iter().enumerate().skip(1) {
+            sum += coefficient / (x + i as f64);
+        }
+        let t = x + G + 0.5;
+        
+        return 0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + sum.ln();
+    }
+    
+    /// Return the range of a series of results (in the order they were recorded) that was recorded in a steady state, without the warm-up at the start or the cool-down at the end.
+    /// 
+    /// The warm-up is found with MSER-5: the truncation that minimises the standard error of the mean of what is left. The cool-down is found the same way, from the end of the series.
+    pub fn get_steady_state_range(values: &[f64]) -> Range<usize> {
+        let start = get_mser_truncation(values);
+        let remaining = values[start..].iter().rev().copied().collect::<Vec<_>>();
+        let end = values.len() - get_mser_truncation(&remaining);
+        
+        return start..end;
+    }
+    
+    /// Return the number of values to leave out at the start of a series to remove its warm-up, using the Marginal Standard Error Rule with batches of MSER_BATCH_SIZE.
+    /// 
+    /// At most half of the series is left out, since the rule is unreliable beyond that.
+    pub fn get_mser_truncation(values: &[f64]) -> usize {
+        let batches = values.chunks_exact(MSER_BATCH_SIZE).map(|batch| batch.iter().sum::<f64>() / (MSER_BATCH_SIZE as f64)).collect::<Vec<_>>();
+        if batches.len() < 2 {
+            return 0;
+        }
+        
+        let mut best = (0, f64::INFINITY);
+        for truncation in 0..=(batches.len() / 2) {
+            let remaining = &batches[truncation..];
+            let count = remaining.len() as f64;
+            let mean = remaining.iter().sum::<f64>() / count;
+            let statistic = remaining.iter().map(|b| (b - mean)*(b - mean)).sum::<f64>() / (count * count);
+            if statistic < best.1 {
+                best = (truncation, statistic);
+            }
+        }
+        
+        return best.0 * MSER_BATCH_SIZE;
+    }
+    
+    /// Return a percentile of some sorted values, interpolating linearly between the closest two (NaN if there are no values)
+    /// 
+    /// * `percentile` - Between 0 and 100
+    pub fn get_percentile(sorted: &[f64], percentile: f64) -> f64 {
+        if sorted.is_empty() {
+            return f64::NAN;
+        }
+        
+        let rank = (percentile / 100.0).clamp(0.0, 1.0) * ((sorted.len() - 1) as f64);
+        let lower = rank.floor() as usize;
+        let upper = rank.ceil() as usize;
+        
+        return sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64);
+    }
+    
+    
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::sync::Arc;
+        use crate::simulation::simulator::simulator::SimArea;
+        
+        const EPSILON: f64 = 1e-9;
+        
+        fn assert_close(a: f64, b: f64) {
+            assert!((a - b).abs() < EPSILON, "{} != {}", a, b);
+        }
+        
+        /// Create a simulation that has recorded the given results, in order: \[(travel time, group, behaviour model name)]
+        fn create_finished_sim(results: &[(f64, usize, &str)]) -> CrowdSim {
+            let mut area = SimArea::new();
+            area.add_start_end_group(vec![(0.0,0.0)], vec![(10.0,0.0)]);
+            let mut crowd_sim = CrowdSim::new(Arc::new(area), 1.0);
+            
+            let mut checkpoint = crowd_sim.create_checkpoint();
+            checkpoint.travel_times = results.iter().enumerate().map(|(i, (travel_time, group, _))| (*travel_time, *group, i as f64)).collect();
+            checkpoint.travel_time_cohorts = vec![Vec::new(); results.len()];
+            checkpoint.travel_time_behaviours = results.iter().map(|(_, _, behaviour)| behaviour.to_string()).collect();
+            crowd_sim.restore_checkpoint(checkpoint).unwrap();
+            
+            return crowd_sim;
+        }
+        
+        #[test]
+        fn travel_time_stats() {
+            let stats = TravelTimeStats::new(&[5.0, 2.0, 9.0, 4.0, 4.0, 7.0, 4.0, 5.0], 2.0);
+            
+            assert_eq!(stats.count, 8);
+            assert_close(stats.mean, 5.0);
+            assert_close(stats.median, 4.5);
+            assert_close(stats.std_dev, 2.0);
+            
+            let expected_percentiles = [(5.0, 2.7), (25.0, 4.0), (75.0, 5.5), (95.0, 8.3)];
+            assert_eq!(stats.percentiles.len(), expected_percentiles.len());
+            for ((p, value), (expected_p, expected_value)) in stats.percentiles.iter().zip(expected_percentiles) {
+                assert_close(*p, expected_p);
+                assert_close(*value, expected_value);
+            }
+            
+            // From the bin containing 2s to the bin containing 9s, including the empty bin in between
+            assert_eq!(stats.histogram, vec![(2.0, 1), (4.0, 5), (6.0, 1), (8.0, 1)]);
+        }
+        
+        #[test]
+        fn travel_time_stats_single_and_empty() {
+            let stats = TravelTimeStats::new(&[12.5], 5.0);
+            assert_eq!(stats.count, 1);
+            assert_close(stats.mean, 12.5);
+            assert_close(stats.median, 12.5);
+            assert_close(stats.std_dev, 0.0);
+            assert!(stats.percentiles.iter().all(|(_, value)| *value == 12.5));
+            assert_eq!(stats.histogram, vec![(10.0, 1)]);
+            
+            let stats = TravelTimeStats::new(&[], 5.0);
+            assert_eq!(stats.count, 0);
+            assert!(stats.mean.is_nan() && stats.median.is_nan() && stats.std_dev.is_nan());
+            assert!(stats.percentiles.iter().all(|(_, value)| value.is_nan()));
+            assert!(stats.histogram.is_empty());
+        }
+        
+        #[test]
+        fn result_breakdown() {
+            // The first and last results are trimmed off
+            let crowd_sim = create_finished_sim(&[
+                (100.0, 0, "left bias"),
+                (10.0, 0, "left bias"),
+                (20.0, 1, "no bias"),
+                (30.0, 0, "no bias"),
+                (40.0, 1, "left bias"),
+                (1000.0, 1, "left bias")
+            ]);
+            let breakdown = ResultBreakdown::new(&crowd_sim, 1, 10.0);
+            
+            assert_eq!(breakdown.overall.count, 4);
+            assert_close(breakdown.overall.mean, 25.0);
+            
+            assert_eq!(breakdown.by_group.len(), 2);
+            assert_eq!(breakdown.by_group[0].count, 2);
+            assert_close(breakdown.by_group[0].mean, 20.0);
+            assert_close(breakdown.by_group[0].std_dev, 10.0);
+            assert_eq!(breakdown.by_group[1].count, 2);
+            assert_close(breakdown.by_group[1].mean, 30.0);
+            
+            // In the order they first appear
+            let names = breakdown.by_behaviour.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>();
+            assert_eq!(names, vec!["left bias", "no bias"]);
+            assert_close(breakdown.by_behaviour[0].1.mean, 25.0);
+            assert_close(breakdown.by_behaviour[0].1.median, 25.0);
+            assert_close(breakdown.by_behaviour[1].1.mean, 25.0);
+            assert_close(breakdown.by_behaviour[1].1.std_dev, 5.0);
+        }
+        
+        #[test]
+        fn result_breakdown_with_too_few_results() {
+            // Trimming is limited to half the results, which leaves nothing, but the groups are still reported
+            let crowd_sim = create_finished_sim(&[(10.0, 0, "no bias"), (20.0, 1, "no bias")]);
+            let breakdown = ResultBreakdown::new(&crowd_sim, 5, 10.0);
+            
+            assert_eq!(breakdown.overall.count, 0);
+            assert!(breakdown.overall.mean.is_nan());
+            assert_eq!(breakdown.by_group.len(), 2);
+            assert!(breakdown.by_group.iter().all(|stats| stats.count == 0));
+            assert!(breakdown.by_behaviour.is_empty());
+        }
+        
+        #[test]
+        fn regularised_incomplete_beta() {
+            // On either side of (a + 1)/(a + b + 2), where the symmetry is used instead
+            assert_close(get_regularised_incomplete_beta(2.0, 3.0, 0.3), 0.3483);
+            assert_close(get_regularised_incomplete_beta(2.0, 3.0, 0.8), 0.9728);
+            // I_x(a, 1) = x^a, and I_0.5(a, a) = 0.5
+            assert_close(get_regularised_incomplete_beta(2.5, 1.0, 0.7), 0.7_f64.powf(2.5));
+            assert_close(get_regularised_incomplete_beta(3.5, 3.5, 0.5), 0.5);
+            // Far out in a tail
+            let tail = get_regularised_incomplete_beta(10.0, 0.5, 0.2);
+            assert!((tail / 1.994_982_493_613_094e-8 - 1.0).abs() < 1e-9, "{}", tail);
+            
+            assert_eq!(get_regularised_incomplete_beta(2.0, 3.0, 0.0), 0.0);
+            assert_eq!(get_regularised_incomplete_beta(2.0, 3.0, 1.0), 1.0);
+        }
+        
+        #[test]
+        fn t_distribution() {
+            // With one degree of freedom, the t-distribution is the Cauchy distribution
+            assert_close(get_t_two_sided_p_value(1.0, 1.0), 0.5);
+            assert_close(get_t_two_sided_p_value(0.0, 10.0), 1.0);
+            assert_close(get_t_two_sided_p_value(2.5, 4.0), 0.066_766_544_811_988_15);
+            assert_eq!(get_t_two_sided_p_value(f64::INFINITY, 4.0), 0.0);
+            
+            assert!((get_t_critical_value(0.05, 9.0) - 2.262_157_162_798_205_5).abs() < 1e-6);
+            assert!((get_t_critical_value(0.05, 1e6) - 1.959_965).abs() < 1e-5);
+            assert!(get_t_critical_value(0.05, f64::NAN).is_nan());
+        }
+        
+        #[test]
+        fn welch_test() {
+            // The first example in the Wikipedia article on Welch's t-test, with reference values worked out to high precision
+            let first = [27.5, 21.0, 19.0, 23.6, 17.0, 17.9, 16.9, 20.1, 21.9, 22.6, 23.1, 19.6, 19.0, 21.7, 21.4];
+            let second = [27.1, 22.0, 20.8, 23.4, 23.4, 23.5, 25.8, 22.0, 24.8, 20.2, 21.9, 22.1, 22.9, 20.5, 24.4];
+            let test = WelchTest::new(&first, &second, 0.95);
+            
+            assert_close(test.mean_difference, -13.0/6.0);
+            assert_close(test.t, -2.455_356_398_286_005);
+            assert_close(test.degrees_of_freedom, 24.988_529_290_231_414);
+            assert_close(test.p_value, 0.021_378_001_462_867_03);
+            // The critical value is found by bisection, so is only accurate to the precision of the p-value
+            assert!((test.confidence_interval.0 - -3.984_096_267_140_928).abs() < 1e-6, "{:?}", test.confidence_interval);
+            assert!((test.confidence_interval.1 - -0.349_237_066_192_405_2).abs() < 1e-6, "{:?}", test.confidence_interval);
+            assert!(test.is_significant(0.05));
+            assert!(!test.is_significant(0.01));
+            
+            // Swapping the samples only changes the sign
+            let swapped = WelchTest::new(&second, &first, 0.95);
+            assert_close(swapped.t, -test.t);
+            assert_close(swapped.p_value, test.p_value);
+            
+            let too_small = WelchTest::new(&[1.0], &second, 0.95);
+            assert!(too_small.t.is_nan() && too_small.degrees_of_freedom.is_nan() && too_small.p_value.is_nan());
+        }
+        
+        /// A steady state around 10 with noise that repeats every 5 values, so that every batch of 5 averages exactly 10
+        fn steady_state(index: usize) -> f64 {
+            return 10.0 + [-1.0, 1.0, 0.0, 2.0, -2.0][index % 5];
+        }
+        
+        #[test]
+        fn mser_truncation() {
+            // A warm-up of 23 values decaying from 50 towards the steady state, which ends part-way through the fifth batch
+            let values = (0..200).map(|i| if i < 23 {10.0 + 40.0 * 0.8_f64.powi(i)} else {steady_state(i as usize)}).collect::<Vec<_>>();
+            assert_eq!(get_mser_truncation(&values), 25);
+            
+            // Nothing to truncate
+            let values = (0..200).map(steady_state).collect::<Vec<_>>();
+            assert_eq!(get_mser_truncation(&values), 0);
+            
+            // A series that never settles is only truncated by half
+            let values = (0..100).map(|i| 100.0 - i as f64).collect::<Vec<_>>();
+            assert_eq!(get_mser_truncation(&values), 50);
+            
+            // Too short for two batches
+            assert_eq!(get_mser_truncation(&[50.0, 40.0, 30.0, 20.0, 10.0, 10.0, 10.0]), 0);
+        }
+        
+        #[test]
+        fn steady_state_range() {
+            // A warm-up of 23 values at twice the steady state, then a cool-down over the last 12 values
+            let values = (0..185).map(|i| match i {
+                0..=22 => 20.0,
+                173.. => 10.0 - (i - 172) as f64,
+                _ => steady_state(i as usize)
+            }).collect::<Vec<_>>();
+            
+            // The warm-up ends part-way through the fifth batch, and the cool-down covers the last three batches counted from the end
+            assert_eq!(get_steady_state_range(&values), 25..170);
+        }
+    }
+    
+}
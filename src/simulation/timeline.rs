@@ -0,0 +1,117 @@
+pub mod timeline {
+    
+    use serde::Deserialize;
+    
+    
+    /// Something that a scenario makes happen during a run, as selected by name in an `[[events]]` table, e.g. `action = "evacuate"`
+    #[derive(Clone, Debug, Deserialize)]
+    #[serde(tag = "action", rename_all = "snake_case")]
+    pub enum EventAction {
+        /// Release a number of pedestrians at once, divided evenly between a group's start positions (see `CrowdSim::add_pedestrian_burst`)
+        SpawnBurst {
+            group: usize,
+            count: usize,
+            /// The name of the behaviour model the pedestrians follow, which some pedestrian in the simulation must already follow (left out = the same model as the first pedestrian)
+            behaviour: Option<String>
+        },
+        /// Open a door, by its index in the area
+        OpenDoor {
+            door: usize
+        },
+        /// Close a door, by its index in the area
+        CloseDoor {
+            door: usize
+        },
+        /// Change the number of pedestrians added to the simulation per second (see `CrowdSim::set_pedestrian_add_rate`)
+        SetArrivalRate {
+            rate: f64
+        },
+        /// Start an evacuation to the area's exits (see `CrowdSim::start_evacuation`)
+        Evacuate
+    }
+    
+    /// An action that happens at a set simulation time, so that a dynamic scenario can be scripted without changing the code.
+    /// 
+    /// In a scenario file, each event is an `[[events]]` table with a `time` and an `action`, along with the action's own fields.
+    #[derive(Clone, Debug, Deserialize)]
+    pub struct ScenarioEvent {
+        /// The simulation time at which the event happens, in seconds
+        pub time: f64,
+        #[serde(flatten)]
+        pub action: EventAction
+    }
+    
+    
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::sync::Arc;
+        use crate::simulation::simulator::simulator::{CrowdSim, SimArea};
+        use crate::simulation::behaviour::behaviour::Etiquette;
+        
+        /// The `[[events]]` tables of a scenario file
+        #[derive(Deserialize)]
+        struct Timeline {
+            events: Vec<ScenarioEvent>
+        }
+        
+        #[test]
+        fn events_happen_in_time_order() {
+            let timeline: Timeline = toml::from_str(r#"
+                [[events]]
+                time = 3.0
+                action = "open_door"
+                door = 0
+                
+                [[events]]
+                time = 1.0
+                action = "spawn_burst"
+                group = 0
+                count = 4
+                
+                [[events]]
+                time = 2.0
+                action = "close_door"
+                door = 3
+                
+                [[events]]
+                time = 4.0
+                action = "set_arrival_rate"
+                rate = 0.5
+            "#).unwrap();
+            
+            // A corridor closed off half way along by a door
+            let mut area = SimArea::new();
+            area.add_wall((-1.0,0.0), (11.0,0.0));
+            area.add_wall((-1.0,4.0), (11.0,4.0));
+            area.add_door((5.0,0.0), (5.0,4.0), false, Vec::new());
+            area.add_start_end_group(vec![(0.0,1.0), (0.0,3.0)], vec![(10.0,1.0), (10.0,3.0)]);
+            
+            let mut crowd_sim = CrowdSim::new(Arc::new(area), 2.0);
+            crowd_sim.set_seed(0);
+            crowd_sim.set_run_limits(Some(120.0), None);
+            crowd_sim.add_pedestrian_set(2, 0, Arc::new(Etiquette::NoBias));
+            crowd_sim.add_events(timeline.events);
+            let (_, finished, _) = crowd_sim.simulate_full(0.1);
+            
+            // Each event happens on time, and the one naming a missing door is skipped
+            let log = crowd_sim.get_event_log();
+            let descriptions: Vec<&str> = log.iter().map(|(_, description)| description.as_str()).collect();
+            assert_eq!(descriptions, vec![
+                "4 pedestrians (no bias) released in group 0",
+                "door skipped: there is no door 3",
+                "door 0 opened",
+                "arrival rate set to 0.5 pedestrians/s"
+            ]);
+            for ((time, _), expected) in log.iter().zip([1.0, 2.0, 3.0, 4.0]) {
+                assert!((expected..expected + 0.15).contains(time), "{} happened at {}s", expected, time);
+            }
+            
+            // Everyone released gets through the door once it has opened
+            assert_eq!(finished, 6);
+            assert_eq!(crowd_sim.get_door_states(), &[true]);
+            assert_eq!(crowd_sim.get_pedestrian_add_rate(), 0.5);
+        }
+    }
+    
+}
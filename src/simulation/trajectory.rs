@@ -0,0 +1,78 @@
+pub mod trajectory {
+    
+    use crate::simulation::pedestrian::pedestrian::Walker;
+    
+    
+    /// The position and movement of one pedestrian at one moment
+    #[derive(Clone, Copy, Debug)]
+    pub struct TrajectorySample {
+        /// The simulation time, in seconds
+        pub time: f64,
+        /// The ID of the pedestrian (see `Walker::get_id`)
+        pub id: u64,
+        pub x: f64,
+        pub y: f64,
+        /// The instantaneous walking speed, in m/s
+        pub speed: f64,
+        /// The facing direction, in radians
+        pub heading: f64
+    }
+    
+    /// Records the trajectory of every walking pedestrian by sampling them all at regular intervals, so that they can be analysed (e.g. for lane formation) or compared with real tracking data.
+    pub struct TrajectoryRecorder {
+        /// The simulation time between samples, in seconds
+        interval: f64,
+        /// The simulation time the next samples are due at
+        next_sample_time: f64,
+        /// Every sample so far, in the order they were taken
+        samples: Vec<TrajectorySample>
+    }
+    
+    impl TrajectoryRecorder {
+        /// * `interval` - The simulation time between samples, in seconds. Samples are taken at the first timestep after each interval, so intervals shorter than a timestep sample every timestep.
+        pub fn new(interval: f64) -> TrajectoryRecorder {
+            assert!(interval > 0.0, "The trajectory sampling interval must be positive");
+            
+            return TrajectoryRecorder {
+                interval,
+                next_sample_time: 0.0,
+                samples: Vec::new()
+            };
+        }
+        
+        /// Sample the pedestrians if the next samples are due
+        /// 
+        /// * `time` - The current simulation time, in seconds
+        /// * `pedestrians` - Every walking pedestrian
+        pub fn record<'a, I: Iterator<Item = &'a Walker>>(&mut self, time: f64, pedestrians: I) {
+            // Allow for rounding errors in the simulation time
+            if time + 1e-9 < self.next_sample_time {
+                return;
+            }
+            
+            self.samples.extend(pedestrians.map(|ped| TrajectorySample {
+                time,
+                id: ped.get_id(),
+                x: ped.x,
+                y: ped.y,
+                speed: ped.inst_speed,
+                heading: ped.facing_direction
+            }));
+            
+            while self.next_sample_time <= time + 1e-9 {
+                self.next_sample_time += self.interval;
+            }
+        }
+        
+        /// Return the simulation time between samples, in seconds
+        pub fn get_interval(&self) -> f64 {
+            return self.interval;
+        }
+        
+        /// Return every sample so far, in the order they were taken (by time, then by pedestrian)
+        pub fn get_samples(&self) -> &[TrajectorySample] {
+            return &self.samples;
+        }
+    }
+    
+}
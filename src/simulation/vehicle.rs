@@ -0,0 +1,486 @@
+pub mod vehicle {
+    
+    use std::collections::HashSet;
+    use raylib::{drawing::{RaylibDrawHandle, RaylibDraw}, color::Color};
+    
+    use crate::simulation::pedestrian::pedestrian::Walker;
+    use crate::simulation::geom::geom::{Vec2, wrap_angle};
+    use crate::simulation::luggage::luggage::capsule_normal_vector;
+    
+    
+    /// The space taken up by a vehicle and the path ahead of it that pedestrians step out of, each as a capsule: (body, path), each (one end, the other end, radius)
+    pub type VehicleFootprint = ((Vec2, Vec2, f64), (Vec2, Vec2, f64));
+    
+    /// How far pedestrians keep out of a vehicle's path to either side, in metres
+    pub const VEHICLE_CLEARANCE: f64 = 0.3;
+    
+    /// How far ahead of a vehicle, in seconds of travel at its current speed, pedestrians step out of its path
+    pub const VEHICLE_YIELD_TIME: f64 = 2.0;
+    
+    /// How close a vehicle stops behind a pedestrian or another vehicle in its path, in metres
+    const VEHICLE_STOP_MARGIN: f64 = 0.5;
+    
+    /// How close a pedestrian needs to come to a moving vehicle for it to count as a conflict, in metres (closer than pedestrians stepping aside keep, see `VEHICLE_CLEARANCE`)
+    const VEHICLE_CONFLICT_DISTANCE: f64 = 0.2;
+    
+    /// The slowest a vehicle can be moving for a near miss with a pedestrian to count as a conflict, in m/s
+    const VEHICLE_CONFLICT_SPEED: f64 = 1.0;
+    
+    
+    const VEHICLE_COLOUR: &str = "D9822B";
+    
+    
+    /// The size and handling of a kind of vehicle, such as a bicycle or a delivery cart
+    #[derive(Clone, Copy, Debug)]
+    pub struct VehicleParams {
+        /// The speed the vehicle travels at when nothing is in its way, in m/s
+        pub speed: f64,
+        /// The length of the vehicle, in metres
+        pub length: f64,
+        /// The width of the vehicle, in metres
+        pub width: f64,
+        /// The fastest the vehicle can turn, in radians per second
+        pub max_turn_rate: f64,
+        /// How quickly the vehicle slows down for something in its path and speeds up again afterwards, in m/s²
+        pub deceleration: f64
+    }
+    
+    /// A route that vehicles of one kind follow through the area, entering at a regular interval, such as a cycle lane or a service road.
+    /// 
+    /// Vehicles don't see walls or obstacles, so the route should be clear of them. They slow down and stop for pedestrians and vehicles in their path,
+    /// and pedestrians give way to them by stepping out of their path.
+    pub struct VehicleLane {
+        /// The points along the route, starting where vehicles enter
+        route: Vec<Vec2>,
+        /// The time between vehicles entering, in seconds
+        headway: f64,
+        params: VehicleParams
+    }
+    
+    impl VehicleLane {
+        /// * `route` - The points along the route, starting where vehicles enter (at least two)
+        /// * `headway` - The time between vehicles entering, in seconds
+        /// * `params` - The size and handling of the vehicles
+        pub fn new(route: Vec<(f64, f64)>, headway: f64, params: VehicleParams) -> VehicleLane {
+            assert!(route.len() >= 2, "A vehicle lane's route needs at least two points");
+            assert!(headway > 0.0, "A vehicle lane's headway must be positive");
+            assert!(params.speed > 0.0 && params.deceleration > 0.0, "A vehicle's speed and deceleration must be positive");
+            
+            return VehicleLane {
+                route: route.into_iter().map(|point| point.into()).collect(),
+                headway,
+                params
+            };
+        }
+        
+        /// Return the points along the route, starting where vehicles enter
+        pub fn get_route(&self) -> &[Vec2] {
+            return &self.route;
+        }
+        
+        /// Return the size and handling of the vehicles
+        pub fn get_params(&self) -> VehicleParams {
+            return self.params;
+        }
+        
+        /// Return the length of the route, in metres
+        pub fn get_length(&self) -> f64 {
+            return self.route.windows(2).map(|segment| segment[0].distance(segment[1])).sum();
+        }
+        
+        /// Draw the route as a dashed line
+        pub fn draw(&self, rl_handle: &mut RaylibDrawHandle, offset: (i32, i32), draw_scale: i32) {
+            let colour = Color::fade(&Color::from_hex(VEHICLE_COLOUR).unwrap(), 0.3);
+            let to_screen = |point: Vec2| (offset.0 + ((draw_scale as f64) * point.x) as i32, offset.1 + ((draw_scale as f64) * point.y) as i32);
+            
+            for segment in self.route.windows(2) {
+                let length = segment[0].distance(segment[1]);
+                let dashes = (length / 0.5) as usize;
+                for dash in (0..dashes).step_by(2) {
+                    let start = to_screen(segment[0] + (segment[1] - segment[0]) * ((dash as f64) / (dashes as f64)));
+                    let end = to_screen(segment[0] + (segment[1] - segment[0]) * (((dash + 1) as f64) / (dashes as f64)));
+                    rl_handle.draw_line(start.0, start.1, end.0, end.1, colour);
+                }
+            }
+        }
+    }
+    
+    /// A single vehicle travelling along a lane
+    #[derive(Clone, Debug)]
+    pub struct Vehicle {
+        id: u64,
+        /// The index of the lane in the area
+        lane: usize,
+        /// The middle of the vehicle
+        position: Vec2,
+        /// The direction the vehicle is facing, in radians
+        heading: f64,
+        /// The vehicle's speed, in m/s
+        speed: f64,
+        /// The index of the point along the route the vehicle is heading for
+        next_point: usize,
+        /// The simulation time the vehicle entered, in seconds
+        start_time: f64,
+        params: VehicleParams
+    }
+    
+    impl Vehicle {
+        pub fn get_id(&self) -> u64 {
+            return self.id;
+        }
+        
+        /// Return the index of the vehicle's lane in the area
+        pub fn get_lane(&self) -> usize {
+            return self.lane;
+        }
+        
+        /// Return the middle of the vehicle
+        pub fn get_position(&self) -> Vec2 {
+            return self.position;
+        }
+        
+        /// Return the vehicle's speed, in m/s
+        pub fn get_speed(&self) -> f64 {
+            return self.speed;
+        }
+        
+        /// Return the unit vector pointing in the direction the vehicle is facing
+        pub fn get_direction(&self) -> Vec2 {
+            return Vec2::from_angle(self.heading);
+        }
+        
+        /// Return the space taken up by the vehicle: (the back end of the capsule's centre line, the front end, radius)
+        pub fn get_body(&self) -> (Vec2, Vec2, f64) {
+            let half_line = self.get_direction() * ((self.params.length - self.params.width).max(0.0) / 2.0);
+            return (self.position - half_line, self.position + half_line, self.params.width / 2.0);
+        }
+        
+        /// Return the space the vehicle will sweep through in the next `VEHICLE_YIELD_TIME` seconds at its current speed, widened by `VEHICLE_CLEARANCE`,
+        /// which pedestrians step out of: (the front end of the vehicle's centre line, the far end, radius)
+        /// 
+        /// The path always reaches past the distance the vehicle stops short of pedestrians, so that pedestrians clear the way for a vehicle that has stopped for them.
+        pub fn get_path(&self) -> (Vec2, Vec2, f64) {
+            let (_, front, radius) = self.get_body();
+            let length = self.speed * VEHICLE_YIELD_TIME + 2.0 * VEHICLE_STOP_MARGIN;
+            return (front, front + self.get_direction() * length, radius + VEHICLE_CLEARANCE);
+        }
+        
+        /// Return how far the vehicle can go before it needs to have stopped, in metres (infinite = nothing in its path)
+        fn get_clear_distance(&self, pedestrians: &[Walker], vehicles: &[Vehicle]) -> f64 {
+            let direction = self.get_direction();
+            let left = Vec2::new(direction.y, -direction.x);
+            let mut clear = f64::INFINITY;
+            
+            for ped in pedestrians {
+                let offset = Vec2::new(ped.x, ped.y) - self.position;
+                let (along, across) = (offset.dot(direction), offset.dot(left));
+                if along > 0.0 && across.abs() < self.params.width/2.0 + ped.get_params().radius {
+                    clear = clear.min(along - self.params.length/2.0 - ped.get_params().radius);
+                }
+            }
+            
+            // Only vehicles in the same lane are followed, so that vehicles on crossing lanes don't both wait for each other
+            for other in vehicles.iter().filter(|other| other.lane == self.lane && other.id != self.id) {
+                let offset = other.position - self.position;
+                let (along, across) = (offset.dot(direction), offset.dot(left));
+                if along > 0.0 && across.abs() < self.params.width {
+                    clear = clear.min(along - self.params.length/2.0 - other.params.length/2.0);
+                }
+            }
+            
+            return clear - VEHICLE_STOP_MARGIN;
+        }
+        
+        /// Steer along the route, slowing down for anything in the vehicle's path, and move. Return whether the vehicle has reached the end of its route.
+        fn update(&mut self, lane: &VehicleLane, clear_distance: f64, time_scale: f64) -> bool {
+            let route = lane.get_route();
+            
+            // Head for the next point once the vehicle is within the distance it can turn in, so that it cuts corners instead of overshooting them
+            let turn_distance = (self.speed / self.params.max_turn_rate).max(self.params.length / 2.0);
+            while self.next_point < route.len() - 1 && self.position.distance(route[self.next_point]) < turn_distance {
+                self.next_point += 1;
+            }
+            
+            let target = route[self.next_point];
+            let turn = wrap_angle((target - self.position).angle() - self.heading);
+            let max_turn = self.params.max_turn_rate * time_scale;
+            self.heading += turn.clamp(-max_turn, max_turn);
+            
+            // The fastest speed the vehicle can still stop from before reaching whatever is in its path
+            let safe_speed = (2.0 * self.params.deceleration * clear_distance.max(0.0)).sqrt();
+            let target_speed = self.params.speed.min(safe_speed);
+            // Vehicles can brake twice as hard as they usually do in an emergency
+            self.speed = if target_speed > self.speed {
+                (self.speed + self.params.deceleration * time_scale).min(target_speed)
+            } else {
+                (self.speed - 2.0 * self.params.deceleration * time_scale).max(target_speed)
+            };
+            
+            self.position = self.position + self.get_direction() * (self.speed * time_scale);
+            
+            let end = route[route.len() - 1];
+            return self.next_point == route.len() - 1 && (end - self.position).dot(self.get_direction()) <= 0.0;
+        }
+        
+        /// Draw the vehicle as a rectangle with a line across the front
+        pub fn draw(&self, rl_handle: &mut RaylibDrawHandle, offset: (i32, i32), draw_scale: i32) {
+            let colour = Color::from_hex(VEHICLE_COLOUR).unwrap();
+            let to_screen = |point: Vec2| (offset.0 + ((draw_scale as f64) * point.x) as i32, offset.1 + ((draw_scale as f64) * point.y) as i32);
+            
+            let direction = self.get_direction();
+            let half_length = direction * (self.params.length / 2.0);
+            let half_width = Vec2::new(-direction.y, direction.x) * (self.params.width / 2.0);
+            let corners = [
+                self.position + half_length + half_width,
+                self.position + half_length - half_width,
+                self.position - half_length - half_width,
+                self.position - half_length + half_width
+            ];
+            for i in 0..4 {
+                let (a, b) = (to_screen(corners[i]), to_screen(corners[(i + 1) % 4]));
+                rl_handle.draw_line(a.0, a.1, b.0, b.1, colour);
+            }
+            let (a, b) = (to_screen(self.position + half_length*0.6 + half_width), to_screen(self.position + half_length*0.6 - half_width));
+            rl_handle.draw_line(a.0, a.1, b.0, b.1, colour);
+        }
+    }
+    
+    /// A vehicle that has reached the end of its lane
+    #[derive(Clone, Copy, Debug)]
+    pub struct VehicleTrip {
+        /// The ID of the vehicle (see `Vehicle::get_id`)
+        pub id: u64,
+        /// The index of the vehicle's lane in the area
+        pub lane: usize,
+        /// The simulation time the vehicle entered, in seconds
+        pub start_time: f64,
+        /// The time the vehicle took to travel along its lane, in seconds
+        pub travel_time: f64,
+        /// The time the vehicle would have taken at its full speed, in seconds
+        pub free_flow_time: f64
+    }
+    
+    impl VehicleTrip {
+        /// Return how much longer the vehicle took than it would have at its full speed, in seconds
+        pub fn get_delay(&self) -> f64 {
+            return self.travel_time - self.free_flow_time;
+        }
+    }
+    
+    /// A pedestrian coming close to a moving vehicle
+    #[derive(Clone, Copy, Debug)]
+    pub struct VehicleConflict {
+        /// The simulation time of the conflict, in seconds
+        pub time: f64,
+        /// The ID of the vehicle (see `Vehicle::get_id`)
+        pub vehicle_id: u64,
+        /// The ID of the pedestrian (see `Walker::get_id`)
+        pub pedestrian_id: u64,
+        /// Where the pedestrian was: (x, y)
+        pub position: (f64, f64),
+        /// The speed of the vehicle, in m/s
+        pub vehicle_speed: f64,
+        /// The distance between the pedestrian and the vehicle, in metres (negative if they overlap)
+        pub distance: f64
+    }
+    
+    /// The vehicles travelling along every lane, and the results of the ones that have finished
+    #[derive(Default)]
+    pub struct VehicleTraffic {
+        /// The vehicles currently in the area
+        vehicles: Vec<Vehicle>,
+        /// The simulation time the next vehicle enters each lane, in seconds
+        next_entry_times: Vec<f64>,
+        next_id: u64,
+        /// Every vehicle that has reached the end of its lane, in order
+        trips: Vec<VehicleTrip>,
+        /// Every conflict between a pedestrian and a vehicle, recorded once per pair, in order
+        conflicts: Vec<VehicleConflict>,
+        /// The pairs of vehicle and pedestrian IDs that have had a conflict
+        conflict_pairs: HashSet<(u64, u64)>
+    }
+    
+    impl VehicleTraffic {
+        /// Let vehicles enter their lanes, move every vehicle along, and record the vehicles that finish and the pedestrians that come close to them
+        /// 
+        /// * `time` - The current simulation time, in seconds
+        /// * `time_scale` - The length of the timestep, in seconds
+        pub fn update(&mut self, lanes: &[VehicleLane], time: f64, time_scale: f64, pedestrians: &[Walker]) {
+            self.next_entry_times.resize(lanes.len(), 0.0);
+            
+            // A vehicle waits to enter until the one in front has moved far enough along
+            for (i, lane) in lanes.iter().enumerate() {
+                let start = lane.get_route()[0];
+                let params = lane.get_params();
+                let blocked = self.vehicles.iter().any(|vehicle| vehicle.lane == i && vehicle.position.distance(start) < params.length + VEHICLE_STOP_MARGIN);
+                if time >= self.next_entry_times[i] && !blocked {
+                    self.vehicles.push(Vehicle {
+                        id: self.next_id,
+                        lane: i,
+                        position: start,
+                        heading: (lane.get_route()[1] - start).angle(),
+                        speed: params.speed,
+                        next_point: 1,
+                        start_time: time,
+                        params
+                    });
+                    self.next_id += 1;
+                    self.next_entry_times[i] += lane.headway;
+                }
+            }
+            
+            // Every vehicle decides how far it can go from where everything was at the start of the timestep
+            let clear_distances: Vec<f64> = self.vehicles.iter().map(|vehicle| vehicle.get_clear_distance(pedestrians, &self.vehicles)).collect();
+            
+            let mut finished = Vec::new();
+            for (vehicle, clear_distance) in self.vehicles.iter_mut().zip(clear_distances) {
+                if vehicle.update(&lanes[vehicle.lane], clear_distance, time_scale) {
+                    finished.push(vehicle.id);
+                    self.trips.push(VehicleTrip {
+                        id: vehicle.id,
+                        lane: vehicle.lane,
+                        start_time: vehicle.start_time,
+                        travel_time: time + time_scale - vehicle.start_time,
+                        free_flow_time: lanes[vehicle.lane].get_length() / vehicle.params.speed
+                    });
+                }
+            }
+            self.vehicles.retain(|vehicle| !finished.contains(&vehicle.id));
+            
+            for vehicle in self.vehicles.iter().filter(|vehicle| vehicle.speed >= VEHICLE_CONFLICT_SPEED) {
+                for ped in pedestrians {
+                    let (dist, _) = capsule_normal_vector(vehicle.get_body(), Vec2::new(ped.x, ped.y));
+                    let distance = dist - ped.get_params().radius;
+                    if distance < VEHICLE_CONFLICT_DISTANCE && self.conflict_pairs.insert((vehicle.id, ped.get_id())) {
+                        self.conflicts.push(VehicleConflict {
+                            time: time + time_scale,
+                            vehicle_id: vehicle.id,
+                            pedestrian_id: ped.get_id(),
+                            position: (ped.x, ped.y),
+                            vehicle_speed: vehicle.speed,
+                            distance
+                        });
+                    }
+                }
+            }
+        }
+        
+        /// Return the vehicles currently in the area
+        pub fn get_vehicles(&self) -> &[Vehicle] {
+            return &self.vehicles;
+        }
+        
+        /// Return every vehicle that has reached the end of its lane, in order
+        pub fn get_trips(&self) -> &[VehicleTrip] {
+            return &self.trips;
+        }
+        
+        /// Return every conflict between a pedestrian and a vehicle, in order. Each pair of pedestrian and vehicle is only recorded the first time they come close.
+        pub fn get_conflicts(&self) -> &[VehicleConflict] {
+            return &self.conflicts;
+        }
+        
+        /// Return the mean and longest delay of the vehicles that have finished, in seconds (None = no vehicles have finished)
+        pub fn get_delay_summary(&self) -> Option<(f64, f64)> {
+            if self.trips.is_empty() {
+                return None;
+            }
+            
+            let total: f64 = self.trips.iter().map(|trip| trip.get_delay()).sum();
+            let longest = self.trips.iter().map(|trip| trip.get_delay()).fold(0.0, f64::max);
+            return Some((total / (self.trips.len() as f64), longest));
+        }
+    }
+    
+    
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::sync::Arc;
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha8Rng;
+        use crate::simulation::simulator::simulator::{CrowdSim, SimArea};
+        use crate::simulation::behaviour::behaviour::Etiquette;
+        use crate::simulation::params::params::SimParams;
+        
+        const BICYCLE: VehicleParams = VehicleParams {speed: 4.0, length: 1.8, width: 0.6, max_turn_rate: 2.0, deceleration: 2.0};
+        
+        #[test]
+        fn vehicles_enter_at_the_headway_and_follow_the_route() {
+            let lanes = [VehicleLane::new(vec![(0.0,0.0), (10.0,0.0), (10.0,10.0)], 2.0, BICYCLE)];
+            let mut traffic = VehicleTraffic::default();
+            for step in 0..100 {
+                traffic.update(&lanes, (step as f64) * 0.1, 0.1, &[]);
+            }
+            
+            // Vehicles enter at 0s, 2s, 4s, ... and take about 5s to ride the 20m route, cutting the corner slightly
+            let trips = traffic.get_trips();
+            assert_eq!(trips.len(), 3);
+            for (i, trip) in trips.iter().enumerate() {
+                assert_eq!(trip.id, i as u64);
+                assert!((trip.start_time - 2.0 * (i as f64)).abs() < 1e-9);
+                assert!(trip.get_delay().abs() < 0.5, "delayed by {}s", trip.get_delay());
+            }
+            assert_eq!(traffic.get_vehicles().len(), 2);
+            assert!(traffic.get_conflicts().is_empty());
+        }
+        
+        #[test]
+        fn vehicles_stop_for_pedestrians_in_their_path() {
+            let lanes = [VehicleLane::new(vec![(0.0,0.0), (20.0,0.0)], 100.0, BICYCLE)];
+            let mut area = SimArea::new();
+            area.add_start_end_group(vec![(10.0,0.0)], vec![(10.0,0.0)]);
+            let mut ped = Walker::new(Arc::new(area), (0, 0), (0, 0), 1.3, Arc::new(Etiquette::NoBias), SimParams::default(), &mut ChaCha8Rng::seed_from_u64(0));
+            ped.x = 10.0;
+            ped.y = 0.1;
+            let radius = ped.get_params().radius;
+            
+            let mut traffic = VehicleTraffic::default();
+            for step in 0..100 {
+                traffic.update(&lanes, (step as f64) * 0.1, 0.1, std::slice::from_ref(&ped));
+            }
+            
+            // The bicycle has stopped short of the pedestrian standing in its way, without coming close enough for a conflict
+            let vehicle = &traffic.get_vehicles()[0];
+            assert_eq!(vehicle.get_speed(), 0.0);
+            let gap = 10.0 - radius - vehicle.get_body().1.x - BICYCLE.width/2.0;
+            assert!(gap > VEHICLE_STOP_MARGIN - 0.1 && gap < VEHICLE_STOP_MARGIN + 0.5, "stopped {}m short", gap);
+            assert!(traffic.get_conflicts().is_empty());
+            
+            // It sets off again once the pedestrian steps aside
+            ped.y = 2.0;
+            for step in 100..150 {
+                traffic.update(&lanes, (step as f64) * 0.1, 0.1, std::slice::from_ref(&ped));
+            }
+            assert_eq!(traffic.get_trips().len(), 1);
+            assert!(traffic.get_trips()[0].get_delay() > 5.0);
+        }
+        
+        #[test]
+        fn pedestrians_step_out_of_the_way() {
+            // Bicycles riding against the flow of pedestrians down the middle of a corridor
+            let mut area = SimArea::new();
+            area.add_wall((-1.0,0.0), (11.0,0.0));
+            area.add_wall((-1.0,4.0), (11.0,4.0));
+            area.add_start_end_group(vec![(0.0,1.0), (0.0,3.0)], vec![(10.0,1.0), (10.0,3.0)]);
+            area.add_vehicle_lane(vec![(12.0,2.0), (-2.0,2.0)], 3.0, BICYCLE);
+            
+            let mut crowd_sim = CrowdSim::new(Arc::new(area), 1.0);
+            crowd_sim.set_seed(0);
+            crowd_sim.set_run_limits(Some(120.0), None);
+            crowd_sim.add_pedestrian_set(10, 0, Arc::new(Etiquette::NoBias));
+            let (_, finished, _) = crowd_sim.simulate_full(0.1);
+            assert_eq!(finished, 10);
+            
+            // Pedestrians clear the bicycles' path in time, so the bicycles are barely held up and never run into anyone
+            let traffic = crowd_sim.get_vehicle_traffic();
+            assert!(traffic.get_trips().len() >= 3);
+            let (_, longest_delay) = traffic.get_delay_summary().unwrap();
+            assert!(longest_delay < 1.0, "longest delay {}s", longest_delay);
+            for conflict in traffic.get_conflicts() {
+                assert!(conflict.distance > 0.0, "{:?}", conflict);
+            }
+        }
+    }
+    
+}
@@ -0,0 +1,196 @@
+pub mod walkway {
+    
+    use raylib::{drawing::{RaylibDrawHandle, RaylibDraw}, color::Color};
+    
+    use crate::simulation::geom::geom::Vec2;
+    
+    
+    const WALKWAY_COLOUR: &str = "5C9EAD";
+    
+    
+    /// A side of a moving walkway, looking along its direction of travel
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum WalkwaySide {
+        Left,
+        Right
+    }
+    
+    /// How pedestrians share a moving walkway: those standing keep to one side, so that those walking can overtake them on the other
+    #[derive(Clone, Copy, Debug)]
+    pub struct WalkwayEtiquette {
+        /// The side that pedestrians stand on
+        pub stand_side: WalkwaySide,
+        /// The fraction of pedestrians that stand, rather than walk, from 0 to 1
+        pub standing_fraction: f64
+    }
+    
+    /// A strip of floor that carries the pedestrians on it along, such as a moving walkway (travelator) or an escalator.
+    /// 
+    /// An escalator is modelled by its footprint on the plan, moving at the horizontal part of its speed.
+    /// The walkway doesn't stop pedestrians stepping on or off at its sides, so it usually needs walls along it as balustrades.
+    pub struct MovingWalkway {
+        /// The middle of the end pedestrians step on at
+        start: Vec2,
+        /// The middle of the end pedestrians step off at
+        end: Vec2,
+        /// The width of the walkway, in metres
+        width: f64,
+        /// The speed the walkway carries pedestrians along at, in m/s
+        speed: f64,
+        /// How pedestrians share the walkway (None = everyone walks wherever they like)
+        etiquette: Option<WalkwayEtiquette>
+    }
+    
+    impl MovingWalkway {
+        /// * `start` - The middle of the end pedestrians step on at
+        /// * `end` - The middle of the end pedestrians step off at
+        /// * `width` - The width of the walkway, in metres
+        /// * `speed` - The speed the walkway carries pedestrians along at, in m/s
+        /// * `etiquette` - How pedestrians share the walkway (None = everyone walks wherever they like)
+        pub fn new(start: (f64, f64), end: (f64, f64), width: f64, speed: f64, etiquette: Option<WalkwayEtiquette>) -> MovingWalkway {
+            assert!(start != end, "A moving walkway's ends must be apart");
+            assert!(width > 0.0, "A moving walkway's width must be positive");
+            
+            return MovingWalkway {
+                start: start.into(),
+                end: end.into(),
+                width,
+                speed,
+                etiquette
+            };
+        }
+        
+        /// Return the unit vector pointing along the walkway's direction of travel
+        pub fn get_direction(&self) -> Vec2 {
+            return (self.end - self.start).normalised().unwrap();
+        }
+        
+        /// Return the velocity the walkway carries the pedestrians on it along at: (x, y), in m/s
+        pub fn get_velocity(&self) -> Vec2 {
+            return self.get_direction() * self.speed;
+        }
+        
+        /// Return how pedestrians share the walkway (None = everyone walks wherever they like)
+        pub fn get_etiquette(&self) -> Option<WalkwayEtiquette> {
+            return self.etiquette;
+        }
+        
+        /// Return the distance of a point along the walkway from its start, and to the left of its middle, in metres (both can be negative)
+        fn get_local_coords(&self, p: Vec2) -> (f64, f64) {
+            let direction = self.get_direction();
+            let offset = p - self.start;
+            // The y axis points down the screen, so this is to the left as drawn
+            let left = Vec2::new(direction.y, -direction.x);
+            
+            return (offset.dot(direction), offset.dot(left));
+        }
+        
+        /// Return whether a point is on the walkway
+        pub fn contains(&self, p: Vec2) -> bool {
+            let (along, across) = self.get_local_coords(p);
+            return along >= 0.0 && along <= self.start.distance(self.end) && across.abs() <= self.width/2.0;
+        }
+        
+        /// Return the distance to the left of the middle of the walkway of the middle of the lane on one side, in metres
+        fn get_lane_offset(&self, side: WalkwaySide) -> f64 {
+            return match side {
+                WalkwaySide::Left => self.width/4.0,
+                WalkwaySide::Right => -self.width/4.0
+            };
+        }
+        
+        /// Return the point at the far end of the walkway in the middle of the lane on one side, which pedestrians in that lane head for
+        pub fn get_lane_end(&self, side: WalkwaySide) -> Vec2 {
+            let direction = self.get_direction();
+            return self.end + Vec2::new(direction.y, -direction.x) * self.get_lane_offset(side);
+        }
+        
+        /// Return whether a point is in the lane on one side of the walkway
+        pub fn is_in_lane(&self, p: Vec2, side: WalkwaySide) -> bool {
+            let (_, across) = self.get_local_coords(p);
+            return (across - self.get_lane_offset(side)).abs() <= self.width/4.0;
+        }
+        
+        /// Draw the walkway, with a line along the middle in its direction of travel
+        pub fn draw(&self, rl_handle: &mut RaylibDrawHandle, offset: (i32, i32), draw_scale: i32) {
+            let colour = Color::fade(&Color::from_hex(WALKWAY_COLOUR).unwrap(), 0.4);
+            let to_screen = |point: Vec2| (offset.0 + ((draw_scale as f64) * point.x) as i32, offset.1 + ((draw_scale as f64) * point.y) as i32);
+            
+            let direction = self.get_direction();
+            let half_width = Vec2::new(-direction.y, direction.x) * (self.width/2.0);
+            let corners = [self.start + half_width, self.end + half_width, self.end - half_width, self.start - half_width];
+            for i in 0..4 {
+                let (a, b) = (to_screen(corners[i]), to_screen(corners[(i + 1) % 4]));
+                rl_handle.draw_line(a.0, a.1, b.0, b.1, colour);
+            }
+            
+            // A chevron at the far end shows the direction of travel
+            let (start, end) = (to_screen(self.start), to_screen(self.end));
+            rl_handle.draw_line(start.0, start.1, end.0, end.1, colour);
+            for side in [half_width, -half_width] {
+                let barb = to_screen(self.end - direction * (self.width/2.0) + side);
+                rl_handle.draw_line(end.0, end.1, barb.0, barb.1, colour);
+            }
+        }
+    }
+    
+    
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::sync::Arc;
+        use crate::simulation::simulator::simulator::{CrowdSim, SimArea};
+        use crate::simulation::behaviour::behaviour::Etiquette;
+        
+        #[test]
+        fn lanes_and_extent() {
+            // Running in the +x direction, so its left (as drawn, with y pointing down the screen) is towards -y
+            let walkway = MovingWalkway::new((0.0, 0.0), (10.0, 0.0), 2.0, 0.5, None);
+            
+            assert!(walkway.contains(Vec2::new(5.0, 0.9)));
+            assert!(!walkway.contains(Vec2::new(5.0, 1.1)));
+            assert!(!walkway.contains(Vec2::new(-0.1, 0.0)));
+            assert!(!walkway.contains(Vec2::new(10.1, 0.0)));
+            
+            assert!(walkway.is_in_lane(Vec2::new(5.0, -0.5), WalkwaySide::Left));
+            assert!(!walkway.is_in_lane(Vec2::new(5.0, -0.5), WalkwaySide::Right));
+            assert_eq!(walkway.get_lane_end(WalkwaySide::Right), Vec2::new(10.0, 0.5));
+            assert_eq!(walkway.get_velocity(), Vec2::new(0.5, 0.0));
+        }
+        
+        /// The time a single pedestrian takes to walk down a corridor with a walkway moving at a given speed along it
+        fn get_travel_time(speed: Option<f64>, etiquette: Option<WalkwayEtiquette>) -> f64 {
+            let mut area = SimArea::new();
+            area.add_wall((-1.0,0.0), (21.0,0.0));
+            area.add_wall((-1.0,2.0), (21.0,2.0));
+            area.add_start_end_group(vec![(0.0,1.0)], vec![(20.0,1.0)]);
+            if let Some(speed) = speed {
+                area.add_moving_walkway((2.0,1.0), (18.0,1.0), 2.0, speed, etiquette);
+            }
+            
+            let mut crowd_sim = CrowdSim::new(Arc::new(area), 1.0);
+            crowd_sim.set_seed(0);
+            crowd_sim.add_pedestrian_set(1, 0, Arc::new(Etiquette::NoBias));
+            crowd_sim.set_run_limits(Some(120.0), None);
+            
+            return crowd_sim.simulate_full(0.1).0;
+        }
+        
+        #[test]
+        fn walkways_carry_pedestrians_along() {
+            let still = get_travel_time(None, None);
+            let walking = get_travel_time(Some(1.0), None);
+            
+            // Walking on the walkway saves about the 16m length at its speed, over walking at ~1.3m/s
+            assert!(walking < still - 4.0, "{}s on the walkway, {}s without", walking, still);
+            
+            // A pedestrian standing on the walkway moves at its speed alone, taking about 16s on it and 3s walking to & from it
+            let standing = get_travel_time(Some(1.0), Some(WalkwayEtiquette {stand_side: WalkwaySide::Right, standing_fraction: 1.0}));
+            assert!((standing - 19.0).abs() < 1.5 && standing > walking, "{}s standing, {}s walking", standing, walking);
+            
+            // A walkway running the other way slows pedestrians down
+            assert!(get_travel_time(Some(-0.5), None) > still);
+        }
+    }
+    
+}